@@ -0,0 +1,110 @@
+use candid::{CandidType, Deserialize, Nat};
+use serde::Serialize;
+use std::fmt;
+
+use super::super::icrc1::account::Account;
+use super::super::icrc1::transfer::{BlockIndex, NumTokens, TransferArg, TransferError};
+
+/// Transfers executed by `icrc4_transfer_batch`, each from a subaccount of the caller.
+pub type TransferBatchArgs = Vec<TransferArg>;
+
+/// Errors of the individual transfers and of the batch as a whole.
+///
+/// `GenericBatchError` and `TooManyRequests` apply to the whole batch and are returned
+/// as the only element of [TransferBatchResults].
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TransferBatchError {
+    BadFee { expected_fee: NumTokens },
+    BadBurn { min_burn_amount: NumTokens },
+    InsufficientFunds { balance: NumTokens },
+    TooOld,
+    CreatedInFuture { ledger_time: u64 },
+    TemporarilyUnavailable,
+    Duplicate { duplicate_of: BlockIndex },
+    GenericError { error_code: Nat, message: String },
+    GenericBatchError { error_code: Nat, message: String },
+    TooManyRequests { limit: Nat },
+}
+
+impl From<TransferError> for TransferBatchError {
+    fn from(err: TransferError) -> Self {
+        match err {
+            TransferError::BadFee { expected_fee } => Self::BadFee { expected_fee },
+            TransferError::BadBurn { min_burn_amount } => Self::BadBurn { min_burn_amount },
+            TransferError::InsufficientFunds { balance } => Self::InsufficientFunds { balance },
+            TransferError::TooOld => Self::TooOld,
+            TransferError::CreatedInFuture { ledger_time } => Self::CreatedInFuture { ledger_time },
+            TransferError::TemporarilyUnavailable => Self::TemporarilyUnavailable,
+            TransferError::Duplicate { duplicate_of } => Self::Duplicate { duplicate_of },
+            TransferError::GenericError {
+                error_code,
+                message,
+            } => Self::GenericError {
+                error_code,
+                message,
+            },
+        }
+    }
+}
+
+impl fmt::Display for TransferBatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BadFee { expected_fee } => TransferError::BadFee {
+                expected_fee: expected_fee.clone(),
+            }
+            .fmt(f),
+            Self::BadBurn { min_burn_amount } => TransferError::BadBurn {
+                min_burn_amount: min_burn_amount.clone(),
+            }
+            .fmt(f),
+            Self::InsufficientFunds { balance } => TransferError::InsufficientFunds {
+                balance: balance.clone(),
+            }
+            .fmt(f),
+            Self::TooOld => TransferError::TooOld.fmt(f),
+            Self::CreatedInFuture { ledger_time } => TransferError::CreatedInFuture {
+                ledger_time: *ledger_time,
+            }
+            .fmt(f),
+            Self::TemporarilyUnavailable => TransferError::TemporarilyUnavailable.fmt(f),
+            Self::Duplicate { duplicate_of } => TransferError::Duplicate {
+                duplicate_of: duplicate_of.clone(),
+            }
+            .fmt(f),
+            Self::GenericError {
+                error_code,
+                message,
+            }
+            | Self::GenericBatchError {
+                error_code,
+                message,
+            } => write!(f, "{} {}", error_code, message),
+            Self::TooManyRequests { limit } => write!(
+                f,
+                "the number of transfers in the batch is above the allowed limit of {}",
+                limit
+            ),
+        }
+    }
+}
+
+/// Result of a single transfer, the block index on success.
+pub type TransferBatchResult = Result<BlockIndex, TransferBatchError>;
+
+/// Results of the transfers in the same order as in [TransferBatchArgs].
+///
+/// `None` marks a transfer that was not processed.
+pub type TransferBatchResults = Vec<Option<TransferBatchResult>>;
+
+/// Accounts queried by `icrc4_balance_of_batch`.
+pub type BalanceQueryArgs = Vec<Account>;
+
+#[derive(CandidType, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BalanceQueryResult {
+    pub account: Account,
+    pub balance: NumTokens,
+}
+
+/// Balances of the accounts in the same order as in [BalanceQueryArgs].
+pub type BalanceQueryResults = Vec<BalanceQueryResult>;
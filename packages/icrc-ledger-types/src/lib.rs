@@ -3,3 +3,4 @@ pub mod icrc1;
 pub mod icrc2;
 pub mod icrc21;
 pub mod icrc3;
+pub mod icrc4;
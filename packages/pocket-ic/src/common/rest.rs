@@ -908,3 +908,191 @@ impl From<MockCanisterHttpResponse> for RawMockCanisterHttpResponse {
         }
     }
 }
+
+#[derive(
+    Clone, Copy, Serialize, Deserialize, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, JsonSchema,
+)]
+pub enum XNetMessageKind {
+    Request,
+    Response,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct RawXNetMessage {
+    pub sender_subnet: RawSubnetId,
+    pub receiver_subnet: RawSubnetId,
+    pub stream_index: Option<u64>,
+    pub kind: XNetMessageKind,
+    pub sender: RawCanisterId,
+    pub receiver: RawCanisterId,
+    pub method_name: Option<String>,
+    pub payload_size_bytes: u64,
+    pub best_effort: bool,
+    pub held: bool,
+}
+
+/// A message from a canister to a canister on the same or another subnet
+/// that has not been delivered to the receiving canister yet.
+/// Messages in a stream are delivered in order of their stream index.
+#[derive(Clone, Serialize, Deserialize, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct XNetMessage {
+    pub sender_subnet: Principal,
+    /// The receiving subnet (equal to the sending subnet for messages in its loopback stream).
+    pub receiver_subnet: Principal,
+    /// The index of the message in the stream (`None` for messages that are still
+    /// in the output queue of the sending canister and not routed into a stream yet).
+    pub stream_index: Option<u64>,
+    pub kind: XNetMessageKind,
+    /// The sending canister (the callee for responses).
+    pub sender: Principal,
+    /// The receiving canister (the caller for responses).
+    pub receiver: Principal,
+    /// The method name of a request (`None` for responses).
+    pub method_name: Option<String>,
+    pub payload_size_bytes: u64,
+    /// Whether the message is a best-effort message that might be dropped.
+    pub best_effort: bool,
+    /// Whether the message is held back by a filter passed to `hold_xnet_messages`.
+    pub held: bool,
+}
+
+impl From<RawXNetMessage> for XNetMessage {
+    fn from(raw_xnet_message: RawXNetMessage) -> Self {
+        Self {
+            sender_subnet: raw_xnet_message.sender_subnet.into(),
+            receiver_subnet: raw_xnet_message.receiver_subnet.into(),
+            stream_index: raw_xnet_message.stream_index,
+            kind: raw_xnet_message.kind,
+            sender: Principal::from_slice(&raw_xnet_message.sender.canister_id),
+            receiver: Principal::from_slice(&raw_xnet_message.receiver.canister_id),
+            method_name: raw_xnet_message.method_name,
+            payload_size_bytes: raw_xnet_message.payload_size_bytes,
+            best_effort: raw_xnet_message.best_effort,
+            held: raw_xnet_message.held,
+        }
+    }
+}
+
+impl From<XNetMessage> for RawXNetMessage {
+    fn from(xnet_message: XNetMessage) -> Self {
+        Self {
+            sender_subnet: xnet_message.sender_subnet.into(),
+            receiver_subnet: xnet_message.receiver_subnet.into(),
+            stream_index: xnet_message.stream_index,
+            kind: xnet_message.kind,
+            sender: xnet_message.sender.into(),
+            receiver: xnet_message.receiver.into(),
+            method_name: xnet_message.method_name,
+            payload_size_bytes: xnet_message.payload_size_bytes,
+            best_effort: xnet_message.best_effort,
+            held: xnet_message.held,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct RawXNetMessageFilter {
+    pub sender_subnet: Option<RawSubnetId>,
+    pub receiver_subnet: Option<RawSubnetId>,
+    pub sender: Option<RawCanisterId>,
+    pub receiver: Option<RawCanisterId>,
+    pub method_name: Option<String>,
+    pub kind: Option<XNetMessageKind>,
+}
+
+/// Selects the XNet messages matching all specified criteria
+/// (the default filter selects all XNet messages).
+#[derive(Clone, Default, Serialize, Deserialize, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub struct XNetMessageFilter {
+    pub sender_subnet: Option<Principal>,
+    pub receiver_subnet: Option<Principal>,
+    pub sender: Option<Principal>,
+    pub receiver: Option<Principal>,
+    /// Only matches requests calling the given method.
+    pub method_name: Option<String>,
+    pub kind: Option<XNetMessageKind>,
+}
+
+impl XNetMessageFilter {
+    pub fn matches(&self, xnet_message: &XNetMessage) -> bool {
+        self.sender_subnet
+            .map_or(true, |subnet| subnet == xnet_message.sender_subnet)
+            && self
+                .receiver_subnet
+                .map_or(true, |subnet| subnet == xnet_message.receiver_subnet)
+            && self
+                .sender
+                .map_or(true, |sender| sender == xnet_message.sender)
+            && self
+                .receiver
+                .map_or(true, |receiver| receiver == xnet_message.receiver)
+            && self.method_name.as_ref().map_or(true, |method_name| {
+                xnet_message.method_name.as_ref() == Some(method_name)
+            })
+            && self.kind.map_or(true, |kind| kind == xnet_message.kind)
+    }
+}
+
+impl From<RawXNetMessageFilter> for XNetMessageFilter {
+    fn from(raw_xnet_message_filter: RawXNetMessageFilter) -> Self {
+        Self {
+            sender_subnet: raw_xnet_message_filter.sender_subnet.map(|s| s.into()),
+            receiver_subnet: raw_xnet_message_filter.receiver_subnet.map(|s| s.into()),
+            sender: raw_xnet_message_filter
+                .sender
+                .map(|c| Principal::from_slice(&c.canister_id)),
+            receiver: raw_xnet_message_filter
+                .receiver
+                .map(|c| Principal::from_slice(&c.canister_id)),
+            method_name: raw_xnet_message_filter.method_name,
+            kind: raw_xnet_message_filter.kind,
+        }
+    }
+}
+
+impl From<XNetMessageFilter> for RawXNetMessageFilter {
+    fn from(xnet_message_filter: XNetMessageFilter) -> Self {
+        Self {
+            sender_subnet: xnet_message_filter.sender_subnet.map(|s| s.into()),
+            receiver_subnet: xnet_message_filter.receiver_subnet.map(|s| s.into()),
+            sender: xnet_message_filter.sender.map(|c| c.into()),
+            receiver: xnet_message_filter.receiver.map(|c| c.into()),
+            method_name: xnet_message_filter.method_name,
+            kind: xnet_message_filter.kind,
+        }
+    }
+}
+
+/// The reason with which the receiving subnet rejects a request
+/// (the sending subnet generates a reject response for the caller).
+#[derive(
+    Clone, Copy, Serialize, Deserialize, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, JsonSchema,
+)]
+pub enum XNetRejectReason {
+    CanisterNotFound,
+    CanisterStopped,
+    CanisterStopping,
+    QueueFull,
+    OutOfMemory,
+}
+
+/// What to do with the next undelivered message in a stream between two subnets.
+#[derive(
+    Clone, Copy, Serialize, Deserialize, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, JsonSchema,
+)]
+pub enum XNetMessageAction {
+    /// Deliver the message to the receiving subnet (even if it is held back)
+    /// and execute a round on the receiving subnet.
+    Deliver,
+    /// Drop the message (only supported for best-effort messages).
+    Drop,
+    /// Reject the request (not supported for responses).
+    Reject(XNetRejectReason),
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, JsonSchema)]
+pub struct RawProcessXNetMessage {
+    pub sender_subnet: RawSubnetId,
+    pub receiver_subnet: RawSubnetId,
+    pub action: XNetMessageAction,
+}
@@ -39,7 +39,8 @@ use crate::{
     common::rest::{
         BlobCompression, BlobId, CanisterHttpRequest, DtsFlag, ExtendedSubnetConfigSet,
         HttpsConfig, InstanceId, MockCanisterHttpResponse, RawEffectivePrincipal, RawMessageId,
-        SubnetId, SubnetKind, SubnetSpec, Topology,
+        SubnetId, SubnetKind, SubnetSpec, Topology, XNetMessage, XNetMessageAction,
+        XNetMessageFilter,
     },
     management_canister::{CanisterId, CanisterStatusResult},
     nonblocking::PocketIc as PocketIcAsync,
@@ -362,15 +363,7 @@ impl PocketIc {
         log_level: Option<Level>,
         bitcoind_addr: Option<Vec<SocketAddr>>,
     ) -> Self {
-        let (tx, rx) = channel();
-        let thread = thread::spawn(move || {
-            let rt = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .unwrap();
-            tx.send(rt).unwrap();
-        });
-        let runtime = rx.recv().unwrap();
+        let (runtime, thread) = Self::start_runtime();
 
         let pocket_ic = runtime.block_on(async {
             PocketIcAsync::from_components(
@@ -392,6 +385,47 @@ impl PocketIc {
         }
     }
 
+    fn start_runtime() -> (Runtime, JoinHandle<()>) {
+        let (tx, rx) = channel();
+        let thread = thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            tx.send(rt).unwrap();
+        });
+        (rx.recv().unwrap(), thread)
+    }
+
+    /// Creates a new PocketIC instance with the same subnets and subnet states
+    /// as this PocketIC instance. The new instance does not persist its state in a state directory.
+    #[instrument(skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn fork(&self) -> Self {
+        let (runtime, thread) = Self::start_runtime();
+        let pocket_ic = runtime.block_on(async { self.pocket_ic.fork().await });
+        Self {
+            pocket_ic,
+            runtime: Arc::new(runtime),
+            thread: Some(thread),
+        }
+    }
+
+    /// Saves the states of all subnets of this PocketIC instance as a snapshot
+    /// with the given name (replacing an existing snapshot with the same name).
+    #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn snapshot(&self, name: &str) {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.snapshot(name).await })
+    }
+
+    /// Restores the states of all subnets of this PocketIC instance
+    /// from the snapshot with the given name.
+    #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn restore(&self, name: &str) {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.restore(name).await })
+    }
+
     /// Returns the topology of the different subnets of this PocketIC instance.
     pub fn topology(&self) -> Topology {
         let runtime = self.runtime.clone();
@@ -937,6 +971,52 @@ impl PocketIc {
                 .await
         })
     }
+
+    /// Get the inter-canister messages that are pending delivery, both between subnets
+    /// and between canisters on the same subnet (in the loopback stream of that subnet),
+    /// including the messages still in the output queues of the sending canisters.
+    /// Messages matching a filter passed to `PocketIc::hold_xnet_messages`
+    /// are marked as held and are not delivered by `PocketIc::tick`.
+    #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn get_xnet_messages(&self) -> Vec<XNetMessage> {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.get_xnet_messages().await })
+    }
+
+    /// Hold back the XNet messages matching the given filter.
+    /// Since XNet streams are delivered in order, any later message
+    /// in the same stream is held back as well.
+    #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn hold_xnet_messages(&self, filter: XNetMessageFilter) {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.hold_xnet_messages(filter).await })
+    }
+
+    /// Stop holding back the XNet messages matching the given filter.
+    /// The filter must be equal to a filter previously passed to `PocketIc::hold_xnet_messages`.
+    #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn release_xnet_messages(&self, filter: XNetMessageFilter) {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async { self.pocket_ic.release_xnet_messages(filter).await })
+    }
+
+    /// Process the next XNet message from the stream between the given subnets
+    /// (the loopback stream of the subnet if both are the same), regardless of whether it is held,
+    /// by delivering, dropping, or rejecting it.
+    #[instrument(ret, skip(self), fields(instance_id=self.pocket_ic.instance_id))]
+    pub fn process_xnet_message(
+        &self,
+        sender_subnet: SubnetId,
+        receiver_subnet: SubnetId,
+        action: XNetMessageAction,
+    ) {
+        let runtime = self.runtime.clone();
+        runtime.block_on(async {
+            self.pocket_ic
+                .process_xnet_message(sender_subnet, receiver_subnet, action)
+                .await
+        })
+    }
 }
 
 impl Default for PocketIc {
@@ -4,8 +4,9 @@ use crate::common::rest::{
     HttpGatewayConfig, HttpGatewayInfo, HttpsConfig, InstanceConfig, InstanceId,
     MockCanisterHttpResponse, RawAddCycles, RawCanisterCall, RawCanisterHttpRequest, RawCanisterId,
     RawCanisterResult, RawCycles, RawEffectivePrincipal, RawMessageId, RawMockCanisterHttpResponse,
-    RawSetStableMemory, RawStableMemory, RawSubmitIngressResult, RawSubnetId, RawTime,
-    RawVerifyCanisterSigArg, RawWasmResult, SubnetId, Topology,
+    RawProcessXNetMessage, RawSetStableMemory, RawStableMemory, RawSubmitIngressResult,
+    RawSubnetId, RawTime, RawVerifyCanisterSigArg, RawWasmResult, RawXNetMessage,
+    RawXNetMessageFilter, SubnetId, Topology, XNetMessage, XNetMessageAction, XNetMessageFilter,
 };
 use crate::management_canister::{
     CanisterId, CanisterIdRecord, CanisterInstallMode, CanisterInstallModeUpgradeInner,
@@ -141,6 +142,51 @@ impl PocketIc {
         }
     }
 
+    /// Creates a new PocketIC instance with the same subnets and subnet states
+    /// as this PocketIC instance. The new instance does not persist its state in a state directory.
+    #[instrument(skip(self), fields(instance_id=self.instance_id))]
+    pub async fn fork(&self) -> Self {
+        let instance_id = match self
+            .reqwest_client
+            .post(self.instance_url().join("fork").unwrap())
+            .send()
+            .await
+            .expect("Failed to get result")
+            .json::<CreateInstanceResponse>()
+            .await
+            .expect("Could not parse response for fork instance request")
+        {
+            CreateInstanceResponse::Created { instance_id, .. } => instance_id,
+            CreateInstanceResponse::Error { message } => panic!("{}", message),
+        };
+        debug!("instance_id={} New instance forked.", instance_id);
+
+        Self {
+            instance_id,
+            max_request_time_ms: self.max_request_time_ms,
+            http_gateway: None,
+            server_url: self.server_url.clone(),
+            reqwest_client: reqwest::Client::new(),
+            _log_guard: None,
+        }
+    }
+
+    /// Saves the states of all subnets of this PocketIC instance as a snapshot
+    /// with the given name (replacing an existing snapshot with the same name).
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub async fn snapshot(&self, name: &str) {
+        let endpoint = format!("snapshots/{}", name);
+        self.post(&endpoint, ()).await
+    }
+
+    /// Restores the states of all subnets of this PocketIC instance
+    /// from the snapshot with the given name.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub async fn restore(&self, name: &str) {
+        let endpoint = format!("snapshots/{}/restore", name);
+        self.post(&endpoint, ()).await
+    }
+
     /// Returns the topology of the different subnets of this PocketIC instance.
     pub async fn topology(&self) -> Topology {
         let endpoint = "read/topology";
@@ -1235,6 +1281,56 @@ impl PocketIc {
             mock_canister_http_response.into();
         self.post(endpoint, raw_mock_canister_http_response).await
     }
+
+    /// Get the inter-canister messages that are pending delivery, both between subnets
+    /// and between canisters on the same subnet (in the loopback stream of that subnet),
+    /// including the messages still in the output queues of the sending canisters.
+    /// Messages matching a filter passed to `PocketIc::hold_xnet_messages`
+    /// are marked as held and are not delivered by `PocketIc::tick`.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub async fn get_xnet_messages(&self) -> Vec<XNetMessage> {
+        let endpoint = "read/get_xnet_messages";
+        let res: Vec<RawXNetMessage> = self.get(endpoint).await;
+        res.into_iter().map(|m| m.into()).collect()
+    }
+
+    /// Hold back the XNet messages matching the given filter.
+    /// Since XNet streams are delivered in order, any later message
+    /// in the same stream is held back as well.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub async fn hold_xnet_messages(&self, filter: XNetMessageFilter) {
+        let endpoint = "update/hold_xnet_messages";
+        let raw_xnet_message_filter: RawXNetMessageFilter = filter.into();
+        self.post(endpoint, raw_xnet_message_filter).await
+    }
+
+    /// Stop holding back the XNet messages matching the given filter.
+    /// The filter must be equal to a filter previously passed to `PocketIc::hold_xnet_messages`.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub async fn release_xnet_messages(&self, filter: XNetMessageFilter) {
+        let endpoint = "update/release_xnet_messages";
+        let raw_xnet_message_filter: RawXNetMessageFilter = filter.into();
+        self.post(endpoint, raw_xnet_message_filter).await
+    }
+
+    /// Process the next XNet message from the stream between the given subnets
+    /// (the loopback stream of the subnet if both are the same), regardless of whether it is held,
+    /// by delivering, dropping, or rejecting it.
+    #[instrument(ret, skip(self), fields(instance_id=self.instance_id))]
+    pub async fn process_xnet_message(
+        &self,
+        sender_subnet: SubnetId,
+        receiver_subnet: SubnetId,
+        action: XNetMessageAction,
+    ) {
+        let endpoint = "update/process_xnet_message";
+        let raw_process_xnet_message = RawProcessXNetMessage {
+            sender_subnet: sender_subnet.into(),
+            receiver_subnet: receiver_subnet.into(),
+            action,
+        };
+        self.post(endpoint, raw_process_xnet_message).await
+    }
 }
 
 /// Call a canister candid method, authenticated. The sender can be impersonated (i.e., the
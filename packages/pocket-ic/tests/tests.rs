@@ -7,7 +7,8 @@ use pocket_ic::management_canister::{
 use pocket_ic::{
     common::rest::{
         BlobCompression, CanisterHttpReply, CanisterHttpResponse, MockCanisterHttpResponse,
-        RawEffectivePrincipal, SubnetKind,
+        RawEffectivePrincipal, SubnetKind, XNetMessageAction, XNetMessageFilter, XNetMessageKind,
+        XNetRejectReason,
     },
     update_candid, DefaultEffectiveCanisterIdError, ErrorCode, PocketIc, PocketIcBuilder,
     WasmResult,
@@ -1303,6 +1304,204 @@ fn test_canister_http_with_one_additional_response() {
     pic.mock_canister_http_response(mock_canister_http_response);
 }
 
+#[test]
+fn test_hold_and_process_xnet_messages() {
+    // We create a PocketIC instance consisting of two application subnets.
+    let pic = PocketIcBuilder::new()
+        .with_application_subnet()
+        .with_application_subnet()
+        .build();
+    let subnet_id_1 = pic.topology().get_app_subnets()[0];
+    let subnet_id_2 = pic.topology().get_app_subnets()[1];
+
+    // We create a test canister on each subnet.
+    let canister_1 = pic.create_canister_on_subnet(None, None, subnet_id_1);
+    let canister_2 = pic.create_canister_on_subnet(None, None, subnet_id_2);
+    for canister in [canister_1, canister_2] {
+        pic.add_cycles(canister, INIT_CYCLES);
+        pic.install_canister(canister, test_canister_wasm(), vec![], None);
+    }
+
+    // We hold back all XNet messages from the first to the second subnet.
+    let filter = XNetMessageFilter {
+        sender_subnet: Some(subnet_id_1),
+        receiver_subnet: Some(subnet_id_2),
+        ..Default::default()
+    };
+    pic.hold_xnet_messages(filter.clone());
+
+    // We make the first canister call the second canister.
+    let msg_id = pic
+        .submit_call(
+            canister_1,
+            Principal::anonymous(),
+            "whois",
+            encode_one(canister_2).unwrap(),
+        )
+        .unwrap();
+    for _ in 0..5 {
+        pic.tick();
+    }
+
+    // The XNet request is held back and thus still pending.
+    let xnet_messages = pic.get_xnet_messages();
+    assert_eq!(xnet_messages.len(), 1);
+    let xnet_message = &xnet_messages[0];
+    assert_eq!(xnet_message.sender_subnet, subnet_id_1);
+    assert_eq!(xnet_message.receiver_subnet, subnet_id_2);
+    assert_eq!(xnet_message.kind, XNetMessageKind::Request);
+    assert_eq!(xnet_message.sender, canister_1);
+    assert_eq!(xnet_message.receiver, canister_2);
+    assert_eq!(xnet_message.method_name, Some("whoami".to_string()));
+    assert!(xnet_message.held);
+
+    // We deliver the held request explicitly: the response is not held back.
+    pic.process_xnet_message(subnet_id_1, subnet_id_2, XNetMessageAction::Deliver);
+    let reply = pic.await_call(msg_id).unwrap();
+    match reply {
+        WasmResult::Reply(data) => {
+            assert_eq!(Decode!(&data, String).unwrap(), canister_2.to_string())
+        }
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    };
+    assert!(pic.get_xnet_messages().is_empty());
+
+    // We make the first canister call the second canister again
+    // and reject the held request on behalf of the second subnet.
+    let msg_id = pic
+        .submit_call(
+            canister_1,
+            Principal::anonymous(),
+            "whois",
+            encode_one(canister_2).unwrap(),
+        )
+        .unwrap();
+    for _ in 0..5 {
+        pic.tick();
+    }
+    assert_eq!(pic.get_xnet_messages().len(), 1);
+    pic.process_xnet_message(
+        subnet_id_1,
+        subnet_id_2,
+        XNetMessageAction::Reject(XNetRejectReason::CanisterStopped),
+    );
+    // The test canister traps on the reject response to its call.
+    pic.await_call(msg_id).unwrap_err();
+
+    // After releasing the filter, XNet messages are delivered by ticking.
+    pic.release_xnet_messages(filter);
+    let xnet_result = pic.update_call(
+        canister_1,
+        Principal::anonymous(),
+        "whois",
+        encode_one(canister_2).unwrap(),
+    );
+    match xnet_result {
+        Ok(WasmResult::Reply(data)) => {
+            assert_eq!(Decode!(&data, String).unwrap(), canister_2.to_string())
+        }
+        _ => panic!("Unexpected update call result: {:?}", xnet_result),
+    };
+}
+
+#[test]
+fn test_hold_and_process_loopback_messages() {
+    // We create a PocketIC instance consisting of a single application subnet.
+    let pic = PocketIcBuilder::new().with_application_subnet().build();
+    let subnet_id = pic.topology().get_app_subnets()[0];
+
+    // We create two test canisters on the subnet.
+    let canister_1 = pic.create_canister_on_subnet(None, None, subnet_id);
+    let canister_2 = pic.create_canister_on_subnet(None, None, subnet_id);
+    for canister in [canister_1, canister_2] {
+        pic.add_cycles(canister, INIT_CYCLES);
+        pic.install_canister(canister, test_canister_wasm(), vec![], None);
+    }
+
+    // We hold back all requests to the second canister.
+    let filter = XNetMessageFilter {
+        receiver: Some(canister_2),
+        kind: Some(XNetMessageKind::Request),
+        ..Default::default()
+    };
+    pic.hold_xnet_messages(filter.clone());
+
+    // We make the first canister call the second canister
+    // and reject the held request on behalf of the subnet.
+    let msg_id = pic
+        .submit_call(
+            canister_1,
+            Principal::anonymous(),
+            "whois",
+            encode_one(canister_2).unwrap(),
+        )
+        .unwrap();
+    for _ in 0..5 {
+        pic.tick();
+    }
+
+    // The request is held back in the loopback stream of the subnet.
+    let xnet_messages = pic.get_xnet_messages();
+    assert_eq!(xnet_messages.len(), 1);
+    let xnet_message = &xnet_messages[0];
+    assert_eq!(xnet_message.sender_subnet, subnet_id);
+    assert_eq!(xnet_message.receiver_subnet, subnet_id);
+    assert!(xnet_message.stream_index.is_some());
+    assert_eq!(xnet_message.kind, XNetMessageKind::Request);
+    assert_eq!(xnet_message.sender, canister_1);
+    assert_eq!(xnet_message.receiver, canister_2);
+    assert_eq!(xnet_message.method_name, Some("whoami".to_string()));
+    assert!(xnet_message.held);
+
+    pic.process_xnet_message(
+        subnet_id,
+        subnet_id,
+        XNetMessageAction::Reject(XNetRejectReason::CanisterStopped),
+    );
+    // The test canister traps on the reject response to its call.
+    pic.await_call(msg_id).unwrap_err();
+    assert!(pic.get_xnet_messages().is_empty());
+
+    // We make the first canister call the second canister again
+    // and step the held request: the response is not held back.
+    let msg_id = pic
+        .submit_call(
+            canister_1,
+            Principal::anonymous(),
+            "whois",
+            encode_one(canister_2).unwrap(),
+        )
+        .unwrap();
+    for _ in 0..5 {
+        pic.tick();
+    }
+    assert_eq!(pic.get_xnet_messages().len(), 1);
+    pic.process_xnet_message(subnet_id, subnet_id, XNetMessageAction::Deliver);
+    let reply = pic.await_call(msg_id).unwrap();
+    match reply {
+        WasmResult::Reply(data) => {
+            assert_eq!(Decode!(&data, String).unwrap(), canister_2.to_string())
+        }
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    };
+    assert!(pic.get_xnet_messages().is_empty());
+
+    // After releasing the filter, the messages are delivered by ticking.
+    pic.release_xnet_messages(filter);
+    let xnet_result = pic.update_call(
+        canister_1,
+        Principal::anonymous(),
+        "whois",
+        encode_one(canister_2).unwrap(),
+    );
+    match xnet_result {
+        Ok(WasmResult::Reply(data)) => {
+            assert_eq!(Decode!(&data, String).unwrap(), canister_2.to_string())
+        }
+        _ => panic!("Unexpected update call result: {:?}", xnet_result),
+    };
+}
+
 #[test]
 fn subnet_metrics() {
     const INIT_CYCLES: u128 = 2_000_000_000_000;
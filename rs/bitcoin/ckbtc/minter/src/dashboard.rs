@@ -299,6 +299,14 @@ pub fn build_metadata(s: &CkBtcMinterState) -> String {
                         <th>Total BTC managed</th>
                         <td>{}</td>
                     </tr>
+                    <tr>
+                        <th>Available UTXOs</th>
+                        <td>{}</td>
+                    </tr>
+                    <tr>
+                        <th>UTXO consolidation</th>
+                        <td>{}</td>
+                    </tr>
                 </tbody>
             </table>",
         s.btc_network,
@@ -315,7 +323,25 @@ pub fn build_metadata(s: &CkBtcMinterState) -> String {
             .unwrap_or_else(|| "N/A".to_string()),
         DisplayAmount(s.kyt_fee),
         DisplayAmount(s.retrieve_btc_min_amount),
-        DisplayAmount(get_total_btc_managed(s))
+        DisplayAmount(get_total_btc_managed(s)),
+        s.available_utxos.len(),
+        s.utxo_consolidation
+            .as_ref()
+            .map(|config| {
+                format!(
+                    "<table>
+                    <tr><th>Min UTXO count</th><td>{}</td></tr>
+                    <tr><th>Max UTXO value</th><td>{}</td></tr>
+                    <tr><th>Max inputs</th><td>{}</td></tr>
+                    <tr><th>Max fee per vbyte (millisatoshi)</th><td>{}</td></tr>
+                    </table>",
+                    config.min_utxo_count,
+                    DisplayAmount(config.max_utxo_value),
+                    config.max_inputs,
+                    config.max_fee_per_vbyte,
+                )
+            })
+            .unwrap_or_else(|| "Disabled".to_string()),
     )
 }
 
@@ -372,6 +398,9 @@ pub fn build_submitted_transactions(s: &CkBtcMinterState) -> String {
                     .unwrap();
 
                     write!(buf, "<td rowspan='{}'>", rowspan).unwrap();
+                    if tx.is_utxo_consolidation() {
+                        write!(buf, "UTXO consolidation").unwrap();
+                    }
                     for req in &tx.requests {
                         write!(
                             buf,
@@ -147,6 +147,7 @@ mod tests {
             mode: crate::state::Mode::GeneralAvailability,
             kyt_principal: Some(CanisterId::from(0)),
             kyt_fee: None,
+            utxo_consolidation: None,
         }
     }
 
@@ -68,6 +68,21 @@ pub const CKBTC_LEDGER_MEMO_SIZE: u16 = 80;
 /// when building transactions.
 pub const UTXOS_COUNT_THRESHOLD: usize = 1_000;
 
+/// The maximum number of UTXOs that a single consolidation transaction can merge.
+pub const MAX_UTXO_CONSOLIDATION_INPUTS: u64 = 1_000;
+
+/// Having a sequence number lower than (0xffffffff - 1) signals the use of replacement by fee.
+/// It allows us to increase the fee of a transaction already sent to the mempool.
+/// The rbf option is used in `resubmit_retrieve_btc`.
+/// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
+const SEQUENCE_RBF_ENABLED: u32 = 0xfffffffd;
+
+/// The default dustRelayFee is 3 sat/vB,
+/// which translates to a dust threshold of 546 satoshi for P2PKH outputs.
+/// The threshold for other types is lower,
+/// so we simply use 546 satoshi as the minimum amount per output.
+const MIN_OUTPUT_AMOUNT: u64 = 546;
+
 #[derive(Clone, Debug, Deserialize, serde::Serialize)]
 pub enum Priority {
     P0,
@@ -94,6 +109,8 @@ pub struct MinterInfo {
     pub min_confirmations: u32,
     pub retrieve_btc_min_amount: u64,
     pub kyt_fee: u64,
+    pub utxo_consolidation: Option<state::UtxoConsolidationConfig>,
+    pub available_utxos_count: Option<u64>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize, Serialize)]
@@ -419,6 +436,150 @@ async fn submit_pending_requests() {
     }
 }
 
+/// Merges small UTXOs into a single output owned by the minter's main account
+/// if the minter has many UTXOs and the Bitcoin fees are low
+/// (see [state::UtxoConsolidationConfig]).
+async fn consolidate_utxos() {
+    // Higher fees only exclude more UTXOs, so there is nothing to consolidate
+    // if there is nothing to consolidate at the minimum relay fee.
+    if state::read_state(|s| {
+        s.utxos_to_consolidate(Some(MIN_RELAY_FEE_PER_VBYTE))
+            .is_empty()
+    }) {
+        return;
+    }
+
+    // We use the current median fee, but never less than the minimum relay fee
+    // so that the Bitcoin network accepts the transaction. We do not fall back to
+    // the last fee percentiles: they are not persisted across upgrades.
+    let fee_per_vbyte = match estimate_fee_per_vbyte().await {
+        Some(fee) => fee.max(MIN_RELAY_FEE_PER_VBYTE),
+        None => return,
+    };
+
+    if state::read_state(|s| s.utxos_to_consolidate(Some(fee_per_vbyte)).is_empty()) {
+        return;
+    }
+
+    let main_account = Account {
+        owner: ic_cdk::id(),
+        subaccount: None,
+    };
+
+    let ecdsa_public_key = updates::get_btc_address::init_ecdsa_public_key().await;
+    let main_address = address::account_to_bitcoin_address(&ecdsa_public_key, &main_account);
+
+    let maybe_sign_request = state::mutate_state(|s| {
+        let mut utxos: BTreeSet<Utxo> = s
+            .utxos_to_consolidate(Some(fee_per_vbyte))
+            .into_iter()
+            .collect();
+        if utxos.is_empty() {
+            return None;
+        }
+        for utxo in utxos.iter() {
+            assert!(s.available_utxos.remove(utxo));
+        }
+
+        match build_consolidation_transaction(&mut utxos, main_address, fee_per_vbyte) {
+            Ok((unsigned_tx, change_output, used_utxos)) => Some(SignTxRequest {
+                key_name: s.ecdsa_key_name.clone(),
+                ecdsa_public_key,
+                change_output,
+                outpoint_account: filter_output_accounts(s, &unsigned_tx),
+                network: s.btc_network,
+                unsigned_tx,
+                requests: vec![],
+                utxos: used_utxos,
+            }),
+            Err(err) => {
+                log!(
+                    P1,
+                    "[consolidate_utxos]: failed to build a consolidation transaction: {:?}",
+                    err
+                );
+                for utxo in utxos {
+                    s.available_utxos.insert(utxo);
+                }
+                None
+            }
+        }
+    });
+
+    let req = match maybe_sign_request {
+        Some(req) => req,
+        None => return,
+    };
+
+    log!(
+        P1,
+        "[consolidate_utxos]: signing a new consolidation transaction: {}",
+        hex::encode(tx::encode_into(&req.unsigned_tx, Vec::new()))
+    );
+
+    // This guard ensures that we return the UTXOs back to the state if
+    // signing or sending the transaction fails or panics.
+    let utxos_guard = guard(req.utxos, |utxos| {
+        undo_sign_request(vec![], utxos);
+    });
+
+    let txid = req.unsigned_tx.txid();
+
+    let signed_tx = match sign_transaction(
+        req.key_name,
+        &req.ecdsa_public_key,
+        &req.outpoint_account,
+        req.unsigned_tx,
+    )
+    .await
+    {
+        Ok(signed_tx) => signed_tx,
+        Err(err) => {
+            log!(
+                P0,
+                "[consolidate_utxos]: failed to sign a BTC transaction: {}",
+                err
+            );
+            return;
+        }
+    };
+
+    match management::send_transaction(&signed_tx, req.network).await {
+        Ok(()) => {
+            log!(
+                P0,
+                "[consolidate_utxos]: sent transaction {} consolidating {} UTXOs",
+                &txid,
+                utxos_guard.len(),
+            );
+
+            // Defuse the guard because we sent the transaction successfully.
+            let used_utxos = ScopeGuard::into_inner(utxos_guard);
+
+            state::mutate_state(|s| {
+                state::audit::consolidated_utxos(
+                    s,
+                    state::SubmittedBtcTransaction {
+                        requests: vec![],
+                        txid,
+                        used_utxos,
+                        change_output: Some(req.change_output),
+                        submitted_at: ic_cdk::api::time(),
+                        fee_per_vbyte: Some(fee_per_vbyte),
+                    },
+                );
+            });
+        }
+        Err(err) => {
+            log!(
+                P0,
+                "[consolidate_utxos]: failed to send a bitcoin transaction: {}",
+                err
+            );
+        }
+    }
+}
+
 fn finalization_time_estimate(min_confirmations: u32, network: Network) -> Duration {
     Duration::from_nanos(
         min_confirmations as u64
@@ -629,18 +790,19 @@ async fn finalize_requests() {
             None => fee_per_vbyte,
         };
 
-        let outputs = submitted_tx
-            .requests
-            .iter()
-            .map(|req| (req.address.clone(), req.amount))
-            .collect();
+        let build_result = if submitted_tx.is_utxo_consolidation() {
+            build_consolidation_transaction(&mut utxos, main_address.clone(), tx_fee_per_vbyte)
+        } else {
+            let outputs = submitted_tx
+                .requests
+                .iter()
+                .map(|req| (req.address.clone(), req.amount))
+                .collect();
 
-        let (unsigned_tx, change_output, used_utxos) = match build_unsigned_transaction(
-            &mut utxos,
-            outputs,
-            main_address.clone(),
-            tx_fee_per_vbyte,
-        ) {
+            build_unsigned_transaction(&mut utxos, outputs, main_address.clone(), tx_fee_per_vbyte)
+        };
+
+        let (unsigned_tx, change_output, used_utxos) = match build_result {
             Ok(tx) => tx,
             // If it's impossible to build a new transaction, the fees probably became too high.
             // Let's ignore this transaction and wait for fees to go down.
@@ -963,12 +1125,6 @@ pub fn build_unsigned_transaction(
 ) -> Result<(tx::UnsignedTransaction, state::ChangeOutput, Vec<Utxo>), BuildTxError> {
     assert!(!outputs.is_empty());
 
-    /// Having a sequence number lower than (0xffffffff - 1) signals the use of replacement by fee.
-    /// It allows us to increase the fee of a transaction already sent to the mempool.
-    /// The rbf option is used in `resubmit_retrieve_btc`.
-    /// https://github.com/bitcoin/bips/blob/master/bip-0125.mediawiki
-    const SEQUENCE_RBF_ENABLED: u32 = 0xfffffffd;
-
     let amount = outputs.iter().map(|(_, amount)| amount).sum::<u64>();
 
     let input_utxos = utxos_selection(amount, minter_utxos, outputs.len());
@@ -1037,11 +1193,6 @@ pub fn build_unsigned_transaction(
     }
 
     let fee_shares = distribute(fee + minter_fee, outputs.len() as u64);
-    // The default dustRelayFee is 3 sat/vB,
-    // which translates to a dust threshold of 546 satoshi for P2PKH outputs.
-    // The threshold for other types is lower,
-    // so we simply use 546 satoshi as the minimum amount per output.
-    const MIN_OUTPUT_AMOUNT: u64 = 546;
 
     for (output, fee_share) in unsigned_tx.outputs.iter_mut().zip(fee_shares.iter()) {
         if output.address != main_address {
@@ -1067,6 +1218,77 @@ pub fn build_unsigned_transaction(
     ))
 }
 
+/// Builds a transaction that merges all the specified UTXOs into a single
+/// output to the minter's main address. The minter pays the fee.
+///
+/// # Panics
+///
+/// This function panics if the `utxos` set is empty as it indicates a bug
+/// in the caller's code.
+///
+/// # Success case properties
+///
+/// * The transaction spends all the specified UTXOs.
+/// ```text
+/// utxos' = ∅
+/// ```
+///
+/// * The only output of the transaction is the minter's change.
+/// ```text
+/// tx.outputs == { value = sum([u.value | u ∈ utxos]) - fee(tx); pubkey = main_pubkey }
+/// ```
+///
+/// # Error case properties
+///
+/// * In case of errors, the function does not modify the inputs.
+/// ```text
+/// result.is_err() => utxos' == utxos
+/// ```
+pub fn build_consolidation_transaction(
+    utxos: &mut BTreeSet<Utxo>,
+    main_address: BitcoinAddress,
+    fee_per_vbyte: u64,
+) -> Result<(tx::UnsignedTransaction, state::ChangeOutput, Vec<Utxo>), BuildTxError> {
+    assert!(!utxos.is_empty());
+
+    let inputs_value = utxos.iter().map(|u| u.value).sum::<u64>();
+
+    let mut unsigned_tx = tx::UnsignedTransaction {
+        inputs: utxos
+            .iter()
+            .map(|utxo| tx::UnsignedInput {
+                previous_output: utxo.outpoint.clone(),
+                value: utxo.value,
+                sequence: SEQUENCE_RBF_ENABLED,
+            })
+            .collect(),
+        outputs: vec![tx::TxOut {
+            address: main_address,
+            value: inputs_value,
+        }],
+        lock_time: 0,
+    };
+
+    let tx_vsize = fake_sign(&unsigned_tx).vsize();
+    let fee = (tx_vsize as u64 * fee_per_vbyte) / 1000;
+
+    if inputs_value <= fee + MIN_OUTPUT_AMOUNT {
+        return Err(BuildTxError::AmountTooLow);
+    }
+
+    unsigned_tx.outputs[0].value = inputs_value - fee;
+    let change_output = state::ChangeOutput {
+        vout: 0,
+        value: inputs_value - fee,
+    };
+
+    Ok((
+        unsigned_tx,
+        change_output,
+        std::mem::take(utxos).into_iter().collect(),
+    ))
+}
+
 /// Distributes an amount across the specified number of shares as fairly as
 /// possible.
 ///
@@ -1199,6 +1421,7 @@ pub fn timer() {
                 submit_pending_requests().await;
                 finalize_requests().await;
                 reimburse_failed_kyt().await;
+                consolidate_utxos().await;
             });
         }
         TaskType::RefreshFeePercentiles => {
@@ -1,6 +1,6 @@
 use crate::lifecycle::upgrade::UpgradeArgs;
-pub use crate::state::Mode;
 use crate::state::{replace_state, CkBtcMinterState};
+pub use crate::state::{Mode, UtxoConsolidationConfig};
 use candid::{CandidType, Deserialize};
 use ic_base_types::CanisterId;
 use ic_btc_interface::Network;
@@ -84,6 +84,11 @@ pub struct InitArgs {
     /// NOTE: this field is optional for backward compatibility.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kyt_principal: Option<CanisterId>,
+
+    /// The configuration of the UTXO consolidation.
+    /// The minter does not consolidate UTXOs if this field is not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utxo_consolidation: Option<UtxoConsolidationConfig>,
 }
 
 pub fn init(args: InitArgs) {
@@ -1,6 +1,6 @@
 use crate::logs::P0;
 use crate::state::eventlog::{replay, Event};
-use crate::state::{replace_state, Mode};
+use crate::state::{replace_state, Mode, UtxoConsolidationConfig};
 use crate::storage::{count_events, events, record_event};
 use candid::{CandidType, Deserialize};
 use ic_base_types::CanisterId;
@@ -32,6 +32,10 @@ pub struct UpgradeArgs {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub kyt_principal: Option<CanisterId>,
+
+    /// If set, overrides the current configuration of the UTXO consolidation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utxo_consolidation: Option<UtxoConsolidationConfig>,
 }
 
 pub fn post_upgrade(upgrade_args: Option<UpgradeArgs>) {
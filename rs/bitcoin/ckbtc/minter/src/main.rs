@@ -217,6 +217,8 @@ fn get_minter_info() -> MinterInfo {
         kyt_fee: s.kyt_fee,
         min_confirmations: s.min_confirmations,
         retrieve_btc_min_amount: s.retrieve_btc_min_amount,
+        utxo_consolidation: s.utxo_consolidation.clone(),
+        available_utxos_count: Some(s.available_utxos.len() as u64),
     })
 }
 
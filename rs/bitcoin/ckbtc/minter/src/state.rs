@@ -71,6 +71,7 @@ pub struct ChangeOutput {
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub struct SubmittedBtcTransaction {
     /// The original retrieve_btc requests that initiated the transaction.
+    /// Empty if the transaction consolidates the minter's UTXOs.
     pub requests: Vec<RetrieveBtcRequest>,
     /// The identifier of the unconfirmed transaction.
     pub txid: Txid,
@@ -86,6 +87,14 @@ pub struct SubmittedBtcTransaction {
     pub fee_per_vbyte: Option<u64>,
 }
 
+impl SubmittedBtcTransaction {
+    /// Returns true if the transaction merges the minter's UTXOs into a single output
+    /// instead of serving retrieve_btc requests.
+    pub fn is_utxo_consolidation(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
 /// Pairs a retrieve_btc request with its outcome.
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub struct FinalizedBtcRetrieval {
@@ -234,6 +243,21 @@ impl Default for Mode {
     }
 }
 
+/// Controls when the minter merges small UTXOs into a single output owned by its main account.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, candid::CandidType, serde::Deserialize)]
+pub struct UtxoConsolidationConfig {
+    /// The minter consolidates UTXOs only if it has at least this many available UTXOs.
+    pub min_utxo_count: u64,
+    /// The maximum value (in satoshi) of a UTXO that the minter consolidates.
+    pub max_utxo_value: u64,
+    /// The maximum number of UTXOs merged by a single consolidation transaction.
+    /// Values below 2 disable the consolidation.
+    pub max_inputs: u64,
+    /// The minter consolidates UTXOs only if the median fee per vbyte (in millisatoshi)
+    /// does not exceed this value.
+    pub max_fee_per_vbyte: u64,
+}
+
 /// The outcome of a UTXO KYT check.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, serde::Deserialize)]
 pub enum UtxoCheckStatus {
@@ -388,6 +412,9 @@ pub struct CkBtcMinterState {
 
     /// Map from burn block index to the the reimbursed request.
     pub reimbursed_transactions: BTreeMap<u64, ReimbursedDeposit>,
+
+    /// The configuration of the UTXO consolidation, if enabled.
+    pub utxo_consolidation: Option<UtxoConsolidationConfig>,
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, CandidType, Serialize, serde::Deserialize)]
@@ -427,6 +454,7 @@ impl CkBtcMinterState {
             mode,
             kyt_fee,
             kyt_principal,
+            utxo_consolidation,
         }: InitArgs,
     ) {
         self.btc_network = btc_network.into();
@@ -442,6 +470,9 @@ impl CkBtcMinterState {
         if let Some(min_confirmations) = min_confirmations {
             self.min_confirmations = min_confirmations;
         }
+        if let Some(utxo_consolidation) = utxo_consolidation {
+            self.utxo_consolidation = Some(utxo_consolidation);
+        }
     }
 
     pub fn upgrade(
@@ -453,6 +484,7 @@ impl CkBtcMinterState {
             mode,
             kyt_principal,
             kyt_fee,
+            utxo_consolidation,
         }: UpgradeArgs,
     ) {
         if let Some(retrieve_btc_min_amount) = retrieve_btc_min_amount {
@@ -482,6 +514,9 @@ impl CkBtcMinterState {
         if let Some(kyt_fee) = kyt_fee {
             self.kyt_fee = kyt_fee;
         }
+        if let Some(utxo_consolidation) = utxo_consolidation {
+            self.utxo_consolidation = Some(utxo_consolidation);
+        }
     }
 
     pub fn validate_config(&self) {
@@ -494,6 +529,14 @@ impl CkBtcMinterState {
         if self.kyt_principal.is_none() {
             ic_cdk::trap("KYT principal is not set");
         }
+        if let Some(config) = &self.utxo_consolidation {
+            if config.max_inputs > crate::MAX_UTXO_CONSOLIDATION_INPUTS {
+                ic_cdk::trap(&format!(
+                    "utxo_consolidation.max_inputs cannot be greater than {}",
+                    crate::MAX_UTXO_CONSOLIDATION_INPUTS
+                ));
+            }
+        }
     }
 
     pub fn check_invariants(&self) -> Result<(), String> {
@@ -726,6 +769,53 @@ impl CkBtcMinterState {
         batch
     }
 
+    /// Returns true if a UTXO consolidation transaction is waiting for finalization.
+    pub fn has_pending_utxo_consolidation(&self) -> bool {
+        self.submitted_transactions
+            .iter()
+            .any(|tx| tx.is_utxo_consolidation())
+    }
+
+    /// Selects the available UTXOs that the minter should merge into a single
+    /// output given the fee per vbyte (in millisatoshi) of the consolidation
+    /// transaction. Returns an empty vector if the minter should not
+    /// consolidate UTXOs at the moment.
+    ///
+    /// The minter consolidates the smallest UTXOs only if the current fee is
+    /// known, there are no retrieve_btc requests to serve and no previous
+    /// consolidation transaction is waiting for finalization.
+    pub fn utxos_to_consolidate(&self, fee_per_vbyte: Option<u64>) -> Vec<Utxo> {
+        let (config, fee_per_vbyte) = match (&self.utxo_consolidation, fee_per_vbyte) {
+            (Some(config), Some(fee_per_vbyte)) => (config, fee_per_vbyte),
+            _ => return vec![],
+        };
+        if fee_per_vbyte > config.max_fee_per_vbyte
+            || (self.available_utxos.len() as u64) < config.min_utxo_count
+            || !self.pending_retrieve_btc_requests.is_empty()
+            || !self.requests_in_flight.is_empty()
+            || self.has_pending_utxo_consolidation()
+        {
+            return vec![];
+        }
+
+        // There is no point in consolidating UTXOs that cannot pay for their own input.
+        let input_fee = (crate::tx_vsize_estimate(1, 0) - crate::tx_vsize_estimate(0, 0))
+            * fee_per_vbyte
+            / 1000;
+        let mut candidates: Vec<&Utxo> = self
+            .available_utxos
+            .iter()
+            .filter(|u| input_fee < u.value && u.value <= config.max_utxo_value)
+            .collect();
+        candidates.sort_by_key(|u| u.value);
+        candidates.truncate(config.max_inputs as usize);
+
+        if candidates.len() < 2 {
+            return vec![];
+        }
+        candidates.into_iter().cloned().collect()
+    }
+
     /// Returns the total number of all retrieve_btc requests that we haven't
     /// finalized yet.
     pub fn count_incomplete_retrieve_btc_requests(&self) -> usize {
@@ -1160,6 +1250,12 @@ impl CkBtcMinterState {
 
         ensure_eq!(self.kyt_fee, other.kyt_fee, "kyt_fee does not match");
 
+        ensure_eq!(
+            self.utxo_consolidation,
+            other.utxo_consolidation,
+            "utxo_consolidation does not match"
+        );
+
         ensure_eq!(
             self.owed_kyt_amount,
             other.owed_kyt_amount,
@@ -1267,6 +1363,7 @@ impl From<InitArgs> for CkBtcMinterState {
             quarantined_utxos: Default::default(),
             pending_reimbursements: Default::default(),
             reimbursed_transactions: Default::default(),
+            utxo_consolidation: args.utxo_consolidation,
         }
     }
 }
@@ -104,6 +104,27 @@ pub enum Event {
         fee_per_vbyte: u64,
     },
 
+    /// Indicates that the minter sent out a new transaction merging the
+    /// specified UTXOs into a single output owned by its main account.
+    #[serde(rename = "consolidated_utxos")]
+    ConsolidatedUtxos {
+        /// The Txid of the Bitcoin transaction.
+        #[serde(rename = "txid")]
+        txid: Txid,
+        /// UTXOs merged by the transaction.
+        #[serde(rename = "utxos")]
+        utxos: Vec<Utxo>,
+        /// The output holding the merged value.
+        #[serde(rename = "change_output")]
+        change_output: ChangeOutput,
+        /// The IC time at which the minter submitted the transaction.
+        #[serde(rename = "submitted_at")]
+        submitted_at: u64,
+        /// The fee per vbyte (in millisatoshi) that we used for the transaction.
+        #[serde(rename = "fee")]
+        fee_per_vbyte: u64,
+    },
+
     /// Indicates that the minter received enough confirmations for a bitcoin
     /// transaction.
     #[serde(rename = "confirmed_transaction")]
@@ -295,6 +316,25 @@ pub fn replay(mut events: impl Iterator<Item = Event>) -> Result<CkBtcMinterStat
                     },
                 );
             }
+            Event::ConsolidatedUtxos {
+                txid,
+                utxos,
+                change_output,
+                submitted_at,
+                fee_per_vbyte,
+            } => {
+                for utxo in utxos.iter() {
+                    state.available_utxos.remove(utxo);
+                }
+                state.push_submitted_transaction(SubmittedBtcTransaction {
+                    requests: vec![],
+                    txid,
+                    used_utxos: utxos,
+                    fee_per_vbyte: Some(fee_per_vbyte),
+                    change_output: Some(change_output),
+                    submitted_at,
+                });
+            }
             Event::ConfirmedBtcTransaction { txid } => {
                 state.finalize_transaction(&txid);
             }
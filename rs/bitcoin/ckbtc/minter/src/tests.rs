@@ -1,13 +1,13 @@
 use crate::MINTER_FEE_CONSTANT;
 use crate::{
-    address::BitcoinAddress, build_unsigned_transaction, estimate_fee, fake_sign, greedy,
-    signature::EncodedSignature, tx, BuildTxError,
+    address::BitcoinAddress, build_consolidation_transaction, build_unsigned_transaction,
+    estimate_fee, fake_sign, greedy, signature::EncodedSignature, tx, BuildTxError,
 };
 use crate::{
     lifecycle::init::InitArgs,
     state::{
         ChangeOutput, CkBtcMinterState, Mode, RetrieveBtcRequest, RetrieveBtcStatus,
-        SubmittedBtcTransaction,
+        SubmittedBtcTransaction, UtxoConsolidationConfig,
     },
 };
 use bitcoin::network::constants::Network as BtcNetwork;
@@ -838,7 +838,8 @@ proptest! {
             min_confirmations: None,
             mode: Mode::GeneralAvailability,
             kyt_fee: None,
-            kyt_principal: None
+            kyt_principal: None,
+            utxo_consolidation: None
         });
         for (utxo, acc_idx) in utxos_acc_idx {
             state.add_utxos(accounts[acc_idx], vec![utxo]);
@@ -862,7 +863,8 @@ proptest! {
             min_confirmations: None,
             mode: Mode::GeneralAvailability,
             kyt_fee: None,
-            kyt_principal: None
+            kyt_principal: None,
+            utxo_consolidation: None
         });
 
         let mut available_amount = 0;
@@ -905,7 +907,8 @@ proptest! {
             min_confirmations: None,
             mode: Mode::GeneralAvailability,
             kyt_fee: None,
-            kyt_principal: None
+            kyt_principal: None,
+            utxo_consolidation: None
         });
 
         for (utxo, acc_idx) in utxos_acc_idx {
@@ -1133,6 +1136,7 @@ fn can_form_a_batch_conditions() {
         mode: Mode::GeneralAvailability,
         kyt_fee: None,
         kyt_principal: None,
+        utxo_consolidation: None,
     });
     // no request, can't form a batch, fail.
     assert!(!state.can_form_a_batch(1, 0));
@@ -1190,6 +1194,7 @@ fn test_build_account_to_utxos_table_pagination() {
         mode: Mode::GeneralAvailability,
         kyt_fee: None,
         kyt_principal: None,
+        utxo_consolidation: None,
     });
     let account1 = Account::from(
         Principal::from_str("gjfkw-yiolw-ncij7-yzhg2-gq6ec-xi6jy-feyni-g26f4-x7afk-thx6z-6ae")
@@ -1240,3 +1245,139 @@ fn test_build_account_to_utxos_table_pagination() {
         assert!(!no_utxo_page.contains(&format!("{}", utxo.outpoint.txid)));
     }
 }
+
+#[test]
+fn test_build_consolidation_transaction() {
+    let mut utxos: BTreeSet<_> = (1..=10)
+        .map(|i| dummy_utxo_from_value(i * 10_000))
+        .collect();
+    let inputs_value = utxos.iter().map(|u| u.value).sum::<u64>();
+    let minter_addr = BitcoinAddress::P2wpkhV0([0; 20]);
+    let fee_per_vbyte = 10_000;
+
+    let (tx, change_output, used_utxos) =
+        build_consolidation_transaction(&mut utxos, minter_addr.clone(), fee_per_vbyte)
+            .expect("failed to build a consolidation transaction");
+
+    assert!(utxos.is_empty());
+    assert_eq!(used_utxos.len(), 10);
+    assert_eq!(tx.inputs.len(), 10);
+    assert_eq!(tx.outputs.len(), 1);
+    assert_eq!(tx.outputs[0].address, minter_addr);
+
+    let fee = fake_sign(&tx).vsize() as u64 * fee_per_vbyte / 1000;
+    assert_eq!(
+        change_output,
+        ChangeOutput {
+            vout: 0,
+            value: inputs_value - fee
+        }
+    );
+    assert_eq!(tx.outputs[0].value, change_output.value);
+
+    // The inputs cannot cover the fee.
+    let mut utxos: BTreeSet<_> = (1..=10).map(dummy_utxo_from_value).collect();
+    let utxos_copy = utxos.clone();
+    assert_eq!(
+        build_consolidation_transaction(&mut utxos, minter_addr, fee_per_vbyte),
+        Err(BuildTxError::AmountTooLow)
+    );
+    assert_eq!(utxos, utxos_copy);
+}
+
+#[test]
+fn utxos_to_consolidate_conditions() {
+    let mut state = CkBtcMinterState::from(InitArgs {
+        btc_network: Network::Regtest.into(),
+        ecdsa_key_name: "".to_string(),
+        retrieve_btc_min_amount: 5_000u64,
+        ledger_id: CanisterId::from_u64(42),
+        max_time_in_queue_nanos: 0,
+        min_confirmations: None,
+        mode: Mode::GeneralAvailability,
+        kyt_fee: None,
+        kyt_principal: None,
+        utxo_consolidation: None,
+    });
+    let account = Account::from(
+        Principal::from_str("gjfkw-yiolw-ncij7-yzhg2-gq6ec-xi6jy-feyni-g26f4-x7afk-thx6z-6ae")
+            .unwrap(),
+    );
+    // UTXOs worth 1_000, 2_000, ..., 10_000 satoshi.
+    state.add_utxos(
+        account,
+        (1..=10).map(|i| dummy_utxo_from_value(i * 1_000)).collect(),
+    );
+    let fee_per_vbyte = 10_000;
+
+    // The consolidation is disabled by default.
+    assert!(state.utxos_to_consolidate(Some(fee_per_vbyte)).is_empty());
+
+    state.utxo_consolidation = Some(UtxoConsolidationConfig {
+        min_utxo_count: 5,
+        max_utxo_value: 8_000,
+        max_inputs: 4,
+        max_fee_per_vbyte: 20_000,
+    });
+    assert_eq!(
+        state
+            .utxos_to_consolidate(Some(fee_per_vbyte))
+            .iter()
+            .map(|u| u.value)
+            .collect::<Vec<_>>(),
+        vec![1_000, 2_000, 3_000, 4_000]
+    );
+    // The smallest UTXO cannot pay for its own input (68 vbytes at 15 sat/vbyte).
+    assert_eq!(
+        state
+            .utxos_to_consolidate(Some(15_000))
+            .iter()
+            .map(|u| u.value)
+            .collect::<Vec<_>>(),
+        vec![2_000, 3_000, 4_000, 5_000]
+    );
+
+    // The current fee is unknown, e.g., because the Bitcoin canister is unavailable.
+    assert!(state.utxos_to_consolidate(None).is_empty());
+
+    // The fees are too high.
+    assert!(state.utxos_to_consolidate(Some(30_000)).is_empty());
+
+    // There are not enough UTXOs.
+    state.utxo_consolidation.as_mut().unwrap().min_utxo_count = 11;
+    assert!(state.utxos_to_consolidate(Some(fee_per_vbyte)).is_empty());
+    state.utxo_consolidation.as_mut().unwrap().min_utxo_count = 5;
+
+    // The minter serves retrieve_btc requests first.
+    state
+        .pending_retrieve_btc_requests
+        .push(RetrieveBtcRequest {
+            amount: 5_000,
+            address: BitcoinAddress::P2wpkhV0([0; 20]),
+            block_index: 0,
+            received_at: 0,
+            kyt_provider: None,
+            reimbursement_account: None,
+        });
+    assert!(state.utxos_to_consolidate(Some(fee_per_vbyte)).is_empty());
+    state.pending_retrieve_btc_requests.clear();
+
+    // There is a pending consolidation transaction.
+    let utxos = state.utxos_to_consolidate(Some(fee_per_vbyte));
+    for utxo in utxos.iter() {
+        state.available_utxos.remove(utxo);
+    }
+    state.push_submitted_transaction(SubmittedBtcTransaction {
+        requests: vec![],
+        txid: [1; 32].into(),
+        used_utxos: utxos,
+        submitted_at: 0,
+        change_output: Some(ChangeOutput {
+            vout: 0,
+            value: 9_000,
+        }),
+        fee_per_vbyte: Some(fee_per_vbyte),
+    });
+    assert!(state.has_pending_utxo_consolidation());
+    assert!(state.utxos_to_consolidate(Some(fee_per_vbyte)).is_empty());
+}
@@ -10,10 +10,11 @@ use ic_ckbtc_kyt::{InitArg as KytInitArg, KytMode, LifecycleArg, SetApiKeyArg};
 use ic_ckbtc_minter::lifecycle::init::{InitArgs as CkbtcMinterInitArgs, MinterArg};
 use ic_ckbtc_minter::lifecycle::upgrade::UpgradeArgs;
 use ic_ckbtc_minter::queries::{EstimateFeeArg, RetrieveBtcStatusRequest, WithdrawalFee};
+use ic_ckbtc_minter::state::eventlog::Event;
 use ic_ckbtc_minter::state::{
     BtcRetrievalStatusV2, Mode, ReimburseDepositTask, ReimbursedDeposit,
     ReimbursementReason::{CallFailed, TaintedDestination},
-    RetrieveBtcStatus, RetrieveBtcStatusV2,
+    RetrieveBtcStatus, RetrieveBtcStatusV2, UtxoConsolidationConfig,
 };
 use ic_ckbtc_minter::updates::get_btc_address::GetBtcAddressArgs;
 use ic_ckbtc_minter::updates::retrieve_btc::{
@@ -112,6 +113,7 @@ fn install_minter(env: &StateMachine, ledger_id: CanisterId) -> CanisterId {
         mode: Mode::GeneralAvailability,
         kyt_fee: None,
         kyt_principal: Some(CanisterId::from(0)),
+        utxo_consolidation: None,
     };
     let minter_arg = MinterArg::Init(args);
     env.install_canister(minter_wasm(), Encode!(&minter_arg).unwrap(), None)
@@ -183,6 +185,7 @@ fn test_wrong_upgrade_parameter() {
         mode: Mode::GeneralAvailability,
         kyt_fee: Some(1001),
         kyt_principal: None,
+        utxo_consolidation: None,
     });
     let args = Encode!(&args).unwrap();
     if env.install_canister(minter_wasm(), args, None).is_ok() {
@@ -198,6 +201,7 @@ fn test_wrong_upgrade_parameter() {
         mode: Mode::GeneralAvailability,
         kyt_fee: Some(1001),
         kyt_principal: None,
+        utxo_consolidation: None,
     });
     let args = Encode!(&args).unwrap();
     if env.install_canister(minter_wasm(), args, None).is_ok() {
@@ -217,6 +221,7 @@ fn test_wrong_upgrade_parameter() {
         mode: Some(Mode::ReadOnly),
         kyt_principal: None,
         kyt_fee: None,
+        utxo_consolidation: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     if env
@@ -245,6 +250,7 @@ fn test_upgrade_read_only() {
         mode: Some(Mode::ReadOnly),
         kyt_principal: Some(CanisterId::from(0)),
         kyt_fee: None,
+        utxo_consolidation: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&minter_arg).unwrap())
@@ -315,6 +321,7 @@ fn test_upgrade_restricted() {
         mode: Some(Mode::RestrictedTo(vec![authorized_principal])),
         kyt_fee: None,
         kyt_principal: Some(CanisterId::from(0)),
+        utxo_consolidation: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&minter_arg).unwrap())
@@ -370,6 +377,7 @@ fn test_upgrade_restricted() {
         mode: Some(Mode::DepositsRestrictedTo(vec![authorized_principal])),
         kyt_principal: Some(CanisterId::from(0)),
         kyt_fee: None,
+        utxo_consolidation: None,
     };
     env.upgrade_canister(minter_id, minter_wasm(), Encode!(&upgrade_args).unwrap())
         .expect("Failed to upgrade the minter canister");
@@ -455,6 +463,7 @@ fn update_balance_should_return_correct_confirmations() {
         mode: None,
         kyt_principal: None,
         kyt_fee: None,
+        utxo_consolidation: None,
     };
     let minter_arg = MinterArg::Upgrade(Some(upgrade_args));
     ckbtc
@@ -573,6 +582,7 @@ fn test_minter() {
         mode: Mode::GeneralAvailability,
         kyt_fee: Some(1001),
         kyt_principal: Some(CanisterId::from(0)),
+        utxo_consolidation: None,
     });
     let args = Encode!(&args).unwrap();
     let minter_id = env.install_canister(minter_wasm(), args, None).unwrap();
@@ -681,6 +691,7 @@ impl CkBtcSetup {
                 mode: Mode::GeneralAvailability,
                 kyt_fee: Some(KYT_FEE),
                 kyt_principal: kyt_id.into(),
+                utxo_consolidation: None,
             }))
             .unwrap(),
         )
@@ -1153,9 +1164,9 @@ impl CkBtcSetup {
         )
     }
 
-    pub fn print_minter_events(&self) {
-        use ic_ckbtc_minter::state::eventlog::{Event, GetEventsArg};
-        let events = Decode!(
+    pub fn get_events(&self) -> Vec<Event> {
+        use ic_ckbtc_minter::state::eventlog::GetEventsArg;
+        Decode!(
             &assert_reply(
                 self.env
                     .query(
@@ -1171,8 +1182,11 @@ impl CkBtcSetup {
             ),
             Vec<Event>
         )
-        .unwrap();
-        println!("{:#?}", events);
+        .unwrap()
+    }
+
+    pub fn print_minter_events(&self) {
+        println!("{:#?}", self.get_events());
     }
 
     pub fn print_minter_logs(&self) {
@@ -1225,7 +1239,7 @@ impl CkBtcSetup {
                 height: 0,
                 outpoint: OutPoint {
                     txid: txid_bytes.into(),
-                    vout: 1,
+                    vout: (tx.output.len() - 1) as u32,
                 },
             },
         );
@@ -1332,6 +1346,143 @@ fn test_transaction_finalization() {
     assert_eq!(ckbtc.get_known_utxos(user), vec![]);
 }
 
+#[test]
+fn test_utxo_consolidation() {
+    let ckbtc = CkBtcSetup::new();
+
+    // Step 1: enable the UTXO consolidation
+
+    let upgrade_args = UpgradeArgs {
+        utxo_consolidation: Some(UtxoConsolidationConfig {
+            min_utxo_count: 3,
+            max_utxo_value: 1_000_000,
+            max_inputs: 3,
+            max_fee_per_vbyte: 10_000,
+        }),
+        ..UpgradeArgs::default()
+    };
+    ckbtc
+        .env
+        .upgrade_canister(
+            ckbtc.minter_id,
+            minter_wasm(),
+            Encode!(&MinterArg::Upgrade(Some(upgrade_args))).unwrap(),
+        )
+        .expect("Failed to upgrade the minter canister");
+
+    // Step 2: deposit three small UTXOs
+
+    let user = Principal::from(ckbtc.caller);
+    let deposit_address = ckbtc.get_btc_address(user);
+    let utxos: Vec<Utxo> = (1..=3)
+        .map(|i| Utxo {
+            height: 0,
+            outpoint: OutPoint {
+                txid: range_to_txid(i..=i + 31),
+                vout: 1,
+            },
+            value: i as u64 * 100_000,
+        })
+        .collect();
+    for utxo in utxos.iter() {
+        ckbtc.push_utxo(deposit_address.clone(), utxo.clone());
+    }
+    let utxo_statuses = Decode!(
+        &assert_reply(
+            ckbtc
+                .env
+                .execute_ingress_as(
+                    ckbtc.caller,
+                    ckbtc.minter_id,
+                    "update_balance",
+                    Encode!(&UpdateBalanceArgs {
+                        owner: Some(user),
+                        subaccount: None,
+                    })
+                    .unwrap()
+                )
+                .expect("failed to update balance")
+        ),
+        Result<Vec<UtxoStatus>, UpdateBalanceError>
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(utxo_statuses.len(), 3);
+    assert_eq!(ckbtc.get_known_utxos(user).len(), 3);
+
+    // Step 3: wait for the consolidation transaction to be signed and sent
+
+    ckbtc.env.advance_time(MAX_TIME_IN_QUEUE);
+    let (txid, change_output) = ckbtc.tick_until("consolidation transaction", 10, |ckbtc| {
+        ckbtc
+            .get_events()
+            .into_iter()
+            .find_map(|event| match event {
+                Event::ConsolidatedUtxos {
+                    txid,
+                    utxos: used_utxos,
+                    change_output,
+                    ..
+                } => {
+                    assert_eq!(used_utxos.len(), 3);
+                    Some((txid, change_output))
+                }
+                _ => None,
+            })
+    });
+    let mempool = ckbtc.mempool();
+    assert_eq!(
+        mempool.len(),
+        1,
+        "consolidation transaction did not appear in the mempool"
+    );
+    let tx = mempool
+        .get(&txid)
+        .expect("the mempool does not contain the consolidation transaction")
+        .clone();
+    assert_eq!(tx.input.len(), 3);
+    assert_eq!(tx.output.len(), 1);
+    assert_eq!(tx.output[0].value, change_output.value);
+    assert!(change_output.value < utxos.iter().map(|u| u.value).sum::<u64>());
+    ckbtc.minter_self_check();
+
+    // Step 4: upgrade the minter, replaying the consolidation event
+
+    ckbtc
+        .env
+        .upgrade_canister(
+            ckbtc.minter_id,
+            minter_wasm(),
+            Encode!(&MinterArg::Upgrade(None)).unwrap(),
+        )
+        .expect("Failed to upgrade the minter canister");
+    ckbtc.minter_self_check();
+
+    // The minter does not send another consolidation transaction while the first one is pending.
+    ckbtc.env.advance_time(MAX_TIME_IN_QUEUE);
+    ckbtc.assert_for_n_ticks("no second consolidation transaction", 5, |ckbtc| {
+        ckbtc.mempool().len() == 1
+    });
+
+    // Step 5: confirm the consolidation transaction
+
+    ckbtc.finalize_transaction(&tx);
+    ckbtc.tick_until("consolidation transaction finalization", 10, |ckbtc| {
+        ckbtc
+            .get_events()
+            .into_iter()
+            .any(|event| matches!(event, Event::ConfirmedBtcTransaction { txid: t } if t == txid))
+            .then_some(())
+    });
+
+    assert_eq!(ckbtc.get_known_utxos(user), vec![]);
+    let main_utxos = ckbtc.get_known_utxos(Principal::from(ckbtc.minter_id));
+    assert_eq!(main_utxos.len(), 1);
+    assert_eq!(main_utxos[0].outpoint.txid, txid);
+    assert_eq!(main_utxos[0].value, change_output.value);
+    ckbtc.minter_self_check();
+}
+
 #[test]
 fn test_min_retrieval_amount_mainnet() {
     let ckbtc = CkBtcSetup::new();
@@ -0,0 +1,435 @@
+// Async mutex is needed to hold the file while writing to it
+#![allow(clippy::disallowed_types)]
+use std::{
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Context, Error};
+use async_trait::async_trait;
+use bytes::Bytes;
+use flate2::{write::GzEncoder, Compression};
+use http::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use prometheus::{register_int_counter_vec_with_registry, IntCounterVec, Registry};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs,
+    io::AsyncWriteExt,
+    sync::{mpsc, Mutex},
+};
+use tracing::warn;
+use url::Url;
+
+use crate::cli::AccessLogConfig;
+
+const FILE_NAME_CURRENT: &str = "access.jsonl.gz";
+const FILE_NAME_PREFIX: &str = "access-";
+const FILE_NAME_SUFFIX: &str = ".jsonl.gz";
+
+// Single request log entry
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccessLogEntry {
+    pub timestamp: u64,
+    pub request_id: String,
+    pub request_type: String,
+    pub http: String,
+    pub status: u16,
+    pub subnet_id: Option<String>,
+    pub node_id: Option<String>,
+    pub canister_id: Option<String>,
+    pub canister_id_actual: Option<String>,
+    pub sender: Option<String>,
+    pub method: Option<String>,
+    pub duration: f64,
+    pub duration_full: f64,
+    pub request_size: u32,
+    pub response_size: u64,
+    pub error_cause: Option<String>,
+    pub cache_status: String,
+    pub cache_bypass_reason: Option<String>,
+    pub rate_limited: Option<String>,
+    pub retry_count: Option<usize>,
+    pub country_code: String,
+}
+
+// Destination for the batches of gzip-compressed JSON lines
+#[async_trait]
+pub trait Sink: Send + Sync {
+    fn name(&self) -> &'static str;
+    async fn write(&self, batch: Bytes) -> Result<(), Error>;
+}
+
+// Encodes the entries as gzip-compressed JSON lines
+fn encode_batch(entries: &[AccessLogEntry]) -> Result<Bytes, Error> {
+    let mut enc = GzEncoder::new(Vec::new(), Compression::default());
+
+    for e in entries {
+        serde_json::to_writer(&mut enc, e)?;
+        enc.write_all(b"\n")?;
+    }
+
+    Ok(Bytes::from(enc.finish()?))
+}
+
+struct CurrentFile {
+    file: fs::File,
+    size: u64,
+}
+
+// Appends the batches to a file in a given directory and rotates it when it grows too large.
+// Gzip allows concatenating the compressed streams, so each file is a valid gzip archive.
+pub struct FileSink {
+    dir: PathBuf,
+    max_size: u64,
+    max_count: usize,
+    current: Mutex<Option<CurrentFile>>,
+}
+
+impl FileSink {
+    pub fn new(dir: PathBuf, max_size: u64, max_count: usize) -> Result<Self, Error> {
+        if max_size == 0 {
+            return Err(anyhow!("max_size should be > 0"));
+        }
+
+        Ok(Self {
+            dir,
+            max_size,
+            max_count,
+            current: Mutex::new(None),
+        })
+    }
+
+    async fn open(&self) -> Result<CurrentFile, Error> {
+        fs::create_dir_all(&self.dir)
+            .await
+            .context("unable to create directory")?;
+
+        let path = self.dir.join(FILE_NAME_CURRENT);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .context("unable to open file")?;
+        let size = file.metadata().await?.len();
+
+        Ok(CurrentFile { file, size })
+    }
+
+    // Renames the current file and removes the oldest rotated files exceeding the limit
+    async fn rotate(&self) -> Result<(), Error> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+
+        fs::rename(
+            self.dir.join(FILE_NAME_CURRENT),
+            self.dir
+                .join(format!("{FILE_NAME_PREFIX}{nanos:020}{FILE_NAME_SUFFIX}")),
+        )
+        .await
+        .context("unable to rename file")?;
+
+        let mut rotated = vec![];
+        let mut dir = fs::read_dir(&self.dir).await?;
+        while let Some(v) = dir.next_entry().await? {
+            let name = v.file_name().to_string_lossy().to_string();
+            if name.starts_with(FILE_NAME_PREFIX) && name.ends_with(FILE_NAME_SUFFIX) {
+                rotated.push(v.path());
+            }
+        }
+
+        // Names contain zero-padded timestamps, so they're sorted oldest-first
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_count);
+        for v in &rotated[..excess] {
+            fs::remove_file(v)
+                .await
+                .context("unable to remove old file")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for FileSink {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    async fn write(&self, batch: Bytes) -> Result<(), Error> {
+        let mut current = self.current.lock().await;
+
+        if current.is_none() {
+            *current = Some(self.open().await?);
+        }
+
+        let f = current.as_mut().unwrap();
+        f.file.write_all(&batch).await.context("unable to write")?;
+        f.file.flush().await.context("unable to flush")?;
+        f.size += batch.len() as u64;
+
+        if f.size >= self.max_size {
+            *current = None;
+            self.rotate().await.context("unable to rotate")?;
+        }
+
+        Ok(())
+    }
+}
+
+// Pushes the batches to an HTTP endpoint as gzip-compressed newline-delimited JSON,
+// which is understood e.g. by Vector's `http_server` source.
+pub struct HttpSink {
+    http_client: reqwest::Client,
+    url: Url,
+}
+
+impl HttpSink {
+    pub fn new(url: Url, timeout: Duration) -> Result<Self, Error> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("unable to create HTTP client")?;
+
+        Ok(Self { http_client, url })
+    }
+}
+
+#[async_trait]
+impl Sink for HttpSink {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn write(&self, batch: Bytes) -> Result<(), Error> {
+        self.http_client
+            .post(self.url.clone())
+            .header(CONTENT_TYPE, "application/x-ndjson")
+            .header(CONTENT_ENCODING, "gzip")
+            .body(batch)
+            .send()
+            .await
+            .context("unable to make request")?
+            .error_for_status()
+            .context("unexpected response status")?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct Metrics {
+    entries: IntCounterVec,
+    batches: IntCounterVec,
+}
+
+impl Metrics {
+    fn new(registry: &Registry) -> Self {
+        Self {
+            entries: register_int_counter_vec_with_registry!(
+                format!("access_log_entries"),
+                format!("Counts access log entries by what happened to them"),
+                &["status"],
+                registry
+            )
+            .unwrap(),
+
+            batches: register_int_counter_vec_with_registry!(
+                format!("access_log_batches"),
+                format!("Counts access log batches written to the sinks"),
+                &["sink", "status"],
+                registry
+            )
+            .unwrap(),
+        }
+    }
+}
+
+// Handle that is used to submit the entries for the export
+#[derive(Clone)]
+pub struct AccessLogger {
+    tx: mpsc::Sender<AccessLogEntry>,
+    sample_rate: f64,
+    keep_failed: bool,
+    metrics: Metrics,
+}
+
+impl AccessLogger {
+    // Samples the entry and queues it for the export.
+    // Drops it if the exporter can't keep up.
+    pub fn log(&self, entry: AccessLogEntry, failed: bool) {
+        if !(failed && self.keep_failed) && rand::random::<f64>() >= self.sample_rate {
+            self.metrics
+                .entries
+                .with_label_values(&["sampled_out"])
+                .inc();
+            return;
+        }
+
+        let status = if self.tx.try_send(entry).is_ok() {
+            "queued"
+        } else {
+            "dropped"
+        };
+
+        self.metrics.entries.with_label_values(&[status]).inc();
+    }
+}
+
+// Collects the entries into batches and writes them to the sinks
+struct Exporter {
+    rx: mpsc::Receiver<AccessLogEntry>,
+    sinks: Vec<Arc<dyn Sink>>,
+    batch_size: usize,
+    flush_interval: Duration,
+    metrics: Metrics,
+}
+
+impl Exporter {
+    async fn flush(&self, batch: &mut Vec<AccessLogEntry>) {
+        if batch.is_empty() {
+            return;
+        }
+
+        let data = match encode_batch(batch) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("AccessLog: unable to encode batch: {e:#}");
+                batch.clear();
+                return;
+            }
+        };
+        batch.clear();
+
+        for s in &self.sinks {
+            let status = match s.write(data.clone()).await {
+                Ok(()) => "ok",
+                Err(e) => {
+                    warn!("AccessLog: unable to write batch to {}: {e:#}", s.name());
+                    "fail"
+                }
+            };
+
+            self.metrics
+                .batches
+                .with_label_values(&[s.name(), status])
+                .inc();
+        }
+    }
+
+    async fn run(mut self) {
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut interval = tokio::time::interval(self.flush_interval);
+
+        loop {
+            tokio::select! {
+                v = self.rx.recv() => {
+                    let Some(v) = v else {
+                        // All senders are gone
+                        self.flush(&mut batch).await;
+                        return;
+                    };
+
+                    batch.push(v);
+                    if batch.len() >= self.batch_size {
+                        self.flush(&mut batch).await;
+                    }
+                }
+
+                _ = interval.tick() => {
+                    self.flush(&mut batch).await;
+                }
+            }
+        }
+    }
+}
+
+fn new_logger(
+    sinks: Vec<Arc<dyn Sink>>,
+    queue_size: usize,
+    batch_size: usize,
+    flush_interval: Duration,
+    sample_rate: f64,
+    keep_failed: bool,
+    registry: &Registry,
+) -> Result<(AccessLogger, Exporter), Error> {
+    if !(0.0..=1.0).contains(&sample_rate) {
+        return Err(anyhow!("sample rate must be in range 0.0..1.0"));
+    }
+
+    if queue_size == 0 || batch_size == 0 || flush_interval == Duration::ZERO {
+        return Err(anyhow!(
+            "queue size, batch size and flush interval should be > 0"
+        ));
+    }
+
+    let (tx, rx) = mpsc::channel(queue_size);
+    let metrics = Metrics::new(registry);
+
+    let logger = AccessLogger {
+        tx,
+        sample_rate,
+        keep_failed,
+        metrics: metrics.clone(),
+    };
+
+    let exporter = Exporter {
+        rx,
+        sinks,
+        batch_size,
+        flush_interval,
+        metrics,
+    };
+
+    Ok((logger, exporter))
+}
+
+// Sets up the access log export if any sinks are configured
+pub fn setup(cli: &AccessLogConfig, registry: &Registry) -> Result<Option<AccessLogger>, Error> {
+    let mut sinks: Vec<Arc<dyn Sink>> = vec![];
+
+    if let Some(v) = &cli.access_log_file_dir {
+        sinks.push(Arc::new(
+            FileSink::new(
+                v.clone(),
+                cli.access_log_file_max_size,
+                cli.access_log_file_max_count,
+            )
+            .context("unable to create file sink")?,
+        ));
+    }
+
+    if let Some(v) = &cli.access_log_http_url {
+        sinks.push(Arc::new(
+            HttpSink::new(
+                v.clone(),
+                Duration::from_millis(cli.access_log_http_timeout),
+            )
+            .context("unable to create HTTP sink")?,
+        ));
+    }
+
+    if sinks.is_empty() {
+        return Ok(None);
+    }
+
+    let (logger, exporter) = new_logger(
+        sinks,
+        cli.access_log_queue_size,
+        cli.access_log_batch_size,
+        Duration::from_millis(cli.access_log_flush_interval),
+        cli.access_log_sample_rate,
+        cli.access_log_keep_failed,
+        registry,
+    )?;
+
+    // Start background task
+    tokio::spawn(exporter.run());
+
+    Ok(Some(logger))
+}
+
+#[cfg(test)]
+pub mod test;
@@ -0,0 +1,126 @@
+use std::{io::Read, sync::Mutex as StdMutex};
+
+use flate2::read::MultiGzDecoder;
+
+use super::*;
+
+fn decode(data: &[u8]) -> Vec<AccessLogEntry> {
+    let mut buf = String::new();
+    MultiGzDecoder::new(data).read_to_string(&mut buf).unwrap();
+
+    buf.lines()
+        .map(|x| serde_json::from_str(x).unwrap())
+        .collect()
+}
+
+fn gen_entry(status: u16) -> AccessLogEntry {
+    AccessLogEntry {
+        timestamp: 1,
+        request_id: "foo".into(),
+        request_type: "query".into(),
+        status,
+        canister_id: Some("aaaaa-aa".into()),
+        method: Some("bar".into()),
+        cache_status: "MISS".into(),
+        rate_limited: (status == 429).then_some("generic".into()),
+        ..Default::default()
+    }
+}
+
+struct MockSink(StdMutex<Vec<AccessLogEntry>>);
+
+#[async_trait]
+impl Sink for MockSink {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn write(&self, batch: Bytes) -> Result<(), Error> {
+        self.0.lock().unwrap().extend(decode(&batch));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_encode_batch() {
+    let entries = vec![gen_entry(200), gen_entry(429), gen_entry(503)];
+    let data = encode_batch(&entries).unwrap();
+    assert_eq!(decode(&data), entries);
+}
+
+#[tokio::test]
+async fn test_file_sink() {
+    let dir = tempfile::tempdir().unwrap();
+    let batch = encode_batch(&[gen_entry(200)]).unwrap();
+
+    // Rotate after every 3 batches, keep 2 rotated files
+    let sink = FileSink::new(dir.path().into(), 3 * batch.len() as u64, 2).unwrap();
+
+    for _ in 0..2 {
+        sink.write(batch.clone()).await.unwrap();
+    }
+
+    // Concatenated batches are readable as a single file
+    let data = std::fs::read(dir.path().join(FILE_NAME_CURRENT)).unwrap();
+    assert_eq!(decode(&data), vec![gen_entry(200); 2]);
+
+    for _ in 0..10 {
+        sink.write(batch.clone()).await.unwrap();
+    }
+
+    let mut files = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|x| x.unwrap().file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    files.sort();
+
+    // 12 batches = 4 rotations, only 2 latest rotated files are kept
+    assert_eq!(files.len(), 2);
+    for f in files {
+        assert!(f.starts_with(FILE_NAME_PREFIX) && f.ends_with(FILE_NAME_SUFFIX));
+        let data = std::fs::read(dir.path().join(f)).unwrap();
+        assert_eq!(decode(&data), vec![gen_entry(200); 3]);
+    }
+}
+
+#[tokio::test]
+async fn test_logger() {
+    let sink = Arc::new(MockSink(StdMutex::new(vec![])));
+
+    // Check bad params
+    assert!(new_logger(
+        vec![sink.clone() as Arc<dyn Sink>],
+        10,
+        10,
+        Duration::from_secs(1),
+        1.1,
+        false,
+        &Registry::new()
+    )
+    .is_err());
+
+    // Sample out everything except failed requests
+    let (logger, exporter) = new_logger(
+        vec![sink.clone() as Arc<dyn Sink>],
+        100,
+        10,
+        Duration::from_secs(3600),
+        0.0,
+        true,
+        &Registry::new(),
+    )
+    .unwrap();
+
+    let task = tokio::spawn(exporter.run());
+
+    for _ in 0..20 {
+        logger.log(gen_entry(200), false);
+        logger.log(gen_entry(503), true);
+    }
+
+    // Exporter flushes the remaining entries once the logger is gone
+    drop(logger);
+    task.await.unwrap();
+
+    assert_eq!(*sink.0.lock().unwrap(), vec![gen_entry(503); 20]);
+}
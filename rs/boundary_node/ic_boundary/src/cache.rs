@@ -1,4 +1,8 @@
-use std::{fmt, sync::Arc, time::Duration};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{anyhow, Context, Error};
 use axum::{
@@ -10,18 +14,33 @@ use axum::{
     Extension,
 };
 use bytes::Bytes;
+use candid::Principal;
+use dashmap::DashSet;
 use http::{
     header::{HeaderMap, CACHE_CONTROL, CONTENT_LENGTH},
     response, Version,
 };
 use ic_bn_lib::http::body::buffer_body;
-use moka::future::{Cache as MokaCache, CacheBuilder as MokaCacheBuilder};
+use ic_crypto_tree_hash::{LookupStatus, MixedHashTree};
+use ic_types::messages::Certificate;
+use moka::{
+    future::{Cache as MokaCache, CacheBuilder as MokaCacheBuilder},
+    sync::Cache as MokaSyncCache,
+};
+use serde::Deserialize;
+use serde_bytes::ByteBuf;
 
 use crate::routes::{ApiError, ErrorCause, RequestContext};
 
 // A list of possible Cache-Control directives that ask us not to cache the response
 const SKIP_CACHE_DIRECTIVES: &[&str] = &["no-store", "no-cache", "max-age=0"];
 
+// Max number of canisters to track the update calls for
+const INVALIDATIONS_CAPACITY: u64 = 1_000_000;
+
+// Max size of a synchronous update call response to look for the certified time in
+const MAX_SYNC_CALL_RESPONSE_SIZE: u64 = 1024 * 1024;
+
 // Reason why the caching was skipped
 #[derive(Clone, PartialEq, Debug)]
 pub enum CacheBypassReason {
@@ -53,6 +72,8 @@ pub enum CacheStatus {
     Bypass(CacheBypassReason),
     Hit,
     Miss,
+    // Stale response was served while it's being revalidated in the background
+    Stale,
 }
 
 // Injects itself into a given response to be accessible by middleware
@@ -70,22 +91,93 @@ impl fmt::Display for CacheStatus {
             Self::Bypass(_) => write!(f, "BYPASS"),
             Self::Hit => write!(f, "HIT"),
             Self::Miss => write!(f, "MISS"),
+            Self::Stale => write!(f, "STALE"),
         }
     }
 }
 
+// Parts of the query response that we're interested in
+#[derive(Deserialize)]
+struct QueryResponseSignatures {
+    #[serde(default)]
+    signatures: Vec<QueryResponseSignature>,
+}
+
+#[derive(Deserialize)]
+struct QueryResponseSignature {
+    timestamp: u64,
+}
+
+// Extracts the latest timestamp that the replicas have signed the query response with
+fn extract_signed_timestamp(body: &[u8]) -> Option<u64> {
+    serde_cbor::from_slice::<QueryResponseSignatures>(body)
+        .ok()?
+        .signatures
+        .into_iter()
+        .map(|x| x.timestamp)
+        .max()
+}
+
+// Parts of the synchronous (v3) update call response that we're interested in
+#[derive(Deserialize)]
+struct SyncCallResponse {
+    status: String,
+    certificate: Option<ByteBuf>,
+}
+
+// Extracts the time certified by the subnet in the certificate of a replied synchronous update call
+fn extract_certified_time(body: &[u8]) -> Option<u64> {
+    let response = serde_cbor::from_slice::<SyncCallResponse>(body).ok()?;
+    if response.status != "replied" {
+        return None;
+    }
+
+    let certificate = serde_cbor::from_slice::<Certificate>(&response.certificate?).ok()?;
+    match certificate.tree.lookup(&[b"time"]) {
+        LookupStatus::Found(MixedHashTree::Leaf(time)) => {
+            leb128::read::unsigned(&mut time.as_slice()).ok()
+        }
+        _ => None,
+    }
+}
+
+fn now_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64
+}
+
 #[derive(Clone)]
 struct CacheItem {
     status: StatusCode,
     version: Version,
     headers: HeaderMap,
     body: Bytes,
+    // Replica-signed timestamp of the response (nanoseconds since epoch).
+    // If the response isn't signed then it's the time when it was stored.
+    timestamp: u64,
+    // Local time when the response was stored (nanoseconds since epoch)
+    inserted: u64,
+}
+
+// Result of a cache lookup
+enum Lookup {
+    Fresh(Response),
+    Stale(Response),
+    Miss,
 }
 
 #[derive(Clone)]
 pub struct Cache {
     cache: MokaCache<Arc<RequestContext>, CacheItem>,
+    // Certified time of the last replied update call per canister (nanoseconds since epoch)
+    invalidations: MokaSyncCache<Principal, u64>,
+    // Requests that are being revalidated in the background
+    revalidating: Arc<DashSet<Arc<RequestContext>>>,
     max_item_size: u64,
+    ttl: Duration,
+    stale_while_revalidate: Duration,
     cache_non_anonymous: bool,
 }
 
@@ -110,11 +202,23 @@ fn weigh_entry(k: &Arc<RequestContext>, v: &CacheItem) -> u32 {
 // Max cost represents the max sum of items' costs that the cache can hold.
 // If this is exceeded then some items would be purged.
 // We assume that a cache item's cost is a number of bytes it takes in memory.
+//
+// The age of an item is counted from the local time it was cached: the network latency and
+// the clock skew between the replicas and us would make a short TTL meaningless if it was
+// counted from the replica-signed timestamp. Items older than `ttl` are served for another
+// `stale_while_revalidate` while being refreshed in the background.
+//
+// The replica-signed timestamp is used to order the responses and to compare them with
+// the update calls: items of the canister that replied to a synchronous (v3) update call
+// certified at or after the time they were signed are considered outdated and are not served.
+// Asynchronous (v2) update calls do not invalidate anything, so their effects become
+// visible in the cached responses only after at most `ttl` + `stale_while_revalidate`.
 impl Cache {
     pub fn new(
         cache_size: u64,
         max_item_size: u64,
         ttl: Duration,
+        stale_while_revalidate: Duration,
         cache_non_anonymous: bool,
     ) -> Result<Self, Error> {
         if max_item_size >= cache_size {
@@ -124,13 +228,23 @@ impl Cache {
         }
 
         let cache = MokaCacheBuilder::new(cache_size)
-            .time_to_live(ttl)
+            .time_to_live(ttl + stale_while_revalidate)
             .weigher(weigh_entry)
             .build();
 
+        // Invalidations older than any cached item are useless
+        let invalidations = MokaSyncCache::builder()
+            .max_capacity(INVALIDATIONS_CAPACITY)
+            .time_to_live(ttl + stale_while_revalidate)
+            .build();
+
         Ok(Self {
             cache,
+            invalidations,
+            revalidating: Arc::new(DashSet::new()),
             max_item_size,
+            ttl,
+            stale_while_revalidate,
             cache_non_anonymous,
         })
     }
@@ -138,11 +252,23 @@ impl Cache {
     // Stores the response components in the cache
     // Response itself cannot be stored since it's not cloneable, so we have to rebuild it
     async fn store(&self, ctx: Arc<RequestContext>, parts: &response::Parts, body: Bytes) {
+        let inserted = now_nanos();
+        let timestamp = extract_signed_timestamp(&body).unwrap_or(inserted);
+
+        // Do not replace the item with an older one, e.g. from a lagging replica
+        if let Some(v) = self.cache.get(&ctx).await {
+            if v.timestamp > timestamp {
+                return;
+            }
+        }
+
         let item = CacheItem {
             status: parts.status,
             version: parts.version,
             headers: parts.headers.clone(),
             body,
+            timestamp,
+            inserted,
         };
 
         // Insert the response into the cache & wait for it to persist there
@@ -150,12 +276,25 @@ impl Cache {
     }
 
     // Looks up the request in the cache
-    async fn lookup(&self, ctx: &RequestContext) -> Option<Response> {
+    async fn lookup(&self, ctx: &RequestContext) -> Lookup {
         let item = match self.cache.get(ctx).await {
             Some(v) => v,
-            None => return None,
+            None => return Lookup::Miss,
         };
 
+        // Check if the canister was updated after the response was signed
+        if let Some(v) = ctx.canister_id.and_then(|x| self.invalidations.get(&x)) {
+            if v >= item.timestamp {
+                return Lookup::Miss;
+            }
+        }
+
+        let age = Duration::from_nanos(now_nanos().saturating_sub(item.inserted));
+        let fresh = age < self.ttl;
+        if !fresh && age >= self.ttl + self.stale_while_revalidate {
+            return Lookup::Miss;
+        }
+
         // If an item was found -> construct a response from the cached data
         let mut builder = Response::builder()
             .status(item.status)
@@ -163,7 +302,70 @@ impl Cache {
 
         *builder.headers_mut().unwrap() = item.headers;
 
-        Some(builder.body(Body::from(item.body)).unwrap())
+        let response = builder.body(Body::from(item.body)).unwrap();
+        if fresh {
+            Lookup::Fresh(response)
+        } else {
+            Lookup::Stale(response)
+        }
+    }
+
+    // Marks the cached responses of the given canister that were signed no later than
+    // the given certified time of an update call reply (nanoseconds since epoch) as outdated
+    pub fn invalidate(&self, canister_id: Principal, certified_time: u64) {
+        // Do not move the invalidation back in time, e.g. because of a lagging replica
+        if let Some(v) = self.invalidations.get(&canister_id) {
+            if v >= certified_time {
+                return;
+            }
+        }
+
+        self.invalidations.insert(canister_id, certified_time);
+    }
+
+    // Checks the response and stores it in the cache if it's cacheable
+    async fn process_response(
+        &self,
+        ctx: Arc<RequestContext>,
+        response: Response,
+    ) -> Result<Response, ApiError> {
+        // Do not cache non-2xx responses
+        if !response.status().is_success() {
+            return Ok(CacheStatus::Bypass(CacheBypassReason::HTTPError).with_response(response));
+        }
+
+        let content_length = extract_content_length(&response).map_err(|_| {
+            ErrorCause::MalformedResponse("Malformed Content-Length header in response".into())
+        })?;
+
+        // Do not cache responses that have no known size (probably streaming etc)
+        let body_size = match content_length {
+            Some(v) => v,
+            None => {
+                return Ok(
+                    CacheStatus::Bypass(CacheBypassReason::SizeUnknown).with_response(response)
+                )
+            }
+        };
+
+        // Do not cache items larger than configured
+        if body_size > self.max_item_size {
+            return Ok(CacheStatus::Bypass(CacheBypassReason::TooBig).with_response(response));
+        }
+
+        // Buffer entire response body to be able to cache it
+        let (parts, body) = response.into_parts();
+        let body = buffer_body(body, body_size as usize, Duration::from_secs(60))
+            .await
+            .context("unable to read body")?;
+
+        // Insert the response into the cache
+        self.store(ctx, &parts, body.clone()).await;
+
+        // Reconstruct the response from components
+        let response = Response::from_parts(parts, Body::from(body));
+
+        Ok(CacheStatus::Miss.with_response(response))
     }
 
     pub fn size(&self) -> u64 {
@@ -231,48 +433,69 @@ pub async fn cache_middleware(
     }
 
     // Try to look up the request in the cache
-    if let Some(v) = cache.lookup(&ctx).await {
-        return Ok(CacheStatus::Hit.with_response(v));
+    match cache.lookup(&ctx).await {
+        Lookup::Fresh(v) => return Ok(CacheStatus::Hit.with_response(v)),
+
+        Lookup::Stale(v) => {
+            // Revalidate in the background unless it's already being done
+            if cache.revalidating.insert(ctx.clone()) {
+                let cache = cache.clone();
+
+                tokio::spawn(async move {
+                    let response = next.run(request).await;
+                    let _ = cache.process_response(ctx.clone(), response).await;
+                    cache.revalidating.remove(&ctx);
+                });
+            }
+
+            return Ok(CacheStatus::Stale.with_response(v));
+        }
+
+        Lookup::Miss => {}
     }
 
     // If not found - pass the request down the stack
     let response = next.run(request).await;
+    cache.process_response(ctx, response).await
+}
 
-    // Do not cache non-2xx responses
-    if !response.status().is_success() {
-        return Ok(CacheStatus::Bypass(CacheBypassReason::HTTPError).with_response(response));
-    }
-
-    let content_length = extract_content_length(&response).map_err(|_| {
-        ErrorCause::MalformedResponse("Malformed Content-Length header in response".into())
-    })?;
+// Axum middleware that invalidates the cached responses of the canisters that have replied
+// to synchronous (v3) update calls. The invalidation is keyed on the time certified in the
+// reply, so that it is comparable with the replica-signed timestamps of the cached responses.
+// Responses without a certified time, like the ones to asynchronous (v2) update calls that
+// may still be executing, do not invalidate anything.
+pub async fn cache_invalidation_middleware(
+    State(cache): State<Arc<Cache>>,
+    Extension(ctx): Extension<Arc<RequestContext>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let response = next.run(request).await;
 
-    // Do not cache responses that have no known size (probably streaming etc)
-    let body_size = match content_length {
-        Some(v) => v,
-        None => {
-            return Ok(CacheStatus::Bypass(CacheBypassReason::SizeUnknown).with_response(response))
-        }
+    let Some(canister_id) = ctx.canister_id else {
+        return Ok(response);
     };
 
-    // Do not cache items larger than configured
-    if body_size > cache.max_item_size {
-        return Ok(CacheStatus::Bypass(CacheBypassReason::TooBig).with_response(response));
+    if !response.status().is_success() {
+        return Ok(response);
     }
 
-    // Buffer entire response body to be able to cache it
+    // Only look into responses of a known & reasonable size
+    let body_size = match extract_content_length(&response) {
+        Ok(Some(v)) if v <= MAX_SYNC_CALL_RESPONSE_SIZE => v,
+        _ => return Ok(response),
+    };
+
     let (parts, body) = response.into_parts();
     let body = buffer_body(body, body_size as usize, Duration::from_secs(60))
         .await
         .context("unable to read body")?;
 
-    // Insert the response into the cache
-    cache.store(ctx, &parts, body.clone()).await;
-
-    // Reconstruct the response from components
-    let response = Response::from_parts(parts, Body::from(body));
+    if let Some(v) = extract_certified_time(&body) {
+        cache.invalidate(canister_id, v);
+    }
 
-    Ok(CacheStatus::Miss.with_response(response))
+    Ok(Response::from_parts(parts, Body::from(body)))
 }
 
 #[cfg(test)]
@@ -8,6 +8,8 @@ use axum::{
 };
 use candid::Principal;
 use http::header::HeaderValue;
+use ic_types::messages::Blob;
+use serde::Serialize;
 use tower::Service;
 
 use crate::routes::ANONYMOUS_PRINCIPAL;
@@ -66,15 +68,370 @@ async fn handler(
     (status_code, "a".repeat(size as usize))
 }
 
+#[derive(Clone)]
+struct SignedTimestamp(u64);
+
+#[derive(Serialize)]
+struct TestQueryResponse {
+    status: &'static str,
+    signatures: Vec<TestQueryResponseSignature>,
+}
+
+#[derive(Serialize)]
+struct TestQueryResponseSignature {
+    timestamp: u64,
+    #[serde(with = "serde_bytes")]
+    signature: Vec<u8>,
+}
+
+fn gen_signed_body(timestamp: u64) -> Vec<u8> {
+    let mut ser = serde_cbor::Serializer::new(Vec::new());
+    ser.self_describe().unwrap();
+    TestQueryResponse {
+        status: "replied",
+        signatures: vec![TestQueryResponseSignature {
+            timestamp,
+            signature: vec![0; 64],
+        }],
+    }
+    .serialize(&mut ser)
+    .unwrap();
+
+    ser.into_inner()
+}
+
+// Generate a query response signed with a requested timestamp
+async fn handler_signed(Extension(ts): Extension<SignedTimestamp>) -> impl IntoResponse {
+    (StatusCode::OK, gen_signed_body(ts.0))
+}
+
+fn gen_signed_request(age: Duration) -> Request<Body> {
+    let mut req = gen_request(CANISTER_1, false);
+    req.extensions_mut()
+        .insert(SignedTimestamp(now_nanos() - age.as_nanos() as u64));
+    req
+}
+
+async fn call_signed(app: &mut Router, age: Duration) -> CacheStatus {
+    let res = app.call(gen_signed_request(age)).await.unwrap();
+    res.extensions().get::<CacheStatus>().cloned().unwrap()
+}
+
+// Pretends that the cached response to the signed request was stored `age` ago
+async fn age_cached(cache: &Cache, age: Duration) {
+    let ctx = gen_request(CANISTER_1, false)
+        .extensions()
+        .get::<Arc<RequestContext>>()
+        .cloned()
+        .unwrap();
+
+    let mut item = cache.cache.get(&ctx).await.unwrap();
+    item.inserted -= age.as_nanos() as u64;
+    cache.cache.insert(ctx, item).await;
+}
+
+#[derive(Serialize)]
+struct TestSyncCallResponse {
+    status: &'static str,
+    #[serde(with = "serde_bytes")]
+    certificate: Vec<u8>,
+}
+
+fn gen_certified_body(status: &'static str, time: u64) -> Vec<u8> {
+    let mut leb_time = vec![];
+    leb128::write::unsigned(&mut leb_time, time).unwrap();
+
+    let certificate = Certificate {
+        tree: MixedHashTree::Labeled("time".into(), Box::new(MixedHashTree::Leaf(leb_time))),
+        signature: Blob(vec![0; 48]),
+        delegation: None,
+    };
+
+    serde_cbor::to_vec(&TestSyncCallResponse {
+        status,
+        certificate: serde_cbor::to_vec(&certificate).unwrap(),
+    })
+    .unwrap()
+}
+
+#[derive(Clone)]
+struct CertifiedReply(&'static str, u64);
+
+// Generate a synchronous update call response certified at a requested time
+async fn handler_certified(Extension(reply): Extension<CertifiedReply>) -> impl IntoResponse {
+    (StatusCode::OK, gen_certified_body(reply.0, reply.1))
+}
+
+fn gen_certified_request(status: &'static str, age: Duration) -> Request<Body> {
+    let mut req = gen_request(CANISTER_1, false);
+    req.extensions_mut()
+        .insert(CertifiedReply(status, now_nanos() - age.as_nanos() as u64));
+    req
+}
+
+#[test]
+fn test_extract_certified_time() {
+    assert_eq!(extract_certified_time(b"foobar"), None);
+    assert_eq!(
+        extract_certified_time(&gen_certified_body("replied", 42)),
+        Some(42)
+    );
+    assert_eq!(
+        extract_certified_time(&gen_certified_body("non_replicated_rejection", 42)),
+        None
+    );
+
+    let no_time = Certificate {
+        tree: MixedHashTree::Labeled("foo".into(), Box::new(MixedHashTree::Leaf(vec![1]))),
+        signature: Blob(vec![]),
+        delegation: None,
+    };
+    assert_eq!(
+        extract_certified_time(
+            &serde_cbor::to_vec(&TestSyncCallResponse {
+                status: "replied",
+                certificate: serde_cbor::to_vec(&no_time).unwrap(),
+            })
+            .unwrap()
+        ),
+        None
+    );
+}
+
+#[tokio::test]
+async fn test_cache_invalidation() -> Result<(), Error> {
+    let cache = Arc::new(Cache::new(
+        MAX_MEM_SIZE,
+        MAX_RESP_SIZE,
+        Duration::from_secs(10),
+        Duration::ZERO,
+        false,
+    )?);
+
+    let mut app =
+        Router::new()
+            .route("/", post(handler_signed))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&cache),
+                cache_middleware,
+            ));
+
+    let mut call =
+        Router::new()
+            .route("/", post(handler_certified))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&cache),
+                cache_invalidation_middleware,
+            ));
+
+    let age = Duration::from_secs(2);
+    assert_eq!(call_signed(&mut app, age).await, CacheStatus::Miss);
+    assert_eq!(call_signed(&mut app, age).await, CacheStatus::Hit);
+
+    // Rejected call does not invalidate the cached responses
+    call.call(gen_certified_request(
+        "non_replicated_rejection",
+        Duration::ZERO,
+    ))
+    .await
+    .unwrap();
+    assert_eq!(call_signed(&mut app, age).await, CacheStatus::Hit);
+
+    // Asynchronous (v2) call is only accepted, it has no certified time and does not
+    // invalidate the cached responses, even though it may be executed later on
+    let mut call_v2 = Router::new()
+        .route("/", post(|| async { StatusCode::ACCEPTED }))
+        .layer(middleware::from_fn_with_state(
+            Arc::clone(&cache),
+            cache_invalidation_middleware,
+        ));
+    let res = call_v2.call(gen_request(CANISTER_1, false)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::ACCEPTED);
+    assert_eq!(call_signed(&mut app, age).await, CacheStatus::Hit);
+
+    // Call certified before the cached response was signed does not invalidate it
+    let res = call
+        .call(gen_certified_request("replied", Duration::from_secs(5)))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(call_signed(&mut app, age).await, CacheStatus::Hit);
+
+    // Call certified after the cached response was signed invalidates it
+    let res = call
+        .call(gen_certified_request("replied", Duration::from_secs(1)))
+        .await
+        .unwrap();
+    let body = buffer_body(res.into_body(), 1024 * 1024, Duration::from_secs(10)).await?;
+    assert!(extract_certified_time(&body).is_some());
+    assert_eq!(call_signed(&mut app, age).await, CacheStatus::Miss);
+
+    // An older call does not move the invalidation back in time
+    cache.invalidate(Principal::from_text(CANISTER_1).unwrap(), 0);
+    assert_eq!(call_signed(&mut app, age).await, CacheStatus::Miss);
+
+    // Responses signed after the call are served from the cache again
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Miss
+    );
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Hit
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_extract_signed_timestamp() {
+    assert_eq!(extract_signed_timestamp(b"foobar"), None);
+    assert_eq!(
+        extract_signed_timestamp(
+            &serde_cbor::to_vec(&TestQueryResponse {
+                status: "replied",
+                signatures: vec![],
+            })
+            .unwrap()
+        ),
+        None
+    );
+    assert_eq!(
+        extract_signed_timestamp(
+            &serde_cbor::to_vec(&TestQueryResponse {
+                status: "replied",
+                signatures: vec![
+                    TestQueryResponseSignature {
+                        timestamp: 10,
+                        signature: vec![],
+                    },
+                    TestQueryResponseSignature {
+                        timestamp: 20,
+                        signature: vec![],
+                    },
+                ],
+            })
+            .unwrap()
+        ),
+        Some(20)
+    );
+}
+
+#[tokio::test]
+async fn test_cache_signed() -> Result<(), Error> {
+    let ttl = Duration::from_secs(10);
+    let swr = Duration::from_secs(60);
+
+    let cache = Arc::new(Cache::new(MAX_MEM_SIZE, MAX_RESP_SIZE, ttl, swr, false)?);
+
+    let mut app =
+        Router::new()
+            .route("/", post(handler_signed))
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&cache),
+                cache_middleware,
+            ));
+
+    // Fresh response is served from the cache
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Miss
+    );
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Hit
+    );
+
+    // The age is counted from the time of caching, not from the signed timestamp,
+    // so the latency and the clock skew don't make the response stale right away
+    cache.clear().await;
+    assert_eq!(
+        call_signed(&mut app, ttl + Duration::from_secs(1)).await,
+        CacheStatus::Miss
+    );
+    assert_eq!(
+        call_signed(&mut app, ttl + Duration::from_secs(1)).await,
+        CacheStatus::Hit
+    );
+
+    // Stale response is served while being revalidated with a fresh one in the background
+    age_cached(&cache, ttl + Duration::from_secs(1)).await;
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Stale
+    );
+    let mut revalidated = false;
+    for _ in 0..100 {
+        if cache.revalidating.is_empty() {
+            revalidated = true;
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(revalidated);
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Hit
+    );
+
+    // A response older than the cached one does not replace it
+    cache.clear().await;
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Miss
+    );
+    let ctx = gen_request(CANISTER_1, false)
+        .extensions()
+        .get::<Arc<RequestContext>>()
+        .cloned()
+        .unwrap();
+    let (parts, _) = Response::new(()).into_parts();
+    let old_body = Bytes::from(gen_signed_body(
+        now_nanos() - Duration::from_secs(5).as_nanos() as u64,
+    ));
+    cache.store(ctx.clone(), &parts, old_body.clone()).await;
+    assert_ne!(cache.cache.get(&ctx).await.unwrap().body, old_body);
+
+    // Responses older than TTL + stale-while-revalidate are not served
+    cache.clear().await;
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Miss
+    );
+    age_cached(&cache, ttl + swr + Duration::from_secs(1)).await;
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Miss
+    );
+
+    // Update call to the canister makes its cached responses outdated
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Hit
+    );
+    cache.invalidate(Principal::from_text(CANISTER_1).unwrap(), now_nanos());
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Miss
+    );
+    assert_eq!(
+        call_signed(&mut app, Duration::ZERO).await,
+        CacheStatus::Hit
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_cache() -> Result<(), Error> {
     // Check that we fail if item size >= max size
-    assert!(Cache::new(1024, 1024, Duration::from_secs(60), false).is_err());
+    assert!(Cache::new(1024, 1024, Duration::from_secs(60), Duration::ZERO, false).is_err());
 
     let cache = Cache::new(
         MAX_MEM_SIZE,
         MAX_RESP_SIZE,
         Duration::from_secs(3600),
+        Duration::ZERO,
         false,
     )?;
     let cache = Arc::new(cache);
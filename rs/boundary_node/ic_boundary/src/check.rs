@@ -23,6 +23,7 @@ use url::Url;
 
 use crate::{
     core::Run,
+    latency::LatencyTracker,
     metrics::{MetricParamsCheck, WithMetricsCheck},
     persist::Persist,
     snapshot::RegistrySnapshot,
@@ -88,6 +89,7 @@ struct NodeActor {
     channel: mpsc::Sender<(usize, NodeState)>,
     token: CancellationToken,
     checker: Arc<dyn Check>,
+    latency_tracker: Option<Arc<LatencyTracker>>,
     state: Option<NodeState>,
     avg_mov_latency: LatencyMovAvg,
     checks_counter: usize,
@@ -100,6 +102,7 @@ impl NodeActor {
         channel: mpsc::Sender<(usize, NodeState)>,
         token: CancellationToken,
         checker: Arc<dyn Check>,
+        latency_tracker: Option<Arc<LatencyTracker>>,
     ) -> Self {
         Self {
             idx,
@@ -107,6 +110,7 @@ impl NodeActor {
             channel,
             token,
             checker,
+            latency_tracker,
             state: None,
             avg_mov_latency: LatencyMovAvg::new(),
             checks_counter: 0,
@@ -122,7 +126,12 @@ impl NodeActor {
 
         let (healthy, height, latency_change) = match &res {
             Ok(res) => {
-                let latency = start.elapsed().as_secs_f64();
+                let elapsed = start.elapsed();
+                if let Some(v) = &self.latency_tracker {
+                    v.observe(self.node.id, elapsed);
+                }
+
+                let latency = elapsed.as_secs_f64();
                 let current_avg = self.avg_mov_latency.get_average();
                 self.avg_mov_latency.add_sample(latency);
                 let latency_change = (latency - current_avg).abs() / current_avg;
@@ -204,6 +213,7 @@ impl SubnetActor {
         check_interval: Duration,
         token: CancellationToken,
         checker: Arc<dyn Check>,
+        latency_tracker: Option<Arc<LatencyTracker>>,
         channel_out: mpsc::Sender<(usize, Subnet)>,
         max_height_lag: u64,
     ) -> Self {
@@ -218,6 +228,7 @@ impl SubnetActor {
                 channel_send.clone(),
                 token_nodes.child_token(),
                 checker.clone(),
+                latency_tracker.clone(),
             );
 
             tracker.spawn(async move {
@@ -389,6 +400,7 @@ impl GlobalActor {
         update_interval: Duration,
         max_height_lag: u64,
         checker: Arc<dyn Check>,
+        latency_tracker: Option<Arc<LatencyTracker>>,
         persister: Arc<dyn Persist>,
         token: CancellationToken,
     ) -> Self {
@@ -404,6 +416,7 @@ impl GlobalActor {
                 check_interval,
                 token_subnets.child_token(),
                 checker.clone(),
+                latency_tracker.clone(),
                 channel_send.clone(),
                 max_height_lag,
             );
@@ -491,6 +504,7 @@ pub struct Runner {
     tracker: TaskTracker,
     token: CancellationToken,
     checker: Arc<dyn Check>,
+    latency_tracker: Option<Arc<LatencyTracker>>,
     persister: Arc<dyn Persist>,
     channel_snapshot: watch::Receiver<Option<Arc<RegistrySnapshot>>>,
 }
@@ -510,12 +524,18 @@ impl Runner {
             token: CancellationToken::new(),
             persister,
             checker,
+            latency_tracker: None,
             check_interval,
             update_interval,
             channel_snapshot,
         }
     }
 
+    // Feed the latencies of the successful health checks into the given tracker
+    pub fn set_latency_tracker(&mut self, latency_tracker: Arc<LatencyTracker>) {
+        self.latency_tracker = Some(latency_tracker);
+    }
+
     // Start global actor
     fn start(&mut self) {
         self.tracker = TaskTracker::new();
@@ -531,6 +551,7 @@ impl Runner {
             self.update_interval,
             self.max_height_lag,
             self.checker.clone(),
+            self.latency_tracker.clone(),
             self.persister.clone(),
             self.token.child_token(),
         );
@@ -39,6 +39,9 @@ pub struct Cli {
 
     #[command(flatten, next_help_heading = "bouncer")]
     pub bouncer: BouncerConfig,
+
+    #[command(flatten, next_help_heading = "access_log")]
+    pub access_log: AccessLogConfig,
 }
 
 #[derive(Args)]
@@ -295,11 +298,31 @@ pub struct RateLimitingConfig {
     ///   canister_id: aaaaa-aa
     ///   methods: ^baz$
     ///   limit: block (this blocks all requests)
+    ///
+    /// - canister_id: aaaaa-aa
+    ///   limit: 1000/1m
+    ///   scope: global (the limit is shared with the peers, see --rate-limit-generic-peers)
     #[clap(
         long,
         default_value = "/run/ic-node/etc/ic-boundary/canister-ratelimit.yml"
     )]
     pub rate_limit_generic: PathBuf,
+
+    /// Comma separated list of peer boundary nodes' metrics endpoints (e.g. http://[::1]:9324)
+    /// to share the counters of the global generic rate-limiter rules with.
+    /// If not specified then the global rules are enforced by this node alone.
+    /// Since the counters are exchanged periodically, the nodes can collectively admit
+    /// more requests than allowed until they learn about each other's counters.
+    #[clap(long, value_delimiter = ',')]
+    pub rate_limit_generic_peers: Vec<Url>,
+
+    /// Unique identifier of this node among the peers. Random if not specified.
+    #[clap(long)]
+    pub rate_limit_generic_node_id: Option<String>,
+
+    /// How frequently to exchange the counters with the peers in milliseconds
+    #[clap(long, default_value = "1000")]
+    pub rate_limit_generic_sync_interval: u64,
 }
 
 #[derive(Args)]
@@ -312,10 +335,18 @@ pub struct CacheConfig {
     #[clap(long, default_value = "131072")]
     pub cache_max_item_size_bytes: u64,
 
-    /// Time-to-live for cache entries in seconds
+    /// Time-to-live for cache entries in seconds.
+    /// The age of an entry is counted from the time it was cached.
+    /// Asynchronous (v2) update calls don't invalidate the entries of the canister,
+    /// so their effects can take up to the TTL plus the stale-while-revalidate time to become visible.
     #[clap(long, default_value = "1")]
     pub cache_ttl_seconds: u64,
 
+    /// For how long in seconds after the TTL has passed an entry can still be served
+    /// while it's being refreshed in the background. Zero disables stale-while-revalidate.
+    #[clap(long, default_value = "0")]
+    pub cache_stale_while_revalidate_seconds: u64,
+
     /// Whether to cache non-anonymous requests
     #[clap(long, default_value = "false")]
     pub cache_non_anonymous: bool,
@@ -336,6 +367,30 @@ pub struct RetryConfig {
     /// Whether to use latency-based routing for /call
     #[clap(long, default_value = "false")]
     pub disable_latency_routing: bool,
+
+    /// Whether to prefer the nodes with lower observed latency for queries and read_state.
+    /// Latency is measured by the health checks and the proxied requests.
+    #[clap(long, default_value = "false")]
+    pub retry_weighted_routing: bool,
+
+    /// Weight of a new latency sample in the moving average, should be in range (0.0..1.0]
+    #[clap(long, default_value = "0.1", value_parser = parse_ewma_alpha)]
+    pub retry_latency_ewma_alpha: f64,
+
+    /// Whether to hedge the queries: if the node does not respond within the estimated
+    /// 95th percentile of its latency - the query is also sent to another node
+    /// and the first response is used.
+    #[clap(long, default_value = "false")]
+    pub retry_hedge_queries: bool,
+
+    /// Minimum delay in milliseconds before sending a hedged query
+    #[clap(long, default_value = "50")]
+    pub retry_hedge_delay_min: u64,
+
+    /// Maximum delay in milliseconds before sending a hedged query.
+    /// Also used if the latency of the node is not yet known.
+    #[clap(long, default_value = "1000")]
+    pub retry_hedge_delay_max: u64,
 }
 
 #[derive(Args)]
@@ -398,3 +453,58 @@ pub struct BouncerConfig {
     #[clap(long, default_value = "blackhole6")]
     pub bouncer_v6_set: String,
 }
+
+#[derive(Args)]
+pub struct AccessLogConfig {
+    /// Directory to write the request logs to as gzip-compressed JSON lines.
+    /// The current file is `access.jsonl.gz`, rotated ones are `access-<timestamp>.jsonl.gz`.
+    #[clap(long)]
+    pub access_log_file_dir: Option<PathBuf>,
+
+    /// Size of the current file in bytes after which it's rotated
+    #[clap(long, default_value = "104857600")]
+    pub access_log_file_max_size: u64,
+
+    /// Number of rotated files to keep, older ones are removed
+    #[clap(long, default_value = "10")]
+    pub access_log_file_max_count: usize,
+
+    /// URL to push the request logs to as gzip-compressed newline-delimited JSON (e.g. Vector's http_server source)
+    #[clap(long)]
+    pub access_log_http_url: Option<Url>,
+
+    /// Timeout for pushing a batch to the HTTP sink in milliseconds
+    #[clap(long, default_value = "10000")]
+    pub access_log_http_timeout: u64,
+
+    /// Maximum number of entries in a batch
+    #[clap(long, default_value = "1000")]
+    pub access_log_batch_size: usize,
+
+    /// How frequently to flush the incomplete batch in milliseconds
+    #[clap(long, default_value = "5000")]
+    pub access_log_flush_interval: u64,
+
+    /// Maximum number of entries waiting for the export, new ones are dropped if exceeded
+    #[clap(long, default_value = "100000")]
+    pub access_log_queue_size: usize,
+
+    /// Fraction of the requests to export, in range 0.0..1.0
+    #[clap(long, default_value = "1.0")]
+    pub access_log_sample_rate: f64,
+
+    /// Export all failed requests regardless of --access-log-sample-rate
+    #[clap(long)]
+    pub access_log_keep_failed: bool,
+}
+
+// Parses the weight of a new sample in an exponentially weighted moving average
+pub fn parse_ewma_alpha(s: &str) -> Result<f64, String> {
+    let alpha = s.parse::<f64>().map_err(|e| e.to_string())?;
+
+    if !(alpha > 0.0 && alpha <= 1.0) {
+        return Err("should be in range (0.0..1.0]".into());
+    }
+
+    Ok(alpha)
+}
@@ -35,21 +35,23 @@ use tower_http::{compression::CompressionLayer, request_id::MakeRequestUuid, Ser
 use tracing::{debug, error, warn};
 
 use crate::{
+    access_log::{self, AccessLogger},
     bouncer,
-    cache::{cache_middleware, Cache},
+    cache::{cache_invalidation_middleware, cache_middleware, Cache},
     check::{Checker, Runner as CheckRunner},
     cli::Cli,
     dns::DnsResolver,
     firewall::{FirewallGenerator, SystemdReloader},
     geoip,
+    latency::LatencyTracker,
     metrics::{
         self, HttpMetricParams, HttpMetricParamsStatus, MetricParams, MetricParamsCheck,
         MetricParamsPersist, MetricParamsSnapshot, MetricsCache, MetricsRunner, WithMetrics,
         WithMetricsCheck, WithMetricsPersist, WithMetricsSnapshot, HTTP_DURATION_BUCKETS,
     },
     persist::{Persist, Persister, Routes},
-    rate_limiting::{generic, RateLimit},
-    retry::{retry_request, RetryParams},
+    rate_limiting::{generic, shared, RateLimit},
+    retry::{retry_request, HedgeParams, RetryParams},
     routes::{self, ErrorCause, Health, Lookup, Proxy, ProxyRouter, RootKey},
     snapshot::{
         generate_stub_snapshot, generate_stub_subnet, RegistrySnapshot, SnapshotPersister,
@@ -189,6 +191,7 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
                 x,
                 cli.cache.cache_max_item_size_bytes,
                 Duration::from_secs(cli.cache.cache_ttl_seconds),
+                Duration::from_secs(cli.cache.cache_stale_while_revalidate_seconds),
                 cli.cache.cache_non_anonymous,
             )
             .expect("unable to initialize cache"),
@@ -202,10 +205,45 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
         None
     };
 
+    // Latency tracking
+    let latency_tracker = (cli.retry.retry_weighted_routing || cli.retry.retry_hedge_queries)
+        .then(|| Arc::new(LatencyTracker::new(cli.retry.retry_latency_ewma_alpha)));
+
+    // Access log export
+    let access_logger = access_log::setup(&cli.access_log, &metrics_registry)
+        .context("unable to setup access log export")?;
+
     // Generic Ratelimiter
-    let generic_limiter = Arc::new(generic::Limiter::new(
-        cli.rate_limiting.rate_limit_generic.clone(),
-    ));
+    let generic_peer_state = if !cli.rate_limiting.rate_limit_generic_peers.is_empty() {
+        Some(Arc::new(
+            shared::PeerState::new(
+                cli.rate_limiting.rate_limit_generic_peers.clone(),
+                Duration::from_millis(cli.rate_limiting.rate_limit_generic_sync_interval),
+            )
+            .context("unable to setup generic rate-limiter peers")?,
+        ))
+    } else {
+        None
+    };
+
+    let generic_limiter = Arc::new(match &generic_peer_state {
+        Some(v) => {
+            let node_id = cli
+                .rate_limiting
+                .rate_limit_generic_node_id
+                .clone()
+                .unwrap_or_else(|| format!("{:016x}", rand::random::<u64>()));
+
+            warn!("GenericLimiter: sharing counters as node '{node_id}'");
+
+            generic::Limiter::new_with_shared_state(
+                cli.rate_limiting.rate_limit_generic.clone(),
+                node_id,
+                v.clone(),
+            )
+        }
+        None => generic::Limiter::new(cli.rate_limiting.rate_limit_generic.clone()),
+    });
 
     // Prepare Axum Router
     let router = setup_router(
@@ -217,6 +255,8 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
         &cli,
         &metrics_registry,
         cache.clone(),
+        access_logger,
+        latency_tracker.clone(),
     );
 
     // HTTP server metrics
@@ -286,6 +326,13 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
 
     let metrics_router = Router::new()
         .route("/metrics", get(metrics::metrics_handler))
+        .merge(
+            generic_peer_state
+                .map(|x| {
+                    Router::new().route(shared::PATH_COUNTERS, get(shared::handler).with_state(x))
+                })
+                .unwrap_or_default(),
+        )
         .layer(
             CompressionLayer::new()
                 .gzip(true)
@@ -329,6 +376,7 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
                     registry_snapshot.clone(),
                     WithMetricsPersist(persister, MetricParamsPersist::new(&metrics_registry)),
                     http_client_check,
+                    latency_tracker,
                     &metrics_registry,
                 )?;
 
@@ -343,11 +391,20 @@ pub async fn main(cli: Cli) -> Result<(), Error> {
             (None, None, vec![])
         };
 
+    let generic_limiter_sync_runner = WithThrottle(
+        generic::Syncer(generic_limiter.clone()),
+        ThrottleParams::new(Duration::from_millis(
+            cli.rate_limiting.rate_limit_generic_sync_interval,
+        )),
+    );
     let generic_limiter_runner = WithThrottle(generic_limiter, ThrottleParams::new(10 * SECOND));
 
     // Runners
-    let mut runners: Vec<Box<dyn Run>> =
-        vec![Box::new(metrics_runner), Box::new(generic_limiter_runner)];
+    let mut runners: Vec<Box<dyn Run>> = vec![
+        Box::new(metrics_runner),
+        Box::new(generic_limiter_runner),
+        Box::new(generic_limiter_sync_runner),
+    ];
     runners.append(&mut registry_runners);
 
     TokioScope::scope_and_block(move |s| {
@@ -422,6 +479,7 @@ fn setup_registry(
     registry_snapshot: Arc<ArcSwapOption<RegistrySnapshot>>,
     persister: WithMetricsPersist<Persister>,
     http_client_check: Arc<dyn http::Client>,
+    latency_tracker: Option<Arc<LatencyTracker>>,
     metrics_registry: &Registry,
 ) -> Result<RegistrySetupResult, Error> {
     // Registry Client
@@ -475,7 +533,7 @@ fn setup_registry(
     );
     let checker = WithMetricsCheck(checker, MetricParamsCheck::new(metrics_registry));
 
-    let check_runner = CheckRunner::new(
+    let mut check_runner = CheckRunner::new(
         channel_snapshot_recv,
         cli.health.max_height_lag,
         Arc::new(persister),
@@ -484,6 +542,10 @@ fn setup_registry(
         Duration::from_millis(cli.health.update_interval),
     );
 
+    if let Some(v) = latency_tracker {
+        check_runner.set_latency_tracker(v);
+    }
+
     let (registry_replicator, nns_pub_key) = if !cli.registry.disable_registry_replicator {
         // Check if we require an NNS key
         let nns_pub_key = {
@@ -664,6 +726,8 @@ pub fn setup_router(
     cli: &Cli,
     metrics_registry: &Registry,
     cache: Option<Arc<Cache>>,
+    access_logger: Option<AccessLogger>,
+    latency_tracker: Option<Arc<LatencyTracker>>,
 ) -> Router {
     let proxy_router = ProxyRouter::new(
         http_client.clone(),
@@ -684,16 +748,22 @@ pub fn setup_router(
         .route(routes::PATH_QUERY, {
             post(routes::handle_canister).with_state(proxy.clone())
         })
-        .layer(option_layer(cache.map(|x| {
+        .layer(option_layer(cache.clone().map(|x| {
             middleware::from_fn_with_state(x.clone(), cache_middleware)
         })));
 
     let call_route = {
         let mut route = Router::new()
-            .route(routes::PATH_CALL, {
+            .route(routes::PATH_CALL_V3, {
                 post(routes::handle_canister).with_state(proxy.clone())
             })
-            .route(routes::PATH_CALL_V3, {
+            // Replied synchronous update calls make the cached query responses of the canister
+            // outdated. Asynchronous (v2) calls are not covered: their replies carry no certified
+            // time and they may still be executing.
+            .layer(option_layer(cache.map(|x| {
+                middleware::from_fn_with_state(x, cache_invalidation_middleware)
+            })))
+            .route(routes::PATH_CALL, {
                 post(routes::handle_canister).with_state(proxy.clone())
             });
 
@@ -738,6 +808,7 @@ pub fn setup_router(
                 metrics_registry,
                 "http_request",
                 cli.monitoring.log_failed_requests_only,
+                access_logger,
             ),
             metrics::metrics_middleware,
         ),
@@ -751,6 +822,12 @@ pub fn setup_router(
             retry_count: cli.retry.retry_count as usize,
             retry_update_call: cli.retry.retry_update_call,
             disable_latency_routing: cli.retry.disable_latency_routing,
+            latency_tracker,
+            weighted_routing: cli.retry.retry_weighted_routing,
+            hedging: cli.retry.retry_hedge_queries.then(|| HedgeParams {
+                delay_min: Duration::from_millis(cli.retry.retry_hedge_delay_min),
+                delay_max: Duration::from_millis(cli.retry.retry_hedge_delay_max),
+            }),
         },
         retry_request,
     );
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+use candid::Principal;
+use dashmap::DashMap;
+
+// z-score of the 95th percentile of a normal distribution
+const Z_P95: f64 = 1.645;
+
+// Exponentially weighted moving average of the latency and its variance
+#[derive(Copy, Clone, Debug)]
+struct Ewma {
+    mean: f64,
+    var: f64,
+}
+
+// Tracks the latency of the nodes as observed by the health checks and the proxied requests
+pub struct LatencyTracker {
+    alpha: f64,
+    nodes: DashMap<Principal, Ewma>,
+}
+
+impl LatencyTracker {
+    // Alpha is the weight of a new sample in range (0.0..1.0]
+    pub fn new(alpha: f64) -> Self {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            panic!("EWMA alpha must be in range (0.0..1.0]");
+        }
+
+        Self {
+            alpha,
+            nodes: DashMap::new(),
+        }
+    }
+
+    pub fn observe(&self, node_id: Principal, latency: Duration) {
+        let x = latency.as_secs_f64();
+
+        self.nodes
+            .entry(node_id)
+            .and_modify(|e| {
+                let diff = x - e.mean;
+                let incr = self.alpha * diff;
+                e.mean += incr;
+                e.var = (1.0 - self.alpha) * (e.var + diff * incr);
+            })
+            .or_insert(Ewma { mean: x, var: 0.0 });
+    }
+
+    // Average latency of the node in seconds
+    pub fn mean(&self, node_id: &Principal) -> Option<f64> {
+        self.nodes.get(node_id).map(|x| x.mean)
+    }
+
+    // Estimated 95th percentile of the node's latency, assuming it's normally distributed
+    pub fn p95(&self, node_id: &Principal) -> Option<Duration> {
+        self.nodes
+            .get(node_id)
+            .map(|x| Duration::from_secs_f64(x.mean + Z_P95 * x.var.sqrt()))
+    }
+}
+
+#[cfg(test)]
+pub mod test;
@@ -0,0 +1,81 @@
+use super::*;
+
+use std::collections::HashMap;
+
+use crate::{cli::parse_ewma_alpha, routes::test::test_route_subnet};
+
+#[test]
+fn test_latency_tracker() {
+    let tracker = LatencyTracker::new(0.5);
+    let id1 = Principal::from_text("f7crg-kabae").unwrap();
+    let id2 = Principal::from_text("sqjm4-qahae-aq").unwrap();
+
+    assert_eq!(tracker.mean(&id1), None);
+    assert_eq!(tracker.p95(&id1), None);
+
+    // First sample initializes the average
+    tracker.observe(id1, Duration::from_millis(100));
+    assert_eq!(tracker.mean(&id1), Some(0.1));
+    assert_eq!(tracker.p95(&id1), Some(Duration::from_millis(100)));
+
+    // Then it moves towards the new samples
+    tracker.observe(id1, Duration::from_millis(300));
+    assert!((tracker.mean(&id1).unwrap() - 0.2).abs() < 1e-9);
+    // Variance is (1 - 0.5) * (0.2 * 0.1) = 0.01, so p95 is 0.2 + 1.645 * 0.1
+    assert_eq!(
+        tracker.p95(&id1).unwrap().as_micros(),
+        Duration::from_secs_f64(0.3645).as_micros()
+    );
+
+    // Stable latency converges with a shrinking deviation
+    for _ in 0..100 {
+        tracker.observe(id1, Duration::from_millis(50));
+    }
+    assert!((tracker.mean(&id1).unwrap() - 0.05).abs() < 1e-6);
+    assert!(tracker.p95(&id1).unwrap() < Duration::from_millis(51));
+
+    // Other nodes are tracked separately
+    assert_eq!(tracker.mean(&id2), None);
+}
+
+#[test]
+fn test_parse_ewma_alpha() {
+    assert_eq!(parse_ewma_alpha("0.1"), Ok(0.1));
+    assert_eq!(parse_ewma_alpha("1"), Ok(1.0));
+
+    for v in ["0", "-0.5", "1.01", "NaN", "inf", "foo", ""] {
+        assert!(parse_ewma_alpha(v).is_err(), "{v}");
+    }
+}
+
+#[test]
+fn test_pick_weighted_nodes() {
+    let tracker = LatencyTracker::new(1.0);
+    let subnet = test_route_subnet(3);
+
+    // Without any data all nodes are picked equally
+    let nodes = subnet.pick_weighted_nodes(3, &tracker).unwrap();
+    assert_eq!(nodes.len(), 3);
+
+    // Node 0 is 100x faster than node 1, node 2 is unknown and treated as the fastest one
+    tracker.observe(subnet.nodes[0].id, Duration::from_millis(1));
+    tracker.observe(subnet.nodes[1].id, Duration::from_millis(100));
+
+    let mut picked = HashMap::new();
+    for _ in 0..1000 {
+        let node = subnet.pick_weighted_nodes(1, &tracker).unwrap();
+        *picked.entry(node[0].id).or_insert(0) += 1;
+    }
+
+    let slow = picked.get(&subnet.nodes[1].id).copied().unwrap_or(0);
+    assert!(slow < 50);
+    assert!(picked[&subnet.nodes[0].id] > 300);
+    assert!(picked[&subnet.nodes[2].id] > 300);
+
+    // Requesting more nodes than available returns all of them
+    assert_eq!(subnet.pick_weighted_nodes(10, &tracker).unwrap().len(), 3);
+
+    // Empty subnet
+    let subnet = test_route_subnet(0);
+    assert!(subnet.pick_weighted_nodes(1, &tracker).is_err());
+}
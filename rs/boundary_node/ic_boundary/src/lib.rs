@@ -1,3 +1,4 @@
+mod access_log;
 mod bouncer;
 mod cache;
 mod check;
@@ -7,6 +8,7 @@ mod dns;
 mod firewall;
 mod geoip;
 mod http;
+mod latency;
 mod metrics;
 mod persist;
 mod rate_limiting;
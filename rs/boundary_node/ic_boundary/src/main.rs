@@ -4,6 +4,7 @@ use tikv_jemallocator::Jemalloc;
 
 use crate::cli::Cli;
 
+mod access_log;
 mod bouncer;
 mod cache;
 mod check;
@@ -13,6 +14,7 @@ mod dns;
 mod firewall;
 mod geoip;
 mod http;
+mod latency;
 mod log;
 mod metrics;
 mod persist;
@@ -1,6 +1,9 @@
 #![allow(clippy::disallowed_types)]
 
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::Error;
 use arc_swap::ArcSwapOption;
@@ -26,6 +29,7 @@ use tower_http::request_id::RequestId;
 use tracing::info;
 
 use crate::{
+    access_log::{AccessLogEntry, AccessLogger},
     cache::{Cache, CacheStatus},
     core::Run,
     geoip,
@@ -356,10 +360,16 @@ pub struct HttpMetricParams {
     pub durationer: HistogramVec,
     pub request_sizer: HistogramVec,
     pub response_sizer: HistogramVec,
+    pub access_logger: Option<AccessLogger>,
 }
 
 impl HttpMetricParams {
-    pub fn new(registry: &Registry, action: &str, log_failed_requests_only: bool) -> Self {
+    pub fn new(
+        registry: &Registry,
+        action: &str,
+        log_failed_requests_only: bool,
+        access_logger: Option<AccessLogger>,
+    ) -> Self {
         const LABELS_HTTP: &[&str] = &[
             "request_type",
             "status_code",
@@ -408,6 +418,8 @@ impl HttpMetricParams {
                 registry
             )
             .unwrap(),
+
+            access_logger,
         }
     }
 }
@@ -561,6 +573,7 @@ pub async fn metrics_middleware(
         durationer,
         request_sizer,
         response_sizer,
+        access_logger,
     } = metric_params;
 
     let (parts, body) = response.into_parts();
@@ -573,6 +586,11 @@ pub async fn metrics_middleware(
         let full_duration = start_time.elapsed().as_secs_f64();
         let failed = error_cause.is_some() || !status_code.is_success();
 
+        let rate_limited = match &error_cause {
+            Some(ErrorCause::RateLimited(v)) => Some(v.to_string()),
+            _ => None,
+        };
+
         let (error_cause, error_details) = match &error_cause {
             Some(v) => (Some(v.to_string()), v.details()),
             None => (None, None),
@@ -584,6 +602,7 @@ pub async fn metrics_middleware(
         };
 
         let retry_result = retry_result.clone();
+        let retry_count = retry_result.as_ref().map(|x| x.retries);
 
         // Prepare labels
         // Otherwise "temporary value dropped" error occurs
@@ -646,11 +665,43 @@ pub async fn metrics_middleware(
                 retry_count = &retry_result.as_ref().map(|x| x.retries),
                 retry_success = &retry_result.map(|x| x.success),
                 %cache_status,
-                cache_bypass_reason = cache_bypass_reason.map(|x| x.to_string()),
+                cache_bypass_reason = cache_bypass_reason.clone(),
                 country_code,
                 client_ip_family = ip_family,
             );
         }
+
+        if let Some(v) = access_logger {
+            v.log(
+                AccessLogEntry {
+                    timestamp: SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs(),
+                    request_id,
+                    request_type: request_type.to_string(),
+                    http: http_version.to_string(),
+                    status: status_code.as_u16(),
+                    subnet_id,
+                    node_id,
+                    canister_id,
+                    canister_id_actual: canister_id_actual.map(|x| x.to_string()),
+                    sender,
+                    method: ctx.method_name.clone(),
+                    duration: proc_duration,
+                    duration_full: full_duration,
+                    request_size: ctx.request_size,
+                    response_size,
+                    error_cause,
+                    cache_status: cache_status.to_string(),
+                    cache_bypass_reason,
+                    rate_limited,
+                    retry_count,
+                    country_code,
+                },
+                failed,
+            );
+        }
     });
 
     Response::from_parts(parts, body)
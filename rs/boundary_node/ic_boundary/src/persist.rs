@@ -8,6 +8,7 @@ use rand::seq::SliceRandom;
 use tracing::{debug, error};
 
 use crate::{
+    latency::LatencyTracker,
     metrics::{MetricParamsPersist, WithMetricsPersist},
     routes::ErrorCause,
     snapshot::{Node, Subnet},
@@ -69,6 +70,49 @@ impl RouteSubnet {
         Ok(nodes)
     }
 
+    // Picks the nodes randomly with a probability inversely proportional to their average latency.
+    // Nodes without known latency get the weight of the fastest node so that they're measured too.
+    pub fn pick_weighted_nodes(
+        &self,
+        n: usize,
+        latency_tracker: &LatencyTracker,
+    ) -> Result<Vec<Arc<Node>>, ErrorCause> {
+        let means = self
+            .nodes
+            .iter()
+            .map(|x| latency_tracker.mean(&x.id))
+            .collect::<Vec<_>>();
+
+        let fastest = means
+            .iter()
+            .flatten()
+            .copied()
+            .min_by(|a, b| a.total_cmp(b));
+
+        let weighted = self
+            .nodes
+            .iter()
+            .zip(means)
+            .map(|(node, mean)| {
+                // Avoid division by zero for the unrealistically fast nodes
+                let weight = mean.or(fastest).map(|x| 1.0 / x.max(1e-6)).unwrap_or(1.0);
+                (node, weight)
+            })
+            .collect::<Vec<_>>();
+
+        let nodes = match weighted.choose_multiple_weighted(&mut rand::thread_rng(), n, |x| x.1) {
+            Ok(v) => v.map(|x| x.0.clone()).collect::<Vec<_>>(),
+            // Weights are always positive & finite, but fall back to random just in case
+            Err(_) => return self.pick_random_nodes(n),
+        };
+
+        if nodes.is_empty() {
+            return Err(ErrorCause::NoHealthyNodes);
+        }
+
+        Ok(nodes)
+    }
+
     // max acceptable number of malicious nodes in a subnet
     pub fn fault_tolerance_factor(&self) -> usize {
         (self.nodes.len() - 1) / 3
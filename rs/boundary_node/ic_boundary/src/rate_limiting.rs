@@ -105,6 +105,7 @@ impl RateLimit {
 }
 
 pub mod generic;
+pub mod shared;
 
 #[cfg(test)]
 pub mod test;
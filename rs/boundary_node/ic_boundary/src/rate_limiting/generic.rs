@@ -1,4 +1,12 @@
-use std::{fmt, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{Context, Error};
 use arc_swap::ArcSwap;
@@ -25,6 +33,7 @@ use tracing::warn;
 use crate::{
     core::Run,
     persist::RouteSubnet,
+    rate_limiting::shared::{Counter, SharedState},
     routes::{ErrorCause, RateLimitCause, RequestContext, RequestType},
 };
 
@@ -78,6 +87,15 @@ impl<'de> Deserialize<'de> for Action {
     }
 }
 
+/// Whether the limit applies to each boundary node separately or to all of them together
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Scope {
+    #[default]
+    Local,
+    Global,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 struct Rule {
     subnet_id: Option<Principal>,
@@ -86,6 +104,25 @@ struct Rule {
     #[serde(default, with = "serde_regex")]
     methods: Option<Regex>,
     limit: Action,
+    #[serde(default)]
+    scope: Scope,
+}
+
+impl Rule {
+    /// Identifies the rule across the nodes that share the same ruleset
+    fn key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{:?}",
+            self.subnet_id.map(|x| x.to_text()).unwrap_or_default(),
+            self.canister_id.map(|x| x.to_text()).unwrap_or_default(),
+            self.request_type.map(|x| x.to_string()).unwrap_or_default(),
+            self.methods
+                .as_ref()
+                .map(|x| x.as_str())
+                .unwrap_or_default(),
+            self.limit,
+        )
+    }
 }
 
 /// Regex does not implement Eq, so do it manually
@@ -95,13 +132,113 @@ impl PartialEq for Rule {
             && self.canister_id == other.canister_id
             && self.subnet_id == other.subnet_id
             && self.limit == other.limit
+            && self.scope == other.scope
     }
 }
 impl Eq for Rule {}
 
+/// Fixed-window counter of a global rule.
+/// The requests admitted by the other nodes in the current window are learned
+/// from the shared state and are taken into account when admitting new ones.
+///
+/// Each counter is stored together with the lower 32 bits of the window it belongs to
+/// in a single atomic, so that switching to a new window and counting a request
+/// happen in one compare-and-swap and no request can be lost in between.
+struct SharedBucket {
+    key: String,
+    limit: u64,
+    interval: Duration,
+    local: AtomicU64,
+    remote: AtomicU64,
+}
+
+impl SharedBucket {
+    fn new(key: String, limit: u32, interval: Duration, now: Duration) -> Self {
+        let empty = pack((now.as_nanos() / interval.as_nanos()) as u64, 0);
+
+        Self {
+            key,
+            limit: limit as u64,
+            interval,
+            local: AtomicU64::new(empty),
+            remote: AtomicU64::new(empty),
+        }
+    }
+
+    fn window(&self, now: Duration) -> u64 {
+        (now.as_nanos() / self.interval.as_nanos()) as u64
+    }
+
+    fn try_acquire(&self, now: Duration) -> bool {
+        let window = self.window(now);
+        let remote = self.remote.load(Ordering::SeqCst);
+
+        self.local
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |state| {
+                // A caller with a slightly stale clock counts towards the newer window
+                let state = roll(state, window);
+                let count = state & COUNT_MASK;
+                let remote = if tag(remote) == tag(state) {
+                    remote & COUNT_MASK
+                } else {
+                    0
+                };
+
+                (count + remote < self.limit).then_some(state + 1)
+            })
+            .is_ok()
+    }
+
+    fn counter(&self, now: Duration) -> Counter {
+        let window = self.window(now);
+        let state = self.local.load(Ordering::SeqCst);
+
+        Counter {
+            key: self.key.clone(),
+            window,
+            count: if tag(state) == window as u32 {
+                state & COUNT_MASK
+            } else {
+                0
+            },
+        }
+    }
+
+    fn set_remote(&self, window: u64, count: u64) {
+        let _ = self
+            .remote
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |state| {
+                // Never replace the counters of a newer window with the ones of an older one
+                (tag(roll(state, window)) == window as u32).then_some(pack(window, count))
+            });
+    }
+}
+
+const COUNT_MASK: u64 = u32::MAX as u64;
+
+/// Packs the lower 32 bits of the window and the count, saturated to 32 bits, together
+fn pack(window: u64, count: u64) -> u64 {
+    (window << 32) | count.min(COUNT_MASK)
+}
+
+fn tag(state: u64) -> u32 {
+    (state >> 32) as u32
+}
+
+/// Resets the packed state if `window` is newer than the one it belongs to.
+/// Window tags are compared with wraparound.
+fn roll(state: u64, window: u64) -> u64 {
+    if (window as u32).wrapping_sub(tag(state)) as i32 > 0 {
+        pack(window, 0)
+    } else {
+        state
+    }
+}
+
 struct Bucket {
     rule: Rule,
     limiter: Option<Ratelimiter>,
+    shared: Option<SharedBucket>,
 }
 
 impl PartialEq for Bucket {
@@ -111,9 +248,18 @@ impl PartialEq for Bucket {
 }
 impl Eq for Bucket {}
 
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
 pub struct Limiter {
     path: PathBuf,
     buckets: ArcSwap<Vec<Bucket>>,
+    node_id: String,
+    shared_state: Option<Arc<dyn SharedState>>,
+    clock: Arc<dyn Fn() -> Duration + Send + Sync>,
 }
 
 impl Limiter {
@@ -121,29 +267,62 @@ impl Limiter {
         Self {
             path,
             buckets: ArcSwap::new(Arc::new(vec![])),
+            node_id: String::new(),
+            shared_state: None,
+            clock: Arc::new(now),
         }
     }
 
-    fn process_rules(rules: Vec<Rule>) -> Vec<Bucket> {
+    /// Creates a limiter that enforces the global rules together with the other nodes
+    /// that use the same shared state. `node_id` must be unique among them.
+    pub fn new_with_shared_state(
+        path: PathBuf,
+        node_id: String,
+        shared_state: Arc<dyn SharedState>,
+    ) -> Self {
+        Self {
+            node_id,
+            shared_state: Some(shared_state),
+            ..Self::new(path)
+        }
+    }
+
+    /// Replaces the time source that determines the windows of the global rules
+    #[cfg(test)]
+    fn with_clock(self, clock: Arc<dyn Fn() -> Duration + Send + Sync>) -> Self {
+        Self { clock, ..self }
+    }
+
+    fn process_rules(rules: Vec<Rule>, now: Duration) -> Vec<Bucket> {
         rules
             .into_iter()
             .map(|rule| {
-                let limiter = if let Action::Limit(limit, duration) = rule.limit {
-                    Some(
-                        Ratelimiter::builder(
-                            1,
-                            duration.checked_div(limit).unwrap_or(Duration::ZERO),
-                        )
-                        .max_tokens(limit as u64)
-                        .initial_available(limit as u64)
-                        .build()
-                        .unwrap(),
-                    )
-                } else {
-                    None
+                let (limiter, shared) = match (&rule.limit, rule.scope) {
+                    (Action::Limit(limit, duration), Scope::Local) => (
+                        Some(
+                            Ratelimiter::builder(
+                                1,
+                                duration.checked_div(*limit).unwrap_or(Duration::ZERO),
+                            )
+                            .max_tokens(*limit as u64)
+                            .initial_available(*limit as u64)
+                            .build()
+                            .unwrap(),
+                        ),
+                        None,
+                    ),
+                    (Action::Limit(limit, duration), Scope::Global) => {
+                        let bucket = SharedBucket::new(rule.key(), *limit, *duration, now);
+                        (None, Some(bucket))
+                    }
+                    (Action::Block, _) => (None, None),
                 };
 
-                Bucket { rule, limiter }
+                Bucket {
+                    rule,
+                    limiter,
+                    shared,
+                }
             })
             .collect()
     }
@@ -162,7 +341,7 @@ impl Limiter {
     }
 
     fn apply_rules(&self, rules: Vec<Rule>) -> bool {
-        let new = Arc::new(Self::process_rules(rules));
+        let new = Arc::new(Self::process_rules(rules, (self.clock)()));
         let old = self.buckets.load_full();
 
         if old != new {
@@ -170,8 +349,8 @@ impl Limiter {
 
             for b in new.as_ref() {
                 warn!(
-                    "GenericLimiter: subnet: {:?}, canister: {:?}, methods: {:?}, action: {:?}",
-                    b.rule.subnet_id, b.rule.canister_id, b.rule.methods, b.rule.limit,
+                    "GenericLimiter: subnet: {:?}, canister: {:?}, methods: {:?}, action: {:?}, scope: {:?}",
+                    b.rule.subnet_id, b.rule.canister_id, b.rule.methods, b.rule.limit, b.rule.scope,
                 );
             }
 
@@ -188,6 +367,38 @@ impl Limiter {
         Ok(())
     }
 
+    /// Exchanges the counters of the global rules with the other nodes
+    async fn sync(&self) -> Result<(), Error> {
+        let Some(shared_state) = &self.shared_state else {
+            return Ok(());
+        };
+
+        let buckets = self.buckets.load_full();
+        let shared = buckets
+            .iter()
+            .filter_map(|b| b.shared.as_ref())
+            .collect::<Vec<_>>();
+
+        if shared.is_empty() {
+            return Ok(());
+        }
+
+        let now = (self.clock)();
+        let counters = shared.iter().map(|b| b.counter(now)).collect::<Vec<_>>();
+        let windows = counters.iter().map(|c| c.window).collect::<Vec<_>>();
+
+        let remote = shared_state
+            .exchange(&self.node_id, counters)
+            .await
+            .context("unable to exchange counters")?;
+
+        for ((b, window), count) in shared.into_iter().zip(windows).zip(remote) {
+            b.set_remote(window, count);
+        }
+
+        Ok(())
+    }
+
     fn acquire_token(
         &self,
         subnet_id: Principal,
@@ -226,7 +437,9 @@ impl Limiter {
                 }
             }
 
-            if let Some(r) = &b.limiter {
+            if let Some(r) = &b.shared {
+                return r.try_acquire((self.clock)());
+            } else if let Some(r) = &b.limiter {
                 return r.try_wait().is_ok();
             } else {
                 // Always block
@@ -246,6 +459,16 @@ impl Run for Arc<Limiter> {
     }
 }
 
+/// Periodically exchanges the counters of the global rules with the other nodes
+pub struct Syncer(pub Arc<Limiter>);
+
+#[async_trait]
+impl Run for Syncer {
+    async fn run(&mut self) -> Result<(), Error> {
+        self.0.sync().await
+    }
+}
+
 pub async fn middleware(
     State(state): State<Arc<Limiter>>,
     Extension(ctx): Extension<Arc<RequestContext>>,
@@ -269,6 +492,7 @@ pub async fn middleware(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::rate_limiting::shared::LocalState;
     use indoc::indoc;
 
     #[test]
@@ -310,6 +534,7 @@ mod test {
                     request_type: None,
                     methods: Some(Regex::new("^.*$").unwrap()),
                     limit: Action::Limit(100, Duration::from_secs(1)),
+                    scope: Scope::Local,
                 },
                 Rule {
                     subnet_id: None,
@@ -317,6 +542,7 @@ mod test {
                     request_type: None,
                     methods: Some(Regex::new("^(foo|bar)$").unwrap()),
                     limit: Action::Limit(60, Duration::from_secs(60)),
+                    scope: Scope::Local,
                 },
                 Rule {
                     subnet_id: Some(
@@ -329,6 +555,7 @@ mod test {
                     request_type: None,
                     methods: None,
                     limit: Action::Limit(90, Duration::from_secs(60)),
+                    scope: Scope::Local,
                 },
                 Rule {
                     subnet_id: None,
@@ -336,6 +563,7 @@ mod test {
                     request_type: None,
                     methods: Some(Regex::new("^(foo|bar)$").unwrap()),
                     limit: Action::Block,
+                    scope: Scope::Local,
                 },
                 Rule {
                     subnet_id: None,
@@ -343,6 +571,7 @@ mod test {
                     request_type: Some(RequestType::Query),
                     methods: Some(Regex::new("^(foo|bar)$").unwrap()),
                     limit: Action::Block,
+                    scope: Scope::Local,
                 },
                 Rule {
                     subnet_id: None,
@@ -350,11 +579,12 @@ mod test {
                     request_type: Some(RequestType::Call),
                     methods: None,
                     limit: Action::Block,
+                    scope: Scope::Local,
                 },
             ],
         );
 
-        Limiter::process_rules(rules);
+        Limiter::process_rules(rules, now());
 
         // Bad canister
         let rules = indoc! {"
@@ -524,4 +754,121 @@ mod test {
             assert!(!limiter.acquire_token(subnet_id, Some(id3), Some("zob"), RequestType::Query));
         }
     }
+
+    #[tokio::test]
+    async fn test_ratelimit_global() {
+        let rules = indoc! {"
+        - canister_id: aaaaa-aa
+          methods: ^foo$
+          limit: 10/1h
+          scope: global
+
+        - canister_id: aaaaa-aa
+          limit: 5/1h
+        "};
+        let rules: Vec<Rule> = serde_yaml::from_str(rules).unwrap();
+        assert_eq!(rules[0].scope, Scope::Global);
+        assert_eq!(rules[1].scope, Scope::Local);
+
+        // Bad scope
+        let bad = indoc! {"
+        - canister_id: aaaaa-aa
+          limit: 5/1h
+          scope: blah
+        "};
+        assert!(serde_yaml::from_str::<Vec<Rule>>(bad).is_err());
+
+        // Start in the middle of a window so that the test doesn't depend on the wall clock
+        let time = Arc::new(AtomicU64::new(1_000 * 3600 + 1800));
+        let clock = {
+            let time = time.clone();
+            Arc::new(move || Duration::from_secs(time.load(Ordering::SeqCst)))
+        };
+
+        let shared_state = Arc::new(LocalState::new());
+        let limiter1 =
+            Limiter::new_with_shared_state("/tmp/foo".into(), "node1".into(), shared_state.clone())
+                .with_clock(clock.clone());
+        let limiter2 =
+            Limiter::new_with_shared_state("/tmp/foo".into(), "node2".into(), shared_state)
+                .with_clock(clock);
+        limiter1.apply_rules(rules.clone());
+        limiter2.apply_rules(rules);
+
+        let id = Principal::from_text("aaaaa-aa").unwrap();
+        let subnet_id =
+            Principal::from_text("3hhby-wmtmw-umt4t-7ieyg-bbiig-xiylg-sblrt-voxgt-bqckd-a75bf-rqe")
+                .unwrap();
+
+        // 6 pass on the 1st node
+        for _ in 0..6 {
+            assert!(limiter1.acquire_token(subnet_id, Some(id), Some("foo"), RequestType::Query));
+        }
+
+        limiter1.sync().await.unwrap();
+        limiter2.sync().await.unwrap();
+
+        // Then only the remaining 4 pass on the 2nd node
+        for _ in 0..4 {
+            assert!(limiter2.acquire_token(subnet_id, Some(id), Some("foo"), RequestType::Query));
+        }
+        for _ in 0..100 {
+            assert!(!limiter2.acquire_token(subnet_id, Some(id), Some("foo"), RequestType::Query));
+        }
+
+        // Until the 1st node learns about them it's still able to pass 4 more
+        for _ in 0..4 {
+            assert!(limiter1.acquire_token(subnet_id, Some(id), Some("foo"), RequestType::Query));
+        }
+        limiter1.sync().await.unwrap();
+        for _ in 0..100 {
+            assert!(!limiter1.acquire_token(subnet_id, Some(id), Some("foo"), RequestType::Query));
+        }
+
+        // Local rule is enforced on each node separately
+        for l in [&limiter1, &limiter2] {
+            for _ in 0..5 {
+                assert!(l.acquire_token(subnet_id, Some(id), Some("bar"), RequestType::Query));
+            }
+            assert!(!l.acquire_token(subnet_id, Some(id), Some("bar"), RequestType::Query));
+        }
+
+        // The global counters are reset in the next window, also the ones learned from the peers
+        time.fetch_add(3600, Ordering::SeqCst);
+        for _ in 0..10 {
+            assert!(limiter2.acquire_token(subnet_id, Some(id), Some("foo"), RequestType::Query));
+        }
+        assert!(!limiter2.acquire_token(subnet_id, Some(id), Some("foo"), RequestType::Query));
+
+        limiter2.sync().await.unwrap();
+        limiter1.sync().await.unwrap();
+        assert!(!limiter1.acquire_token(subnet_id, Some(id), Some("foo"), RequestType::Query));
+    }
+
+    #[test]
+    fn test_shared_bucket_window() {
+        let bucket = SharedBucket::new("foo".into(), 2, Duration::from_secs(10), Duration::ZERO);
+
+        assert!(bucket.try_acquire(Duration::from_secs(1)));
+        assert!(bucket.try_acquire(Duration::from_secs(2)));
+        assert!(!bucket.try_acquire(Duration::from_secs(3)));
+
+        // Next window
+        assert!(bucket.try_acquire(Duration::from_secs(11)));
+        assert_eq!(bucket.counter(Duration::from_secs(12)).count, 1);
+
+        // A stale clock counts towards the current window instead of resetting it
+        assert!(bucket.try_acquire(Duration::from_secs(9)));
+        assert!(!bucket.try_acquire(Duration::from_secs(12)));
+        assert_eq!(bucket.counter(Duration::from_secs(12)).count, 2);
+
+        // Remote counters are taken into account only in their window
+        bucket.set_remote(2, 1);
+        assert!(bucket.try_acquire(Duration::from_secs(21)));
+        assert!(!bucket.try_acquire(Duration::from_secs(22)));
+
+        // and never replaced by the ones of an older window
+        bucket.set_remote(1, 5);
+        assert_eq!(bucket.remote.load(Ordering::SeqCst), pack(2, 1));
+    }
 }
@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, Error};
+use arc_swap::ArcSwap;
+use async_trait::async_trait;
+use axum::{extract::State, Json};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use url::Url;
+
+/// Path under which the node serves its counters to its peers
+pub const PATH_COUNTERS: &str = "/ratelimit/counters";
+
+/// Number of requests admitted by a node for a given global rule in a given time window
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct Counter {
+    pub key: String,
+    pub window: u64,
+    pub count: u64,
+}
+
+/// Counters of a single node as served to the peers
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct NodeCounters {
+    pub node_id: String,
+    pub counters: Vec<Counter>,
+}
+
+/// Storage for the request counters shared between the API boundary nodes
+#[async_trait]
+pub trait SharedState: Send + Sync {
+    /// Publishes the counters of the given node and returns, for each of them,
+    /// the sum of the counters published by all other nodes for the same key and window.
+    async fn exchange(&self, node_id: &str, counters: Vec<Counter>) -> Result<Vec<u64>, Error>;
+}
+
+/// In-memory shared state.
+/// Limiters sharing the same instance behave as if they were running on different nodes,
+/// which is mostly useful for testing.
+#[derive(Default)]
+pub struct LocalState {
+    // key -> (window, node_id -> count)
+    counters: Mutex<HashMap<String, (u64, HashMap<String, u64>)>>,
+}
+
+impl LocalState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SharedState for LocalState {
+    async fn exchange(&self, node_id: &str, counters: Vec<Counter>) -> Result<Vec<u64>, Error> {
+        let mut state = self.counters.lock().unwrap();
+
+        Ok(counters
+            .into_iter()
+            .map(|c| {
+                let (window, nodes) = state
+                    .entry(c.key)
+                    .or_insert_with(|| (c.window, HashMap::new()));
+
+                // Counters from the previous windows are not needed anymore
+                if *window < c.window {
+                    *window = c.window;
+                    nodes.clear();
+                }
+
+                // The counter is from the previous window, nothing to report
+                if *window > c.window {
+                    return 0;
+                }
+
+                nodes.insert(node_id.to_string(), c.count);
+                nodes
+                    .iter()
+                    .filter(|(id, _)| id.as_str() != node_id)
+                    .map(|(_, count)| count)
+                    .sum()
+            })
+            .collect())
+    }
+}
+
+/// Shared state that is gossiped between the nodes over HTTP.
+/// Each node serves its own counters under `PATH_COUNTERS` and pulls the counters of its peers.
+pub struct PeerState {
+    http_client: reqwest::Client,
+    peers: Vec<Url>,
+    own: ArcSwap<NodeCounters>,
+}
+
+impl PeerState {
+    pub fn new(peers: Vec<Url>, timeout: Duration) -> Result<Self, Error> {
+        let http_client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .context("unable to create HTTP client")?;
+
+        let peers = peers
+            .into_iter()
+            .map(|x| x.join(PATH_COUNTERS))
+            .collect::<Result<Vec<_>, _>>()
+            .context("unable to build peer URL")?;
+
+        Ok(Self {
+            http_client,
+            peers,
+            own: ArcSwap::new(Arc::new(NodeCounters {
+                node_id: String::new(),
+                counters: vec![],
+            })),
+        })
+    }
+
+    async fn fetch(&self, url: &Url) -> Result<NodeCounters, Error> {
+        self.http_client
+            .get(url.clone())
+            .send()
+            .await
+            .context("unable to make request")?
+            .error_for_status()
+            .context("unexpected response status")?
+            .json()
+            .await
+            .context("unable to decode counters")
+    }
+}
+
+#[async_trait]
+impl SharedState for PeerState {
+    async fn exchange(&self, node_id: &str, counters: Vec<Counter>) -> Result<Vec<u64>, Error> {
+        self.own.store(Arc::new(NodeCounters {
+            node_id: node_id.to_string(),
+            counters: counters.clone(),
+        }));
+
+        let responses = join_all(self.peers.iter().map(|x| self.fetch(x))).await;
+
+        let mut remote: HashMap<(&str, u64), u64> = HashMap::new();
+        for (url, resp) in self.peers.iter().zip(responses.iter()) {
+            match resp {
+                // Skip ourselves if we're listed among the peers
+                Ok(v) if v.node_id == node_id => {}
+                Ok(v) => {
+                    for c in &v.counters {
+                        *remote.entry((c.key.as_str(), c.window)).or_default() += c.count;
+                    }
+                }
+                Err(e) => warn!("GenericLimiter: unable to fetch counters from {url}: {e:#}"),
+            }
+        }
+
+        Ok(counters
+            .iter()
+            .map(|c| {
+                remote
+                    .get(&(c.key.as_str(), c.window))
+                    .copied()
+                    .unwrap_or_default()
+            })
+            .collect())
+    }
+}
+
+/// Serves the counters of this node to its peers
+pub async fn handler(State(state): State<Arc<PeerState>>) -> Json<NodeCounters> {
+    Json(state.own.load_full().as_ref().clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn counter(key: &str, window: u64, count: u64) -> Counter {
+        Counter {
+            key: key.into(),
+            window,
+            count,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_state() {
+        let state = LocalState::new();
+
+        let r = state
+            .exchange("node1", vec![counter("foo", 1, 5), counter("bar", 1, 3)])
+            .await
+            .unwrap();
+        assert_eq!(r, vec![0, 0]);
+
+        let r = state
+            .exchange("node2", vec![counter("foo", 1, 2), counter("bar", 1, 1)])
+            .await
+            .unwrap();
+        assert_eq!(r, vec![5, 3]);
+
+        let r = state
+            .exchange("node3", vec![counter("foo", 1, 1)])
+            .await
+            .unwrap();
+        assert_eq!(r, vec![7]);
+
+        // Updated counter replaces the old one
+        let r = state
+            .exchange("node1", vec![counter("foo", 1, 10)])
+            .await
+            .unwrap();
+        assert_eq!(r, vec![3]);
+
+        // New window drops the counters from the previous one
+        let r = state
+            .exchange("node2", vec![counter("foo", 2, 1)])
+            .await
+            .unwrap();
+        assert_eq!(r, vec![0]);
+
+        // Stale window reports nothing
+        let r = state
+            .exchange("node1", vec![counter("foo", 1, 11)])
+            .await
+            .unwrap();
+        assert_eq!(r, vec![0]);
+
+        let r = state
+            .exchange("node1", vec![counter("foo", 2, 4)])
+            .await
+            .unwrap();
+        assert_eq!(r, vec![1]);
+    }
+}
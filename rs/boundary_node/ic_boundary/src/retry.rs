@@ -1,25 +1,44 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
-    body::{to_bytes, Body},
+    body::{to_bytes, Body, Bytes},
     extract::{Request, State},
     middleware::Next,
     response::{IntoResponse, Response},
     Extension,
 };
-use http::StatusCode;
+use http::{request::Parts, StatusCode};
+use tokio::select;
 
 use crate::{
+    latency::LatencyTracker,
     persist::RouteSubnet,
-    routes::{ApiError, ErrorCause, RequestContext},
+    routes::{ApiError, ErrorCause, RequestContext, RequestType},
     snapshot::Node,
 };
 
+#[derive(Clone)]
+pub struct HedgeParams {
+    // Bounds for the delay after which the hedged request is sent.
+    // Within them the delay is the estimated p95 latency of the first node.
+    pub delay_min: Duration,
+    pub delay_max: Duration,
+}
+
 #[derive(Clone)]
 pub struct RetryParams {
     pub retry_count: usize,
     pub retry_update_call: bool,
     pub disable_latency_routing: bool,
+    // If set - the latencies of the requests are tracked
+    pub latency_tracker: Option<Arc<LatencyTracker>>,
+    // Whether to use the tracked latencies to pick the nodes for non-calls
+    pub weighted_routing: bool,
+    // If set - the queries are hedged
+    pub hedging: Option<HedgeParams>,
 }
 
 #[derive(Clone)]
@@ -51,6 +70,73 @@ fn request_needs_retrying(response: &Response) -> bool {
     }
 }
 
+// Passes the request to the given node down the stack and records its latency if it succeeds
+async fn send_request(
+    next: Next,
+    request: Request,
+    node: Arc<Node>,
+    latency_tracker: Option<&LatencyTracker>,
+) -> (Response, Arc<Node>) {
+    let start = Instant::now();
+    let response = next.run(request).await;
+
+    if let Some(v) = latency_tracker {
+        if response.status().is_success() && response.extensions().get::<ErrorCause>().is_none() {
+            v.observe(node.id, start.elapsed());
+        }
+    }
+
+    (response, node)
+}
+
+fn build_request(parts: &Parts, body: &Bytes, node: &Arc<Node>) -> Request {
+    let mut request = Request::from_parts(parts.clone(), Body::from(body.clone()));
+    request.extensions_mut().insert(node.clone());
+    request
+}
+
+// Sends the request to the first node and, if it does not respond within a deadline,
+// also to the second one. The first response that does not need retrying wins.
+async fn send_hedged_request(
+    next: Next,
+    parts: &Parts,
+    body: &Bytes,
+    node: Arc<Node>,
+    node_hedge: Arc<Node>,
+    params: &RetryParams,
+    hedge: &HedgeParams,
+) -> (Response, Arc<Node>) {
+    let latency_tracker = params.latency_tracker.as_deref();
+
+    let deadline = latency_tracker
+        .and_then(|x| x.p95(&node.id))
+        .unwrap_or(hedge.delay_max)
+        .clamp(hedge.delay_min, hedge.delay_max);
+
+    let request = build_request(parts, body, &node);
+    let first = send_request(next.clone(), request, node, latency_tracker);
+    tokio::pin!(first);
+
+    select! {
+        v = &mut first => return v,
+        _ = tokio::time::sleep(deadline) => {},
+    }
+
+    let request = build_request(parts, body, &node_hedge);
+    let second = send_request(next, request, node_hedge, latency_tracker);
+    tokio::pin!(second);
+
+    // If the winner has failed then wait for the other one
+    select! {
+        v = &mut first => {
+            if request_needs_retrying(&v.0) { second.await } else { v }
+        }
+        v = &mut second => {
+            if request_needs_retrying(&v.0) { first.await } else { v }
+        }
+    }
+}
+
 // Middleware that optionally retries the request according to the predefined conditions
 pub async fn retry_request(
     State(params): State<RetryParams>,
@@ -59,21 +145,37 @@ pub async fn retry_request(
     mut request: Request,
     next: Next,
 ) -> Result<impl IntoResponse, ApiError> {
-    // Select up to 1+retry_count nodes from the subnet if there are any
+    // Queries are hedged with one more node if enabled
+    let hedge = params
+        .hedging
+        .as_ref()
+        .filter(|_| ctx.request_type == RequestType::Query);
+
+    // Select up to 1+retry_count(+1 for hedging) nodes from the subnet if there are any
+    let count = 1 + params.retry_count + usize::from(hedge.is_some());
     let nodes = if !params.disable_latency_routing && (ctx.request_type.is_call()) {
         let factor = subnet.fault_tolerance_factor() + 1;
-        subnet.pick_n_out_of_m_closest(1 + params.retry_count, factor)?
+        subnet.pick_n_out_of_m_closest(count, factor)?
+    } else if let Some(v) = params
+        .latency_tracker
+        .as_ref()
+        .filter(|_| params.weighted_routing)
+    {
+        subnet.pick_weighted_nodes(count, v)?
     } else {
-        subnet.pick_random_nodes(1 + params.retry_count)?
+        subnet.pick_random_nodes(count)?
     };
 
     // Skip retrying in certain cases
-    if params.retry_count == 0 || (ctx.request_type.is_call() && !params.retry_update_call) {
+    if hedge.is_none()
+        && (params.retry_count == 0 || (ctx.request_type.is_call() && !params.retry_update_call))
+    {
         // Pick one node and pass the request down the stack
         // At this point there would be at least one node in the vector
         let node = nodes[0].clone();
         request.extensions_mut().insert(node.clone());
-        let mut response = next.run(request).await;
+        let (mut response, node) =
+            send_request(next, request, node, params.latency_tracker.as_deref()).await;
         response.extensions_mut().insert(node);
         return Ok(response);
     }
@@ -90,10 +192,43 @@ pub async fn retry_request(
     // And it cannot fail since it's already in-memory.
     let body = to_bytes(body, usize::MAX).await.unwrap();
 
-    for node in nodes.into_iter() {
-        let mut request = Request::from_parts(parts.clone(), Body::from(body.clone()));
-        request.extensions_mut().insert(node.clone());
-        let mut response = next.clone().run(request).await;
+    let mut nodes = nodes.into_iter();
+
+    // Hedging is done instead of the first attempt if there are at least 2 nodes
+    if let Some(hedge) = hedge {
+        if nodes.len() >= 2 {
+            let (node, node_hedge) = (nodes.next().unwrap(), nodes.next().unwrap());
+            let (mut response, node) = send_hedged_request(
+                next.clone(),
+                &parts,
+                &body,
+                node,
+                node_hedge,
+                &params,
+                hedge,
+            )
+            .await;
+
+            if !request_needs_retrying(&response) {
+                response.extensions_mut().insert(node);
+                return Ok(response);
+            }
+
+            response_last = Some(response);
+            node_last = Some(node);
+            retry_result.retries += 1;
+        }
+    }
+
+    for node in nodes {
+        let request = build_request(&parts, &body, &node);
+        let (mut response, node) = send_request(
+            next.clone(),
+            request,
+            node,
+            params.latency_tracker.as_deref(),
+        )
+        .await;
 
         // Stop if the request does not need retrying
         if !request_needs_retrying(&response) {
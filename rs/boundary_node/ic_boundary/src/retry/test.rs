@@ -2,7 +2,11 @@ use super::*;
 
 use std::{
     str::FromStr,
-    sync::{Arc, RwLock},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
 };
 
 use anyhow::Error;
@@ -78,6 +82,9 @@ async fn test_retry() -> Result<(), Error> {
                 retry_count: 3,
                 retry_update_call: false,
                 disable_latency_routing: true,
+                latency_tracker: None,
+                weighted_routing: false,
+                hedging: None,
             },
             retry_request,
         ));
@@ -144,6 +151,9 @@ async fn test_retry() -> Result<(), Error> {
                 retry_count: 3,
                 retry_update_call: true,
                 disable_latency_routing: true,
+                latency_tracker: None,
+                weighted_routing: false,
+                hedging: None,
             },
             retry_request,
         ));
@@ -159,3 +169,64 @@ async fn test_retry() -> Result<(), Error> {
 
     Ok(())
 }
+
+// Responds slowly to the first request and quickly to the others
+async fn handler_slow_first(State(state): State<Arc<AtomicUsize>>) -> impl IntoResponse {
+    if state.fetch_add(1, Ordering::SeqCst) == 0 {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        return "slow".into_response();
+    }
+
+    "fast".into_response()
+}
+
+#[tokio::test]
+async fn test_retry_hedging() -> Result<(), Error> {
+    let state = Arc::new(AtomicUsize::new(0));
+    let latency_tracker = Arc::new(LatencyTracker::new(0.5));
+
+    let mut app = Router::new()
+        .route("/", post(handler_slow_first).with_state(Arc::clone(&state)))
+        .layer(middleware::from_fn_with_state(
+            RetryParams {
+                retry_count: 0,
+                retry_update_call: false,
+                disable_latency_routing: true,
+                latency_tracker: Some(latency_tracker.clone()),
+                weighted_routing: true,
+                hedging: Some(HedgeParams {
+                    delay_min: Duration::from_millis(50),
+                    delay_max: Duration::from_millis(100),
+                }),
+            },
+            retry_request,
+        ));
+
+    // The hedged request wins
+    let start = Instant::now();
+    let req = gen_request(RequestType::Query);
+    let res = app.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(state.load(Ordering::SeqCst), 2);
+
+    let body = to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(body, "fast");
+
+    // Calls are not hedged
+    state.store(0, Ordering::SeqCst);
+    let req = gen_request(RequestType::Call);
+    tokio::time::timeout(Duration::from_secs(1), app.call(req))
+        .await
+        .unwrap_err();
+    assert_eq!(state.load(Ordering::SeqCst), 1);
+
+    // Fast responses don't trigger hedging
+    let req = gen_request(RequestType::Query);
+    let res = app.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(state.load(Ordering::SeqCst), 2);
+
+    Ok(())
+}
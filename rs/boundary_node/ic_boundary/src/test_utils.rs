@@ -324,8 +324,17 @@ pub fn setup_test_router(
         &cli,
         &metrics_registry,
         enable_cache.then_some(Arc::new(
-            Cache::new(10485760, 262144, Duration::from_secs(1), false).unwrap(),
+            Cache::new(
+                10485760,
+                262144,
+                Duration::from_secs(1),
+                Duration::ZERO,
+                false,
+            )
+            .unwrap(),
         )),
+        None,
+        None,
     );
 
     let router = router.layer(axum::middleware::from_fn(add_conninfo));
@@ -135,6 +135,8 @@ impl Execution {
                 instance_stats,
                 system_api_call_counters,
                 canister_log,
+                instruction_profile,
+                coverage,
             },
             deltas,
             instance_or_system_api,
@@ -202,6 +204,8 @@ impl Execution {
                     instance_stats,
                     system_api_call_counters,
                     canister_log,
+                    instruction_profile,
+                    coverage,
                 };
                 self.sandbox_manager.controller.execution_finished(
                     protocol::ctlsvc::ExecutionFinishedRequest {
@@ -229,6 +233,8 @@ impl Execution {
                     instance_stats,
                     system_api_call_counters,
                     canister_log,
+                    instruction_profile,
+                    coverage,
                 };
 
                 self.sandbox_manager.controller.execution_finished(
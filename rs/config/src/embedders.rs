@@ -112,6 +112,14 @@ pub struct FeatureFlags {
     pub best_effort_responses: FlagStatus,
     /// Collect a backtrace from the canister when it panics.
     pub canister_backtrace: FlagStatus,
+    /// Attribute the instructions executed by a message to the call stacks
+    /// of Wasm functions. This changes the instrumentation of Wasm modules and
+    /// should only be enabled for non-replicated testing environments.
+    pub instruction_profiling: FlagStatus,
+    /// Record the basic blocks of canister modules executed by messages. This
+    /// changes the instrumentation of Wasm modules and should only be enabled
+    /// for non-replicated testing environments.
+    pub wasm_coverage: FlagStatus,
 }
 
 impl FeatureFlags {
@@ -123,6 +131,8 @@ impl FeatureFlags {
             wasm64: FlagStatus::Disabled,
             best_effort_responses: FlagStatus::Disabled,
             canister_backtrace: FlagStatus::Disabled,
+            instruction_profiling: FlagStatus::Disabled,
+            wasm_coverage: FlagStatus::Disabled,
         }
     }
 }
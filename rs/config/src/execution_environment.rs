@@ -5,7 +5,7 @@ use ic_types::{
     Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, time::Duration};
+use std::{path::PathBuf, str::FromStr, time::Duration};
 
 const MIB: u64 = 1024 * 1024;
 const GIB: u64 = MIB * 1024;
@@ -129,12 +129,19 @@ const BITCOIN_MAINNET_SOFT_LAUNCH_CANISTER_ID: &str = "gsvzx-syaaa-aaaan-aaabq-c
 /// The capacity of the Wasm compilation cache.
 pub const MAX_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(10 * GIB);
 
+/// The capacity of the on-disk tier of the Wasm compilation cache.
+pub const MAX_PERSISTENT_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(20 * GIB);
+
 /// Maximum number of controllers allowed in a request (specified in the interface spec).
 pub const MAX_ALLOWED_CONTROLLERS_COUNT: usize = 10;
 
 /// Maximum number of canister snapshots that can be stored for a single canister.
 pub const MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER: usize = 1;
 
+/// Maximum number of bytes of canister snapshot data that can be read or
+/// uploaded with a single call to the management canister.
+pub const MAX_SNAPSHOT_DATA_CHUNK_SIZE: NumBytes = NumBytes::new(2_000_000);
+
 /// Maximum number of http outcall requests in-flight on a subnet.
 /// To support 100 req/s with a worst case request latency of 30s the queue size needs buffer 100 req/s * 30s = 3000 req.
 /// The worst case request latency used here should be equivalent to the request timeout in the adapter.
@@ -261,6 +268,26 @@ pub struct Config {
     /// The capacity of the Wasm compilation cache.
     pub max_compilation_cache_size: NumBytes,
 
+    /// Indicates whether compiled Wasm modules are also stored on disk, so
+    /// that they can be reused after a replica restart instead of being
+    /// recompiled.
+    pub persistent_compilation_cache: FlagStatus,
+
+    /// The directory of the on-disk compilation cache. The directory is owned
+    /// by the cache: anything in it that does not belong to the current
+    /// replica version and embedder configuration is deleted. If not set, the
+    /// replica uses a directory under the state root.
+    pub persistent_compilation_cache_dir: Option<PathBuf>,
+
+    /// The capacity of the on-disk compilation cache.
+    pub max_persistent_compilation_cache_size: NumBytes,
+
+    /// The directory into which the instruction profiles of executed messages
+    /// are written as folded stacks, one file per message. Profiles are only
+    /// collected if the `instruction_profiling` feature flag of the embedders
+    /// config is enabled.
+    pub instruction_profile_dir: Option<PathBuf>,
+
     /// Indicate whether query stats should be collected or not.
     pub query_stats_aggregation: FlagStatus,
 
@@ -350,6 +377,10 @@ impl Default for Config {
             query_cache_max_expiry_time: QUERY_CACHE_MAX_EXPIRY_TIME,
             query_cache_data_certificate_expiry_time: QUERY_CACHE_DATA_CERTIFICATE_EXPIRY_TIME,
             max_compilation_cache_size: MAX_COMPILATION_CACHE_SIZE,
+            persistent_compilation_cache: FlagStatus::Disabled,
+            persistent_compilation_cache_dir: None,
+            max_persistent_compilation_cache_size: MAX_PERSISTENT_COMPILATION_CACHE_SIZE,
+            instruction_profile_dir: None,
             query_stats_aggregation: FlagStatus::Enabled,
             query_stats_epoch_length: QUERY_STATS_EPOCH_LENGTH,
             stop_canister_timeout_duration: STOP_CANISTER_TIMEOUT_DURATION,
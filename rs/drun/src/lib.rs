@@ -3,15 +3,18 @@
 use crate::message::{msg_stream_from_file, Message};
 use hex::encode;
 use ic_config::{subnet_config::SubnetConfig, Config};
-use ic_crypto_test_utils_ni_dkg::dummy_initial_dkg_transcript_with_master_key;
+use ic_crypto_test_utils_ni_dkg::{dummy_initial_dkg_transcript_with_master_key, SecretKeyBytes};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
 use ic_execution_environment::ExecutionServices;
 use ic_http_endpoints_metrics::MetricsHttpEndpoint;
 use ic_interfaces::{
-    execution_environment::{IngressHistoryReader, QueryExecutionError},
+    execution_environment::{IngressHistoryReader, QueryExecutionError, QueryExecutionService},
     messaging::MessageRouting,
 };
+use ic_interfaces_certified_stream_store::{CertifiedStreamStore, EncodeStreamError};
+use ic_interfaces_state_manager::StateReader;
+use ic_management_canister_types::IC_00;
 use ic_messaging::MessageRoutingImpl;
 use ic_metrics::MetricsRegistry;
 use ic_protobuf::registry::{
@@ -32,15 +35,17 @@ use ic_state_machine_tests::certify_latest_state_helper;
 use ic_state_manager::StateManagerImpl;
 use ic_test_utilities_consensus::fake::FakeVerifier;
 use ic_test_utilities_registry::{
-    add_subnet_record, insert_initial_dkg_transcript, SubnetRecordBuilder,
+    add_single_subnet_record, add_subnet_list_record, insert_initial_dkg_transcript,
+    SubnetRecordBuilder,
 };
 use ic_types::malicious_flags::MaliciousFlags;
 use ic_types::{
     batch::Batch,
     ingress::{IngressState, IngressStatus, WasmResult},
-    messages::{MessageId, SignedIngress},
-    replica_config::ReplicaConfig,
-    time, CanisterId, NodeId, NumInstructions, PrincipalId, Randomness, RegistryVersion, SubnetId,
+    messages::{extract_effective_canister_id, MessageId, SignedIngress},
+    time,
+    xnet::CertifiedStreamSlice,
+    CanisterId, NodeId, NumInstructions, PrincipalId, Randomness, RegistryVersion, SubnetId,
 };
 use ic_types::{
     batch::{BatchMessages, BlockmakerMetrics},
@@ -55,6 +60,7 @@ use std::fs::OpenOptions;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::{thread::sleep, time::Duration};
+use tempfile::TempDir;
 use tower::util::ServiceExt;
 
 mod message;
@@ -74,19 +80,159 @@ pub struct DrunOptions {
     pub subnet_type: SubnetType,
 }
 
-/// Deliver a single message to the Message Routing layer
-fn deliver_message(
-    msg: SignedIngress,
-    message_routing: &dyn MessageRouting,
-    ingress_hist_reader: &dyn IngressHistoryReader,
-    extra_batches: u64,
-) {
+/// A subnet run by `drun`, with its own state and Message Routing instance.
+struct Subnet {
+    subnet_id: SubnetId,
+    state_manager: Arc<StateManagerImpl>,
+    message_routing: MessageRoutingImpl,
+    ingress_hist_reader: Box<dyn IngressHistoryReader>,
+    query_handler: QueryExecutionService,
+    // The temporary state directory of all but the first subnet, which uses the
+    // configured state directory. It must be declared last, so that it is only
+    // removed once the state manager and Message Routing are shut down.
+    _state_dir: Option<TempDir>,
+}
+
+/// All subnets run by `drun`.
+///
+/// Execution proceeds in rounds: in each round, a single batch is delivered to
+/// every subnet, in the order in which the subnets were declared, and the next
+/// subnet only receives its batch after the previous one has finished
+/// executing. Each batch includes certified slices of the streams that the
+/// other subnets have produced for the subnet so far, so XNet messages are
+/// routed through the regular stream builder and stream handler.
+struct Subnets {
+    subnets: Vec<Subnet>,
+    routing_table: RoutingTable,
+    secret_key: SecretKeyBytes,
+}
+
+impl Subnets {
+    /// Returns the index of the subnet hosting `canister_id`. Queries to the
+    /// management canister are handled by the first subnet.
+    fn index_of(&self, canister_id: CanisterId) -> Result<usize, String> {
+        if canister_id == IC_00 {
+            return Ok(0);
+        }
+        let subnet_id = self
+            .routing_table
+            .route(canister_id.get())
+            .ok_or_else(|| format!("Canister {} is not hosted by any subnet", canister_id))?;
+        Ok(self
+            .subnets
+            .iter()
+            .position(|subnet| subnet.subnet_id == subnet_id)
+            .unwrap())
+    }
+
+    /// Returns the index of the subnet that the ingress message `msg` is
+    /// delivered to. Like on the IC, messages to the management canister are
+    /// routed by their effective canister ID; those without one (e.g. canister
+    /// creation) are handled by the first subnet.
+    fn index_of_ingress(&self, msg: &SignedIngress) -> Result<usize, String> {
+        if msg.canister_id() != IC_00 {
+            return self.index_of(msg.canister_id());
+        }
+        match extract_effective_canister_id(msg.content(), self.subnets[0].subnet_id) {
+            Ok(Some(effective_canister_id)) => self.index_of(effective_canister_id),
+            Ok(None) => Ok(0),
+            Err(err) => Err(format!(
+                "Failed to extract the effective canister ID of {}: {}",
+                msg.id(),
+                err.into_user_error(msg.content().method_name())
+            )),
+        }
+    }
+
+    /// Returns the subnet with the given index.
+    fn get(&self, index: usize) -> Result<&Subnet, String> {
+        self.subnets.get(index).ok_or_else(|| {
+            format!(
+                "Subnet {} does not exist, only {} subnets were declared",
+                index,
+                self.subnets.len()
+            )
+        })
+    }
+
+    /// Makes sure the latest state of the given subnet is certified.
+    fn certify_latest_state(&self, subnet: &Subnet) {
+        certify_latest_state_helper(
+            Arc::clone(&subnet.state_manager),
+            &self.secret_key,
+            subnet.subnet_id,
+        );
+    }
+
+    /// Executes a single round, i.e. delivers a batch to every subnet and
+    /// waits for it to be executed. If `msg` is provided, the ingress message
+    /// is included in the batch of the subnet with the given index.
+    fn execute_round(&self, mut msg: Option<(usize, SignedIngress)>) {
+        for (index, subnet) in self.subnets.iter().enumerate() {
+            let msgs = match msg.take() {
+                Some((msg_index, msg)) if msg_index == index => vec![msg],
+                other => {
+                    msg = other;
+                    vec![]
+                }
+            };
+            let mut batch = build_batch(&subnet.message_routing, msgs);
+            batch.messages.certified_stream_slices = self.certified_stream_slices(subnet);
+            let height = batch.batch_number;
+            while subnet.message_routing.deliver_batch(batch.clone()).is_err() {
+                sleep(WAIT_PER_BATCH);
+            }
+            while subnet.state_manager.latest_state_height() < height {
+                sleep(WAIT_PER_BATCH);
+            }
+        }
+    }
+
+    /// Returns certified slices of the streams from all other subnets to
+    /// `subnet`, starting at the first message that `subnet` has not inducted
+    /// yet.
+    fn certified_stream_slices(&self, subnet: &Subnet) -> BTreeMap<SubnetId, CertifiedStreamSlice> {
+        let state = subnet.state_manager.get_latest_state().take();
+        let mut slices = BTreeMap::new();
+        for remote in self.subnets.iter() {
+            if remote.subnet_id == subnet.subnet_id {
+                continue;
+            }
+            let msg_begin = state
+                .get_stream(&remote.subnet_id)
+                .map(|stream| stream.signals_end());
+            self.certify_latest_state(remote);
+            match remote.state_manager.encode_certified_stream_slice(
+                subnet.subnet_id,
+                msg_begin,
+                msg_begin,
+                None,
+                None,
+            ) {
+                Ok(slice) => {
+                    slices.insert(remote.subnet_id, slice);
+                }
+                // The remote subnet has not sent anything to `subnet` yet.
+                Err(EncodeStreamError::NoStreamForSubnet(_)) => {}
+                Err(err) => panic!(
+                    "Failed to encode stream from {} to {}: {:?}",
+                    remote.subnet_id, subnet.subnet_id, err
+                ),
+            }
+        }
+        slices
+    }
+}
+
+/// Deliver a single message to the subnet with the given index.
+fn deliver_message(msg: SignedIngress, index: usize, subnets: &Subnets, extra_batches: u64) {
     let message_id = msg.id();
+    let ingress_hist_reader = subnets.subnets[index].ingress_hist_reader.as_ref();
 
-    let _ = execute_ingress_message(message_routing, msg, &message_id, ingress_hist_reader);
+    let _ = execute_ingress_message(subnets, index, msg, &message_id, ingress_hist_reader);
     // print result after waiting, to not interleave the result
     // with debug.print messages from subsequent calls. revise after DFN-1269.
-    wait_extra_batches(message_routing, extra_batches);
+    wait_extra_batches(subnets, extra_batches);
     print_ingress_result(&message_id, ingress_hist_reader);
 }
 
@@ -108,13 +254,13 @@ fn setup_logger(log_file: PathBuf) -> Logger {
     slog::Logger::root(drain, slog::o!())
 }
 
+/// Returns a registry containing the given subnets, each with a single node,
+/// and a routing table assigning a canister range to each of them.
 fn get_registry(
     metrics_registry: &MetricsRegistry,
-    subnet_id: SubnetId,
+    subnets: &[(SubnetId, SubnetType, NodeId)],
     root_subnet_id: SubnetId,
-    subnet_type: SubnetType,
-    node_ids: &[NodeId],
-) -> Arc<RegistryClientImpl> {
+) -> (Arc<RegistryClientImpl>, RoutingTable) {
     let registry_version = RegistryVersion::from(1);
     let data_provider = Arc::new(ProtoRegistryDataProvider::new());
     let root_subnet_id_proto = SubnetIdProto {
@@ -130,8 +276,10 @@ fn get_registry(
         )
         .unwrap();
     let mut routing_table = RoutingTable::new();
-    routing_table_insert_subnet(&mut routing_table, subnet_id).unwrap();
-    let pb_routing_table = PbRoutingTable::from(routing_table);
+    for (subnet_id, _, _) in subnets {
+        routing_table_insert_subnet(&mut routing_table, *subnet_id).unwrap();
+    }
+    let pb_routing_table = PbRoutingTable::from(routing_table.clone());
     data_provider
         .add(
             &make_routing_table_record_key(),
@@ -148,31 +296,42 @@ fn get_registry(
         )
         .unwrap();
 
-    // Set subnetwork list(needed for filling network_topology.nns_subnet_id)
-    let mut record = SubnetRecordBuilder::from(node_ids).build();
-    record.subnet_type = i32::from(subnet_type);
+    for (subnet_id, subnet_type, node_id) in subnets {
+        let mut record = SubnetRecordBuilder::from(&[*node_id]).build();
+        record.subnet_type = i32::from(*subnet_type);
 
-    insert_initial_dkg_transcript(registry_version.get(), subnet_id, &record, &data_provider);
-    add_subnet_record(&data_provider, registry_version.get(), subnet_id, record);
+        insert_initial_dkg_transcript(registry_version.get(), *subnet_id, &record, &data_provider);
+        add_single_subnet_record(&data_provider, registry_version.get(), *subnet_id, record);
+    }
+    // Set subnetwork list(needed for filling network_topology.nns_subnet_id)
+    add_subnet_list_record(
+        &data_provider,
+        registry_version.get(),
+        subnets.iter().map(|(subnet_id, _, _)| *subnet_id).collect(),
+    );
 
     let registry_client = Arc::new(RegistryClientImpl::new(
         data_provider,
         Some(metrics_registry),
     ));
     registry_client.fetch_and_start_polling().unwrap();
-    registry_client
+    (registry_client, routing_table)
 }
 
-pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
-    let DrunOptions {
-        msg_filename,
-        mut cfg,
-        extra_batches,
-        log_file,
-        instruction_limit,
-        subnet_type,
-    } = uo;
-    // Hardcoded magic values to create a ReplicaConfig that parses.
+/// Sets up a subnet with the given ID and type that is driven by `drun`. The
+/// subnet's state is kept in `state_dir`, if provided; or in the configured
+/// state directory otherwise.
+#[allow(clippy::too_many_arguments)]
+fn setup_subnet(
+    cfg: &Config,
+    subnet_id: SubnetId,
+    subnet_type: SubnetType,
+    state_dir: Option<TempDir>,
+    instruction_limit: Option<u64>,
+    registry: Arc<RegistryClientImpl>,
+    metrics_registry: &MetricsRegistry,
+    log: &Logger,
+) -> Subnet {
     let mut subnet_config = SubnetConfig::new(subnet_type);
 
     // If an instruction limit was specified, update the config with the provided instruction limit.
@@ -185,7 +344,6 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
         subnet_config
             .scheduler_config
             .max_instructions_per_message_without_dts = instruction_limit;
-        cfg.hypervisor.max_query_call_graph_instructions = instruction_limit;
     }
 
     // DTS aborts uncompleted messsages if they reach a checkpoint and retries them
@@ -196,28 +354,6 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
         disable_dts(&mut subnet_config);
     }
 
-    let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(0));
-    let root_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
-    let replica_config = ReplicaConfig {
-        node_id: NodeId::from(PrincipalId::new_node_test_id(27)),
-        subnet_id,
-    };
-
-    let msg_stream = msg_stream_from_file(&msg_filename)?;
-    let log = match log_file {
-        Some(log_file) => setup_logger(log_file),
-        None => slog::Logger::root(slog::Discard, slog::o!()),
-    };
-
-    let metrics_registry = MetricsRegistry::global();
-    let registry = get_registry(
-        &metrics_registry,
-        subnet_id,
-        root_subnet_id,
-        subnet_type,
-        &[replica_config.node_id],
-    );
-
     let cycles_account_manager = Arc::new(CyclesAccountManager::new(
         subnet_config.scheduler_config.max_instructions_per_message,
         subnet_type,
@@ -225,13 +361,17 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
         subnet_config.cycles_account_manager_config,
     ));
 
+    let mut state_manager_config = cfg.state_manager.clone();
+    if let Some(state_dir) = &state_dir {
+        state_manager_config.state_root = state_dir.path().to_path_buf();
+    }
     let state_manager = Arc::new(StateManagerImpl::new(
         Arc::new(FakeVerifier::new()),
-        replica_config.subnet_id,
+        subnet_id,
         subnet_type,
         log.clone().into(),
-        &metrics_registry,
-        &cfg.state_manager,
+        metrics_registry,
+        &state_manager_config,
         None,
         ic_types::malicious_flags::MaliciousFlags::default(),
     ));
@@ -241,8 +381,8 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
     let (_, ingress_history_writer, ingress_hist_reader, query_handler, scheduler) =
         ExecutionServices::setup_execution(
             log.clone().into(),
-            &metrics_registry,
-            replica_config.subnet_id,
+            metrics_registry,
+            subnet_id,
             subnet_type,
             subnet_config.scheduler_config,
             cfg.hypervisor.clone(),
@@ -253,44 +393,160 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
         )
         .into_parts();
 
-    let runtime = tokio::runtime::Handle::current();
-    let _metrics_endpoint =
-        MetricsHttpEndpoint::new(runtime.clone(), cfg.metrics, metrics_registry.clone(), &log);
-
     let message_routing = MessageRoutingImpl::new(
         Arc::clone(&state_manager) as _,
         Arc::clone(&state_manager) as _,
         Arc::clone(&ingress_history_writer) as _,
         scheduler,
-        cfg.hypervisor,
+        cfg.hypervisor.clone(),
         cycles_account_manager,
-        replica_config.subnet_id,
-        &metrics_registry,
+        subnet_id,
+        metrics_registry,
         log.clone().into(),
-        Arc::clone(&registry) as _,
+        registry as _,
         MaliciousFlags::default(),
     );
 
+    Subnet {
+        subnet_id,
+        state_manager,
+        message_routing,
+        ingress_hist_reader,
+        query_handler,
+        _state_dir: state_dir,
+    }
+}
+
+/// Sets up subnets of the given types, all driven by `drun`. The first subnet
+/// is the root subnet and keeps its state in the configured state directory,
+/// the others keep their states in temporary directories.
+fn setup_subnets(
+    cfg: &Config,
+    subnet_types: Vec<SubnetType>,
+    instruction_limit: Option<u64>,
+    metrics_registry: &MetricsRegistry,
+    log: &Logger,
+) -> Result<Subnets, String> {
+    let root_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+    let subnet_configs: Vec<_> = subnet_types
+        .into_iter()
+        .enumerate()
+        .map(|(index, subnet_type)| {
+            // Skip the ID of the root subnet.
+            let subnet_id_index = if index == 0 { 0 } else { index as u64 + 1 };
+            (
+                SubnetId::from(PrincipalId::new_subnet_test_id(subnet_id_index)),
+                subnet_type,
+                NodeId::from(PrincipalId::new_node_test_id(27 + index as u64)),
+            )
+        })
+        .collect();
+
+    let (registry, routing_table) = get_registry(metrics_registry, &subnet_configs, root_subnet_id);
+
+    let mut subnets = Vec::with_capacity(subnet_configs.len());
+    for (index, (subnet_id, subnet_type, _)) in subnet_configs.iter().enumerate() {
+        // Only the metrics of the first subnet are exported, since the other
+        // subnets would register the same metrics again.
+        let (metrics_registry, state_dir) = if index == 0 {
+            (metrics_registry.clone(), None)
+        } else {
+            let state_dir = tempfile::Builder::new()
+                .prefix(&format!("drun_subnet_{}_", index))
+                .tempdir()
+                .map_err(|err| {
+                    format!(
+                        "Failed to create the state directory of subnet {}: {}",
+                        index, err
+                    )
+                })?;
+            (MetricsRegistry::new(), Some(state_dir))
+        };
+        subnets.push(setup_subnet(
+            cfg,
+            *subnet_id,
+            *subnet_type,
+            state_dir,
+            instruction_limit,
+            Arc::clone(&registry),
+            &metrics_registry,
+            log,
+        ));
+    }
+    let (_ni_dkg_transcript, secret_key) =
+        dummy_initial_dkg_transcript_with_master_key(&mut StdRng::seed_from_u64(42));
+    Ok(Subnets {
+        subnets,
+        routing_table,
+        secret_key,
+    })
+}
+
+pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
+    let DrunOptions {
+        msg_filename,
+        mut cfg,
+        extra_batches,
+        log_file,
+        instruction_limit,
+        subnet_type,
+    } = uo;
+
+    if let Some(instruction_limit) = instruction_limit {
+        cfg.hypervisor.max_query_call_graph_instructions = NumInstructions::new(instruction_limit);
+    }
+
+    // Additional subnets may be declared at the beginning of the message file.
+    // Without any declarations, a single subnet of type `subnet_type` is used.
+    let mut msg_stream = msg_stream_from_file(&msg_filename)?.peekable();
+    let mut subnet_types = vec![];
+    while let Some(Ok(Message::Subnet(declared_subnet_type))) = msg_stream.peek() {
+        subnet_types.push(*declared_subnet_type);
+        msg_stream.next();
+    }
+    if subnet_types.is_empty() {
+        subnet_types.push(subnet_type);
+    }
+
+    let log = match log_file {
+        Some(log_file) => setup_logger(log_file),
+        None => slog::Logger::root(slog::Discard, slog::o!()),
+    };
+
+    let metrics_registry = MetricsRegistry::global();
+    let runtime = tokio::runtime::Handle::current();
+    let _metrics_endpoint = MetricsHttpEndpoint::new(
+        runtime.clone(),
+        cfg.metrics.clone(),
+        metrics_registry.clone(),
+        &log,
+    );
+
+    let subnets = setup_subnets(
+        &cfg,
+        subnet_types,
+        instruction_limit,
+        &metrics_registry,
+        &log,
+    )?;
+
     for parse_result in msg_stream {
         match parse_result? {
-            Message::Install(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
+            Message::Install(canister_id, msg) => {
+                let index = subnets.index_of(canister_id)?;
+                deliver_message(msg, index, &subnets, extra_batches);
             }
 
             Message::Query(q) => {
-                let (_ni_dkg_transcript, secret_key) =
-                    dummy_initial_dkg_transcript_with_master_key(&mut StdRng::seed_from_u64(42));
-                certify_latest_state_helper(
-                    state_manager.clone(),
-                    &secret_key,
-                    replica_config.subnet_id,
-                );
-                let query_result = match query_handler.clone().oneshot((q, None)).await.unwrap() {
+                let subnet = subnets.get(subnets.index_of(q.receiver)?)?;
+                subnets.certify_latest_state(subnet);
+                let query_result = match subnet
+                    .query_handler
+                    .clone()
+                    .oneshot((q, None))
+                    .await
+                    .unwrap()
+                {
                     Ok((result, _)) => result,
                     Err(QueryExecutionError::CertifiedStateUnavailable) => {
                         panic!("Certified state unavailable for query call.")
@@ -300,20 +556,18 @@ pub async fn run_drun(uo: DrunOptions) -> Result<(), String> {
             }
 
             Message::Ingress(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
-                );
+                let index = subnets.index_of_ingress(&msg)?;
+                deliver_message(msg, index, &subnets, extra_batches);
+            }
+
+            Message::Create(msg, index) => {
+                subnets.get(index)?;
+                deliver_message(msg, index, &subnets, extra_batches);
             }
 
-            Message::Create(msg) => {
-                deliver_message(
-                    msg,
-                    &message_routing,
-                    ingress_hist_reader.as_ref(),
-                    extra_batches,
+            Message::Subnet(_) => {
+                return Err(
+                    "Subnets must be declared before all other messages in the file.".to_string(),
                 );
             }
         }
@@ -397,25 +651,22 @@ fn build_batch(message_routing: &dyn MessageRouting, msgs: Vec<SignedIngress>) -
 /// block forever (in case of bugs), this function will panic if the
 /// process is not finished in some amount of time.
 fn execute_ingress_message(
-    message_routing: &dyn MessageRouting,
+    subnets: &Subnets,
+    index: usize,
     msg: SignedIngress,
     msg_id: &MessageId,
     ingress_history: &dyn IngressHistoryReader,
 ) -> Result<WasmResult, UserError> {
-    let mut batch = build_batch(message_routing, vec![msg]);
+    let mut msg = Some((index, msg));
     for _ in 0..MAX_BATCHES_UNTIL_RESPONSE {
-        // In the first batch we try to send the ingress message itself. If it fails, we
-        // repeat with the same batch.
+        // In the first round we send the ingress message itself.
         //
-        // After the batch with a message is delivered, we keep submitting work to
+        // After the round with the message is executed, we keep submitting work to
         // message routing in the form of empty batches till the ingress message has
         // finished executing. This is necessary to get message routing to process
         // potential inter-canister messages that the ingress message may have
         // triggered.
-        if message_routing.deliver_batch(batch.clone()).is_ok() {
-            batch = build_batch(message_routing, vec![])
-        }
-        sleep(WAIT_PER_BATCH);
+        subnets.execute_round(msg.take());
 
         let ingress_result = (ingress_history.get_latest_status())(msg_id);
         match ingress_result {
@@ -459,22 +710,111 @@ fn execute_ingress_message(
 ///
 /// This is a temporary measure until DFN-1269 is resolved. In that ticket, we
 /// will actually try to wait until all messages have been executed.
-fn wait_extra_batches(message_routing: &dyn MessageRouting, extra_batches: u64) {
+fn wait_extra_batches(subnets: &Subnets, extra_batches: u64) {
     for _ in 0..extra_batches {
-        loop {
-            let batch = build_batch(message_routing, vec![]);
-            let ok = message_routing.deliver_batch(batch).is_ok();
-            sleep(WAIT_PER_BATCH);
-            if ok {
-                break;
-            };
-        }
+        subnets.execute_round(None);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ic_management_canister_types::{CanisterIdRecord, Method, Payload};
+    use ic_registry_routing_table::CANISTER_IDS_PER_SUBNET;
+    use ic_test_utilities_types::messages::SignedIngressBuilder;
+
+    /// Executes the ingress message `msg` on the subnet with the given index.
+    fn execute(
+        subnets: &Subnets,
+        index: usize,
+        msg: SignedIngress,
+    ) -> Result<WasmResult, UserError> {
+        let message_id = msg.id();
+        let ingress_history = subnets.subnets[index].ingress_hist_reader.as_ref();
+        execute_ingress_message(subnets, index, msg, &message_id, ingress_history)
+    }
+
+    /// Runs the example message file `xnet_in.txt`, in which a canister on
+    /// subnet 0 calls a canister on subnet 1.
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_xnet_call() {
+        let tmp = tempfile::tempdir().unwrap();
+        let wasm_file = tmp.path().join("xnet.wasm");
+        std::fs::write(
+            &wasm_file,
+            wat::parse_str(include_str!("../xnet.wat")).unwrap(),
+        )
+        .unwrap();
+        let msg_file = tmp.path().join("xnet_in.txt");
+        std::fs::write(
+            &msg_file,
+            include_str!("../xnet_in.txt").replace("/path/xnet.wasm", wasm_file.to_str().unwrap()),
+        )
+        .unwrap();
+
+        let mut subnet_types = vec![];
+        let mut msgs = vec![];
+        for msg in msg_stream_from_file(msg_file.to_str().unwrap()).unwrap() {
+            match msg.unwrap() {
+                Message::Subnet(subnet_type) => subnet_types.push(subnet_type),
+                msg => msgs.push(msg),
+            }
+        }
+        assert_eq!(subnet_types.len(), 2);
+
+        let (cfg, _cfg_tmp) = Config::temp_config();
+        let log = slog::Logger::root(slog::Discard, slog::o!());
+        let subnets =
+            setup_subnets(&cfg, subnet_types, None, &MetricsRegistry::new(), &log).unwrap();
+
+        let mut indices = vec![];
+        let mut results = vec![];
+        for msg in msgs {
+            let (index, msg) = match msg {
+                Message::Create(msg, index) => (index, msg),
+                Message::Install(canister_id, msg) => (subnets.index_of(canister_id).unwrap(), msg),
+                Message::Ingress(msg) => (subnets.index_of_ingress(&msg).unwrap(), msg),
+                msg => panic!("Unexpected message: {:?}", msg),
+            };
+            indices.push(index);
+            results.push(execute(&subnets, index, msg));
+        }
+        assert_eq!(indices, vec![0, 1, 0, 1, 0]);
+        for result in &results {
+            assert!(result.is_ok(), "Unexpected result: {:?}", result);
+        }
+        assert_eq!(
+            results.last().unwrap(),
+            &Ok(WasmResult::Reply(b"pong".to_vec()))
+        );
+
+        // The request and the response were routed through the XNet streams.
+        let (subnet_0, subnet_1) = (&subnets.subnets[0], &subnets.subnets[1]);
+        let stream_end = |from: &Subnet, to: &Subnet| {
+            from.state_manager
+                .get_latest_state()
+                .take()
+                .get_stream(&to.subnet_id)
+                .map(|stream| stream.messages_end().get())
+        };
+        assert_eq!(stream_end(subnet_0, subnet_1), Some(1));
+        assert_eq!(stream_end(subnet_1, subnet_0), Some(1));
+
+        // Management canister calls are routed by their effective canister ID.
+        let canister_1 = CanisterId::from_u64(CANISTER_IDS_PER_SUBNET);
+        let status = SignedIngressBuilder::new()
+            .canister_id(IC_00)
+            .method_name(Method::CanisterStatus)
+            .method_payload(CanisterIdRecord::from(canister_1).encode())
+            .nonce(100)
+            .build();
+        assert_eq!(subnets.index_of_ingress(&status), Ok(1));
+        assert!(matches!(
+            execute(&subnets, 1, status),
+            Ok(WasmResult::Reply(_))
+        ));
+    }
+
     #[test]
     fn test_get_random_seed() {
         let seed_1 = get_random_seed();
@@ -5,6 +5,7 @@ use ic_execution_environment::execution::upgrade::ENHANCED_ORTHOGONAL_PERSISTENC
 use ic_management_canister_types::{
     self as ic00, CanisterInstallModeV2, CanisterUpgradeOptions, Payload, WasmMemoryPersistence,
 };
+use ic_registry_subnet_type::SubnetType;
 use ic_types::{
     messages::{Query, QuerySource, SignedIngress},
     time::expiry_time_from_now,
@@ -15,7 +16,7 @@ use std::{
     fmt,
     fs::File,
     io::{self, Read},
-    str::Chars,
+    str::{Chars, FromStr},
     string::FromUtf8Error,
 };
 
@@ -23,8 +24,13 @@ use std::{
 pub(crate) enum Message {
     Ingress(SignedIngress),
     Query(Query),
-    Install(SignedIngress),
-    Create(SignedIngress),
+    /// An `install_code` message for the given canister.
+    Install(CanisterId, SignedIngress),
+    /// A canister creation message to be executed on the subnet with the given
+    /// index.
+    Create(SignedIngress, usize),
+    /// Declares an additional subnet of the given type.
+    Subnet(SubnetType),
 }
 
 #[derive(Debug)]
@@ -178,7 +184,11 @@ fn parse_message(s: &str, nonce: u64) -> Result<Message, String> {
             method_name: validate_method_name(method_name)?,
             method_payload: parse_octet_string(payload)?,
         })),
-        ["create"] => parse_create(nonce),
+        ["create"] => parse_create(nonce, 0),
+        ["create", subnet] => parse_create(nonce, parse_subnet_index(subnet)?),
+        ["subnet", subnet_type] => SubnetType::from_str(subnet_type)
+            .map(Message::Subnet)
+            .map_err(|err| format!("Failed to parse subnet type {}: {}", subnet_type, err)),
         ["install", canister_id, wasm_file, payload] => {
             parse_install(nonce, canister_id, payload, wasm_file, "install")
         }
@@ -206,7 +216,13 @@ fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
     }
 }
 
-fn parse_create(nonce: u64) -> Result<Message, String> {
+fn parse_subnet_index(subnet: &str) -> Result<usize, String> {
+    subnet
+        .parse()
+        .map_err(|err| format!("Failed to parse subnet index {}: {}", subnet, err))
+}
+
+fn parse_create(nonce: u64, subnet: usize) -> Result<Message, String> {
     use ic_test_utilities_types::messages::SignedIngressBuilder;
 
     let signed_ingress = SignedIngressBuilder::new()
@@ -216,7 +232,7 @@ fn parse_create(nonce: u64) -> Result<Message, String> {
         .nonce(nonce)
         .build();
 
-    Ok(Message::Create(signed_ingress))
+    Ok(Message::Create(signed_ingress, subnet))
 }
 
 fn contains_icp_private_custom_section(wasm_binary: &[u8], name: &str) -> Result<bool, String> {
@@ -286,7 +302,7 @@ fn parse_install(
         )
         .nonce(nonce)
         .build();
-    Ok(Message::Install(signed_ingress))
+    Ok(Message::Install(canister_id, signed_ingress))
 }
 
 fn validate_method_name(method_name: &str) -> Result<String, String> {
@@ -492,6 +508,29 @@ mod tests {
         assert!(parse_message(s, 0).is_err());
     }
 
+    #[test]
+    fn test_parse_subnet_declarations() {
+        assert_eq!(
+            parse_message("subnet application", 0).unwrap(),
+            Message::Subnet(SubnetType::Application)
+        );
+        assert_eq!(
+            parse_message("subnet verified_application", 0).unwrap(),
+            Message::Subnet(SubnetType::VerifiedApplication)
+        );
+        assert!(parse_message("subnet nns", 0).is_err());
+
+        match parse_message("create 1", 0).unwrap() {
+            Message::Create(_, subnet) => assert_eq!(subnet, 1),
+            msg => panic!("parse_message() returned an unexpected message: {:?}", msg),
+        }
+        match parse_message("create", 0).unwrap() {
+            Message::Create(_, subnet) => assert_eq!(subnet, 0),
+            msg => panic!("parse_message() returned an unexpected message: {:?}", msg),
+        }
+        assert!(parse_message("create first", 0).is_err());
+    }
+
     #[test]
     fn test_illegal_method_name_must_fail() {
         let s = &format!("query {} 0read \"\\xzz\"", APP_CANISTER_URL);
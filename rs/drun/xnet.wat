@@ -0,0 +1,46 @@
+;; A canister that forwards `ping` calls to another canister's `pong` method
+;; and replies with the response. Used by `xnet_in.txt`.
+(module
+  (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i32)))
+  (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i32 i32 i32)))
+  (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+  (import "ic0" "msg_reply" (func $msg_reply))
+  (import "ic0" "msg_reject" (func $msg_reject (param i32 i32)))
+  (import "ic0" "msg_reject_msg_size" (func $msg_reject_msg_size (result i32)))
+  (import "ic0" "msg_reject_msg_copy" (func $msg_reject_msg_copy (param i32 i32 i32)))
+  (import "ic0" "call_new"
+    (func $call_new (param i32 i32 i32 i32 i32 i32 i32 i32)))
+  (import "ic0" "call_perform" (func $call_perform (result i32)))
+
+  ;; Calls `pong` on the canister whose (binary) ID is the argument.
+  (func $ping
+    (call $msg_arg_data_copy (i32.const 100) (i32.const 0) (call $msg_arg_data_size))
+    (call $call_new
+      (i32.const 100) (call $msg_arg_data_size) ;; callee
+      (i32.const 0) (i32.const 4)               ;; method name "pong"
+      (i32.const 0) (i32.const 0)               ;; reply callback
+      (i32.const 1) (i32.const 0))              ;; reject callback
+    (if (call $call_perform)
+      (then (call $msg_reject (i32.const 4) (i32.const 19)))))
+
+  ;; Replies with the response to `ping`.
+  (func $on_reply (param $env i32)
+    (call $msg_arg_data_copy (i32.const 200) (i32.const 0) (call $msg_arg_data_size))
+    (call $msg_reply_data_append (i32.const 200) (call $msg_arg_data_size))
+    (call $msg_reply))
+
+  ;; Rejects `ping` with the reject message of the call.
+  (func $on_reject (param $env i32)
+    (call $msg_reject_msg_copy (i32.const 200) (i32.const 0) (call $msg_reject_msg_size))
+    (call $msg_reject (i32.const 200) (call $msg_reject_msg_size)))
+
+  ;; Replies with "pong".
+  (func $pong
+    (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+    (call $msg_reply))
+
+  (table funcref (elem $on_reply $on_reject))
+  (memory 1)
+  (data (i32.const 0) "pongcall_perform failed")
+  (export "canister_update ping" (func $ping))
+  (export "canister_update pong" (func $pong)))
@@ -6,16 +6,40 @@ use ic_types::NumBytes;
 use ic_utils_lru_cache::LruCache;
 use ic_wasm_types::{CanisterModule, WasmHash};
 
+mod persistent;
+
+pub use persistent::{CompilationFingerprint, PersistentCompilationCache};
+
+#[cfg(test)]
+mod tests;
+
 /// Stores the serialized modules of wasm code that has already been compiled so
 /// that it can be used again without recompiling.
+///
+/// Optionally, successfully compiled modules are also written to a
+/// `PersistentCompilationCache`, which is consulted on misses of the in-memory
+/// cache. Compilation errors are only cached in memory.
 pub struct CompilationCache {
     cache: Mutex<LruCache<WasmHash, HypervisorResult<Arc<SerializedModule>>>>,
+    persistent: Option<PersistentCompilationCache>,
 }
 
 impl CompilationCache {
     pub fn new(capacity: NumBytes) -> Self {
         Self {
             cache: Mutex::new(LruCache::new(capacity)),
+            persistent: None,
+        }
+    }
+
+    /// Creates a cache that is backed by the given on-disk tier.
+    pub fn new_with_persistent_tier(
+        capacity: NumBytes,
+        persistent: PersistentCompilationCache,
+    ) -> Self {
+        Self {
+            cache: Mutex::new(LruCache::new(capacity)),
+            persistent: Some(persistent),
         }
     }
 
@@ -24,25 +48,39 @@ impl CompilationCache {
         canister_module: &CanisterModule,
         serialized_module: HypervisorResult<Arc<SerializedModule>>,
     ) {
+        let wasm_hash = WasmHash::from(canister_module);
+        if let (Some(persistent), Ok(serialized_module)) = (&self.persistent, &serialized_module) {
+            persistent.insert(&wasm_hash, serialized_module);
+        }
         self.cache
             .lock()
             .unwrap()
-            .push(WasmHash::from(canister_module), serialized_module);
+            .push(wasm_hash, serialized_module);
     }
 
     pub fn get(
         &self,
         canister_module: &CanisterModule,
     ) -> Option<HypervisorResult<Arc<SerializedModule>>> {
+        let wasm_hash = WasmHash::from(canister_module);
+        if let Some(result) = self.cache.lock().unwrap().get(&wasm_hash) {
+            return Some(result.clone().map_err(|e| e.clone()));
+        }
+        // The lock on the in-memory cache is not held while reading from disk
+        // so that lookups of other modules are not blocked.
+        let serialized_module = self.persistent.as_ref()?.get(&wasm_hash)?;
         self.cache
             .lock()
             .unwrap()
-            .get(&WasmHash::from(canister_module))
-            .map(|o| o.clone().map_err(|e| e.clone()))
+            .push(wasm_hash, Ok(Arc::clone(&serialized_module)));
+        Some(Ok(serialized_module))
     }
 
     #[doc(hidden)]
     pub fn clear_for_testing(&self) {
-        self.cache.lock().unwrap().clear()
+        self.cache.lock().unwrap().clear();
+        if let Some(persistent) = &self.persistent {
+            persistent.clear();
+        }
     }
 }
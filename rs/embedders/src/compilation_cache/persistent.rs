@@ -0,0 +1,429 @@
+//! A disk-backed tier of the compilation cache.
+//!
+//! Compiled modules are stored as one file per Wasm hash in a subdirectory
+//! named after a fingerprint of the replica version and the embedder
+//! configuration. Modules compiled by a different replica version or with a
+//! different configuration therefore never match, and their directories are
+//! removed when the cache is opened. Every entry additionally records its Wasm
+//! hash and a checksum of the module, which are validated on load. An entry
+//! that fails validation is deleted and reported as a miss so that the caller
+//! falls back to compilation.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_crypto_sha2::Sha256;
+use ic_logger::{info, warn, ReplicaLogger};
+use ic_metrics::MetricsRegistry;
+use ic_types::{CountBytes, NumBytes, ReplicaVersion};
+use ic_utils_lru_cache::LruCache;
+use ic_wasm_types::WasmHash;
+use prometheus::{IntCounterVec, IntGauge};
+use serde::{Deserialize, Serialize};
+
+use crate::SerializedModule;
+
+/// The extension of the files holding cache entries.
+const ENTRY_EXTENSION: &str = "module";
+/// The extension of partially written entries.
+const TMP_EXTENSION: &str = "tmp";
+
+/// Version of the on-disk entry format. Bumping it invalidates all entries.
+const ENTRY_FORMAT_VERSION: u32 = 1;
+
+const LOOKUP_HIT: &str = "hit";
+const LOOKUP_MISS: &str = "miss";
+const LOOKUP_INVALID: &str = "invalid";
+
+/// A fingerprint of everything besides the Wasm binary that influences the
+/// compiled module.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct CompilationFingerprint([u8; 32]);
+
+impl CompilationFingerprint {
+    /// Computes the fingerprint for the given embedder configuration and the
+    /// current replica version. The replica version covers the instrumentation
+    /// code and the Wasmtime version that are compiled into the binary.
+    pub fn new(embedder_config: &EmbeddersConfig) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.write(&ENTRY_FORMAT_VERSION.to_le_bytes());
+        let version = ReplicaVersion::default();
+        hasher.write(&(version.as_ref().len() as u64).to_le_bytes());
+        hasher.write(version.as_ref().as_bytes());
+        let config =
+            bincode::serialize(embedder_config).expect("Failed to serialize the embedder config.");
+        hasher.write(&config);
+        Self(hasher.finish())
+    }
+
+    fn to_hex(self) -> String {
+        to_hex(&self.0)
+    }
+}
+
+/// The size of an entry file, used as the value of the eviction index.
+struct EntrySize(u64);
+
+impl CountBytes for EntrySize {
+    fn count_bytes(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// The format of an entry file.
+#[derive(Deserialize, Serialize)]
+struct PersistedModule {
+    fingerprint: [u8; 32],
+    wasm_hash: [u8; 32],
+    /// SHA-256 of `module`.
+    checksum: [u8; 32],
+    /// The bincode encoding of the `SerializedModule`.
+    #[serde(with = "serde_bytes")]
+    module: Vec<u8>,
+}
+
+struct PersistentCompilationCacheMetrics {
+    lookups: IntCounterVec,
+    errors: IntCounterVec,
+    size: IntGauge,
+}
+
+impl PersistentCompilationCacheMetrics {
+    fn new(metrics_registry: &MetricsRegistry) -> Self {
+        Self {
+            lookups: metrics_registry.int_counter_vec(
+                "execution_persistent_compilation_cache_lookups",
+                "Lookups in the persistent compilation cache by result (hit, miss or invalid).",
+                &["result"],
+            ),
+            errors: metrics_registry.int_counter_vec(
+                "execution_persistent_compilation_cache_errors",
+                "I/O errors of the persistent compilation cache by operation.",
+                &["operation"],
+            ),
+            size: metrics_registry.int_gauge(
+                "execution_persistent_compilation_cache_size_bytes",
+                "The total size of the entries in the persistent compilation cache.",
+            ),
+        }
+    }
+}
+
+/// Stores serialized modules on disk so that they survive replica restarts.
+///
+/// The total size of the stored entries is bounded by the capacity. Entries
+/// are evicted in least-recently used order; after a restart the modification
+/// time of the entry files is used to restore that order.
+pub struct PersistentCompilationCache {
+    dir: PathBuf,
+    fingerprint: CompilationFingerprint,
+    capacity: NumBytes,
+    index: Mutex<LruCache<WasmHash, EntrySize>>,
+    metrics: PersistentCompilationCacheMetrics,
+    log: ReplicaLogger,
+}
+
+impl PersistentCompilationCache {
+    /// Opens the cache stored in `root`, creating the directory if needed.
+    /// Entries of other fingerprints as well as leftovers of interrupted
+    /// writes are removed.
+    pub fn open(
+        root: &Path,
+        capacity: NumBytes,
+        fingerprint: CompilationFingerprint,
+        metrics_registry: &MetricsRegistry,
+        log: ReplicaLogger,
+    ) -> io::Result<Self> {
+        let dir = root.join(fingerprint.to_hex());
+        fs::create_dir_all(&dir)?;
+        remove_stale_fingerprints(root, &dir, &log);
+
+        let cache = Self {
+            dir,
+            fingerprint,
+            capacity,
+            index: Mutex::new(LruCache::new(capacity)),
+            metrics: PersistentCompilationCacheMetrics::new(metrics_registry),
+            log,
+        };
+        cache.load_index()?;
+        Ok(cache)
+    }
+
+    /// Returns the module stored for the given hash, if there is a valid one.
+    pub fn get(&self, wasm_hash: &WasmHash) -> Option<Arc<SerializedModule>> {
+        if self.index.lock().unwrap().get(wasm_hash).is_none() {
+            self.metrics.lookups.with_label_values(&[LOOKUP_MISS]).inc();
+            return None;
+        }
+        let path = self.entry_path(wasm_hash);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                self.metrics.errors.with_label_values(&["read"]).inc();
+                warn!(
+                    self.log,
+                    "Failed to read persistent compilation cache entry {}: {}",
+                    path.display(),
+                    err
+                );
+                self.remove(wasm_hash);
+                self.metrics.lookups.with_label_values(&[LOOKUP_MISS]).inc();
+                return None;
+            }
+        };
+        match self.decode(wasm_hash, &bytes) {
+            Ok(module) => {
+                self.metrics.lookups.with_label_values(&[LOOKUP_HIT]).inc();
+                Some(Arc::new(module))
+            }
+            Err(reason) => {
+                warn!(
+                    self.log,
+                    "Discarding invalid persistent compilation cache entry {}: {}",
+                    path.display(),
+                    reason
+                );
+                self.remove(wasm_hash);
+                self.metrics
+                    .lookups
+                    .with_label_values(&[LOOKUP_INVALID])
+                    .inc();
+                None
+            }
+        }
+    }
+
+    /// Stores the module for the given hash, evicting older entries if the
+    /// capacity would be exceeded. Failures are logged and otherwise ignored
+    /// because the cache is an optimization only.
+    pub fn insert(&self, wasm_hash: &WasmHash, module: &SerializedModule) {
+        let encoded = match self.encode(wasm_hash, module) {
+            Ok(encoded) => encoded,
+            Err(err) => {
+                self.metrics.errors.with_label_values(&["encode"]).inc();
+                warn!(
+                    self.log,
+                    "Failed to encode module for the persistent compilation cache: {}", err
+                );
+                return;
+            }
+        };
+        let size = encoded.len() as u64;
+        // The index also accounts the key of every entry. An entry exceeding the
+        // capacity would be evicted by its own insertion, so it is not stored at all.
+        if wasm_hash.count_bytes() as u64 + size > self.capacity.get() {
+            return;
+        }
+        let path = self.entry_path(wasm_hash);
+        if let Err(err) = write_atomically(&path, &encoded) {
+            self.metrics.errors.with_label_values(&["write"]).inc();
+            warn!(
+                self.log,
+                "Failed to write persistent compilation cache entry {}: {}",
+                path.display(),
+                err
+            );
+            return;
+        }
+        let evicted = self
+            .index
+            .lock()
+            .unwrap()
+            .push(wasm_hash.clone(), EntrySize(size));
+        for (evicted_hash, _) in evicted {
+            // Re-inserting a hash returns its previous entry, whose file has
+            // just been replaced. The inserted entry itself is never evicted
+            // because it fits into the capacity.
+            if evicted_hash != *wasm_hash {
+                self.delete_file(&self.entry_path(&evicted_hash));
+            }
+        }
+        self.update_size_metric();
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        let mut index = self.index.lock().unwrap();
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            for entry in entries.flatten() {
+                self.delete_file(&entry.path());
+            }
+        }
+        index.clear();
+        drop(index);
+        self.update_size_metric();
+    }
+
+    fn remove(&self, wasm_hash: &WasmHash) {
+        self.index.lock().unwrap().pop(wasm_hash);
+        self.delete_file(&self.entry_path(wasm_hash));
+        self.update_size_metric();
+    }
+
+    /// Populates the eviction index from the entry files, the least recently
+    /// modified first.
+    fn load_index(&self) -> io::Result<()> {
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let wasm_hash = match path.extension().and_then(|ext| ext.to_str()) {
+                Some(ENTRY_EXTENSION) => path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(from_hex)
+                    .map(WasmHash::from),
+                _ => None,
+            };
+            let metadata = fs::metadata(&path)?;
+            match wasm_hash {
+                Some(wasm_hash) if metadata.is_file() => {
+                    let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+                    entries.push((modified, wasm_hash, metadata.len()));
+                }
+                _ => self.delete_file(&path),
+            }
+        }
+        entries.sort_by_key(|(modified, _, _)| *modified);
+
+        let num_entries = entries.len();
+        let mut index = self.index.lock().unwrap();
+        for (_, wasm_hash, size) in entries {
+            for (evicted_hash, _) in index.push(wasm_hash, EntrySize(size)) {
+                self.delete_file(&self.entry_path(&evicted_hash));
+            }
+        }
+        let retained = index.len();
+        drop(index);
+        self.update_size_metric();
+        info!(
+            self.log,
+            "Opened persistent compilation cache {} with {} entries ({} evicted)",
+            self.dir.display(),
+            retained,
+            num_entries - retained
+        );
+        Ok(())
+    }
+
+    fn encode(&self, wasm_hash: &WasmHash, module: &SerializedModule) -> bincode::Result<Vec<u8>> {
+        let module = bincode::serialize(module)?;
+        bincode::serialize(&PersistedModule {
+            fingerprint: self.fingerprint.0,
+            wasm_hash: wasm_hash.to_slice(),
+            checksum: Sha256::hash(&module),
+            module,
+        })
+    }
+
+    fn decode(&self, wasm_hash: &WasmHash, bytes: &[u8]) -> Result<SerializedModule, String> {
+        let persisted: PersistedModule =
+            bincode::deserialize(bytes).map_err(|err| format!("malformed entry: {}", err))?;
+        if persisted.fingerprint != self.fingerprint.0 {
+            return Err("fingerprint mismatch".to_string());
+        }
+        if persisted.wasm_hash != wasm_hash.to_slice() {
+            return Err("Wasm hash mismatch".to_string());
+        }
+        if Sha256::hash(&persisted.module) != persisted.checksum {
+            return Err("checksum mismatch".to_string());
+        }
+        bincode::deserialize(&persisted.module).map_err(|err| format!("malformed module: {}", err))
+    }
+
+    fn entry_path(&self, wasm_hash: &WasmHash) -> PathBuf {
+        self.dir.join(format!(
+            "{}.{}",
+            to_hex(&wasm_hash.to_slice()),
+            ENTRY_EXTENSION
+        ))
+    }
+
+    fn delete_file(&self, path: &Path) {
+        match fs::remove_file(path) {
+            Ok(()) => (),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => {
+                self.metrics.errors.with_label_values(&["delete"]).inc();
+                warn!(
+                    self.log,
+                    "Failed to delete persistent compilation cache file {}: {}",
+                    path.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    fn update_size_metric(&self) {
+        let size = self.index.lock().unwrap().count_bytes();
+        self.metrics.size.set(size as i64);
+    }
+}
+
+/// Removes all subdirectories of `root` except for `current`.
+fn remove_stale_fingerprints(root: &Path, current: &Path, log: &ReplicaLogger) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path == current {
+            continue;
+        }
+        let result = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => info!(
+                log,
+                "Removed stale persistent compilation cache {}",
+                path.display()
+            ),
+            Err(err) => warn!(
+                log,
+                "Failed to remove stale persistent compilation cache {}: {}",
+                path.display(),
+                err
+            ),
+        }
+    }
+}
+
+/// Writes the file under a unique temporary name in the same directory and
+/// renames it, so that readers never observe partially written entries and
+/// concurrent writers of the same entry do not interfere with each other.
+/// The temporary file is removed if any step fails.
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut file = tempfile::Builder::new()
+        .suffix(&format!(".{}", TMP_EXTENSION))
+        .tempfile_in(dir)?;
+    file.write_all(bytes)?;
+    file.as_file().sync_data()?;
+    file.persist(path).map_err(|err| err.error)?;
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<[u8; 32]> {
+    if s.len() != 64 || !s.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
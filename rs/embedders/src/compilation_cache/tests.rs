@@ -0,0 +1,170 @@
+use std::{fs, path::Path, path::PathBuf, sync::Arc};
+
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_interfaces::execution_environment::HypervisorError;
+use ic_logger::replica_logger::no_op_logger;
+use ic_metrics::MetricsRegistry;
+use ic_types::NumBytes;
+use ic_wasm_types::{BinaryEncodedWasm, CanisterModule};
+
+use super::{CompilationCache, CompilationFingerprint, PersistentCompilationCache};
+use crate::{wasm_utils::compile, SerializedModule, WasmtimeEmbedder};
+
+const CAPACITY: NumBytes = NumBytes::new(1024 * 1024 * 1024);
+
+fn canister_module(exported_name: &str) -> CanisterModule {
+    let wat = format!(
+        r#"(module (func (export "canister_update {}") (drop (i32.const 1))))"#,
+        exported_name
+    );
+    CanisterModule::new(wat::parse_str(wat).unwrap())
+}
+
+fn compile_module(canister_module: &CanisterModule) -> Arc<SerializedModule> {
+    let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+    let wasm = BinaryEncodedWasm::new(canister_module.as_slice().to_vec());
+    let (_, result) = compile(&embedder, &wasm);
+    Arc::new(result.unwrap().1)
+}
+
+fn open_cache(root: &Path, capacity: NumBytes, config: &EmbeddersConfig) -> CompilationCache {
+    let persistent = PersistentCompilationCache::open(
+        root,
+        capacity,
+        CompilationFingerprint::new(config),
+        &MetricsRegistry::new(),
+        no_op_logger(),
+    )
+    .unwrap();
+    CompilationCache::new_with_persistent_tier(CAPACITY, persistent)
+}
+
+/// Returns the entry files of all fingerprints stored under `root`.
+fn entry_files(root: &Path) -> Vec<PathBuf> {
+    fs::read_dir(root)
+        .unwrap()
+        .flat_map(|dir| fs::read_dir(dir.unwrap().path()).unwrap())
+        .map(|entry| entry.unwrap().path())
+        .collect()
+}
+
+#[test]
+fn modules_survive_reopening() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let canister_module = canister_module("foo");
+    let serialized_module = compile_module(&canister_module);
+
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    cache.insert(&canister_module, Ok(Arc::clone(&serialized_module)));
+    drop(cache);
+
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    let cached = cache.get(&canister_module).unwrap().unwrap();
+    assert_eq!(*cached, *serialized_module);
+}
+
+#[test]
+fn compilation_errors_are_not_persisted() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let canister_module = canister_module("foo");
+
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    cache.insert(
+        &canister_module,
+        Err(HypervisorError::ToolchainContractViolation {
+            error: "invalid".to_string(),
+        }),
+    );
+    assert!(entry_files(tmp.path()).is_empty());
+}
+
+#[test]
+fn corrupted_entry_is_discarded() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let canister_module = canister_module("foo");
+
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    cache.insert(&canister_module, Ok(compile_module(&canister_module)));
+    drop(cache);
+
+    let files = entry_files(tmp.path());
+    assert_eq!(files.len(), 1);
+    let mut bytes = fs::read(&files[0]).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    fs::write(&files[0], bytes).unwrap();
+
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    assert!(cache.get(&canister_module).is_none());
+    assert!(entry_files(tmp.path()).is_empty());
+}
+
+#[test]
+fn entries_of_other_configs_are_removed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let canister_module = canister_module("foo");
+
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    cache.insert(&canister_module, Ok(compile_module(&canister_module)));
+    drop(cache);
+
+    let other_config = EmbeddersConfig {
+        max_globals: config.max_globals + 1,
+        ..config
+    };
+    let cache = open_cache(tmp.path(), CAPACITY, &other_config);
+    assert!(cache.get(&canister_module).is_none());
+    assert!(entry_files(tmp.path()).is_empty());
+}
+
+#[test]
+fn least_recently_used_entries_are_evicted() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let foo = canister_module("foo");
+    let bar = canister_module("bar");
+
+    // Determine the size of a single entry and allow only one to be stored.
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    cache.insert(&foo, Ok(compile_module(&foo)));
+    let entry_size = fs::metadata(&entry_files(tmp.path())[0]).unwrap().len();
+    cache.clear_for_testing();
+    drop(cache);
+
+    let cache = open_cache(
+        tmp.path(),
+        NumBytes::new(entry_size + entry_size / 2),
+        &config,
+    );
+    cache.insert(&foo, Ok(compile_module(&foo)));
+    cache.insert(&bar, Ok(compile_module(&bar)));
+    drop(cache);
+    assert_eq!(entry_files(tmp.path()).len(), 1);
+
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    assert!(cache.get(&foo).is_none());
+    assert!(cache.get(&bar).is_some());
+}
+
+#[test]
+fn entries_exceeding_the_capacity_are_not_stored() {
+    let tmp = tempfile::tempdir().unwrap();
+    let config = EmbeddersConfig::default();
+    let foo = canister_module("foo");
+
+    let cache = open_cache(tmp.path(), CAPACITY, &config);
+    cache.insert(&foo, Ok(compile_module(&foo)));
+    let entry_size = fs::metadata(&entry_files(tmp.path())[0]).unwrap().len();
+    cache.clear_for_testing();
+    drop(cache);
+
+    // The capacity fits the entry file, but not the entry together with its key.
+    let cache = open_cache(tmp.path(), NumBytes::new(entry_size), &config);
+    cache.insert(&foo, Ok(compile_module(&foo)));
+    drop(cache);
+    assert!(entry_files(tmp.path()).is_empty());
+}
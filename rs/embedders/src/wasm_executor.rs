@@ -466,6 +466,8 @@ pub fn wasm_execution_error(
             instance_stats: InstanceStats::default(),
             system_api_call_counters: SystemApiCallCounters::default(),
             canister_log: Default::default(),
+            instruction_profile: None,
+            coverage: None,
         },
         None,
     )
@@ -634,6 +636,8 @@ pub fn process(
                     instance_stats: InstanceStats::default(),
                     system_api_call_counters: SystemApiCallCounters::default(),
                     canister_log: Default::default(),
+                    instruction_profile: None,
+                    coverage: None,
                 },
                 None,
                 Err(system_api.unwrap()), // should be safe because we've passed Some(api) to new_instance
@@ -652,6 +656,8 @@ pub fn process(
     // Get the executed/remaining instructions for the message and the slice.
     let instruction_counter = instance.instruction_counter();
     let instance_stats = instance.get_stats();
+    let instruction_profile = instance.take_instruction_profile();
+    let coverage = instance.take_coverage();
     //unwrap should not fail, because we have passed Some(system_api) to the instance above
     let system_api = instance.store_data_mut().system_api_mut().unwrap();
     let system_api_call_counters = system_api.call_counters();
@@ -693,6 +699,8 @@ pub fn process(
                         instance_stats,
                         system_api_call_counters,
                         canister_log,
+                        instruction_profile,
+                        coverage,
                     },
                     None,
                     Ok(instance),
@@ -828,6 +836,8 @@ pub fn process(
             instance_stats,
             system_api_call_counters,
             canister_log,
+            instruction_profile,
+            coverage,
         },
         wasm_state_changes,
         Ok(instance),
@@ -1,10 +1,10 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     time::Instant,
 };
 
 use ic_config::embedders::Config as EmbeddersConfig;
-use ic_interfaces::execution_environment::HypervisorResult;
+use ic_interfaces::execution_environment::{BlockCoverage, CoveredBlock, HypervisorResult};
 use ic_replicated_state::{
     canister_state::{execution_state::WasmMetadata, WASM_PAGE_SIZE_IN_BYTES},
     EmbedderCache, NumWasmPages, PageIndex,
@@ -206,6 +206,8 @@ fn validate_and_instrument(
         config.cost_to_compile_wasm_instruction,
         config.feature_flags.write_barrier,
         config.feature_flags.wasm_native_stable_memory,
+        config.feature_flags.instruction_profiling,
+        config.feature_flags.wasm_coverage,
         config.metering_type,
         config.subnet_type,
         config.dirty_page_overhead,
@@ -225,6 +227,97 @@ pub fn validate_and_instrument_for_testing(
     validate_and_instrument(wasm, embedder.config())
 }
 
+/// Returns the function names from the name section of the given module,
+/// keyed by function index. Returns an empty map if the module has no name
+/// section.
+pub fn function_names(wasm: &BinaryEncodedWasm) -> BTreeMap<u32, String> {
+    let mut names = BTreeMap::new();
+    for payload in wasmparser::Parser::new(0).parse_all(wasm.as_slice()) {
+        let Ok(wasmparser::Payload::CustomSection(reader)) = payload else {
+            continue;
+        };
+        let wasmparser::KnownCustom::Name(subsections) = reader.as_known() else {
+            continue;
+        };
+        for subsection in subsections.into_iter().flatten() {
+            if let wasmparser::Name::Function(name_map) = subsection {
+                for naming in name_map.into_iter().flatten() {
+                    names.insert(naming.index, naming.name.to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Resolves the executed blocks against the canister module. The offsets of
+/// the blocks are left out if the module cannot be parsed.
+pub fn covered_blocks(wasm: &BinaryEncodedWasm, coverage: &BlockCoverage) -> Vec<CoveredBlock> {
+    let names = function_names(wasm);
+    let offsets = block_offsets(wasm, coverage).unwrap_or_default();
+    coverage
+        .blocks
+        .iter()
+        .map(|&(function_index, instruction_index)| CoveredBlock {
+            function_index,
+            function_name: names.get(&function_index).cloned(),
+            instruction_index,
+            code_offset: offsets.get(&(function_index, instruction_index)).copied(),
+        })
+        .collect()
+}
+
+/// Returns the offsets of the first instructions of the given blocks relative
+/// to the start of the code section.
+fn block_offsets(
+    wasm: &BinaryEncodedWasm,
+    coverage: &BlockCoverage,
+) -> wasmparser::Result<BTreeMap<(u32, u32), u64>> {
+    let mut offsets = BTreeMap::new();
+    let mut function_index = 0;
+    let mut code_section_start = 0;
+    for payload in wasmparser::Parser::new(0).parse_all(wasm.as_slice()) {
+        match payload? {
+            wasmparser::Payload::ImportSection(reader) => {
+                for import in reader {
+                    if let wasmparser::TypeRef::Func(_) = import?.ty {
+                        function_index += 1;
+                    }
+                }
+            }
+            wasmparser::Payload::CodeSectionStart { range, .. } => {
+                code_section_start = range.start;
+            }
+            wasmparser::Payload::CodeSectionEntry(body) => {
+                let mut instruction_indices = coverage
+                    .blocks
+                    .range((function_index, 0)..=(function_index, u32::MAX))
+                    .map(|(_, instruction_index)| *instruction_index)
+                    .peekable();
+                let mut reader = body.get_operators_reader()?;
+                let mut position = 0;
+                while let Some(&instruction_index) = instruction_indices.peek() {
+                    if reader.eof() {
+                        break;
+                    }
+                    let (_, offset) = reader.read_with_offset()?;
+                    if position == instruction_index {
+                        offsets.insert(
+                            (function_index, instruction_index),
+                            (offset - code_section_start) as u64,
+                        );
+                        instruction_indices.next();
+                    }
+                    position += 1;
+                }
+                function_index += 1;
+            }
+            _ => {}
+        }
+    }
+    Ok(offsets)
+}
+
 fn compile_inner(
     embedder: &WasmtimeEmbedder,
     wasm: &BinaryEncodedWasm,
@@ -109,6 +109,39 @@
 //! (memory (export "stable_memory_bytemap") i32 (i64.const STABLE_BYTEMAP_SIZE) (i64.const STABLE_BYTEMAP_SIZE))
 //! ```
 //!
+//! # Instruction profiling
+//!
+//! If instruction profiling is enabled, every function of the original module
+//! is replaced by an unmetered wrapper that calls the original body between
+//! two calls to the host:
+//!
+//! ```wasm
+//! (func $f (param i64) (result i32)
+//!   i32.const ORIGINAL_INDEX_OF_F
+//!   call $profile_enter
+//!   local.get 0
+//!   call $f_body
+//!   i32.const ORIGINAL_INDEX_OF_F
+//!   call $profile_exit)
+//! ```
+//!
+//! # Coverage
+//!
+//! If Wasm coverage is enabled, the function `coverage_hit` is imported after
+//! all other imported functions and a call to it is inserted at the beginning
+//! of every block of the original functions, after the metering code:
+//!
+//! ```wasm
+//! i32.const ORIGINAL_FUNCTION_INDEX
+//! i32.const ORIGINAL_INSTRUCTION_INDEX
+//! call $coverage_hit
+//! ```
+//!
+//! The instruction index is the position of the first instruction of the
+//! block in the body of the original function. The calls are injected after
+//! computing the compilation cost, which hence does not depend on whether
+//! coverage is enabled.
+//!
 
 use super::system_api_replacements::replacement_functions;
 use super::validation::API_VERSION_IC0;
@@ -712,6 +745,9 @@ const TRY_GROW_WASM_MEMORY_FUN_NAME: &str = "try_grow_wasm_memory";
 const TRY_GROW_STABLE_MEMORY_FUN_NAME: &str = "try_grow_stable_memory";
 const INTERNAL_TRAP_FUN_NAME: &str = "internal_trap";
 const STABLE_READ_FIRST_ACCESS_NAME: &str = "stable_read_first_access";
+const PROFILE_ENTER_FUN_NAME: &str = "profile_enter";
+const PROFILE_EXIT_FUN_NAME: &str = "profile_exit";
+const COVERAGE_HIT_FUN_NAME: &str = "coverage_hit";
 const TABLE_STR: &str = "table";
 pub(crate) const INSTRUCTIONS_COUNTER_GLOBAL_NAME: &str = "canister counter_instructions";
 pub(crate) const DIRTY_PAGES_COUNTER_GLOBAL_NAME: &str = "canister counter_dirty_pages";
@@ -940,6 +976,8 @@ pub(super) fn instrument(
     cost_to_compile_wasm_instruction: NumInstructions,
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
+    instruction_profiling: FlagStatus,
+    wasm_coverage: FlagStatus,
     metering_type: MeteringType,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
@@ -947,6 +985,8 @@ pub(super) fn instrument(
     max_stable_memory_size: NumBytes,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
     let main_memory_type = main_memory_type(&module);
+    let num_original_imported_functions = num_imported_functions(&module);
+    let num_original_functions = module.functions.len();
     let stable_memory_index;
     let mut module = inject_helper_functions(module, wasm_native_stable_memory, main_memory_type);
     module = export_table(module);
@@ -961,6 +1001,11 @@ pub(super) fn instrument(
     let mut extra_strs: Vec<String> = Vec::new();
     module = export_mutable_globals(module, &mut extra_strs);
 
+    let coverage_hit_fn = match wasm_coverage {
+        FlagStatus::Enabled => Some(inject_coverage_import(&mut module)),
+        FlagStatus::Disabled => None,
+    };
+
     let mut num_imported_functions = 0;
     let mut num_imported_globals = 0;
     for imp in &module.imports {
@@ -1009,13 +1054,15 @@ pub(super) fn instrument(
     }
 
     // inject instructions counter decrementation
-    for func_body in &mut module.code_sections {
-        inject_metering(
+    let mut coverage_blocks = Vec::with_capacity(module.code_sections.len());
+    for func_body in module.code_sections.iter_mut() {
+        coverage_blocks.push(inject_metering(
             &mut func_body.instructions,
             &special_indices,
             metering_type,
             main_memory_type,
-        );
+            coverage_hit_fn,
+        ));
     }
 
     // Collect all the function types of the locally defined functions inside the
@@ -1099,6 +1146,10 @@ pub(super) fn instrument(
     for body in &module.code_sections {
         wasm_instruction_count += body.instructions.len() as u64;
     }
+    if let Some(coverage_hit_fn) = coverage_hit_fn {
+        // The placeholder calls to `coverage_hit` are not part of the cost.
+        wasm_instruction_count -= num_coverage_placeholders(&module, coverage_hit_fn);
+    }
     for global in &module.globals {
         // Each global has a single instruction initializer and an `End`
         // instruction will be added during encoding.
@@ -1108,6 +1159,24 @@ pub(super) fn instrument(
         wasm_instruction_count += 2;
     }
 
+    // Injected after computing the compilation cost, so that the cost does
+    // not depend on whether coverage or profiling is enabled.
+    if let Some(coverage_hit_fn) = coverage_hit_fn {
+        inject_coverage(
+            &mut module,
+            coverage_hit_fn,
+            num_original_imported_functions,
+            coverage_blocks,
+        );
+    }
+    if instruction_profiling == FlagStatus::Enabled {
+        inject_profiling(
+            &mut module,
+            num_original_functions,
+            num_original_imported_functions,
+        )?;
+    }
+
     let result = module.encode().map_err(|err| {
         WasmInstrumentationError::WasmSerializeError(WasmError::new(err.to_string()))
     })?;
@@ -1120,6 +1189,168 @@ pub(super) fn instrument(
     })
 }
 
+/// Imports the `coverage_hit` function after all other imported functions and
+/// returns its index.
+fn inject_coverage_import(module: &mut Module<'_>) -> u32 {
+    let coverage_type_idx = add_func_type(module, FuncType::new([ValType::I32, ValType::I32], []));
+    let coverage_hit_fn = num_imported_functions(module);
+    module.imports.push(Import {
+        module: INSTRUMENTED_FUN_MODULE,
+        name: COVERAGE_HIT_FUN_NAME,
+        ty: TypeRef::Func(coverage_type_idx),
+    });
+    // The new import precedes all locally defined functions.
+    mutate_function_indices(module, |i| if i >= coverage_hit_fn { i + 1 } else { i });
+    coverage_hit_fn
+}
+
+/// Returns the number of placeholder calls to `coverage_hit` (see
+/// `inject_metering()`) in the module.
+fn num_coverage_placeholders(module: &Module<'_>, coverage_hit_fn: u32) -> u64 {
+    module
+        .code_sections
+        .iter()
+        .flat_map(|body| body.instructions.iter())
+        .filter(|op| {
+            matches!(op, Operator::Call { function_index } if *function_index == coverage_hit_fn)
+        })
+        .count() as u64
+}
+
+/// Replaces the placeholder calls to `coverage_hit` in the original functions
+/// with calls reporting the original function index and the position of the
+/// block in the original function (see `inject_metering()`).
+fn inject_coverage(
+    module: &mut Module<'_>,
+    coverage_hit_fn: u32,
+    num_original_imported_functions: u32,
+    coverage_blocks: Vec<Vec<u32>>,
+) {
+    use Operator::*;
+    for (func_ix, block_positions) in coverage_blocks.into_iter().enumerate() {
+        let function_index = num_original_imported_functions + func_ix as u32;
+        let func_body = &mut module.code_sections[func_ix];
+        let mut block_positions = block_positions.into_iter();
+        let mut elems = Vec::with_capacity(func_body.instructions.len());
+        for op in func_body.instructions.drain(..) {
+            match op {
+                Call {
+                    function_index: index,
+                } if index == coverage_hit_fn => {
+                    let position = block_positions
+                        .next()
+                        .expect("More coverage placeholders than blocks");
+                    elems.extend_from_slice(&[
+                        I32Const {
+                            value: function_index as i32,
+                        },
+                        I32Const {
+                            value: position as i32,
+                        },
+                        Call {
+                            function_index: coverage_hit_fn,
+                        },
+                    ]);
+                }
+                op => elems.push(op),
+            }
+        }
+        debug_assert!(block_positions.next().is_none());
+        func_body.instructions = elems;
+    }
+}
+
+fn num_imported_functions(module: &Module<'_>) -> u32 {
+    module
+        .imports
+        .iter()
+        .filter(|imp| matches!(imp.ty, TypeRef::Func(_)))
+        .count() as u32
+}
+
+/// Wraps the first `num_wrapped_functions` locally defined functions, i.e. the
+/// functions of the original module, to notify the host whenever they are
+/// entered or exited.
+///
+/// The body of each wrapped function is moved to a new function at the end of
+/// the function index space and replaced by a wrapper that calls the new
+/// function between calls to `profile_enter` and `profile_exit`. The wrapper
+/// keeps the index of the original function, so all calls, exports, table
+/// entries and names remain valid. The hooks receive the index of the function
+/// in the original module, which can be resolved with the module's name
+/// section.
+///
+/// The wrappers are not metered, so the instructions charged for a message
+/// are the same as without profiling.
+fn inject_profiling(
+    module: &mut Module<'_>,
+    num_wrapped_functions: usize,
+    num_original_imported_functions: u32,
+) -> Result<(), WasmInstrumentationError> {
+    let profile_type_idx = add_func_type(module, FuncType::new([ValType::I32], []));
+    let profile_enter_fn = num_imported_functions(module);
+    let profile_exit_fn = profile_enter_fn + 1;
+    module.imports.push(Import {
+        module: INSTRUMENTED_FUN_MODULE,
+        name: PROFILE_ENTER_FUN_NAME,
+        ty: TypeRef::Func(profile_type_idx),
+    });
+    module.imports.push(Import {
+        module: INSTRUMENTED_FUN_MODULE,
+        name: PROFILE_EXIT_FUN_NAME,
+        ty: TypeRef::Func(profile_type_idx),
+    });
+    // The new imports precede all locally defined functions.
+    mutate_function_indices(module, |i| if i >= profile_enter_fn { i + 2 } else { i });
+    let first_defined_fn = profile_exit_fn + 1;
+
+    for func_ix in 0..num_wrapped_functions {
+        let type_idx = module.functions[func_ix];
+        let num_params = match &module.types[type_idx as usize].composite_type.inner {
+            CompositeInnerType::Func(ty) => ty.params().len() as u32,
+            other => {
+                return Err(WasmInstrumentationError::InvalidFunctionType(format!(
+                    "Function has type which is not a function type. Found type: {:?}",
+                    other
+                )))
+            }
+        };
+        let original_index = (num_original_imported_functions + func_ix as u32) as i32;
+        let inner_fn = first_defined_fn + module.functions.len() as u32;
+
+        use Operator::*;
+        let mut instructions = vec![
+            I32Const {
+                value: original_index,
+            },
+            Call {
+                function_index: profile_enter_fn,
+            },
+        ];
+        instructions.extend((0..num_params).map(|local_index| LocalGet { local_index }));
+        instructions.extend([
+            Call {
+                function_index: inner_fn,
+            },
+            I32Const {
+                value: original_index,
+            },
+            Call {
+                function_index: profile_exit_fn,
+            },
+            End,
+        ]);
+        let wrapper = ic_wasm_transform::Body {
+            locals: vec![],
+            instructions,
+        };
+        let inner = std::mem::replace(&mut module.code_sections[func_ix], wrapper);
+        module.functions.push(type_idx);
+        module.code_sections.push(inner);
+    }
+    Ok(())
+}
+
 fn calculate_api_indexes(module: &Module<'_>) -> BTreeMap<SystemApiFunc, u32> {
     module
         .imports
@@ -1480,33 +1711,58 @@ impl InjectionPoint {
 // - we insert a function call before each dynamic cost instruction which
 //   performs an overflow check and then decrements the counter by the value at
 //   the top of the stack.
+// If coverage is enabled, we additionally insert a placeholder call to
+// `coverage_hit` at the beginning of every block, after the metering code, and
+// return the positions of the blocks in the original code. The placeholders are
+// neither metered nor part of the compilation cost: they are only turned into
+// calls reporting the block positions (by `inject_coverage()`) after the
+// compilation cost has been computed.
 fn inject_metering(
     code: &mut Vec<Operator>,
     export_data_module: &SpecialIndices,
     metering_type: MeteringType,
     mem_type: WasmMemoryType,
-) {
-    let points = match metering_type {
-        MeteringType::None => Vec::new(),
-        MeteringType::New => injections(code, mem_type),
+    coverage_hit_fn: Option<u32>,
+) -> Vec<u32> {
+    let points = match (metering_type, coverage_hit_fn) {
+        (MeteringType::None, None) => Vec::new(),
+        _ => injections(code, mem_type),
     };
-    let points = points.iter().filter(|point| match point.cost_detail {
-        InjectionPointCostDetail::StaticCost {
-            scope: Scope::ReentrantBlockStart,
-            cost: _,
-        } => true,
-        InjectionPointCostDetail::StaticCost { scope: _, cost } => cost > 0,
-        InjectionPointCostDetail::DynamicCost { .. } => true,
-    });
+    let needs_metering = |point: &InjectionPoint| {
+        metering_type == MeteringType::New
+            && match point.cost_detail {
+                InjectionPointCostDetail::StaticCost {
+                    scope: Scope::ReentrantBlockStart,
+                    cost: _,
+                } => true,
+                InjectionPointCostDetail::StaticCost { scope: _, cost } => cost > 0,
+                InjectionPointCostDetail::DynamicCost { .. } => true,
+            }
+    };
+    let code_len = code.len();
+    let needs_coverage = |point: &InjectionPoint| {
+        coverage_hit_fn.is_some()
+            // There is no block after the final `end` of the function.
+            && point.position < code_len
+            && matches!(
+                point.cost_detail,
+                InjectionPointCostDetail::StaticCost { .. }
+            )
+    };
+    let points = points
+        .iter()
+        .filter(|point| needs_metering(point) || needs_coverage(point));
     let orig_elems = code;
     let mut elems: Vec<Operator> = Vec::new();
     let mut last_injection_position = 0;
+    let mut coverage_blocks = Vec::new();
 
     use Operator::*;
 
     for point in points {
         elems.extend_from_slice(&orig_elems[last_injection_position..point.position]);
         match point.cost_detail {
+            _ if !needs_metering(point) => (),
             InjectionPointCostDetail::StaticCost { scope, cost } => {
                 elems.extend_from_slice(&[
                     GlobalGet {
@@ -1557,10 +1813,17 @@ fn inject_metering(
                 }
             }
         }
+        if let (true, Some(coverage_hit_fn)) = (needs_coverage(point), coverage_hit_fn) {
+            elems.push(Call {
+                function_index: coverage_hit_fn,
+            });
+            coverage_blocks.push(point.position as u32);
+        }
         last_injection_position = point.position;
     }
     elems.extend_from_slice(&orig_elems[last_injection_position..]);
     *orig_elems = elems;
+    coverage_blocks
 }
 
 // This function adds mem barrier writes, assuming that arguments
@@ -1,4 +1,5 @@
 pub mod host_memory;
+mod instruction_profiler;
 mod signal_stack;
 mod system_api;
 pub mod system_api_complexity;
@@ -20,7 +21,8 @@ use wasmtime::{
 pub use host_memory::WasmtimeMemoryCreator;
 use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_interfaces::execution_environment::{
-    CanisterBacktrace, HypervisorError, HypervisorResult, InstanceStats, SystemApi, TrapCode,
+    BlockCoverage, CanisterBacktrace, HypervisorError, HypervisorResult, InstanceStats,
+    InstructionProfile, SystemApi, TrapCode,
 };
 use ic_logger::{debug, error, fatal, ReplicaLogger};
 use ic_replicated_state::{
@@ -33,6 +35,7 @@ use ic_types::{
     CanisterId, NumInstructions, NumOsPages, MAX_STABLE_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::{BinaryEncodedWasm, WasmEngineError};
+pub use instruction_profiler::InstructionProfiler;
 use memory_tracker::{DirtyPageTracking, PageBitmap, SigsegvMemoryTracker};
 use signal_stack::WasmtimeSignalStack;
 
@@ -420,6 +423,14 @@ impl WasmtimeEmbedder {
                     .table_elements(MAX_STORE_TABLE_ELEMENTS)
                     .build(),
                 canister_backtrace: self.config.feature_flags.canister_backtrace,
+                instruction_profiler: match self.config.feature_flags.instruction_profiling {
+                    FlagStatus::Enabled => Some(InstructionProfiler::default()),
+                    FlagStatus::Disabled => None,
+                },
+                coverage: match self.config.feature_flags.wasm_coverage {
+                    FlagStatus::Enabled => Some(BlockCoverage::default()),
+                    FlagStatus::Disabled => None,
+                },
             },
         );
         store.limiter(|state| &mut state.limits);
@@ -738,6 +749,12 @@ pub struct StoreData {
     pub num_stable_dirty_pages_from_non_native_writes: NumOsPages,
     pub limits: StoreLimits,
     pub canister_backtrace: FlagStatus,
+    /// Collects the instruction profile of the message if instruction
+    /// profiling is enabled.
+    pub instruction_profiler: Option<InstructionProfiler>,
+    /// Collects the executed blocks of the message if Wasm coverage is
+    /// enabled.
+    pub coverage: Option<BlockCoverage>,
 }
 
 impl StoreData {
@@ -1246,6 +1263,24 @@ impl WasmtimeInstance {
         instruction_counter
     }
 
+    /// Returns the instruction profile of the message if instruction profiling
+    /// is enabled. Must be called at most once, after the execution finished.
+    pub fn take_instruction_profile(&mut self) -> Option<InstructionProfile> {
+        let instruction_counter = self.instruction_counter();
+        let store_data = self.store.data_mut();
+        let profiler = store_data.instruction_profiler.take()?;
+        let instructions_executed = store_data
+            .system_api()
+            .ok()?
+            .message_instructions_executed(instruction_counter);
+        Some(profiler.finish(instructions_executed.get()))
+    }
+
+    /// Returns the blocks executed by the message if Wasm coverage is enabled.
+    pub fn take_coverage(&mut self) -> Option<BlockCoverage> {
+        self.store.data_mut().coverage.take()
+    }
+
     /// Returns the heap size.
     /// Result is guaranteed to fit in a `u32`.
     pub fn heap_size(&mut self, canister_memory_type: CanisterMemoryType) -> NumWasmPages {
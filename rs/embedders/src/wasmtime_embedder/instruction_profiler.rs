@@ -0,0 +1,86 @@
+//! Host side of the instruction profiling.
+//!
+//! If instruction profiling is enabled, the instrumentation wraps every
+//! function of the canister module so that the host is notified whenever a
+//! function is entered or exited. The profiler keeps track of the current call
+//! stack and attributes the instructions executed between two such events to
+//! the stack that was active in between.
+
+use ic_interfaces::execution_environment::InstructionProfile;
+
+#[derive(Default)]
+pub struct InstructionProfiler {
+    /// The indices of the functions on the call stack, outermost first.
+    stack: Vec<u32>,
+    /// The number of instructions executed by the message at the last event.
+    last_instructions_executed: u64,
+    profile: InstructionProfile,
+}
+
+impl InstructionProfiler {
+    /// Records that the function with the given index was entered after the
+    /// message executed `instructions_executed` instructions.
+    pub fn enter(&mut self, function_index: u32, instructions_executed: u64) {
+        self.attribute(instructions_executed);
+        self.stack.push(function_index);
+    }
+
+    /// Records that the function with the given index returned after the
+    /// message executed `instructions_executed` instructions.
+    pub fn exit(&mut self, function_index: u32, instructions_executed: u64) {
+        self.attribute(instructions_executed);
+        let popped = self.stack.pop();
+        debug_assert_eq!(popped, Some(function_index));
+    }
+
+    /// Returns the profile of the message. If the execution trapped, the
+    /// remaining instructions are attributed to the stack at the trap.
+    pub fn finish(mut self, instructions_executed: u64) -> InstructionProfile {
+        self.attribute(instructions_executed);
+        self.profile
+    }
+
+    /// Attributes the instructions executed since the last event to the
+    /// current call stack.
+    fn attribute(&mut self, instructions_executed: u64) {
+        let delta = instructions_executed.saturating_sub(self.last_instructions_executed);
+        self.last_instructions_executed =
+            self.last_instructions_executed.max(instructions_executed);
+        if delta > 0 && !self.stack.is_empty() {
+            *self.profile.stacks.entry(self.stack.clone()).or_default() += delta;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instructions_are_attributed_to_innermost_function() {
+        let mut profiler = InstructionProfiler::default();
+        profiler.enter(1, 0);
+        profiler.enter(2, 10);
+        profiler.exit(2, 25);
+        profiler.enter(3, 30);
+        profiler.exit(3, 31);
+        profiler.exit(1, 40);
+        let profile = profiler.finish(40);
+        assert_eq!(
+            profile.stacks.into_iter().collect::<Vec<_>>(),
+            vec![(vec![1], 24), (vec![1, 2], 15), (vec![1, 3], 1)]
+        );
+    }
+
+    #[test]
+    fn remaining_instructions_are_attributed_to_trapping_stack() {
+        let mut profiler = InstructionProfiler::default();
+        profiler.enter(1, 5);
+        profiler.enter(2, 10);
+        let profile = profiler.finish(17);
+        assert_eq!(
+            profile.stacks.into_iter().collect::<Vec<_>>(),
+            vec![(vec![1], 5), (vec![1, 2], 7)]
+        );
+    }
+}
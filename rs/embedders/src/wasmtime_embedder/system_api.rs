@@ -286,6 +286,18 @@ fn ic0_performance_counter_helper(
     }
 }
 
+/// Returns the number of instructions executed by the current message so far,
+/// including the instructions of previous slices.
+fn message_instructions_executed(caller: &mut Caller<'_, StoreData>) -> HypervisorResult<u64> {
+    let num_instructions_global = get_num_instructions_global(caller)?;
+    let instruction_counter = load_value(&num_instructions_global, caller)?;
+    Ok(caller
+        .data()
+        .system_api()?
+        .message_instructions_executed(instruction_counter)
+        .get())
+}
+
 pub(crate) fn syscalls<
     I: TryInto<usize>
         + TryInto<u64>
@@ -1035,6 +1047,44 @@ pub(crate) fn syscalls<
         })
         .unwrap();
 
+    linker
+        .func_wrap("__", "profile_enter", {
+            move |mut caller: Caller<'_, StoreData>, function_index: u32| -> Result<(), _> {
+                with_error_handling(&mut caller, |c| {
+                    let instructions_executed = message_instructions_executed(c)?;
+                    if let Some(profiler) = c.data_mut().instruction_profiler.as_mut() {
+                        profiler.enter(function_index, instructions_executed);
+                    }
+                    Ok(())
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("__", "profile_exit", {
+            move |mut caller: Caller<'_, StoreData>, function_index: u32| -> Result<(), _> {
+                with_error_handling(&mut caller, |c| {
+                    let instructions_executed = message_instructions_executed(c)?;
+                    if let Some(profiler) = c.data_mut().instruction_profiler.as_mut() {
+                        profiler.exit(function_index, instructions_executed);
+                    }
+                    Ok(())
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("__", "coverage_hit", {
+            move |mut caller: Caller<'_, StoreData>, function_index: u32, instruction_index: u32| {
+                if let Some(coverage) = caller.data_mut().coverage.as_mut() {
+                    coverage.blocks.insert((function_index, instruction_index));
+                }
+            }
+        })
+        .unwrap();
+
     match main_memory_type {
         WasmMemoryType::Wasm32 => {
             linker
@@ -270,6 +270,44 @@ fn test_exports_only_reserved_symbols() {
     }
 }
 
+#[test]
+#[allow(clippy::field_reassign_with_default)]
+fn wasm_coverage_does_not_change_compilation_cost() {
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func $branch (param $x i32) (result i32)
+                (if (result i32) (local.get $x)
+                    (then (i32.const 1))
+                    (else (i32.const 2))
+                )
+            )
+            (func (export "canister_update test")
+                (drop (call $branch (i32.const 1)))
+            )
+        )"#,
+    )
+    .map(BinaryEncodedWasm::new)
+    .unwrap();
+
+    let instrument = |wasm_coverage| {
+        let mut config = EmbeddersConfig::default();
+        config.feature_flags.wasm_coverage = wasm_coverage;
+        validate_and_instrument_for_testing(&WasmtimeEmbedder::new(config, no_op_logger()), &wasm)
+            .unwrap()
+            .1
+    };
+    let without_coverage = instrument(FlagStatus::Disabled);
+    let with_coverage = instrument(FlagStatus::Enabled);
+
+    // The coverage calls are injected, but not accounted for.
+    assert!(with_coverage.binary.as_slice().len() > without_coverage.binary.as_slice().len());
+    assert_eq!(
+        without_coverage.compilation_cost,
+        with_coverage.compilation_cost
+    );
+}
+
 fn instr_used(instance: &mut WasmtimeInstance) -> u64 {
     let instruction_counter = instance.instruction_counter();
     let system_api = instance.store_data().system_api().unwrap();
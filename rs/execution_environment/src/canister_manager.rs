@@ -14,7 +14,8 @@ use crate::{
 use ic_base_types::NumSeconds;
 use ic_config::embedders::Config as EmbeddersConfig;
 use ic_config::{
-    execution_environment::MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER, flag_status::FlagStatus,
+    execution_environment::{MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER, MAX_SNAPSHOT_DATA_CHUNK_SIZE},
+    flag_status::FlagStatus,
 };
 use ic_cycles_account_manager::WasmExecutionMode;
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
@@ -25,27 +26,32 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_management_canister_types::{
-    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterSnapshotResponse,
-    CanisterStatusResultV2, CanisterStatusType, ChunkHash, InstallChunkedCodeArgs,
-    InstallCodeArgsV2, Method as Ic00Method, StoredChunksReply, UploadChunkReply,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallModeV2, CanisterSnapshotDataKind,
+    CanisterSnapshotDataOffset, CanisterSnapshotResponse, CanisterStatusResultV2,
+    CanisterStatusType, ChunkHash, Global as Ic00Global, InstallChunkedCodeArgs, InstallCodeArgsV2,
+    Method as Ic00Method, ReadCanisterSnapshotDataResponse, ReadCanisterSnapshotMetadataResponse,
+    StoredChunksReply, UploadCanisterSnapshotMetadataArgs, UploadChunkReply,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_snapshots::{CanisterSnapshot, CanisterSnapshotError},
+    canister_snapshots::{
+        CanisterSnapshot, CanisterSnapshotError, ExecutionStateSnapshot, PageMemory,
+    },
     canister_state::{
-        execution_state::Memory,
+        execution_state::{Global, Memory},
         system_state::{
             wasm_chunk_store::{self, WasmChunkStore},
             CyclesUseCase, ReservationError,
         },
-        NextExecution,
+        NextExecution, WASM_PAGE_SIZE_IN_BYTES,
     },
     metadata_state::subnet_call_context_manager::InstallCodeCallId,
-    page_map::PageAllocatorFileDescriptor,
-    CallOrigin, CanisterState, NetworkTopology, ReplicatedState, SchedulerState, SystemState,
+    page_map::{Buffer, PageAllocatorFileDescriptor},
+    CallOrigin, CanisterState, NetworkTopology, NumWasmPages, PageMap, ReplicatedState,
+    SchedulerState, SystemState,
 };
-use ic_system_api::ExecutionParameters;
+use ic_system_api::{ExecutionParameters, CERTIFIED_DATA_MAX_LENGTH};
 use ic_types::{
     ingress::{IngressState, IngressStatus},
     messages::{
@@ -55,7 +61,7 @@ use ic_types::{
     nominal_cycles::NominalCycles,
     CanisterId, CanisterTimer, ComputeAllocation, Cycles, InvalidComputeAllocationError,
     InvalidMemoryAllocationError, MemoryAllocation, NumBytes, NumInstructions, PrincipalId,
-    SnapshotId, SubnetId, Time,
+    SnapshotId, SubnetId, Time, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use ic_wasm_transform::Module;
 use ic_wasm_types::{doc_ref, AsErrorHelp, CanisterModule, ErrorHelp, WasmHash};
@@ -502,7 +508,11 @@ impl CanisterManager {
             | Ok(Ic00Method::TakeCanisterSnapshot)
             | Ok(Ic00Method::LoadCanisterSnapshot)
             | Ok(Ic00Method::ListCanisterSnapshots)
-            | Ok(Ic00Method::DeleteCanisterSnapshot) => {
+            | Ok(Ic00Method::DeleteCanisterSnapshot)
+            | Ok(Ic00Method::ReadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::ReadCanisterSnapshotData)
+            | Ok(Ic00Method::UploadCanisterSnapshotMetadata)
+            | Ok(Ic00Method::UploadCanisterSnapshotData) => {
                 match effective_canister_id {
                     Some(canister_id) => {
                         let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
@@ -1208,6 +1218,19 @@ impl CanisterManager {
                 .total_query_stats
                 .egress_payload_size,
             wasm_memory_limit.map(|x| x.get()),
+            canister
+                .system_state
+                .global_timer
+                .to_nanos_since_unix_epoch(),
+            canister
+                .scheduler_state
+                .last_heartbeat_execution_round
+                .map(|round| round.get()),
+            canister
+                .scheduler_state
+                .last_global_timer_execution_round
+                .map(|round| round.get()),
+            canister.scheduler_state.task_skip_reason.map(Into::into),
         ))
     }
 
@@ -1801,34 +1824,95 @@ impl CanisterManager {
             return (Err(err), NumInstructions::new(0));
         };
 
-        let replace_snapshot_size = match replace_snapshot {
+        let replace_snapshot_size =
+            match self.validate_replace_snapshot(canister, replace_snapshot, state) {
+                Ok(size) => size,
+                Err(err) => return (Err(err), NumInstructions::new(0)),
+            };
+
+        if let Err(err) = self.check_heap_delta_rate_limit(canister) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        let new_snapshot_size = canister.snapshot_size_bytes();
+        if let Err(err) = self.reserve_snapshot_memory(
+            canister,
+            new_snapshot_size,
+            replace_snapshot_size,
+            round_limits,
+            resource_saturation,
+            subnet_size,
+        ) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        // Charge for taking a snapshot of the canister.
+        let instructions = self
+            .config
+            .canister_snapshot_baseline_instructions
+            .saturating_add(&new_snapshot_size.get().into());
+
+        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
+            &sender,
+            canister,
+            instructions,
+            subnet_size,
+            // For the `take_canister_snapshot` operation, it does not matter if this is a Wasm64 or Wasm32 module
+            // since the number of instructions charged depends on constant set fee and snapshot size
+            // and Wasm64 does not bring any additional overhead for this operation.
+            // The only overhead is during execution time.
+            WasmExecutionMode::Wasm32,
+        ) {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
+                0.into(),
+            );
+        };
+
+        // Create new snapshot.
+        let new_snapshot = match CanisterSnapshot::from_canister(canister, state.time())
+            .map_err(CanisterManagerError::from)
+        {
+            Ok(s) => s,
+            Err(err) => return (Err(err), instructions),
+        };
+
+        let snapshot_id = self.make_room_for_snapshot(
+            canister,
+            replace_snapshot.map(|snapshot_id| (snapshot_id, replace_snapshot_size)),
+            &new_snapshot,
+            state,
+            round_limits,
+        );
+        state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(new_snapshot));
+        (
+            Ok(CanisterSnapshotResponse::new(
+                &snapshot_id,
+                state.time().as_nanos_since_unix_epoch(),
+                new_snapshot_size,
+            )),
+            instructions,
+        )
+    }
+
+    /// Checks that the snapshot to be replaced by a new one exists and belongs
+    /// to the canister, or that the canister can have one more snapshot if
+    /// there is nothing to replace.
+    ///
+    /// Returns the size of the snapshot that is going to be replaced.
+    fn validate_replace_snapshot(
+        &self,
+        canister: &CanisterState,
+        replace_snapshot: Option<SnapshotId>,
+        state: &ReplicatedState,
+    ) -> Result<NumBytes, CanisterManagerError> {
+        match replace_snapshot {
             // Check that replace snapshot ID exists if provided.
             Some(replace_snapshot) => {
-                match state.canister_snapshots.get(replace_snapshot) {
-                    None => {
-                        // If not found, the operation fails due to invalid parameters.
-                        return (
-                            Err(CanisterManagerError::CanisterSnapshotNotFound {
-                                canister_id: canister.canister_id(),
-                                snapshot_id: replace_snapshot,
-                            }),
-                            NumInstructions::new(0),
-                        );
-                    }
-                    Some(snapshot) => {
-                        // Verify the provided replacement snapshot belongs to this canister.
-                        if snapshot.canister_id() != canister.canister_id() {
-                            return (
-                                Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
-                                    canister_id: canister.canister_id(),
-                                    snapshot_id: replace_snapshot,
-                                }),
-                                NumInstructions::new(0),
-                            );
-                        }
-                        snapshot.size()
-                    }
-                }
+                let snapshot = get_canister_snapshot(canister, replace_snapshot, state)?;
+                Ok(snapshot.size())
             }
             // No replace snapshot ID provided, check whether the maximum number of snapshots
             // has been reached.
@@ -1838,32 +1922,43 @@ impl CanisterManager {
                     .count_by_canister(&canister.canister_id())
                     >= MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER
                 {
-                    return (
-                        Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
-                            canister_id: canister.canister_id(),
-                            limit: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
-                        }),
-                        NumInstructions::new(0),
-                    );
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id: canister.canister_id(),
+                        limit: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
+                    });
                 }
-                0.into()
+                Ok(0.into())
             }
-        };
+        }
+    }
 
+    fn check_heap_delta_rate_limit(
+        &self,
+        canister: &CanisterState,
+    ) -> Result<(), CanisterManagerError> {
         if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled
             && canister.scheduler_state.heap_delta_debit >= self.config.heap_delta_rate_limit
         {
-            return (
-                Err(CanisterManagerError::CanisterHeapDeltaRateLimited {
-                    canister_id: canister.canister_id(),
-                    value: canister.scheduler_state.heap_delta_debit,
-                    limit: self.config.heap_delta_rate_limit,
-                }),
-                NumInstructions::new(0),
-            );
+            return Err(CanisterManagerError::CanisterHeapDeltaRateLimited {
+                canister_id: canister.canister_id(),
+                value: canister.scheduler_state.heap_delta_debit,
+                limit: self.config.heap_delta_rate_limit,
+            });
         }
+        Ok(())
+    }
 
-        let new_snapshot_size = canister.snapshot_size_bytes();
+    /// Checks that the canister and the subnet can afford a new snapshot of
+    /// the given size and reserves cycles for it if needed.
+    fn reserve_snapshot_memory(
+        &self,
+        canister: &mut CanisterState,
+        new_snapshot_size: NumBytes,
+        replace_snapshot_size: NumBytes,
+        round_limits: &RoundLimits,
+        resource_saturation: &ResourceSaturation,
+        subnet_size: usize,
+    ) -> Result<(), CanisterManagerError> {
         let new_snapshot_increase = NumBytes::from(
             new_snapshot_size
                 .get()
@@ -1877,120 +1972,91 @@ impl CanisterManager {
                 .saturating_sub(replace_snapshot_size.get()),
         );
 
-        {
-            // Run the following checks on memory usage and return an error
-            // if any fails:
-            // 1. Check new usage will not freeze canister
-            // 2. Check subnet has available memory
-            // 3. Reserve cycles on canister
-            // 4. Actually deduct memory from subnet (asserting it won't fail)
-
-            // Calculate if any cycles will need to be reserved.
-            let reservation_cycles = self.cycles_account_manager.storage_reservation_cycles(
-                new_snapshot_increase,
-                resource_saturation,
-                subnet_size,
-            );
+        // Run the following checks on memory usage and return an error
+        // if any fails:
+        // 1. Check new usage will not freeze canister
+        // 2. Check subnet has available memory
+        // 3. Reserve cycles on canister
+        // The memory is actually deducted from the subnet once the snapshot is created.
+
+        // Calculate if any cycles will need to be reserved.
+        let reservation_cycles = self.cycles_account_manager.storage_reservation_cycles(
+            new_snapshot_increase,
+            resource_saturation,
+            subnet_size,
+        );
 
-            // Memory usage will increase by the snapshot size.
-            // Check that it doesn't bump the canister over the freezing threshold.
-            let threshold = self.cycles_account_manager.freeze_threshold_cycles(
-                canister.system_state.freeze_threshold,
-                canister.memory_allocation(),
-                new_memory_usage,
-                canister.message_memory_usage(),
-                canister.compute_allocation(),
-                subnet_size,
-                canister.system_state.reserved_balance(),
-            );
+        // Memory usage will increase by the snapshot size.
+        // Check that it doesn't bump the canister over the freezing threshold.
+        let threshold = self.cycles_account_manager.freeze_threshold_cycles(
+            canister.system_state.freeze_threshold,
+            canister.memory_allocation(),
+            new_memory_usage,
+            canister.message_memory_usage(),
+            canister.compute_allocation(),
+            subnet_size,
+            canister.system_state.reserved_balance(),
+        );
 
-            if canister.system_state.balance() < threshold + reservation_cycles {
-                return (
-                    Err(CanisterManagerError::InsufficientCyclesInMemoryGrow {
+        if canister.system_state.balance() < threshold + reservation_cycles {
+            return Err(CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                bytes: new_snapshot_increase,
+                available: canister.system_state.balance(),
+                threshold,
+            });
+        }
+        // Verify that the subnet has enough memory for a new snapshot.
+        round_limits
+            .subnet_available_memory
+            .check_available_memory(new_snapshot_increase, NumBytes::from(0), NumBytes::from(0))
+            .map_err(
+                |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                    requested: new_snapshot_increase,
+                    available: NumBytes::from(
+                        round_limits
+                            .subnet_available_memory
+                            .get_execution_memory()
+                            .max(0) as u64,
+                    ),
+                },
+            )?;
+        // Reserve needed cycles if the subnet is becoming saturated.
+        canister
+            .system_state
+            .reserve_cycles(reservation_cycles)
+            .map_err(|err| match err {
+                ReservationError::InsufficientCycles {
+                    requested,
+                    available,
+                } => CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                    bytes: new_snapshot_increase,
+                    available,
+                    threshold: requested,
+                },
+                ReservationError::ReservedLimitExceed { requested, limit } => {
+                    CanisterManagerError::ReservedCyclesLimitExceededInMemoryGrow {
                         bytes: new_snapshot_increase,
-                        available: canister.system_state.balance(),
-                        threshold,
-                    }),
-                    NumInstructions::new(0),
-                );
-            }
-            // Verify that the subnet has enough memory for a new snapshot.
-            if let Err(err) = round_limits
-                .subnet_available_memory
-                .check_available_memory(new_snapshot_increase, NumBytes::from(0), NumBytes::from(0))
-                .map_err(
-                    |_| CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
-                        requested: new_snapshot_increase,
-                        available: NumBytes::from(
-                            round_limits
-                                .subnet_available_memory
-                                .get_execution_memory()
-                                .max(0) as u64,
-                        ),
-                    },
-                )
-            {
-                return (Err(err), NumInstructions::new(0));
-            };
-            // Reserve needed cycles if the subnet is becoming saturated.
-            if let Err(err) = canister
-                .system_state
-                .reserve_cycles(reservation_cycles)
-                .map_err(|err| match err {
-                    ReservationError::InsufficientCycles {
                         requested,
-                        available,
-                    } => CanisterManagerError::InsufficientCyclesInMemoryGrow {
-                        bytes: new_snapshot_increase,
-                        available,
-                        threshold: requested,
-                    },
-                    ReservationError::ReservedLimitExceed { requested, limit } => {
-                        CanisterManagerError::ReservedCyclesLimitExceededInMemoryGrow {
-                            bytes: new_snapshot_increase,
-                            requested,
-                            limit,
-                        }
+                        limit,
                     }
-                })
-            {
-                return (Err(err), NumInstructions::new(0));
-            };
-        }
-
-        // Charge for taking a snapshot of the canister.
-        let instructions = self
-            .config
-            .canister_snapshot_baseline_instructions
-            .saturating_add(&new_snapshot_size.get().into());
-
-        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
-            &sender,
-            canister,
-            instructions,
-            subnet_size,
-            // For the `take_canister_snapshot` operation, it does not matter if this is a Wasm64 or Wasm32 module
-            // since the number of instructions charged depends on constant set fee and snapshot size
-            // and Wasm64 does not bring any additional overhead for this operation.
-            // The only overhead is during execution time.
-            WasmExecutionMode::Wasm32,
-        ) {
-            return (
-                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
-                0.into(),
-            );
-        };
-
-        // Create new snapshot.
-        let new_snapshot = match CanisterSnapshot::from_canister(canister, state.time())
-            .map_err(CanisterManagerError::from)
-        {
-            Ok(s) => s,
-            Err(err) => return (Err(err), instructions),
-        };
+                }
+            })
+    }
 
+    /// Deletes the snapshot that is replaced, if any, and accounts for the
+    /// memory and heap delta of the new snapshot.
+    ///
+    /// Returns the ID under which the new snapshot must be stored.
+    fn make_room_for_snapshot(
+        &self,
+        canister: &mut CanisterState,
+        replace_snapshot: Option<(SnapshotId, NumBytes)>,
+        new_snapshot: &CanisterSnapshot,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> SnapshotId {
         // Delete old snapshot identified by `replace_snapshot` ID.
-        if let Some(replace_snapshot) = replace_snapshot {
+        if let Some((replace_snapshot, replace_snapshot_size)) = replace_snapshot {
             state.canister_snapshots.remove(replace_snapshot);
             canister.system_state.snapshots_memory_usage = canister
                 .system_state
@@ -2015,7 +2081,7 @@ impl CanisterManager {
         // Actually deduct memory from the subnet. It's safe to unwrap
         // here because we already checked the available memory above.
         round_limits.subnet_available_memory
-            .try_decrement(new_snapshot_size, NumBytes::from(0), NumBytes::from(0))
+            .try_decrement(new_snapshot.size(), NumBytes::from(0), NumBytes::from(0))
             .expect("Error: Cannot fail to decrement SubnetAvailableMemory after checking for availability");
 
         if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled {
@@ -2029,23 +2095,11 @@ impl CanisterManager {
             .heap_delta_estimate
             .saturating_add(&new_snapshot.heap_delta());
 
-        let snapshot_id =
-            SnapshotId::from((canister.canister_id(), canister.new_local_snapshot_id()));
-        state
-            .canister_snapshots
-            .push(snapshot_id, Arc::new(new_snapshot));
         canister.system_state.snapshots_memory_usage = canister
             .system_state
             .snapshots_memory_usage
-            .saturating_add(&new_snapshot_size);
-        (
-            Ok(CanisterSnapshotResponse::new(
-                &snapshot_id,
-                state.time().as_nanos_since_unix_epoch(),
-                new_snapshot_size,
-            )),
-            instructions,
-        )
+            .saturating_add(&new_snapshot.size());
+        SnapshotId::from((canister.canister_id(), canister.new_local_snapshot_id()))
     }
 
     pub(crate) fn load_canister_snapshot(
@@ -2078,6 +2132,13 @@ impl CanisterManager {
             );
         }
 
+        // The Wasm module of an uploaded snapshot is built (once) before loading it.
+        if let Some(snapshot) = state.canister_snapshots.get_mut(snapshot_id) {
+            if snapshot.canister_id() == canister_id && snapshot.has_wasm_module_upload() {
+                Arc::make_mut(snapshot).finish_wasm_module_upload();
+            }
+        }
+
         // Check that snapshot ID exists.
         let snapshot: &Arc<CanisterSnapshot> = match state.canister_snapshots.get(snapshot_id) {
             None => {
@@ -2174,6 +2235,25 @@ impl CanisterManager {
                 }
             };
 
+            // Uploaded snapshots may declare globals that do not match the module.
+            if !globals_match(
+                &new_execution_state.exported_globals,
+                &execution_snapshot.exported_globals,
+            ) {
+                return (
+                    Err(CanisterManagerError::CanisterSnapshotInconsistent {
+                        message: format!(
+                            "the Wasm module of snapshot {} exports {} globals, \
+                            which do not match the {} globals stored in the snapshot",
+                            snapshot_id,
+                            new_execution_state.exported_globals.len(),
+                            execution_snapshot.exported_globals.len(),
+                        ),
+                    }),
+                    instructions_used,
+                );
+            }
+
             new_execution_state.exported_globals = execution_snapshot.exported_globals.clone();
             new_execution_state.stable_memory = Memory::from(&execution_snapshot.stable_memory);
             new_execution_state.wasm_memory = Memory::from(&execution_snapshot.wasm_memory);
@@ -2333,6 +2413,520 @@ impl CanisterManager {
         );
         Ok(())
     }
+
+    /// Returns the metadata of the given canister snapshot, which is needed
+    /// to download the snapshot data and to upload it again.
+    ///
+    /// Reading the metadata of a canister snapshot can only be initiated by the controllers.
+    pub(crate) fn read_canister_snapshot_metadata(
+        &self,
+        sender: PrincipalId,
+        canister: &CanisterState,
+        snapshot_id: SnapshotId,
+        state: &ReplicatedState,
+    ) -> Result<ReadCanisterSnapshotMetadataResponse, CanisterManagerError> {
+        // Check sender is a controller.
+        validate_controller(canister, &sender)?;
+
+        let snapshot = get_canister_snapshot(canister, snapshot_id, state)?;
+        Ok(ReadCanisterSnapshotMetadataResponse {
+            taken_at_timestamp: snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+            canister_version: snapshot.canister_version(),
+            wasm_module_size: snapshot.wasm_module_len() as u64,
+            exported_globals: snapshot
+                .exported_globals()
+                .iter()
+                .map(Ic00Global::from)
+                .collect(),
+            wasm_memory_size: page_memory_size_bytes(snapshot.wasm_memory()),
+            stable_memory_size: page_memory_size_bytes(snapshot.stable_memory()),
+            wasm_chunk_store: snapshot
+                .chunk_store()
+                .keys()
+                .map(|hash| ChunkHash {
+                    hash: hash.to_vec(),
+                })
+                .collect(),
+            certified_data: snapshot.certified_data().clone(),
+        })
+    }
+
+    /// Returns a part of the given canister snapshot.
+    ///
+    /// Reading canister snapshot data can only be initiated by the controllers
+    /// and is charged like uploading a chunk of the same size.
+    pub(crate) fn read_canister_snapshot_data(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        snapshot_id: SnapshotId,
+        kind: &CanisterSnapshotDataKind,
+        state: &ReplicatedState,
+    ) -> (
+        Result<ReadCanisterSnapshotDataResponse, CanisterManagerError>,
+        NumInstructions,
+    ) {
+        // Check sender is a controller.
+        if let Err(err) = validate_controller(canister, &sender) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        let snapshot = match get_canister_snapshot(canister, snapshot_id, state) {
+            Ok(snapshot) => snapshot,
+            Err(err) => return (Err(err), NumInstructions::new(0)),
+        };
+
+        if let CanisterSnapshotDataKind::WasmModule { size, .. }
+        | CanisterSnapshotDataKind::WasmMemory { size, .. }
+        | CanisterSnapshotDataKind::StableMemory { size, .. } = kind
+        {
+            if *size > MAX_SNAPSHOT_DATA_CHUNK_SIZE.get() {
+                return (
+                    Err(CanisterManagerError::CanisterSnapshotDataTooLarge {
+                        size: *size,
+                        limit: MAX_SNAPSHOT_DATA_CHUNK_SIZE.get(),
+                    }),
+                    NumInstructions::new(0),
+                );
+            }
+        }
+
+        let chunk = match kind {
+            CanisterSnapshotDataKind::WasmModule { offset, size } => {
+                validate_snapshot_subslice(*offset, *size, snapshot.wasm_module_len() as u64)
+                    .map(|()| snapshot.read_wasm_module(*offset as usize, *size as usize))
+            }
+            CanisterSnapshotDataKind::WasmMemory { offset, size } => {
+                read_page_memory(snapshot.wasm_memory(), *offset, *size)
+            }
+            CanisterSnapshotDataKind::StableMemory { offset, size } => {
+                read_page_memory(snapshot.stable_memory(), *offset, *size)
+            }
+            CanisterSnapshotDataKind::WasmChunk { hash } => {
+                read_wasm_chunk(snapshot.chunk_store(), hash)
+            }
+        };
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => return (Err(err), NumInstructions::new(0)),
+        };
+
+        let instructions = self
+            .config
+            .upload_wasm_chunk_instructions
+            .saturating_add(&(chunk.len() as u64).into());
+        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
+            &sender,
+            canister,
+            instructions,
+            subnet_size,
+            // Reading snapshot data does not execute any Wasm code.
+            WasmExecutionMode::Wasm32,
+        ) {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
+                0.into(),
+            );
+        };
+
+        (
+            Ok(ReadCanisterSnapshotDataResponse::new(chunk)),
+            instructions,
+        )
+    }
+
+    /// Creates a new canister snapshot from uploaded metadata. The contents
+    /// of the snapshot are zeroed and have to be written with
+    /// `write_canister_snapshot_data` before the snapshot can be loaded.
+    ///
+    /// The size of the snapshot is fixed by the metadata, so the memory for
+    /// the whole snapshot is reserved and paid for when it is created.
+    /// Creating a canister snapshot from metadata can only be initiated by the controllers.
+    pub(crate) fn create_snapshot_from_metadata(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        args: &UploadCanisterSnapshotMetadataArgs,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        resource_saturation: &ResourceSaturation,
+    ) -> (Result<SnapshotId, CanisterManagerError>, NumInstructions) {
+        // Check sender is a controller.
+        if let Err(err) = validate_controller(canister, &sender) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        let replace_snapshot = args.replace_snapshot();
+        let replace_snapshot_size =
+            match self.validate_replace_snapshot(canister, replace_snapshot, state) {
+                Ok(size) => size,
+                Err(err) => return (Err(err), NumInstructions::new(0)),
+            };
+
+        let new_snapshot_size = match self.validate_snapshot_metadata(args) {
+            Ok(size) => size,
+            Err(err) => return (Err(err), NumInstructions::new(0)),
+        };
+
+        if let Err(err) = self.check_heap_delta_rate_limit(canister) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        if let Err(err) = self.reserve_snapshot_memory(
+            canister,
+            new_snapshot_size,
+            replace_snapshot_size,
+            round_limits,
+            resource_saturation,
+            subnet_size,
+        ) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        let instructions = self.config.canister_snapshot_baseline_instructions;
+        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
+            &sender,
+            canister,
+            instructions,
+            subnet_size,
+            // Creating a snapshot from metadata does not execute any Wasm code.
+            WasmExecutionMode::Wasm32,
+        ) {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
+                0.into(),
+            );
+        };
+
+        let new_page_memory = |size_bytes: u64| PageMemory {
+            page_map: PageMap::new(Arc::clone(&self.fd_factory)),
+            size: NumWasmPages::new((size_bytes / WASM_PAGE_SIZE_IN_BYTES as u64) as usize),
+        };
+        // The Wasm module is uploaded into a buffer of the snapshot (see below)
+        // and is only built once the snapshot is loaded.
+        let execution_snapshot = ExecutionStateSnapshot {
+            wasm_binary: CanisterModule::new(vec![]),
+            exported_globals: args.exported_globals.iter().map(Global::from).collect(),
+            stable_memory: new_page_memory(args.stable_memory_size),
+            wasm_memory: new_page_memory(args.wasm_memory_size),
+        };
+        let mut new_snapshot = CanisterSnapshot::new(
+            canister.canister_id(),
+            state.time(),
+            canister.system_state.canister_version,
+            args.certified_data.clone(),
+            WasmChunkStore::new(Arc::clone(&self.fd_factory)),
+            execution_snapshot,
+            new_snapshot_size,
+        );
+        new_snapshot.start_wasm_module_upload(args.wasm_module_size as usize);
+
+        let snapshot_id = self.make_room_for_snapshot(
+            canister,
+            replace_snapshot.map(|snapshot_id| (snapshot_id, replace_snapshot_size)),
+            &new_snapshot,
+            state,
+            round_limits,
+        );
+        state
+            .canister_snapshots
+            .push_uploaded(snapshot_id, Arc::new(new_snapshot));
+        (Ok(snapshot_id), instructions)
+    }
+
+    /// Validates the metadata of a snapshot to be uploaded and returns the
+    /// size of the new snapshot.
+    fn validate_snapshot_metadata(
+        &self,
+        args: &UploadCanisterSnapshotMetadataArgs,
+    ) -> Result<NumBytes, CanisterManagerError> {
+        let invalid_metadata = |message: String| {
+            Err(CanisterManagerError::CanisterSnapshotInvalidMetadata { message })
+        };
+        if args.wasm_module_size > EmbeddersConfig::new().wasm_max_size.get() {
+            return invalid_metadata(format!(
+                "Wasm module size {} exceeds the limit of {} bytes",
+                args.wasm_module_size,
+                EmbeddersConfig::new().wasm_max_size
+            ));
+        }
+        for (name, size, limit) in [
+            (
+                "Wasm memory",
+                args.wasm_memory_size,
+                MAX_WASM_MEMORY_IN_BYTES,
+            ),
+            (
+                "stable memory",
+                args.stable_memory_size,
+                MAX_STABLE_MEMORY_IN_BYTES,
+            ),
+        ] {
+            if size % WASM_PAGE_SIZE_IN_BYTES as u64 != 0 {
+                return invalid_metadata(format!(
+                    "{} size {} is not a multiple of the Wasm page size",
+                    name, size
+                ));
+            }
+            if size > limit {
+                return invalid_metadata(format!(
+                    "{} size {} exceeds the limit of {} bytes",
+                    name, size, limit
+                ));
+            }
+        }
+        let chunk_store_size = args
+            .wasm_chunk_store_size
+            .saturating_mul(wasm_chunk_store::chunk_size().get());
+        if chunk_store_size > self.config.wasm_chunk_store_max_size.get() {
+            return invalid_metadata(format!(
+                "{} chunks exceed the Wasm chunk store limit of {} bytes",
+                args.wasm_chunk_store_size, self.config.wasm_chunk_store_max_size
+            ));
+        }
+        if args.certified_data.len() > CERTIFIED_DATA_MAX_LENGTH {
+            return invalid_metadata(format!(
+                "certified data of {} bytes exceeds the limit of {} bytes",
+                args.certified_data.len(),
+                CERTIFIED_DATA_MAX_LENGTH
+            ));
+        }
+
+        // Same as `CanisterState::snapshot_size_bytes`, 8 bytes are used per global.
+        Ok(NumBytes::new(
+            args.wasm_module_size
+                + args.wasm_memory_size
+                + args.stable_memory_size
+                + 8 * args.exported_globals.len() as u64
+                + chunk_store_size
+                + args.certified_data.len() as u64,
+        ))
+    }
+
+    /// Writes a part of the given canister snapshot.
+    ///
+    /// Only the existing contents of the snapshot can be overwritten, i.e.
+    /// the size of the snapshot never changes. Wasm chunks can be added
+    /// as long as the chunk store capacity declared in the metadata is not exceeded.
+    /// Writing canister snapshot data can only be initiated by the controllers.
+    pub(crate) fn write_canister_snapshot_data(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister: &mut CanisterState,
+        snapshot_id: SnapshotId,
+        kind: &CanisterSnapshotDataOffset,
+        chunk: &[u8],
+        state: &mut ReplicatedState,
+    ) -> (Result<(), CanisterManagerError>, NumInstructions) {
+        // Check sender is a controller.
+        if let Err(err) = validate_controller(canister, &sender) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        if chunk.len() as u64 > MAX_SNAPSHOT_DATA_CHUNK_SIZE.get() {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotDataTooLarge {
+                    size: chunk.len() as u64,
+                    limit: MAX_SNAPSHOT_DATA_CHUNK_SIZE.get(),
+                }),
+                NumInstructions::new(0),
+            );
+        }
+
+        let snapshot = match get_canister_snapshot(canister, snapshot_id, state) {
+            Ok(snapshot) => snapshot,
+            Err(err) => return (Err(err), NumInstructions::new(0)),
+        };
+        let chunk_len = chunk.len() as u64;
+        let validation = match kind {
+            CanisterSnapshotDataOffset::WasmModule { offset } => {
+                validate_snapshot_subslice(*offset, chunk_len, snapshot.wasm_module_len() as u64)
+            }
+            CanisterSnapshotDataOffset::WasmMemory { offset } => validate_snapshot_subslice(
+                *offset,
+                chunk_len,
+                page_memory_size_bytes(snapshot.wasm_memory()),
+            ),
+            CanisterSnapshotDataOffset::StableMemory { offset } => validate_snapshot_subslice(
+                *offset,
+                chunk_len,
+                page_memory_size_bytes(snapshot.stable_memory()),
+            ),
+            CanisterSnapshotDataOffset::WasmChunk => snapshot
+                .chunk_store()
+                .can_insert_chunk(chunk_store_capacity(snapshot), chunk)
+                .map_err(|message| CanisterManagerError::WasmChunkStoreError { message }),
+        };
+        if let Err(err) = validation.and_then(|()| self.check_heap_delta_rate_limit(canister)) {
+            return (Err(err), NumInstructions::new(0));
+        }
+
+        let instructions = self
+            .config
+            .upload_wasm_chunk_instructions
+            .saturating_add(&chunk_len.into());
+        if let Err(err) = self.cycles_account_manager.consume_cycles_for_instructions(
+            &sender,
+            canister,
+            instructions,
+            subnet_size,
+            // Writing snapshot data does not execute any Wasm code.
+            WasmExecutionMode::Wasm32,
+        ) {
+            return (
+                Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err)),
+                0.into(),
+            );
+        };
+
+        // Unwrapping is safe because the snapshot was found above.
+        let snapshot = Arc::make_mut(state.canister_snapshots.get_mut(snapshot_id).unwrap());
+        let heap_delta_before = snapshot.heap_delta();
+        match kind {
+            CanisterSnapshotDataOffset::WasmModule { offset } => {
+                snapshot.write_wasm_module(*offset as usize, chunk)
+            }
+            CanisterSnapshotDataOffset::WasmMemory { offset } => write_page_memory(
+                &mut snapshot.execution_snapshot_mut().wasm_memory,
+                *offset,
+                chunk,
+            ),
+            CanisterSnapshotDataOffset::StableMemory { offset } => write_page_memory(
+                &mut snapshot.execution_snapshot_mut().stable_memory,
+                *offset,
+                chunk,
+            ),
+            CanisterSnapshotDataOffset::WasmChunk => {
+                let capacity = chunk_store_capacity(snapshot);
+                // Cannot fail because `can_insert_chunk` succeeded above.
+                snapshot
+                    .chunk_store_mut()
+                    .insert_chunk(capacity, chunk)
+                    .expect("Inserting a validated chunk into the snapshot's chunk store failed");
+            }
+        }
+
+        let heap_delta_increase = NumBytes::new(
+            snapshot
+                .heap_delta()
+                .get()
+                .saturating_sub(heap_delta_before.get()),
+        );
+        if self.config.rate_limiting_of_heap_delta == FlagStatus::Enabled {
+            canister.scheduler_state.heap_delta_debit = canister
+                .scheduler_state
+                .heap_delta_debit
+                .saturating_add(&heap_delta_increase);
+        }
+        state.metadata.heap_delta_estimate = state
+            .metadata
+            .heap_delta_estimate
+            .saturating_add(&heap_delta_increase);
+
+        (Ok(()), instructions)
+    }
+}
+
+/// Returns the snapshot identified by `snapshot_id` if it exists and belongs
+/// to the given canister.
+fn get_canister_snapshot<'a>(
+    canister: &CanisterState,
+    snapshot_id: SnapshotId,
+    state: &'a ReplicatedState,
+) -> Result<&'a Arc<CanisterSnapshot>, CanisterManagerError> {
+    match state.canister_snapshots.get(snapshot_id) {
+        // If not found, the operation fails due to invalid parameters.
+        None => Err(CanisterManagerError::CanisterSnapshotNotFound {
+            canister_id: canister.canister_id(),
+            snapshot_id,
+        }),
+        // Verify the provided snapshot ID belongs to this canister.
+        Some(snapshot) if snapshot.canister_id() != canister.canister_id() => {
+            Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                canister_id: canister.canister_id(),
+                snapshot_id,
+            })
+        }
+        Some(snapshot) => Ok(snapshot),
+    }
+}
+
+fn page_memory_size_bytes(memory: &PageMemory) -> u64 {
+    memory.size.get() as u64 * WASM_PAGE_SIZE_IN_BYTES as u64
+}
+
+/// Returns the capacity of the snapshot's chunk store, i.e. the part of the
+/// snapshot size that is not used by its other contents, in whole chunks.
+fn chunk_store_capacity(snapshot: &CanisterSnapshot) -> NumBytes {
+    let other_contents_size = snapshot.wasm_module_len() as u64
+        + page_memory_size_bytes(snapshot.wasm_memory())
+        + page_memory_size_bytes(snapshot.stable_memory())
+        + 8 * snapshot.exported_globals().len() as u64
+        + snapshot.certified_data().len() as u64;
+    let chunk_size = wasm_chunk_store::chunk_size().get();
+    let capacity = snapshot.size().get().saturating_sub(other_contents_size);
+    NumBytes::new(capacity / chunk_size * chunk_size)
+}
+
+/// Checks that `size` bytes starting at `offset` lie within data of length `len`.
+fn validate_snapshot_subslice(
+    offset: u64,
+    size: u64,
+    len: u64,
+) -> Result<(), CanisterManagerError> {
+    match offset.checked_add(size) {
+        Some(end) if end <= len => Ok(()),
+        _ => Err(CanisterManagerError::CanisterSnapshotInvalidSubslice { offset, size, len }),
+    }
+}
+
+fn read_page_memory(
+    memory: &PageMemory,
+    offset: u64,
+    size: u64,
+) -> Result<Vec<u8>, CanisterManagerError> {
+    validate_snapshot_subslice(offset, size, page_memory_size_bytes(memory))?;
+    let mut chunk = vec![0; size as usize];
+    Buffer::new(memory.page_map.clone()).read(&mut chunk, offset as usize);
+    Ok(chunk)
+}
+
+fn write_page_memory(memory: &mut PageMemory, offset: u64, chunk: &[u8]) {
+    let mut buffer = Buffer::new(memory.page_map.clone());
+    buffer.write(chunk, offset as usize);
+    memory.page_map = buffer.into_page_map();
+}
+
+fn read_wasm_chunk(
+    wasm_chunk_store: &WasmChunkStore,
+    hash: &[u8],
+) -> Result<Vec<u8>, CanisterManagerError> {
+    let hash = hash
+        .try_into()
+        .map_err(|_| CanisterManagerError::WasmChunkStoreError {
+            message: "Chunk hash is invalid. The length is not 32".to_string(),
+        })?;
+    let pages = wasm_chunk_store.get_chunk_data(&hash).ok_or_else(|| {
+        CanisterManagerError::WasmChunkStoreError {
+            message: format!("Chunk hash {:?} was not found", &hash[..32]),
+        }
+    })?;
+    Ok(pages.flatten().copied().collect())
+}
+
+/// Returns true if the globals stored in a snapshot can be used to initialize
+/// the globals exported by its Wasm module.
+fn globals_match(module_globals: &[Global], snapshot_globals: &[Global]) -> bool {
+    module_globals.len() == snapshot_globals.len()
+        && module_globals
+            .iter()
+            .zip(snapshot_globals)
+            .all(|(module_global, snapshot_global)| {
+                module_global.type_name() == snapshot_global.type_name()
+            })
 }
 
 #[derive(Eq, PartialEq, Debug)]
@@ -2438,6 +3032,21 @@ pub(crate) enum CanisterManagerError {
         limit: usize,
     },
     CanisterSnapshotNotEnoughCycles(CanisterOutOfCyclesError),
+    CanisterSnapshotInvalidMetadata {
+        message: String,
+    },
+    CanisterSnapshotInvalidSubslice {
+        offset: u64,
+        size: u64,
+        len: u64,
+    },
+    CanisterSnapshotDataTooLarge {
+        size: u64,
+        limit: u64,
+    },
+    CanisterSnapshotInconsistent {
+        message: String,
+    },
     LongExecutionAlreadyInProgress {
         canister_id: CanisterId,
     },
@@ -2608,6 +3217,27 @@ impl AsErrorHelp for CanisterManagerError {
                 suggestion: "".to_string(),
                 doc_link: "".to_string(),
             },
+            CanisterManagerError::CanisterSnapshotInvalidMetadata { .. } => ErrorHelp::UserError {
+                suggestion: "Use the metadata returned by `read_canister_snapshot_metadata` \
+                    for the snapshot that is uploaded."
+                    .to_string(),
+                doc_link: "".to_string(),
+            },
+            CanisterManagerError::CanisterSnapshotInvalidSubslice { .. } => ErrorHelp::UserError {
+                suggestion: "Use `read_canister_snapshot_metadata` to check the sizes of \
+                    the snapshot contents."
+                    .to_string(),
+                doc_link: "".to_string(),
+            },
+            CanisterManagerError::CanisterSnapshotDataTooLarge { .. } => ErrorHelp::UserError {
+                suggestion: "Split the data into smaller chunks.".to_string(),
+                doc_link: "".to_string(),
+            },
+            CanisterManagerError::CanisterSnapshotInconsistent { .. } => ErrorHelp::UserError {
+                suggestion: "Upload the remaining data of the snapshot before loading it."
+                    .to_string(),
+                doc_link: "".to_string(),
+            },
             CanisterManagerError::LongExecutionAlreadyInProgress { .. } => ErrorHelp::UserError {
                 suggestion: "Try waiting for the long execution to complete.".to_string(),
                 doc_link: doc_ref("long-execution-already-in-progress"),
@@ -2922,6 +3552,38 @@ impl From<CanisterManagerError> for UserError {
                     format!("Canister snapshotting failed with `{}`{additional_help}", err),
                 )
             }
+            CanisterSnapshotInvalidMetadata { message } => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!(
+                        "Invalid canister snapshot metadata: {}.{additional_help}", message
+                    )
+                )
+            }
+            CanisterSnapshotInvalidSubslice { offset, size, len } => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!(
+                        "Invalid subslice of canister snapshot data: offset {} and size {} exceed the length {}.{additional_help}", offset, size, len,
+                    )
+                )
+            }
+            CanisterSnapshotDataTooLarge { size, limit } => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!(
+                        "Canister snapshot data of {} bytes exceeds the limit of {} bytes per call.{additional_help}", size, limit,
+                    )
+                )
+            }
+            CanisterSnapshotInconsistent { message } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!(
+                        "Canister snapshot is inconsistent: {}.{additional_help}", message
+                    )
+                )
+            }
             LongExecutionAlreadyInProgress { canister_id } => {
                 Self::new(
                     ErrorCode::CanisterRejectedMessage,
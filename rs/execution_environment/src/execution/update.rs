@@ -18,7 +18,7 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{info, ReplicaLogger};
 use ic_management_canister_types::IC_00;
-use ic_replicated_state::{num_bytes_try_from, CallOrigin, CanisterState};
+use ic_replicated_state::{num_bytes_try_from, CallOrigin, CanisterState, TaskSkipReason};
 use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::messages::{
     CallContextId, CanisterCall, CanisterCallOrTask, CanisterMessage, CanisterMessageOrTask,
@@ -76,6 +76,13 @@ pub fn execute_update(
                     ) {
                         Ok(cycles) => cycles,
                         Err(err) => {
+                            if let CanisterCallOrTask::Task(
+                                CanisterTask::Heartbeat | CanisterTask::GlobalTimer,
+                            ) = call_or_task
+                            {
+                                canister.scheduler_state.task_skip_reason =
+                                    Some(task_skip_reason(&canister, &round, subnet_size));
+                            }
                             return finish_call_with_error(
                                 UserError::new(ErrorCode::CanisterOutOfCycles, err),
                                 canister,
@@ -668,6 +675,29 @@ impl PausedExecution for PausedCallExecution {
     }
 }
 
+/// Returns why a heartbeat or global timer task of the canister could not be
+/// prepaid.
+fn task_skip_reason(
+    canister: &CanisterState,
+    round: &RoundContext,
+    subnet_size: usize,
+) -> TaskSkipReason {
+    let freezing_threshold = round.cycles_account_manager.freeze_threshold_cycles(
+        canister.system_state.freeze_threshold,
+        canister.system_state.memory_allocation,
+        canister.memory_usage(),
+        canister.message_memory_usage(),
+        canister.compute_allocation(),
+        subnet_size,
+        canister.system_state.reserved_balance(),
+    );
+    if canister.system_state.balance() < freezing_threshold {
+        TaskSkipReason::Frozen
+    } else {
+        TaskSkipReason::OutOfCycles
+    }
+}
+
 fn into_message_or_task(call_or_task: CanisterCallOrTask) -> CanisterMessageOrTask {
     match call_or_task {
         CanisterCallOrTask::Call(CanisterCall::Request(r)) => {
@@ -4,12 +4,15 @@ use ic_base_types::NumBytes;
 use ic_config::subnet_config::SubnetConfig;
 use ic_cycles_account_manager::ResourceSaturation;
 use ic_cycles_account_manager::WasmExecutionMode;
-use ic_error_types::{ErrorCode, RejectCode};
+use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_management_canister_types::{
-    self as ic00, CanisterChange, CanisterChangeDetails, CanisterSnapshotResponse,
-    ClearChunkStoreArgs, DeleteCanisterSnapshotArgs, ListCanisterSnapshotArgs,
-    LoadCanisterSnapshotArgs, Method, Payload as Ic00Payload, TakeCanisterSnapshotArgs,
-    UploadChunkArgs,
+    self as ic00, CanisterChange, CanisterChangeDetails, CanisterSnapshotDataKind,
+    CanisterSnapshotDataOffset, CanisterSnapshotResponse, ClearChunkStoreArgs,
+    DeleteCanisterSnapshotArgs, ListCanisterSnapshotArgs, LoadCanisterSnapshotArgs, Method,
+    Payload as Ic00Payload, ReadCanisterSnapshotDataArgs, ReadCanisterSnapshotDataResponse,
+    ReadCanisterSnapshotMetadataArgs, ReadCanisterSnapshotMetadataResponse,
+    TakeCanisterSnapshotArgs, UploadCanisterSnapshotDataArgs, UploadCanisterSnapshotMetadataArgs,
+    UploadCanisterSnapshotMetadataResponse, UploadChunkArgs,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
@@ -1836,6 +1839,434 @@ fn snapshot_must_include_globals() {
     assert_eq!(result, WasmResult::Reply(vec![1, 0, 0, 0]));
 }
 
+const COUNTER_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (import "ic0" "msg_reply_data_append"
+          (func $msg_reply_data_append (param i32 i32)))
+        (func $read_counter
+          (i32.store (i32.const 0) (global.get 0))
+          (call $msg_reply_data_append (i32.const 0) (i32.const 4))
+          (call $msg_reply)
+        )
+        (func $increase_counter
+          (global.set 0 (i32.add (global.get 0) (i32.const 1)))
+          (call $msg_reply)
+        )
+        (memory $memory 1)
+        (export "memory" (memory $memory))
+        (global (export "counter") (mut i32) (i32.const 0))
+        (export "canister_query read_counter" (func $read_counter))
+        (export "canister_update increase_counter" (func $increase_counter))
+    )"#;
+
+fn read_snapshot_data(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    snapshot_id: SnapshotId,
+    kind: CanisterSnapshotDataKind,
+) -> Result<Vec<u8>, UserError> {
+    let args = ReadCanisterSnapshotDataArgs::new(canister_id, snapshot_id, kind);
+    let result = test.subnet_message("read_canister_snapshot_data", args.encode())?;
+    Ok(ReadCanisterSnapshotDataResponse::decode(&result.bytes())
+        .unwrap()
+        .chunk)
+}
+
+fn upload_snapshot_data(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    snapshot_id: SnapshotId,
+    kind: CanisterSnapshotDataOffset,
+    chunk: Vec<u8>,
+) -> Result<(), UserError> {
+    let args = UploadCanisterSnapshotDataArgs::new(canister_id, snapshot_id, kind, chunk);
+    test.subnet_message("upload_canister_snapshot_data", args.encode())
+        .map(|_| ())
+}
+
+#[test]
+fn read_canister_snapshot_metadata_decode_round_trip() {
+    let canister_id = canister_test_id(4);
+    let snapshot_id = SnapshotId::from((canister_id, 6));
+    let args = ReadCanisterSnapshotMetadataArgs::new(canister_id, snapshot_id);
+    let encoded_args = args.encode();
+    assert_eq!(
+        args,
+        ReadCanisterSnapshotMetadataArgs::decode(encoded_args.as_slice()).unwrap()
+    );
+}
+
+#[test]
+fn read_canister_snapshot_metadata_fails_invalid_controller() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(1);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_manual_execution()
+        .with_caller(own_subnet, caller_canister)
+        .build();
+
+    // Create new canister.
+    let canister_id = test
+        .create_canister_with_allocation(Cycles::new(1_000_000_000_000_000), None, None)
+        .unwrap();
+
+    // Inject a read_canister_snapshot_metadata request.
+    let snapshot_id = SnapshotId::from((canister_id, 6));
+    let args = ReadCanisterSnapshotMetadataArgs::new(canister_id, snapshot_id);
+    test.inject_call_to_ic00(
+        Method::ReadCanisterSnapshotMetadata,
+        args.encode(),
+        Cycles::new(1_000_000_000),
+    );
+    test.execute_subnet_message();
+
+    // Reject expected: caller is not a controller of the canister.
+    let (receiver, response) = &get_output_messages(test.state_mut()).pop().unwrap();
+    assert_matches!(response, RequestOrResponse::Response(_));
+    if let RequestOrResponse::Response(res) = response {
+        assert_eq!(res.originator, *receiver);
+        res.response_payload.assert_contains_reject(
+            RejectCode::CanisterError,
+            &format!(
+                "Only the controllers of the canister {} can control it.\n\
+                    Canister's controllers: {}\n\
+                    Sender's ID: {}",
+                canister_id,
+                test.user_id().get(),
+                caller_canister.get(),
+            ),
+        );
+    }
+}
+
+#[test]
+fn read_canister_snapshot_data_fails_invalid_subslice() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_binary(wat::parse_str(COUNTER_WAT).unwrap())
+        .unwrap();
+
+    // Take a snapshot.
+    let args = TakeCanisterSnapshotArgs::new(canister_id, None);
+    let result = test
+        .subnet_message("take_canister_snapshot", args.encode())
+        .unwrap();
+    let snapshot_id = CanisterSnapshotResponse::decode(&result.bytes())
+        .unwrap()
+        .snapshot_id();
+
+    // Reading beyond the end of the Wasm memory fails.
+    let err = read_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataKind::WasmMemory {
+            offset: 65_000,
+            size: 1_000,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InvalidManagementPayload);
+
+    // Offsets that overflow are rejected as well.
+    let err = read_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataKind::WasmModule {
+            offset: u64::MAX,
+            size: 1,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InvalidManagementPayload);
+}
+
+#[test]
+fn upload_canister_snapshot_metadata_fails_invalid_memory_size() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test
+        .canister_from_binary(wat::parse_str(COUNTER_WAT).unwrap())
+        .unwrap();
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_memory_size: 1_000,
+        ..Default::default()
+    };
+    let err = test
+        .subnet_message("upload_canister_snapshot_metadata", args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::InvalidManagementPayload);
+    assert_eq!(
+        test.state()
+            .canister_snapshots
+            .count_by_canister(&canister_id),
+        0
+    );
+}
+
+#[test]
+fn downloaded_canister_snapshot_can_be_uploaded_to_another_canister() {
+    let wasm = wat::parse_str(COUNTER_WAT).unwrap();
+    let mut test = ExecutionTestBuilder::new().build();
+    let source_id = test.canister_from_binary(wasm.clone()).unwrap();
+    let target_id = test.canister_from_binary(wasm).unwrap();
+
+    // Modify the global and the chunk store of the source canister.
+    test.ingress(source_id, "increase_counter", vec![]).unwrap();
+    let upload_args = UploadChunkArgs {
+        canister_id: source_id.into(),
+        chunk: vec![1, 2, 3, 4, 5],
+    };
+    test.subnet_message("upload_chunk", upload_args.encode())
+        .unwrap();
+
+    // Take a snapshot of the source canister and download it.
+    let args = TakeCanisterSnapshotArgs::new(source_id, None);
+    let result = test
+        .subnet_message("take_canister_snapshot", args.encode())
+        .unwrap();
+    let source_snapshot_id = CanisterSnapshotResponse::decode(&result.bytes())
+        .unwrap()
+        .snapshot_id();
+
+    let args = ReadCanisterSnapshotMetadataArgs::new(source_id, source_snapshot_id);
+    let result = test
+        .subnet_message("read_canister_snapshot_metadata", args.encode())
+        .unwrap();
+    let metadata = ReadCanisterSnapshotMetadataResponse::decode(&result.bytes()).unwrap();
+    assert_eq!(metadata.exported_globals, vec![ic00::Global::I32(1)]);
+    assert_eq!(metadata.wasm_chunk_store.len(), 1);
+
+    let wasm_module = read_snapshot_data(
+        &mut test,
+        source_id,
+        source_snapshot_id,
+        CanisterSnapshotDataKind::WasmModule {
+            offset: 0,
+            size: metadata.wasm_module_size,
+        },
+    )
+    .unwrap();
+    let wasm_memory = read_snapshot_data(
+        &mut test,
+        source_id,
+        source_snapshot_id,
+        CanisterSnapshotDataKind::WasmMemory {
+            offset: 0,
+            size: metadata.wasm_memory_size,
+        },
+    )
+    .unwrap();
+    let wasm_chunk = read_snapshot_data(
+        &mut test,
+        source_id,
+        source_snapshot_id,
+        CanisterSnapshotDataKind::WasmChunk {
+            hash: metadata.wasm_chunk_store[0].hash.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(wasm_chunk, vec![1, 2, 3, 4, 5]);
+
+    // Upload the snapshot to the target canister.
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: target_id.get(),
+        replace_snapshot: None,
+        wasm_module_size: metadata.wasm_module_size,
+        exported_globals: metadata.exported_globals.clone(),
+        wasm_memory_size: metadata.wasm_memory_size,
+        stable_memory_size: metadata.stable_memory_size,
+        wasm_chunk_store_size: metadata.wasm_chunk_store.len() as u64,
+        certified_data: metadata.certified_data.clone(),
+    };
+    let result = test
+        .subnet_message("upload_canister_snapshot_metadata", args.encode())
+        .unwrap();
+    let target_snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&result.bytes())
+        .unwrap()
+        .get_snapshot_id();
+    let snapshot = test
+        .state()
+        .canister_snapshots
+        .get(target_snapshot_id)
+        .unwrap();
+    assert_eq!(snapshot.canister_id(), target_id);
+
+    upload_snapshot_data(
+        &mut test,
+        target_id,
+        target_snapshot_id,
+        CanisterSnapshotDataOffset::WasmModule { offset: 0 },
+        wasm_module,
+    )
+    .unwrap();
+    upload_snapshot_data(
+        &mut test,
+        target_id,
+        target_snapshot_id,
+        CanisterSnapshotDataOffset::WasmMemory { offset: 0 },
+        wasm_memory,
+    )
+    .unwrap();
+    upload_snapshot_data(
+        &mut test,
+        target_id,
+        target_snapshot_id,
+        CanisterSnapshotDataOffset::WasmChunk,
+        wasm_chunk.clone(),
+    )
+    .unwrap();
+    // The chunk store is full now.
+    upload_snapshot_data(
+        &mut test,
+        target_id,
+        target_snapshot_id,
+        CanisterSnapshotDataOffset::WasmChunk,
+        vec![6, 7, 8],
+    )
+    .unwrap_err();
+
+    let unflushed_changes = test.state_mut().canister_snapshots.take_unflushed_changes();
+    assert_eq!(
+        unflushed_changes,
+        vec![
+            SnapshotOperation::Backup(source_id, source_snapshot_id),
+            SnapshotOperation::Upload(target_snapshot_id),
+        ]
+    );
+
+    // Load the uploaded snapshot and check that the global was restored.
+    let args = LoadCanisterSnapshotArgs::new(target_id, target_snapshot_id, None);
+    test.subnet_message("load_canister_snapshot", args.encode())
+        .unwrap();
+    let result = test
+        .non_replicated_query(target_id, "read_counter", vec![])
+        .unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![1, 0, 0, 0]));
+    assert_eq!(
+        test.canister_state(target_id)
+            .system_state
+            .wasm_chunk_store
+            .keys()
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn load_uploaded_canister_snapshot_fails_when_globals_do_not_match() {
+    let wasm = wat::parse_str(COUNTER_WAT).unwrap();
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_binary(wasm.clone()).unwrap();
+
+    // Upload a snapshot whose metadata declares no globals.
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: wasm.len() as u64,
+        wasm_memory_size: 65_536,
+        ..Default::default()
+    };
+    let result = test
+        .subnet_message("upload_canister_snapshot_metadata", args.encode())
+        .unwrap();
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&result.bytes())
+        .unwrap()
+        .get_snapshot_id();
+    upload_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataOffset::WasmModule { offset: 0 },
+        wasm,
+    )
+    .unwrap();
+
+    let args = LoadCanisterSnapshotArgs::new(canister_id, snapshot_id, None);
+    let err = test
+        .subnet_message("load_canister_snapshot", args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+}
+
+#[test]
+fn uploading_canister_snapshot_wasm_module_is_heap_delta_rate_limited() {
+    const HEAP_DELTA_RATE_LIMIT: u64 = 1_000_000;
+    let wasm = wat::parse_str(COUNTER_WAT).unwrap();
+    let mut test = ExecutionTestBuilder::new()
+        .with_heap_delta_rate_limit(NumBytes::new(HEAP_DELTA_RATE_LIMIT))
+        .build();
+    let canister_id = test.canister_from_binary(wasm.clone()).unwrap();
+    test.canister_state_mut(canister_id)
+        .scheduler_state
+        .heap_delta_debit = NumBytes::new(0);
+
+    let args = UploadCanisterSnapshotMetadataArgs {
+        canister_id: canister_id.get(),
+        wasm_module_size: wasm.len() as u64,
+        ..Default::default()
+    };
+    let result = test
+        .subnet_message("upload_canister_snapshot_metadata", args.encode())
+        .unwrap();
+    let snapshot_id = UploadCanisterSnapshotMetadataResponse::decode(&result.bytes())
+        .unwrap()
+        .get_snapshot_id();
+
+    // The uploaded Wasm module counts as heap delta.
+    let heap_delta_estimate_before = test.state().metadata.heap_delta_estimate;
+    upload_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataOffset::WasmModule { offset: 0 },
+        wasm.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        test.state().metadata.heap_delta_estimate,
+        heap_delta_estimate_before + NumBytes::new(wasm.len() as u64)
+    );
+    assert_eq!(
+        test.canister_state(canister_id)
+            .scheduler_state
+            .heap_delta_debit,
+        NumBytes::new(wasm.len() as u64)
+    );
+    let module = read_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataKind::WasmModule {
+            offset: 0,
+            size: wasm.len() as u64,
+        },
+    )
+    .unwrap();
+    assert_eq!(module, wasm);
+
+    // Neither data nor metadata can be uploaded by a heap delta rate limited canister.
+    test.canister_state_mut(canister_id)
+        .scheduler_state
+        .heap_delta_debit = NumBytes::new(HEAP_DELTA_RATE_LIMIT);
+    let err = upload_snapshot_data(
+        &mut test,
+        canister_id,
+        snapshot_id,
+        CanisterSnapshotDataOffset::WasmModule { offset: 0 },
+        wasm,
+    )
+    .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterHeapDeltaRateLimited);
+    let err = test
+        .subnet_message("upload_canister_snapshot_metadata", args.encode())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterHeapDeltaRateLimited);
+}
+
 /// Early warning system / stumbling block forcing the authors of changes adding
 /// or removing canister state fields to think about and/or ask the Execution
 /// team to think about any repercussions to the canister snapshot logic.
@@ -1900,5 +2331,8 @@ fn canister_snapshot_change_guard_do_not_modify_without_reading_doc_comment() {
         install_code_debit: _,
         time_of_last_allocation_charge: _,
         total_query_stats: _,
+        last_heartbeat_execution_round: _,
+        last_global_timer_execution_round: _,
+        task_skip_reason: _,
     } = scheduler_state;
 }
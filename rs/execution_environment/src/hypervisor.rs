@@ -1,19 +1,24 @@
 use ic_canister_sandbox_backend_lib::replica_controller::sandboxed_execution_controller::SandboxedExecutionController;
+use ic_config::embedders::Config as EmbeddersConfig;
 use ic_config::execution_environment::{Config, MAX_COMPILATION_CACHE_SIZE};
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_embedders::wasm_executor::{WasmExecutionResult, WasmExecutor};
-use ic_embedders::wasm_utils::decoding::decoded_wasm_size;
+use ic_embedders::wasm_executor::{PausedWasmExecution, WasmExecutionResult, WasmExecutor};
+use ic_embedders::wasm_utils::decoding::{decode_wasm, decoded_wasm_size};
+use ic_embedders::wasm_utils::function_names;
 use ic_embedders::{wasm_executor::WasmExecutorImpl, WasmExecutionInput, WasmtimeEmbedder};
-use ic_embedders::{CompilationCache, CompilationResult};
+use ic_embedders::{
+    CompilationCache, CompilationFingerprint, CompilationResult, PersistentCompilationCache,
+};
 use ic_interfaces::execution_environment::{
-    HypervisorError, HypervisorResult, WasmExecutionOutput,
+    HypervisorError, HypervisorResult, InstructionProfile, WasmExecutionOutput,
 };
-use ic_logger::ReplicaLogger;
+use ic_logger::{info, warn, ReplicaLogger};
 use ic_management_canister_types::LogVisibilityV2;
 use ic_metrics::buckets::decimal_buckets_with_zero;
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::canister_state::execution_state::WasmBinary;
 use ic_replicated_state::NetworkTopology;
 use ic_replicated_state::{page_map::allocated_pages_count, ExecutionState, SystemState};
 use ic_system_api::ExecutionParameters;
@@ -24,16 +29,23 @@ use ic_types::{
 };
 use ic_wasm_types::CanisterModule;
 use prometheus::{Histogram, HistogramVec, IntCounter, IntGauge};
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::Arc,
+};
 
 use crate::execution::common::{apply_canister_state_changes, update_round_limits};
 use crate::execution_environment::{as_round_instructions, CompilationCostHandling, RoundLimits};
 use crate::metrics::CallTreeMetrics;
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 
+mod coverage;
 #[cfg(test)]
 mod tests;
 
+pub use coverage::CoverageCollector;
+
 #[doc(hidden)] // pub for usage in tests
 pub struct HypervisorMetrics {
     accessed_pages: HistogramVec,
@@ -207,6 +219,11 @@ pub struct Hypervisor {
     deterministic_time_slicing: FlagStatus,
     cost_to_compile_wasm_instruction: NumInstructions,
     dirty_page_overhead: NumInstructions,
+    instruction_profile_dir: Option<PathBuf>,
+    /// The number of instruction profiles started so far, used to order the
+    /// profile files.
+    instruction_profile_count: AtomicU64,
+    coverage: Option<Arc<CoverageCollector>>,
 }
 
 impl Hypervisor {
@@ -281,6 +298,13 @@ impl Hypervisor {
         embedder_config.subnet_type = own_subnet_type;
         embedder_config.dirty_page_overhead = dirty_page_overhead;
 
+        let compilation_cache = Arc::new(new_compilation_cache(
+            &config,
+            &embedder_config,
+            metrics_registry,
+            &log,
+        ));
+
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
                 let executor = SandboxedExecutionController::new(
@@ -310,12 +334,18 @@ impl Hypervisor {
             own_subnet_type,
             log,
             cycles_account_manager,
-            compilation_cache: Arc::new(CompilationCache::new(config.max_compilation_cache_size)),
+            compilation_cache,
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config
                 .embedders_config
                 .cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            instruction_profile_dir: config.instruction_profile_dir,
+            instruction_profile_count: AtomicU64::new(0),
+            coverage: match config.embedders_config.feature_flags.wasm_coverage {
+                FlagStatus::Enabled => Some(Arc::new(CoverageCollector::default())),
+                FlagStatus::Disabled => None,
+            },
         }
     }
 
@@ -342,9 +372,17 @@ impl Hypervisor {
             deterministic_time_slicing,
             cost_to_compile_wasm_instruction,
             dirty_page_overhead,
+            instruction_profile_dir: None,
+            instruction_profile_count: AtomicU64::new(0),
+            coverage: None,
         }
     }
 
+    /// Returns the collector of the Wasm coverage if Wasm coverage is enabled.
+    pub fn coverage(&self) -> Option<Arc<CoverageCollector>> {
+        self.coverage.clone()
+    }
+
     #[cfg(test)]
     pub fn compile_count(&self) -> u64 {
         self.metrics.compile.get_sample_count()
@@ -450,6 +488,29 @@ impl Hypervisor {
             execution_state.is_wasm64,
         );
         let api_type_str = api_type.as_str();
+        let instruction_profile_writer = self.instruction_profile_dir.as_ref().map(|dir| {
+            let count = self
+                .instruction_profile_count
+                .fetch_add(1, Ordering::Relaxed);
+            InstructionProfileWriter {
+                path: dir.join(instruction_profile_file_name(
+                    count,
+                    system_state.canister_id,
+                    api_type_str,
+                    &func_ref,
+                )),
+                log: self.log.clone(),
+            }
+        });
+        let output_recorder = if instruction_profile_writer.is_some() || self.coverage.is_some() {
+            Some(ExecutionOutputRecorder {
+                instruction_profile_writer,
+                coverage: self.coverage.clone(),
+                wasm_binary: Arc::clone(&execution_state.wasm_binary),
+            })
+        } else {
+            None
+        };
         let (compilation_result, mut execution_result) = Arc::clone(&self.wasm_executor).execute(
             WasmExecutionInput {
                 api_type,
@@ -503,7 +564,10 @@ impl Hypervisor {
             }
         }
 
-        execution_result
+        match output_recorder {
+            Some(recorder) => recorder.record_when_finished(execution_result),
+            None => execution_result,
+        }
     }
 
     #[doc(hidden)]
@@ -511,3 +575,183 @@ impl Hypervisor {
         self.compilation_cache.clear_for_testing()
     }
 }
+
+/// Creates the compilation cache, backed by the on-disk tier if it is enabled.
+/// If the on-disk tier cannot be opened, only the in-memory cache is used.
+fn new_compilation_cache(
+    config: &Config,
+    embedder_config: &EmbeddersConfig,
+    metrics_registry: &MetricsRegistry,
+    log: &ReplicaLogger,
+) -> CompilationCache {
+    if config.persistent_compilation_cache == FlagStatus::Disabled {
+        return CompilationCache::new(config.max_compilation_cache_size);
+    }
+    let Some(dir) = &config.persistent_compilation_cache_dir else {
+        warn!(
+            log,
+            "The persistent compilation cache is enabled but no directory is configured"
+        );
+        return CompilationCache::new(config.max_compilation_cache_size);
+    };
+    match PersistentCompilationCache::open(
+        dir,
+        config.max_persistent_compilation_cache_size,
+        CompilationFingerprint::new(embedder_config),
+        metrics_registry,
+        log.clone(),
+    ) {
+        Ok(persistent) => {
+            info!(
+                log,
+                "Using persistent compilation cache in {}",
+                dir.display()
+            );
+            CompilationCache::new_with_persistent_tier(
+                config.max_compilation_cache_size,
+                persistent,
+            )
+        }
+        Err(err) => {
+            warn!(
+                log,
+                "Failed to open the persistent compilation cache in {}: {}",
+                dir.display(),
+                err
+            );
+            CompilationCache::new(config.max_compilation_cache_size)
+        }
+    }
+}
+
+/// Returns the name of the file holding the instruction profile of a message,
+/// e.g. `00000042_rwlgt-iiaaa-aaaaa-aaaaa-cai_update_transfer.folded`.
+fn instruction_profile_file_name(
+    count: u64,
+    canister_id: CanisterId,
+    api_type_str: &str,
+    func_ref: &FuncRef,
+) -> String {
+    let function = match func_ref {
+        FuncRef::Method(method) => method.name(),
+        FuncRef::UpdateClosure(_) | FuncRef::QueryClosure(_) => "closure".to_string(),
+    };
+    let sanitize = |s: &str| -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect()
+    };
+    format!(
+        "{:08}_{}_{}_{}.folded",
+        count,
+        canister_id,
+        sanitize(api_type_str),
+        sanitize(&function)
+    )
+}
+
+/// Records the instruction profile and the coverage of a message once the
+/// execution of the message finishes, possibly after several slices.
+struct ExecutionOutputRecorder {
+    instruction_profile_writer: Option<InstructionProfileWriter>,
+    coverage: Option<Arc<CoverageCollector>>,
+    wasm_binary: Arc<WasmBinary>,
+}
+
+impl ExecutionOutputRecorder {
+    fn record_when_finished(self, execution_result: WasmExecutionResult) -> WasmExecutionResult {
+        match execution_result {
+            WasmExecutionResult::Finished(slice, mut output, state_changes) => {
+                if let (Some(writer), Some(profile)) = (
+                    &self.instruction_profile_writer,
+                    &output.instruction_profile,
+                ) {
+                    writer.write(profile, &self.wasm_binary);
+                }
+                if let (Some(collector), Some(coverage)) = (&self.coverage, output.coverage.take())
+                {
+                    collector.record(&self.wasm_binary.binary, coverage);
+                }
+                WasmExecutionResult::Finished(slice, output, state_changes)
+            }
+            WasmExecutionResult::Paused(slice, paused) => WasmExecutionResult::Paused(
+                slice,
+                Box::new(PausedRecordedExecution {
+                    paused,
+                    recorder: self,
+                }),
+            ),
+        }
+    }
+}
+
+/// Writes the instruction profile of a message to a file.
+struct InstructionProfileWriter {
+    path: PathBuf,
+    log: ReplicaLogger,
+}
+
+impl InstructionProfileWriter {
+    /// Writes the profile in the folded-stack format, using the names from
+    /// the name section of the canister module.
+    fn write(&self, profile: &InstructionProfile, wasm_binary: &WasmBinary) {
+        // The module was already validated when it was installed, so it is
+        // decoded without a size limit.
+        let names = decode_wasm(NumBytes::new(u64::MAX), wasm_binary.binary.to_shared_vec())
+            .map(|wasm| function_names(&wasm))
+            .unwrap_or_default();
+        let folded = profile.to_folded(|index| match names.get(&index) {
+            Some(name) => name.replace(';', ":"),
+            None => format!("wasm-function[{}]", index),
+        });
+        let result = self
+            .path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|()| std::fs::write(&self.path, folded));
+        if let Err(err) = result {
+            warn!(
+                self.log,
+                "Failed to write the instruction profile {}: {}",
+                self.path.display(),
+                err
+            );
+        }
+    }
+}
+
+/// A paused execution whose instruction profile and coverage are recorded
+/// when it finishes.
+struct PausedRecordedExecution {
+    paused: Box<dyn PausedWasmExecution>,
+    recorder: ExecutionOutputRecorder,
+}
+
+impl std::fmt::Debug for PausedRecordedExecution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PausedRecordedExecution")
+            .field("paused", &self.paused)
+            .field(
+                "instruction_profile_path",
+                &self
+                    .recorder
+                    .instruction_profile_writer
+                    .as_ref()
+                    .map(|writer| &writer.path),
+            )
+            .field("coverage", &self.recorder.coverage.is_some())
+            .finish()
+    }
+}
+
+impl PausedWasmExecution for PausedRecordedExecution {
+    fn resume(self: Box<Self>, execution_state: &ExecutionState) -> WasmExecutionResult {
+        let this = *self;
+        this.recorder
+            .record_when_finished(this.paused.resume(execution_state))
+    }
+
+    fn abort(self: Box<Self>) {
+        self.paused.abort()
+    }
+}
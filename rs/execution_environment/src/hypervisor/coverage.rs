@@ -0,0 +1,67 @@
+//! Collects the Wasm coverage of canister modules across messages if Wasm
+//! coverage is enabled.
+
+use ic_embedders::wasm_utils::{covered_blocks, decoding::decode_wasm};
+use ic_interfaces::execution_environment::{BlockCoverage, CoveredBlock};
+use ic_types::NumBytes;
+use ic_wasm_types::{CanisterModule, WasmHash};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+struct ModuleCoverage {
+    module: CanisterModule,
+    coverage: BlockCoverage,
+}
+
+/// Accumulates the executed blocks of all messages per canister module. Since
+/// coverage is keyed by the module hash, canisters running the same module
+/// share their coverage.
+#[derive(Default)]
+pub struct CoverageCollector {
+    modules: Mutex<BTreeMap<WasmHash, ModuleCoverage>>,
+}
+
+impl CoverageCollector {
+    /// Adds the blocks executed by a message of the given module.
+    pub(crate) fn record(&self, module: &CanisterModule, coverage: BlockCoverage) {
+        self.modules
+            .lock()
+            .unwrap()
+            .entry(WasmHash::from(module))
+            .or_insert_with(|| ModuleCoverage {
+                module: module.clone(),
+                coverage: BlockCoverage::default(),
+            })
+            .coverage
+            .merge(coverage);
+    }
+
+    /// Returns the blocks of the module with the given hash that were executed
+    /// since the last reset, resolved against the module.
+    pub fn covered_blocks(&self, wasm_hash: &WasmHash) -> Vec<CoveredBlock> {
+        let modules = self.modules.lock().unwrap();
+        let Some(ModuleCoverage { module, coverage }) = modules.get(wasm_hash) else {
+            return vec![];
+        };
+        // The module was already validated when it was installed, so it is
+        // decoded without a size limit.
+        match decode_wasm(NumBytes::new(u64::MAX), module.to_shared_vec()) {
+            Ok(wasm) => covered_blocks(&wasm, coverage),
+            Err(_) => coverage
+                .blocks
+                .iter()
+                .map(|&(function_index, instruction_index)| CoveredBlock {
+                    function_index,
+                    function_name: None,
+                    instruction_index,
+                    code_offset: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Discards the coverage collected so far.
+    pub fn reset(&self) {
+        self.modules.lock().unwrap().clear();
+    }
+}
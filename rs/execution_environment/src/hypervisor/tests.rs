@@ -41,6 +41,7 @@ use ic_types::{
     CanisterId, ComputeAllocation, Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES,
 };
 use ic_universal_canister::{call_args, wasm, UNIVERSAL_CANISTER_WASM};
+use ic_wasm_types::WasmHash;
 #[cfg(not(all(target_arch = "aarch64", target_vendor = "apple")))]
 use proptest::prelude::*;
 #[cfg(not(all(target_arch = "aarch64", target_vendor = "apple")))]
@@ -7877,3 +7878,132 @@ fn wasm64_correct_execution_state() {
 fn wasm32_correct_execution_state() {
     check_correct_execution_state(false);
 }
+
+const PROFILED_WAT: &str = r#"
+    (module
+        (func $inner (param $x i32) (result i32)
+            (i32.mul (local.get $x) (local.get $x))
+        )
+        (func $outer
+            (drop (call $inner (i32.const 3)))
+            (drop (call $inner (i32.const 4)))
+        )
+        (func $test (export "canister_update test")
+            (call $outer)
+        )
+    )"#;
+
+#[test]
+fn instruction_profile_is_written_as_folded_stacks() {
+    let profile_dir = tempfile::tempdir().unwrap();
+    let mut test = ExecutionTestBuilder::new()
+        .with_instruction_profiling(profile_dir.path().to_path_buf())
+        .build();
+    let canister_id = test.canister_from_wat(PROFILED_WAT).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+
+    let files: Vec<_> = std::fs::read_dir(profile_dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(files.len(), 1);
+    let file_name = files[0].file_name().unwrap().to_str().unwrap().to_string();
+    assert!(
+        file_name.ends_with(&format!("_{}_update_test.folded", canister_id)),
+        "{}",
+        file_name
+    );
+
+    let folded = std::fs::read_to_string(&files[0]).unwrap();
+    let stacks: Vec<(&str, u64)> = folded
+        .lines()
+        .map(|line| {
+            let (stack, instructions) = line.rsplit_once(' ').unwrap();
+            (stack, instructions.parse().unwrap())
+        })
+        .collect();
+    assert_eq!(
+        stacks.iter().map(|(stack, _)| *stack).collect::<Vec<_>>(),
+        vec!["test", "test;outer", "test;outer;inner"]
+    );
+    assert!(stacks.iter().all(|(_, instructions)| *instructions > 0));
+}
+
+#[test]
+fn instruction_profiling_does_not_change_executed_instructions() {
+    let profile_dir = tempfile::tempdir().unwrap();
+    let mut executed_instructions = vec![];
+    for builder in [
+        ExecutionTestBuilder::new(),
+        ExecutionTestBuilder::new().with_instruction_profiling(profile_dir.path().to_path_buf()),
+    ] {
+        let mut test = builder.build();
+        let canister_id = test.canister_from_wat(PROFILED_WAT).unwrap();
+        let result = test.ingress(canister_id, "test", vec![]);
+        assert_empty_reply(result);
+        executed_instructions.push(test.executed_instructions());
+    }
+    assert_eq!(executed_instructions[0], executed_instructions[1]);
+}
+
+const COVERAGE_WAT: &str = r#"
+    (module
+        (func $branch (param $x i32) (result i32)
+            (if (result i32) (local.get $x)
+                (then (i32.const 1))
+                (else (i32.const 2))
+            )
+        )
+        (func $test (export "canister_update test")
+            (drop (call $branch (i32.const 1)))
+        )
+    )"#;
+
+#[test]
+fn wasm_coverage_records_executed_blocks() {
+    let mut test = ExecutionTestBuilder::new().with_wasm_coverage().build();
+    let canister_id = test.canister_from_wat(COVERAGE_WAT).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+
+    let coverage = test.hypervisor_deprecated().coverage().unwrap();
+    let wasm_hash = WasmHash::from(&test.execution_state(canister_id).wasm_binary.binary);
+    let blocks = coverage.covered_blocks(&wasm_hash);
+    assert_eq!(
+        blocks
+            .iter()
+            .map(|block| (
+                block.function_name.as_deref().unwrap(),
+                block.instruction_index
+            ))
+            .collect::<Vec<_>>(),
+        // The `else` branch of `$branch` starting at instruction 4 is not
+        // executed.
+        vec![("branch", 0), ("branch", 2), ("branch", 6), ("test", 0)]
+    );
+    let offsets: Vec<u64> = blocks
+        .iter()
+        .map(|block| block.code_offset.unwrap())
+        .collect();
+    assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+
+    coverage.reset();
+    assert_eq!(coverage.covered_blocks(&wasm_hash), vec![]);
+}
+
+#[test]
+fn wasm_coverage_does_not_change_executed_instructions() {
+    let mut executed_instructions = vec![];
+    for builder in [
+        ExecutionTestBuilder::new(),
+        ExecutionTestBuilder::new().with_wasm_coverage(),
+    ] {
+        let mut test = builder.build();
+        let canister_id = test.canister_from_wat(COVERAGE_WAT).unwrap();
+        let result = test.ingress(canister_id, "test", vec![]);
+        assert_empty_reply(result);
+        executed_instructions.push(test.executed_instructions());
+    }
+    assert_eq!(executed_instructions[0], executed_instructions[1]);
+}
@@ -20,7 +20,7 @@ pub use execution_environment::{
     ExecuteMessageResult, ExecutionEnvironment, ExecutionResponse, RoundInstructions, RoundLimits,
 };
 pub use history::{IngressHistoryReaderImpl, IngressHistoryWriterImpl};
-pub use hypervisor::{Hypervisor, HypervisorMetrics};
+pub use hypervisor::{CoverageCollector, Hypervisor, HypervisorMetrics};
 use ic_base_types::PrincipalId;
 use ic_config::{execution_environment::Config, subnet_config::SchedulerConfig};
 use ic_cycles_account_manager::CyclesAccountManager;
@@ -42,8 +42,8 @@ use ic_types::{
 pub use metrics::IngressFilterMetrics;
 pub use query_handler::InternalHttpQueryHandler;
 use query_handler::{HttpQueryHandler, QueryScheduler, QuerySchedulerFlag};
-pub use scheduler::RoundSchedule;
 use scheduler::SchedulerImpl;
+pub use scheduler::{MessageExecutionTrace, RoundSchedule};
 use std::sync::Arc;
 use tokio::sync::mpsc::Sender;
 
@@ -86,6 +86,9 @@ pub struct ExecutionServices {
     pub query_execution_service: QueryExecutionService,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
     pub query_stats_payload_builder: QueryStatsPayloadBuilderParams,
+    /// The collector of the Wasm coverage if Wasm coverage is enabled in the
+    /// embedders config.
+    pub coverage: Option<Arc<CoverageCollector>>,
 }
 
 impl ExecutionServices {
@@ -103,6 +106,38 @@ impl ExecutionServices {
         state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
         fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
         completed_execution_messages_tx: Sender<(MessageId, Height)>,
+    ) -> ExecutionServices {
+        Self::setup_execution_with_trace(
+            logger,
+            metrics_registry,
+            own_subnet_id,
+            own_subnet_type,
+            scheduler_config,
+            config,
+            cycles_account_manager,
+            state_reader,
+            fd_factory,
+            completed_execution_messages_tx,
+            None,
+        )
+    }
+
+    /// Same as `setup_execution()`, but if `execution_trace_tx` is set, the
+    /// scheduler sends a `MessageExecutionTrace` of every executed canister
+    /// and subnet message to it.
+    #[allow(clippy::type_complexity, clippy::too_many_arguments)]
+    pub fn setup_execution_with_trace(
+        logger: ReplicaLogger,
+        metrics_registry: &MetricsRegistry,
+        own_subnet_id: SubnetId,
+        own_subnet_type: SubnetType,
+        scheduler_config: SchedulerConfig,
+        config: Config,
+        cycles_account_manager: Arc<CyclesAccountManager>,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+        completed_execution_messages_tx: Sender<(MessageId, Height)>,
+        execution_trace_tx: Option<std::sync::mpsc::Sender<MessageExecutionTrace>>,
     ) -> ExecutionServices {
         let hypervisor = Arc::new(Hypervisor::new(
             config.clone(),
@@ -114,6 +149,7 @@ impl ExecutionServices {
             scheduler_config.dirty_page_overhead,
             Arc::clone(&fd_factory),
         ));
+        let coverage = hypervisor.coverage();
 
         let ingress_history_writer = Arc::new(IngressHistoryWriterImpl::new(
             config.clone(),
@@ -180,7 +216,7 @@ impl ExecutionServices {
             ingress_filter_metrics.clone(),
         );
 
-        let scheduler = Box::new(SchedulerImpl::new(
+        let scheduler = SchedulerImpl::new(
             scheduler_config,
             own_subnet_id,
             Arc::clone(&ingress_history_writer) as Arc<_>,
@@ -192,7 +228,11 @@ impl ExecutionServices {
             config.rate_limiting_of_instructions,
             config.deterministic_time_slicing,
             Arc::clone(&fd_factory),
-        ));
+        );
+        let scheduler = Box::new(match execution_trace_tx {
+            Some(execution_trace_tx) => scheduler.with_execution_trace(execution_trace_tx),
+            None => scheduler,
+        });
 
         Self {
             ingress_filter,
@@ -201,6 +241,7 @@ impl ExecutionServices {
             query_execution_service,
             scheduler,
             query_stats_payload_builder,
+            coverage,
         }
     }
 
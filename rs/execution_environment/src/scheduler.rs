@@ -28,7 +28,7 @@ use ic_replicated_state::{
     num_bytes_try_from,
     page_map::PageAllocatorFileDescriptor,
     CanisterState, CanisterStatus, ExecutionTask, InputQueueType, NetworkTopology, NumWasmPages,
-    ReplicatedState,
+    ReplicatedState, TaskSkipReason,
 };
 use ic_system_api::InstructionLimits;
 use ic_types::{
@@ -46,10 +46,12 @@ use std::{
     cell::RefCell,
     collections::{BTreeMap, BTreeSet},
     str::FromStr,
-    sync::Arc,
+    sync::{mpsc::Sender, Arc},
 };
 use strum::IntoEnumIterator;
 
+mod execution_trace;
+pub use execution_trace::MessageExecutionTrace;
 mod scheduler_metrics;
 use scheduler_metrics::*;
 mod round_schedule;
@@ -142,6 +144,8 @@ pub(crate) struct SchedulerImpl {
     rate_limiting_of_instructions: FlagStatus,
     deterministic_time_slicing: FlagStatus,
     fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+    /// If set, a trace of every executed canister and subnet message is sent here.
+    execution_trace_tx: Option<Sender<MessageExecutionTrace>>,
 }
 
 impl SchedulerImpl {
@@ -173,9 +177,20 @@ impl SchedulerImpl {
             rate_limiting_of_instructions,
             deterministic_time_slicing,
             fd_factory,
+            execution_trace_tx: None,
         }
     }
 
+    /// Makes the scheduler send a `MessageExecutionTrace` of every executed
+    /// canister and subnet message to `execution_trace_tx`.
+    pub(crate) fn with_execution_trace(
+        mut self,
+        execution_trace_tx: Sender<MessageExecutionTrace>,
+    ) -> Self {
+        self.execution_trace_tx = Some(execution_trace_tx);
+        self
+    }
+
     /// Makes progress in executing long-running `install_code` messages.
     fn advance_long_running_install_code(
         &self,
@@ -233,6 +248,7 @@ impl SchedulerImpl {
     fn drain_subnet_queues(
         &self,
         mut state: ReplicatedState,
+        current_round: ExecutionRound,
         csprng: &mut Csprng,
         round_limits: &mut RoundLimits,
         measurement_scope: &MeasurementScope,
@@ -271,6 +287,7 @@ impl SchedulerImpl {
                 let (new_state, message_instructions) = self.execute_subnet_message(
                     msg,
                     state,
+                    current_round,
                     csprng,
                     round_limits,
                     registry_settings,
@@ -303,6 +320,7 @@ impl SchedulerImpl {
         &self,
         msg: CanisterMessage,
         state: ReplicatedState,
+        current_round: ExecutionRound,
         csprng: &mut Csprng,
         round_limits: &mut RoundLimits,
         registry_settings: &RegistryExecutionSettings,
@@ -316,6 +334,20 @@ impl SchedulerImpl {
             &msg,
         );
 
+        // The description, ingress message ID and output queue size before
+        // execution, if the execution is traced.
+        let trace_before = self.execution_trace_tx.as_ref().map(|_| {
+            let message_id = match &msg {
+                CanisterMessage::Ingress(ingress) => Some(ingress.message_id.clone()),
+                CanisterMessage::Request(_) | CanisterMessage::Response(_) => None,
+            };
+            (
+                msg.to_string(),
+                message_id,
+                state.subnet_queues().output_queues_message_count(),
+            )
+        });
+
         let instructions_before = round_limits.instructions;
         let (new_state, message_instructions) = self.exec_env.execute_subnet_message(
             msg,
@@ -331,6 +363,30 @@ impl SchedulerImpl {
             as_num_instructions(instructions_before - round_limits.instructions);
         let messages = NumMessages::from(message_instructions.map(|_| 1).unwrap_or(0));
         measurement_scope.add(round_instructions_executed, NumSlices::from(1), messages);
+
+        if let (
+            Some(execution_trace_tx),
+            Some((description, message_id, output_messages_before)),
+            Some(instructions_used),
+        ) = (&self.execution_trace_tx, trace_before, message_instructions)
+        {
+            let ingress_status = message_id.map(|message_id| {
+                let status = new_state.get_ingress_status(&message_id);
+                (message_id, status)
+            });
+            // The receiver going away must not affect execution.
+            let _ = execution_trace_tx.send(MessageExecutionTrace::new(
+                current_round,
+                CanisterId::ic_00(),
+                Some(description),
+                instructions_used,
+                Cycles::zero(),
+                Cycles::zero(),
+                ingress_status.as_ref(),
+                new_state.subnet_queues().output_queues_message_count() as i64
+                    - output_messages_before as i64,
+            ));
+        }
         (new_state, message_instructions)
     }
 
@@ -347,14 +403,6 @@ impl SchedulerImpl {
 
         let now = state.time();
         for canister in state.canisters_iter_mut() {
-            // Add `Heartbeat` or `GlobalTimer` for running canisters only.
-            match canister.system_state.status() {
-                CanisterStatusType::Running => {}
-                CanisterStatusType::Stopping | CanisterStatusType::Stopped => {
-                    continue;
-                }
-            }
-
             let may_schedule_heartbeat = canister.exports_heartbeat_method();
             let may_schedule_global_timer = canister.exports_global_timer_method()
                 && canister.system_state.global_timer.has_reached_deadline(now);
@@ -364,10 +412,22 @@ impl SchedulerImpl {
                 continue;
             }
 
+            // Add `Heartbeat` or `GlobalTimer` for running canisters only.
+            match canister.system_state.status() {
+                CanisterStatusType::Running => {}
+                CanisterStatusType::Stopping | CanisterStatusType::Stopped => {
+                    canister.scheduler_state.task_skip_reason =
+                        Some(TaskSkipReason::CanisterNotRunning);
+                    continue;
+                }
+            }
+
             match canister.next_execution() {
                 NextExecution::ContinueLong | NextExecution::ContinueInstallCode => {
                     // Do not add a heartbeat task if a long execution
                     // is pending.
+                    canister.scheduler_state.task_skip_reason =
+                        Some(TaskSkipReason::LongExecutionPending);
                 }
                 NextExecution::None | NextExecution::StartNew => {
                     for _ in 0..NextScheduledMethod::iter().count() {
@@ -440,6 +500,7 @@ impl SchedulerImpl {
                 if !subnet_round_limits.reached() {
                     state = self.drain_subnet_queues(
                         state,
+                        current_round,
                         csprng,
                         &mut subnet_round_limits,
                         &subnet_measurement_scope,
@@ -701,6 +762,7 @@ impl SchedulerImpl {
                 let logger = new_logger!(self.log; messaging.round => round_id.get());
                 let rate_limiting_of_heap_delta = self.rate_limiting_of_heap_delta;
                 let deterministic_time_slicing = self.deterministic_time_slicing;
+                let trace_execution = self.execution_trace_tx.is_some();
                 let round_limits = RoundLimits {
                     instructions: round_limits.instructions,
                     subnet_available_memory: round_limits_per_thread.subnet_available_memory,
@@ -722,6 +784,7 @@ impl SchedulerImpl {
                         round_limits,
                         subnet_size,
                         is_first_iteration,
+                        trace_execution,
                     );
                 });
             }
@@ -761,6 +824,12 @@ impl SchedulerImpl {
                 result.messages_executed,
             );
             heap_delta += result.heap_delta;
+            if let Some(execution_trace_tx) = &self.execution_trace_tx {
+                for trace in result.traces {
+                    // The receiver going away must not affect execution.
+                    let _ = execution_trace_tx.send(trace);
+                }
+            }
         }
 
         // Since there are multiple threads, we update the global limit using
@@ -1323,6 +1392,7 @@ impl Scheduler for SchedulerImpl {
                         .into(),
                     ),
                     state,
+                    current_round,
                     &mut csprng,
                     &mut subnet_round_limits,
                     registry_settings,
@@ -1382,6 +1452,7 @@ impl Scheduler for SchedulerImpl {
                 let (new_state, _) = self.execute_subnet_message(
                     CanisterMessage::Request(raw_rand_context.request.into()),
                     state,
+                    current_round,
                     &mut csprng,
                     &mut subnet_round_limits,
                     registry_settings,
@@ -1686,6 +1757,7 @@ struct ExecutionThreadResult {
     messages_executed: NumMessages,
     heap_delta: NumBytes,
     round_limits: RoundLimits,
+    traces: Vec<MessageExecutionTrace>,
 }
 
 /// Executes the given canisters one by one. For each canister it
@@ -1709,6 +1781,7 @@ fn execute_canisters_on_thread(
     mut round_limits: RoundLimits,
     subnet_size: usize,
     is_first_iteration: bool,
+    trace_execution: bool,
 ) -> ExecutionThreadResult {
     // Since this function runs on a helper thread, we cannot use a nested scope
     // here. Instead, we propagate metrics to the outer scope manually via
@@ -1723,6 +1796,7 @@ fn execute_canisters_on_thread(
     let mut total_slices_executed = NumSlices::from(0);
     let mut total_messages_executed = NumMessages::from(0);
     let mut total_heap_delta = NumBytes::from(0);
+    let mut traces = vec![];
 
     let instruction_limits = InstructionLimits::new(
         deterministic_time_slicing,
@@ -1768,8 +1842,12 @@ fn execute_canisters_on_thread(
 
             let instructions_before = round_limits.instructions;
             let canister_had_paused_execution = canister.has_paused_execution();
+            let system_task = start_system_task(&mut canister);
+            let balance_before = canister.system_state.balance();
+            let output_messages_before =
+                canister.system_state.queues().output_queues_message_count();
             let ExecuteCanisterResult {
-                canister: new_canister,
+                canister: mut new_canister,
                 instructions_used,
                 heap_delta,
                 ingress_status,
@@ -1788,6 +1866,25 @@ fn execute_canisters_on_thread(
                 // We only want to count the canister as executed if it used instructions.
                 executed_canister_ids.insert(new_canister.canister_id());
             }
+            if let Some(system_task) = system_task {
+                finish_system_task(&mut new_canister, system_task, round_id);
+            }
+            if let (true, Some(instructions_used)) = (trace_execution, instructions_used) {
+                traces.push(MessageExecutionTrace::new(
+                    round_id,
+                    new_canister.canister_id(),
+                    description.clone(),
+                    instructions_used,
+                    balance_before,
+                    new_canister.system_state.balance(),
+                    ingress_status.as_ref(),
+                    new_canister
+                        .system_state
+                        .queues()
+                        .output_queues_message_count() as i64
+                        - output_messages_before as i64,
+                ));
+            }
             ingress_results.extend(ingress_status);
             let round_instructions_executed =
                 as_num_instructions(instructions_before - round_limits.instructions);
@@ -1872,6 +1969,7 @@ fn execute_canisters_on_thread(
         messages_executed: total_messages_executed,
         heap_delta: total_heap_delta,
         round_limits,
+        traces,
     }
 }
 
@@ -2183,7 +2281,11 @@ fn can_execute_subnet_msg(
         | Ic00Method::ClearChunkStore
         | Ic00Method::TakeCanisterSnapshot
         | Ic00Method::ListCanisterSnapshots
-        | Ic00Method::DeleteCanisterSnapshot => true,
+        | Ic00Method::DeleteCanisterSnapshot
+        | Ic00Method::ReadCanisterSnapshotMetadata
+        | Ic00Method::ReadCanisterSnapshotData
+        | Ic00Method::UploadCanisterSnapshotMetadata
+        | Ic00Method::UploadCanisterSnapshotData => true,
     }
 }
 
@@ -2247,7 +2349,11 @@ fn get_instructions_limits_for_subnet_message(
             | TakeCanisterSnapshot
             | LoadCanisterSnapshot
             | ListCanisterSnapshots
-            | DeleteCanisterSnapshot => default_limits,
+            | DeleteCanisterSnapshot
+            | ReadCanisterSnapshotMetadata
+            | ReadCanisterSnapshotData
+            | UploadCanisterSnapshotMetadata
+            | UploadCanisterSnapshotData => default_limits,
             InstallCode | InstallChunkedCode => InstructionLimits::new(
                 dts,
                 config.max_instructions_per_install_code,
@@ -2311,6 +2417,38 @@ fn is_next_method_chosen(
     }
 }
 
+/// If the next task of the canister is a heartbeat or global timer, returns
+/// the task and clears the skip reason recorded for the canister, so that
+/// `finish_system_task()` can tell whether the task was skipped.
+fn start_system_task(canister: &mut CanisterState) -> Option<ExecutionTask> {
+    match canister.system_state.task_queue.front() {
+        Some(task @ (ExecutionTask::Heartbeat | ExecutionTask::GlobalTimer)) => {
+            let task = task.clone();
+            canister.scheduler_state.task_skip_reason = None;
+            Some(task)
+        }
+        _ => None,
+    }
+}
+
+/// Records the round in which the heartbeat or global timer task returned by
+/// `start_system_task()` was executed, unless the execution recorded a skip
+/// reason, e.g. because the canister was out of cycles.
+fn finish_system_task(canister: &mut CanisterState, task: ExecutionTask, round: ExecutionRound) {
+    if canister.scheduler_state.task_skip_reason.is_some() {
+        return;
+    }
+    match task {
+        ExecutionTask::Heartbeat => {
+            canister.scheduler_state.last_heartbeat_execution_round = Some(round);
+        }
+        ExecutionTask::GlobalTimer => {
+            canister.scheduler_state.last_global_timer_execution_round = Some(round);
+        }
+        _ => {}
+    }
+}
+
 /// Enqueues `task` (optionally followed by `other_task`) at the front of
 /// `canister`'s task queue.
 fn enqueue_tasks(
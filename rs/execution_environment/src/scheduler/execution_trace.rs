@@ -0,0 +1,74 @@
+use ic_types::{
+    ingress::{IngressState, IngressStatus, WasmResult},
+    messages::MessageId,
+    CanisterId, Cycles, ExecutionRound, NumInstructions,
+};
+use serde::Serialize;
+
+/// Describes a single message or task executed by the scheduler.
+///
+/// Traces are only produced if the scheduler was set up with an execution
+/// trace sender (see `ExecutionServices::setup_execution_with_trace`). Subnet
+/// messages are traced with the management canister as `canister_id` and a
+/// zero `cycles_balance_delta`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MessageExecutionTrace {
+    /// The execution round (batch height) in which the message was executed.
+    pub round: u64,
+    pub canister_id: String,
+    /// A description of the executed message or task, including the method
+    /// name for ingress messages and requests.
+    pub description: String,
+    pub instructions_used: u64,
+    /// The change in the canister's cycles balance caused by the execution,
+    /// i.e. the negated amount of cycles charged plus any cycles received.
+    pub cycles_balance_delta: i128,
+    /// The outcome of the execution for ingress messages, or `None` for
+    /// messages and tasks that do not update the ingress history.
+    pub outcome: Option<String>,
+    /// The ID of the ingress message, if an ingress message was executed.
+    pub message_id: Option<String>,
+    /// The change in the number of messages in the canister's output queues,
+    /// i.e. the number of produced outgoing requests and responses.
+    pub output_messages_delta: i64,
+}
+
+impl MessageExecutionTrace {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
+        round: ExecutionRound,
+        canister_id: CanisterId,
+        description: Option<String>,
+        instructions_used: NumInstructions,
+        balance_before: Cycles,
+        balance_after: Cycles,
+        ingress_status: Option<&(MessageId, IngressStatus)>,
+        output_messages_delta: i64,
+    ) -> Self {
+        Self {
+            round: round.get(),
+            canister_id: canister_id.to_string(),
+            description: description.unwrap_or_default(),
+            instructions_used: instructions_used.get(),
+            cycles_balance_delta: balance_after.get() as i128 - balance_before.get() as i128,
+            outcome: ingress_status.map(|(_, status)| outcome(status)),
+            message_id: ingress_status.map(|(message_id, _)| message_id.to_string()),
+            output_messages_delta,
+        }
+    }
+}
+
+/// Returns a short description of the given ingress status.
+fn outcome(status: &IngressStatus) -> String {
+    match status {
+        IngressStatus::Known { state, .. } => match state {
+            IngressState::Received => "received".to_string(),
+            IngressState::Processing => "processing".to_string(),
+            IngressState::Completed(WasmResult::Reply(_)) => "replied".to_string(),
+            IngressState::Completed(WasmResult::Reject(msg)) => format!("rejected: {}", msg),
+            IngressState::Failed(err) => format!("failed: {}", err),
+            IngressState::Done => "done".to_string(),
+        },
+        IngressStatus::Unknown => "unknown".to_string(),
+    }
+}
@@ -68,7 +68,7 @@ use crate::{
     as_round_instructions, ExecutionEnvironment, Hypervisor, IngressHistoryWriterImpl, RoundLimits,
 };
 
-use super::{RoundSchedule, SchedulerImpl};
+use super::{MessageExecutionTrace, RoundSchedule, SchedulerImpl};
 use crate::metrics::MeasurementScope;
 use ic_crypto_prng::{Csprng, RandomnessPurpose::ExecutionThread};
 use ic_types::time::UNIX_EPOCH;
@@ -561,6 +561,7 @@ impl SchedulerTest {
         let measurements = MeasurementScope::root(&self.scheduler.metrics.round_subnet_queue);
         self.scheduler.drain_subnet_queues(
             state,
+            self.round,
             &mut csprng,
             &mut round_limits,
             &measurements,
@@ -666,6 +667,7 @@ pub(crate) struct SchedulerTestBuilder {
     metrics_registry: MetricsRegistry,
     round_summary: Option<ExecutionRoundSummary>,
     replica_version: ReplicaVersion,
+    execution_trace_tx: Option<std::sync::mpsc::Sender<MessageExecutionTrace>>,
 }
 
 impl Default for SchedulerTestBuilder {
@@ -691,6 +693,7 @@ impl Default for SchedulerTestBuilder {
             metrics_registry: MetricsRegistry::new(),
             round_summary: None,
             replica_version: ReplicaVersion::default(),
+            execution_trace_tx: None,
         }
     }
 }
@@ -767,6 +770,16 @@ impl SchedulerTestBuilder {
         }
     }
 
+    pub fn with_execution_trace(
+        self,
+        execution_trace_tx: std::sync::mpsc::Sender<MessageExecutionTrace>,
+    ) -> Self {
+        Self {
+            execution_trace_tx: Some(execution_trace_tx),
+            ..self
+        }
+    }
+
     pub fn build(self) -> SchedulerTest {
         let first_xnet_canister = u64::MAX / 2;
         let routing_table = Arc::new(
@@ -915,6 +928,10 @@ impl SchedulerTestBuilder {
             deterministic_time_slicing,
             Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
         );
+        let scheduler = match self.execution_trace_tx {
+            Some(execution_trace_tx) => scheduler.with_execution_trace(execution_trace_tx),
+            None => scheduler,
+        };
         SchedulerTest {
             state: Some(state),
             next_canister_id: 0,
@@ -1169,6 +1186,8 @@ impl TestWasmExecutorCore {
                 instance_stats: InstanceStats::default(),
                 system_api_call_counters: SystemApiCallCounters::default(),
                 canister_log: Default::default(),
+                instruction_profile: None,
+                coverage: None,
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -1211,6 +1230,8 @@ impl TestWasmExecutorCore {
             instance_stats,
             system_api_call_counters: SystemApiCallCounters::default(),
             canister_log: Default::default(),
+            instruction_profile: None,
+            coverage: None,
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
@@ -1601,6 +1601,100 @@ fn can_execute_messages_with_just_enough_instructions() {
     );
 }
 
+#[test]
+fn execution_trace_is_sent_for_executed_messages() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut test = SchedulerTestBuilder::new()
+        .with_scheduler_config(SchedulerConfig {
+            scheduler_cores: 2,
+            instruction_overhead_per_execution: NumInstructions::from(0),
+            instruction_overhead_per_canister: NumInstructions::from(0),
+            ..SchedulerConfig::application_subnet()
+        })
+        .with_execution_trace(tx)
+        .build();
+
+    // Bump the round number up to 1.
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    assert!(rx.try_recv().is_err());
+
+    let canister_a = test.create_canister();
+    let canister_b = test.create_canister();
+    let message_id = test.send_ingress(canister_a, ingress(50));
+    test.send_ingress(
+        canister_b,
+        ingress(70).call(other_side(canister_a, 10), on_response(20)),
+    );
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    let mut traces: Vec<_> = rx.try_iter().collect();
+    traces.sort_by_key(|trace| trace.instructions_used);
+    let canister_ids: Vec<_> = traces
+        .iter()
+        .map(|trace| trace.canister_id.clone())
+        .collect();
+    assert_eq!(
+        canister_ids,
+        vec![
+            canister_a.to_string(),
+            canister_a.to_string(),
+            canister_b.to_string(),
+            canister_b.to_string(),
+        ]
+    );
+    let instructions: Vec<_> = traces.iter().map(|trace| trace.instructions_used).collect();
+    assert_eq!(instructions, vec![10, 20, 50, 70]);
+    for trace in traces.iter() {
+        assert_eq!(trace.round, 1);
+    }
+
+    // The ingress message to `canister_a` completed in this round.
+    let ingress_trace = &traces[2];
+    assert_eq!(ingress_trace.message_id, Some(message_id.to_string()));
+    assert!(ingress_trace.outcome.is_some());
+    assert!(ingress_trace.description.starts_with("Ingress"));
+    assert!(ingress_trace.cycles_balance_delta < 0);
+    // The request and the response are not ingress messages.
+    assert_eq!(traces[0].outcome, None);
+    assert!(traces[0].description.starts_with("Request"));
+    assert!(traces[1].description.starts_with("Response"));
+    // `canister_b` produced the request to `canister_a`.
+    assert_eq!(traces[3].output_messages_delta, 1);
+}
+
+#[test]
+fn execution_trace_is_sent_for_subnet_messages() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut test = SchedulerTestBuilder::new().with_execution_trace(tx).build();
+
+    // Bump the round number up to 1.
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let canister = test.create_canister();
+    test.inject_call_to_ic00(
+        Method::CanisterStatus,
+        CanisterIdRecord::from(canister).encode(),
+        Cycles::zero(),
+        canister_test_id(10),
+        InputQueueType::LocalSubnet,
+    );
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+
+    let traces: Vec<_> = rx.try_iter().collect();
+    assert_eq!(traces.len(), 1);
+    let trace = &traces[0];
+    assert_eq!(trace.round, 1);
+    assert_eq!(trace.canister_id, CanisterId::ic_00().to_string());
+    assert!(trace
+        .description
+        .starts_with("Request, method name canister_status"));
+    assert_eq!(trace.outcome, None);
+    assert_eq!(trace.cycles_balance_delta, 0);
+    // The management canister responded to the request.
+    assert_eq!(trace.output_messages_delta, 1);
+}
+
 #[test]
 fn execute_idle_and_canisters_with_messages() {
     let mut test = SchedulerTestBuilder::new()
@@ -2092,6 +2186,55 @@ fn global_timer_is_not_scheduled_if_the_canister_is_stopped() {
     assert_eq!(metrics.round_inner.messages.get_sample_sum(), 1.0);
 }
 
+#[test]
+fn heartbeat_execution_round_is_recorded() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let canister = test.create_canister_with(
+        Cycles::new(1_000_000_000_000),
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        Some(SystemMethod::CanisterHeartbeat),
+        None,
+        None,
+    );
+    assert_eq!(
+        test.canister_state(canister)
+            .scheduler_state
+            .last_heartbeat_execution_round,
+        None
+    );
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let scheduler_state = &test.canister_state(canister).scheduler_state;
+    assert_eq!(
+        scheduler_state.last_heartbeat_execution_round,
+        Some(test.last_round())
+    );
+    assert_eq!(scheduler_state.last_global_timer_execution_round, None);
+    assert_eq!(scheduler_state.task_skip_reason, None);
+}
+
+#[test]
+fn heartbeat_skip_reason_is_recorded_if_the_canister_is_stopped() {
+    let mut test = SchedulerTestBuilder::new().build();
+    let canister = test.create_canister_with(
+        Cycles::new(1_000_000_000_000),
+        ComputeAllocation::zero(),
+        MemoryAllocation::BestEffort,
+        Some(SystemMethod::CanisterHeartbeat),
+        None,
+        Some(CanisterStatusType::Stopped),
+    );
+
+    test.execute_round(ExecutionRoundType::OrdinaryRound);
+    let scheduler_state = &test.canister_state(canister).scheduler_state;
+    assert_eq!(scheduler_state.last_heartbeat_execution_round, None);
+    assert_eq!(
+        scheduler_state.task_skip_reason,
+        Some(TaskSkipReason::CanisterNotRunning)
+    );
+}
+
 #[test]
 fn execute_heartbeat_before_messages() {
     // This test sets up a canister on a system subnet with a heartbeat method and
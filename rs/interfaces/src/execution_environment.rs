@@ -269,6 +269,62 @@ impl SystemApiCallCounters {
     }
 }
 
+/// The instructions executed by a message, attributed to the call stacks of
+/// Wasm functions. Only collected if instruction profiling is enabled.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+pub struct InstructionProfile {
+    /// Maps call stacks to the instructions executed while the stack was
+    /// active, excluding the instructions executed by callees. A stack lists
+    /// the indices of the functions in the canister's Wasm module, starting
+    /// with the outermost function.
+    pub stacks: BTreeMap<Vec<u32>, u64>,
+}
+
+impl InstructionProfile {
+    /// Returns the profile in the folded-stack format understood by
+    /// flamegraph tools: one `outer;...;inner instructions` line per stack.
+    pub fn to_folded(&self, function_name: impl Fn(u32) -> String) -> String {
+        let mut result = String::new();
+        for (stack, instructions) in &self.stacks {
+            let frames: Vec<String> = stack.iter().map(|index| function_name(*index)).collect();
+            result.push_str(&format!("{} {}\n", frames.join(";"), instructions));
+        }
+        result
+    }
+}
+
+/// The basic blocks of a canister's Wasm module that were executed by a
+/// message. Only collected if Wasm coverage is enabled.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize)]
+pub struct BlockCoverage {
+    /// The executed blocks, given as the index of the function in the
+    /// canister's Wasm module and the index of the first instruction of the
+    /// block within the body of the function.
+    pub blocks: BTreeSet<(u32, u32)>,
+}
+
+impl BlockCoverage {
+    pub fn merge(&mut self, other: BlockCoverage) {
+        self.blocks.extend(other.blocks);
+    }
+}
+
+/// An executed basic block of a canister's Wasm module, resolved against the
+/// module so that it can be mapped back to the source code.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CoveredBlock {
+    pub function_index: u32,
+    /// The name of the function from the name section, if present.
+    pub function_name: Option<String>,
+    /// The index of the first instruction of the block within the body of the
+    /// function.
+    pub instruction_index: u32,
+    /// The offset of the first instruction of the block relative to the start
+    /// of the code section, as used by DWARF line tables. `None` if the
+    /// module could not be parsed.
+    pub code_offset: Option<u64>,
+}
+
 /// Tracks the available memory on a subnet. The main idea is to separately track
 /// the execution available memory, the message available memory and the wasm custom
 /// sections available memory. The different flavors of memory are independent of each
@@ -1268,6 +1324,11 @@ pub struct WasmExecutionOutput {
     /// How many times each tracked System API call was invoked.
     pub system_api_call_counters: SystemApiCallCounters,
     pub canister_log: CanisterLog,
+    /// The instruction profile of the message if instruction profiling is
+    /// enabled.
+    pub instruction_profile: Option<InstructionProfile>,
+    /// The executed basic blocks if Wasm coverage is enabled.
+    pub coverage: Option<BlockCoverage>,
 }
 
 impl fmt::Display for WasmExecutionOutput {
@@ -1,6 +1,6 @@
 use candid::{CandidType, Deserialize, Nat, Principal};
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
-use icrc_ledger_types::icrc1::transfer::BlockIndex;
+use icrc_ledger_types::icrc1::transfer::{BlockIndex, Memo};
 use icrc_ledger_types::icrc3::blocks::GenericBlock;
 use icrc_ledger_types::icrc3::transactions::Transaction;
 
@@ -68,6 +68,76 @@ pub struct GetAccountTransactionsError {
 pub type GetAccountTransactionsResult =
     Result<GetAccountTransactionsResponse, GetAccountTransactionsError>;
 
+#[derive(Copy, Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum OperationType {
+    Mint,
+    Burn,
+    Transfer,
+    Approve,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct TransactionFilter {
+    // Only return the transactions created by the ledger at or after this time
+    // (in nanoseconds since the UNIX epoch).
+    pub start_time: Option<u64>,
+    // Only return the transactions created by the ledger before this time
+    // (in nanoseconds since the UNIX epoch).
+    pub end_time: Option<u64>,
+    // Only return the transactions with one of these operation types.
+    pub operation_types: Option<Vec<OperationType>>,
+    // Only return the transactions between the account and this counterparty,
+    // i.e. transfers in either direction and approvals of the counterparty as spender.
+    pub counterparty: Option<Account>,
+    // Only return the transactions with exactly this memo.
+    pub memo: Option<Memo>,
+}
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetAccountTransactionsFilteredArgs {
+    pub account: Account,
+    // The txid of the last transaction seen by the client.
+    // If None then the results will start from the most recent
+    // txid. If set then the results will start from the next
+    // most recent txid after start (start won't be included).
+    pub start: Option<BlockIndex>,
+    // Maximum number of transactions to fetch.
+    pub max_results: Nat,
+    pub filter: TransactionFilter,
+}
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct GetAccountTransactionsFilteredResponse {
+    pub transactions: Vec<TransactionWithId>,
+    // If set then there may be more transactions matching the filter
+    // that are older than this txid. The client should pass it as
+    // start to fetch them.
+    pub next_start: Option<BlockIndex>,
+}
+
+pub type GetAccountTransactionsFilteredResult =
+    Result<GetAccountTransactionsFilteredResponse, GetAccountTransactionsError>;
+
+#[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct AccountStats {
+    // The total amount of tokens credited to the account, including
+    // mints and collected fees.
+    pub total_in: Nat,
+    // The total amount of tokens debited from the account, including
+    // burns and paid fees.
+    pub total_out: Nat,
+    // The number of transactions the account is involved in.
+    pub num_transactions: u64,
+    // The txid and the timestamp of the oldest transaction of the account.
+    pub first_tx_id: Option<BlockIndex>,
+    pub first_activity_timestamp: Option<u64>,
+    // The txid and the timestamp of the most recent transaction of the account.
+    pub last_tx_id: Option<BlockIndex>,
+    pub last_activity_timestamp: Option<u64>,
+}
+
+pub type GetAccountStatsResult = Result<AccountStats, GetAccountTransactionsError>;
+
 #[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct ListSubaccountsArgs {
     pub owner: Principal,
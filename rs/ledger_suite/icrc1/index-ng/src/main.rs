@@ -10,9 +10,12 @@ use ic_icrc1::blocks::{encoded_block_to_generic_block, generic_block_to_encoded_
 use ic_icrc1::endpoints::StandardRecord;
 use ic_icrc1::{Block, Operation};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
-    GetAccountTransactionsResult, GetBlocksMethod, IndexArg, InitArg, ListSubaccountsArgs, Log,
-    LogEntry, Status, TransactionWithId, UpgradeArg, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    AccountStats, FeeCollectorRanges, GetAccountStatsResult, GetAccountTransactionsArgs,
+    GetAccountTransactionsError, GetAccountTransactionsFilteredArgs,
+    GetAccountTransactionsFilteredResponse, GetAccountTransactionsFilteredResult,
+    GetAccountTransactionsResponse, GetAccountTransactionsResult, GetBlocksMethod, IndexArg,
+    InitArg, ListSubaccountsArgs, Log, LogEntry, OperationType, Status, TransactionFilter,
+    TransactionWithId, UpgradeArg, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_ledger_canister_core::runtime::total_memory_size_bytes;
 use ic_ledger_core::block::{BlockIndex as BlockIndex64, BlockType, EncodedBlock};
@@ -31,7 +34,8 @@ use icrc_ledger_types::icrc3::blocks::{
     GetBlocksResult,
 };
 use icrc_ledger_types::icrc3::transactions::Transaction;
-use num_traits::ToPrimitive;
+
+use num_traits::{Bounded, ToPrimitive};
 use scopeguard::guard;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
@@ -55,9 +59,19 @@ const BLOCK_LOG_INDEX_MEMORY_ID: MemoryId = MemoryId::new(1);
 const BLOCK_LOG_DATA_MEMORY_ID: MemoryId = MemoryId::new(2);
 const ACCOUNT_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(3);
 const ACCOUNT_DATA_MEMORY_ID: MemoryId = MemoryId::new(4);
+const ACCOUNT_COUNTERPARTY_BLOCK_IDS_MEMORY_ID: MemoryId = MemoryId::new(5);
+const ACCOUNT_STATS_MEMORY_ID: MemoryId = MemoryId::new(6);
 
 const DEFAULT_RETRIEVE_BLOCKS_FROM_LEDGER_INTERVAL: Duration = Duration::from_secs(1);
 
+/// The maximum number of blocks of an account that [get_account_transactions_filtered]
+/// examines in a single request.
+const MAX_BLOCKS_TO_SCAN: usize = 10_000;
+
+/// The maximum number of already synced blocks added to the account activity
+/// indexes by a single [build_index] run, see [index_account_activity_backlog].
+const MAX_BLOCKS_TO_INDEX_ACTIVITY: u64 = 10_000;
+
 #[cfg(not(feature = "u256-tokens"))]
 type Tokens = ic_icrc1_tokens_u64::U64;
 
@@ -77,6 +91,19 @@ type AccountBlockIdsMap = StableBTreeMap<AccountBlockIdsMapKey, (), VM>;
 type AccountDataMapKey = (AccountDataType, (Blob<29>, [u8; 32]));
 type AccountDataMap = StableBTreeMap<AccountDataMapKey, Tokens, VM>;
 
+// The block indexes of the transactions between an account and a counterparty,
+// keyed by the hashes of both accounts. As above, the block indexes are stored
+// in reverse order.
+type AccountCounterpartyBlockIdsMapKey = (
+    ([u8; Sha256::DIGEST_LEN], [u8; Sha256::DIGEST_LEN]),
+    Reverse<u64>,
+);
+type AccountCounterpartyBlockIdsMap = StableBTreeMap<AccountCounterpartyBlockIdsMapKey, (), VM>;
+
+// The account is represented as in [AccountDataMapKey]
+type AccountStatsMapKey = (Blob<29>, [u8; 32]);
+type AccountStatsMap = StableBTreeMap<AccountStatsMapKey, StoredAccountStats, VM>;
+
 thread_local! {
     /// Static memory manager to manage the memory available for stable structures.
     static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> = RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
@@ -104,6 +131,17 @@ thread_local! {
         RefCell::new(AccountDataMap::init(memory_manager.get(ACCOUNT_DATA_MEMORY_ID)))
     });
 
+    /// Map that contains the block ids of the transactions between two accounts.
+    /// The accounts are hashed to save space.
+    static ACCOUNT_COUNTERPARTY_BLOCK_IDS: RefCell<AccountCounterpartyBlockIdsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AccountCounterpartyBlockIdsMap::init(memory_manager.get(ACCOUNT_COUNTERPARTY_BLOCK_IDS_MEMORY_ID)))
+    });
+
+    /// Map that contains the aggregated statistics of the accounts.
+    static ACCOUNT_STATS: RefCell<AccountStatsMap> = with_memory_manager(|memory_manager| {
+        RefCell::new(AccountStatsMap::init(memory_manager.get(ACCOUNT_STATS_MEMORY_ID)))
+    });
+
     /// Profiling data to understand cycles usage
     static PROFILING_DATA: RefCell<SpanStats> = RefCell::new(SpanStats::default());
 
@@ -136,6 +174,17 @@ struct State {
     /// index. Lower values will result in a more responsive UI, but higher costs due to increased
     /// cycle burn for the index, ledger and archive(s).
     retrieve_blocks_from_ledger_interval: Option<Duration>,
+
+    /// The number of blocks added to the account activity indexes, i.e.
+    /// [ACCOUNT_COUNTERPARTY_BLOCK_IDS] and [ACCOUNT_STATS]. It's lower than
+    /// the number of blocks in the block log only while the indexes are built
+    /// for the blocks synced before they were introduced.
+    #[serde(default)]
+    num_blocks_with_activity_indexed: u64,
+
+    /// Same as [last_fee] but for the blocks added to the account activity indexes.
+    #[serde(default)]
+    activity_last_fee: Option<Tokens>,
 }
 
 impl State {
@@ -157,6 +206,8 @@ impl Default for State {
             fee_collectors: Default::default(),
             last_fee: None,
             retrieve_blocks_from_ledger_interval: None,
+            num_blocks_with_activity_indexed: 0,
+            activity_last_fee: None,
         }
     }
 }
@@ -208,6 +259,46 @@ impl Storable for AccountDataType {
     };
 }
 
+/// The aggregated statistics of an account as stored in [ACCOUNT_STATS].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+struct StoredAccountStats {
+    total_in: Tokens,
+    total_out: Tokens,
+    num_transactions: u64,
+    /// The block index and the timestamp of the oldest transaction.
+    first_activity: Option<(BlockIndex64, u64)>,
+    /// The block index and the timestamp of the most recent transaction.
+    last_activity: Option<(BlockIndex64, u64)>,
+}
+
+impl Storable for StoredAccountStats {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        let mut buf = vec![];
+        ciborium::ser::into_writer(self, &mut buf).expect("failed to encode account stats");
+        Cow::Owned(buf)
+    }
+
+    fn from_bytes(bytes: Cow<'_, [u8]>) -> Self {
+        ciborium::de::from_reader(&bytes[..]).expect("failed to decode account stats")
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
+}
+
+impl From<StoredAccountStats> for AccountStats {
+    fn from(stats: StoredAccountStats) -> Self {
+        Self {
+            total_in: stats.total_in.into(),
+            total_out: stats.total_out.into(),
+            num_transactions: stats.num_transactions,
+            first_tx_id: stats.first_activity.map(|(id, _)| id.into()),
+            first_activity_timestamp: stats.first_activity.map(|(_, ts)| ts),
+            last_tx_id: stats.last_activity.map(|(id, _)| id.into()),
+            last_activity_timestamp: stats.last_activity.map(|(_, ts)| ts),
+        }
+    }
+}
+
 // Ephemeral data that doesn't need to be saved between upgrades
 #[derive(Clone, Debug, Default)]
 struct Cache {
@@ -222,6 +313,22 @@ fn test_account_data_type_storable() {
     );
 }
 
+#[test]
+fn test_account_stats_storable() {
+    let stats = StoredAccountStats {
+        total_in: Tokens::from(1_000_000u64),
+        total_out: Tokens::from(10_000u64),
+        num_transactions: 3,
+        first_activity: Some((1, 1_000)),
+        last_activity: Some((5, 2_000)),
+    };
+    assert_eq!(stats, StoredAccountStats::from_bytes(stats.to_bytes()));
+    assert_eq!(
+        StoredAccountStats::default(),
+        StoredAccountStats::from_bytes(StoredAccountStats::default().to_bytes())
+    );
+}
+
 /// A helper function to access the scalar state.
 fn with_state<R>(f: impl FnOnce(&State) -> R) -> R {
     STATE.with(|cell| f(cell.borrow().get()))
@@ -259,6 +366,18 @@ fn with_account_data<R>(f: impl FnOnce(&mut AccountDataMap) -> R) -> R {
     ACCOUNT_DATA.with(|cell| f(&mut cell.borrow_mut()))
 }
 
+/// A helper function to access the account counterparty block ids.
+fn with_account_counterparty_block_ids<R>(
+    f: impl FnOnce(&mut AccountCounterpartyBlockIdsMap) -> R,
+) -> R {
+    ACCOUNT_COUNTERPARTY_BLOCK_IDS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// A helper function to access the account statistics.
+fn with_account_stats<R>(f: impl FnOnce(&mut AccountStatsMap) -> R) -> R {
+    ACCOUNT_STATS.with(|cell| f(&mut cell.borrow_mut()))
+}
+
 /// A helper function that returns a decoded block stored in the
 /// block log at the given index or None if there is no block at that index.
 /// This function can trap if the index at the given block cannot be decoded
@@ -287,11 +406,22 @@ fn change_balance(account: Account, f: impl FnOnce(Tokens) -> Tokens) {
 }
 
 fn balance_key(account: Account) -> (AccountDataType, (Blob<29>, [u8; 32])) {
+    (AccountDataType::Balance, account_stats_key(account))
+}
+
+fn account_stats_key(account: Account) -> AccountStatsMapKey {
     let owner = Blob::try_from(account.owner.as_slice()).unwrap();
-    (
-        AccountDataType::Balance,
-        (owner, *account.effective_subaccount()),
-    )
+    (owner, *account.effective_subaccount())
+}
+
+/// A helper function to change the statistics of an account.
+fn change_account_stats(account: Account, f: impl FnOnce(&mut StoredAccountStats)) {
+    let key = account_stats_key(account);
+    with_account_stats(|account_stats| {
+        let mut stats = account_stats.get(&key).unwrap_or_default();
+        f(&mut stats);
+        account_stats.insert(key, stats);
+    });
 }
 
 #[init]
@@ -570,6 +700,7 @@ pub async fn build_index() -> Option<()> {
             state.is_build_index_running = false;
         });
     });
+    index_account_activity_backlog();
     let num_indexed = match find_get_blocks_method().await {
         GetBlocksMethod::GetBlocks => fetch_blocks_via_get_blocks().await?,
         GetBlocksMethod::ICRC3GetBlocks => fetch_blocks_via_icrc3().await?,
@@ -711,6 +842,12 @@ fn append_block(block_index: BlockIndex64, block: GenericBlock) {
 
         // change the balance of the involved accounts
         process_balance_changes(block_index, &decoded_block);
+
+        // update the account activity indexes unless they are still
+        // being built for the previous blocks
+        if with_state(|state| state.num_blocks_with_activity_indexed) == block_index {
+            index_account_activity(block_index, &decoded_block);
+        }
     });
 }
 
@@ -791,12 +928,7 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
                 fee,
                 ..
             } => {
-                let fee = block.effective_fee.or(fee).unwrap_or_else(|| {
-                    ic_cdk::trap(&format!(
-                        "Block {} is of type Transfer but has no fee or effective fee!",
-                        block_index
-                    ))
-                });
+                let fee = get_transfer_fee(block_index, block, fee);
                 mutate_state(|s| s.last_fee = Some(fee));
                 debit(
                     block_index,
@@ -815,23 +947,8 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
             Operation::Approve {
                 from, fee, spender, ..
             } => {
-                let fee = match fee.or(block.effective_fee) {
-                    Some(fee) => fee,
-                    // NB. There was a bug in the ledger which would create
-                    // approve blocks with the fee fields unset. The bug was
-                    // quickly fixed, but there are a few blocks on the mainnet
-                    // that don't have their fee fields populated.
-                    None => match with_state(|state| state.last_fee) {
-                        Some(last_fee) => {
-                            log!(
-                                P1,
-                                "fee and effective_fee aren't set in block {block_index}, using last transfer fee {last_fee}"
-                            );
-                            last_fee
-                        }
-                        None => ic_cdk::trap(&format!("bug: index is stuck because block with index {block_index} doesn't contain a fee and no fee has been recorded before")),
-                    }
-                };
+                let fee =
+                    get_approve_fee(block_index, block, fee, with_state(|state| state.last_fee));
 
                 // It is possible that the spender account has not existed prior to this approve transaction.
                 // Until a transfer_from transaction occurs such account would not show up in a `list_subaccounts` query as the spender is not involved in any credit or debit calls at this point.
@@ -845,6 +962,149 @@ fn process_balance_changes(block_index: BlockIndex64, block: &Block<Tokens>) {
     );
 }
 
+fn get_transfer_fee(
+    block_index: BlockIndex64,
+    block: &Block<Tokens>,
+    fee: Option<Tokens>,
+) -> Tokens {
+    block.effective_fee.or(fee).unwrap_or_else(|| {
+        ic_cdk::trap(&format!(
+            "Block {} is of type Transfer but has no fee or effective fee!",
+            block_index
+        ))
+    })
+}
+
+fn get_approve_fee(
+    block_index: BlockIndex64,
+    block: &Block<Tokens>,
+    fee: Option<Tokens>,
+    last_fee: Option<Tokens>,
+) -> Tokens {
+    match fee.or(block.effective_fee) {
+        Some(fee) => fee,
+        // NB. There was a bug in the ledger which would create
+        // approve blocks with the fee fields unset. The bug was
+        // quickly fixed, but there are a few blocks on the mainnet
+        // that don't have their fee fields populated.
+        None => match last_fee {
+            Some(last_fee) => {
+                log!(
+                    P1,
+                    "fee and effective_fee aren't set in block {block_index}, using last transfer fee {last_fee}"
+                );
+                last_fee
+            }
+            None => ic_cdk::trap(&format!("bug: index is stuck because block with index {block_index} doesn't contain a fee and no fee has been recorded before")),
+        }
+    }
+}
+
+/// Adds the block to the account activity indexes, i.e. records the block
+/// for every pair of counterparties and updates the statistics of the
+/// involved accounts. The blocks must be added in order.
+fn index_account_activity(block_index: BlockIndex64, block: &Block<Tokens>) {
+    measure_span(
+        &PROFILING_DATA,
+        "append_blocks.index_account_activity",
+        move || {
+            with_account_counterparty_block_ids(|account_counterparty_block_ids| {
+                for (account, counterparty) in get_counterparties(block) {
+                    account_counterparty_block_ids.insert(
+                        account_counterparty_block_ids_key(account, counterparty, block_index),
+                        (),
+                    );
+                }
+            });
+
+            for account in get_accounts(block) {
+                change_account_stats(account, |stats| {
+                    stats.num_transactions += 1;
+                    stats
+                        .first_activity
+                        .get_or_insert((block_index, block.timestamp));
+                    stats.last_activity = Some((block_index, block.timestamp));
+                });
+            }
+
+            // The totals mirror the balance changes in [process_balance_changes]
+            match block.transaction.operation {
+                Operation::Burn { from, amount, .. } => add_total_out(from, amount),
+                Operation::Mint { to, amount } => add_total_in(to, amount),
+                Operation::Transfer {
+                    from,
+                    to,
+                    amount,
+                    fee,
+                    ..
+                } => {
+                    let fee = get_transfer_fee(block_index, block, fee);
+                    mutate_state(|s| s.activity_last_fee = Some(fee));
+                    add_total_out(
+                        from,
+                        amount.checked_add(&fee).unwrap_or_else(Tokens::max_value),
+                    );
+                    add_total_in(to, amount);
+                    if let Some(fee_collector) = get_fee_collector(block_index, block) {
+                        add_total_in(fee_collector, fee);
+                    }
+                }
+                Operation::Approve { from, fee, .. } => {
+                    let fee = get_approve_fee(
+                        block_index,
+                        block,
+                        fee,
+                        with_state(|state| state.activity_last_fee),
+                    );
+                    add_total_out(from, fee);
+                }
+            }
+
+            mutate_state(|s| s.num_blocks_with_activity_indexed = block_index + 1);
+        },
+    );
+}
+
+/// Adds the blocks synced before the account activity indexes were
+/// introduced to the indexes, at most [MAX_BLOCKS_TO_INDEX_ACTIVITY]
+/// blocks at a time.
+fn index_account_activity_backlog() {
+    let start = with_state(|state| state.num_blocks_with_activity_indexed);
+    let end = with_blocks(|blocks| blocks.len()).min(start + MAX_BLOCKS_TO_INDEX_ACTIVITY);
+    for block_index in start..end {
+        let block = get_decoded_block(block_index).unwrap_or_else(|| {
+            trap(&format!(
+                "Block {} not found in the block log while indexing account activity",
+                block_index
+            ))
+        });
+        index_account_activity(block_index, &block);
+    }
+    if start < end {
+        log!(P1, "Indexed account activity of blocks {}..{}", start, end);
+    }
+}
+
+// The totals saturate instead of trapping as, unlike the balances,
+// they are not bounded by the total supply.
+fn add_total_in(account: Account, amount: Tokens) {
+    change_account_stats(account, |stats| {
+        stats.total_in = stats
+            .total_in
+            .checked_add(&amount)
+            .unwrap_or_else(Tokens::max_value);
+    });
+}
+
+fn add_total_out(account: Account, amount: Tokens) {
+    change_account_stats(account, |stats| {
+        stats.total_out = stats
+            .total_out
+            .checked_add(&amount)
+            .unwrap_or_else(Tokens::max_value);
+    });
+}
+
 fn debit(block_index: BlockIndex64, account: Account, amount: Tokens) {
     change_balance(account, |balance| {
         balance.checked_sub(&amount).unwrap_or_else(|| {
@@ -893,6 +1153,24 @@ fn get_accounts(block: &Block<Tokens>) -> Vec<Account> {
     }
 }
 
+// The pairs of (account, counterparty) for every account in [get_accounts]
+fn get_counterparties(block: &Block<Tokens>) -> Vec<(Account, Account)> {
+    match block.transaction.operation {
+        Operation::Burn { .. } | Operation::Mint { .. } => vec![],
+        Operation::Transfer { from, to, .. } => vec![(from, to), (to, from)],
+        Operation::Approve { from, spender, .. } => vec![(from, spender)],
+    }
+}
+
+fn get_operation_type(block: &Block<Tokens>) -> OperationType {
+    match block.transaction.operation {
+        Operation::Burn { .. } => OperationType::Burn,
+        Operation::Mint { .. } => OperationType::Mint,
+        Operation::Transfer { .. } => OperationType::Transfer,
+        Operation::Approve { .. } => OperationType::Approve,
+    }
+}
+
 fn get_fee_collector(block_index: BlockIndex64, block: &Block<Tokens>) -> Option<Account> {
     if block.fee_collector.is_some() {
         block.fee_collector
@@ -920,6 +1198,17 @@ fn account_block_ids_key(account: Account, block_index: BlockIndex64) -> Account
     (account_sha256(account), Reverse(block_index))
 }
 
+fn account_counterparty_block_ids_key(
+    account: Account,
+    counterparty: Account,
+    block_index: BlockIndex64,
+) -> AccountCounterpartyBlockIdsMapKey {
+    (
+        (account_sha256(account), account_sha256(counterparty)),
+        Reverse(block_index),
+    )
+}
+
 fn decode_icrc1_block(_txid: u64, bytes: Vec<u8>) -> GenericBlock {
     let encoded_block = EncodedBlock::from(bytes);
     encoded_block_to_generic_block(&encoded_block)
@@ -1007,6 +1296,150 @@ fn get_account_transactions(arg: GetAccountTransactionsArgs) -> GetAccountTransa
     })
 }
 
+/// Returns an error if the account activity indexes are still being built.
+fn check_account_activity_indexed() -> Result<(), GetAccountTransactionsError> {
+    let num_blocks = with_blocks(|blocks| blocks.len());
+    let num_indexed = with_state(|state| state.num_blocks_with_activity_indexed);
+    if num_indexed < num_blocks {
+        return Err(GetAccountTransactionsError {
+            message: format!(
+                "The account activity index is being built: {} out of {} blocks indexed",
+                num_indexed, num_blocks
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the index of the first block with a timestamp greater than
+/// or equal to the given one. This relies on the block timestamps being
+/// monotonically non-decreasing, which is guaranteed by the ledger.
+fn first_block_at_or_after(timestamp: u64) -> BlockIndex64 {
+    let (mut lo, mut hi) = (0, with_blocks(|blocks| blocks.len()));
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let block = get_decoded_block(mid)
+            .unwrap_or_else(|| trap(&format!("Block {} not found in the block log", mid)));
+        if block.timestamp < timestamp {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+    lo
+}
+
+fn matches_filter(filter: &TransactionFilter, block: &Block<Tokens>) -> bool {
+    if let Some(operation_types) = &filter.operation_types {
+        if !operation_types.contains(&get_operation_type(block)) {
+            return false;
+        }
+    }
+    if let Some(memo) = &filter.memo {
+        if block.transaction.memo.as_ref() != Some(memo) {
+            return false;
+        }
+    }
+    true
+}
+
+#[query]
+#[candid_method(query)]
+fn get_account_transactions_filtered(
+    arg: GetAccountTransactionsFilteredArgs,
+) -> GetAccountTransactionsFilteredResult {
+    check_account_activity_indexed()?;
+
+    let length = arg
+        .max_results
+        .0
+        .to_u64()
+        .expect("The length must be a u64!")
+        .min(with_state(|opts| opts.max_blocks_per_response))
+        .min(usize::MAX as u64) as usize;
+    let start = arg
+        .start
+        .map_or(u64::MAX, |n| n.0.to_u64().expect("start must be a u64!"));
+    let filter = arg.filter;
+
+    // The time range translates to a range of block indexes: [lowest, upper)
+    let lowest = filter.start_time.map_or(0, first_block_at_or_after);
+    let upper = filter.end_time.map_or(start, |end_time| {
+        start.min(first_block_at_or_after(end_time))
+    });
+
+    // Block indexes of the account (and the counterparty) in the range, most
+    // recent first. One more than the limit is fetched to know if there are more.
+    let indices = match filter.counterparty {
+        None => {
+            let key = account_block_ids_key(arg.account, upper);
+            with_account_block_ids(|account_block_ids| {
+                account_block_ids
+                    .range(key..)
+                    .take_while(|(k, _)| k.0 == key.0 && k.1 .0 >= lowest)
+                    .filter(|(k, _)| k.1 .0 < upper)
+                    .take(MAX_BLOCKS_TO_SCAN + 1)
+                    .map(|(k, _)| k.1 .0)
+                    .collect::<Vec<BlockIndex64>>()
+            })
+        }
+        Some(counterparty) => {
+            let key = account_counterparty_block_ids_key(arg.account, counterparty, upper);
+            with_account_counterparty_block_ids(|account_counterparty_block_ids| {
+                account_counterparty_block_ids
+                    .range(key..)
+                    .take_while(|(k, _)| k.0 == key.0 && k.1 .0 >= lowest)
+                    .filter(|(k, _)| k.1 .0 < upper)
+                    .take(MAX_BLOCKS_TO_SCAN + 1)
+                    .map(|(k, _)| k.1 .0)
+                    .collect::<Vec<BlockIndex64>>()
+            })
+        }
+    };
+
+    let mut transactions = vec![];
+    let mut next_start = None;
+    let mut last_examined = None;
+    for (scanned, id) in indices.iter().copied().enumerate() {
+        if transactions.len() == length || scanned == MAX_BLOCKS_TO_SCAN {
+            // there may be more matching transactions, the client continues
+            // from the last examined block (or from [upper] if none was examined)
+            next_start = Some(last_examined.unwrap_or(upper).into());
+            break;
+        }
+        last_examined = Some(id);
+        let block = get_decoded_block(id).unwrap_or_else(|| {
+            trap(&format!(
+                "Block {} not found in the block log, account blocks map is corrupted!",
+                id
+            ))
+        });
+        if matches_filter(&filter, &block) {
+            transactions.push(TransactionWithId {
+                id: id.into(),
+                transaction: block.into(),
+            });
+        }
+    }
+
+    Ok(GetAccountTransactionsFilteredResponse {
+        transactions,
+        next_start,
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn get_account_stats(account: Account) -> GetAccountStatsResult {
+    check_account_activity_indexed()?;
+    let stats = with_account_stats(|account_stats| {
+        account_stats
+            .get(&account_stats_key(account))
+            .unwrap_or_default()
+    });
+    Ok(stats.into())
+}
+
 fn encoded_block_bytes_to_flat_transaction(
     block_index: BlockIndex64,
     block: Vec<u8>,
@@ -1145,6 +1578,11 @@ pub fn encode_metrics(w: &mut ic_metrics_encoder::MetricsEncoder<Vec<u8>>) -> st
         with_blocks(|blocks| blocks.len()) as f64,
         "Total number of blocks stored in the stable memory.",
     )?;
+    w.encode_gauge(
+        "index_number_of_blocks_with_activity_indexed",
+        with_state(|state| state.num_blocks_with_activity_indexed) as f64,
+        "Number of blocks added to the account activity indexes.",
+    )?;
     w.encode_gauge(
         "index_last_wait_time",
         with_state(|state| state.last_wait_time)
@@ -7,9 +7,12 @@ use candid::{Decode, Encode, Nat, Principal};
 use ic_agent::identity::Identity;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_icrc1_index_ng::{
-    FeeCollectorRanges, GetAccountTransactionsArgs, GetAccountTransactionsResponse,
+    AccountStats, FeeCollectorRanges, GetAccountStatsResult, GetAccountTransactionsArgs,
+    GetAccountTransactionsFilteredArgs, GetAccountTransactionsFilteredResponse,
+    GetAccountTransactionsFilteredResult, GetAccountTransactionsResponse,
     GetAccountTransactionsResult, GetBlocksResponse, IndexArg, InitArg as IndexInitArg,
-    ListSubaccountsArgs, TransactionWithId, DEFAULT_MAX_BLOCKS_PER_RESPONSE,
+    ListSubaccountsArgs, OperationType, TransactionFilter, TransactionWithId,
+    DEFAULT_MAX_BLOCKS_PER_RESPONSE,
 };
 use ic_icrc1_ledger::{ChangeFeeCollector, LedgerArgument, UpgradeArgs as LedgerUpgradeArgs};
 use ic_icrc1_test_utils::{
@@ -18,7 +21,7 @@ use ic_icrc1_test_utils::{
 use ic_rosetta_test_utils::test_http_request_decoding_quota;
 use ic_state_machine_tests::StateMachine;
 use icrc_ledger_types::icrc1::account::{Account, Subaccount};
-use icrc_ledger_types::icrc1::transfer::{BlockIndex, TransferArg, TransferError};
+use icrc_ledger_types::icrc1::transfer::{BlockIndex, Memo, TransferArg, TransferError};
 use icrc_ledger_types::icrc2::approve::{ApproveArgs, ApproveError};
 use icrc_ledger_types::icrc3::blocks::GetBlocksRequest;
 use icrc_ledger_types::icrc3::transactions::{Mint, Transaction, Transfer};
@@ -288,6 +291,40 @@ fn get_account_transactions(
         .expect("Failed to perform GetAccountTransactionsArgs")
 }
 
+fn get_account_transactions_filtered(
+    env: &StateMachine,
+    index_id: CanisterId,
+    account: Account,
+    start: Option<u64>,
+    max_results: u64,
+    filter: TransactionFilter,
+) -> GetAccountTransactionsFilteredResponse {
+    let req = GetAccountTransactionsFilteredArgs {
+        account,
+        start: start.map(|n| n.into()),
+        max_results: max_results.into(),
+        filter,
+    };
+    let req = Encode!(&req).expect("Failed to encode GetAccountTransactionsFilteredArgs");
+    let res = env
+        .execute_ingress(index_id, "get_account_transactions_filtered", req)
+        .expect("Failed to get_account_transactions_filtered")
+        .bytes();
+    Decode!(&res, GetAccountTransactionsFilteredResult)
+        .expect("Failed to decode GetAccountTransactionsFilteredResult")
+        .expect("Failed to perform get_account_transactions_filtered")
+}
+
+fn get_account_stats(env: &StateMachine, index_id: CanisterId, account: Account) -> AccountStats {
+    let res = env
+        .execute_ingress(index_id, "get_account_stats", Encode!(&account).unwrap())
+        .expect("Failed to get_account_stats")
+        .bytes();
+    Decode!(&res, GetAccountStatsResult)
+        .expect("Failed to decode GetAccountStatsResult")
+        .expect("Failed to perform get_account_stats")
+}
+
 fn list_subaccounts(
     env: &StateMachine,
     index: CanisterId,
@@ -775,6 +812,191 @@ fn test_get_account_transactions_pagination() {
     }
 }
 
+#[test]
+fn test_get_account_transactions_filtered() {
+    let env = &StateMachine::new();
+    let fee_collector = account(42, 0);
+    let minter = minter_identity().sender().unwrap();
+    let ledger_id = install_ledger(
+        env,
+        vec![(account(1, 0), 10_000_000)], // txid: 0
+        default_archive_options(),
+        Some(fee_collector),
+        minter,
+    );
+    let index_id = install_index_ng(env, index_init_arg_without_interval(ledger_id));
+
+    let transfer_with_memo = |from: Account, to: Account, amount: u64, memo: Vec<u8>| {
+        env.advance_time(Duration::from_secs(1));
+        let req = TransferArg {
+            from_subaccount: from.subaccount,
+            to,
+            amount: amount.into(),
+            created_at_time: None,
+            fee: None,
+            memo: Some(Memo::from(memo)),
+        };
+        icrc1_transfer(env, ledger_id, from.owner.into(), req)
+    };
+
+    transfer_with_memo(account(1, 0), account(2, 0), 100_000, vec![1]); // txid: 1
+    env.advance_time(Duration::from_secs(1));
+    transfer(env, ledger_id, account(1, 0), account(3, 0), 200_000); // txid: 2
+    env.advance_time(Duration::from_secs(1));
+    approve(env, ledger_id, account(1, 0), account(2, 0), 1_000); // txid: 3
+    transfer_with_memo(account(2, 0), account(1, 0), 50_000, vec![1]); // txid: 4
+    env.advance_time(Duration::from_secs(1));
+    transfer(env, ledger_id, account(1, 0), account(2, 0), 300_000); // txid: 5
+
+    wait_until_sync_is_completed(env, index_id, ledger_id);
+
+    let filtered_ids = |start: Option<u64>, max_results: u64, filter: TransactionFilter| {
+        let res = get_account_transactions_filtered(
+            env,
+            index_id,
+            account(1, 0),
+            start,
+            max_results,
+            filter,
+        );
+        let ids: Vec<u64> = res
+            .transactions
+            .iter()
+            .map(|tx| tx.id.0.to_u64().unwrap())
+            .collect();
+        (ids, res.next_start.map(|n| n.0.to_u64().unwrap()))
+    };
+
+    // no filter
+    assert_eq!(
+        filtered_ids(None, u64::MAX, TransactionFilter::default()),
+        (vec![5, 4, 3, 2, 1, 0], None)
+    );
+
+    // counterparty
+    let by_counterparty = TransactionFilter {
+        counterparty: Some(account(2, 0)),
+        ..Default::default()
+    };
+    assert_eq!(
+        filtered_ids(None, u64::MAX, by_counterparty.clone()),
+        (vec![5, 4, 3, 1], None)
+    );
+    assert_eq!(
+        filtered_ids(None, 2, by_counterparty.clone()),
+        (vec![5, 4], Some(4))
+    );
+    assert_eq!(
+        filtered_ids(Some(4), 2, by_counterparty),
+        (vec![3, 1], None)
+    );
+
+    // operation types
+    let by_operation_types = |operation_types: Vec<OperationType>| TransactionFilter {
+        operation_types: Some(operation_types),
+        ..Default::default()
+    };
+    assert_eq!(
+        filtered_ids(
+            None,
+            u64::MAX,
+            by_operation_types(vec![OperationType::Approve])
+        ),
+        (vec![3], None)
+    );
+    assert_eq!(
+        filtered_ids(
+            None,
+            u64::MAX,
+            by_operation_types(vec![OperationType::Mint, OperationType::Approve])
+        ),
+        (vec![3, 0], None)
+    );
+    assert_eq!(
+        filtered_ids(
+            None,
+            u64::MAX,
+            by_operation_types(vec![OperationType::Burn])
+        ),
+        (vec![], None)
+    );
+
+    // memo
+    let by_memo = TransactionFilter {
+        memo: Some(Memo::from(vec![1])),
+        ..Default::default()
+    };
+    assert_eq!(filtered_ids(None, u64::MAX, by_memo), (vec![4, 1], None));
+
+    // time range, the end is excluded
+    let timestamps: Vec<u64> = get_account_transactions(env, index_id, account(1, 0), None, 10)
+        .transactions
+        .into_iter()
+        .rev()
+        .map(|tx| tx.transaction.timestamp)
+        .collect();
+    let by_time_range = TransactionFilter {
+        start_time: Some(timestamps[2]),
+        end_time: Some(timestamps[5]),
+        ..Default::default()
+    };
+    assert_eq!(
+        filtered_ids(None, u64::MAX, by_time_range),
+        (vec![4, 3, 2], None)
+    );
+
+    // all the filters combined
+    let combined = TransactionFilter {
+        start_time: Some(timestamps[1]),
+        end_time: None,
+        operation_types: Some(vec![OperationType::Transfer]),
+        counterparty: Some(account(2, 0)),
+        memo: Some(Memo::from(vec![1])),
+    };
+    assert_eq!(filtered_ids(None, u64::MAX, combined), (vec![4, 1], None));
+
+    // account statistics
+    let stats = get_account_stats(env, index_id, account(1, 0));
+    assert_eq!(stats.total_in, Nat::from(10_000_000u64 + 50_000));
+    assert_eq!(
+        stats.total_out,
+        Nat::from(100_000u64 + 200_000 + 300_000 + 4 * FEE)
+    );
+    assert_eq!(
+        stats.total_in - stats.total_out,
+        Nat::from(icrc1_balance_of(env, index_id, account(1, 0)))
+    );
+    assert_eq!(stats.num_transactions, 6);
+    assert_eq!(stats.first_tx_id, Some(Nat::from(0u64)));
+    assert_eq!(stats.first_activity_timestamp, Some(timestamps[0]));
+    assert_eq!(stats.last_tx_id, Some(Nat::from(5u64)));
+    assert_eq!(stats.last_activity_timestamp, Some(timestamps[5]));
+
+    // the fee collector collects the fees of the transfers
+    let stats = get_account_stats(env, index_id, fee_collector);
+    assert_eq!(stats.total_in, Nat::from(4 * FEE));
+    assert_eq!(stats.total_out, Nat::from(0u64));
+    assert_eq!(
+        stats.total_in,
+        Nat::from(icrc1_balance_of(env, index_id, fee_collector))
+    );
+
+    // accounts without transactions have empty statistics
+    let stats = get_account_stats(env, index_id, account(7, 0));
+    assert_eq!(
+        stats,
+        AccountStats {
+            total_in: Nat::from(0u64),
+            total_out: Nat::from(0u64),
+            num_transactions: 0,
+            first_tx_id: None,
+            first_activity_timestamp: None,
+            last_tx_id: None,
+            last_activity_timestamp: None,
+        }
+    );
+}
+
 #[test]
 fn test_icrc1_balance_of() {
     // 1 case only because the test is expensive to run.
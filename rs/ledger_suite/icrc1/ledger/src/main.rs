@@ -56,6 +56,10 @@ use icrc_ledger_types::{
 use icrc_ledger_types::{
     icrc1::transfer::{TransferArg, TransferError},
     icrc2::transfer_from::{TransferFromArgs, TransferFromError},
+    icrc4::batch::{
+        BalanceQueryArgs, BalanceQueryResult, BalanceQueryResults, TransferBatchArgs,
+        TransferBatchError, TransferBatchResults,
+    },
 };
 use num_traits::{bounds::Bounded, ToPrimitive};
 use serde_bytes::ByteBuf;
@@ -63,6 +67,10 @@ use std::cell::RefCell;
 use std::io::{Read, Write};
 
 const MAX_MESSAGE_SIZE: u64 = 1024 * 1024;
+/// The maximum number of transfers in a single icrc4_transfer_batch request.
+const MAX_TRANSFER_BATCH_SIZE: usize = 100;
+/// The maximum number of accounts in a single icrc4_balance_of_batch request.
+const MAX_BALANCE_QUERY_BATCH_SIZE: usize = 1_000;
 
 #[cfg(not(feature = "u256-tokens"))]
 pub type Tokens = ic_icrc1_tokens_u64::U64;
@@ -594,6 +602,91 @@ async fn icrc1_transfer(arg: TransferArg) -> Result<Nat, TransferError> {
     })
 }
 
+#[update]
+#[candid_method(update)]
+async fn icrc4_transfer_batch(args: TransferBatchArgs) -> TransferBatchResults {
+    // Batches above the limit are rejected as a whole with a single batch-level error.
+    if args.len() > MAX_TRANSFER_BATCH_SIZE {
+        return vec![Some(Err(TransferBatchError::TooManyRequests {
+            limit: Nat::from(MAX_TRANSFER_BATCH_SIZE),
+        }))];
+    }
+
+    let caller = ic_cdk::api::caller();
+
+    // Each transfer is applied as if it was submitted with a separate icrc1_transfer call,
+    // i.e. it results in its own block and is deduplicated against all previous transactions,
+    // including the ones that precede it in the same batch.
+    let results: TransferBatchResults = args
+        .into_iter()
+        .map(|arg| {
+            let from_account = Account {
+                owner: caller,
+                subaccount: arg.from_subaccount,
+            };
+            let result = execute_transfer_not_async(
+                from_account,
+                arg.to,
+                None,
+                arg.fee,
+                arg.amount,
+                arg.memo,
+                arg.created_at_time,
+            )
+            .map(Nat::from)
+            .map_err(convert_transfer_error)
+            .map_err(|err| {
+                let err: TransferError = match err.try_into() {
+                    Ok(err) => err,
+                    Err(err) => ic_cdk::trap(&err),
+                };
+                TransferBatchError::from(err)
+            });
+            Some(result)
+        })
+        .collect();
+
+    // NB. we need to set the certified data before the first async call to make sure that the
+    // blockchain state agrees with the certificate while archiving is in progress.
+    ic_cdk::api::set_certified_data(&Access::with_ledger(Ledger::root_hash));
+
+    archive_blocks::<Access>(&LOG, MAX_MESSAGE_SIZE).await;
+    results
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc4_balance_of_batch(args: BalanceQueryArgs) -> BalanceQueryResults {
+    if args.len() > MAX_BALANCE_QUERY_BATCH_SIZE {
+        ic_cdk::trap(&format!(
+            "the number of accounts in the batch {} is above the allowed limit of {}",
+            args.len(),
+            MAX_BALANCE_QUERY_BATCH_SIZE
+        ))
+    }
+
+    Access::with_ledger(|ledger| {
+        args.into_iter()
+            .map(|account| BalanceQueryResult {
+                balance: ledger.balances().account_balance(&account).into(),
+                account,
+            })
+            .collect()
+    })
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc4_maximum_update_batch_size() -> Option<Nat> {
+    Some(Nat::from(MAX_TRANSFER_BATCH_SIZE))
+}
+
+#[query]
+#[candid_method(query)]
+fn icrc4_maximum_query_batch_size() -> Option<Nat> {
+    Some(Nat::from(MAX_BALANCE_QUERY_BATCH_SIZE))
+}
+
 #[update]
 #[candid_method(update)]
 async fn icrc2_transfer_from(arg: TransferFromArgs) -> Result<Nat, TransferFromError> {
@@ -661,6 +754,10 @@ fn supported_standards() -> Vec<StandardRecord> {
             name: "ICRC-3".to_string(),
             url: "https://github.com/dfinity/ICRC-1/tree/main/standards/ICRC-3".to_string(),
         },
+        StandardRecord {
+            name: "ICRC-4".to_string(),
+            url: "https://github.com/dfinity/ICRC/tree/main/ICRCs/ICRC-4".to_string(),
+        },
         StandardRecord {
             name: "ICRC-21".to_string(),
             url: "https://github.com/dfinity/wg-identity-authentication/blob/main/topics/ICRC-21/icrc_21_consent_msg.md".to_string(),
@@ -30,6 +30,9 @@ use icrc_ledger_types::icrc3::archive::{GetArchivesArgs, GetArchivesResult, Quer
 use icrc_ledger_types::icrc3::blocks::{
     ArchivedBlocks, BlockWithId, GetBlocksRequest, GetBlocksResponse, GetBlocksResult,
 };
+use icrc_ledger_types::icrc4::batch::{
+    BalanceQueryResults, TransferBatchError, TransferBatchResults,
+};
 use num_traits::ToPrimitive;
 use std::collections::BTreeMap;
 use std::path::PathBuf;
@@ -1207,6 +1210,202 @@ fn test_icrc3_get_blocks_number_of_blocks_limit() {
     check_icrc3_get_block_limit(vec![(0, 1), (0, 100)]);
 }
 
+#[test]
+fn test_icrc4_transfer_batch() {
+    let env = StateMachine::new();
+    let from = account(1);
+
+    let args = LedgerArgument::Init(InitArgs {
+        minting_account: MINTER,
+        fee_collector_account: None,
+        initial_balances: vec![(from, Nat::from(10_000_000u64))],
+        transfer_fee: FEE.into(),
+        decimals: Some(DECIMAL_PLACES),
+        token_name: TOKEN_NAME.to_string(),
+        token_symbol: TOKEN_SYMBOL.to_string(),
+        metadata: vec![],
+        archive_options: ArchiveOptions {
+            trigger_threshold: ARCHIVE_TRIGGER_THRESHOLD as usize,
+            num_blocks_to_archive: NUM_BLOCKS_TO_ARCHIVE as usize,
+            node_max_memory_size_bytes: None,
+            max_message_size_bytes: None,
+            controller_id: PrincipalId::new_user_test_id(100),
+            more_controller_ids: None,
+            cycles_for_archive_creation: None,
+            max_transactions_per_response: None,
+        },
+        max_memo_length: None,
+        feature_flags: None,
+        maximum_number_of_accounts: None,
+        accounts_overflow_trim_quantity: None,
+    });
+    let ledger_id = env
+        .install_canister(ledger_wasm(), Encode!(&args).unwrap(), None)
+        .expect("Unable to install the ledger");
+
+    let query_batch_size = |method: &str| {
+        let res = env
+            .query(ledger_id, method, Encode!().unwrap())
+            .expect("Unable to query the batch size")
+            .bytes();
+        Decode!(&res, Option<Nat>).unwrap()
+    };
+    assert_eq!(
+        query_batch_size("icrc4_maximum_update_batch_size"),
+        Some(Nat::from(100u64))
+    );
+    assert_eq!(
+        query_batch_size("icrc4_maximum_query_batch_size"),
+        Some(Nat::from(1_000u64))
+    );
+
+    let transfer_batch = |args: Vec<TransferArg>| {
+        env.execute_ingress_as(
+            from.owner.into(),
+            ledger_id,
+            "icrc4_transfer_batch",
+            Encode!(&args).unwrap(),
+        )
+        .map(|res| Decode!(&res.bytes(), TransferBatchResults).unwrap())
+    };
+
+    let balance_of_batch = |accounts: Vec<Account>| {
+        let res = env
+            .query(
+                ledger_id,
+                "icrc4_balance_of_batch",
+                Encode!(&accounts).unwrap(),
+            )
+            .expect("Unable to perform icrc4_balance_of_batch")
+            .bytes();
+        Decode!(&res, BalanceQueryResults)
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.account, r.balance))
+            .collect::<Vec<_>>()
+    };
+
+    let now = env
+        .time()
+        .duration_since(std::time::SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64;
+    let transfer_arg = |to: Account, amount: u64| TransferArg {
+        from_subaccount: None,
+        to,
+        amount: amount.into(),
+        fee: None,
+        created_at_time: Some(now),
+        memo: None,
+    };
+
+    let results = transfer_batch(vec![
+        transfer_arg(account(2), 100_000),
+        transfer_arg(account(3), 200_000),
+        // Duplicates are detected within the same batch
+        transfer_arg(account(2), 100_000),
+        transfer_arg(account(4), 100_000_000),
+        TransferArg {
+            fee: Some(Nat::from(FEE + 1)),
+            ..transfer_arg(account(4), 100_000)
+        },
+    ])
+    .expect("Unable to perform icrc4_transfer_batch");
+
+    assert_eq!(
+        results,
+        vec![
+            Some(Ok(Nat::from(1u64))),
+            Some(Ok(Nat::from(2u64))),
+            Some(Err(TransferBatchError::Duplicate {
+                duplicate_of: Nat::from(1u64)
+            })),
+            Some(Err(TransferBatchError::InsufficientFunds {
+                balance: Nat::from(10_000_000u64 - 300_000 - 2 * FEE)
+            })),
+            Some(Err(TransferBatchError::BadFee {
+                expected_fee: Nat::from(FEE)
+            })),
+        ]
+    );
+
+    assert_eq!(
+        balance_of_batch(vec![from, account(2), account(3), account(4)]),
+        vec![
+            (from, Nat::from(10_000_000u64 - 300_000 - 2 * FEE)),
+            (account(2), Nat::from(100_000u64)),
+            (account(3), Nat::from(200_000u64)),
+            (account(4), Nat::from(0u64)),
+        ]
+    );
+
+    // Duplicates are detected across the batches and the single transfers
+    let results = transfer_batch(vec![transfer_arg(account(3), 200_000)])
+        .expect("Unable to perform icrc4_transfer_batch");
+    assert_eq!(
+        results,
+        vec![Some(Err(TransferBatchError::Duplicate {
+            duplicate_of: Nat::from(2u64)
+        }))]
+    );
+
+    // Each transfer is recorded as a regular ICRC-3 transfer block
+    let res = icrc3_get_blocks(
+        &env,
+        ledger_id,
+        vec![GetBlocksRequest {
+            start: Nat::from(1u64),
+            length: Nat::from(10u64),
+        }],
+    );
+    assert_eq!(res.log_length, 3u64);
+    let transfers = res
+        .blocks
+        .into_iter()
+        .map(|b| {
+            let block = Block::<Tokens>::try_from(Value::from(b.block)).unwrap();
+            match block.transaction.operation {
+                Operation::Transfer {
+                    from, to, amount, ..
+                } => (from, to, amount),
+                op => panic!("unexpected operation {op:?}"),
+            }
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        transfers,
+        vec![
+            (from, account(2), Tokens::from(100_000u64)),
+            (from, account(3), Tokens::from(200_000u64)),
+        ]
+    );
+
+    // Batches above the limit are rejected as a whole
+    let results = transfer_batch(vec![transfer_arg(account(2), 1); 101])
+        .expect("Unable to perform icrc4_transfer_batch");
+    assert_eq!(
+        results,
+        vec![Some(Err(TransferBatchError::TooManyRequests {
+            limit: Nat::from(100u64)
+        }))]
+    );
+    assert_eq!(
+        balance_of_batch(vec![account(2)]),
+        vec![(account(2), Nat::from(100_000u64))]
+    );
+
+    let err = env
+        .query(
+            ledger_id,
+            "icrc4_balance_of_batch",
+            Encode!(&vec![from; 1_001]).unwrap(),
+        )
+        .unwrap_err();
+    assert!(err
+        .description()
+        .contains("is above the allowed limit of 1000"));
+}
+
 mod verify_written_blocks {
     use super::*;
     use ic_icrc1_ledger::FeatureFlags;
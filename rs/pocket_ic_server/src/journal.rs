@@ -0,0 +1,295 @@
+/// This module contains the recording of operations executed on PocketIC instances
+/// into journal files and the deterministic replay of such journal files.
+///
+/// A journal file is a sequence of JSON values, one per line: the first line is a
+/// [JournalHeader] describing how the instance was created and every further line is a
+/// [JournalEntry] describing an operation executed on the instance (in execution order).
+/// If the instance executes an operation that cannot be recorded (e.g., a call through
+/// the IC HTTP interface), then the last line is a [JournalEnd] and no further operations are recorded.
+/// Replaying a journal file creates a fresh instance from the header, executes all recorded
+/// operations on it, and reports the first operation whose outcome diverges from the recording.
+use crate::pocket_ic::{
+    AddCycles, AdvanceTimeAndTick, AwaitIngressMessage, ExecuteIngressMessage, GetCanisterHttp,
+    GetCyclesBalance, GetStableMemory, GetSubnet, GetTime, GetTopology, GetXNetMessages,
+    HoldXNetMessages, MockCanisterHttp, PocketIc, ProcessXNetMessage, PubKey, Query,
+    ReleaseXNetMessages, SetStableMemory, SetTime, SubmitIngressMessage, Tick,
+};
+use crate::state_api::state::OpOut;
+use crate::{OpId, Operation};
+use pocket_ic::common::rest::InstanceConfig;
+use serde::{Deserialize, Serialize};
+use slog::Level;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// The operations that are recorded into a journal.
+/// Operations of the IC HTTP interface (e.g., `CallRequest`) are not recorded
+/// because their inputs are raw HTTP requests. Those of them that can modify the instance
+/// end the journal (see [JournalEnd]).
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum RecordedOperation {
+    SetTime(SetTime),
+    GetTopology(GetTopology),
+    GetTime(GetTime),
+    GetCanisterHttp(GetCanisterHttp),
+    MockCanisterHttp(MockCanisterHttp),
+    GetXNetMessages(GetXNetMessages),
+    HoldXNetMessages(HoldXNetMessages),
+    ReleaseXNetMessages(ReleaseXNetMessages),
+    ProcessXNetMessage(ProcessXNetMessage),
+    PubKey(PubKey),
+    Tick(Tick),
+    AdvanceTimeAndTick(AdvanceTimeAndTick),
+    SubmitIngressMessage(SubmitIngressMessage),
+    AwaitIngressMessage(AwaitIngressMessage),
+    ExecuteIngressMessage(ExecuteIngressMessage),
+    Query(Query),
+    SetStableMemory(SetStableMemory),
+    GetStableMemory(GetStableMemory),
+    GetCyclesBalance(GetCyclesBalance),
+    GetSubnet(GetSubnet),
+    AddCycles(AddCycles),
+}
+
+impl RecordedOperation {
+    fn operation(&self) -> &dyn Operation {
+        match self {
+            RecordedOperation::SetTime(op) => op,
+            RecordedOperation::GetTopology(op) => op,
+            RecordedOperation::GetTime(op) => op,
+            RecordedOperation::GetCanisterHttp(op) => op,
+            RecordedOperation::MockCanisterHttp(op) => op,
+            RecordedOperation::GetXNetMessages(op) => op,
+            RecordedOperation::HoldXNetMessages(op) => op,
+            RecordedOperation::ReleaseXNetMessages(op) => op,
+            RecordedOperation::ProcessXNetMessage(op) => op,
+            RecordedOperation::PubKey(op) => op,
+            RecordedOperation::Tick(op) => op,
+            RecordedOperation::AdvanceTimeAndTick(op) => op,
+            RecordedOperation::SubmitIngressMessage(op) => op,
+            RecordedOperation::AwaitIngressMessage(op) => op,
+            RecordedOperation::ExecuteIngressMessage(op) => op,
+            RecordedOperation::Query(op) => op,
+            RecordedOperation::SetStableMemory(op) => op,
+            RecordedOperation::GetStableMemory(op) => op,
+            RecordedOperation::GetCyclesBalance(op) => op,
+            RecordedOperation::GetSubnet(op) => op,
+            RecordedOperation::AddCycles(op) => op,
+        }
+    }
+}
+
+impl Operation for RecordedOperation {
+    fn compute(&self, pocket_ic: &mut PocketIc) -> OpOut {
+        self.operation().compute(pocket_ic)
+    }
+
+    fn id(&self) -> OpId {
+        self.operation().id()
+    }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(self.clone())
+    }
+}
+
+/// Describes how the recorded instance was created.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalHeader {
+    pub seed: u64,
+    pub instance_config: InstanceConfig,
+}
+
+/// An operation executed on the recorded instance.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalEntry {
+    /// The position of the operation in the journal (starting at 0).
+    pub seq_no: u64,
+    /// The time of the instance (in nanoseconds since the Unix epoch) before the operation.
+    pub time: u64,
+    pub operation: RecordedOperation,
+    /// The outcome of the operation formatted using its `Debug` implementation.
+    pub output: String,
+}
+
+/// The last line of a journal whose instance executed an operation that cannot be recorded:
+/// the journal cannot be replayed beyond that operation.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct JournalEnd {
+    /// The position of the unrecordable operation in the journal.
+    pub seq_no: u64,
+    /// The time of the instance (in nanoseconds since the Unix epoch) before the operation.
+    pub time: u64,
+    /// The identifier of the unrecordable operation.
+    pub unrecordable_operation: String,
+}
+
+impl std::fmt::Display for JournalEnd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Operation #{} ({}) could not be recorded and the journal ends before it.",
+            self.seq_no, self.unrecordable_operation
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JournalLine {
+    Entry(JournalEntry),
+    End(JournalEnd),
+}
+
+/// Appends the operations executed on an instance to a journal file.
+pub struct Journal {
+    file: File,
+    next_seq_no: u64,
+}
+
+impl Journal {
+    /// Creates a new journal file at the given path and writes the given header into it.
+    pub fn create(path: &Path, header: &JournalHeader) -> std::io::Result<Self> {
+        let mut journal = Self {
+            file: File::create(path)?,
+            next_seq_no: 0,
+        };
+        journal.append(header)?;
+        Ok(journal)
+    }
+
+    /// Records an operation executed on the instance at the given time with the given outcome.
+    pub fn record(
+        &mut self,
+        time: u64,
+        operation: RecordedOperation,
+        output: &OpOut,
+    ) -> std::io::Result<()> {
+        let entry = JournalEntry {
+            seq_no: self.next_seq_no,
+            time,
+            operation,
+            output: format!("{:?}", output),
+        };
+        self.next_seq_no += 1;
+        self.append(&entry)
+    }
+
+    /// Ends the journal with an operation executed on the instance at the given time
+    /// that cannot be recorded.
+    pub fn stop(mut self, time: u64, op_id: &OpId) -> std::io::Result<()> {
+        let end = JournalEnd {
+            seq_no: self.next_seq_no,
+            time,
+            unrecordable_operation: op_id.0.clone(),
+        };
+        self.append(&end)
+    }
+
+    // Every value is written (unbuffered) on a separate line so that the journal
+    // is complete up to the last executed operation even if the server crashes.
+    fn append<T: Serialize>(&mut self, value: &T) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+        self.file.write_all(&line)
+    }
+}
+
+/// The first operation whose replay diverges from the recording.
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    pub entry: JournalEntry,
+    /// The time of the replayed instance (in nanoseconds since the Unix epoch) before the operation.
+    pub replayed_time: u64,
+    /// The outcome of the replayed operation formatted using its `Debug` implementation.
+    pub replayed_output: String,
+}
+
+impl std::fmt::Display for Divergence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Operation #{} ({}) diverged:",
+            self.entry.seq_no,
+            self.entry.operation.id().0
+        )?;
+        writeln!(
+            f,
+            "  recorded time: {}, replayed time: {}",
+            self.entry.time, self.replayed_time
+        )?;
+        writeln!(f, "  recorded output: {}", self.entry.output)?;
+        write!(f, "  replayed output: {}", self.replayed_output)
+    }
+}
+
+/// The outcome of replaying a journal.
+#[derive(Clone, Debug)]
+pub enum ReplayOutcome {
+    /// All operations were replayed faithfully.
+    Matched,
+    /// The replay of an operation diverged from the recording.
+    Diverged(Divergence),
+    /// All recorded operations were replayed faithfully, but the journal ends
+    /// with an operation that could not be recorded.
+    Incomplete(JournalEnd),
+}
+
+/// Replays the journal file at the given path against a fresh instance
+/// and returns the first operation whose replay diverges from the recording
+/// or the operation that could not be recorded at the end of the journal.
+/// Note that an instance created from a state directory is replayed against
+/// the current contents of that state directory.
+/// This function must not be called from an async context.
+pub fn replay_journal(runtime: Arc<Runtime>, path: &Path) -> Result<ReplayOutcome, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open journal {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+    let header: JournalHeader = match lines.next() {
+        Some(line) => {
+            let line = line.map_err(|e| format!("Failed to read journal header: {}", e))?;
+            serde_json::from_str(&line)
+                .map_err(|e| format!("Failed to parse journal header: {}", e))?
+        }
+        None => return Err("The journal is empty".to_string()),
+    };
+    let instance_config = header.instance_config;
+    let log_level = instance_config
+        .log_level
+        .map(|log_level| {
+            Level::from_str(&log_level).map_err(|e| format!("Failed to parse log level: {:?}", e))
+        })
+        .transpose()?;
+    let mut pocket_ic = PocketIc::new(
+        runtime,
+        header.seed,
+        instance_config.subnet_config_set,
+        instance_config.state_dir,
+        instance_config.nonmainnet_features,
+        log_level,
+        instance_config.bitcoind_addr,
+    );
+    for line in lines {
+        let line = line.map_err(|e| format!("Failed to read journal entry: {}", e))?;
+        let entry = match serde_json::from_str(&line)
+            .map_err(|e| format!("Failed to parse journal entry: {}", e))?
+        {
+            JournalLine::Entry(entry) => entry,
+            JournalLine::End(end) => return Ok(ReplayOutcome::Incomplete(end)),
+        };
+        let replayed_time = pocket_ic.get_time_nanos();
+        let replayed_output = format!("{:?}", entry.operation.compute(&mut pocket_ic));
+        if replayed_time != entry.time || replayed_output != entry.output {
+            return Ok(ReplayOutcome::Diverged(Divergence {
+                entry,
+                replayed_time,
+                replayed_output,
+            }));
+        }
+    }
+    Ok(ReplayOutcome::Matched)
+}
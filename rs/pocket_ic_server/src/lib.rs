@@ -34,9 +34,11 @@
 //! The start state is a dedicated state that always exists independent of which computations have
 //! been carried out. A state which has no outcoming computations is called a leaf.
 
+pub mod journal;
 pub mod pocket_ic;
 pub mod state_api;
 
+use crate::journal::RecordedOperation;
 use crate::state_api::state::OpOut;
 use ::pocket_ic::common::rest::{BinaryBlob, BlobId};
 use axum::async_trait;
@@ -57,6 +59,19 @@ pub trait Operation {
 
     /// Returns the unique identifier of this operation.
     fn id(&self) -> OpId;
+
+    /// Returns this operation in a form that can be recorded into a journal
+    /// and replayed, or `None` if this operation is not recorded.
+    fn recorded(&self) -> Option<RecordedOperation> {
+        None
+    }
+
+    /// True iff this operation can modify the instance, but is not recorded
+    /// (see [Self::recorded]) and thus a journal of the instance cannot be replayed
+    /// beyond this operation.
+    fn stops_journal(&self) -> bool {
+        false
+    }
 }
 
 /// Uniquely identifies an operation.
@@ -26,6 +26,7 @@ use ic_crypto_iccsa::{public_key_bytes_from_der, types::SignatureBytes, verify};
 use ic_crypto_sha2::Sha256;
 use ic_crypto_utils_threshold_sig_der::parse_threshold_sig_key_from_der;
 use pocket_ic::common::rest::{BinaryBlob, BlobCompression, BlobId, RawVerifyCanisterSigArg};
+use pocket_ic_server::journal::{replay_journal, ReplayOutcome};
 use pocket_ic_server::state_api::routes::{handler_read_graph, timeout_or_default};
 use pocket_ic_server::state_api::{
     routes::{http_gateway_routes, instances_routes, status, AppState, RouterExt},
@@ -68,6 +69,15 @@ struct Args {
     /// The time-to-live of the PocketIC server in seconds
     #[clap(long, default_value_t = TTL_SEC)]
     ttl: u64,
+    /// The directory into which the operations executed on every PocketIC instance are recorded
+    /// (one journal file per instance)
+    #[clap(long)]
+    journal_dir: Option<PathBuf>,
+    /// Replay the given journal file against a fresh PocketIC instance and exit
+    /// (with a non-zero exit code if the replay diverges from the recording
+    /// or if the journal ends with an operation that could not be recorded)
+    #[clap(long)]
+    replay: Option<PathBuf>,
 }
 
 /// Get the path of the current running binary.
@@ -107,6 +117,34 @@ fn main() {
 async fn start(runtime: Arc<Runtime>) {
     let args = Args::parse();
 
+    if let Some(journal_path) = args.replay {
+        let _guard = setup_tracing();
+        let result = tokio::task::spawn_blocking(move || replay_journal(runtime, &journal_path))
+            .await
+            .expect("Failed to replay journal");
+        match result {
+            Ok(ReplayOutcome::Matched) => {
+                println!("The replay of the journal matches the recording.");
+            }
+            Ok(ReplayOutcome::Diverged(divergence)) => {
+                eprintln!("{}", divergence);
+                std::process::exit(1);
+            }
+            Ok(ReplayOutcome::Incomplete(end)) => {
+                eprintln!(
+                    "The replay of the journal matches the recording up to the end of the journal."
+                );
+                eprintln!("{}", end);
+                std::process::exit(3);
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(2);
+            }
+        }
+        return;
+    }
+
     let port_file = if let Some(ref port_file_path) = args.port_file {
         match create_file(port_file_path) {
             Ok(f) => Some(f),
@@ -127,9 +165,12 @@ async fn start(runtime: Arc<Runtime>) {
 
     let _guard = setup_tracing();
     // The shared, mutable state of the PocketIC process.
-    let api_state = PocketIcApiStateBuilder::default()
-        .with_port(real_port)
-        .build();
+    let mut api_state_builder = PocketIcApiStateBuilder::default().with_port(real_port);
+    if let Some(journal_dir) = args.journal_dir {
+        std::fs::create_dir_all(&journal_dir).expect("Could not create journal directory");
+        api_state_builder = api_state_builder.with_journal_dir(journal_dir);
+    }
+    let api_state = api_state_builder.build();
     // A time-to-live mechanism: Requests bump this value, and the server
     // gracefully shuts down when the value wasn't bumped for a while.
     let min_alive_until = Arc::new(RwLock::new(Instant::now()));
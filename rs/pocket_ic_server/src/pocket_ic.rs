@@ -1,4 +1,5 @@
 #![allow(clippy::disallowed_types)]
+use crate::journal::RecordedOperation;
 use crate::state_api::state::{HasStateLabel, OpOut, PocketIcError, StateLabel};
 use crate::{async_trait, copy_dir, BlobStore, OpId, Operation};
 use askama::Template;
@@ -48,7 +49,7 @@ use ic_registry_routing_table::{CanisterIdRange, RoutingTable, CANISTER_IDS_PER_
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
     finalize_registry, StateMachine, StateMachineBuilder, StateMachineConfig, StateMachineStateDir,
-    SubmitIngressError,
+    SubmitIngressError, XNetMessageHoldFilter,
 };
 use ic_test_utilities_registry::add_subnet_list_record;
 use ic_types::ingress::{IngressState, IngressStatus};
@@ -58,9 +59,10 @@ use ic_types::{
     crypto::{BasicSig, BasicSigOf, CryptoResult, Signable},
     messages::{
         CertificateDelegation, HttpCallContent, HttpRequestEnvelope, MessageId as OtherMessageId,
-        QueryResponseHash, ReplicaHealthStatus, SignedIngress,
+        QueryResponseHash, ReplicaHealthStatus, RequestOrResponse, SignedIngress,
     },
     time::GENESIS,
+    xnet::{RejectReason, StreamIndex},
     CanisterId, Height, NodeId, NumInstructions, PrincipalId, RegistryVersion, SubnetId,
 };
 use ic_types::{NumBytes, Time};
@@ -70,7 +72,8 @@ use pocket_ic::common::rest::{
     self, BinaryBlob, BlobCompression, CanisterHttpHeader, CanisterHttpMethod, CanisterHttpRequest,
     CanisterHttpResponse, DtsFlag, ExtendedSubnetConfigSet, MockCanisterHttpResponse, RawAddCycles,
     RawCanisterCall, RawCanisterId, RawEffectivePrincipal, RawMessageId, RawSetStableMemory,
-    SubnetInstructionConfig, SubnetKind, SubnetSpec, Topology,
+    SubnetInstructionConfig, SubnetKind, SubnetSpec, Topology, XNetMessage, XNetMessageAction,
+    XNetMessageFilter, XNetMessageKind, XNetRejectReason,
 };
 use serde::{Deserialize, Serialize};
 use slog::Level;
@@ -78,11 +81,11 @@ use std::hash::Hash;
 use std::str::FromStr;
 use std::{
     cmp::max,
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fs::{remove_file, File},
     io::{BufReader, Write},
     net::SocketAddr,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
     time::{Duration, SystemTime},
 };
@@ -244,6 +247,11 @@ pub struct PocketIc {
     log_level: Option<Level>,
     bitcoind_addr: Option<Vec<SocketAddr>>,
     _bitcoin_adapter_parts: Option<BitcoinAdapterParts>,
+    xnet_message_hold_filters: Vec<XNetMessageFilter>,
+    // The temporary directory holding the subnet states of an instance created
+    // from a saved state. This field must be the last one so that the directory
+    // is deleted after the subnets have been dropped.
+    _saved_state_dir: Option<TempDir>,
 }
 
 impl Drop for PocketIc {
@@ -256,28 +264,7 @@ impl Drop for PocketIc {
             for subnet in subnets.values() {
                 subnet.await_state_hash();
             }
-            let mut topology_file = File::create(state_dir.join("topology.json")).unwrap();
-            let subnet_configs = self
-                .topology
-                .subnet_configs
-                .iter()
-                .map(|(seed, config)| {
-                    let time = subnets.get(&config.subnet_id).unwrap().time();
-                    (
-                        hex::encode(seed),
-                        RawSubnetConfigInternal {
-                            subnet_config: config.clone(),
-                            time,
-                        },
-                    )
-                })
-                .collect();
-            let raw_topology: RawTopologyInternal = RawTopologyInternal {
-                subnet_configs,
-                default_effective_canister_id: self.topology.default_effective_canister_id.into(),
-            };
-            let topology_json = serde_json::to_string(&raw_topology).unwrap();
-            topology_file.write_all(topology_json.as_bytes()).unwrap();
+            self.write_topology(&subnets, state_dir).unwrap();
         }
         for subnet in subnets.values() {
             subnet.drop_payload_builder();
@@ -285,11 +272,34 @@ impl Drop for PocketIc {
     }
 }
 
+// Copies the state directory of a subnet, skipping the tip and temporary directories
+// which the state manager discards when it is initialized from the copied state.
+fn copy_subnet_state_dir(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> std::io::Result<()> {
+    std::fs::create_dir_all(&dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        if entry.file_name() == "tip" || entry.file_name() == "tmp" {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_dir(entry.path(), dst.as_ref().join(entry.file_name()))?;
+        } else {
+            std::fs::copy(entry.path(), dst.as_ref().join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
 impl PocketIc {
     pub(crate) fn canister_http_adapters(&self) -> CanisterHttpAdapters {
         self.canister_http_adapters.clone()
     }
 
+    // Time is kept in sync across subnets, so one can take any subnet.
+    pub(crate) fn get_time_nanos(&self) -> u64 {
+        systemtime_to_unix_epoch_nanos(self.any_subnet().time())
+    }
+
     pub(crate) fn topology(&self) -> Topology {
         let mut subnet_configs = BTreeMap::new();
         let subnets = self.subnets.read().unwrap();
@@ -316,6 +326,184 @@ impl PocketIc {
         }
     }
 
+    fn write_topology(
+        &self,
+        subnets: &BTreeMap<SubnetId, Arc<StateMachine>>,
+        state_dir: &Path,
+    ) -> std::io::Result<()> {
+        let mut topology_file = File::create(state_dir.join("topology.json"))?;
+        let subnet_configs = self
+            .topology
+            .subnet_configs
+            .iter()
+            .map(|(seed, config)| {
+                let time = subnets.get(&config.subnet_id).unwrap().time();
+                (
+                    hex::encode(seed),
+                    RawSubnetConfigInternal {
+                        subnet_config: config.clone(),
+                        time,
+                    },
+                )
+            })
+            .collect();
+        let raw_topology: RawTopologyInternal = RawTopologyInternal {
+            subnet_configs,
+            default_effective_canister_id: self.topology.default_effective_canister_id.into(),
+        };
+        let topology_json = serde_json::to_string(&raw_topology).unwrap();
+        topology_file.write_all(topology_json.as_bytes())
+    }
+
+    /// Checkpoints the states of all subnets and copies them together with the topology
+    /// into `target_dir` so that a new instance can be created from `target_dir`
+    /// (see `PocketIc::from_saved_state`).
+    pub(crate) fn save_state(&self, target_dir: &Path) -> std::io::Result<()> {
+        let subnets = self.subnets.read().unwrap();
+        for subnet in subnets.values() {
+            subnet.checkpointed_tick();
+        }
+        for subnet in subnets.values() {
+            subnet.await_state_hash();
+        }
+        for (subnet_seed, config) in self.topology.subnet_configs.iter() {
+            let subnet = subnets.get(&config.subnet_id).unwrap();
+            copy_subnet_state_dir(
+                subnet.state_dir_path(),
+                target_dir.join(hex::encode(subnet_seed)),
+            )?;
+        }
+        self.write_topology(&subnets, target_dir)
+    }
+
+    /// Creates a new instance from a directory populated by `PocketIc::save_state`.
+    /// The new instance takes ownership of the directory and does not persist
+    /// its state when dropped.
+    pub(crate) fn from_saved_state(
+        runtime: Arc<Runtime>,
+        seed: u64,
+        saved_state_dir: TempDir,
+        nonmainnet_features: bool,
+        log_level: Option<Level>,
+        bitcoind_addr: Option<Vec<SocketAddr>>,
+    ) -> Self {
+        let mut pocket_ic = Self::new(
+            runtime,
+            seed,
+            ExtendedSubnetConfigSet::default(),
+            Some(saved_state_dir.path().to_path_buf()),
+            nonmainnet_features,
+            log_level,
+            bitcoind_addr,
+        );
+        pocket_ic.state_dir = None;
+        pocket_ic._saved_state_dir = Some(saved_state_dir);
+        pocket_ic
+    }
+
+    /// Creates a copy of this instance with the same subnets and subnet states.
+    pub(crate) fn fork(&self, seed: u64) -> std::io::Result<Self> {
+        let saved_state_dir = TempDir::new()?;
+        self.save_state(saved_state_dir.path())?;
+        Ok(Self::from_saved_state(
+            self.runtime.clone(),
+            seed,
+            saved_state_dir,
+            self.nonmainnet_features,
+            self.log_level,
+            self.bitcoind_addr.clone(),
+        ))
+    }
+
+    /// Replaces this instance by an instance created from a directory
+    /// populated by `PocketIc::save_state`. The directory itself is left untouched.
+    /// If this instance persists its state in a `state_dir`, then the restored
+    /// subnet states replace the subnet states in that `state_dir`.
+    ///
+    /// The saved state is copied before this instance is dropped so that this instance
+    /// is returned back with the error if copying fails. Otherwise, the error is returned
+    /// without an instance.
+    pub(crate) fn restore(
+        self,
+        seed: u64,
+        saved_state_dir: &Path,
+    ) -> Result<Self, (Option<Self>, std::io::Error)> {
+        // The saved state is staged next to the subnet states in the `state_dir`
+        // (on the same file system) so that it can be moved into place by renaming.
+        let staging_dir = match &self.state_dir {
+            Some(state_dir) => tempfile::Builder::new()
+                .prefix(".restore")
+                .tempdir_in(state_dir),
+            None => TempDir::new(),
+        };
+        let staging_dir =
+            match staging_dir.and_then(|dir| copy_dir(saved_state_dir, dir.path()).map(|_| dir)) {
+                Ok(dir) => dir,
+                Err(e) => return Err((Some(self), e)),
+            };
+
+        let runtime = self.runtime.clone();
+        let state_dir = self.state_dir.clone();
+        let nonmainnet_features = self.nonmainnet_features;
+        let log_level = self.log_level;
+        let bitcoind_addr = self.bitcoind_addr.clone();
+        let subnet_seeds: Vec<_> = self.topology.subnet_configs.keys().cloned().collect();
+        // The subnets must be dropped before their states can be replaced.
+        drop(self);
+        if let Some(state_dir) = state_dir {
+            Self::move_saved_state_into_place(&staging_dir, &state_dir, &subnet_seeds)
+                .map_err(|e| (None, e))?;
+            Ok(Self::new(
+                runtime,
+                seed,
+                ExtendedSubnetConfigSet::default(),
+                Some(state_dir),
+                nonmainnet_features,
+                log_level,
+                bitcoind_addr,
+            ))
+        } else {
+            Ok(Self::from_saved_state(
+                runtime,
+                seed,
+                staging_dir,
+                nonmainnet_features,
+                log_level,
+                bitcoind_addr,
+            ))
+        }
+    }
+
+    // Moves the subnet states and the topology of the previous instance out of `state_dir`
+    // into `staging_dir` (to be deleted together with it), and the saved state staged
+    // in `staging_dir` into `state_dir`.
+    fn move_saved_state_into_place(
+        staging_dir: &TempDir,
+        state_dir: &Path,
+        subnet_seeds: &[[u8; 32]],
+    ) -> std::io::Result<()> {
+        let previous_state_dir = staging_dir.path().join(".previous");
+        std::fs::create_dir(&previous_state_dir)?;
+        let previous_entries = subnet_seeds
+            .iter()
+            .map(hex::encode)
+            .chain(std::iter::once("topology.json".to_string()));
+        for name in previous_entries {
+            let path = state_dir.join(&name);
+            if path.exists() {
+                std::fs::rename(path, previous_state_dir.join(name))?;
+            }
+        }
+        for entry in std::fs::read_dir(staging_dir.path())? {
+            let entry = entry?;
+            if entry.path() == previous_state_dir {
+                continue;
+            }
+            std::fs::rename(entry.path(), state_dir.join(entry.file_name()))?;
+        }
+        Ok(())
+    }
+
     fn create_state_machine_state_dir(
         state_dir: &Option<PathBuf>,
         subnet_seed: &[u8; 32],
@@ -709,6 +897,8 @@ impl PocketIc {
             log_level,
             bitcoind_addr,
             _bitcoin_adapter_parts,
+            xnet_message_hold_filters: vec![],
+            _saved_state_dir: None,
         }
     }
 
@@ -716,6 +906,30 @@ impl PocketIc {
         self.state_label.bump();
     }
 
+    // Installs the current XNet message hold filters on all subnets.
+    fn install_xnet_message_hold_filters(&self) {
+        for (subnet_id, subnet) in self.subnets.read().unwrap().iter() {
+            let hold_filter: Option<Arc<XNetMessageHoldFilter>> =
+                if self.xnet_message_hold_filters.is_empty() {
+                    None
+                } else {
+                    let sender_subnet = *subnet_id;
+                    let filters = self.xnet_message_hold_filters.clone();
+                    Some(Arc::new(move |receiver_subnet, stream_index, msg| {
+                        let xnet_message = xnet_message_from(
+                            sender_subnet,
+                            receiver_subnet,
+                            Some(stream_index),
+                            msg,
+                            false,
+                        );
+                        filters.iter().any(|filter| filter.matches(&xnet_message))
+                    }))
+                };
+            subnet.set_xnet_message_hold_filter(hold_filter);
+        }
+    }
+
     fn try_route_canister(&self, canister_id: CanisterId) -> Option<Arc<StateMachine>> {
         let subnet_id = self.routing_table.route(canister_id.into());
         subnet_id.map(|subnet_id| self.get_subnet_with_id(subnet_id).unwrap())
@@ -941,7 +1155,7 @@ pub struct ConversionError {
     message: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SetTime {
     pub time: Time,
 }
@@ -958,9 +1172,13 @@ impl Operation for SetTime {
     fn id(&self) -> OpId {
         OpId(format!("set_time_{}", self.time))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::SetTime(self.clone()))
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 pub struct GetTopology;
 
 impl Operation for GetTopology {
@@ -975,24 +1193,30 @@ impl Operation for GetTopology {
     fn id(&self) -> OpId {
         OpId("get_topology".into())
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::GetTopology(*self))
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 pub struct GetTime;
 
 impl Operation for GetTime {
     fn compute(&self, pic: &mut PocketIc) -> OpOut {
-        // Time is kept in sync across subnets, so one can take any subnet.
-        let nanos = systemtime_to_unix_epoch_nanos(pic.any_subnet().time());
-        OpOut::Time(nanos)
+        OpOut::Time(pic.get_time_nanos())
     }
 
     fn id(&self) -> OpId {
         OpId("get_time".into())
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::GetTime(*self))
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 pub struct GetCanisterHttp;
 
 fn http_method_from(
@@ -1044,6 +1268,10 @@ impl Operation for GetCanisterHttp {
     fn id(&self) -> OpId {
         OpId("get_canister_http".into())
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::GetCanisterHttp(*self))
+    }
 }
 
 // START COPY from rs/https_outcalls/client/src/client.rs
@@ -1209,7 +1437,7 @@ fn process_mock_canister_https_response(
     OpOut::NoOutput
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MockCanisterHttp {
     pub mock_canister_http_response: MockCanisterHttpResponse,
 }
@@ -1225,9 +1453,223 @@ impl Operation for MockCanisterHttp {
             self.mock_canister_http_response
         ))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::MockCanisterHttp(self.clone()))
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+fn xnet_message_from(
+    sender_subnet: SubnetId,
+    receiver_subnet: SubnetId,
+    stream_index: Option<StreamIndex>,
+    msg: &RequestOrResponse,
+    held: bool,
+) -> XNetMessage {
+    let (kind, method_name) = match msg {
+        RequestOrResponse::Request(request) => {
+            (XNetMessageKind::Request, Some(request.method_name.clone()))
+        }
+        RequestOrResponse::Response(_) => (XNetMessageKind::Response, None),
+    };
+    XNetMessage {
+        sender_subnet: sender_subnet.get().0,
+        receiver_subnet: receiver_subnet.get().0,
+        stream_index: stream_index.map(|stream_index| stream_index.get()),
+        kind,
+        sender: msg.sender().get().0,
+        receiver: msg.receiver().get().0,
+        method_name,
+        payload_size_bytes: msg.payload_size_bytes().get(),
+        best_effort: msg.is_best_effort(),
+        held,
+    }
+}
+
+fn get_xnet_messages(pic: &PocketIc) -> Vec<XNetMessage> {
+    let mut xnet_messages = vec![];
+    let subnets = pic.subnets.read().unwrap();
+    for (sender_subnet, sender) in subnets.iter() {
+        // Streams are delivered in order and thus all messages after a held message are held, too.
+        let mut held_streams = BTreeSet::new();
+        for (receiver_subnet, receiver) in subnets.iter() {
+            // The receiving subnet of the loopback stream is the sending subnet itself.
+            let msg_begin = receiver.expected_xnet_message_index(*sender_subnet);
+            for (stream_index, msg) in sender.xnet_messages(*receiver_subnet, msg_begin) {
+                if sender.is_xnet_message_held(*receiver_subnet, stream_index, &msg) {
+                    held_streams.insert(*receiver_subnet);
+                }
+                xnet_messages.push(xnet_message_from(
+                    *sender_subnet,
+                    *receiver_subnet,
+                    Some(stream_index),
+                    &msg,
+                    held_streams.contains(receiver_subnet),
+                ));
+            }
+        }
+        // Messages in the output queues are routed into the streams after all messages
+        // that are already in the streams.
+        for (receiver_subnet, msg) in sender.output_queue_messages() {
+            let mut xnet_message =
+                xnet_message_from(*sender_subnet, receiver_subnet, None, &msg, false);
+            xnet_message.held = held_streams.contains(&receiver_subnet)
+                || pic
+                    .xnet_message_hold_filters
+                    .iter()
+                    .any(|filter| filter.matches(&xnet_message));
+            xnet_messages.push(xnet_message);
+        }
+    }
+    xnet_messages
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct GetXNetMessages;
+
+impl Operation for GetXNetMessages {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        OpOut::XNetMessages(get_xnet_messages(pic))
+    }
+
+    fn id(&self) -> OpId {
+        OpId("get_xnet_messages".into())
+    }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::GetXNetMessages(*self))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HoldXNetMessages {
+    pub filter: XNetMessageFilter,
+}
+
+impl Operation for HoldXNetMessages {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        pic.xnet_message_hold_filters.push(self.filter.clone());
+        pic.install_xnet_message_hold_filters();
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("hold_xnet_messages({:?})", self.filter))
+    }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::HoldXNetMessages(self.clone()))
+    }
+}
+
+/// Removes all hold filters equal to the given filter.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReleaseXNetMessages {
+    pub filter: XNetMessageFilter,
+}
+
+impl Operation for ReleaseXNetMessages {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        pic.xnet_message_hold_filters
+            .retain(|filter| *filter != self.filter);
+        pic.install_xnet_message_hold_filters();
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!("release_xnet_messages({:?})", self.filter))
+    }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::ReleaseXNetMessages(self.clone()))
+    }
+}
+
+/// Delivers, drops, or rejects the next undelivered message
+/// in the stream from `sender_subnet` to `receiver_subnet`.
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
+pub struct ProcessXNetMessage {
+    pub sender_subnet: SubnetId,
+    pub receiver_subnet: SubnetId,
+    pub action: XNetMessageAction,
+}
+
+impl Operation for ProcessXNetMessage {
+    fn compute(&self, pic: &mut PocketIc) -> OpOut {
+        let Some(sender) = pic.get_subnet_with_id(self.sender_subnet) else {
+            return OpOut::Error(PocketIcError::SubnetNotFound(self.sender_subnet.get().0));
+        };
+        let Some(receiver) = pic.get_subnet_with_id(self.receiver_subnet) else {
+            return OpOut::Error(PocketIcError::SubnetNotFound(self.receiver_subnet.get().0));
+        };
+        let msg_begin = receiver.expected_xnet_message_index(self.sender_subnet);
+        let Some((_, msg)) = sender
+            .xnet_messages(self.receiver_subnet, msg_begin)
+            .into_iter()
+            .next()
+        else {
+            return OpOut::Error(PocketIcError::InvalidXNetMessageAction(format!(
+                "No undelivered message from subnet {} to subnet {}",
+                self.sender_subnet, self.receiver_subnet
+            )));
+        };
+        match self.action {
+            // Messages in the loopback stream are inducted by the sending subnet itself.
+            XNetMessageAction::Deliver if self.sender_subnet == self.receiver_subnet => {
+                sender.deliver_next_loopback_message();
+            }
+            XNetMessageAction::Deliver => {
+                let xnet_payload = sender
+                    .generate_xnet_payload(
+                        self.receiver_subnet,
+                        Some(msg_begin),
+                        Some(msg_begin),
+                        Some(1),
+                        None,
+                    )
+                    .unwrap();
+                receiver.execute_block_with_xnet_payload(xnet_payload);
+            }
+            XNetMessageAction::Drop => {
+                if !msg.is_best_effort() {
+                    return OpOut::Error(PocketIcError::InvalidXNetMessageAction(
+                        "Only best-effort messages can be dropped".to_string(),
+                    ));
+                }
+                receiver.skip_xnet_message(self.sender_subnet, None);
+            }
+            XNetMessageAction::Reject(reject_reason) => {
+                if let RequestOrResponse::Response(_) = msg {
+                    return OpOut::Error(PocketIcError::InvalidXNetMessageAction(
+                        "Only requests can be rejected".to_string(),
+                    ));
+                }
+                let reject_reason = match reject_reason {
+                    XNetRejectReason::CanisterNotFound => RejectReason::CanisterNotFound,
+                    XNetRejectReason::CanisterStopped => RejectReason::CanisterStopped,
+                    XNetRejectReason::CanisterStopping => RejectReason::CanisterStopping,
+                    XNetRejectReason::QueueFull => RejectReason::QueueFull,
+                    XNetRejectReason::OutOfMemory => RejectReason::OutOfMemory,
+                };
+                receiver.skip_xnet_message(self.sender_subnet, Some(reject_reason));
+            }
+        }
+        OpOut::NoOutput
+    }
+
+    fn id(&self) -> OpId {
+        OpId(format!(
+            "process_xnet_message({},{},{:?})",
+            self.sender_subnet, self.receiver_subnet, self.action
+        ))
+    }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::ProcessXNetMessage(*self))
+    }
+}
+
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 pub struct PubKey {
     pub subnet_id: SubnetId,
 }
@@ -1244,9 +1686,13 @@ impl Operation for PubKey {
     fn id(&self) -> OpId {
         OpId(format!("root_key_{}", self.subnet_id))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::PubKey(*self))
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 pub struct Tick;
 
 impl Operation for Tick {
@@ -1260,9 +1706,13 @@ impl Operation for Tick {
     fn id(&self) -> OpId {
         OpId("tick".to_string())
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::Tick(*self))
+    }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize)]
 pub struct AdvanceTimeAndTick(pub Duration);
 
 impl Operation for AdvanceTimeAndTick {
@@ -1277,9 +1727,13 @@ impl Operation for AdvanceTimeAndTick {
     fn id(&self) -> OpId {
         OpId(format!("advance_time_and_tick({:?})", self.0))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::AdvanceTimeAndTick(*self))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct SubmitIngressMessage(pub CanisterCall);
 
 impl Operation for SubmitIngressMessage {
@@ -1316,9 +1770,13 @@ impl Operation for SubmitIngressMessage {
         let call_id = self.0.id();
         OpId(format!("submit_update_{}", call_id.0))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::SubmitIngressMessage(self.clone()))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct MessageId {
     effective_principal: EffectivePrincipal,
     msg_id: OtherMessageId,
@@ -1348,7 +1806,7 @@ impl TryFrom<RawMessageId> for MessageId {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AwaitIngressMessage(pub MessageId);
 
 impl Operation for AwaitIngressMessage {
@@ -1392,9 +1850,13 @@ impl Operation for AwaitIngressMessage {
     fn id(&self) -> OpId {
         OpId(format!("await_update_{}", self.0.msg_id))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::AwaitIngressMessage(self.clone()))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ExecuteIngressMessage(pub CanisterCall);
 
 impl Operation for ExecuteIngressMessage {
@@ -1457,8 +1919,13 @@ impl Operation for ExecuteIngressMessage {
         let call_id = self.0.id();
         OpId(format!("canister_update_{}", call_id.0))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::ExecuteIngressMessage(self.clone()))
+    }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Query(pub CanisterCall);
 
 impl Operation for Query {
@@ -1486,6 +1953,10 @@ impl Operation for Query {
         let call_id = self.0.id();
         OpId(format!("canister_query_{}", call_id.0))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::Query(self.clone()))
+    }
 }
 
 pub struct DashboardRequest {}
@@ -1727,6 +2198,10 @@ impl Operation for CallRequest {
         true
     }
 
+    fn stops_journal(&self) -> bool {
+        true
+    }
+
     fn id(&self) -> OpId {
         let mut hasher = Sha256::new();
         self.bytes.hash(&mut hasher);
@@ -1963,7 +2438,7 @@ impl From<EffectivePrincipal> for RawEffectivePrincipal {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct CanisterCall {
     pub effective_principal: EffectivePrincipal,
     pub sender: PrincipalId,
@@ -2023,7 +2498,7 @@ impl CanisterCall {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub struct SetStableMemory {
     pub canister_id: CanisterId,
     pub data: Vec<u8>,
@@ -2091,9 +2566,13 @@ impl Operation for SetStableMemory {
         let hash = Digest(hasher.finish());
         OpId(format!("set_stable_memory({}_{})", self.canister_id, hash))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::SetStableMemory(self.clone()))
+    }
 }
 
-#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub struct GetStableMemory {
     pub canister_id: CanisterId,
 }
@@ -2111,9 +2590,13 @@ impl Operation for GetStableMemory {
     fn id(&self) -> OpId {
         OpId(format!("get_stable_memory({})", self.canister_id))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::GetStableMemory(self.clone()))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GetCyclesBalance {
     pub canister_id: CanisterId,
 }
@@ -2130,9 +2613,13 @@ impl Operation for GetCyclesBalance {
     fn id(&self) -> OpId {
         OpId(format!("get_cycles_balance({})", self.canister_id))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::GetCyclesBalance(self.clone()))
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GetSubnet {
     pub canister_id: CanisterId,
 }
@@ -2155,6 +2642,10 @@ impl Operation for GetSubnet {
     fn id(&self) -> OpId {
         OpId(format!("get_subnet({})", self.canister_id))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::GetSubnet(self.clone()))
+    }
 }
 
 /// Add cycles to a given canister.
@@ -2162,7 +2653,7 @@ impl Operation for GetSubnet {
 /// # Panics
 ///
 /// Panics if the canister does not exist.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct AddCycles {
     canister_id: CanisterId,
     amount: u128,
@@ -2200,6 +2691,10 @@ impl Operation for AddCycles {
     fn id(&self) -> OpId {
         OpId(format!("add_cycles({},{})", self.canister_id, self.amount))
     }
+
+    fn recorded(&self) -> Option<RecordedOperation> {
+        Some(RecordedOperation::AddCycles(self.clone()))
+    }
 }
 
 struct Digest([u8; 32]);
@@ -2325,6 +2820,8 @@ fn route(
                     pic.topology
                         .subnet_configs
                         .insert(subnet_seed, subnet_config_internal);
+                    // We install the XNet message hold filters on the new subnet.
+                    pic.install_xnet_message_hold_filters();
                     // We update the registry by creating a new registry version
                     // and inserting new records at that new registry version.
                     let registry_version = pic.registry_data_provider.latest_version();
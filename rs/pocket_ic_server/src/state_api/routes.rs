@@ -9,8 +9,9 @@ use super::state::{ApiState, OpOut, PocketIcError, StateLabel, UpdateReply};
 use crate::pocket_ic::{
     AddCycles, AwaitIngressMessage, CallRequest, CallRequestVersion, CanisterReadStateRequest,
     DashboardRequest, ExecuteIngressMessage, GetCanisterHttp, GetCyclesBalance, GetStableMemory,
-    GetSubnet, GetTime, GetTopology, MockCanisterHttp, PubKey, Query, QueryRequest,
-    SetStableMemory, SetTime, StatusRequest, SubmitIngressMessage, SubnetReadStateRequest, Tick,
+    GetSubnet, GetTime, GetTopology, GetXNetMessages, HoldXNetMessages, MockCanisterHttp,
+    ProcessXNetMessage, PubKey, Query, QueryRequest, ReleaseXNetMessages, SetStableMemory, SetTime,
+    StatusRequest, SubmitIngressMessage, SubnetReadStateRequest, Tick,
 };
 use crate::{async_trait, pocket_ic::PocketIc, BlobStore, InstanceId, OpId, Operation};
 use aide::{
@@ -33,13 +34,14 @@ use backoff::backoff::Backoff;
 use backoff::{ExponentialBackoff, ExponentialBackoffBuilder};
 use hyper::header;
 use ic_http_endpoints_public::cors_layer;
-use ic_types::{CanisterId, SubnetId};
+use ic_types::{CanisterId, PrincipalId, SubnetId};
 use pocket_ic::common::rest::{
     self, ApiResponse, AutoProgressConfig, ExtendedSubnetConfigSet, HttpGatewayConfig,
     HttpGatewayDetails, InstanceConfig, MockCanisterHttpResponse, RawAddCycles, RawCanisterCall,
     RawCanisterHttpRequest, RawCanisterId, RawCanisterResult, RawCycles, RawMessageId,
-    RawMockCanisterHttpResponse, RawSetStableMemory, RawStableMemory, RawSubmitIngressResult,
-    RawSubnetId, RawTime, RawWasmResult, Topology,
+    RawMockCanisterHttpResponse, RawProcessXNetMessage, RawSetStableMemory, RawStableMemory,
+    RawSubmitIngressResult, RawSubnetId, RawTime, RawWasmResult, RawXNetMessage,
+    RawXNetMessageFilter, Topology,
 };
 use pocket_ic::WasmResult;
 use serde::Serialize;
@@ -79,6 +81,7 @@ where
         .directory_route("/get_stable_memory", post(handler_get_stable_memory))
         .directory_route("/get_subnet", post(handler_get_subnet))
         .directory_route("/pub_key", post(handler_pub_key))
+        .directory_route("/get_xnet_messages", get(handler_get_xnet_messages))
 }
 
 pub fn instance_update_routes<S>() -> ApiRouter<S>
@@ -104,6 +107,12 @@ where
         .directory_route("/set_stable_memory", post(handler_set_stable_memory))
         .directory_route("/tick", post(handler_tick))
         .directory_route("/mock_canister_http", post(handler_mock_canister_http))
+        .directory_route("/hold_xnet_messages", post(handler_hold_xnet_messages))
+        .directory_route(
+            "/release_xnet_messages",
+            post(handler_release_xnet_messages),
+        )
+        .directory_route("/process_xnet_message", post(handler_process_xnet_message))
 }
 
 pub fn instance_api_v2_routes<S>() -> ApiRouter<S>
@@ -179,6 +188,17 @@ where
         // Deletes an instance.
         .directory_route("/:id", delete(delete_instance))
         //
+        // Creates a new IC instance with the same subnets and subnet states as the given instance.
+        // Returns an InstanceId.
+        .api_route("/:id/fork", post(fork_instance))
+        //
+        // Saves the states of all subnets of an instance as a named snapshot
+        // (replacing an existing snapshot with the same name).
+        .api_route("/:id/snapshots/:name", post(snapshot_instance))
+        //
+        // Restores the states of all subnets of an instance from a named snapshot.
+        .api_route("/:id/snapshots/:name/restore", post(restore_instance))
+        //
         // All the read-only endpoints
         .nest("/:id/read", instance_read_routes())
         //
@@ -481,6 +501,18 @@ impl TryFrom<OpOut> for Vec<RawCanisterHttpRequest> {
     }
 }
 
+impl TryFrom<OpOut> for Vec<RawXNetMessage> {
+    type Error = OpConversionError;
+    fn try_from(value: OpOut) -> Result<Self, Self::Error> {
+        match value {
+            OpOut::XNetMessages(xnet_messages) => {
+                Ok(xnet_messages.into_iter().map(|m| m.into()).collect())
+            }
+            _ => Err(OpConversionError),
+        }
+    }
+}
+
 #[async_trait]
 impl FromOpOut for PocketHttpResponse {
     async fn from(value: OpOut) -> (StatusCode, ApiResponse<PocketHttpResponse>) {
@@ -583,6 +615,17 @@ pub async fn handler_mock_canister_http(
     (code, Json(response))
 }
 
+pub async fn handler_get_xnet_messages(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+) -> (StatusCode, Json<ApiResponse<Vec<RawXNetMessage>>>) {
+    let timeout = timeout_or_default(headers);
+    let op = GetXNetMessages {};
+    let (code, response) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
 pub async fn handler_get_cycles(
     State(AppState { api_state, .. }): State<AppState>,
     Path(instance_id): Path<InstanceId>,
@@ -841,6 +884,13 @@ async fn op_out_to_response(op_out: OpOut) -> Response {
             )),
         )
             .into_response(),
+        opout @ OpOut::XNetMessages(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::Success(
+                Vec::<RawXNetMessage>::try_from(opout).unwrap(),
+            )),
+        )
+            .into_response(),
         OpOut::RawResponse(fut) => {
             let (status, headers, bytes) = fut.await;
             let code = StatusCode::from_u16(status).unwrap();
@@ -1022,6 +1072,52 @@ pub async fn handler_set_stable_memory(
     }
 }
 
+pub async fn handler_hold_xnet_messages(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+    extract::Json(raw_xnet_message_filter): extract::Json<RawXNetMessageFilter>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let timeout = timeout_or_default(headers);
+    let op = HoldXNetMessages {
+        filter: raw_xnet_message_filter.into(),
+    };
+    let (code, response) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
+pub async fn handler_release_xnet_messages(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+    extract::Json(raw_xnet_message_filter): extract::Json<RawXNetMessageFilter>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let timeout = timeout_or_default(headers);
+    let op = ReleaseXNetMessages {
+        filter: raw_xnet_message_filter.into(),
+    };
+    let (code, response) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
+pub async fn handler_process_xnet_message(
+    State(AppState { api_state, .. }): State<AppState>,
+    headers: HeaderMap,
+    Path(instance_id): Path<InstanceId>,
+    extract::Json(raw_process_xnet_message): extract::Json<RawProcessXNetMessage>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    let timeout = timeout_or_default(headers);
+    let op = ProcessXNetMessage {
+        sender_subnet: SubnetId::new(PrincipalId(raw_process_xnet_message.sender_subnet.into())),
+        receiver_subnet: SubnetId::new(PrincipalId(
+            raw_process_xnet_message.receiver_subnet.into(),
+        )),
+        action: raw_process_xnet_message.action,
+    };
+    let (code, response) = run_operation(api_state, instance_id, timeout, op).await;
+    (code, Json(response))
+}
+
 pub async fn handler_tick(
     State(AppState { api_state, .. }): State<AppState>,
     Path(instance_id): Path<InstanceId>,
@@ -1069,6 +1165,7 @@ pub async fn create_instance(
     }): State<AppState>,
     extract::Json(instance_config): extract::Json<InstanceConfig>,
 ) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    let journal_instance_config = instance_config.clone();
     let subnet_configs = instance_config.subnet_config_set;
 
     let skip_validate_subnet_configs = instance_config
@@ -1113,17 +1210,20 @@ pub async fn create_instance(
     };
 
     let (instance_id, topology) = api_state
-        .add_instance(move |seed| {
-            PocketIc::new(
-                runtime,
-                seed,
-                subnet_configs,
-                instance_config.state_dir,
-                instance_config.nonmainnet_features,
-                log_level,
-                instance_config.bitcoind_addr,
-            )
-        })
+        .add_instance(
+            move |seed| {
+                PocketIc::new(
+                    runtime,
+                    seed,
+                    subnet_configs,
+                    instance_config.state_dir,
+                    instance_config.nonmainnet_features,
+                    log_level,
+                    instance_config.bitcoind_addr,
+                )
+            },
+            journal_instance_config,
+        )
         .await;
     (
         StatusCode::CREATED,
@@ -1149,6 +1249,55 @@ pub async fn delete_instance(
     StatusCode::OK
 }
 
+/// Create a new IC instance from the current state of a given instance.
+/// The new InstanceId will be returned.
+pub async fn fork_instance(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path(id): Path<InstanceId>,
+) -> (StatusCode, Json<rest::CreateInstanceResponse>) {
+    match api_state.fork_instance(id).await {
+        Ok((instance_id, topology)) => (
+            StatusCode::CREATED,
+            Json(rest::CreateInstanceResponse::Created {
+                instance_id,
+                topology,
+            }),
+        ),
+        Err(message) => (
+            StatusCode::BAD_REQUEST,
+            Json(rest::CreateInstanceResponse::Error { message }),
+        ),
+    }
+}
+
+pub async fn snapshot_instance(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path((id, name)): Path<(InstanceId, String)>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    if let Err(e) = api_state.snapshot_instance(id, name).await {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error { message: e }),
+        )
+    } else {
+        (StatusCode::OK, Json(ApiResponse::Success(())))
+    }
+}
+
+pub async fn restore_instance(
+    State(AppState { api_state, .. }): State<AppState>,
+    Path((id, name)): Path<(InstanceId, String)>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    if let Err(e) = api_state.restore_instance(id, name).await {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::Error { message: e }),
+        )
+    } else {
+        (StatusCode::OK, Json(ApiResponse::Success(())))
+    }
+}
+
 pub async fn list_http_gateways(
     State(AppState { api_state, .. }): State<AppState>,
 ) -> Json<Vec<HttpGatewayDetails>> {
@@ -2,6 +2,7 @@
 /// This module contains the core state of the PocketIc server.
 /// Axum handlers operate on a global state of type ApiState, whose
 /// interface guarantees consistency and determinism.
+use crate::journal::{Journal, JournalHeader};
 use crate::pocket_ic::{
     AdvanceTimeAndTick, ApiResponse, EffectivePrincipal, GetCanisterHttp, MockCanisterHttp,
     PocketIc,
@@ -48,7 +49,8 @@ use ic_types::{
 use pocket_ic::common::rest::{
     CanisterHttpHeader, CanisterHttpMethod, CanisterHttpReject, CanisterHttpReply,
     CanisterHttpRequest, CanisterHttpResponse, HttpGatewayBackend, HttpGatewayConfig,
-    HttpGatewayDetails, HttpGatewayInfo, MockCanisterHttpResponse, Topology,
+    HttpGatewayDetails, HttpGatewayInfo, InstanceConfig, MockCanisterHttpResponse, Topology,
+    XNetMessage,
 };
 use pocket_ic::{ErrorCode, UserError, WasmResult};
 use serde::{Deserialize, Serialize};
@@ -56,6 +58,7 @@ use std::{
     collections::HashMap, fmt, path::PathBuf, str::FromStr, sync::atomic::AtomicU64, sync::Arc,
     time::Duration,
 };
+use tempfile::TempDir;
 use tokio::{
     sync::mpsc::error::TryRecvError,
     sync::mpsc::Receiver,
@@ -131,6 +134,7 @@ struct ProgressThread {
 struct Instance {
     progress_thread: Option<ProgressThread>,
     state: InstanceState,
+    journal: Option<Journal>,
 }
 
 /// The state of the PocketIC API.
@@ -144,6 +148,11 @@ pub struct ApiState {
     port: Option<u16>,
     // HTTP gateway infos (`None` = stopped)
     http_gateways: Arc<RwLock<Vec<Option<HttpGatewayDetails>>>>,
+    // Named snapshots of instance states (see `PocketIc::save_state`)
+    snapshots: RwLock<HashMap<(InstanceId, String), Arc<TempDir>>>,
+    // The directory into which the operations executed on instances are recorded
+    // (`None` = no recording)
+    journal_dir: Option<PathBuf>,
 }
 
 #[derive(Default)]
@@ -151,6 +160,7 @@ pub struct PocketIcApiStateBuilder {
     initial_instances: Vec<PocketIc>,
     sync_wait_time: Option<Duration>,
     port: Option<u16>,
+    journal_dir: Option<PathBuf>,
 }
 
 impl PocketIcApiStateBuilder {
@@ -174,6 +184,16 @@ impl PocketIcApiStateBuilder {
         }
     }
 
+    /// Records the operations executed on every instance created via [ApiState::add_instance]
+    /// into a journal file `instance_<instance_id>.jsonl` in the given directory.
+    /// Such a journal file can be replayed using [crate::journal::replay_journal].
+    pub fn with_journal_dir(self, journal_dir: PathBuf) -> Self {
+        Self {
+            journal_dir: Some(journal_dir),
+            ..self
+        }
+    }
+
     /// Will make the given instance available in the initial state.
     pub fn add_initial_instance(mut self, instance: PocketIc) -> Self {
         self.initial_instances.push(instance);
@@ -195,6 +215,7 @@ impl PocketIcApiStateBuilder {
                 Mutex::new(Instance {
                     progress_thread: None,
                     state: InstanceState::Available(instance),
+                    journal: None,
                 })
             })
             .collect();
@@ -209,6 +230,8 @@ impl PocketIcApiStateBuilder {
             sync_wait_time,
             port: self.port,
             http_gateways: Arc::new(RwLock::new(Vec::new())),
+            snapshots: RwLock::new(HashMap::new()),
+            journal_dir: self.journal_dir,
         })
     }
 }
@@ -228,6 +251,7 @@ pub enum OpOut {
     MessageId((EffectivePrincipal, Vec<u8>)),
     Topology(Topology),
     CanisterHttp(Vec<CanisterHttpRequest>),
+    XNetMessages(Vec<XNetMessage>),
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Debug, Deserialize, Serialize)]
@@ -238,6 +262,7 @@ pub enum PocketIcError {
     RequestRoutingError(String),
     InvalidCanisterHttpRequestId((SubnetId, CanisterHttpRequestId)),
     InvalidMockCanisterHttpResponses((usize, usize)),
+    InvalidXNetMessageAction(String),
 }
 
 impl From<Result<ic_state_machine_tests::WasmResult, ic_state_machine_tests::UserError>> for OpOut {
@@ -297,6 +322,9 @@ impl std::fmt::Debug for OpOut {
                     actual, expected
                 )
             }
+            OpOut::Error(PocketIcError::InvalidXNetMessageAction(msg)) => {
+                write!(f, "InvalidXNetMessageAction({})", msg)
+            }
             OpOut::Bytes(bytes) => write!(f, "Bytes({})", base64::encode(bytes)),
             OpOut::StableMemBytes(bytes) => write!(f, "StableMemory({})", base64::encode(bytes)),
             OpOut::MaybeSubnetId(Some(subnet_id)) => write!(f, "SubnetId({})", subnet_id),
@@ -324,6 +352,9 @@ impl std::fmt::Debug for OpOut {
             OpOut::CanisterHttp(canister_http_reqeusts) => {
                 write!(f, "CanisterHttp({:?})", canister_http_reqeusts)
             }
+            OpOut::XNetMessages(xnet_messages) => {
+                write!(f, "XNetMessages({:?})", xnet_messages)
+            }
         }
     }
 }
@@ -707,7 +738,14 @@ impl ApiState {
         Self::read_result(self.graph.clone(), state_label, op_id)
     }
 
-    pub async fn add_instance<F>(&self, f: F) -> (InstanceId, Topology)
+    /// Creates a new instance using the given function (taking a seed as its argument).
+    /// The given instance config is only used to record the operations executed on the instance
+    /// (see [PocketIcApiStateBuilder::with_journal_dir]) and must match the function.
+    pub async fn add_instance<F>(
+        &self,
+        f: F,
+        instance_config: InstanceConfig,
+    ) -> (InstanceId, Topology)
     where
         F: FnOnce(u64) -> PocketIc + std::marker::Send + 'static,
     {
@@ -716,18 +754,195 @@ impl ApiState {
         let instance = tokio::task::spawn_blocking(move || f(seed))
             .await
             .expect("Failed to create PocketIC instance");
+        let journal_header = JournalHeader {
+            seed,
+            instance_config,
+        };
+        self.insert_instance(instance, Some(journal_header)).await
+    }
+
+    async fn insert_instance(
+        &self,
+        instance: PocketIc,
+        journal_header: Option<JournalHeader>,
+    ) -> (InstanceId, Topology) {
         let topology = instance.topology();
         let mut instances = self.instances.write().await;
         let instance_id = instances.len();
+        let journal = match (&self.journal_dir, journal_header) {
+            (Some(journal_dir), Some(journal_header)) => {
+                let journal_path = journal_dir.join(format!("instance_{}.jsonl", instance_id));
+                match Journal::create(&journal_path, &journal_header) {
+                    Ok(journal) => Some(journal),
+                    Err(e) => {
+                        error!("Failed to create journal {}: {}", journal_path.display(), e);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
         instances.push(Mutex::new(Instance {
             progress_thread: None,
             state: InstanceState::Available(instance),
+            journal,
         }));
         (instance_id, topology)
     }
 
+    // Waits until the given instance is available, marks it as busy with the given `op_id`,
+    // and runs `f` on it in a blocking task. The instance state returned by `f` replaces the busy state.
+    async fn with_available_instance<F, T>(
+        &self,
+        instance_id: InstanceId,
+        op_id: OpId,
+        f: F,
+    ) -> Result<T, String>
+    where
+        F: FnOnce(PocketIc) -> (InstanceState, T) + Send + 'static,
+        T: Send + 'static,
+    {
+        let (time, pocket_ic) = loop {
+            let instances = self.instances.read().await;
+            let Some(instance_mutex) = instances.get(instance_id) else {
+                return Err("Instance not found".to_string());
+            };
+            let mut instance = instance_mutex.lock().await;
+            match &instance.state {
+                InstanceState::Available(pocket_ic) => {
+                    let busy = InstanceState::Busy {
+                        state_label: pocket_ic.get_state_label(),
+                        op_id: op_id.clone(),
+                    };
+                    let InstanceState::Available(pocket_ic) =
+                        std::mem::replace(&mut instance.state, busy)
+                    else {
+                        unreachable!()
+                    };
+                    break (pocket_ic.get_time_nanos(), pocket_ic);
+                }
+                InstanceState::Deleted => {
+                    return Err("Instance was deleted".to_string());
+                }
+                InstanceState::Busy { .. } => {}
+            }
+            drop(instance);
+            drop(instances);
+            sleep(MIN_OPERATION_DELAY).await;
+        };
+        let (state, result) = spawn_blocking(move || f(pocket_ic))
+            .await
+            .expect("Failed to run operation on PocketIC instance");
+        let instances = self.instances.read().await;
+        let mut instance = instances[instance_id].lock().await;
+        instance.state = state;
+        // The journal of the instance cannot be replayed beyond this point
+        // because `f` is not a recorded operation.
+        if let Some(journal) = instance.journal.take() {
+            if let Err(e) = journal.stop(time, &op_id) {
+                error!("Failed to end journal at operation {}: {}", op_id.0, e);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Creates a new instance with the same subnets and subnet states as the given instance.
+    /// The new InstanceId will be returned.
+    pub async fn fork_instance(
+        &self,
+        instance_id: InstanceId,
+    ) -> Result<(InstanceId, Topology), String> {
+        let seed = self.seed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let fork = self
+            .with_available_instance(
+                instance_id,
+                OpId("fork_instance".to_string()),
+                move |mut pocket_ic| {
+                    let fork = pocket_ic.fork(seed);
+                    // Saving the state executes a round on every subnet.
+                    pocket_ic.bump_state_label();
+                    (InstanceState::Available(pocket_ic), fork)
+                },
+            )
+            .await?
+            .map_err(|e| format!("Failed to fork instance: {}", e))?;
+        Ok(self.insert_instance(fork, None).await)
+    }
+
+    /// Saves the state of all subnets of the given instance as a snapshot with the given name.
+    /// An existing snapshot of that instance with the same name is replaced.
+    pub async fn snapshot_instance(
+        &self,
+        instance_id: InstanceId,
+        name: String,
+    ) -> Result<(), String> {
+        let snapshot = self
+            .with_available_instance(
+                instance_id,
+                OpId(format!("snapshot_instance({})", name)),
+                move |mut pocket_ic| {
+                    let snapshot = TempDir::new().and_then(|snapshot_dir| {
+                        pocket_ic.save_state(snapshot_dir.path())?;
+                        Ok(snapshot_dir)
+                    });
+                    // Saving the state executes a round on every subnet.
+                    pocket_ic.bump_state_label();
+                    (InstanceState::Available(pocket_ic), snapshot)
+                },
+            )
+            .await?
+            .map_err(|e| format!("Failed to snapshot instance: {}", e))?;
+        self.snapshots
+            .write()
+            .await
+            .insert((instance_id, name), Arc::new(snapshot));
+        Ok(())
+    }
+
+    /// Restores the state of all subnets of the given instance from the snapshot with the given name.
+    /// The snapshot is kept and can be restored again.
+    pub async fn restore_instance(
+        &self,
+        instance_id: InstanceId,
+        name: String,
+    ) -> Result<(), String> {
+        let Some(snapshot) = self
+            .snapshots
+            .read()
+            .await
+            .get(&(instance_id, name.clone()))
+            .cloned()
+        else {
+            return Err(format!("Snapshot {} not found", name));
+        };
+        let seed = self.seed.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.with_available_instance(
+            instance_id,
+            OpId(format!("restore_instance({})", name)),
+            move |pocket_ic| match pocket_ic.restore(seed, snapshot.path()) {
+                Ok(pocket_ic) => (InstanceState::Available(pocket_ic), Ok(())),
+                Err((Some(pocket_ic), e)) => (
+                    InstanceState::Available(pocket_ic),
+                    Err(format!("Failed to restore snapshot {}: {}", name, e)),
+                ),
+                Err((None, e)) => (
+                    InstanceState::Deleted,
+                    Err(format!(
+                        "Failed to restore snapshot {} (the instance has been deleted): {}",
+                        name, e
+                    )),
+                ),
+            },
+        )
+        .await?
+    }
+
     pub async fn delete_instance(&self, instance_id: InstanceId) {
         self.stop_progress(instance_id).await;
+        self.snapshots
+            .write()
+            .await
+            .retain(|(id, _), _| *id != instance_id);
         loop {
             let instances = self.instances.read().await;
             let mut instance = instances[instance_id].lock().await;
@@ -1404,6 +1619,7 @@ impl ApiState {
                         let old_state_label = state_label.clone();
                         let op_id = op_id.clone();
                         let graph = graph.clone();
+                        let record = instance.journal.is_some();
                         move || {
                             trace!(
                                 "bg_task::start instance_id={} state_label={:?} op_id={}",
@@ -1411,6 +1627,11 @@ impl ApiState {
                                 old_state_label,
                                 op_id.0,
                             );
+                            let journal_time = if record {
+                                Some(pocket_ic.get_time_nanos())
+                            } else {
+                                None
+                            };
                             let result = op.compute(&mut pocket_ic);
                             pocket_ic.bump_state_label();
                             let new_state_label = pocket_ic.get_state_label();
@@ -1423,6 +1644,24 @@ impl ApiState {
                                 .insert(op_id.clone(), (new_state_label, result.clone()));
                             drop(graph_guard);
                             let mut instance = instances[instance_id].blocking_lock();
+                            if let Some(time) = journal_time {
+                                if let Some(recorded_op) = op.recorded() {
+                                    if let Some(journal) = instance.journal.as_mut() {
+                                        if let Err(e) = journal.record(time, recorded_op, &result) {
+                                            error!("Failed to record operation {}: {}", op_id.0, e);
+                                        }
+                                    }
+                                } else if op.stops_journal() {
+                                    if let Some(journal) = instance.journal.take() {
+                                        if let Err(e) = journal.stop(time, &op_id) {
+                                            error!(
+                                                "Failed to end journal at operation {}: {}",
+                                                op_id.0, e
+                                            );
+                                        }
+                                    }
+                                }
+                            }
                             if let InstanceState::Deleted = &instance.state {
                                 error!("The instance is deleted immediately after an operation. This is a bug!");
                                 std::mem::drop(pocket_ic);
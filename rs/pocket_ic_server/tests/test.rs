@@ -13,8 +13,9 @@ use ic_registry_transport::pb::v1::{
 use ic_utils::interfaces::ManagementCanister;
 use nix::sys::signal::Signal;
 use pocket_ic::common::rest::{
-    CreateHttpGatewayResponse, HttpGatewayBackend, HttpGatewayConfig, HttpGatewayDetails,
-    HttpsConfig, InstanceConfig, SubnetConfigSet, SubnetKind, Topology,
+    CreateHttpGatewayResponse, CreateInstanceResponse, HttpGatewayBackend, HttpGatewayConfig,
+    HttpGatewayDetails, HttpsConfig, InstanceConfig, InstanceId, RawCanisterCall,
+    RawCanisterResult, RawEffectivePrincipal, RawWasmResult, SubnetConfigSet, SubnetKind, Topology,
 };
 use pocket_ic::{update_candid, PocketIc, PocketIcBuilder, WasmResult};
 use rcgen::{CertificateParams, KeyPair};
@@ -23,6 +24,7 @@ use reqwest::blocking::Client;
 use reqwest::Client as NonblockingClient;
 use reqwest::{StatusCode, Url};
 use slog::Level;
+use std::ffi::OsStr;
 use std::io::Read;
 use std::io::Write;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
@@ -37,6 +39,15 @@ fn start_server_helper(
     test_driver_pid: Option<u32>,
     capture_stdout: bool,
     capture_stderr: bool,
+) -> (Url, Child) {
+    start_server_helper_with_args(test_driver_pid, capture_stdout, capture_stderr, &[])
+}
+
+fn start_server_helper_with_args(
+    test_driver_pid: Option<u32>,
+    capture_stdout: bool,
+    capture_stderr: bool,
+    extra_args: &[&OsStr],
 ) -> (Url, Child) {
     let bin_path = std::env::var_os("POCKET_IC_BIN").expect("Missing PocketIC binary");
     let port_file_path = if let Some(test_driver_pid) = test_driver_pid {
@@ -50,6 +61,7 @@ fn start_server_helper(
     // so that the server doesn't die during the test if the runner
     // is overloaded
     cmd.arg("--ttl").arg("300");
+    cmd.args(extra_args);
     if capture_stdout {
         cmd.stdout(std::process::Stdio::piped());
     }
@@ -852,6 +864,258 @@ fn canister_state_dir(shutdown_signal: Option<Signal>) {
     check_counter(&pic, app_canister_id, 3);
 }
 
+fn query_counter_on_instance(
+    server_url: &Url,
+    instance_id: InstanceId,
+    canister_id: Principal,
+) -> u32 {
+    let raw_canister_call = RawCanisterCall {
+        sender: Principal::anonymous().as_slice().to_vec(),
+        canister_id: canister_id.as_slice().to_vec(),
+        effective_principal: RawEffectivePrincipal::None,
+        method: "read".to_string(),
+        payload: vec![],
+    };
+    let response = Client::new()
+        .post(
+            server_url
+                .join(&format!("instances/{}/read/query", instance_id))
+                .unwrap(),
+        )
+        .json(&raw_canister_call)
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    match response.json::<RawCanisterResult>().unwrap() {
+        RawCanisterResult::Ok(RawWasmResult::Reply(data)) => {
+            u32::from_le_bytes(data.try_into().unwrap())
+        }
+        res => panic!("Unexpected query call response: {:?}", res),
+    }
+}
+
+/// Tests that a PocketIC instance can be forked into a new instance
+/// and restored from a named snapshot, using the endpoints
+/// `/instances/<instance_id>/fork` and `/instances/<instance_id>/snapshots/<name>`.
+#[test]
+fn fork_and_snapshot_instance() {
+    const INIT_CYCLES: u128 = 2_000_000_000_000;
+
+    let (server_url, _) = start_server_helper(None, false, false);
+    let pic = PocketIcBuilder::new()
+        .with_server_url(server_url.clone())
+        .with_nns_subnet()
+        .with_application_subnet()
+        .build();
+    let client = Client::new();
+    let instance_url = server_url
+        .join(&format!("instances/{}/", pic.instance_id()))
+        .unwrap();
+
+    // We create a counter canister and bump its counter once.
+    let canister_id = pic.create_canister();
+    pic.add_cycles(canister_id, INIT_CYCLES);
+    let counter_wasm = wat::parse_str(COUNTER_WAT).unwrap();
+    pic.install_canister(canister_id, counter_wasm, vec![], None);
+    pic.update_call(canister_id, Principal::anonymous(), "write", vec![])
+        .unwrap();
+    check_counter(&pic, canister_id, 1);
+
+    // Take a snapshot and fork the instance.
+    let response = client
+        .post(instance_url.join("snapshots/one").unwrap())
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post(instance_url.join("fork").unwrap())
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let fork_instance_id = match response.json::<CreateInstanceResponse>().unwrap() {
+        CreateInstanceResponse::Created {
+            instance_id,
+            topology,
+        } => {
+            let source_topology = pic.topology();
+            assert_eq!(topology.get_nns(), source_topology.get_nns());
+            assert_eq!(
+                topology.get_app_subnets(),
+                source_topology.get_app_subnets()
+            );
+            assert_eq!(
+                topology.default_effective_canister_id,
+                source_topology.default_effective_canister_id
+            );
+            instance_id
+        }
+        CreateInstanceResponse::Error { message } => panic!("{}", message),
+    };
+    assert_ne!(fork_instance_id, pic.instance_id());
+
+    // Bump the counter on the original instance: the fork is not affected.
+    pic.update_call(canister_id, Principal::anonymous(), "write", vec![])
+        .unwrap();
+    check_counter(&pic, canister_id, 2);
+    assert_eq!(
+        query_counter_on_instance(&server_url, fork_instance_id, canister_id),
+        1
+    );
+
+    // Restoring the snapshot resets the counter and the snapshot can be restored again.
+    for _ in 0..2 {
+        let response = client
+            .post(instance_url.join("snapshots/one/restore").unwrap())
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        check_counter(&pic, canister_id, 1);
+        pic.update_call(canister_id, Principal::anonymous(), "write", vec![])
+            .unwrap();
+        check_counter(&pic, canister_id, 2);
+    }
+
+    // Restoring an unknown snapshot fails.
+    let response = client
+        .post(instance_url.join("snapshots/two/restore").unwrap())
+        .send()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Tests that the operations executed on a PocketIC instance are recorded into a journal file
+/// if the PocketIC server is started with `--journal-dir` and that the journal file
+/// can be replayed using `--replay`, reporting the first diverging operation.
+#[test]
+fn record_and_replay_journal() {
+    const INIT_CYCLES: u128 = 2_000_000_000_000;
+
+    let journal_dir = TempDir::new().unwrap();
+    let (server_url, _) = start_server_helper_with_args(
+        None,
+        false,
+        false,
+        &[OsStr::new("--journal-dir"), journal_dir.path().as_os_str()],
+    );
+    let pic = PocketIcBuilder::new()
+        .with_server_url(server_url)
+        .with_nns_subnet()
+        .with_application_subnet()
+        .build();
+    let journal_path = journal_dir
+        .path()
+        .join(format!("instance_{}.jsonl", pic.instance_id()));
+
+    // We create a counter canister, bump its counter, and advance time.
+    let canister_id = pic.create_canister();
+    pic.add_cycles(canister_id, INIT_CYCLES);
+    let counter_wasm = wat::parse_str(COUNTER_WAT).unwrap();
+    pic.install_canister(canister_id, counter_wasm, vec![], None);
+    pic.update_call(canister_id, Principal::anonymous(), "write", vec![])
+        .unwrap();
+    pic.advance_time(Duration::from_secs(60));
+    pic.tick();
+    check_counter(&pic, canister_id, 1);
+    drop(pic);
+
+    // The recorded journal is replayed faithfully.
+    let bin_path = std::env::var_os("POCKET_IC_BIN").expect("Missing PocketIC binary");
+    let replay = |journal_path: &std::path::Path| {
+        Command::new(PathBuf::from(bin_path.clone()))
+            .arg("--replay")
+            .arg(journal_path)
+            .output()
+            .unwrap()
+    };
+    let output = replay(&journal_path);
+    assert!(output.status.success());
+
+    // We tamper with the output of the last recorded operation (the counter query):
+    // the replay reports that operation as diverging.
+    let journal = std::fs::read_to_string(&journal_path).unwrap();
+    let mut lines: Vec<String> = journal.lines().map(|line| line.to_string()).collect();
+    let mut last_entry: serde_json::Value = serde_json::from_str(lines.last().unwrap()).unwrap();
+    last_entry["output"] = serde_json::Value::String("tampered".to_string());
+    *lines.last_mut().unwrap() = last_entry.to_string();
+    let tampered_journal_path = journal_dir.path().join("tampered.jsonl");
+    std::fs::write(&tampered_journal_path, lines.join("\n")).unwrap();
+    let output = replay(&tampered_journal_path);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("Operation #{} (", lines.len() - 2)));
+    assert!(stderr.contains("recorded output: tampered"));
+}
+
+/// Tests that a call through the IC HTTP interface ends the journal of a PocketIC instance
+/// and that the replay of such a journal reports the call.
+#[test]
+fn journal_ends_at_http_call() {
+    const INIT_CYCLES: u128 = 2_000_000_000_000;
+
+    let journal_dir = TempDir::new().unwrap();
+    let (server_url, _) = start_server_helper_with_args(
+        None,
+        false,
+        false,
+        &[OsStr::new("--journal-dir"), journal_dir.path().as_os_str()],
+    );
+    let pic = PocketIcBuilder::new()
+        .with_server_url(server_url.clone())
+        .with_application_subnet()
+        .build();
+    let journal_path = journal_dir
+        .path()
+        .join(format!("instance_{}.jsonl", pic.instance_id()));
+    let instance_url = server_url
+        .join(&format!("instances/{}/", pic.instance_id()))
+        .unwrap();
+
+    let canister_id = pic.create_canister();
+    pic.add_cycles(canister_id, INIT_CYCLES);
+    let counter_wasm = wat::parse_str(COUNTER_WAT).unwrap();
+    pic.install_canister(canister_id, counter_wasm, vec![], None);
+
+    // We bump the counter through the IC HTTP interface of the instance.
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    rt.block_on(async {
+        let agent = ic_agent::Agent::builder()
+            .with_url(instance_url.to_string())
+            .build()
+            .unwrap();
+        agent.fetch_root_key().await.unwrap();
+        agent
+            .update(&canister_id, "write")
+            .with_arg(vec![])
+            .call()
+            .await
+            .unwrap();
+    });
+    pic.tick();
+    check_counter(&pic, canister_id, 1);
+    drop(pic);
+
+    // The journal ends with the call and no further operations are recorded.
+    let journal = std::fs::read_to_string(&journal_path).unwrap();
+    let last_line: serde_json::Value =
+        serde_json::from_str(journal.lines().last().unwrap()).unwrap();
+    assert!(last_line["unrecordable_operation"].is_string());
+
+    // The replay reports the call.
+    let bin_path = std::env::var_os("POCKET_IC_BIN").expect("Missing PocketIC binary");
+    let output = Command::new(PathBuf::from(bin_path))
+        .arg("--replay")
+        .arg(&journal_path)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("Operation #{} (", journal.lines().count() - 2)));
+    assert!(stderr.contains("could not be recorded"));
+}
+
 /// Test that PocketIC can handle synchronous update calls, i.e. `/api/v3/.../call`.
 #[test]
 fn test_specified_id_call_v3() {
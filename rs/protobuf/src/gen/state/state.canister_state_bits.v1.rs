@@ -650,6 +650,15 @@ pub struct CanisterStateBits {
     pub wasm_memory_threshold: ::core::option::Option<u64>,
     #[prost(enumeration = "OnLowWasmMemoryHookStatus", optional, tag = "53")]
     pub on_low_wasm_memory_hook_status: ::core::option::Option<i32>,
+    /// The last round in which the canister's heartbeat was executed.
+    #[prost(uint64, optional, tag = "54")]
+    pub last_heartbeat_execution_round: ::core::option::Option<u64>,
+    /// The last round in which the canister's global timer was executed.
+    #[prost(uint64, optional, tag = "55")]
+    pub last_global_timer_execution_round: ::core::option::Option<u64>,
+    /// Why the heartbeat or global timer was not executed when it was last due.
+    #[prost(enumeration = "TaskSkipReason", optional, tag = "56")]
+    pub task_skip_reason: ::core::option::Option<i32>,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -855,3 +864,38 @@ impl OnLowWasmMemoryHookStatus {
         }
     }
 }
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TaskSkipReason {
+    Unspecified = 0,
+    CanisterNotRunning = 1,
+    LongExecutionPending = 2,
+    OutOfCycles = 3,
+    Frozen = 4,
+}
+impl TaskSkipReason {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "TASK_SKIP_REASON_UNSPECIFIED",
+            Self::CanisterNotRunning => "TASK_SKIP_REASON_CANISTER_NOT_RUNNING",
+            Self::LongExecutionPending => "TASK_SKIP_REASON_LONG_EXECUTION_PENDING",
+            Self::OutOfCycles => "TASK_SKIP_REASON_OUT_OF_CYCLES",
+            Self::Frozen => "TASK_SKIP_REASON_FROZEN",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "TASK_SKIP_REASON_UNSPECIFIED" => Some(Self::Unspecified),
+            "TASK_SKIP_REASON_CANISTER_NOT_RUNNING" => Some(Self::CanisterNotRunning),
+            "TASK_SKIP_REASON_LONG_EXECUTION_PENDING" => Some(Self::LongExecutionPending),
+            "TASK_SKIP_REASON_OUT_OF_CYCLES" => Some(Self::OutOfCycles),
+            "TASK_SKIP_REASON_FROZEN" => Some(Self::Frozen),
+            _ => None,
+        }
+    }
+}
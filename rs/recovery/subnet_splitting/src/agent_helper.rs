@@ -1,5 +1,5 @@
 use ic_agent::{export::Principal, hash_tree::Label, lookup_value, Agent, Certificate};
-use ic_base_types::SubnetId;
+use ic_base_types::{CanisterId, PrincipalId, SubnetId};
 use ic_crypto_utils_threshold_sig_der::{parse_threshold_sig_key, public_key_to_der};
 use ic_recovery::{
     error::{RecoveryError, RecoveryResult},
@@ -16,6 +16,7 @@ const NNS_REGISTRY_CANISTER_ID: &str = "rwlgt-iiaaa-aaaaa-aaaaa-cai";
 const SUBNET_LABEL: &[u8] = b"subnet";
 const PUBLIC_KEY_LABEL: &[u8] = b"public_key";
 const CANISTER_RANGES_LABEL: &[u8] = b"canister_ranges";
+const CONTROLLERS_LABEL: &str = "controllers";
 
 type StorageType = Vec<u8>;
 
@@ -142,6 +143,31 @@ impl AgentHelper {
         })
     }
 
+    /// Reads the controllers of the given canister from the certified state tree.
+    ///
+    /// See: https://internetcomputer.org/docs/current/references/ic-interface-spec#state-tree-canister-information
+    /// for more information
+    pub(crate) fn read_canister_controllers(
+        &self,
+        canister_id: CanisterId,
+    ) -> RecoveryResult<Vec<PrincipalId>> {
+        let controllers = block_on(
+            self.agent
+                .read_state_canister_info(canister_id.get().0, CONTROLLERS_LABEL),
+        )
+        .map_err(|err| agent_error("Failed to read the canister controllers", err))?;
+
+        let controllers: Vec<Principal> = serde_cbor::from_slice(&controllers)
+            .map_err(|err| agent_error("Failed to deserialize the canister controllers", err))?;
+
+        debug!(
+            self.logger,
+            "Controllers of canister {}: {:?}", canister_id, controllers
+        );
+
+        Ok(controllers.into_iter().map(PrincipalId::from).collect())
+    }
+
     /// Validates the state tree.
     pub(crate) fn validate_state_tree(&self, state_tree: &StateTree) -> RecoveryResult<()> {
         self.agent
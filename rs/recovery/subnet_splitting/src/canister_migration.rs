@@ -0,0 +1,634 @@
+//! Migrates individual canisters from one existing subnet to another, offline.
+//!
+//! This is an operator-driven recovery procedure, like subnet splitting: it requires NNS
+//! proposals to halt both subnets at a CUP height and to update the registry, as well as
+//! operator access to the nodes to download, modify and upload the subnet states. It is *not*
+//! a management canister operation that controllers of a canister can trigger themselves.
+//!
+//! Unlike subnet splitting, the destination subnet is an existing subnet, hosting canisters of
+//! its own. The operator specifies a controller of the canisters, which is checked against the
+//! canisters' states, and the canisters must have been stopped beforehand. In-flight messages
+//! are handled the same way as during a subnet split: the `canister_migrations` registry entry
+//! ensures that messages addressed to the migrated canisters are rerouted (or rejected) while
+//! the routing table is being updated.
+use crate::{
+    admin_helper::{
+        get_halt_subnet_at_cup_height_command, get_propose_to_complete_canister_migration_command,
+        get_propose_to_prepare_canister_migration_command,
+        get_propose_to_reroute_canister_ranges_command,
+    },
+    layout::Layout,
+    steps::{CheckCanisterControllersStep, MigrateCanistersStep, ReadRegistryStep, WaitForCUPStep},
+    target_subnet::TargetSubnet,
+    utils::{canister_ids_to_ranges, get_state_hash},
+};
+
+use clap::Parser;
+use ic_base_types::{CanisterId, PrincipalId, SubnetId};
+use ic_recovery::{
+    cli::{consent_given, read_optional},
+    error::{RecoveryError, RecoveryResult},
+    recovery_iterator::RecoveryIterator,
+    recovery_state::{HasRecoveryState, RecoveryState},
+    registry_helper::RegistryPollingStrategy,
+    steps::{AdminStep, DownloadIcStateStep, Step, UploadAndRestartStep},
+    NeuronArgs, Recovery, RecoveryArgs, IC_REGISTRY_LOCAL_STORE,
+};
+use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
+use ic_registry_subnet_type::SubnetType;
+use serde::{Deserialize, Serialize};
+use slog::Logger;
+use strum::{EnumMessage, IntoEnumIterator};
+use strum_macros::{EnumIter, EnumString};
+
+use std::{collections::HashMap, iter::Peekable, net::IpAddr};
+
+const SUBNET_TYPE_ALLOW_LIST: [SubnetType; 2] =
+    [SubnetType::Application, SubnetType::VerifiedApplication];
+
+/// Directories which are not downloaded from the nodes.
+const DOWNLOAD_EXCLUDES: [&str; 3] = ["orchestrator", "ic_consensus_pool", IC_REGISTRY_LOCAL_STORE];
+
+#[derive(
+    Copy,
+    Clone,
+    PartialEq,
+    Debug,
+    Deserialize,
+    EnumIter,
+    EnumMessage,
+    EnumString,
+    Serialize,
+    clap::ValueEnum,
+)]
+pub enum StepType {
+    CheckCanisterControllers,
+    PrepareCanisterMigration,
+    CheckRegistryForCanisterMigrationsEntry,
+    HaltSourceSubnetAtCupHeight,
+    HaltDestinationSubnetAtCupHeight,
+    RerouteCanisterRanges,
+    CheckRegistryForRoutingTableEntry,
+    DownloadStateFromSourceSubnet,
+    DownloadStateFromDestinationSubnet,
+    MigrateCanisters,
+    ProposeCupForSourceSubnet,
+    UploadStateToSourceSubnet,
+    ProposeCupForDestinationSubnet,
+    UploadStateToDestinationSubnet,
+    WaitForCUPOnSourceSubnet,
+    WaitForCUPOnDestinationSubnet,
+    UnhaltSourceSubnet,
+    UnhaltDestinationSubnet,
+    CompleteCanisterMigration,
+    CheckRegistryForCanisterMigrationsEntryAgain,
+    Cleanup,
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Parser, Serialize)]
+#[clap(version = "1.0")]
+pub struct CanisterMigrationArgs {
+    /// Id of the subnet currently hosting the canisters.
+    #[clap(long, value_parser=ic_recovery::util::subnet_id_from_str)]
+    pub source_subnet_id: SubnetId,
+
+    /// Id of the subnet the canisters will be moved to.
+    #[clap(long, value_parser=ic_recovery::util::subnet_id_from_str)]
+    pub destination_subnet_id: SubnetId,
+
+    /// The canisters to be moved to the destination subnet. They must have been stopped by one of
+    /// their controllers.
+    #[clap(long, num_args(1..), required = true)]
+    pub canister_ids: Vec<CanisterId>,
+
+    /// The principal on whose behalf the canisters are migrated. Must be a controller of all
+    /// canisters.
+    #[clap(long)]
+    pub controller: PrincipalId,
+
+    /// Public ssh key to be deployed to both subnets for read only access.
+    #[clap(long)]
+    pub pub_key: Option<String>,
+
+    /// If the downloaded states should be backed up locally.
+    #[clap(long)]
+    pub keep_downloaded_state: Option<bool>,
+
+    /// IP address of the node from the source subnet to download the state from.
+    #[clap(long)]
+    pub download_node_source: Option<IpAddr>,
+
+    /// IP address of the node from the destination subnet to download the state from.
+    #[clap(long)]
+    pub download_node_destination: Option<IpAddr>,
+
+    /// IP address of the node to upload the new source subnet state to.
+    #[clap(long)]
+    pub upload_node_source: Option<IpAddr>,
+
+    /// IP address of the node to upload the new destination subnet state to.
+    #[clap(long)]
+    pub upload_node_destination: Option<IpAddr>,
+
+    /// If present the tool will start execution for the provided step, skipping the initial ones.
+    #[clap(long = "resume")]
+    #[clap(value_enum)]
+    pub next_step: Option<StepType>,
+}
+
+pub struct CanisterMigration {
+    step_iterator: Peekable<StepTypeIter>,
+    params: CanisterMigrationArgs,
+    recovery_args: RecoveryArgs,
+    neuron_args: Option<NeuronArgs>,
+    recovery: Recovery,
+    canister_id_ranges_to_move: Vec<CanisterIdRange>,
+    layout: Layout,
+    logger: Logger,
+}
+
+impl CanisterMigration {
+    pub fn new(
+        logger: Logger,
+        recovery_args: RecoveryArgs,
+        neuron_args: Option<NeuronArgs>,
+        canister_migration_args: CanisterMigrationArgs,
+    ) -> Self {
+        let recovery = Recovery::new(
+            logger.clone(),
+            recovery_args.clone(),
+            neuron_args.clone(),
+            recovery_args.nns_url.clone(),
+            RegistryPollingStrategy::WithEveryRead,
+        )
+        .expect("Failed to initialize recovery");
+
+        let canister_id_ranges_to_move =
+            canister_ids_to_ranges(&canister_migration_args.canister_ids);
+
+        Self::check_preconditions(
+            &recovery,
+            canister_migration_args.source_subnet_id,
+            canister_migration_args.destination_subnet_id,
+            &canister_migration_args.canister_ids,
+        )
+        .expect("Subnets and canisters should satisfy all the preconditions");
+
+        Self {
+            step_iterator: StepType::iter().peekable(),
+            params: canister_migration_args,
+            recovery_args,
+            neuron_args,
+            layout: Layout::new(&recovery),
+            recovery,
+            canister_id_ranges_to_move,
+            logger,
+        }
+    }
+
+    /// Checks whether the subnets and the canisters satisfy the following preconditions:
+    ///
+    /// 1) Both subnets are different subnets of the same type, which is an `Application` type
+    /// 2) Neither subnet is a Chain key subnet
+    /// 3) All canisters are hosted by the source subnet, according to the routing table
+    fn check_preconditions(
+        recovery: &Recovery,
+        source_subnet_id: SubnetId,
+        destination_subnet_id: SubnetId,
+        canister_ids: &[CanisterId],
+    ) -> RecoveryResult<()> {
+        if source_subnet_id == destination_subnet_id {
+            return Err(RecoveryError::ValidationFailed(format!(
+                "Source and destination subnet are the same subnet {}",
+                source_subnet_id
+            )));
+        }
+
+        let source_subnet_type = Self::get_subnet_type(recovery, source_subnet_id)?;
+        let destination_subnet_type = Self::get_subnet_type(recovery, destination_subnet_id)?;
+        if source_subnet_type != destination_subnet_type {
+            return Err(RecoveryError::ValidationFailed(format!(
+                "Both subnets should have the same subnet type. Source subnet type = {:?}, \
+                destination subnet type = {:?}",
+                source_subnet_type, destination_subnet_type,
+            )));
+        }
+
+        let (registry_version, Some(routing_table)) =
+            recovery.registry_helper.get_routing_table()?
+        else {
+            return Err(RecoveryError::ValidationFailed(
+                "Routing table should not be empty".to_string(),
+            ));
+        };
+
+        for canister_id in canister_ids {
+            let hosting_subnet_id = routing_table.route(canister_id.get());
+            if hosting_subnet_id != Some(source_subnet_id) {
+                return Err(RecoveryError::ValidationFailed(format!(
+                    "Canister {} is routed to {:?} instead of the source subnet {} \
+                    at registry version {}",
+                    canister_id, hosting_subnet_id, source_subnet_id, registry_version,
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_subnet_type(recovery: &Recovery, subnet_id: SubnetId) -> RecoveryResult<SubnetType> {
+        let validation_error = |error_message| {
+            Err(RecoveryError::ValidationFailed(format!(
+                "Subnet {}: {}",
+                subnet_id, error_message
+            )))
+        };
+
+        let (_, Some(subnet_record)) = recovery.registry_helper.get_subnet_record(subnet_id)?
+        else {
+            return validation_error("Subnet Record should not be empty".to_string());
+        };
+
+        if subnet_record
+            .chain_key_config
+            .as_ref()
+            .is_some_and(|chain_key_config| !chain_key_config.key_configs.is_empty())
+        {
+            return validation_error("Subnet should not be a Chain key subnet".to_string());
+        }
+
+        let subnet_type = subnet_record
+            .subnet_type()
+            .try_into()
+            .expect("Unexpected subnet type");
+
+        if !SUBNET_TYPE_ALLOW_LIST.contains(&subnet_type) {
+            return validation_error(format!(
+                "Subnet's type ({:?}) is not allowed for canister migration. Allowlist: {:?}",
+                subnet_type, SUBNET_TYPE_ALLOW_LIST,
+            ));
+        }
+
+        Ok(subnet_type)
+    }
+
+    pub fn get_recovery_api(&self) -> &Recovery {
+        &self.recovery
+    }
+
+    fn halt_at_cup_height(&self, target_subnet: TargetSubnet) -> impl Step {
+        AdminStep {
+            logger: self.recovery.logger.clone(),
+            ic_admin_cmd: get_halt_subnet_at_cup_height_command(
+                &self.recovery.admin_helper,
+                self.subnet_id(target_subnet),
+                &self.params.pub_key,
+            ),
+        }
+    }
+
+    fn download_state_step(&self, target_subnet: TargetSubnet) -> RecoveryResult<Box<dyn Step>> {
+        let Some(node_ip) = self.download_node(target_subnet) else {
+            return Err(RecoveryError::StepSkipped);
+        };
+        let keep_downloaded_state = self.params.keep_downloaded_state == Some(true);
+
+        let step: Box<dyn Step> = match target_subnet {
+            TargetSubnet::Source => self
+                .recovery
+                .get_download_state_step(
+                    node_ip,
+                    self.params.pub_key.is_some(),
+                    keep_downloaded_state,
+                    DOWNLOAD_EXCLUDES.to_vec(),
+                )
+                .into(),
+            TargetSubnet::Destination => DownloadIcStateStep {
+                logger: self.recovery.logger.clone(),
+                try_readonly: self.params.pub_key.is_some(),
+                node_ip,
+                target: self
+                    .layout
+                    .original_destination_data_dir()
+                    .display()
+                    .to_string(),
+                working_dir: self
+                    .layout
+                    .work_dir(TargetSubnet::Destination)
+                    .display()
+                    .to_string(),
+                keep_downloaded_state,
+                require_confirmation: !self.recovery_args.test_mode,
+                key_file: self.recovery.key_file.clone(),
+                additional_excludes: DOWNLOAD_EXCLUDES.iter().map(|x| x.to_string()).collect(),
+            }
+            .into(),
+        };
+
+        Ok(step)
+    }
+
+    fn unhalt(&self, target_subnet: TargetSubnet) -> impl Step {
+        self.recovery.halt_subnet(
+            self.subnet_id(target_subnet),
+            /*is_halted=*/ false,
+            /*keys=*/ &[],
+        )
+    }
+
+    fn propose_cup(&self, target_subnet: TargetSubnet) -> RecoveryResult<impl Step> {
+        let checkpoints_dir = self.layout.checkpoints_dir(target_subnet);
+
+        let (max_name, max_height) =
+            Recovery::get_latest_checkpoint_name_and_height(&checkpoints_dir)?;
+
+        let max_checkpoint_dir = checkpoints_dir.join(max_name);
+        let state_hash = get_state_hash(max_checkpoint_dir)?;
+
+        self.recovery.update_recovery_cup(
+            self.subnet_id(target_subnet),
+            Recovery::get_recovery_height(max_height),
+            state_hash,
+            /*replacement_nodes=*/ &[],
+            /*registry_params=*/ None,
+            /*chain_key_subnet_id=*/ None,
+        )
+    }
+
+    fn upload_and_restart_step(&self, target_subnet: TargetSubnet) -> RecoveryResult<impl Step> {
+        match self.upload_node(target_subnet) {
+            Some(node_ip) => Ok(UploadAndRestartStep {
+                logger: self.recovery.logger.clone(),
+                node_ip,
+                work_dir: self.layout.work_dir(target_subnet),
+                data_src: self.layout.ic_state_dir(target_subnet),
+                require_confirmation: !self.recovery_args.skip_prompts,
+                key_file: self.recovery.key_file.clone(),
+                check_ic_replay_height: false,
+            }),
+            None => Err(RecoveryError::StepSkipped),
+        }
+    }
+
+    fn wait_for_cup_step(&self, target_subnet: TargetSubnet) -> RecoveryResult<impl Step> {
+        match self.upload_node(target_subnet) {
+            Some(node_ip) => Ok(WaitForCUPStep {
+                logger: self.recovery.logger.clone(),
+                layout: self.layout.clone(),
+                node_ip,
+                target_subnet,
+            }),
+            None => Err(RecoveryError::StepSkipped),
+        }
+    }
+
+    fn download_node(&self, target_subnet: TargetSubnet) -> Option<IpAddr> {
+        match target_subnet {
+            TargetSubnet::Source => self.params.download_node_source,
+            TargetSubnet::Destination => self.params.download_node_destination,
+        }
+    }
+
+    fn upload_node(&self, target_subnet: TargetSubnet) -> Option<IpAddr> {
+        match target_subnet {
+            TargetSubnet::Source => self.params.upload_node_source,
+            TargetSubnet::Destination => self.params.upload_node_destination,
+        }
+    }
+
+    fn subnet_id(&self, target_subnet: TargetSubnet) -> SubnetId {
+        match target_subnet {
+            TargetSubnet::Source => self.params.source_subnet_id,
+            TargetSubnet::Destination => self.params.destination_subnet_id,
+        }
+    }
+}
+
+impl RecoveryIterator<StepType, StepTypeIter> for CanisterMigration {
+    fn get_step_iterator(&mut self) -> &mut Peekable<StepTypeIter> {
+        &mut self.step_iterator
+    }
+
+    fn store_next_step(&mut self, step_type: Option<StepType>) {
+        self.params.next_step = step_type;
+    }
+
+    fn get_logger(&self) -> &Logger {
+        &self.logger
+    }
+
+    fn interactive(&self) -> bool {
+        !self.recovery_args.skip_prompts
+    }
+
+    fn read_step_params(&mut self, step_type: StepType) {
+        match step_type {
+            StepType::HaltSourceSubnetAtCupHeight => {
+                if self.params.pub_key.is_none() {
+                    self.params.pub_key = read_optional(
+                        &self.logger,
+                        "Enter public key to add readonly SSH access to both subnets. Ensure the right format.\n\
+                        Format:   ssh-ed25519 <pubkey> <identity>\n\
+                        Example:  ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIPwS/0S6xH0g/xLDV0Tz7VeMZE9AKPeSbLmCsq9bY3F1 foo@dfinity.org\n\
+                        Enter your key: ",
+                    )
+                }
+            }
+
+            StepType::DownloadStateFromSourceSubnet => {
+                if self.params.download_node_source.is_none() {
+                    self.params.download_node_source =
+                        read_optional(&self.logger, "Enter download IP on the Source Subnet:");
+                }
+
+                self.params.keep_downloaded_state = Some(consent_given(
+                    &self.logger,
+                    "Preserve original downloaded states locally?",
+                ));
+            }
+
+            StepType::DownloadStateFromDestinationSubnet => {
+                if self.params.download_node_destination.is_none() {
+                    self.params.download_node_destination =
+                        read_optional(&self.logger, "Enter download IP on the Destination Subnet:");
+                }
+            }
+
+            StepType::UploadStateToSourceSubnet => {
+                if self.params.upload_node_source.is_none() {
+                    self.params.upload_node_source = read_optional(
+                        &self.logger,
+                        "Enter IP of node in the Source Subnet with admin access: ",
+                    );
+                }
+            }
+
+            StepType::UploadStateToDestinationSubnet => {
+                if self.params.upload_node_destination.is_none() {
+                    self.params.upload_node_destination = read_optional(
+                        &self.logger,
+                        "Enter IP of node in the Destination Subnet with admin access: ",
+                    );
+                }
+            }
+
+            _ => (),
+        }
+    }
+
+    fn get_step_impl(&self, step_type: StepType) -> RecoveryResult<Box<dyn Step>> {
+        let step: Box<dyn Step> = match step_type {
+            StepType::CheckCanisterControllers => CheckCanisterControllersStep {
+                canister_ids: self.params.canister_ids.clone(),
+                controller: self.params.controller,
+                nns_url: self.recovery_args.nns_url.clone(),
+                layout: self.layout.clone(),
+                logger: self.recovery.logger.clone(),
+            }
+            .into(),
+
+            StepType::PrepareCanisterMigration => AdminStep {
+                logger: self.recovery.logger.clone(),
+                ic_admin_cmd: get_propose_to_prepare_canister_migration_command(
+                    &self.recovery.admin_helper,
+                    &self.canister_id_ranges_to_move,
+                    self.params.source_subnet_id,
+                    self.params.destination_subnet_id,
+                ),
+            }
+            .into(),
+
+            StepType::CheckRegistryForCanisterMigrationsEntry
+            | StepType::CheckRegistryForCanisterMigrationsEntryAgain => {
+                let registry_helper = self.recovery.registry_helper.clone();
+
+                ReadRegistryStep {
+                    logger: self.recovery.logger.clone(),
+                    label: "Canister Migrations".to_string(),
+                    querier: move || registry_helper.get_canister_migrations(),
+                    interactive: !self.recovery_args.skip_prompts,
+                }
+                .into()
+            }
+
+            StepType::HaltSourceSubnetAtCupHeight => {
+                self.halt_at_cup_height(TargetSubnet::Source).into()
+            }
+            StepType::HaltDestinationSubnetAtCupHeight => {
+                self.halt_at_cup_height(TargetSubnet::Destination).into()
+            }
+
+            StepType::RerouteCanisterRanges => AdminStep {
+                logger: self.recovery.logger.clone(),
+                ic_admin_cmd: get_propose_to_reroute_canister_ranges_command(
+                    &self.recovery.admin_helper,
+                    &self.canister_id_ranges_to_move,
+                    self.params.source_subnet_id,
+                    self.params.destination_subnet_id,
+                ),
+            }
+            .into(),
+
+            StepType::CheckRegistryForRoutingTableEntry => {
+                let registry_helper = self.recovery.registry_helper.clone();
+                let source_subnet = self.params.source_subnet_id;
+                let destination_subnet = self.params.destination_subnet_id;
+
+                let get_ranges = move |routing_table: RoutingTable| {
+                    HashMap::from([
+                        (source_subnet, routing_table.ranges(source_subnet)),
+                        (destination_subnet, routing_table.ranges(destination_subnet)),
+                    ])
+                };
+
+                ReadRegistryStep {
+                    logger: self.recovery.logger.clone(),
+                    label: "Routing Table".to_string(),
+                    querier: move || {
+                        registry_helper.get_routing_table().map(
+                            |(registry_version, routing_table)| {
+                                (registry_version, routing_table.map(get_ranges))
+                            },
+                        )
+                    },
+                    interactive: !self.recovery_args.skip_prompts,
+                }
+                .into()
+            }
+
+            StepType::DownloadStateFromSourceSubnet => {
+                self.download_state_step(TargetSubnet::Source)?
+            }
+            StepType::DownloadStateFromDestinationSubnet => {
+                self.download_state_step(TargetSubnet::Destination)?
+            }
+
+            StepType::MigrateCanisters => MigrateCanistersStep {
+                source_subnet_id: self.params.source_subnet_id,
+                destination_subnet_id: self.params.destination_subnet_id,
+                canister_id_ranges_to_move: self.canister_id_ranges_to_move.clone(),
+                layout: self.layout.clone(),
+                logger: self.recovery.logger.clone(),
+            }
+            .into(),
+
+            StepType::ProposeCupForSourceSubnet => self.propose_cup(TargetSubnet::Source)?.into(),
+            StepType::UploadStateToSourceSubnet => {
+                self.upload_and_restart_step(TargetSubnet::Source)?.into()
+            }
+            StepType::ProposeCupForDestinationSubnet => {
+                self.propose_cup(TargetSubnet::Destination)?.into()
+            }
+            StepType::UploadStateToDestinationSubnet => self
+                .upload_and_restart_step(TargetSubnet::Destination)?
+                .into(),
+            StepType::WaitForCUPOnSourceSubnet => {
+                self.wait_for_cup_step(TargetSubnet::Source)?.into()
+            }
+            StepType::WaitForCUPOnDestinationSubnet => {
+                self.wait_for_cup_step(TargetSubnet::Destination)?.into()
+            }
+            StepType::UnhaltSourceSubnet => self.unhalt(TargetSubnet::Source).into(),
+            StepType::UnhaltDestinationSubnet => self.unhalt(TargetSubnet::Destination).into(),
+
+            StepType::CompleteCanisterMigration => AdminStep {
+                logger: self.recovery.logger.clone(),
+                ic_admin_cmd: get_propose_to_complete_canister_migration_command(
+                    &self.recovery.admin_helper,
+                    &self.canister_id_ranges_to_move,
+                    self.params.source_subnet_id,
+                    self.params.destination_subnet_id,
+                ),
+            }
+            .into(),
+
+            StepType::Cleanup => self.recovery.get_cleanup_step().into(),
+        };
+
+        Ok(step)
+    }
+}
+
+impl Iterator for CanisterMigration {
+    type Item = (StepType, Box<dyn Step>);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_step()
+    }
+}
+
+impl HasRecoveryState for CanisterMigration {
+    type StepType = StepType;
+    type SubcommandArgsType = CanisterMigrationArgs;
+
+    fn get_next_step(&self) -> Option<Self::StepType> {
+        self.params.next_step
+    }
+
+    fn get_state(&self) -> RecoveryResult<RecoveryState<Self::SubcommandArgsType>> {
+        Ok(RecoveryState {
+            recovery_args: self.recovery_args.clone(),
+            neuron_args: self.neuron_args.clone(),
+            subcommand_args: self.params.clone(),
+        })
+    }
+}
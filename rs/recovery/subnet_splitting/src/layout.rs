@@ -15,6 +15,7 @@ use std::path::{Path, PathBuf};
 /// |  |-- expected_manifests.data
 /// |  |-- original_source_manifest.data
 /// |  |-- nns.pem
+/// |  |-- original_destination_data/
 /// |  |-- pruned_state_tree.cbor
 /// |  |-- (destination_)work_dir/
 /// |  |   |-- data/
@@ -86,6 +87,12 @@ impl Layout {
             .join("cup.types.v1.CatchUpPackage.pb")
     }
 
+    /// Directory where the original state of the destination subnet is backed up to, when
+    /// migrating canisters between two existing subnets.
+    pub(crate) fn original_destination_data_dir(&self) -> PathBuf {
+        self.root.join("original_destination_data")
+    }
+
     pub(crate) fn work_dir(&self, target_subnet: TargetSubnet) -> PathBuf {
         match target_subnet {
             TargetSubnet::Source => self.source_working_dir.clone(),
@@ -7,7 +7,7 @@ use crate::{
     validation::validate_artifacts,
 };
 
-use ic_base_types::SubnetId;
+use ic_base_types::{CanisterId, PrincipalId, SubnetId};
 use ic_metrics::MetricsRegistry;
 use ic_recovery::{
     cli::consent_given,
@@ -18,9 +18,9 @@ use ic_recovery::{
     util::parse_hex_str,
     Recovery, CUPS_DIR, IC_REGISTRY_LOCAL_STORE,
 };
-use ic_registry_routing_table::CanisterIdRange;
+use ic_registry_routing_table::{CanisterIdRange, CanisterIdRanges};
 use ic_registry_subnet_type::SubnetType;
-use ic_state_manager::split::resolve_ranges_and_split;
+use ic_state_manager::split::{migrate_canisters, resolve_ranges_and_split};
 use ic_types::Height;
 use slog::{error, info, Logger};
 use url::Url;
@@ -173,6 +173,102 @@ impl Step for SplitStateStep {
     }
 }
 
+pub(crate) struct CheckCanisterControllersStep {
+    pub(crate) canister_ids: Vec<CanisterId>,
+    pub(crate) controller: PrincipalId,
+    pub(crate) nns_url: Url,
+    pub(crate) layout: Layout,
+    pub(crate) logger: Logger,
+}
+
+impl Step for CheckCanisterControllersStep {
+    fn descr(&self) -> String {
+        format!(
+            "Check that {} is a controller of all canisters {:?}, as certified in the state tree. \
+            The canisters must also have been stopped by one of their controllers.",
+            self.controller, self.canister_ids,
+        )
+    }
+
+    fn exec(&self) -> RecoveryResult<()> {
+        let agent_helper = AgentHelper::new(
+            &self.nns_url,
+            Some(self.layout.nns_public_key_file()),
+            self.logger.clone(),
+        )?;
+
+        for canister_id in &self.canister_ids {
+            let controllers = agent_helper.read_canister_controllers(*canister_id)?;
+
+            if !controllers.contains(&self.controller) {
+                return Err(RecoveryError::ValidationFailed(format!(
+                    "{} is not a controller of canister {}. Controllers: {:?}",
+                    self.controller, canister_id, controllers,
+                )));
+            }
+        }
+
+        info!(self.logger, "Validation passed!");
+        Ok(())
+    }
+}
+
+pub(crate) struct MigrateCanistersStep {
+    pub(crate) source_subnet_id: SubnetId,
+    pub(crate) destination_subnet_id: SubnetId,
+    pub(crate) canister_id_ranges_to_move: Vec<CanisterIdRange>,
+    pub(crate) layout: Layout,
+    pub(crate) logger: Logger,
+}
+
+impl Step for MigrateCanistersStep {
+    fn descr(&self) -> String {
+        format!(
+            "Moving the canisters within {:?} from the state of the source subnet {} (work dir: {}) \
+            to the state of the destination subnet {} (work dir: {}) and removing all but the \
+            highest checkpoints.",
+            self.canister_id_ranges_to_move,
+            self.source_subnet_id,
+            self.layout.work_dir(TargetSubnet::Source).display(),
+            self.destination_subnet_id,
+            self.layout.work_dir(TargetSubnet::Destination).display(),
+        )
+    }
+
+    fn exec(&self) -> RecoveryResult<()> {
+        // 1. Move the canisters.
+        let canister_id_ranges =
+            CanisterIdRanges::try_from(self.canister_id_ranges_to_move.clone()).map_err(|err| {
+                RecoveryError::ValidationFailed(format!(
+                    "Invalid canister ID ranges {:?}: {:?}",
+                    self.canister_id_ranges_to_move, err
+                ))
+            })?;
+
+        info!(self.logger, "Moving the canisters");
+        let canister_ids = migrate_canisters(
+            self.layout.ic_state_dir(TargetSubnet::Source),
+            self.layout.ic_state_dir(TargetSubnet::Destination),
+            canister_id_ranges,
+            &MetricsRegistry::new(),
+            self.logger.clone().into(),
+        )
+        .map_err(RecoveryError::OutputError)?;
+        info!(self.logger, "Moved canisters {:?}", canister_ids);
+
+        // 2. Remove all the other checkpoints
+        info!(self.logger, "Removing past checkpoints");
+        for target_subnet in [TargetSubnet::Source, TargetSubnet::Destination] {
+            Recovery::remove_all_but_highest_checkpoints(
+                &self.layout.checkpoints_dir(target_subnet),
+                &self.logger,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 pub(crate) struct ComputeExpectedManifestsStep {
     pub(crate) state_tool_helper: StateToolHelper,
     pub(crate) source_subnet_id: SubnetId,
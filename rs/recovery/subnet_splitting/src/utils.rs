@@ -1,4 +1,4 @@
-use ic_base_types::SubnetId;
+use ic_base_types::{CanisterId, SubnetId};
 use ic_protobuf::types::v1 as pb;
 use ic_recovery::{
     error::{RecoveryError, RecoveryResult},
@@ -38,6 +38,21 @@ pub fn canister_id_ranges_to_strings(canister_id_ranges: &[CanisterIdRange]) ->
         .collect::<Vec<_>>()
 }
 
+/// Converts the given canister IDs into sorted, single-canister ID ranges.
+pub(crate) fn canister_ids_to_ranges(canister_ids: &[CanisterId]) -> Vec<CanisterIdRange> {
+    let mut canister_ids = canister_ids.to_vec();
+    canister_ids.sort();
+    canister_ids.dedup();
+
+    canister_ids
+        .into_iter()
+        .map(|canister_id| CanisterIdRange {
+            start: canister_id,
+            end: canister_id,
+        })
+        .collect()
+}
+
 /// Computes the state hash of the given checkpoint.
 pub(crate) fn get_state_hash(checkpoint_dir: impl AsRef<Path>) -> RecoveryResult<String> {
     let manifest = manifest_from_path(checkpoint_dir.as_ref()).map_err(|e| {
@@ -138,4 +153,24 @@ mod tests {
                 .unwrap();
         assert!(find_expected_state_hash_for_subnet_id(&path, subnet_id_3).is_err());
     }
+
+    #[test]
+    fn canister_ids_to_ranges_test() {
+        let canister_1 = CanisterId::from_u64(1);
+        let canister_2 = CanisterId::from_u64(2);
+
+        assert_eq!(
+            canister_ids_to_ranges(&[canister_2, canister_1, canister_2]),
+            vec![
+                CanisterIdRange {
+                    start: canister_1,
+                    end: canister_1,
+                },
+                CanisterIdRange {
+                    start: canister_2,
+                    end: canister_2,
+                },
+            ]
+        );
+    }
 }
@@ -40,6 +40,13 @@ pub struct ReplayToolArgs {
     #[clap(long)]
     /// The replay will stop at this height and make a checkpoint.
     pub replay_until_height: Option<u64>,
+
+    /// If set, a trace of every canister and subnet message executed during
+    /// the replay (canister, message, instructions, cycles balance change,
+    /// outcome and produced outgoing messages) is appended to this file as
+    /// JSON lines. Subnet messages are traced as executed by `aaaaa-aa`.
+    #[clap(long)]
+    pub execution_trace: Option<PathBuf>,
 }
 
 #[derive(Clone, Subcommand)]
@@ -53,6 +53,7 @@ mod validator;
 ///     config: Some(PathBuf::from("/path/to/ic.json5")),
 ///     canister_caller_id: None,
 ///     replay_until_height: None,
+///     execution_trace: None,
 ///     data_root: None,
 ///     subcmd: Some(SubCommand::RestoreFromBackup(RestoreFromBackupCmd {
 ///         registry_local_store_path: PathBuf::from("/path/to/ic_registry_local_store"),
@@ -119,6 +120,7 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 &cmd.registry_local_store_path,
                 subnet_id,
                 cmd.start_height,
+                args.execution_trace,
             )
             .with_replay_target_height(target_height);
             *res_clone.borrow_mut() = player.restore(cmd.start_height + 1);
@@ -133,9 +135,8 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                     "Target height cannot be used with any sub-command in subnet-recovery mode."
                 );
                 }
-                (_, target_height) => {
-                    Player::new(cfg, subnet_id).with_replay_target_height(target_height)
-                }
+                (_, target_height) => Player::new(cfg, subnet_id, args.execution_trace)
+                    .with_replay_target_height(target_height),
             };
 
             if let Some(SubCommand::GetRecoveryCup(cmd)) = subcmd {
@@ -16,7 +16,7 @@ use ic_consensus_utils::{
 };
 use ic_crypto_for_verification_only::CryptoComponentForVerificationOnly;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutionServices, MessageExecutionTrace};
 use ic_interfaces::{
     certification::CertificationPool,
     execution_environment::{IngressHistoryReader, QueryExecutionError, QueryExecutionService},
@@ -67,12 +67,15 @@ use ic_types::{
     CryptoHashOfPartialState, CryptoHashOfState, Height, NodeId, PrincipalId, Randomness,
     RegistryVersion, ReplicaVersion, SubnetId, Time, UserId,
 };
+use ic_utils_thread::JoinOnDrop;
 use serde::{Deserialize, Serialize};
 use slog_async::AsyncGuard;
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
+    fs::File,
+    io::{BufWriter, Write},
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{mpsc::Receiver, Arc},
     time::Duration,
 };
 use tempfile::TempDir;
@@ -132,6 +135,11 @@ pub struct Player {
     // None means finalized height.
     replay_target_height: Option<u64>,
     runtime: Runtime,
+    // Handle to the thread writing the execution trace, if any. Stored so that
+    // on drop, we wait for it to write out all traces. It must be declared
+    // last, so that the thread is joined after the scheduler (owned by
+    // `message_routing`) has dropped the sending side of its channel.
+    _execution_trace_writer: Option<JoinOnDrop<()>>,
 }
 
 impl Player {
@@ -144,6 +152,7 @@ impl Player {
         registry_local_store_path: &Path,
         subnet_id: SubnetId,
         start_height: u64,
+        execution_trace: Option<PathBuf>,
     ) -> Self {
         let (log, _async_log_guard) = new_replica_logger_from_config(&cfg.logger);
 
@@ -202,6 +211,7 @@ impl Player {
             replica_version,
             log,
             _async_log_guard,
+            execution_trace,
         );
         player.tmp_dir = Some(tmp_dir);
         player
@@ -209,7 +219,10 @@ impl Player {
 
     /// Create and return a `Player` from a replica configuration object for
     /// subnet recovery.
-    pub fn new(cfg: Config, subnet_id: SubnetId) -> Self {
+    ///
+    /// If `execution_trace` is set, a trace of every executed canister and
+    /// subnet message is appended to that file as one JSON object per line.
+    pub fn new(cfg: Config, subnet_id: SubnetId, execution_trace: Option<PathBuf>) -> Self {
         let (log, _async_log_guard) = new_replica_logger_from_config(&cfg.logger);
         let metrics_registry = MetricsRegistry::new();
         let registry = setup_registry(cfg.clone(), Some(&metrics_registry));
@@ -248,6 +261,7 @@ impl Player {
             replica_version,
             log,
             _async_log_guard,
+            execution_trace,
         )
     }
 
@@ -261,6 +275,7 @@ impl Player {
         replica_version: ReplicaVersion,
         log: ReplicaLogger,
         _async_log_guard: AsyncGuard,
+        execution_trace: Option<PathBuf>,
     ) -> Self {
         println!("Setting default replica version {}", replica_version);
         if ReplicaVersion::set_default_version(replica_version.clone()).is_err() {
@@ -302,7 +317,27 @@ impl Player {
             MaliciousFlags::default(),
         ));
         let (completed_execution_messages_tx, _) = tokio::sync::mpsc::channel(1);
-        let execution_service = ExecutionServices::setup_execution(
+        let (execution_trace_tx, execution_trace_writer) = execution_trace
+            .map(|path| {
+                let file = File::options()
+                    .create(true)
+                    .append(true)
+                    .open(&path)
+                    .unwrap_or_else(|err| {
+                        panic!("Couldn't open execution trace file {:?}: {}", path, err)
+                    });
+                println!("Writing the execution trace to {:?}...", path);
+                let (tx, rx) = std::sync::mpsc::channel();
+                let writer = JoinOnDrop::new(
+                    std::thread::Builder::new()
+                        .name("Execution Trace Writer".to_string())
+                        .spawn(move || write_execution_trace(rx, BufWriter::new(file)))
+                        .expect("Couldn't spawn the execution trace writer thread"),
+                );
+                (tx, writer)
+            })
+            .unzip();
+        let execution_service = ExecutionServices::setup_execution_with_trace(
             log.clone(),
             &metrics_registry,
             subnet_id,
@@ -313,6 +348,7 @@ impl Player {
             Arc::clone(&state_manager) as Arc<_>,
             state_manager.get_fd_factory(),
             completed_execution_messages_tx,
+            execution_trace_tx,
         );
         let message_routing = Arc::new(MessageRoutingImpl::new(
             state_manager.clone(),
@@ -380,6 +416,7 @@ impl Player {
             tmp_dir: None,
             replay_target_height: None,
             runtime,
+            _execution_trace_writer: execution_trace_writer,
         }
     }
 
@@ -1353,6 +1390,19 @@ fn get_share_certified_hashes(
         .collect::<Vec<_>>()
 }
 
+/// Writes the received execution traces to `out` as JSON lines, flushing
+/// whenever no further traces are pending. Returns once the sending side (i.e.
+/// the scheduler) is dropped.
+fn write_execution_trace(rx: Receiver<MessageExecutionTrace>, mut out: BufWriter<File>) {
+    while let Ok(trace) = rx.recv() {
+        for trace in std::iter::once(trace).chain(rx.try_iter()) {
+            serde_json::to_writer(&mut out, &trace).expect("Couldn't serialize execution trace");
+            writeln!(out).expect("Couldn't write execution trace");
+        }
+        out.flush().expect("Couldn't flush execution trace");
+    }
+}
+
 fn write_records_to_local_store(
     local_store_path: &Path,
     latest_version: RegistryVersion,
@@ -193,13 +193,23 @@ pub fn construct_ic_stack(
     let max_canister_http_requests_in_flight =
         config.hypervisor.max_canister_http_requests_in_flight;
 
+    let mut hypervisor_config = config.hypervisor.clone();
+    if hypervisor_config.persistent_compilation_cache_dir.is_none() {
+        hypervisor_config.persistent_compilation_cache_dir = Some(
+            config
+                .state_manager
+                .state_root()
+                .join(config.state_manager.compilation_cache_dirname()),
+        );
+    }
+
     let execution_services = ExecutionServices::setup_execution(
         log.clone(),
         metrics_registry,
         subnet_id,
         subnet_type,
         subnet_config.scheduler_config,
-        config.hypervisor.clone(),
+        hypervisor_config,
         cycles_account_manager.clone(),
         state_manager.clone(),
         state_manager.get_fd_factory(),
@@ -721,6 +721,10 @@ fn can_get_canister_information() {
                 0u128,
                 0u128,
                 Some(DEFAULT_WASM_MEMORY_LIMIT.get()),
+                None,
+                None,
+                None,
+                None,
             )
         );
 
@@ -778,7 +782,11 @@ fn can_get_canister_information() {
                     0u128,
                     0u128,
                     0u128,
-                    Some(DEFAULT_WASM_MEMORY_LIMIT.get())
+                    Some(DEFAULT_WASM_MEMORY_LIMIT.get()),
+                    None,
+                    None,
+                    None,
+                    None,
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
                 2 * BALANCE_EPSILON,
@@ -10,10 +10,14 @@ use ic_validate_eq_derive::ValidateEq;
 use ic_wasm_types::CanisterModule;
 
 use std::{
+    borrow::Cow,
     collections::{BTreeMap, BTreeSet},
     sync::Arc,
 };
 
+/// The size of the segments of a `WasmModuleUpload`.
+const WASM_MODULE_UPLOAD_SEGMENT_SIZE: usize = 1 << 20;
+
 /// A collection of canister snapshots and their IDs.
 ///
 /// Additionally, keeps track of all the accumulated changes
@@ -59,9 +63,30 @@ impl CanisterSnapshots {
     /// Additionally, adds a new item to the `unflushed_changes`
     /// which represents the new backup accumulated since the last flush to the disk.
     pub fn push(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) -> SnapshotId {
-        let canister_id = snapshot.canister_id();
+        self.unflushed_changes.push(SnapshotOperation::Backup(
+            snapshot.canister_id(),
+            snapshot_id,
+        ));
+        self.insert(snapshot_id, snapshot)
+    }
+
+    /// Adds a snapshot created from uploaded metadata in the collection.
+    ///
+    /// Unlike `push`, the snapshot is not a copy of the canister's state, so
+    /// the new item added to the `unflushed_changes` only creates an empty
+    /// snapshot directory. The uploaded contents are written at the next checkpoint.
+    pub fn push_uploaded(
+        &mut self,
+        snapshot_id: SnapshotId,
+        snapshot: Arc<CanisterSnapshot>,
+    ) -> SnapshotId {
         self.unflushed_changes
-            .push(SnapshotOperation::Backup(canister_id, snapshot_id));
+            .push(SnapshotOperation::Upload(snapshot_id));
+        self.insert(snapshot_id, snapshot)
+    }
+
+    fn insert(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) -> SnapshotId {
+        let canister_id = snapshot.canister_id();
         self.memory_usage += snapshot.size();
         self.snapshots.insert(snapshot_id, snapshot);
         let snapshot_ids = self.snapshot_ids.entry(canister_id).or_default();
@@ -302,6 +327,86 @@ pub struct ExecutionStateSnapshot {
     pub wasm_memory: PageMemory,
 }
 
+/// The Wasm module of a snapshot whose contents are being uploaded.
+///
+/// The module is split into segments that are shared between the clones of
+/// the snapshot, so that a write only copies the segments it modifies. The
+/// `CanisterModule` (and its hash) is only built once the upload is finished
+/// (see `CanisterSnapshot::finish_wasm_module_upload`).
+#[derive(Clone, Eq, PartialEq)]
+struct WasmModuleUpload {
+    segments: Vec<Arc<Vec<u8>>>,
+    len: usize,
+    /// The number of bytes written so far, counted as heap delta.
+    bytes_written: u64,
+}
+
+impl WasmModuleUpload {
+    fn zeroed(len: usize) -> Self {
+        let segments = (0..len)
+            .step_by(WASM_MODULE_UPLOAD_SEGMENT_SIZE)
+            .map(|start| Arc::new(vec![0; WASM_MODULE_UPLOAD_SEGMENT_SIZE.min(len - start)]))
+            .collect();
+        Self {
+            segments,
+            len,
+            bytes_written: 0,
+        }
+    }
+
+    fn from_slice(module: &[u8]) -> Self {
+        Self {
+            segments: module
+                .chunks(WASM_MODULE_UPLOAD_SEGMENT_SIZE)
+                .map(|segment| Arc::new(segment.to_vec()))
+                .collect(),
+            len: module.len(),
+            bytes_written: 0,
+        }
+    }
+
+    /// Panics if the written range exceeds the module.
+    fn write(&mut self, mut offset: usize, mut bytes: &[u8]) {
+        self.bytes_written += bytes.len() as u64;
+        while !bytes.is_empty() {
+            let segment =
+                Arc::make_mut(&mut self.segments[offset / WASM_MODULE_UPLOAD_SEGMENT_SIZE]);
+            let start = offset % WASM_MODULE_UPLOAD_SEGMENT_SIZE;
+            let len = bytes.len().min(segment.len() - start);
+            segment[start..start + len].copy_from_slice(&bytes[..len]);
+            offset += len;
+            bytes = &bytes[len..];
+        }
+    }
+
+    /// Panics if the read range exceeds the module.
+    fn read(&self, mut offset: usize, len: usize) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(len);
+        while bytes.len() < len {
+            let segment = &self.segments[offset / WASM_MODULE_UPLOAD_SEGMENT_SIZE];
+            let start = offset % WASM_MODULE_UPLOAD_SEGMENT_SIZE;
+            let end = segment.len().min(start + len - bytes.len());
+            bytes.extend_from_slice(&segment[start..end]);
+            offset += end - start;
+        }
+        bytes
+    }
+
+    fn to_vec(&self) -> Vec<u8> {
+        self.read(0, self.len)
+    }
+}
+
+impl std::fmt::Debug for WasmModuleUpload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Ignore the actual module contents when debug formatting.
+        f.debug_struct("WasmModuleUpload")
+            .field("len", &self.len)
+            .field("bytes_written", &self.bytes_written)
+            .finish()
+    }
+}
+
 /// Contains all information related to a canister snapshot.
 #[derive(Clone, Eq, PartialEq, Debug, ValidateEq)]
 pub struct CanisterSnapshot {
@@ -320,6 +425,10 @@ pub struct CanisterSnapshot {
     chunk_store: WasmChunkStore,
     #[validate_eq(CompareWithValidateEq)]
     execution_snapshot: ExecutionStateSnapshot,
+    /// The Wasm module being uploaded, if any. It supersedes the Wasm module
+    /// of the `execution_snapshot` until the upload is finished.
+    #[validate_eq(Ignore)]
+    wasm_module_upload: Option<WasmModuleUpload>,
 }
 
 impl CanisterSnapshot {
@@ -340,6 +449,7 @@ impl CanisterSnapshot {
             chunk_store,
             execution_snapshot,
             size,
+            wasm_module_upload: None,
         }
     }
 
@@ -368,6 +478,7 @@ impl CanisterSnapshot {
             chunk_store: canister.system_state.wasm_chunk_store.clone(),
             execution_snapshot,
             size: canister.snapshot_size_bytes(),
+            wasm_module_upload: None,
         })
     }
 
@@ -399,10 +510,70 @@ impl CanisterSnapshot {
         &self.execution_snapshot.wasm_memory
     }
 
+    /// Returns the Wasm module of this snapshot. The contents of a Wasm module
+    /// upload in progress are only reflected after `Self::finish_wasm_module_upload`.
     pub fn canister_module(&self) -> &CanisterModule {
         &self.execution_snapshot.wasm_binary
     }
 
+    /// Returns the Wasm module of this snapshot including the contents of
+    /// a Wasm module upload in progress (which is built into a new module).
+    pub fn current_canister_module(&self) -> Cow<'_, CanisterModule> {
+        match &self.wasm_module_upload {
+            None => Cow::Borrowed(&self.execution_snapshot.wasm_binary),
+            Some(upload) => Cow::Owned(CanisterModule::new(upload.to_vec())),
+        }
+    }
+
+    /// Returns the size of the Wasm module of this snapshot.
+    pub fn wasm_module_len(&self) -> usize {
+        match &self.wasm_module_upload {
+            None => self.execution_snapshot.wasm_binary.len(),
+            Some(upload) => upload.len,
+        }
+    }
+
+    /// Returns `len` bytes of the Wasm module of this snapshot starting at `offset`.
+    ///
+    /// Panics if the range exceeds the Wasm module.
+    pub fn read_wasm_module(&self, offset: usize, len: usize) -> Vec<u8> {
+        match &self.wasm_module_upload {
+            None => self.execution_snapshot.wasm_binary.as_slice()[offset..offset + len].to_vec(),
+            Some(upload) => upload.read(offset, len),
+        }
+    }
+
+    /// Replaces the Wasm module of this snapshot by a zero-filled module of
+    /// the given size whose contents are going to be uploaded.
+    pub fn start_wasm_module_upload(&mut self, len: usize) {
+        self.wasm_module_upload = Some(WasmModuleUpload::zeroed(len));
+    }
+
+    /// Overwrites the Wasm module of this snapshot at the given offset.
+    /// The `CanisterModule` is only built from the written contents by
+    /// `Self::finish_wasm_module_upload`.
+    ///
+    /// Panics if the written range exceeds the Wasm module.
+    pub fn write_wasm_module(&mut self, offset: usize, bytes: &[u8]) {
+        let wasm_binary = &self.execution_snapshot.wasm_binary;
+        self.wasm_module_upload
+            .get_or_insert_with(|| WasmModuleUpload::from_slice(wasm_binary.as_slice()))
+            .write(offset, bytes);
+    }
+
+    /// Returns true iff a Wasm module upload is in progress.
+    pub fn has_wasm_module_upload(&self) -> bool {
+        self.wasm_module_upload.is_some()
+    }
+
+    /// Builds the Wasm module of this snapshot from the contents of the Wasm
+    /// module upload in progress, if any.
+    pub fn finish_wasm_module_upload(&mut self) {
+        if let Some(upload) = self.wasm_module_upload.take() {
+            self.execution_snapshot.wasm_binary = CanisterModule::new(upload.to_vec());
+        }
+    }
+
     pub fn exported_globals(&self) -> &Vec<Global> {
         &self.execution_snapshot.exported_globals
     }
@@ -426,7 +597,8 @@ impl CanisterSnapshot {
     /// Returns the heap delta produced by this snapshot.
     ///
     /// The heap delta includes the delta of the wasm memory, stable memory and
-    /// the chunk store, i.e. the snapshot parts that are backed by `PageMap`s.
+    /// the chunk store, i.e. the snapshot parts that are backed by `PageMap`s,
+    /// as well as the bytes written by a Wasm module upload in progress.
     pub fn heap_delta(&self) -> NumBytes {
         let delta_pages = self
             .execution_snapshot
@@ -438,7 +610,12 @@ impl CanisterSnapshot {
                 .stable_memory
                 .page_map
                 .num_delta_pages();
-        NumBytes::from((delta_pages * PAGE_SIZE) as u64) + self.chunk_store.heap_delta()
+        let wasm_module_delta = self
+            .wasm_module_upload
+            .as_ref()
+            .map_or(0, |upload| upload.bytes_written);
+        NumBytes::from((delta_pages * PAGE_SIZE) as u64 + wasm_module_delta)
+            + self.chunk_store.heap_delta()
     }
 }
 
@@ -455,6 +632,7 @@ pub enum SnapshotOperation {
     Delete(SnapshotId),
     Backup(CanisterId, SnapshotId),
     Restore(CanisterId, SnapshotId),
+    Upload(SnapshotId),
 }
 
 #[cfg(test)]
@@ -624,4 +802,45 @@ mod tests {
             NumBytes::from(0)
         );
     }
+
+    #[test]
+    fn test_wasm_module_upload() {
+        let canister_id = canister_test_id(0);
+        let (_, mut snapshot) = fake_canister_snapshot(canister_id, 1);
+        let len = 2 * WASM_MODULE_UPLOAD_SEGMENT_SIZE + 10;
+        snapshot.start_wasm_module_upload(len);
+        assert!(snapshot.has_wasm_module_upload());
+        assert_eq!(snapshot.wasm_module_len(), len);
+        assert_eq!(snapshot.heap_delta(), NumBytes::from(0));
+
+        // A write spanning two segments.
+        let offset = WASM_MODULE_UPLOAD_SEGMENT_SIZE - 2;
+        snapshot.write_wasm_module(offset, &[1, 2, 3, 4]);
+        assert_eq!(
+            snapshot.read_wasm_module(offset - 1, 6),
+            vec![0, 1, 2, 3, 4, 0]
+        );
+        assert_eq!(snapshot.heap_delta(), NumBytes::from(4));
+
+        // A clone shares the segments, but not the writes.
+        let clone = snapshot.clone();
+        snapshot.write_wasm_module(len - 1, &[5]);
+        assert_eq!(clone.read_wasm_module(len - 1, 1), vec![0]);
+        assert_eq!(snapshot.read_wasm_module(len - 1, 1), vec![5]);
+
+        // The module is only built when the upload is finished.
+        assert_eq!(snapshot.canister_module().as_slice(), &[1, 2, 3]);
+        let mut expected = vec![0; len];
+        expected[offset..offset + 4].copy_from_slice(&[1, 2, 3, 4]);
+        expected[len - 1] = 5;
+        assert_eq!(snapshot.current_canister_module().as_slice(), &expected[..]);
+        snapshot.finish_wasm_module_upload();
+        assert!(!snapshot.has_wasm_module_upload());
+        assert_eq!(snapshot.canister_module().as_slice(), &expected[..]);
+
+        // Writing to a finished module starts a new upload from its contents.
+        snapshot.write_wasm_module(0, &[6]);
+        expected[0] = 6;
+        assert_eq!(snapshot.current_canister_module().as_slice(), &expected[..]);
+    }
 }
@@ -8,7 +8,9 @@ use crate::canister_state::queues::CanisterOutputQueuesIterator;
 use crate::canister_state::system_state::{ExecutionTask, SystemState};
 use crate::{InputQueueType, StateError};
 pub use execution_state::{EmbedderCache, ExecutionState, ExportedFunctions, Global};
-use ic_management_canister_types::{CanisterStatusType, LogVisibilityV2};
+use ic_management_canister_types::{CanisterStatusType, CanisterTaskSkipReason, LogVisibilityV2};
+use ic_protobuf::proxy::ProxyDecodeError;
+use ic_protobuf::state::canister_state_bits::v1 as pb;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::batch::TotalQueryStats;
 use ic_types::methods::SystemMethod;
@@ -83,6 +85,16 @@ pub struct SchedulerState {
     /// At the end of an "epoch", each node deterministically aggregates all those partial
     /// query statistics received from consensus blocks and mutates these values.
     pub total_query_stats: TotalQueryStats,
+
+    /// The last round in which the canister's heartbeat was executed.
+    pub last_heartbeat_execution_round: Option<ExecutionRound>,
+
+    /// The last round in which the canister's global timer was executed.
+    pub last_global_timer_execution_round: Option<ExecutionRound>,
+
+    /// Why the heartbeat or global timer was not executed when it was last
+    /// due. Reset when one of them is executed.
+    pub task_skip_reason: Option<TaskSkipReason>,
 }
 
 impl Default for SchedulerState {
@@ -97,6 +109,9 @@ impl Default for SchedulerState {
             install_code_debit: 0.into(),
             time_of_last_allocation_charge: UNIX_EPOCH,
             total_query_stats: TotalQueryStats::default(),
+            last_heartbeat_execution_round: None,
+            last_global_timer_execution_round: None,
+            task_skip_reason: None,
         }
     }
 }
@@ -110,6 +125,59 @@ impl SchedulerState {
     }
 }
 
+/// The reason why the scheduler did not execute a due heartbeat or global
+/// timer of a canister.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum TaskSkipReason {
+    /// The canister is stopping or stopped.
+    CanisterNotRunning,
+    /// A long execution or an `install_code` of the canister is pending.
+    LongExecutionPending,
+    /// The canister does not have enough cycles to execute the task.
+    OutOfCycles,
+    /// The cycles balance of the canister is below its freezing threshold.
+    Frozen,
+}
+
+impl From<TaskSkipReason> for CanisterTaskSkipReason {
+    fn from(item: TaskSkipReason) -> Self {
+        match item {
+            TaskSkipReason::CanisterNotRunning => Self::CanisterNotRunning,
+            TaskSkipReason::LongExecutionPending => Self::LongExecutionPending,
+            TaskSkipReason::OutOfCycles => Self::OutOfCycles,
+            TaskSkipReason::Frozen => Self::Frozen,
+        }
+    }
+}
+
+impl From<&TaskSkipReason> for pb::TaskSkipReason {
+    fn from(item: &TaskSkipReason) -> Self {
+        match item {
+            TaskSkipReason::CanisterNotRunning => Self::CanisterNotRunning,
+            TaskSkipReason::LongExecutionPending => Self::LongExecutionPending,
+            TaskSkipReason::OutOfCycles => Self::OutOfCycles,
+            TaskSkipReason::Frozen => Self::Frozen,
+        }
+    }
+}
+
+impl TryFrom<pb::TaskSkipReason> for TaskSkipReason {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::TaskSkipReason) -> Result<Self, Self::Error> {
+        match value {
+            pb::TaskSkipReason::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
+                typ: "TaskSkipReason",
+                err: format!("Unexpected value of task skip reason: {:?}", value),
+            }),
+            pb::TaskSkipReason::CanisterNotRunning => Ok(Self::CanisterNotRunning),
+            pb::TaskSkipReason::LongExecutionPending => Ok(Self::LongExecutionPending),
+            pb::TaskSkipReason::OutOfCycles => Ok(Self::OutOfCycles),
+            pb::TaskSkipReason::Frozen => Ok(Self::Frozen),
+        }
+    }
+}
+
 /// The full state of a single canister.
 #[derive(Clone, PartialEq, Debug, ValidateEq)]
 pub struct CanisterState {
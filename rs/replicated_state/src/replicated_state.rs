@@ -3,7 +3,7 @@ use super::{
     metadata_state::{IngressHistoryState, Stream, Streams, SystemMetadata},
 };
 use crate::{
-    canister_snapshots::CanisterSnapshots,
+    canister_snapshots::{CanisterSnapshot, CanisterSnapshots},
     canister_state::{
         queues::{CanisterInput, CanisterQueuesLoopDetector},
         system_state::{push_input, CanisterOutputQueuesIterator},
@@ -14,13 +14,14 @@ use crate::{
     },
     CanisterQueues,
 };
-use ic_base_types::PrincipalId;
+use ic_base_types::{PrincipalId, SnapshotId};
 use ic_btc_replica_types::BitcoinAdapterResponse;
 use ic_error_types::{ErrorCode, UserError};
 use ic_interfaces::messaging::{
     IngressInductionError, LABEL_VALUE_CANISTER_NOT_FOUND, LABEL_VALUE_CANISTER_STOPPED,
     LABEL_VALUE_CANISTER_STOPPING,
 };
+use ic_management_canister_types::CanisterStatusType;
 use ic_protobuf::state::queues::v1::canister_queues::NextInputQueue;
 use ic_registry_routing_table::RoutingTable;
 use ic_registry_subnet_type::SubnetType;
@@ -1098,6 +1099,71 @@ impl ReplicatedState {
 
         self.update_stream_guaranteed_responses_size_bytes();
     }
+
+    /// Removes the canister with the given ID and all of its snapshots from the
+    /// state, as the first step of migrating the canister to another subnet (see
+    /// [`Self::put_migrated_canister()`] for the second step).
+    ///
+    /// In order for no in-flight messages to be lost, the canister must be
+    /// stopped and have empty input and output queues. Messages that are still
+    /// in streams are rerouted by Message Routing, based on the registry's
+    /// canister migrations and routing table entries.
+    pub fn take_canister_for_migration(
+        &mut self,
+        canister_id: &CanisterId,
+    ) -> Result<(CanisterState, Vec<(SnapshotId, Arc<CanisterSnapshot>)>), String> {
+        let canister_state = self
+            .canister_states
+            .get(canister_id)
+            .ok_or_else(|| format!("Canister {} not found", canister_id))?;
+        if canister_state.status() != CanisterStatusType::Stopped {
+            return Err(format!(
+                "Canister {} must be stopped before migrating it, but it is {}",
+                canister_id,
+                canister_state.system_state.status_string()
+            ));
+        }
+        if canister_state.has_input() || canister_state.has_output() {
+            return Err(format!(
+                "Canister {} has messages in its queues and cannot be migrated",
+                canister_id
+            ));
+        }
+
+        let snapshots = self.canister_snapshots.list_snapshots(*canister_id);
+        self.canister_snapshots.delete_snapshots(*canister_id);
+        let canister_state = self.canister_states.remove(canister_id).unwrap();
+        Ok((canister_state, snapshots))
+    }
+
+    /// Inserts a canister and its snapshots, previously taken out of the state of
+    /// another subnet by [`Self::take_canister_for_migration()`].
+    ///
+    /// The snapshots are added like uploaded snapshots, i.e. their contents must
+    /// not be backed by files of another subnet's checkpoint. Fails if the
+    /// canister or any of its snapshots already exist on this subnet.
+    pub fn put_migrated_canister(
+        &mut self,
+        canister_state: CanisterState,
+        snapshots: Vec<(SnapshotId, Arc<CanisterSnapshot>)>,
+    ) -> Result<(), String> {
+        let canister_id = canister_state.canister_id();
+        if self.canister_states.contains_key(&canister_id) {
+            return Err(format!("Canister {} already exists", canister_id));
+        }
+        if let Some((snapshot_id, _)) = snapshots
+            .iter()
+            .find(|(snapshot_id, _)| self.canister_snapshots.contains(snapshot_id))
+        {
+            return Err(format!("Snapshot {} already exists", snapshot_id));
+        }
+
+        for (snapshot_id, snapshot) in snapshots {
+            self.canister_snapshots.push_uploaded(snapshot_id, snapshot);
+        }
+        self.canister_states.insert(canister_id, canister_state);
+        Ok(())
+    }
 }
 
 /// Converts a `CanisterInput` popped from a subnet input queue into a
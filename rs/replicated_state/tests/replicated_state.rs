@@ -1,4 +1,4 @@
-use ic_base_types::{CanisterId, NumBytes, NumSeconds, PrincipalId, SubnetId};
+use ic_base_types::{CanisterId, NumBytes, NumSeconds, PrincipalId, SnapshotId, SubnetId};
 use ic_btc_interface::Network;
 use ic_btc_replica_types::{
     BitcoinAdapterResponse, BitcoinAdapterResponseWrapper, BitcoinReject,
@@ -15,6 +15,7 @@ use ic_replicated_state::metadata_state::subnet_call_context_manager::BitcoinSen
 use ic_replicated_state::replicated_state::testing::ReplicatedStateTesting;
 use ic_replicated_state::testing::{CanisterQueuesTesting, SystemStateTesting};
 use ic_replicated_state::{
+    canister_snapshots::CanisterSnapshot,
     canister_state::execution_state::{CustomSection, CustomSectionType, WasmMetadata},
     metadata_state::subnet_call_context_manager::{BitcoinGetSuccessorsContext, SubnetCallContext},
     replicated_state::{MemoryTaken, PeekableOutputIterator, ReplicatedStateMessageRouting},
@@ -870,6 +871,67 @@ fn split() {
     assert_eq!(expected, state_b);
 }
 
+#[test]
+fn migrate_canister() {
+    const SUBNET_A: SubnetId = SUBNET_ID;
+    const SUBNET_B: SubnetId = SUBNET_1;
+
+    // Fixture with a running canister and a stopped canister with a snapshot.
+    let mut fixture = ReplicatedStateFixture::new();
+    let stopped_canister = CanisterState::new(
+        SystemState::new_stopped_for_testing(
+            OTHER_CANISTER_ID,
+            user_test_id(24).get(),
+            Cycles::new(1 << 36),
+            NumSeconds::from(100_000),
+        ),
+        Some(ExecutionStateBuilder::default().build()),
+        SchedulerState::default(),
+    );
+    let snapshot_id = SnapshotId::from((OTHER_CANISTER_ID, 0));
+    let snapshot =
+        Arc::new(CanisterSnapshot::from_canister(&stopped_canister, UNIX_EPOCH).unwrap());
+    fixture.state.put_canister_state(stopped_canister.clone());
+    fixture
+        .state
+        .canister_snapshots
+        .push(snapshot_id, Arc::clone(&snapshot));
+    assert_eq!(SUBNET_A, fixture.state.metadata.own_subnet_id);
+
+    // Running canisters cannot be migrated.
+    assert!(fixture
+        .state
+        .take_canister_for_migration(&CANISTER_ID)
+        .is_err());
+    assert!(fixture.state.canister_state(&CANISTER_ID).is_some());
+
+    // The stopped canister is removed, together with its snapshot.
+    let (canister_state, snapshots) = fixture
+        .state
+        .take_canister_for_migration(&OTHER_CANISTER_ID)
+        .unwrap();
+    assert_eq!(stopped_canister, canister_state);
+    assert_eq!(vec![(snapshot_id, Arc::clone(&snapshot))], snapshots);
+    assert!(fixture.state.canister_state(&OTHER_CANISTER_ID).is_none());
+    assert!(!fixture.state.canister_snapshots.contains(&snapshot_id));
+
+    // And can be inserted into the state of another subnet.
+    let mut state_b = ReplicatedState::new(SUBNET_B, SubnetType::Application);
+    state_b
+        .put_migrated_canister(canister_state.clone(), snapshots.clone())
+        .unwrap();
+    assert_eq!(
+        Some(&stopped_canister),
+        state_b.canister_state(&OTHER_CANISTER_ID)
+    );
+    assert_eq!(Some(&snapshot), state_b.canister_snapshots.get(snapshot_id));
+
+    // But only once.
+    assert!(state_b
+        .put_migrated_canister(canister_state, snapshots)
+        .is_err());
+}
+
 #[test]
 fn input_source_roundtrip() {
     use ic_protobuf::state::queues::v1::canister_queues as pb;
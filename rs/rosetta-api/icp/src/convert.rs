@@ -12,8 +12,10 @@ use crate::request::Request;
 use crate::request_types::{
     ChangeAutoStakeMaturityMetadata, DisburseMetadata, FollowMetadata, KeyMetadata,
     MergeMaturityMetadata, NeuronIdentifierMetadata, NeuronInfoMetadata, PublicKeyOrPrincipal,
-    RegisterVoteMetadata, RequestResultMetadata, SetDissolveTimestampMetadata, SpawnMetadata,
-    StakeMaturityMetadata, Status, STATUS_COMPLETED,
+    RegisterVoteMetadata, RequestResultMetadata, SetDissolveTimestampMetadata, SnsDisburseMetadata,
+    SnsFollowMetadata, SnsNeuronIdentifierMetadata, SnsRegisterVoteMetadata,
+    SnsSetDissolveTimestampMetadata, SnsStakeMetadata, SpawnMetadata, StakeMaturityMetadata,
+    Status, STATUS_COMPLETED,
 };
 use crate::transaction_id::TransactionIdentifier;
 use crate::{convert, errors};
@@ -33,6 +35,7 @@ use rosetta_core::convert::principal_id_from_public_key;
 use serde_json::map::Map;
 use serde_json::{from_value, Number, Value};
 use std::convert::{TryFrom, TryInto};
+use std::str::FromStr;
 
 /// This module converts from ledger_canister data structures to Rosetta data
 /// structures
@@ -292,6 +295,96 @@ pub fn operations_to_requests(
                 };
                 state.follow(account, pid, neuron_index, topic, followees)?;
             }
+            OperationType::SnsStake => {
+                let SnsStakeMetadata {
+                    sns_ledger_canister_id,
+                    sns_governance_canister_id,
+                    neuron_index,
+                    amount,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.sns_stake(
+                    account,
+                    sns_ledger_canister_id,
+                    sns_governance_canister_id,
+                    neuron_index,
+                    amount,
+                )?;
+            }
+            OperationType::SnsClaimNeuron => {
+                let SnsNeuronIdentifierMetadata {
+                    sns_governance_canister_id,
+                    neuron_index,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.sns_claim_neuron(account, sns_governance_canister_id, neuron_index)?;
+            }
+            OperationType::SnsSetDissolveTimestamp => {
+                let SnsSetDissolveTimestampMetadata {
+                    sns_governance_canister_id,
+                    neuron_index,
+                    timestamp,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.sns_set_dissolve_timestamp(
+                    account,
+                    sns_governance_canister_id,
+                    neuron_index,
+                    timestamp,
+                )?;
+            }
+            OperationType::SnsDisburse => {
+                let SnsDisburseMetadata {
+                    sns_governance_canister_id,
+                    neuron_index,
+                    amount,
+                    recipient,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                let recipient = recipient
+                    .map(|r| icrc_ledger_types::icrc1::account::Account::from_str(&r))
+                    .transpose()
+                    .map_err(|e| op_error(o, format!("Invalid recipient account: {}", e)))?;
+                state.sns_disburse(
+                    account,
+                    sns_governance_canister_id,
+                    neuron_index,
+                    amount,
+                    recipient,
+                )?;
+            }
+            OperationType::SnsFollow => {
+                let SnsFollowMetadata {
+                    sns_governance_canister_id,
+                    neuron_index,
+                    function_id,
+                    followees,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.sns_follow(
+                    account,
+                    sns_governance_canister_id,
+                    neuron_index,
+                    function_id,
+                    followees,
+                )?;
+            }
+            OperationType::SnsRegisterVote => {
+                let SnsRegisterVoteMetadata {
+                    sns_governance_canister_id,
+                    neuron_index,
+                    proposal,
+                    vote,
+                } = o.metadata.clone().try_into()?;
+                validate_neuron_management_op()?;
+                state.sns_register_vote(
+                    account,
+                    sns_governance_canister_id,
+                    neuron_index,
+                    proposal,
+                    vote,
+                )?;
+            }
         }
     }
 
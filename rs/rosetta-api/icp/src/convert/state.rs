@@ -3,11 +3,13 @@ use crate::models::seconds::Seconds;
 use crate::request::Request;
 use crate::request_types::{
     AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, ListNeurons, MergeMaturity, NeuronInfo,
-    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Stake,
+    PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, SetDissolveTimestamp, SnsClaimNeuron,
+    SnsDisburse, SnsFollow, SnsRegisterVote, SnsSetDissolveTimestamp, SnsStake, Spawn, Stake,
     StakeMaturity, StartDissolve, StopDissolve,
 };
 use ic_types::PrincipalId;
 use icp_ledger::{Operation, Tokens, DEFAULT_TRANSFER_FEE};
+use icrc_ledger_types::icrc1::account::Account;
 
 /// Helper for `from_operations` that creates `Transfer`s from related
 /// debit/credit/fee operations.
@@ -384,6 +386,125 @@ impl State {
         }));
         Ok(())
     }
+
+    pub fn sns_stake(
+        &mut self,
+        account: icp_ledger::AccountIdentifier,
+        sns_ledger_canister_id: PrincipalId,
+        sns_governance_canister_id: PrincipalId,
+        neuron_index: u64,
+        amount: u64,
+    ) -> Result<(), ApiError> {
+        if amount == 0 {
+            let msg = "The amount to stake in an SNS neuron must be positive".to_string();
+            let err = ApiError::InvalidTransaction(false, msg.into());
+            return Err(err);
+        }
+        self.flush()?;
+        self.actions.push(Request::SnsStake(SnsStake {
+            account,
+            sns_ledger_canister_id,
+            sns_governance_canister_id,
+            amount,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn sns_claim_neuron(
+        &mut self,
+        account: icp_ledger::AccountIdentifier,
+        sns_governance_canister_id: PrincipalId,
+        neuron_index: u64,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::SnsClaimNeuron(SnsClaimNeuron {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+        }));
+        Ok(())
+    }
+
+    pub fn sns_set_dissolve_timestamp(
+        &mut self,
+        account: icp_ledger::AccountIdentifier,
+        sns_governance_canister_id: PrincipalId,
+        neuron_index: u64,
+        timestamp: Seconds,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions
+            .push(Request::SnsSetDissolveTimestamp(SnsSetDissolveTimestamp {
+                account,
+                sns_governance_canister_id,
+                neuron_index,
+                timestamp,
+            }));
+        Ok(())
+    }
+
+    pub fn sns_disburse(
+        &mut self,
+        account: icp_ledger::AccountIdentifier,
+        sns_governance_canister_id: PrincipalId,
+        neuron_index: u64,
+        amount: Option<u64>,
+        recipient: Option<Account>,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::SnsDisburse(SnsDisburse {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+            amount,
+            recipient,
+        }));
+        Ok(())
+    }
+
+    pub fn sns_follow(
+        &mut self,
+        account: icp_ledger::AccountIdentifier,
+        sns_governance_canister_id: PrincipalId,
+        neuron_index: u64,
+        function_id: u64,
+        followees: Vec<String>,
+    ) -> Result<(), ApiError> {
+        if let Some(invalid) = followees.iter().find(|f| hex::decode(f).is_err()) {
+            let msg = format!("Invalid SNS followee neuron id: {}", invalid);
+            let err = ApiError::InvalidTransaction(false, msg.into());
+            return Err(err);
+        }
+        self.flush()?;
+        self.actions.push(Request::SnsFollow(SnsFollow {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+            function_id,
+            followees,
+        }));
+        Ok(())
+    }
+
+    pub fn sns_register_vote(
+        &mut self,
+        account: icp_ledger::AccountIdentifier,
+        sns_governance_canister_id: PrincipalId,
+        neuron_index: u64,
+        proposal: u64,
+        vote: i32,
+    ) -> Result<(), ApiError> {
+        self.flush()?;
+        self.actions.push(Request::SnsRegisterVote(SnsRegisterVote {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+            proposal,
+            vote,
+        }));
+        Ok(())
+    }
 }
 
 /// Structure for manipulating tokens in relation to account, for example during transfers.
@@ -2,7 +2,10 @@ use super::*;
 use crate::models::amount::signed_amount;
 use crate::models::operation::OperationType;
 use crate::models::OperationIdentifier;
-use crate::request_types::Stake;
+use crate::request_types::{
+    SnsClaimNeuron, SnsDisburse, SnsFollow, SnsRegisterVote, SnsSetDissolveTimestamp, SnsStake,
+    Stake,
+};
 use crate::DEFAULT_TOKEN_SYMBOL;
 use icp_ledger::AccountIdentifier;
 use icp_ledger::Operation as LedgerOperation;
@@ -180,6 +183,83 @@ fn test_can_handle_multiple_transfers() {
     );
 }
 
+#[test]
+fn test_sns_requests_round_trip() {
+    let sns_ledger_canister_id = PrincipalId::new_user_test_id(1);
+    let sns_governance_canister_id = PrincipalId::new_user_test_id(2);
+    let requests = vec![
+        Request::SnsStake(SnsStake {
+            account: test_account(1),
+            sns_ledger_canister_id,
+            sns_governance_canister_id,
+            amount: 100_000_000,
+            neuron_index: 3,
+        }),
+        Request::SnsClaimNeuron(SnsClaimNeuron {
+            account: test_account(1),
+            sns_governance_canister_id,
+            neuron_index: 3,
+        }),
+        Request::SnsSetDissolveTimestamp(SnsSetDissolveTimestamp {
+            account: test_account(1),
+            sns_governance_canister_id,
+            neuron_index: 3,
+            timestamp: crate::models::seconds::Seconds(1_700_000_000),
+        }),
+        Request::SnsFollow(SnsFollow {
+            account: test_account(1),
+            sns_governance_canister_id,
+            neuron_index: 3,
+            function_id: 0,
+            followees: vec!["0a0b0c".to_string()],
+        }),
+        Request::SnsRegisterVote(SnsRegisterVote {
+            account: test_account(1),
+            sns_governance_canister_id,
+            neuron_index: 3,
+            proposal: 42,
+            vote: 1,
+        }),
+        Request::SnsDisburse(SnsDisburse {
+            account: test_account(1),
+            sns_governance_canister_id,
+            neuron_index: 3,
+            amount: Some(50_000_000),
+            recipient: Some(icrc_ledger_types::icrc1::account::Account {
+                owner: PrincipalId::new_user_test_id(3).0,
+                subaccount: Some([7; 32]),
+            }),
+        }),
+    ];
+
+    let operations = Request::requests_to_operations(&requests, DEFAULT_TOKEN_SYMBOL).unwrap();
+    assert!(operations.iter().all(|op| op.amount.is_none()));
+    assert_eq!(
+        operations_to_requests(&operations, false, DEFAULT_TOKEN_SYMBOL),
+        Ok(requests)
+    );
+}
+
+#[test]
+fn test_sns_stake_requires_positive_amount() {
+    let operation = Operation {
+        metadata: Some(
+            SnsStakeMetadata {
+                sns_ledger_canister_id: PrincipalId::new_user_test_id(1),
+                sns_governance_canister_id: PrincipalId::new_user_test_id(2),
+                neuron_index: 0,
+                amount: 0,
+            }
+            .try_into()
+            .unwrap(),
+        ),
+        ..OperationBuilder::new(0, OperationType::SnsStake)
+            .account(test_account(1))
+            .build()
+    };
+    assert!(operations_to_requests(&[operation], false, DEFAULT_TOKEN_SYMBOL).is_err());
+}
+
 #[test]
 fn account_identifier_decode_test() {
     // a good address
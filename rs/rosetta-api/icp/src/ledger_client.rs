@@ -9,6 +9,12 @@ mod handle_register_vote;
 mod handle_remove_hotkey;
 mod handle_send;
 mod handle_set_dissolve_timestamp;
+mod handle_sns_claim_neuron;
+mod handle_sns_disburse;
+mod handle_sns_follow;
+mod handle_sns_register_vote;
+mod handle_sns_set_dissolve_timestamp;
+mod handle_sns_stake;
 mod handle_spawn;
 mod handle_stake;
 mod handle_stake_maturity;
@@ -19,6 +25,7 @@ pub mod list_neurons_response;
 mod neuron_response;
 pub mod pending_proposals_response;
 pub mod proposal_info_response;
+mod sns_response;
 
 use candid::{Decode, Encode};
 use core::ops::Deref;
@@ -64,13 +71,21 @@ use crate::{
         handle_merge_maturity::handle_merge_maturity, handle_neuron_info::handle_neuron_info,
         handle_register_vote::handle_register_vote, handle_remove_hotkey::handle_remove_hotkey,
         handle_send::handle_send, handle_set_dissolve_timestamp::handle_set_dissolve_timestamp,
-        handle_spawn::handle_spawn, handle_stake::handle_stake,
+        handle_sns_claim_neuron::handle_sns_claim_neuron, handle_sns_disburse::handle_sns_disburse,
+        handle_sns_follow::handle_sns_follow, handle_sns_register_vote::handle_sns_register_vote,
+        handle_sns_set_dissolve_timestamp::handle_sns_set_dissolve_timestamp,
+        handle_sns_stake::handle_sns_stake, handle_spawn::handle_spawn, handle_stake::handle_stake,
         handle_stake_maturity::handle_stake_maturity, handle_start_dissolve::handle_start_dissolve,
         handle_stop_dissolve::handle_stop_dissolve, neuron_response::NeuronResponse,
+        sns_response::SnsResponse,
     },
     models::{EnvelopePair, SignedTransaction},
     request::{request_result::RequestResult, transaction_results::TransactionResults, Request},
     request_types::{RequestType, Status},
+    sns_governance::{
+        get_neuron_response, GetNeuron, GetNeuronResponse, Neuron as SnsNeuron,
+        NeuronId as SnsNeuronId,
+    },
     transaction_id::TransactionIdentifier,
 };
 use rosetta_core::objects::ObjectMap;
@@ -111,6 +126,12 @@ pub trait LedgerAccess {
         acc_id: NeuronIdOrSubaccount,
         verified: bool,
     ) -> Result<NeuronInfo, ApiError>;
+    async fn sns_neuron(
+        &self,
+        sns_governance_canister_id: CanisterId,
+        neuron_id: SnsNeuronId,
+        verified: bool,
+    ) -> Result<SnsNeuron, ApiError>;
     async fn proposal_info(&self, proposal_id: u64) -> Result<ProposalInfo, ApiError>;
     async fn pending_proposals(&self) -> Result<Vec<ProposalInfo>, ApiError>;
     async fn list_known_neurons(&self) -> Result<Vec<KnownNeuron>, ApiError>;
@@ -135,6 +156,7 @@ pub enum OperationOutput {
     NeuronResponse(NeuronResponse),
     ProposalInfoResponse(ProposalInfoResponse),
     ListNeuronsResponse(ListNeuronsResponse),
+    SnsResponse(SnsResponse),
 }
 
 fn public_key_to_der(key: ThresholdSigPublicKey) -> Result<Vec<u8>, ApiError> {
@@ -454,6 +476,58 @@ impl LedgerAccess for LedgerClient {
         Ok(ninfo)
     }
 
+    async fn sns_neuron(
+        &self,
+        sns_governance_canister_id: CanisterId,
+        neuron_id: SnsNeuronId,
+        verified: bool,
+    ) -> Result<SnsNeuron, ApiError> {
+        if self.offline {
+            return Err(ApiError::NotAvailableOffline(false, Details::default()));
+        }
+
+        let agent = &self.canister_access.as_ref().unwrap().agent;
+
+        let arg = CandidOne(GetNeuron {
+            neuron_id: Some(neuron_id),
+        })
+        .into_bytes()
+        .map_err(|e| ApiError::internal_error(format!("Serialization failed: {:?}", e)))?;
+        let bytes = if verified {
+            agent
+                .update(&sns_governance_canister_id.get().0, "get_neuron")
+                .with_arg(arg)
+                .call_and_wait()
+                .await
+        } else {
+            agent
+                .query(&sns_governance_canister_id.get().0, "get_neuron")
+                .with_arg(arg)
+                .call()
+                .await
+        }
+        .map_err(|e| ApiError::invalid_request(format!("{}", e)))?;
+        let response: GetNeuronResponse =
+            CandidOne::from_bytes(bytes).map(|c| c.0).map_err(|e| {
+                ApiError::internal_error(format!(
+                    "Deserialization of SNS get_neuron response failed: {:?}",
+                    e
+                ))
+            })?;
+
+        match response.result {
+            Some(get_neuron_response::Result::Neuron(neuron)) => Ok(neuron),
+            Some(get_neuron_response::Result::Error(e)) => Err(ApiError::ICError(ICError {
+                retriable: false,
+                error_message: format!("{}", e),
+                ic_http_status: 0,
+            })),
+            None => Err(ApiError::internal_error(
+                "SNS get_neuron returned an empty response",
+            )),
+        }
+    }
+
     async fn transfer_fee(&self) -> Result<TransferFee, ApiError> {
         let agent = &self.canister_access.as_ref().unwrap().agent;
         let arg = CandidOne(TransferFeeArgs {})
@@ -672,6 +746,9 @@ impl LedgerClient {
                     OperationOutput::ListNeuronsResponse(response) => {
                         result.response = Some(ObjectMap::try_from(response)?)
                     }
+                    OperationOutput::SnsResponse(response) => {
+                        result.response = Some(ObjectMap::try_from(response)?)
+                    }
                 }
                 result.status = Status::Completed;
                 Ok(())
@@ -830,6 +907,12 @@ impl LedgerClient {
             RequestType::Stake { .. } => handle_stake(bytes),
             RequestType::StartDissolve { .. } => handle_start_dissolve(bytes, request_type),
             RequestType::StopDissolve { .. } => handle_stop_dissolve(bytes, request_type),
+            RequestType::SnsStake { .. } => handle_sns_stake(bytes),
+            RequestType::SnsClaimNeuron { .. } => handle_sns_claim_neuron(bytes),
+            RequestType::SnsSetDissolveTimestamp { .. } => handle_sns_set_dissolve_timestamp(bytes),
+            RequestType::SnsDisburse { .. } => handle_sns_disburse(bytes),
+            RequestType::SnsFollow { .. } => handle_sns_follow(bytes),
+            RequestType::SnsRegisterVote { .. } => handle_sns_register_vote(bytes),
         }
     }
 }
@@ -0,0 +1,28 @@
+use crate::{
+    errors::ApiError,
+    ledger_client::{sns_response::SnsResponse, OperationOutput},
+    sns_governance::{
+        manage_neuron_response::{ClaimOrRefreshResponse, Command},
+        ManageNeuronResponse,
+    },
+};
+
+pub fn handle_sns_claim_neuron(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode SNS_CLAIM_NEURON response: {}", err))?;
+    match &response.command {
+        Some(Command::ClaimOrRefresh(ClaimOrRefreshResponse {
+            refreshed_neuron_id,
+        })) => Ok(Ok(Some(OperationOutput::SnsResponse(SnsResponse {
+            sns_neuron_id: refreshed_neuron_id.as_ref().map(ToString::to_string),
+            ..Default::default()
+        })))),
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not claim SNS neuron: {}", err).into(),
+        ))),
+        _ => panic!("Unexpected SNS claim neuron result: {:?}", response.command),
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{
+    errors::ApiError,
+    ledger_client::{sns_response::SnsResponse, OperationOutput},
+    sns_governance::{
+        manage_neuron_response::{Command, DisburseResponse},
+        ManageNeuronResponse,
+    },
+};
+
+pub fn handle_sns_disburse(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode SNS_DISBURSE response: {}", err))?;
+    match &response.command {
+        Some(Command::Disburse(DisburseResponse {
+            transfer_block_height,
+        })) => Ok(Ok(Some(OperationOutput::SnsResponse(SnsResponse {
+            sns_ledger_block_index: Some(*transfer_block_height),
+            ..Default::default()
+        })))),
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not disburse SNS neuron: {}", err).into(),
+        ))),
+        _ => panic!("Unexpected SNS disburse result: {:?}", response.command),
+    }
+}
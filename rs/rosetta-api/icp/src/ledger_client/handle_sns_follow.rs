@@ -0,0 +1,23 @@
+use crate::{
+    errors::ApiError,
+    ledger_client::OperationOutput,
+    sns_governance::{
+        manage_neuron_response::{Command, FollowResponse},
+        ManageNeuronResponse,
+    },
+};
+
+pub fn handle_sns_follow(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode SNS_FOLLOW response: {}", err))?;
+    match &response.command {
+        Some(Command::Follow(FollowResponse {})) => Ok(Ok(None)),
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not follow with SNS neuron: {}", err).into(),
+        ))),
+        _ => panic!("Unexpected SNS follow result: {:?}", response.command),
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{
+    errors::ApiError,
+    ledger_client::OperationOutput,
+    sns_governance::{
+        manage_neuron_response::{Command, RegisterVoteResponse},
+        ManageNeuronResponse,
+    },
+};
+
+pub fn handle_sns_register_vote(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode SNS_REGISTER_VOTE response: {}", err))?;
+    match &response.command {
+        Some(Command::RegisterVote(RegisterVoteResponse {})) => Ok(Ok(None)),
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not register vote with SNS neuron: {}", err).into(),
+        ))),
+        _ => panic!(
+            "Unexpected SNS register vote result: {:?}",
+            response.command
+        ),
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{
+    errors::ApiError,
+    ledger_client::OperationOutput,
+    sns_governance::{
+        manage_neuron_response::{Command, ConfigureResponse},
+        ManageNeuronResponse,
+    },
+};
+
+pub fn handle_sns_set_dissolve_timestamp(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: ManageNeuronResponse = candid::decode_one(bytes.as_ref()).map_err(|err| {
+        format!(
+            "Could not decode SNS_SET_DISSOLVE_TIMESTAMP response: {}",
+            err
+        )
+    })?;
+    match &response.command {
+        Some(Command::Configure(ConfigureResponse {})) => Ok(Ok(None)),
+        Some(Command::Error(err)) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not set SNS neuron dissolve timestamp: {}", err).into(),
+        ))),
+        _ => panic!(
+            "Unexpected SNS set dissolve timestamp result: {:?}",
+            response.command
+        ),
+    }
+}
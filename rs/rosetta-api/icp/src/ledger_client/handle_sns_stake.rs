@@ -0,0 +1,27 @@
+use crate::{
+    errors::ApiError,
+    ledger_client::{sns_response::SnsResponse, OperationOutput},
+};
+use candid::Nat;
+use icrc_ledger_types::icrc1::transfer::TransferError;
+
+pub fn handle_sns_stake(
+    bytes: Vec<u8>,
+) -> Result<Result<Option<OperationOutput>, ApiError>, String> {
+    let response: Result<Nat, TransferError> = candid::decode_one(bytes.as_ref())
+        .map_err(|err| format!("Could not decode SNS_STAKE response: {}", err))?;
+    match response {
+        Ok(block_index) => {
+            let block_index = u64::try_from(&block_index.0)
+                .map_err(|err| format!("SNS ledger block index does not fit in u64: {}", err))?;
+            Ok(Ok(Some(OperationOutput::SnsResponse(SnsResponse {
+                sns_ledger_block_index: Some(block_index),
+                ..Default::default()
+            }))))
+        }
+        Err(err) => Ok(Err(ApiError::TransactionRejected(
+            false,
+            format!("Could not stake SNS neuron: {}", err).into(),
+        ))),
+    }
+}
@@ -0,0 +1,25 @@
+use crate::errors::ApiError;
+use rosetta_core::objects::ObjectMap;
+use serde_json::Value;
+
+/// The outcome of an SNS operation. SNS ledger block indices are reported
+/// separately from `block_index`, which always refers to the ICP ledger.
+#[derive(serde::Serialize, Default)]
+pub struct SnsResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sns_ledger_block_index: Option<u64>,
+    /// Hex encoded id of the SNS neuron.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sns_neuron_id: Option<String>,
+}
+
+impl TryFrom<SnsResponse> for ObjectMap {
+    type Error = ApiError;
+    fn try_from(d: SnsResponse) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(Value::Object(o)) => Ok(o),
+            Ok(o) => Err(ApiError::internal_error(format!("Could not convert SnsResponse to ObjectMap. Expected type Object but received: {:?}",o))),
+            Err(err) => Err(ApiError::internal_error(format!("Could not convert SnsResponse to ObjectMap: {:?}",err))),
+        }
+    }
+}
@@ -6,6 +6,7 @@ pub mod request;
 pub mod request_handler;
 pub mod request_types;
 pub mod rosetta_server;
+pub mod sns_governance;
 pub mod transaction_id;
 
 pub const API_VERSION: &str = "1.4.10";
@@ -8,6 +8,7 @@ use crate::{convert::from_hex, errors, errors::ApiError, request_types::RequestT
 use ic_types::messages::{
     HttpCallContent, HttpCanisterUpdate, HttpReadStateContent, HttpRequestEnvelope,
 };
+use ic_types::PrincipalId;
 pub use rosetta_core::identifiers::*;
 pub use rosetta_core::miscellaneous::*;
 pub use rosetta_core::models::Ed25519KeyPair as EdKeypair;
@@ -15,6 +16,7 @@ pub use rosetta_core::objects::*;
 pub use rosetta_core::request_types::*;
 pub use rosetta_core::response_types::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::convert::{TryFrom, TryInto};
 use std::str::FromStr;
 
@@ -370,6 +372,24 @@ pub enum BalanceAccountType {
         #[serde(skip_serializing_if = "Option::is_none")]
         verified_query: Option<bool>,
     },
+    /// A neuron of an SNS governance canister. The neuron is identified either
+    /// by its hex encoded id or by the public key and neuron index used to
+    /// stake it.
+    #[serde(rename = "sns_neuron")]
+    SnsNeuron {
+        #[serde(rename = "sns_governance_canister_id")]
+        sns_governance_canister_id: PrincipalId,
+
+        #[serde(rename = "neuron_id")]
+        neuron_id: Option<String>,
+
+        #[serde(flatten)]
+        subaccount_components: Option<NeuronSubaccountComponents>,
+
+        #[serde(rename = "verified_query")]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        verified_query: Option<bool>,
+    },
 }
 
 impl Default for BalanceAccountType {
@@ -482,6 +502,64 @@ fn test_neuron_info_request_parsing() {
     );
 }
 
+#[test]
+fn test_sns_neuron_info_request_parsing() {
+    let r1: AccountBalanceMetadata = serde_json::from_str(
+        r#"{
+            "account_type": "sns_neuron",
+            "sns_governance_canister_id": "rrkah-fqaaa-aaaaa-aaaaq-cai",
+            "neuron_id": "0a0b0c"
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        r1,
+        AccountBalanceMetadata {
+            account_type: BalanceAccountType::SnsNeuron {
+                sns_governance_canister_id: PrincipalId::from_str("rrkah-fqaaa-aaaaa-aaaaq-cai")
+                    .unwrap(),
+                neuron_id: Some("0a0b0c".to_string()),
+                subaccount_components: None,
+                verified_query: None,
+            }
+        }
+    );
+
+    let r2: AccountBalanceMetadata = serde_json::from_str(
+        r#"{
+            "account_type": "sns_neuron",
+            "sns_governance_canister_id": "rrkah-fqaaa-aaaaa-aaaaq-cai",
+            "neuron_index": 2,
+            "public_key": {
+              "hex_bytes": "1b400d60aaf34eaf6dcbab9bba46001a23497886cf11066f7846933d30e5ad3f",
+              "curve_type": "edwards25519"
+            },
+            "verified_query": true
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        r2,
+        AccountBalanceMetadata {
+            account_type: BalanceAccountType::SnsNeuron {
+                sns_governance_canister_id: PrincipalId::from_str("rrkah-fqaaa-aaaaa-aaaaq-cai")
+                    .unwrap(),
+                neuron_id: None,
+                subaccount_components: Some(NeuronSubaccountComponents {
+                    neuron_index: 2,
+                    public_key: PublicKey {
+                        hex_bytes:
+                            "1b400d60aaf34eaf6dcbab9bba46001a23497886cf11066f7846933d30e5ad3f"
+                                .to_string(),
+                        curve_type: CurveType::Edwards25519
+                    }
+                }),
+                verified_query: Some(true),
+            }
+        }
+    );
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
 pub enum NeuronState {
     #[serde(rename = "NOT_DISSOLVING")]
@@ -538,6 +616,59 @@ impl From<NeuronInfoResponse> for ObjectMap {
     }
 }
 
+/// Response for SNS neuron public information.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsNeuronInfoResponse {
+    #[serde(rename = "verified_query")]
+    pub verified_query: bool,
+
+    #[serde(rename = "retrieved_at_timestamp_seconds")]
+    pub retrieved_at_timestamp_seconds: u64,
+
+    /// The hex encoded id of the neuron.
+    #[serde(rename = "neuron_id")]
+    pub neuron_id: String,
+
+    /// The current state of the neuron.
+    #[serde(rename = "state")]
+    pub state: NeuronState,
+
+    /// The current age of the neuron.
+    #[serde(rename = "age_seconds")]
+    pub age_seconds: u64,
+
+    /// The current dissolve delay of the neuron.
+    #[serde(rename = "dissolve_delay_seconds")]
+    pub dissolve_delay_seconds: u64,
+
+    #[serde(rename = "created_timestamp_seconds")]
+    pub created_timestamp_seconds: u64,
+
+    /// Current stake of the neuron, in e8s of the SNS token.
+    #[serde(rename = "stake_e8s")]
+    pub stake_e8s: u64,
+
+    #[serde(rename = "maturity_e8s_equivalent")]
+    pub maturity_e8s_equivalent: u64,
+
+    #[serde(rename = "staked_maturity_e8s_equivalent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staked_maturity_e8s_equivalent: Option<u64>,
+
+    /// The hex encoded ids of the followees, per SNS function id.
+    #[serde(rename = "followees")]
+    pub followees: BTreeMap<u64, Vec<String>>,
+}
+
+impl From<SnsNeuronInfoResponse> for ObjectMap {
+    fn from(p: SnsNeuronInfoResponse) -> Self {
+        match serde_json::to_value(p) {
+            Ok(serde_json::Value::Object(o)) => o,
+            _ => unreachable!(),
+        }
+    }
+}
+
 impl TryFrom<Option<ObjectMap>> for NeuronInfoResponse {
     type Error = ApiError;
     fn try_from(o: Option<ObjectMap>) -> Result<Self, Self::Error> {
@@ -25,4 +25,10 @@ pub enum OperationType {
     NeuronInfo,
     ListNeurons,
     Follow,
+    SnsStake,
+    SnsClaimNeuron,
+    SnsSetDissolveTimestamp,
+    SnsDisburse,
+    SnsFollow,
+    SnsRegisterVote,
 }
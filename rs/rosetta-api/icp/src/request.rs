@@ -1,11 +1,20 @@
 use crate::{
-    convert, convert::principal_id_from_public_key_or_principal, errors::ApiError, models,
-    models::seconds::Seconds, request_types::*,
+    convert,
+    convert::principal_id_from_public_key_or_principal,
+    errors::ApiError,
+    models,
+    models::seconds::Seconds,
+    request_types::*,
+    sns_governance::{
+        manage_neuron::{self as sns_manage_neuron, Command as SnsCommand},
+        ManageNeuron as SnsManageNeuron,
+    },
 };
 use dfn_candid::CandidOne;
 use ic_nns_governance_api::pb::v1::manage_neuron::{self, configure, Command, Configure};
 use ic_types::PrincipalId;
 use icp_ledger::Tokens;
+use icrc_ledger_types::icrc1::transfer::TransferArg;
 use on_wire::FromWire;
 use std::convert::{TryFrom, TryInto};
 
@@ -59,6 +68,18 @@ pub enum Request {
     ListNeurons(ListNeurons),
     #[serde(rename = "FOLLOW")]
     Follow(Follow),
+    #[serde(rename = "SNS_STAKE")]
+    SnsStake(SnsStake),
+    #[serde(rename = "SNS_CLAIM_NEURON")]
+    SnsClaimNeuron(SnsClaimNeuron),
+    #[serde(rename = "SNS_SET_DISSOLVE_TIMESTAMP")]
+    SnsSetDissolveTimestamp(SnsSetDissolveTimestamp),
+    #[serde(rename = "SNS_DISBURSE")]
+    SnsDisburse(SnsDisburse),
+    #[serde(rename = "SNS_FOLLOW")]
+    SnsFollow(SnsFollow),
+    #[serde(rename = "SNS_REGISTER_VOTE")]
+    SnsRegisterVote(SnsRegisterVote),
 }
 
 impl Request {
@@ -151,6 +172,32 @@ impl Request {
                 neuron_index: *neuron_index,
                 controller: controller.map(PublicKeyOrPrincipal::Principal),
             }),
+            Request::SnsStake(SnsStake { neuron_index, .. }) => Ok(RequestType::SnsStake {
+                neuron_index: *neuron_index,
+            }),
+            Request::SnsClaimNeuron(SnsClaimNeuron { neuron_index, .. }) => {
+                Ok(RequestType::SnsClaimNeuron {
+                    neuron_index: *neuron_index,
+                })
+            }
+            Request::SnsSetDissolveTimestamp(SnsSetDissolveTimestamp { neuron_index, .. }) => {
+                Ok(RequestType::SnsSetDissolveTimestamp {
+                    neuron_index: *neuron_index,
+                })
+            }
+            Request::SnsDisburse(SnsDisburse { neuron_index, .. }) => {
+                Ok(RequestType::SnsDisburse {
+                    neuron_index: *neuron_index,
+                })
+            }
+            Request::SnsFollow(SnsFollow { neuron_index, .. }) => Ok(RequestType::SnsFollow {
+                neuron_index: *neuron_index,
+            }),
+            Request::SnsRegisterVote(SnsRegisterVote { neuron_index, .. }) => {
+                Ok(RequestType::SnsRegisterVote {
+                    neuron_index: *neuron_index,
+                })
+            }
         }
     }
 
@@ -181,6 +228,12 @@ impl Request {
                 Request::NeuronInfo(o) => builder.neuron_info(o),
                 Request::ListNeurons(o) => builder.list_neurons(o),
                 Request::Follow(o) => builder.follow(o),
+                Request::SnsStake(o) => builder.sns_stake(o),
+                Request::SnsClaimNeuron(o) => builder.sns_claim_neuron(o),
+                Request::SnsSetDissolveTimestamp(o) => builder.sns_set_dissolve_timestamp(o),
+                Request::SnsDisburse(o) => builder.sns_disburse(o),
+                Request::SnsFollow(o) => builder.sns_follow(o),
+                Request::SnsRegisterVote(o) => builder.sns_register_vote(o),
             }?;
         }
         Ok(builder.build())
@@ -208,6 +261,12 @@ impl Request {
                 | Request::ListNeurons(_) // not neuron management but we need it signed.
                 | Request::NeuronInfo(_) // not neuron management but we need it signed.
                 | Request::Follow(_)
+                | Request::SnsStake(_)
+                | Request::SnsClaimNeuron(_)
+                | Request::SnsSetDissolveTimestamp(_)
+                | Request::SnsDisburse(_)
+                | Request::SnsFollow(_)
+                | Request::SnsRegisterVote(_)
         )
     }
 }
@@ -244,6 +303,20 @@ impl TryFrom<&models::Request> for Request {
             .map(|m| m.0.command)
         };
 
+        let sns_manage_neuron = || {
+            CandidOne::<SnsManageNeuron>::from_bytes(payload.update_content().arg.0.clone())
+                .map_err(|e| {
+                    ApiError::invalid_request(format!("Could not parse SNS manage_neuron: {}", e))
+                })
+                .map(|m| m.0.command)
+        };
+
+        let canister_id = || {
+            PrincipalId::try_from(payload.update_content().canister_id.0.clone()).map_err(|e| {
+                ApiError::internal_error(format!("Could not parse envelope canister id: {}", e))
+            })
+        };
+
         match request_type {
             RequestType::Send => {
                 let icp_ledger::SendArgs {
@@ -502,6 +575,124 @@ impl TryFrom<&models::Request> for Request {
                     Err(ApiError::invalid_request("Invalid follow request."))
                 }
             }
+            RequestType::SnsStake { neuron_index } => {
+                let TransferArg { to, amount, .. } =
+                    CandidOne::<TransferArg>::from_bytes(payload.update_content().arg.0.clone())
+                        .map_err(|e| {
+                            ApiError::invalid_request(format!(
+                                "Could not parse icrc1_transfer: {}",
+                                e
+                            ))
+                        })?
+                        .0;
+                let amount = u64::try_from(&amount.0).map_err(|e| {
+                    ApiError::invalid_request(format!("Invalid SNS stake amount: {}", e))
+                })?;
+                Ok(Request::SnsStake(SnsStake {
+                    account,
+                    sns_ledger_canister_id: canister_id()?,
+                    sns_governance_canister_id: PrincipalId::from(to.owner),
+                    amount,
+                    neuron_index: *neuron_index,
+                }))
+            }
+            RequestType::SnsClaimNeuron { neuron_index } => {
+                if let Some(SnsCommand::ClaimOrRefresh(_)) = sns_manage_neuron()? {
+                    Ok(Request::SnsClaimNeuron(SnsClaimNeuron {
+                        account,
+                        sns_governance_canister_id: canister_id()?,
+                        neuron_index: *neuron_index,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request(
+                        "Invalid SNS claim neuron request.",
+                    ))
+                }
+            }
+            RequestType::SnsSetDissolveTimestamp { neuron_index } => {
+                if let Some(SnsCommand::Configure(sns_manage_neuron::Configure {
+                    operation:
+                        Some(sns_manage_neuron::configure::Operation::SetDissolveTimestamp(
+                            sns_manage_neuron::SetDissolveTimestamp {
+                                dissolve_timestamp_seconds,
+                            },
+                        )),
+                })) = sns_manage_neuron()?
+                {
+                    Ok(Request::SnsSetDissolveTimestamp(SnsSetDissolveTimestamp {
+                        account,
+                        sns_governance_canister_id: canister_id()?,
+                        neuron_index: *neuron_index,
+                        timestamp: Seconds(dissolve_timestamp_seconds),
+                    }))
+                } else {
+                    Err(ApiError::invalid_request(
+                        "Request is missing set dissolve timestamp operation.",
+                    ))
+                }
+            }
+            RequestType::SnsDisburse { neuron_index } => {
+                if let Some(SnsCommand::Disburse(sns_manage_neuron::Disburse {
+                    amount,
+                    to_account,
+                })) = sns_manage_neuron()?
+                {
+                    let recipient = to_account
+                        .map(icrc_ledger_types::icrc1::account::Account::try_from)
+                        .transpose()
+                        .map_err(|e| {
+                            ApiError::invalid_request(format!(
+                                "Could not parse recipient account: {}",
+                                e
+                            ))
+                        })?;
+                    Ok(Request::SnsDisburse(SnsDisburse {
+                        account,
+                        sns_governance_canister_id: canister_id()?,
+                        neuron_index: *neuron_index,
+                        amount: amount.map(|a| a.e8s),
+                        recipient,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid SNS disburse request."))
+                }
+            }
+            RequestType::SnsFollow { neuron_index } => {
+                if let Some(SnsCommand::Follow(sns_manage_neuron::Follow {
+                    function_id,
+                    followees,
+                })) = sns_manage_neuron()?
+                {
+                    Ok(Request::SnsFollow(SnsFollow {
+                        account,
+                        sns_governance_canister_id: canister_id()?,
+                        neuron_index: *neuron_index,
+                        function_id,
+                        followees: followees.iter().map(|n| n.to_string()).collect(),
+                    }))
+                } else {
+                    Err(ApiError::invalid_request("Invalid SNS follow request."))
+                }
+            }
+            RequestType::SnsRegisterVote { neuron_index } => {
+                if let Some(SnsCommand::RegisterVote(sns_manage_neuron::RegisterVote {
+                    proposal: Some(proposal),
+                    vote,
+                })) = sns_manage_neuron()?
+                {
+                    Ok(Request::SnsRegisterVote(SnsRegisterVote {
+                        account,
+                        sns_governance_canister_id: canister_id()?,
+                        neuron_index: *neuron_index,
+                        proposal: proposal.id,
+                        vote,
+                    }))
+                } else {
+                    Err(ApiError::invalid_request(
+                        "Invalid SNS register vote request.",
+                    ))
+                }
+            }
         }
     }
 }
@@ -21,9 +21,11 @@ use crate::{
         BlockTransaction, BlockTransactionResponse, CallResponse, Error, NetworkIdentifier,
         NetworkOptionsResponse, NetworkStatusResponse, NeuronInfoResponse, NeuronState,
         NeuronSubaccountComponents, OperationStatus, PartialBlockIdentifier,
-        QueryBlockRangeRequest, QueryBlockRangeResponse, SearchTransactionsResponse, Version,
+        QueryBlockRangeRequest, QueryBlockRangeResponse, SearchTransactionsResponse,
+        SnsNeuronInfoResponse, Version,
     },
     request_types::{GetProposalInfo, STATUS_COMPLETED},
+    sns_governance::{NeuronId as SnsNeuronId, NeuronState as SnsNeuronState},
     API_VERSION, MAX_BLOCKS_PER_QUERY_BLOCK_RANGE_REQUEST, NODE_VERSION,
 };
 use ic_ledger_canister_blocks_synchronizer::{
@@ -49,6 +51,12 @@ use strum::IntoEnumIterator;
 /// The maximum amount of blocks to retrieve in a single search.
 const MAX_SEARCH_LIMIT: usize = 10_000;
 
+/// The neuron whose information is attached to an /account/balance response.
+enum NeuronInfoRequest {
+    Nns(NeuronIdOrSubaccount, bool),
+    Sns(CanisterId, SnsNeuronId, bool),
+}
+
 #[derive(Clone)]
 pub struct RosettaRequestHandler {
     blockchain: String,
@@ -125,15 +133,16 @@ impl RosettaRequestHandler {
                             neuron_index,
                         )?;
 
-                    Some((
+                    Some(NeuronInfoRequest::Nns(
                         NeuronIdOrSubaccount::Subaccount(neuron_subaccount.to_vec()),
                         verified,
                     ))
                 } else {
                     match neuron_id {
-                        Some(id) => {
-                            Some((NeuronIdOrSubaccount::NeuronId(NeuronId { id }), verified))
-                        }
+                        Some(id) => Some(NeuronInfoRequest::Nns(
+                            NeuronIdOrSubaccount::NeuronId(NeuronId { id }),
+                            verified,
+                        )),
                         None => {
                             return Err(ApiError::invalid_request(
                                 "Invalid neuron account balance request: either neuron_id or public_key must be present",
@@ -142,12 +151,64 @@ impl RosettaRequestHandler {
                     }
                 }
             }
+            BalanceAccountType::SnsNeuron {
+                sns_governance_canister_id,
+                neuron_id,
+                subaccount_components,
+                verified_query,
+            } => {
+                let verified = verified_query.unwrap_or(false);
+                let sns_governance_canister_id = CanisterId::try_from(sns_governance_canister_id)
+                    .map_err(|e| {
+                    ApiError::invalid_request(format!("Invalid SNS governance canister id: {}", e))
+                })?;
+
+                // The id of an SNS neuron is the subaccount it was staked with.
+                let neuron_id = match (neuron_id, subaccount_components) {
+                    (Some(_), Some(_)) => {
+                        return Err(ApiError::invalid_request(
+                            "Only one of neuron_id or the combination of public_key and neuron_index must be present",
+                        ));
+                    }
+                    (Some(id), None) => hex::decode(&id).map_err(|e| {
+                        ApiError::invalid_request(format!("Invalid SNS neuron id {}: {}", id, e))
+                    })?,
+                    (
+                        None,
+                        Some(NeuronSubaccountComponents {
+                            public_key,
+                            neuron_index,
+                        }),
+                    ) => crate::convert::neuron_subaccount_bytes_from_public_key(
+                        &public_key,
+                        neuron_index,
+                    )?
+                    .to_vec(),
+                    (None, None) => {
+                        return Err(ApiError::invalid_request(
+                            "Invalid SNS neuron account balance request: either neuron_id or public_key must be present",
+                        ));
+                    }
+                };
+
+                Some(NeuronInfoRequest::Sns(
+                    sns_governance_canister_id,
+                    SnsNeuronId { id: neuron_id },
+                    verified,
+                ))
+            }
         };
 
-        let neuron_info = if let Some((neuron_id, verified)) = neuron_info_request_params {
-            Some(self.neuron_info(neuron_id, verified).await?)
-        } else {
-            None
+        let neuron_info: Option<ObjectMap> = match neuron_info_request_params {
+            Some(NeuronInfoRequest::Nns(neuron_id, verified)) => {
+                Some(self.neuron_info(neuron_id, verified).await?.into())
+            }
+            Some(NeuronInfoRequest::Sns(sns_governance_canister_id, neuron_id, verified)) => Some(
+                self.sns_neuron_info(sns_governance_canister_id, neuron_id, verified)
+                    .await?
+                    .into(),
+            ),
+            None => None,
         };
 
         let account_id = icp_ledger::AccountIdentifier::from_hex(&msg.account_identifier.address)
@@ -164,7 +225,7 @@ impl RosettaRequestHandler {
         Ok(AccountBalanceResponse {
             block_identifier: block.block_identifier,
             balances: vec![amount],
-            metadata: neuron_info.map(|ni| ni.into()),
+            metadata: neuron_info,
         })
     }
 
@@ -864,6 +925,60 @@ impl RosettaRequestHandler {
         })
     }
 
+    pub async fn sns_neuron_info(
+        &self,
+        sns_governance_canister_id: CanisterId,
+        neuron_id: SnsNeuronId,
+        verified: bool,
+    ) -> Result<SnsNeuronInfoResponse, ApiError> {
+        let res = self
+            .ledger
+            .sns_neuron(sns_governance_canister_id, neuron_id, verified)
+            .await?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| ApiError::internal_error(e.to_string()))?
+            .as_secs();
+        let state = match res.state(now) {
+            SnsNeuronState::NotDissolving => NeuronState::NotDissolving,
+            SnsNeuronState::Dissolving => NeuronState::Dissolving,
+            SnsNeuronState::Dissolved => NeuronState::Dissolved,
+        };
+        let neuron_id = res
+            .id
+            .as_ref()
+            .map(ToString::to_string)
+            .ok_or_else(|| ApiError::internal_error("SNS neuron has no id"))?;
+
+        Ok(SnsNeuronInfoResponse {
+            verified_query: verified,
+            retrieved_at_timestamp_seconds: now,
+            neuron_id,
+            state,
+            age_seconds: res.age_seconds(now),
+            dissolve_delay_seconds: res.dissolve_delay_seconds(now),
+            created_timestamp_seconds: res.created_timestamp_seconds,
+            stake_e8s: res.stake_e8s(),
+            maturity_e8s_equivalent: res.maturity_e8s_equivalent,
+            staked_maturity_e8s_equivalent: res.staked_maturity_e8s_equivalent,
+            followees: res
+                .followees
+                .iter()
+                .map(|(function_id, followees)| {
+                    (
+                        *function_id,
+                        followees
+                            .followees
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect(),
+                    )
+                })
+                .collect(),
+        })
+    }
+
     pub async fn rosetta_blocks_mode(&self) -> RosettaBlocksMode {
         self.ledger.rosetta_blocks_mode().await
     }
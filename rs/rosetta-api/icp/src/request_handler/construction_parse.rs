@@ -6,7 +6,13 @@ use crate::{
     request_types::{
         AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, ListNeurons, MergeMaturity,
         NeuronInfo, PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType,
-        SetDissolveTimestamp, Spawn, Stake, StakeMaturity, StartDissolve, StopDissolve,
+        SetDissolveTimestamp, SnsClaimNeuron, SnsDisburse, SnsFollow, SnsRegisterVote,
+        SnsSetDissolveTimestamp, SnsStake, Spawn, Stake, StakeMaturity, StartDissolve,
+        StopDissolve,
+    },
+    sns_governance::{
+        manage_neuron::{self as sns_manage_neuron, Command as SnsCommand},
+        ManageNeuron as SnsManageNeuron,
     },
 };
 use rosetta_core::objects::ObjectMap;
@@ -15,6 +21,7 @@ use ic_nns_governance_api::pb::v1::{
     manage_neuron::{self, Command, NeuronIdOrSubaccount},
     ClaimOrRefreshNeuronFromAccount, ManageNeuron,
 };
+use icrc_ledger_types::icrc1::{account::Account, transfer::TransferArg};
 
 use crate::{models::seconds::Seconds, request::Request};
 use ic_types::{
@@ -50,7 +57,16 @@ impl RosettaRequestHandler {
         let mut from_ai = vec![];
         let mut metadata = serde_json::Map::new();
 
-        for (request_type, HttpCanisterUpdate { arg, sender, .. }) in updates {
+        for (
+            request_type,
+            HttpCanisterUpdate {
+                canister_id,
+                arg,
+                sender,
+                ..
+            },
+        ) in updates
+        {
             let from = PrincipalId::try_from(sender.0)
                 .map_err(|e| ApiError::internal_error(e.to_string()))?
                 .into();
@@ -105,6 +121,24 @@ impl RosettaRequestHandler {
                     neuron_index,
                     controller,
                 } => follow(&mut requests, arg, from, neuron_index, controller)?,
+                RequestType::SnsStake { neuron_index } => {
+                    sns_stake(&mut requests, canister_id, arg, from, neuron_index)?
+                }
+                RequestType::SnsClaimNeuron { neuron_index } => {
+                    sns_claim_neuron(&mut requests, canister_id, arg, from, neuron_index)?
+                }
+                RequestType::SnsSetDissolveTimestamp { neuron_index } => {
+                    sns_set_dissolve_timestamp(&mut requests, canister_id, arg, from, neuron_index)?
+                }
+                RequestType::SnsDisburse { neuron_index } => {
+                    sns_disburse(&mut requests, canister_id, arg, from, neuron_index)?
+                }
+                RequestType::SnsFollow { neuron_index } => {
+                    sns_follow(&mut requests, canister_id, arg, from, neuron_index)?
+                }
+                RequestType::SnsRegisterVote { neuron_index } => {
+                    sns_register_vote(&mut requests, canister_id, arg, from, neuron_index)?
+                }
             }
         }
 
@@ -595,6 +629,185 @@ fn follow(
     Ok(())
 }
 
+/// Handle SNS_STAKE.
+fn sns_stake(
+    requests: &mut Vec<Request>,
+    canister_id: Blob,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    let TransferArg { to, amount, .. } = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!("Could not decode SNS Stake argument: {:?}", e))
+    })?;
+    let amount = u64::try_from(&amount.0).map_err(|e| {
+        ApiError::internal_error(format!("SNS Stake amount does not fit in u64: {:?}", e))
+    })?;
+    requests.push(Request::SnsStake(SnsStake {
+        account: from,
+        sns_ledger_canister_id: principal_id(canister_id)?,
+        sns_governance_canister_id: PrincipalId::from(to.owner),
+        amount,
+        neuron_index,
+    }));
+    Ok(())
+}
+
+/// Handle SNS_CLAIM_NEURON.
+fn sns_claim_neuron(
+    requests: &mut Vec<Request>,
+    canister_id: Blob,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    match sns_manage_neuron_command(arg)? {
+        SnsCommand::ClaimOrRefresh(_) => {
+            requests.push(Request::SnsClaimNeuron(SnsClaimNeuron {
+                account: from,
+                sns_governance_canister_id: principal_id(canister_id)?,
+                neuron_index,
+            }));
+            Ok(())
+        }
+        e => Err(ApiError::internal_error(format!(
+            "Incompatible SNS manage_neuron command: {:?}",
+            e
+        ))),
+    }
+}
+
+/// Handle SNS_SET_DISSOLVE_TIMESTAMP.
+fn sns_set_dissolve_timestamp(
+    requests: &mut Vec<Request>,
+    canister_id: Blob,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    match sns_manage_neuron_command(arg)? {
+        SnsCommand::Configure(sns_manage_neuron::Configure {
+            operation: Some(sns_manage_neuron::configure::Operation::SetDissolveTimestamp(d)),
+        }) => {
+            requests.push(Request::SnsSetDissolveTimestamp(SnsSetDissolveTimestamp {
+                account: from,
+                sns_governance_canister_id: principal_id(canister_id)?,
+                neuron_index,
+                timestamp: Seconds(d.dissolve_timestamp_seconds),
+            }));
+            Ok(())
+        }
+        e => Err(ApiError::internal_error(format!(
+            "Incompatible SNS manage_neuron command: {:?}",
+            e
+        ))),
+    }
+}
+
+/// Handle SNS_DISBURSE.
+fn sns_disburse(
+    requests: &mut Vec<Request>,
+    canister_id: Blob,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    match sns_manage_neuron_command(arg)? {
+        SnsCommand::Disburse(sns_manage_neuron::Disburse { amount, to_account }) => {
+            let recipient = to_account
+                .map(Account::try_from)
+                .transpose()
+                .map_err(ApiError::internal_error)?;
+            requests.push(Request::SnsDisburse(SnsDisburse {
+                account: from,
+                sns_governance_canister_id: principal_id(canister_id)?,
+                neuron_index,
+                amount: amount.map(|amount| amount.e8s),
+                recipient,
+            }));
+            Ok(())
+        }
+        e => Err(ApiError::internal_error(format!(
+            "Incompatible SNS manage_neuron command: {:?}",
+            e
+        ))),
+    }
+}
+
+/// Handle SNS_FOLLOW.
+fn sns_follow(
+    requests: &mut Vec<Request>,
+    canister_id: Blob,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    match sns_manage_neuron_command(arg)? {
+        SnsCommand::Follow(sns_manage_neuron::Follow {
+            function_id,
+            followees,
+        }) => {
+            requests.push(Request::SnsFollow(SnsFollow {
+                account: from,
+                sns_governance_canister_id: principal_id(canister_id)?,
+                neuron_index,
+                function_id,
+                followees: followees.iter().map(ToString::to_string).collect(),
+            }));
+            Ok(())
+        }
+        e => Err(ApiError::internal_error(format!(
+            "Incompatible SNS manage_neuron command: {:?}",
+            e
+        ))),
+    }
+}
+
+/// Handle SNS_REGISTER_VOTE.
+fn sns_register_vote(
+    requests: &mut Vec<Request>,
+    canister_id: Blob,
+    arg: Blob,
+    from: AccountIdentifier,
+    neuron_index: u64,
+) -> Result<(), ApiError> {
+    match sns_manage_neuron_command(arg)? {
+        SnsCommand::RegisterVote(sns_manage_neuron::RegisterVote {
+            proposal: Some(proposal),
+            vote,
+        }) => {
+            requests.push(Request::SnsRegisterVote(SnsRegisterVote {
+                account: from,
+                sns_governance_canister_id: principal_id(canister_id)?,
+                neuron_index,
+                proposal: proposal.id,
+                vote,
+            }));
+            Ok(())
+        }
+        e => Err(ApiError::internal_error(format!(
+            "Incompatible SNS manage_neuron command: {:?}",
+            e
+        ))),
+    }
+}
+
+fn sns_manage_neuron_command(arg: Blob) -> Result<SnsCommand, ApiError> {
+    let manage: SnsManageNeuron = candid::decode_one(arg.0.as_ref()).map_err(|e| {
+        ApiError::internal_error(format!(
+            "Could not decode SNS ManageNeuron argument: {:?}",
+            e
+        ))
+    })?;
+    manage
+        .command
+        .ok_or_else(|| ApiError::internal_error("Missing SNS manage_neuron command".to_string()))
+}
+
+fn principal_id(canister_id: Blob) -> Result<PrincipalId, ApiError> {
+    PrincipalId::try_from(canister_id.0).map_err(|e| ApiError::internal_error(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use ic_base_types::CanisterId;
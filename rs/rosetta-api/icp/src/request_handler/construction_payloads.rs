@@ -5,6 +5,7 @@ use ic_types::{
     PrincipalId,
 };
 use icp_ledger::{Memo, Operation, SendArgs, Tokens};
+use icrc_ledger_types::icrc1::{account::Account, transfer::TransferArg};
 use on_wire::IntoWire;
 use rand::Rng;
 use std::{collections::HashMap, sync::Arc, time::Duration};
@@ -30,7 +31,13 @@ use crate::{
     request_types::{
         AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, ListNeurons, MergeMaturity,
         NeuronInfo, PublicKeyOrPrincipal, RegisterVote, RemoveHotKey, RequestType,
-        SetDissolveTimestamp, Spawn, Stake, StakeMaturity, StartDissolve, StopDissolve,
+        SetDissolveTimestamp, SnsClaimNeuron, SnsDisburse, SnsFollow, SnsRegisterVote,
+        SnsSetDissolveTimestamp, SnsStake, Spawn, Stake, StakeMaturity, StartDissolve,
+        StopDissolve,
+    },
+    sns_governance::{
+        manage_neuron as sns_manage_neuron, ManageNeuron as SnsManageNeuron,
+        NeuronId as SnsNeuronId, ProposalId as SnsProposalId,
     },
 };
 use rosetta_core::convert::principal_id_from_public_key;
@@ -229,6 +236,49 @@ impl RosettaRequestHandler {
                     &pks_map,
                     &ingress_expiries,
                 )?,
+                Request::SnsStake(req) => handle_sns_stake(
+                    req,
+                    created_at_time,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::SnsClaimNeuron(req) => handle_sns_claim_neuron(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::SnsSetDissolveTimestamp(req) => handle_sns_set_dissolve_timestamp(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::SnsDisburse(req) => handle_sns_disburse(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::SnsFollow(req) => handle_sns_follow(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
+                Request::SnsRegisterVote(req) => handle_sns_register_vote(
+                    req,
+                    &mut payloads,
+                    &mut updates,
+                    &pks_map,
+                    &ingress_expiries,
+                )?,
             }
         }
 
@@ -946,6 +996,276 @@ fn add_neuron_management_payload(
     Ok(())
 }
 
+/// Handle SNS_STAKE.
+fn handle_sns_stake(
+    req: SnsStake,
+    created_at_time: ic_ledger_core::timestamp::TimeStamp,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let account = req.account;
+    let neuron_index = req.neuron_index;
+    let pk = pks_map.get(&account).ok_or_else(|| {
+        ApiError::internal_error(format!(
+            "Cannot find public key for account identifier {}",
+            account,
+        ))
+    })?;
+
+    // SNS neurons are staked by transferring tokens to the neuron's staking
+    // subaccount of the SNS governance canister. The subaccount is derived
+    // exactly like the NNS one.
+    let neuron_subaccount = neuron_subaccount(account, None, neuron_index, pks_map);
+    let args = TransferArg {
+        from_subaccount: None,
+        to: Account {
+            owner: req.sns_governance_canister_id.0,
+            subaccount: Some(neuron_subaccount),
+        },
+        fee: None,
+        created_at_time: Some(created_at_time.as_nanos_since_unix_epoch()),
+        memo: Some(neuron_index.into()),
+        amount: req.amount.into(),
+    };
+
+    let update = HttpCanisterUpdate {
+        canister_id: Blob(req.sns_ledger_canister_id.to_vec()),
+        method_name: "icrc1_transfer".to_string(),
+        arg: Blob(CandidOne(args).into_bytes().expect("Serialization failed")),
+        nonce: Some(Blob(
+            CandidOne(neuron_index)
+                .into_bytes()
+                .expect("Serialization of neuron_index failed"),
+        )),
+        sender: Blob(
+            principal_id_from_public_key(pk)
+                .map_err(|err| ApiError::InvalidPublicKey(false, err.into()))?
+                .into_vec(),
+        ),
+        ingress_expiry: 0,
+    };
+
+    add_payloads(
+        payloads,
+        ingress_expiries,
+        &to_model_account_identifier(&account),
+        &update,
+        SignatureType::from(pk.curve_type),
+    );
+    updates.push((RequestType::SnsStake { neuron_index }, update));
+    Ok(())
+}
+
+/// Handle SNS_CLAIM_NEURON.
+fn handle_sns_claim_neuron(
+    req: SnsClaimNeuron,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = sns_manage_neuron::Command::ClaimOrRefresh(sns_manage_neuron::ClaimOrRefresh {
+        by: Some(sns_manage_neuron::claim_or_refresh::By::MemoAndController(
+            sns_manage_neuron::claim_or_refresh::MemoAndController {
+                memo: neuron_index,
+                controller: None,
+            },
+        )),
+    });
+    add_sns_neuron_management_payload(
+        RequestType::SnsClaimNeuron { neuron_index },
+        req.account,
+        req.sns_governance_canister_id,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )
+}
+
+/// Handle SNS_SET_DISSOLVE_TIMESTAMP.
+fn handle_sns_set_dissolve_timestamp(
+    req: SnsSetDissolveTimestamp,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = sns_manage_neuron::Command::Configure(sns_manage_neuron::Configure {
+        operation: Some(
+            sns_manage_neuron::configure::Operation::SetDissolveTimestamp(
+                sns_manage_neuron::SetDissolveTimestamp {
+                    dissolve_timestamp_seconds: Duration::from(req.timestamp).as_secs(),
+                },
+            ),
+        ),
+    });
+    add_sns_neuron_management_payload(
+        RequestType::SnsSetDissolveTimestamp { neuron_index },
+        req.account,
+        req.sns_governance_canister_id,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )
+}
+
+/// Handle SNS_DISBURSE.
+fn handle_sns_disburse(
+    req: SnsDisburse,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = sns_manage_neuron::Command::Disburse(sns_manage_neuron::Disburse {
+        amount: req
+            .amount
+            .map(|e8s| sns_manage_neuron::disburse::Amount { e8s }),
+        to_account: req.recipient.map(From::from),
+    });
+    add_sns_neuron_management_payload(
+        RequestType::SnsDisburse { neuron_index },
+        req.account,
+        req.sns_governance_canister_id,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )
+}
+
+/// Handle SNS_FOLLOW.
+fn handle_sns_follow(
+    req: SnsFollow,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let followees = req
+        .followees
+        .iter()
+        .map(|id| {
+            hex::decode(id).map(|id| SnsNeuronId { id }).map_err(|e| {
+                ApiError::invalid_request(format!("Invalid SNS followee neuron id {}: {}", id, e))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let command = sns_manage_neuron::Command::Follow(sns_manage_neuron::Follow {
+        function_id: req.function_id,
+        followees,
+    });
+    add_sns_neuron_management_payload(
+        RequestType::SnsFollow { neuron_index },
+        req.account,
+        req.sns_governance_canister_id,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )
+}
+
+/// Handle SNS_REGISTER_VOTE.
+fn handle_sns_register_vote(
+    req: SnsRegisterVote,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_index = req.neuron_index;
+    let command = sns_manage_neuron::Command::RegisterVote(sns_manage_neuron::RegisterVote {
+        proposal: Some(SnsProposalId { id: req.proposal }),
+        vote: req.vote,
+    });
+    add_sns_neuron_management_payload(
+        RequestType::SnsRegisterVote { neuron_index },
+        req.account,
+        req.sns_governance_canister_id,
+        neuron_index,
+        command,
+        payloads,
+        updates,
+        pks_map,
+        ingress_expiries,
+    )
+}
+
+fn add_sns_neuron_management_payload(
+    request_type: RequestType,
+    account: icp_ledger::AccountIdentifier,
+    sns_governance_canister_id: PrincipalId,
+    neuron_index: u64,
+    command: sns_manage_neuron::Command,
+    payloads: &mut Vec<SigningPayload>,
+    updates: &mut Vec<(RequestType, HttpCanisterUpdate)>,
+    pks_map: &HashMap<icp_ledger::AccountIdentifier, &PublicKey>,
+    ingress_expiries: &[u64],
+) -> Result<(), ApiError> {
+    let neuron_subaccount = neuron_subaccount(account, None, neuron_index, pks_map);
+
+    let pk = pks_map.get(&account).ok_or_else(|| {
+        ApiError::internal_error(format!(
+            "SNS neuron management - Cannot find public key for account {}",
+            account,
+        ))
+    })?;
+
+    let manage_neuron = SnsManageNeuron {
+        subaccount: neuron_subaccount.to_vec(),
+        command: Some(command),
+    };
+
+    let update = HttpCanisterUpdate {
+        canister_id: Blob(sns_governance_canister_id.to_vec()),
+        method_name: "manage_neuron".to_string(),
+        arg: Blob(
+            CandidOne(manage_neuron)
+                .into_bytes()
+                .expect("Serialization failed"),
+        ),
+        nonce: Some(Blob(
+            CandidOne(neuron_index)
+                .into_bytes()
+                .expect("Serialization of neuron_index failed"),
+        )),
+        sender: Blob(
+            principal_id_from_public_key(pk)
+                .map_err(|err| ApiError::InvalidPublicKey(false, err.into()))?
+                .into_vec(),
+        ),
+        ingress_expiry: 0,
+    };
+
+    add_payloads(
+        payloads,
+        ingress_expiries,
+        &convert::to_model_account_identifier(&account),
+        &update,
+        SignatureType::from(pk.curve_type),
+    );
+
+    updates.push((request_type, update));
+    Ok(())
+}
+
 /// Add transaction and read state messages for a given update to the payloads vector.
 /// Payloads are added for each ingress expiries.
 fn add_payloads(
@@ -8,7 +8,8 @@ use crate::request::Request;
 use crate::request_handler::{verify_network_id, RosettaRequestHandler};
 use crate::request_types::{
     AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, ListNeurons, MergeMaturity, NeuronInfo,
-    RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Stake, StakeMaturity, StartDissolve,
+    RegisterVote, RemoveHotKey, SetDissolveTimestamp, SnsClaimNeuron, SnsDisburse, SnsFollow,
+    SnsRegisterVote, SnsSetDissolveTimestamp, SnsStake, Spawn, Stake, StakeMaturity, StartDissolve,
     StopDissolve,
 };
 use icp_ledger::Operation;
@@ -80,6 +81,12 @@ fn required_public_key(request: Request) -> Result<icp_ledger::AccountIdentifier
         | Request::StakeMaturity(StakeMaturity { account, .. })
         | Request::NeuronInfo(NeuronInfo { account, .. })
         | Request::ListNeurons(ListNeurons { account, .. })
-        | Request::Follow(Follow { account, .. }) => Ok(account),
+        | Request::Follow(Follow { account, .. })
+        | Request::SnsStake(SnsStake { account, .. })
+        | Request::SnsClaimNeuron(SnsClaimNeuron { account, .. })
+        | Request::SnsSetDissolveTimestamp(SnsSetDissolveTimestamp { account, .. })
+        | Request::SnsDisburse(SnsDisburse { account, .. })
+        | Request::SnsFollow(SnsFollow { account, .. })
+        | Request::SnsRegisterVote(SnsRegisterVote { account, .. }) => Ok(account),
     }
 }
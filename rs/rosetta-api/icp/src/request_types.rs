@@ -41,6 +41,12 @@ pub const STAKE_MATURITY: &str = "STAKE_MATURITY";
 pub const NEURON_INFO: &str = "NEURON_INFO";
 pub const LIST_NEURONS: &str = "LIST_NEURONS";
 pub const FOLLOW: &str = "FOLLOW";
+pub const SNS_STAKE: &str = "SNS_STAKE";
+pub const SNS_CLAIM_NEURON: &str = "SNS_CLAIM_NEURON";
+pub const SNS_SET_DISSOLVE_TIMESTAMP: &str = "SNS_SET_DISSOLVE_TIMESTAMP";
+pub const SNS_DISBURSE: &str = "SNS_DISBURSE";
+pub const SNS_FOLLOW: &str = "SNS_FOLLOW";
+pub const SNS_REGISTER_VOTE: &str = "SNS_REGISTER_VOTE";
 
 /// `RequestType` contains all supported values of `Operation.type`.
 /// Extra information, such as `neuron_index` should only be included
@@ -102,6 +108,18 @@ pub enum RequestType {
         neuron_index: u64,
         controller: Option<PublicKeyOrPrincipal>,
     },
+    #[serde(rename = "SNS_STAKE")]
+    SnsStake { neuron_index: u64 },
+    #[serde(rename = "SNS_CLAIM_NEURON")]
+    SnsClaimNeuron { neuron_index: u64 },
+    #[serde(rename = "SNS_SET_DISSOLVE_TIMESTAMP")]
+    SnsSetDissolveTimestamp { neuron_index: u64 },
+    #[serde(rename = "SNS_DISBURSE")]
+    SnsDisburse { neuron_index: u64 },
+    #[serde(rename = "SNS_FOLLOW")]
+    SnsFollow { neuron_index: u64 },
+    #[serde(rename = "SNS_REGISTER_VOTE")]
+    SnsRegisterVote { neuron_index: u64 },
 }
 
 impl RequestType {
@@ -123,6 +141,12 @@ impl RequestType {
             RequestType::NeuronInfo { .. } => NEURON_INFO,
             RequestType::ListNeurons { .. } => LIST_NEURONS,
             RequestType::Follow { .. } => FOLLOW,
+            RequestType::SnsStake { .. } => SNS_STAKE,
+            RequestType::SnsClaimNeuron { .. } => SNS_CLAIM_NEURON,
+            RequestType::SnsSetDissolveTimestamp { .. } => SNS_SET_DISSOLVE_TIMESTAMP,
+            RequestType::SnsDisburse { .. } => SNS_DISBURSE,
+            RequestType::SnsFollow { .. } => SNS_FOLLOW,
+            RequestType::SnsRegisterVote { .. } => SNS_REGISTER_VOTE,
         }
     }
 
@@ -148,6 +172,12 @@ impl RequestType {
                 | RequestType::NeuronInfo { .. }
                 | RequestType::ListNeurons { .. }
                 | RequestType::Follow { .. }
+                | RequestType::SnsStake { .. }
+                | RequestType::SnsClaimNeuron { .. }
+                | RequestType::SnsSetDissolveTimestamp { .. }
+                | RequestType::SnsDisburse { .. }
+                | RequestType::SnsFollow { .. }
+                | RequestType::SnsRegisterVote { .. }
         )
     }
 }
@@ -377,6 +407,75 @@ pub struct Follow {
     pub neuron_index: u64,
 }
 
+/// Transfers `amount` SNS tokens from the default ICRC-1 account of the
+/// signer to the staking subaccount of the SNS governance canister that is
+/// derived from the signer and `neuron_index`.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsStake {
+    pub account: icp_ledger::AccountIdentifier,
+    pub sns_ledger_canister_id: PrincipalId,
+    pub sns_governance_canister_id: PrincipalId,
+    /// The amount to stake, in e8s of the SNS token.
+    pub amount: u64,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+/// Claims (or refreshes) the SNS neuron whose staking subaccount was funded
+/// with `SnsStake`.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsClaimNeuron {
+    pub account: icp_ledger::AccountIdentifier,
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsSetDissolveTimestamp {
+    pub account: icp_ledger::AccountIdentifier,
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    /// The number of seconds since Unix epoch.
+    pub timestamp: Seconds,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsDisburse {
+    pub account: icp_ledger::AccountIdentifier,
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    /// The amount to disburse, in e8s of the SNS token. The whole stake is
+    /// disbursed if not set.
+    pub amount: Option<u64>,
+    /// The ICRC-1 account receiving the tokens. The signer's default account
+    /// is used if not set.
+    pub recipient: Option<icrc_ledger_types::icrc1::account::Account>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsFollow {
+    pub account: icp_ledger::AccountIdentifier,
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    pub function_id: u64,
+    /// Hex encoded ids of the SNS neurons to follow.
+    pub followees: Vec<String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsRegisterVote {
+    pub account: icp_ledger::AccountIdentifier,
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    pub proposal: u64,
+    pub vote: i32,
+}
+
 #[derive(Clone, Eq, Ord, PartialOrd, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "snake_case")]
 // Externally tagged by default.
@@ -908,6 +1007,203 @@ impl TryFrom<ApproveMetadata> for ObjectMap {
     }
 }
 
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsStakeMetadata {
+    pub sns_ledger_canister_id: PrincipalId,
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    /// The amount to stake, in e8s of the SNS token.
+    pub amount: u64,
+}
+
+impl TryFrom<Option<ObjectMap>> for SnsStakeMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<ObjectMap>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse an SNS_STAKE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl TryFrom<SnsStakeMetadata> for ObjectMap {
+    type Error = ApiError;
+    fn try_from(d: SnsStakeMetadata) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(Value::Object(o)) => Ok(o),
+            Ok(o) => Err(ApiError::internal_error(format!("Could not convert SnsStakeMetadata to ObjectMap. Expected type Object but received: {:?}",o))),
+            Err(err) => Err(ApiError::internal_error(format!("Could not convert SnsStakeMetadata to ObjectMap: {:?}",err))),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsNeuronIdentifierMetadata {
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+}
+
+impl TryFrom<Option<ObjectMap>> for SnsNeuronIdentifierMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<ObjectMap>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse an SNS neuron identifier operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl TryFrom<SnsNeuronIdentifierMetadata> for ObjectMap {
+    type Error = ApiError;
+    fn try_from(d: SnsNeuronIdentifierMetadata) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(Value::Object(o)) => Ok(o),
+            Ok(o) => Err(ApiError::internal_error(format!("Could not convert SnsNeuronIdentifierMetadata to ObjectMap. Expected type Object but received: {:?}",o))),
+            Err(err) => Err(ApiError::internal_error(format!("Could not convert SnsNeuronIdentifierMetadata to ObjectMap: {:?}",err))),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsSetDissolveTimestampMetadata {
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    #[serde(rename = "dissolve_time_utc_seconds")]
+    /// The number of seconds since Unix epoch.
+    pub timestamp: Seconds,
+}
+
+impl TryFrom<Option<ObjectMap>> for SnsSetDissolveTimestampMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<ObjectMap>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse an SNS_SET_DISSOLVE_TIMESTAMP operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl TryFrom<SnsSetDissolveTimestampMetadata> for ObjectMap {
+    type Error = ApiError;
+    fn try_from(d: SnsSetDissolveTimestampMetadata) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(Value::Object(o)) => Ok(o),
+            Ok(o) => Err(ApiError::internal_error(format!("Could not convert SnsSetDissolveTimestampMetadata to ObjectMap. Expected type Object but received: {:?}",o))),
+            Err(err) => Err(ApiError::internal_error(format!("Could not convert SnsSetDissolveTimestampMetadata to ObjectMap: {:?}",err))),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsDisburseMetadata {
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub amount: Option<u64>,
+    /// The recipient in the ICRC-1 textual account encoding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub recipient: Option<String>,
+}
+
+impl TryFrom<Option<ObjectMap>> for SnsDisburseMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<ObjectMap>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse an SNS_DISBURSE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl TryFrom<SnsDisburseMetadata> for ObjectMap {
+    type Error = ApiError;
+    fn try_from(d: SnsDisburseMetadata) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(Value::Object(o)) => Ok(o),
+            Ok(o) => Err(ApiError::internal_error(format!("Could not convert SnsDisburseMetadata to ObjectMap. Expected type Object but received: {:?}",o))),
+            Err(err) => Err(ApiError::internal_error(format!("Could not convert SnsDisburseMetadata to ObjectMap: {:?}",err))),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsFollowMetadata {
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    pub function_id: u64,
+    pub followees: Vec<String>,
+}
+
+impl TryFrom<Option<ObjectMap>> for SnsFollowMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<ObjectMap>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse an SNS_FOLLOW operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl TryFrom<SnsFollowMetadata> for ObjectMap {
+    type Error = ApiError;
+    fn try_from(d: SnsFollowMetadata) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(Value::Object(o)) => Ok(o),
+            Ok(o) => Err(ApiError::internal_error(format!("Could not convert SnsFollowMetadata to ObjectMap. Expected type Object but received: {:?}",o))),
+            Err(err) => Err(ApiError::internal_error(format!("Could not convert SnsFollowMetadata to ObjectMap: {:?}",err))),
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Serialize)]
+pub struct SnsRegisterVoteMetadata {
+    pub sns_governance_canister_id: PrincipalId,
+    #[serde(default)]
+    pub neuron_index: u64,
+    pub proposal: u64,
+    pub vote: i32,
+}
+
+impl TryFrom<Option<ObjectMap>> for SnsRegisterVoteMetadata {
+    type Error = ApiError;
+    fn try_from(o: Option<ObjectMap>) -> Result<Self, Self::Error> {
+        serde_json::from_value(serde_json::Value::Object(o.unwrap_or_default())).map_err(|e| {
+            ApiError::internal_error(format!(
+                "Could not parse an SNS_REGISTER_VOTE operation metadata from metadata JSON object: {}",
+                e
+            ))
+        })
+    }
+}
+
+impl TryFrom<SnsRegisterVoteMetadata> for ObjectMap {
+    type Error = ApiError;
+    fn try_from(d: SnsRegisterVoteMetadata) -> Result<ObjectMap, Self::Error> {
+        match serde_json::to_value(d) {
+            Ok(Value::Object(o)) => Ok(o),
+            Ok(o) => Err(ApiError::internal_error(format!("Could not convert SnsRegisterVoteMetadata to ObjectMap. Expected type Object but received: {:?}",o))),
+            Err(err) => Err(ApiError::internal_error(format!("Could not convert SnsRegisterVoteMetadata to ObjectMap: {:?}",err))),
+        }
+    }
+}
+
 /// Transaction is a bit of a misnomer, since operations can succeed or fail
 /// independently from a Transaction.
 #[derive(Default)]
@@ -1424,6 +1720,183 @@ impl TransactionBuilder {
         });
         Ok(())
     }
+
+    pub fn sns_stake(&mut self, req: &SnsStake) -> Result<(), ApiError> {
+        let SnsStake {
+            account,
+            sns_ledger_canister_id,
+            sns_governance_canister_id,
+            amount,
+            neuron_index,
+        } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            type_: OperationType::SnsStake.to_string(),
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                SnsStakeMetadata {
+                    sns_ledger_canister_id: *sns_ledger_canister_id,
+                    sns_governance_canister_id: *sns_governance_canister_id,
+                    neuron_index: *neuron_index,
+                    amount: *amount,
+                }
+                .try_into()?,
+            ),
+        });
+        Ok(())
+    }
+
+    pub fn sns_claim_neuron(&mut self, req: &SnsClaimNeuron) -> Result<(), ApiError> {
+        let SnsClaimNeuron {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+        } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            type_: OperationType::SnsClaimNeuron.to_string(),
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                SnsNeuronIdentifierMetadata {
+                    sns_governance_canister_id: *sns_governance_canister_id,
+                    neuron_index: *neuron_index,
+                }
+                .try_into()?,
+            ),
+        });
+        Ok(())
+    }
+
+    pub fn sns_set_dissolve_timestamp(
+        &mut self,
+        req: &SnsSetDissolveTimestamp,
+    ) -> Result<(), ApiError> {
+        let SnsSetDissolveTimestamp {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+            timestamp,
+        } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            type_: OperationType::SnsSetDissolveTimestamp.to_string(),
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                SnsSetDissolveTimestampMetadata {
+                    sns_governance_canister_id: *sns_governance_canister_id,
+                    neuron_index: *neuron_index,
+                    timestamp: *timestamp,
+                }
+                .try_into()?,
+            ),
+        });
+        Ok(())
+    }
+
+    pub fn sns_disburse(&mut self, req: &SnsDisburse) -> Result<(), ApiError> {
+        let SnsDisburse {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+            amount,
+            recipient,
+        } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            type_: OperationType::SnsDisburse.to_string(),
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                SnsDisburseMetadata {
+                    sns_governance_canister_id: *sns_governance_canister_id,
+                    neuron_index: *neuron_index,
+                    amount: *amount,
+                    recipient: recipient.as_ref().map(ToString::to_string),
+                }
+                .try_into()?,
+            ),
+        });
+        Ok(())
+    }
+
+    pub fn sns_follow(&mut self, req: &SnsFollow) -> Result<(), ApiError> {
+        let SnsFollow {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+            function_id,
+            followees,
+        } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            type_: OperationType::SnsFollow.to_string(),
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                SnsFollowMetadata {
+                    sns_governance_canister_id: *sns_governance_canister_id,
+                    neuron_index: *neuron_index,
+                    function_id: *function_id,
+                    followees: followees.clone(),
+                }
+                .try_into()?,
+            ),
+        });
+        Ok(())
+    }
+
+    pub fn sns_register_vote(&mut self, req: &SnsRegisterVote) -> Result<(), ApiError> {
+        let SnsRegisterVote {
+            account,
+            sns_governance_canister_id,
+            neuron_index,
+            proposal,
+            vote,
+        } = req;
+        let operation_identifier = self.allocate_op_id();
+        self.ops.push(Operation {
+            operation_identifier,
+            type_: OperationType::SnsRegisterVote.to_string(),
+            status: None,
+            account: Some(to_model_account_identifier(account)),
+            amount: None,
+            related_operations: None,
+            coin_change: None,
+            metadata: Some(
+                SnsRegisterVoteMetadata {
+                    sns_governance_canister_id: *sns_governance_canister_id,
+                    neuron_index: *neuron_index,
+                    proposal: *proposal,
+                    vote: *vote,
+                }
+                .try_into()?,
+            ),
+        });
+        Ok(())
+    }
 }
 
 /// Converts an optional PrincipalId to an optional PublicKeyOrPrincipal.
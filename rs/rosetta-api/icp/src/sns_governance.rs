@@ -0,0 +1,429 @@
+//! Candid types of the SNS governance canister interface used by Rosetta.
+//!
+//! These mirror the subset of the `sns_governance.did` types that Rosetta sends and receives,
+//! so that Rosetta does not have to link the whole SNS governance implementation. Records may
+//! omit fields that Rosetta does not read, and the variants of the requests may omit commands
+//! that Rosetta does not send, as both are compatible with the canister interface.
+use candid::{CandidType, Deserialize};
+use ic_types::PrincipalId;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NeuronId {
+    #[serde(with = "serde_bytes")]
+    pub id: Vec<u8>,
+}
+
+impl fmt::Display for NeuronId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.id))
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProposalId {
+    pub id: u64,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Subaccount {
+    #[serde(with = "serde_bytes")]
+    pub subaccount: Vec<u8>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Account {
+    pub owner: Option<PrincipalId>,
+    pub subaccount: Option<Subaccount>,
+}
+
+impl TryFrom<Account> for icrc_ledger_types::icrc1::account::Account {
+    type Error = String;
+
+    fn try_from(account: Account) -> Result<Self, String> {
+        let owner = account
+            .owner
+            .ok_or_else(|| "The value in field owner is missing".to_string())?;
+        let subaccount = account
+            .subaccount
+            .map(|s| {
+                s.subaccount.as_slice().try_into().map_err(|_| {
+                    format!(
+                        "Invalid Subaccount length. Expected 32, found {}",
+                        s.subaccount.len()
+                    )
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            owner: owner.0,
+            subaccount,
+        })
+    }
+}
+
+impl From<icrc_ledger_types::icrc1::account::Account> for Account {
+    fn from(account: icrc_ledger_types::icrc1::account::Account) -> Self {
+        Account {
+            owner: Some(account.owner.into()),
+            subaccount: account.subaccount.map(|subaccount| Subaccount {
+                subaccount: subaccount.to_vec(),
+            }),
+        }
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GovernanceError {
+    pub error_type: i32,
+    pub error_message: String,
+}
+
+impl fmt::Display for GovernanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "error type {}: {}", self.error_type, self.error_message)
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Followees {
+    pub followees: Vec<NeuronId>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DissolveState {
+    WhenDissolvedTimestampSeconds(u64),
+    DissolveDelaySeconds(u64),
+}
+
+/// The state of a neuron, derived from its dissolve state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NeuronState {
+    NotDissolving,
+    Dissolving,
+    Dissolved,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Neuron {
+    pub id: Option<NeuronId>,
+    pub cached_neuron_stake_e8s: u64,
+    pub neuron_fees_e8s: u64,
+    pub created_timestamp_seconds: u64,
+    pub aging_since_timestamp_seconds: u64,
+    pub followees: BTreeMap<u64, Followees>,
+    pub maturity_e8s_equivalent: u64,
+    pub staked_maturity_e8s_equivalent: Option<u64>,
+    pub dissolve_state: Option<DissolveState>,
+}
+
+impl Neuron {
+    /// Returns the state of the neuron at `now_seconds`, as SNS governance computes it.
+    pub fn state(&self, now_seconds: u64) -> NeuronState {
+        match self.dissolve_state {
+            Some(DissolveState::DissolveDelaySeconds(d)) if d > 0 => NeuronState::NotDissolving,
+            Some(DissolveState::WhenDissolvedTimestampSeconds(ts)) if ts > now_seconds => {
+                NeuronState::Dissolving
+            }
+            _ => NeuronState::Dissolved,
+        }
+    }
+
+    pub fn age_seconds(&self, now_seconds: u64) -> u64 {
+        now_seconds.saturating_sub(self.aging_since_timestamp_seconds)
+    }
+
+    pub fn dissolve_delay_seconds(&self, now_seconds: u64) -> u64 {
+        match self.dissolve_state {
+            Some(DissolveState::DissolveDelaySeconds(d)) => d,
+            Some(DissolveState::WhenDissolvedTimestampSeconds(ts)) => {
+                ts.saturating_sub(now_seconds)
+            }
+            None => 0,
+        }
+    }
+
+    pub fn stake_e8s(&self) -> u64 {
+        self.cached_neuron_stake_e8s
+            .saturating_sub(self.neuron_fees_e8s)
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetNeuron {
+    pub neuron_id: Option<NeuronId>,
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GetNeuronResponse {
+    pub result: Option<get_neuron_response::Result>,
+}
+
+pub mod get_neuron_response {
+    use candid::{CandidType, Deserialize};
+
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+    pub enum Result {
+        Error(super::GovernanceError),
+        Neuron(super::Neuron),
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManageNeuron {
+    #[serde(with = "serde_bytes")]
+    pub subaccount: Vec<u8>,
+    pub command: Option<manage_neuron::Command>,
+}
+
+pub mod manage_neuron {
+    use super::{Account, NeuronId, ProposalId};
+    use candid::{CandidType, Deserialize};
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct IncreaseDissolveDelay {
+        pub additional_dissolve_delay_seconds: u32,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct StartDissolving {}
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct StopDissolving {}
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct SetDissolveTimestamp {
+        pub dissolve_timestamp_seconds: u64,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct ChangeAutoStakeMaturity {
+        pub requested_setting_for_auto_stake_maturity: bool,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Configure {
+        pub operation: Option<configure::Operation>,
+    }
+
+    pub mod configure {
+        use candid::{CandidType, Deserialize};
+
+        #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum Operation {
+            IncreaseDissolveDelay(super::IncreaseDissolveDelay),
+            StartDissolving(super::StartDissolving),
+            StopDissolving(super::StopDissolving),
+            SetDissolveTimestamp(super::SetDissolveTimestamp),
+            ChangeAutoStakeMaturity(super::ChangeAutoStakeMaturity),
+        }
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct Disburse {
+        pub amount: Option<disburse::Amount>,
+        pub to_account: Option<Account>,
+    }
+
+    pub mod disburse {
+        use candid::{CandidType, Deserialize};
+
+        #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct Amount {
+            pub e8s: u64,
+        }
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct Follow {
+        pub function_id: u64,
+        pub followees: Vec<NeuronId>,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct RegisterVote {
+        pub proposal: Option<ProposalId>,
+        pub vote: i32,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct ClaimOrRefresh {
+        pub by: Option<claim_or_refresh::By>,
+    }
+
+    pub mod claim_or_refresh {
+        use candid::{CandidType, Deserialize};
+        use ic_types::PrincipalId;
+
+        #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+        pub struct MemoAndController {
+            pub memo: u64,
+            pub controller: Option<PrincipalId>,
+        }
+
+        #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+        pub struct Empty {}
+
+        #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+        pub enum By {
+            MemoAndController(MemoAndController),
+            NeuronId(Empty),
+        }
+    }
+
+    /// The commands that Rosetta sends to SNS governance.
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+    pub enum Command {
+        Configure(Configure),
+        Disburse(Disburse),
+        Follow(Follow),
+        RegisterVote(RegisterVote),
+        ClaimOrRefresh(ClaimOrRefresh),
+    }
+}
+
+#[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ManageNeuronResponse {
+    pub command: Option<manage_neuron_response::Command>,
+}
+
+pub mod manage_neuron_response {
+    use super::{GovernanceError, NeuronId, ProposalId};
+    use candid::{CandidType, Deserialize};
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct ConfigureResponse {}
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct DisburseResponse {
+        pub transfer_block_height: u64,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct MergeMaturityResponse {
+        pub merged_maturity_e8s: u64,
+        pub new_stake_e8s: u64,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct DisburseMaturityResponse {
+        pub amount_disbursed_e8s: u64,
+        pub amount_deducted_e8s: Option<u64>,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct StakeMaturityResponse {
+        pub maturity_e8s: u64,
+        pub staked_maturity_e8s: u64,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct FollowResponse {}
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct MakeProposalResponse {
+        pub proposal_id: Option<ProposalId>,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct RegisterVoteResponse {}
+
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct SplitResponse {
+        pub created_neuron_id: Option<NeuronId>,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+    pub struct ClaimOrRefreshResponse {
+        pub refreshed_neuron_id: Option<NeuronId>,
+    }
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct AddNeuronPermissionsResponse {}
+
+    #[derive(CandidType, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct RemoveNeuronPermissionsResponse {}
+
+    /// All the responses of SNS governance to `manage_neuron`, so that any response decodes.
+    #[derive(CandidType, Deserialize, Clone, Debug, PartialEq, Eq)]
+    pub enum Command {
+        Error(GovernanceError),
+        Configure(ConfigureResponse),
+        Disburse(DisburseResponse),
+        Follow(FollowResponse),
+        MakeProposal(MakeProposalResponse),
+        RegisterVote(RegisterVoteResponse),
+        Split(SplitResponse),
+        ClaimOrRefresh(ClaimOrRefreshResponse),
+        MergeMaturity(MergeMaturityResponse),
+        DisburseMaturity(DisburseMaturityResponse),
+        AddNeuronPermission(AddNeuronPermissionsResponse),
+        RemoveNeuronPermission(RemoveNeuronPermissionsResponse),
+        StakeMaturity(StakeMaturityResponse),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn neuron(dissolve_state: Option<DissolveState>) -> Neuron {
+        Neuron {
+            id: Some(NeuronId { id: vec![1, 2, 3] }),
+            cached_neuron_stake_e8s: 100,
+            neuron_fees_e8s: 10,
+            created_timestamp_seconds: 0,
+            aging_since_timestamp_seconds: 50,
+            followees: BTreeMap::new(),
+            maturity_e8s_equivalent: 0,
+            staked_maturity_e8s_equivalent: None,
+            dissolve_state,
+        }
+    }
+
+    #[test]
+    fn test_neuron_state() {
+        let now = 1_000;
+
+        let n = neuron(Some(DissolveState::DissolveDelaySeconds(500)));
+        assert_eq!(n.state(now), NeuronState::NotDissolving);
+        assert_eq!(n.dissolve_delay_seconds(now), 500);
+
+        let n = neuron(Some(DissolveState::WhenDissolvedTimestampSeconds(1_200)));
+        assert_eq!(n.state(now), NeuronState::Dissolving);
+        assert_eq!(n.dissolve_delay_seconds(now), 200);
+
+        let n = neuron(Some(DissolveState::WhenDissolvedTimestampSeconds(800)));
+        assert_eq!(n.state(now), NeuronState::Dissolved);
+        assert_eq!(n.dissolve_delay_seconds(now), 0);
+
+        let n = neuron(Some(DissolveState::DissolveDelaySeconds(0)));
+        assert_eq!(n.state(now), NeuronState::Dissolved);
+        assert_eq!(neuron(None).state(now), NeuronState::Dissolved);
+
+        assert_eq!(n.age_seconds(now), 950);
+        assert_eq!(n.stake_e8s(), 90);
+        assert_eq!(n.id.unwrap().to_string(), "010203");
+    }
+
+    #[test]
+    fn test_account_conversion() {
+        let account = icrc_ledger_types::icrc1::account::Account {
+            owner: PrincipalId::new_user_test_id(1).0,
+            subaccount: Some([7; 32]),
+        };
+        assert_eq!(
+            icrc_ledger_types::icrc1::account::Account::try_from(Account::from(account)),
+            Ok(account)
+        );
+
+        let short_subaccount = Account {
+            owner: Some(PrincipalId::new_user_test_id(1)),
+            subaccount: Some(Subaccount {
+                subaccount: vec![7; 31],
+            }),
+        };
+        assert!(icrc_ledger_types::icrc1::account::Account::try_from(short_subaccount).is_err());
+    }
+}
@@ -66,7 +66,13 @@ impl TransactionIdentifier {
             | RequestType::StakeMaturity { .. }
             | RequestType::NeuronInfo { .. }
             | RequestType::ListNeurons { .. }
-            | RequestType::Follow { .. } => {
+            | RequestType::Follow { .. }
+            | RequestType::SnsStake { .. }
+            | RequestType::SnsClaimNeuron { .. }
+            | RequestType::SnsSetDissolveTimestamp { .. }
+            | RequestType::SnsDisburse { .. }
+            | RequestType::SnsFollow { .. }
+            | RequestType::SnsRegisterVote { .. } => {
                 // Unfortunately, staking operations don't really have a transaction ID
                 Ok(TransactionIdentifier::from(
                     NEURON_MANAGEMENT_PSEUDO_HASH.to_string(),
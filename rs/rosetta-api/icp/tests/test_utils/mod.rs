@@ -235,6 +235,15 @@ impl LedgerAccess for TestLedger {
         panic!("Neuron info not available through TestLedger");
     }
 
+    async fn sns_neuron(
+        &self,
+        _sns_governance_canister_id: CanisterId,
+        _neuron_id: ic_rosetta_api::sns_governance::NeuronId,
+        _: bool,
+    ) -> Result<ic_rosetta_api::sns_governance::Neuron, ApiError> {
+        panic!("SNS neuron not available through TestLedger");
+    }
+
     async fn transfer_fee(&self) -> Result<TransferFee, ApiError> {
         Ok(TransferFee {
             transfer_fee: self.transfer_fee,
@@ -45,6 +45,10 @@ pub struct RosettaOptions {
     pub symbol: Option<String>,
 
     pub decimals: Option<u32>,
+
+    /// The ledgers to serve in the format expected by `--multi-tokens`.
+    /// If set then [ledger_id], [symbol] and [decimals] are ignored.
+    pub multi_tokens: Option<String>,
 }
 
 impl Default for RosettaOptions {
@@ -58,6 +62,7 @@ impl Default for RosettaOptions {
             offline: true,
             symbol: Some(DEFAULT_TOKEN_SYMBOL.to_string()),
             decimals: Some(DEFAULT_DECIMAL_PLACES.into()),
+            multi_tokens: None,
         }
     }
 }
@@ -74,8 +79,6 @@ pub async fn start_rosetta(rosetta_bin: &Path, arguments: RosettaOptions) -> Ros
 
     let mut command = &mut Command::new(rosetta_bin);
     command = command
-        .arg("--ledger-id")
-        .arg(arguments.ledger_id.to_string())
         .arg("--network-type")
         .arg(arguments.network_type)
         .arg("--store-type")
@@ -94,12 +97,20 @@ pub async fn start_rosetta(rosetta_bin: &Path, arguments: RosettaOptions) -> Ros
         command = command.arg("--offline");
     }
 
-    if let Some(symbol) = arguments.symbol {
-        command = command.arg("--icrc1-symbol").arg(symbol);
-    }
+    if let Some(multi_tokens) = arguments.multi_tokens {
+        command = command.arg("--multi-tokens").arg(multi_tokens);
+    } else {
+        command = command
+            .arg("--ledger-id")
+            .arg(arguments.ledger_id.to_string());
 
-    if let Some(decimals) = arguments.decimals {
-        command = command.arg("--icrc1-decimals").arg(decimals.to_string());
+        if let Some(symbol) = arguments.symbol {
+            command = command.arg("--icrc1-symbol").arg(symbol);
+        }
+
+        if let Some(decimals) = arguments.decimals {
+            command = command.arg("--icrc1-decimals").arg(decimals.to_string());
+        }
     }
 
     if arguments.exit_on_sync {
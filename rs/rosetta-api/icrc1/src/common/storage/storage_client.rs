@@ -36,6 +36,9 @@ impl StorageClient {
             .unwrap()
             .execute("PRAGMA foreign_keys = 1", [])?;
         storage_client.create_tables()?;
+        storage_operations::migrate_storage(
+            &mut storage_client.storage_connection.lock().unwrap(),
+        )?;
         Ok(storage_client)
     }
 
@@ -469,5 +472,33 @@ mod tests {
                assert!(storage_client_memory.update_account_balances().is_err())
                }
            }
+
+           #[test]
+           fn test_backfilling_spender_columns(blockchain in prop::collection::vec(blocks_strategy::<U256>(arb_amount()),0..20)){
+               let tmpdir = create_tmp_dir();
+               let file_path = tmpdir.path().join("db.sqlite");
+               let rosetta_blocks = blockchain.into_iter().enumerate().map(|(index,block)| RosettaBlock::from_encoded_block(&block.encode(),index as u64).unwrap()).collect::<Vec<_>>();
+               let read_spenders = |storage_client: &StorageClient| {
+                   let open_connection = storage_client.storage_connection.lock().unwrap();
+                   let mut stmt = open_connection.prepare("SELECT spender_principal, spender_subaccount FROM blocks ORDER BY idx").unwrap();
+                   stmt.query_map([], |row| Ok((row.get::<_, Option<Vec<u8>>>(0)?, row.get::<_, Option<Vec<u8>>>(1)?))).unwrap().map(|row| row.unwrap()).collect::<Vec<_>>()
+               };
+
+               let storage_client = StorageClient::new_persistent(&file_path).unwrap();
+               storage_client.store_blocks(rosetta_blocks).unwrap();
+               let spenders = read_spenders(&storage_client);
+
+               // Simulate a store written before the spenders of transfers and burns were stored
+               {
+                   let open_connection = storage_client.storage_connection.lock().unwrap();
+                   open_connection.execute("UPDATE blocks SET spender_principal = NULL, spender_subaccount = NULL WHERE operation_type IN ('transfer', 'burn')", []).unwrap();
+                   open_connection.pragma_update(None, "user_version", 0).unwrap();
+               }
+               drop(storage_client);
+
+               // The spenders are restored from the stored blocks when the store is opened again
+               let storage_client = StorageClient::new_persistent(&file_path).unwrap();
+               assert_eq!(read_spenders(&storage_client), spenders);
+           }
        }
 }
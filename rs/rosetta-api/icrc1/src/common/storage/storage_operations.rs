@@ -13,6 +13,9 @@ use serde_bytes::ByteBuf;
 use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 
+// The current version of the layout of the data in the store, see `migrate_storage`
+const STORAGE_SCHEMA_VERSION: u32 = 1;
+
 pub fn store_metadata(
     connection: &mut Connection,
     metadata: Vec<MetadataEntry>,
@@ -280,30 +283,34 @@ pub fn store_blocks(
             crate::common::storage::types::IcrcOperation::Transfer {
                 from,
                 to,
+                spender,
                 amount,
                 fee,
-                ..
             } => (
                 "transfer",
                 Some(from.owner),
                 Some(*from.effective_subaccount()),
                 Some(to.owner),
                 Some(*to.effective_subaccount()),
-                None,
-                None,
+                spender.map(|spender| spender.owner),
+                spender.map(|spender| *spender.effective_subaccount()),
                 amount,
                 None,
                 fee,
                 None,
             ),
-            crate::common::storage::types::IcrcOperation::Burn { from, amount, .. } => (
+            crate::common::storage::types::IcrcOperation::Burn {
+                from,
+                spender,
+                amount,
+            } => (
                 "burn",
                 Some(from.owner),
                 Some(*from.effective_subaccount()),
                 None,
                 None,
-                None,
-                None,
+                spender.map(|spender| spender.owner),
+                spender.map(|spender| *spender.effective_subaccount()),
                 amount,
                 None,
                 None,
@@ -358,6 +365,59 @@ pub fn store_blocks(
     Ok(())
 }
 
+// Brings a store written by an older version of Rosetta up to the current schema version.
+// The version is kept in the SQLite `user_version` pragma, which is 0 for stores that predate it.
+pub fn migrate_storage(connection: &mut Connection) -> anyhow::Result<()> {
+    let version: u32 = connection.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= STORAGE_SCHEMA_VERSION {
+        return Ok(());
+    }
+
+    // Version 1: The spender of ICRC-2 transfers and burns is stored in the spender columns
+    if version < 1 {
+        backfill_spender_columns(connection)?;
+    }
+
+    connection.pragma_update(None, "user_version", STORAGE_SCHEMA_VERSION)?;
+    Ok(())
+}
+
+// Fills in the spender columns of the transfers and burns that were stored without them
+fn backfill_spender_columns(connection: &mut Connection) -> anyhow::Result<()> {
+    const BATCH_SIZE: u64 = 100000;
+    let update_tx = connection.transaction()?;
+    let mut last_idx: Option<u64> = None;
+    loop {
+        let rosetta_blocks = {
+            let mut stmt = update_tx.prepare_cached("SELECT idx,serialized_block FROM blocks WHERE (?1 IS NULL OR idx > ?1) AND operation_type IN ('transfer', 'burn') AND spender_principal IS NULL ORDER BY idx LIMIT ?2")?;
+            read_blocks(&mut stmt, params![last_idx, BATCH_SIZE])?
+        };
+        let Some(last_block) = rosetta_blocks.last() else {
+            break;
+        };
+        last_idx = Some(last_block.index);
+
+        for rosetta_block in rosetta_blocks.iter() {
+            let spender = match rosetta_block.get_transaction().operation {
+                crate::common::storage::types::IcrcOperation::Transfer { spender, .. }
+                | crate::common::storage::types::IcrcOperation::Burn { spender, .. } => spender,
+                _ => None,
+            };
+            if let Some(spender) = spender {
+                update_tx
+                    .prepare_cached("UPDATE blocks SET spender_principal = :spender_principal, spender_subaccount = :spender_subaccount WHERE idx = :idx")?
+                    .execute(named_params! {
+                        ":idx": rosetta_block.index,
+                        ":spender_principal": spender.owner.as_slice(),
+                        ":spender_subaccount": spender.effective_subaccount().as_slice(),
+                    })?;
+            }
+        }
+    }
+    update_tx.commit()?;
+    Ok(())
+}
+
 // Returns a RosettaBlock if the block index exists in the database, else returns None.
 // Returns an Error if the query fails.
 pub fn get_block_at_idx(
@@ -1,7 +1,7 @@
 use super::{services, types::ConstructionPayloadsRequestMetadata};
 use crate::{
-    common::{types::Error, utils::utils::verify_network_id},
-    AppState,
+    common::{types::Error, utils::utils::get_state_from_network_id},
+    MultiTokenAppState,
 };
 use axum::{extract::State, response::Result, Json};
 use rosetta_core::{request_types::*, response_types::*};
@@ -9,10 +9,10 @@ use std::sync::Arc;
 use std::time::SystemTime;
 
 pub async fn construction_derive(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<MultiTokenAppState>>,
     Json(request): Json<ConstructionDeriveRequest>,
 ) -> Result<Json<ConstructionDeriveResponse>> {
-    verify_network_id(&request.network_identifier, &state)
+    let state = get_state_from_network_id(&request.network_identifier, &state)
         .map_err(|err| Error::invalid_network_id(&err))?;
     Ok(Json(services::construction_derive(
         request.public_key.clone(),
@@ -20,19 +20,19 @@ pub async fn construction_derive(
 }
 
 pub async fn construction_preprocess(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<MultiTokenAppState>>,
     Json(request): Json<ConstructionPreprocessRequest>,
 ) -> Result<Json<ConstructionPreprocessResponse>> {
-    verify_network_id(&request.network_identifier, &state)
+    let state = get_state_from_network_id(&request.network_identifier, &state)
         .map_err(|err| Error::invalid_network_id(&err))?;
     Ok(Json(services::construction_preprocess(request.operations)?))
 }
 
 pub async fn construction_metadata(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<MultiTokenAppState>>,
     Json(request): Json<ConstructionMetadataRequest>,
 ) -> Result<Json<ConstructionMetadataResponse>> {
-    verify_network_id(&request.network_identifier, &state)
+    let state = get_state_from_network_id(&request.network_identifier, &state)
         .map_err(|err| Error::invalid_network_id(&err))?;
     Ok(Json(
         services::construction_metadata(
@@ -49,10 +49,10 @@ pub async fn construction_metadata(
 }
 
 pub async fn construction_submit(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<MultiTokenAppState>>,
     Json(request): Json<ConstructionSubmitRequest>,
 ) -> Result<Json<ConstructionSubmitResponse>> {
-    verify_network_id(&request.network_identifier, &state)
+    let state = get_state_from_network_id(&request.network_identifier, &state)
         .map_err(|err| Error::invalid_network_id(&err))?;
     Ok(Json(
         services::construction_submit(
@@ -65,10 +65,10 @@ pub async fn construction_submit(
 }
 
 pub async fn construction_hash(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<MultiTokenAppState>>,
     Json(request): Json<ConstructionHashRequest>,
 ) -> Result<Json<ConstructionHashResponse>> {
-    verify_network_id(&request.network_identifier, &state)
+    let state = get_state_from_network_id(&request.network_identifier, &state)
         .map_err(|err| Error::invalid_network_id(&err))?;
     Ok(Json(services::construction_hash(
         request.signed_transaction,
@@ -76,10 +76,10 @@ pub async fn construction_hash(
 }
 
 pub async fn construction_combine(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<MultiTokenAppState>>,
     Json(request): Json<ConstructionCombineRequest>,
 ) -> Result<Json<ConstructionCombineResponse>> {
-    verify_network_id(&request.network_identifier, &state)
+    let state = get_state_from_network_id(&request.network_identifier, &state)
         .map_err(|err| Error::invalid_network_id(&err))?;
     Ok(Json(services::construction_combine(
         request.unsigned_transaction,
@@ -88,10 +88,10 @@ pub async fn construction_combine(
 }
 
 pub async fn construction_payloads(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<MultiTokenAppState>>,
     Json(request): Json<ConstructionPayloadsRequest>,
 ) -> Result<Json<ConstructionPayloadsResponse>> {
-    verify_network_id(&request.network_identifier, &state)
+    let state = get_state_from_network_id(&request.network_identifier, &state)
         .map_err(|err| Error::invalid_network_id(&err))?;
     Ok(Json(services::construction_payloads(
         request.operations,
@@ -107,10 +107,10 @@ pub async fn construction_payloads(
 }
 
 pub async fn construction_parse(
-    State(state): State<Arc<AppState>>,
+    State(state): State<Arc<MultiTokenAppState>>,
     Json(request): Json<ConstructionParseRequest>,
 ) -> Result<Json<ConstructionParseResponse>> {
-    verify_network_id(&request.network_identifier, &state)
+    let state = get_state_from_network_id(&request.network_identifier, &state)
         .map_err(|err| Error::invalid_network_id(&err))?;
     Ok(Json(services::construction_parse(
         request.transaction,
@@ -537,6 +537,7 @@ mod test {
     use super::*;
     use candid::Nat;
     use ic_agent::Identity;
+    use ic_base_types::PrincipalId;
     use ic_icrc1_test_utils::minter_identity;
     use ic_icrc1_test_utils::valid_transactions_strategy;
     use ic_icrc1_test_utils::LedgerEndpointArg;
@@ -656,4 +657,60 @@ mod test {
             )
             .unwrap();
     }
+
+    #[test]
+    fn test_transfer_from_arg_conversion() {
+        let spender = Account {
+            owner: PrincipalId::new_user_test_id(1).0,
+            subaccount: Some([1; 32]),
+        };
+        let operation = crate::common::storage::types::IcrcOperation::Transfer {
+            from: Account {
+                owner: PrincipalId::new_user_test_id(2).0,
+                subaccount: None,
+            },
+            to: Account {
+                owner: PrincipalId::new_user_test_id(3).0,
+                subaccount: Some([3; 32]),
+            },
+            spender: Some(spender),
+            amount: Nat::from(1_000_000u64),
+            fee: Some(Nat::from(DEFAULT_TRANSFER_FEE)),
+        };
+        let memo = Some(Memo::from(vec![1, 2, 3]));
+        let created_at_time = 1_000_000_000;
+
+        // The rosetta core operations of a transfer from are built by icrc2_transfer_from
+        // which has to be called by the spender
+        let rosetta_core_operations = icrc1_operation_to_rosetta_core_operations(
+            operation.clone(),
+            Currency::new("XTST".to_owned(), 8),
+            Some(Nat::from(DEFAULT_TRANSFER_FEE)),
+        )
+        .unwrap();
+        assert_eq!(
+            CanisterMethodName::new_from_rosetta_core_operations(&rosetta_core_operations).unwrap(),
+            CanisterMethodName::Icrc2TransferFrom
+        );
+        assert_eq!(
+            extract_caller_principal_from_rosetta_core_operation(rosetta_core_operations).unwrap(),
+            spender.owner
+        );
+
+        let candid_bytes = build_icrc1_ledger_canister_method_args(
+            operation.clone(),
+            memo.clone(),
+            created_at_time,
+        )
+        .unwrap();
+        let icrc1_transaction = build_icrc1_transaction_from_canister_method_args(
+            &CanisterMethodName::Icrc2TransferFrom,
+            &spender.owner,
+            candid_bytes,
+        )
+        .unwrap();
+        assert_eq!(icrc1_transaction.operation, operation);
+        assert_eq!(icrc1_transaction.memo, memo);
+        assert_eq!(icrc1_transaction.created_at_time, Some(created_at_time));
+    }
 }
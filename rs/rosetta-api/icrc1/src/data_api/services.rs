@@ -29,12 +29,14 @@ use rosetta_core::response_types::SearchTransactionsResponse;
 use rosetta_core::{identifiers::*, miscellaneous::Version, objects::*, response_types::*};
 use strum::IntoEnumIterator;
 
-pub fn network_list(ledger_id: &Principal) -> NetworkListResponse {
+pub fn network_list(ledger_ids: impl IntoIterator<Item = Principal>) -> NetworkListResponse {
     NetworkListResponse {
-        network_identifiers: vec![NetworkIdentifier::new(
-            DEFAULT_BLOCKCHAIN.to_owned(),
-            ledger_id.to_string(),
-        )],
+        network_identifiers: ledger_ids
+            .into_iter()
+            .map(|ledger_id| {
+                NetworkIdentifier::new(DEFAULT_BLOCKCHAIN.to_owned(), ledger_id.to_string())
+            })
+            .collect(),
     }
 }
 
@@ -184,15 +186,33 @@ pub fn account_balance(
     decimals: u8,
     symbol: String,
 ) -> Result<AccountBalanceResponse, Error> {
+    // Balances are read from the balance history, which may lag behind the stored blocks
+    // while a synchronization is in progress.
+    let highest_processed_block_idx = storage_client
+        .get_highest_block_idx_in_account_balance_table()
+        .map_err(|e| Error::unable_to_find_account_balance(&e))?
+        .ok_or_else(|| {
+            Error::unable_to_find_account_balance(
+                &"No account balances have been processed yet".to_owned(),
+            )
+        })?;
+
     let rosetta_block = match partial_block_identifier {
         Some(block_id) => get_rosetta_block_from_partial_block_identifier(block_id, storage_client)
             .map_err(|err| Error::invalid_block_identifier(&err))?,
         None => storage_client
-            .get_block_with_highest_block_idx()
+            .get_block_at_idx(highest_processed_block_idx)
             .map_err(|e| Error::unable_to_find_block(&e))?
             .ok_or_else(|| Error::unable_to_find_block(&"Current block not found".to_owned()))?,
     };
 
+    if rosetta_block.index > highest_processed_block_idx {
+        return Err(Error::unable_to_find_account_balance(&format!(
+            "Account balances are only available up to block {}, but block {} was requested",
+            highest_processed_block_idx, rosetta_block.index
+        )));
+    }
+
     let balance = storage_client
         .get_account_balance_at_block_idx(
             &(Account::try_from(account_identifier.clone())
@@ -356,12 +376,22 @@ pub fn search_transactions(
         parameters.push((":tx_hash", Box::new(tx_hash)));
     }
 
-    if let Some(operation_type) = operation_type {
-        command.push_str("AND operation_type = :operation_type ");
-        parameters.push((
-            ":operation_type",
-            Box::new(operation_type.to_string().to_lowercase()),
-        ));
+    match operation_type {
+        // Spender operations are part of the transfers and burns made on behalf of an account
+        // through ICRC-2, i.e. icrc2_transfer_from
+        Some(OperationType::Spender) => {
+            command.push_str(
+                "AND operation_type IN ('transfer', 'burn') AND spender_principal IS NOT NULL ",
+            );
+        }
+        Some(operation_type) => {
+            command.push_str("AND operation_type = :operation_type ");
+            parameters.push((
+                ":operation_type",
+                Box::new(operation_type.to_string().to_lowercase()),
+            ));
+        }
+        None => {}
     }
 
     if let Some(account) = account {
@@ -582,6 +612,64 @@ mod test {
                     }
                     }
 
+                    #[test]
+                    fn test_historical_account_balance_service(blockchain in valid_blockchain_strategy::<U256>(BLOCKCHAIN_LENGTH)){
+                        let storage_client_memory = Arc::new(StorageClient::new_in_memory().unwrap());
+                        let mut rosetta_blocks = vec![];
+                        for block in blockchain.into_iter() {
+                            // Only Mint blocks are used so that every block yields a valid balance history.
+                            if let ic_icrc1::Operation::Mint{..} = block.transaction.operation {
+                                rosetta_blocks.push(RosettaBlock::from_generic_block(encoded_block_to_generic_block(&block.encode()), rosetta_blocks.len() as u64).unwrap());
+                            }
+                        }
+                        if rosetta_blocks.len() > 1 {
+                        let metadata = Metadata {
+                            symbol: "ICP".to_string(),
+                            decimals: 8,
+                        };
+                        let (processed_blocks, pending_blocks) = rosetta_blocks.split_at(rosetta_blocks.len() - 1);
+                        storage_client_memory.store_blocks(processed_blocks.to_vec()).unwrap();
+
+                        // Without a balance history no balance can be returned
+                        let account = match processed_blocks[0].get_transaction().operation {
+                            IcrcOperation::Mint { to, .. } => to,
+                            _ => unreachable!(),
+                        };
+                        assert!(account_balance(&storage_client_memory, &account.into(), &None, metadata.decimals, metadata.symbol.clone()).is_err());
+
+                        storage_client_memory.update_account_balances().unwrap();
+
+                        // The balance at every block is the sum of all mints to the account up to that block
+                        let mut expected_balance = Nat(BigUint::zero());
+                        for rosetta_block in processed_blocks {
+                            if let IcrcOperation::Mint { to, amount } = rosetta_block.get_transaction().operation {
+                                if to == account {
+                                    expected_balance = Nat(expected_balance.0 + amount.0);
+                                }
+                            }
+                            let response = account_balance(
+                                &storage_client_memory,
+                                &account.into(),
+                                &Some(PartialBlockIdentifier { index: Some(rosetta_block.index), hash: None }),
+                                metadata.decimals,
+                                metadata.symbol.clone(),
+                            ).unwrap();
+                            assert_eq!(response.block_identifier, rosetta_block.clone().get_block_identifier());
+                            assert_eq!(response.balances[0].value, expected_balance.0.to_string());
+                        }
+
+                        // Blocks that are stored but not yet reflected in the balance history are rejected
+                        storage_client_memory.store_blocks(pending_blocks.to_vec()).unwrap();
+                        let pending_block_identifier = Some(PartialBlockIdentifier { index: Some(pending_blocks[0].index), hash: None });
+                        assert!(account_balance(&storage_client_memory, &account.into(), &pending_block_identifier, metadata.decimals, metadata.symbol.clone()).is_err());
+                        let response = account_balance(&storage_client_memory, &account.into(), &None, metadata.decimals, metadata.symbol.clone()).unwrap();
+                        assert_eq!(response.block_identifier, processed_blocks.last().unwrap().clone().get_block_identifier());
+
+                        storage_client_memory.update_account_balances().unwrap();
+                        assert!(account_balance(&storage_client_memory, &account.into(), &pending_block_identifier, metadata.decimals, metadata.symbol.clone()).is_ok());
+                        }
+                    }
+
                     #[test]
                     fn test_block_service(blockchain in valid_blockchain_strategy::<U256>(BLOCKCHAIN_LENGTH)){
                         let storage_client_memory = Arc::new(StorageClient::new_in_memory().unwrap());
@@ -10,7 +10,7 @@ use icrc_ledger_types::icrc3::archive::ArchiveInfo;
 use num_traits::ToPrimitive;
 use rosetta_core::objects::Currency;
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     sync::{Arc, Mutex},
 };
 use tokio::sync::Mutex as AsyncMutex;
@@ -28,6 +28,24 @@ pub struct AppState {
     pub metadata: Metadata,
 }
 
+/// The state of all the ICRC-1 ledgers served by Rosetta.
+/// Requests are routed to a ledger using the network of their network identifier,
+/// which is the textual representation of the ledger canister id.
+pub struct MultiTokenAppState {
+    pub token_states: BTreeMap<String, Arc<AppState>>,
+}
+
+impl MultiTokenAppState {
+    pub fn new(token_states: impl IntoIterator<Item = Arc<AppState>>) -> Self {
+        Self {
+            token_states: token_states
+                .into_iter()
+                .map(|state| (state.ledger_id.to_string(), state))
+                .collect(),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct Metadata {
     pub symbol: String,
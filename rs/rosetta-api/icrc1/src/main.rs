@@ -17,11 +17,13 @@ use ic_icrc_rosetta::{
     construction_api::endpoints::*,
     data_api::endpoints::*,
     ledger_blocks_synchronization::blocks_synchronizer::start_synching_blocks,
-    AppState, Metadata,
+    AppState, Metadata, MultiTokenAppState,
 };
 use ic_sys::fs::write_string_using_tmp_file;
 use icrc_ledger_agent::{CallMode, Icrc1Agent};
 use lazy_static::lazy_static;
+use std::collections::HashSet;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::{path::PathBuf, process};
 use tokio::{net::TcpListener, sync::Mutex as AsyncMutex};
@@ -54,11 +56,71 @@ enum NetworkType {
     Testnet,
 }
 
+/// An ICRC-1 ledger served by Rosetta.
+#[derive(Clone, Debug)]
+struct TokenDef {
+    ledger_id: CanisterId,
+    icrc1_symbol: Option<String>,
+    icrc1_decimals: Option<u8>,
+}
+
+impl TokenDef {
+    fn are_metadata_args_set(&self) -> bool {
+        self.icrc1_symbol.is_some() && self.icrc1_decimals.is_some()
+    }
+}
+
+impl FromStr for TokenDef {
+    type Err = String;
+
+    /// Parses a token definition of the form `<ledger_id>[:<symbol>[:<decimals>]]`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':');
+        let ledger_id = parts.next().unwrap_or_default();
+        let ledger_id = CanisterId::from_str(ledger_id)
+            .map_err(|err| format!("Invalid ledger id {}: {}", ledger_id, err))?;
+        let icrc1_symbol = parts.next().map(|symbol| symbol.to_string());
+        let icrc1_decimals = parts
+            .next()
+            .map(|decimals| {
+                decimals
+                    .parse::<u8>()
+                    .map_err(|err| format!("Invalid decimals {}: {}", decimals, err))
+            })
+            .transpose()?;
+        if parts.next().is_some() {
+            return Err(format!(
+                "Invalid token definition {}, expected <ledger_id>[:<symbol>[:<decimals>]]",
+                s
+            ));
+        }
+        Ok(Self {
+            ledger_id,
+            icrc1_symbol,
+            icrc1_decimals,
+        })
+    }
+}
+
 #[derive(Debug, Parser)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    /// The id of the ICRC-1 ledger.
+    /// Either this or [multi_tokens] must be set.
     #[arg(short, long)]
-    ledger_id: CanisterId,
+    ledger_id: Option<CanisterId>,
+
+    /// The ICRC-1 ledgers to serve, separated by commas.
+    /// Each ledger is defined as `<ledger_id>[:<symbol>[:<decimals>]]` where the
+    /// symbol and the decimals have the same meaning as [icrc1_symbol] and [icrc1_decimals].
+    /// Requests are routed to a ledger using the network of their network identifier.
+    #[arg(long, value_delimiter = ',', conflicts_with_all = ["ledger_id", "icrc1_symbol", "icrc1_decimals"])]
+    multi_tokens: Vec<TokenDef>,
+
+    /// The directory to use for the stores of the ledgers set with [multi_tokens] if [store_type] is file.
+    /// The store of every ledger is the file `<ledger_id>.sqlite` in this directory.
+    #[arg(long, default_value = "/data")]
+    multi_tokens_store_dir: PathBuf,
 
     /// The symbol of the ICRC-1 token.
     /// If set Rosetta will check the symbol against the ledger it connects to. If the symbol does not match, it will exit.
@@ -138,8 +200,38 @@ impl Args {
         })
     }
 
-    fn are_metadata_args_set(&self) -> bool {
-        self.icrc1_symbol.is_some() && self.icrc1_decimals.is_some()
+    /// Return the ledgers Rosetta should serve.
+    fn get_token_defs(&self) -> Result<Vec<TokenDef>> {
+        if let Some(ledger_id) = self.ledger_id {
+            return Ok(vec![TokenDef {
+                ledger_id,
+                icrc1_symbol: self.icrc1_symbol.clone(),
+                icrc1_decimals: self.icrc1_decimals,
+            }]);
+        }
+        if self.multi_tokens.is_empty() {
+            bail!("Either 'ledger-id' or 'multi-tokens' must be specified.");
+        }
+        let mut ledger_ids = HashSet::new();
+        for token_def in &self.multi_tokens {
+            if !ledger_ids.insert(token_def.ledger_id) {
+                bail!(
+                    "The ledger {} is specified more than once in 'multi-tokens'.",
+                    token_def.ledger_id
+                );
+            }
+        }
+        Ok(self.multi_tokens.clone())
+    }
+
+    /// Return the file to use for the store of the given ledger if [store_type] is file.
+    fn get_store_file(&self, token_def: &TokenDef) -> PathBuf {
+        if self.multi_tokens.is_empty() {
+            self.store_file.clone()
+        } else {
+            self.multi_tokens_store_dir
+                .join(format!("{}.sqlite", token_def.ledger_id))
+        }
     }
 }
 
@@ -202,30 +294,31 @@ fn add_request_span() -> FnTraceLayer {
 }
 
 async fn load_metadata(
-    args: &Args,
+    token_def: &TokenDef,
+    offline: bool,
     icrc1_agent: &Icrc1Agent,
     storage: &StorageClient,
 ) -> anyhow::Result<Metadata> {
-    if args.offline {
+    if offline {
         let db_metadata_entries = storage.read_metadata()?;
         // If metadata is empty and the args are not set, bail out.
-        if db_metadata_entries.is_empty() && !args.are_metadata_args_set() {
+        if db_metadata_entries.is_empty() && !token_def.are_metadata_args_set() {
             bail!("Metadata must be initialized by starting Rosetta in online mode first or by providing ICRC-1 metadata arguments.");
         }
 
         // If metadata is set in args and not entries are found in the database,
-        // return the metadata from the args.
-        if args.are_metadata_args_set() && db_metadata_entries.is_empty() {
+        // return the metadata from the token_def.
+        if token_def.are_metadata_args_set() && db_metadata_entries.is_empty() {
             return Ok(Metadata::from_args(
-                args.icrc1_symbol.clone().unwrap(),
-                args.icrc1_decimals.unwrap(),
+                token_def.icrc1_symbol.clone().unwrap(),
+                token_def.icrc1_decimals.unwrap(),
             ));
         }
 
         // Populate a metadata object with the database entries.
         let db_metadata = Metadata::from_metadata_entries(&db_metadata_entries)?;
         // If the metadata args are not set, return using the db metadata.
-        if !args.are_metadata_args_set() {
+        if !token_def.are_metadata_args_set() {
             return Ok(db_metadata);
         }
 
@@ -285,10 +378,20 @@ async fn main() -> Result<()> {
 
     let _guard = init_logs(args.log_level, &args.log_file)?;
 
-    let storage = Arc::new(match args.store_type {
-        StoreType::InMemory => StorageClient::new_in_memory()?,
-        StoreType::File => StorageClient::new_persistent(&args.store_file)?,
-    });
+    let token_defs = args.get_token_defs()?;
+
+    if args.exit_on_sync && args.offline {
+        bail!("'exit-on-sync' and 'offline' parameters cannot be specified at the same time.");
+    }
+
+    if !args.multi_tokens.is_empty() && matches!(args.store_type, StoreType::File) {
+        std::fs::create_dir_all(&args.multi_tokens_store_dir).with_context(|| {
+            format!(
+                "Failed to create the store directory {}",
+                args.multi_tokens_store_dir.display()
+            )
+        })?;
+    }
 
     let network_url = args.effective_network_url();
 
@@ -313,56 +416,73 @@ async fn main() -> Result<()> {
         ic_agent.status().await?.replica_health_status
     );
 
-    let icrc1_agent = Arc::new(Icrc1Agent {
-        agent: ic_agent,
-        ledger_canister_id: args.ledger_id.into(),
-    });
+    let mut token_states = vec![];
+    for token_def in token_defs {
+        let store_file = args.get_store_file(&token_def);
+        let storage = Arc::new(match args.store_type {
+            StoreType::InMemory => StorageClient::new_in_memory()?,
+            StoreType::File => StorageClient::new_persistent(&store_file)?,
+        });
 
-    let metadata = load_metadata(&args, &icrc1_agent, &storage).await?;
-    if let Some(token_symbol) = args.icrc1_symbol.clone() {
-        if metadata.symbol != token_symbol {
-            bail!(
-                "Provided symbol does not match symbol retrieved in online mode. Expected: {}, Got: {}",
-                metadata.symbol, token_symbol
-            );
-        }
-    }
+        let icrc1_agent = Arc::new(Icrc1Agent {
+            agent: ic_agent.clone(),
+            ledger_canister_id: token_def.ledger_id.into(),
+        });
 
-    info!(
-        "ICRC Rosetta is connected to the ICRC-1 ledger: {}",
-        args.ledger_id
-    );
-    info!(
-        "The token symbol of the ICRC-1 ledger is: {}",
-        metadata.symbol
-    );
+        let metadata = load_metadata(&token_def, args.offline, &icrc1_agent, &storage).await?;
+        if let Some(token_symbol) = token_def.icrc1_symbol.clone() {
+            if metadata.symbol != token_symbol {
+                bail!(
+                    "Provided symbol does not match symbol retrieved in online mode. Expected: {}, Got: {}",
+                    metadata.symbol, token_symbol
+                );
+            }
+        }
 
-    let shared_state = Arc::new(AppState {
-        icrc1_agent: icrc1_agent.clone(),
-        ledger_id: args.ledger_id,
-        synched: Arc::new(Mutex::new(None)),
-        storage: storage.clone(),
-        archive_canister_ids: Arc::new(AsyncMutex::new(vec![])),
-        metadata,
-    });
+        info!(
+            "ICRC Rosetta is connected to the ICRC-1 ledger: {}",
+            token_def.ledger_id
+        );
+        info!(
+            "The token symbol of the ICRC-1 ledger {} is: {}",
+            token_def.ledger_id, metadata.symbol
+        );
+
+        let token_state = Arc::new(AppState {
+            icrc1_agent,
+            ledger_id: token_def.ledger_id,
+            synched: Arc::new(Mutex::new(None)),
+            storage,
+            archive_canister_ids: Arc::new(AsyncMutex::new(vec![])),
+            metadata,
+        });
+        token_states.push((token_state, store_file));
+    }
 
     if args.exit_on_sync {
-        if args.offline {
-            bail!("'exit-on-sync' and 'offline' parameters cannot be specified at the same time.");
+        for (token_state, _) in &token_states {
+            info!(
+                "Starting to sync blocks of ledger {}",
+                token_state.ledger_id
+            );
+            start_synching_blocks(
+                token_state.icrc1_agent.clone(),
+                token_state.storage.clone(),
+                *MAXIMUM_BLOCKS_PER_REQUEST,
+                Arc::new(AsyncMutex::new(vec![])),
+            )
+            .await?;
         }
 
-        info!("Starting to sync blocks");
-        start_synching_blocks(
-            icrc1_agent.clone(),
-            storage.clone(),
-            *MAXIMUM_BLOCKS_PER_REQUEST,
-            Arc::new(AsyncMutex::new(vec![])),
-        )
-        .await?;
-
         process::exit(0);
     }
 
+    let shared_state = Arc::new(MultiTokenAppState::new(
+        token_states
+            .iter()
+            .map(|(token_state, _)| token_state.clone()),
+    ));
+
     let app = Router::new()
         .route("/ready", get(ready))
         .route("/health", get(health))
@@ -404,38 +524,45 @@ async fn main() -> Result<()> {
     }
 
     if !args.offline {
-        tokio::task::spawn_blocking(move || {
-            let mut sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
-
-            let block_sync_storage = match args.store_type {
-                StoreType::InMemory => storage.clone(),
-                StoreType::File => {
-                    Arc::new(StorageClient::new_persistent(&args.store_file).unwrap())
-                }
-            };
-
-            tokio::runtime::Handle::current().block_on(async {
-                loop {
-                    if let Err(e) = start_synching_blocks(
-                        icrc1_agent.clone(),
-                        block_sync_storage.clone(),
-                        *MAXIMUM_BLOCKS_PER_REQUEST,
-                        shared_state.clone().archive_canister_ids.clone(),
-                    )
-                    .await
-                    {
-                        error!("Error while syncing blocks: {}", e);
-                        sync_wait_secs =
-                            std::cmp::min(sync_wait_secs * 2, MAX_BLOCK_SYNC_WAIT_SECS);
-                        info!("Retrying in {} seconds.", sync_wait_secs);
-                    } else {
-                        sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+        // Every ledger is synchronized by its own task
+        for (token_state, store_file) in token_states {
+            let store_type = args.store_type.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+
+                let block_sync_storage = match store_type {
+                    StoreType::InMemory => token_state.storage.clone(),
+                    StoreType::File => {
+                        Arc::new(StorageClient::new_persistent(&store_file).unwrap())
                     }
-
-                    tokio::time::sleep(std::time::Duration::from_secs(sync_wait_secs)).await;
-                }
+                };
+
+                tokio::runtime::Handle::current().block_on(async {
+                    loop {
+                        if let Err(e) = start_synching_blocks(
+                            token_state.icrc1_agent.clone(),
+                            block_sync_storage.clone(),
+                            *MAXIMUM_BLOCKS_PER_REQUEST,
+                            token_state.archive_canister_ids.clone(),
+                        )
+                        .await
+                        {
+                            error!(
+                                "Error while syncing blocks of ledger {}: {}",
+                                token_state.ledger_id, e
+                            );
+                            sync_wait_secs =
+                                std::cmp::min(sync_wait_secs * 2, MAX_BLOCK_SYNC_WAIT_SECS);
+                            info!("Retrying in {} seconds.", sync_wait_secs);
+                        } else {
+                            sync_wait_secs = BLOCK_SYNC_WAIT_SECS;
+                        }
+
+                        tokio::time::sleep(std::time::Duration::from_secs(sync_wait_secs)).await;
+                    }
+                });
             });
-        });
+        }
     }
 
     info!("Starting Rosetta server");
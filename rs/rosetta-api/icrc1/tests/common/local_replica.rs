@@ -90,8 +90,16 @@ fn icrc_ledger_wasm() -> Vec<u8> {
 const STARTING_CYCLES_PER_CANISTER: u128 = 2_000_000_000_000_000;
 
 pub fn create_and_install_icrc_ledger(pocket_ic: &PocketIc, init_args: InitArgs) -> Principal {
-    let wasm_module = local_replica::icrc_ledger_wasm();
     let canister_id = Principal::from_str("2ouva-viaaa-aaaaq-aaamq-cai").unwrap();
+    create_and_install_icrc_ledger_with_id(pocket_ic, init_args, canister_id)
+}
+
+pub fn create_and_install_icrc_ledger_with_id(
+    pocket_ic: &PocketIc,
+    init_args: InitArgs,
+    canister_id: Principal,
+) -> Principal {
+    let wasm_module = local_replica::icrc_ledger_wasm();
     let custom_encoded_init_args = Encode!(&(LedgerArgument::Init(init_args.clone()))).unwrap();
     pocket_ic
         .create_canister_with_id(None, None, canister_id)
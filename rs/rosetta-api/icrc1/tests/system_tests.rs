@@ -1,5 +1,7 @@
 use crate::common::local_replica;
-use crate::common::local_replica::{create_and_install_icrc_ledger, test_identity};
+use crate::common::local_replica::{
+    create_and_install_icrc_ledger, create_and_install_icrc_ledger_with_id, test_identity,
+};
 use crate::common::utils::{get_rosetta_blocks_from_icrc1_ledger, wait_for_rosetta_block};
 use candid::Nat;
 use candid::Principal;
@@ -410,6 +412,129 @@ fn test_network_list() {
     });
 }
 
+#[test]
+fn test_multi_tokens() {
+    let rt = Runtime::new().unwrap();
+    let setup = Setup::builder().build();
+
+    // Install a second ledger with a different symbol next to the first one
+    let first_ledger_id = setup.icrc1_ledger_canister_id;
+    let second_ledger_id = CanisterId::from_u64(
+        u64::from_be_bytes(first_ledger_id.as_slice()[..8].try_into().unwrap()) + 1,
+    )
+    .get()
+    .0;
+    let second_ledger_symbol = "XTST2";
+    create_and_install_icrc_ledger_with_id(
+        &setup.pocket_ic,
+        InitArgsBuilder::with_symbol_and_name(second_ledger_symbol, "Second Test Token")
+            .with_transfer_fee(DEFAULT_TRANSFER_FEE)
+            .with_minting_account(setup.minting_account)
+            .with_initial_balance(*TEST_ACCOUNT, 1_000_000_000u64)
+            .build(),
+        second_ledger_id,
+    );
+
+    rt.block_on(async {
+        let replica_url = format!("http://localhost:{}", setup.port);
+        let rosetta_context = start_rosetta(
+            &rosetta_bin(),
+            RosettaOptions {
+                network_url: Some(replica_url),
+                offline: false,
+                multi_tokens: Some(format!(
+                    "{}:{},{}",
+                    first_ledger_id, DEFAULT_TOKEN_SYMBOL, second_ledger_id
+                )),
+                ..RosettaOptions::default()
+            },
+        )
+        .await;
+        let rosetta_client =
+            RosettaClient::from_str_url(&format!("http://0.0.0.0:{}", rosetta_context.port))
+                .expect("Unable to parse url");
+        let network_identifier = |ledger_id: Principal| {
+            NetworkIdentifier::new(DEFAULT_BLOCKCHAIN.to_owned(), ledger_id.to_string())
+        };
+
+        // Both ledgers are listed
+        let mut network_list = rosetta_client
+            .network_list()
+            .await
+            .expect("Unable to call network_list")
+            .network_identifiers;
+        network_list.sort_by_key(|network_identifier| network_identifier.network.clone());
+        let mut expected = vec![
+            network_identifier(first_ledger_id),
+            network_identifier(second_ledger_id),
+        ];
+        expected.sort_by_key(|network_identifier| network_identifier.network.clone());
+        assert_eq!(network_list, expected);
+
+        // Every ledger is synced independently
+        let second_ledger_agent = Icrc1Agent {
+            agent: local_replica::get_testing_agent(setup.port).await,
+            ledger_canister_id: second_ledger_id,
+        };
+        let block_index = second_ledger_agent
+            .transfer(TransferArg {
+                from_subaccount: None,
+                to: Account::from(PrincipalId::new_user_test_id(1).0),
+                fee: None,
+                amount: 1_000u64.into(),
+                memo: None,
+                created_at_time: None,
+            })
+            .await
+            .expect("sending transfer failed")
+            .expect("transfer resulted in an error");
+        assert_eq!(block_index, 1u64);
+        assert_eq!(
+            wait_for_rosetta_block(&rosetta_client, network_identifier(second_ledger_id), 1).await,
+            Some(1)
+        );
+        assert_eq!(
+            wait_for_rosetta_block(&rosetta_client, network_identifier(first_ledger_id), 0).await,
+            Some(0)
+        );
+
+        // Every ledger is served with its own metadata
+        for (ledger_id, symbol) in [
+            (first_ledger_id, DEFAULT_TOKEN_SYMBOL),
+            (second_ledger_id, second_ledger_symbol),
+        ] {
+            let block = rosetta_client
+                .block(
+                    network_identifier(ledger_id),
+                    PartialBlockIdentifier {
+                        index: Some(0),
+                        hash: None,
+                    },
+                )
+                .await
+                .expect("Unable to call block")
+                .block
+                .expect("Block 0 not found");
+            let amount = block.transactions[0].operations[0]
+                .amount
+                .clone()
+                .expect("The mint operation has no amount");
+            assert_eq!(amount.currency.symbol, symbol);
+        }
+
+        // Requests for ledgers that are not served are rejected
+        let unknown_ledger_id = CanisterId::from_u64(
+            u64::from_be_bytes(second_ledger_id.as_slice()[..8].try_into().unwrap()) + 1,
+        )
+        .get()
+        .0;
+        assert!(rosetta_client
+            .network_status(network_identifier(unknown_ledger_id))
+            .await
+            .is_err());
+    });
+}
+
 #[test]
 fn test_network_options() {
     let rt = Runtime::new().unwrap();
@@ -1427,6 +1552,33 @@ fn test_rosetta_transfer_from() {
             .unwrap();
 
         assert_eq!(balance_receiver, transfer_amount);
+
+        // Blocks 0 and 1 mint the initial balances. The approve (block 2) and
+        // the transfer from (block 3) can be found by the spender
+        wait_for_rosetta_block(&env.rosetta_client, env.network_identifier.clone(), 3).await;
+        for (type_, expected_block_indices) in [
+            (None, vec![3, 2]),
+            (Some(OperationType::Approve), vec![2]),
+            (Some(OperationType::Spender), vec![3]),
+            (Some(OperationType::Transfer), vec![3]),
+        ] {
+            let request = SearchTransactionsRequest {
+                network_identifier: env.network_identifier.clone(),
+                account_identifier: Some(spender_account.into()),
+                type_: type_.map(|type_| type_.to_string()),
+                ..Default::default()
+            };
+            let block_indices: Vec<u64> = env
+                .rosetta_client
+                .search_transactions(&request)
+                .await
+                .expect("Unable to call search_transactions")
+                .transactions
+                .into_iter()
+                .map(|transaction| transaction.block_identifier.index)
+                .collect();
+            assert_eq!(block_indices, expected_block_indices);
+        }
     });
 }
 
@@ -21,6 +21,7 @@ use ic_replicated_state::{
     },
     page_map::{Shard, StorageLayout, StorageResult},
     CallContextManager, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    TaskSkipReason,
 };
 use ic_sys::{fs::sync_path, mmap::ScopedMmap};
 use ic_types::{
@@ -180,6 +181,9 @@ pub struct CanisterStateBits {
     pub next_snapshot_id: u64,
     pub snapshots_memory_usage: NumBytes,
     pub on_low_wasm_memory_hook_status: OnLowWasmMemoryHookStatus,
+    pub last_heartbeat_execution_round: Option<ExecutionRound>,
+    pub last_global_timer_execution_round: Option<ExecutionRound>,
+    pub task_skip_reason: Option<TaskSkipReason>,
 }
 
 /// This struct contains bits of the `CanisterSnapshot` that are not already
@@ -2349,6 +2353,14 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
                 )
                 .into(),
             ),
+            last_heartbeat_execution_round: item.last_heartbeat_execution_round.map(|r| r.get()),
+            last_global_timer_execution_round: item
+                .last_global_timer_execution_round
+                .map(|r| r.get()),
+            task_skip_reason: item
+                .task_skip_reason
+                .as_ref()
+                .map(|reason| pb_canister_state_bits::TaskSkipReason::from(reason).into()),
         }
     }
 }
@@ -2508,6 +2520,19 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
                 "CanisterStateBits::on_low_wasm_memory_hook_status",
             )
             .unwrap_or_default(),
+            last_heartbeat_execution_round: value.last_heartbeat_execution_round.map(From::from),
+            last_global_timer_execution_round: value
+                .last_global_timer_execution_round
+                .map(From::from),
+            task_skip_reason: value
+                .task_skip_reason
+                .map(|reason| {
+                    TaskSkipReason::try_from(
+                        pb_canister_state_bits::TaskSkipReason::try_from(reason)
+                            .unwrap_or_default(),
+                    )
+                })
+                .transpose()?,
         })
     }
 }
@@ -61,6 +61,9 @@ fn default_canister_state_bits() -> CanisterStateBits {
         next_snapshot_id: 0,
         snapshots_memory_usage: NumBytes::from(0),
         on_low_wasm_memory_hook_status: OnLowWasmMemoryHookStatus::default(),
+        last_heartbeat_execution_round: None,
+        last_global_timer_execution_round: None,
+        task_skip_reason: None,
     }
 }
 
@@ -270,6 +273,33 @@ fn test_encode_decode_task_queue() {
     }
 }
 
+#[test]
+fn test_encode_decode_task_scheduling_info() {
+    for task_skip_reason in [
+        None,
+        Some(TaskSkipReason::CanisterNotRunning),
+        Some(TaskSkipReason::LongExecutionPending),
+        Some(TaskSkipReason::OutOfCycles),
+        Some(TaskSkipReason::Frozen),
+    ] {
+        let canister_state_bits = CanisterStateBits {
+            last_heartbeat_execution_round: Some(ExecutionRound::from(7)),
+            last_global_timer_execution_round: None,
+            task_skip_reason,
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(
+            canister_state_bits.last_heartbeat_execution_round,
+            Some(ExecutionRound::from(7))
+        );
+        assert_eq!(canister_state_bits.last_global_timer_execution_round, None);
+        assert_eq!(canister_state_bits.task_skip_reason, task_skip_reason);
+    }
+}
+
 #[test]
 fn test_removal_when_last_dropped() {
     with_test_replica_logger(|log| {
@@ -21,7 +21,7 @@ use ic_crypto_tree_hash::{sparse_labeled_tree_from_paths, Label, Path as Labeled
 use ic_crypto_utils_threshold_sig_der::threshold_sig_public_key_to_der;
 use ic_cycles_account_manager::CyclesAccountManager;
 pub use ic_error_types::{ErrorCode, UserError};
-use ic_execution_environment::{ExecutionServices, IngressHistoryReaderImpl};
+use ic_execution_environment::{CoverageCollector, ExecutionServices, IngressHistoryReaderImpl};
 use ic_http_endpoints_public::{metrics::HttpHandlerMetrics, IngressWatcher, IngressWatcherHandle};
 use ic_https_outcalls_consensus::payload_builder::CanisterHttpPayloadBuilderImpl;
 use ic_ingress_manager::{IngressManager, RandomStateKind};
@@ -31,7 +31,9 @@ use ic_interfaces::{
     certification::{Verifier, VerifierError},
     consensus::{PayloadBuilder as ConsensusPayloadBuilder, PayloadValidationError},
     consensus_pool::ConsensusTime,
-    execution_environment::{IngressFilterService, IngressHistoryReader, QueryExecutionService},
+    execution_environment::{
+        CoveredBlock, IngressFilterService, IngressHistoryReader, QueryExecutionService,
+    },
     ingress_pool::{
         IngressPool, IngressPoolObject, PoolSection, UnvalidatedIngressArtifact,
         ValidatedIngressArtifact,
@@ -95,8 +97,13 @@ use ic_registry_subnet_features::{
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_state::{system_state::CyclesUseCase, NumWasmPages, WASM_PAGE_SIZE_IN_BYTES},
-    metadata_state::subnet_call_context_manager::{SignWithThresholdContext, ThresholdArguments},
+    metadata_state::{
+        subnet_call_context_manager::{SignWithThresholdContext, ThresholdArguments},
+        Stream,
+    },
     page_map::Buffer,
+    replicated_state::{ReplicatedStateMessageRouting, MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN},
+    testing::ReplicatedStateTesting,
     CheckpointLoadingMetrics, Memory, PageMap, ReplicatedState,
 };
 use ic_state_layout::{CheckpointLayout, ReadOnly};
@@ -132,12 +139,12 @@ use ic_types::{
     malicious_flags::MaliciousFlags,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpCallContent, HttpCanisterUpdate,
-        HttpRequestEnvelope, Payload as MsgPayload, Query, QuerySource, RejectContext,
-        SignedIngress, EXPECTED_MESSAGE_ID_LENGTH,
+        HttpRequestEnvelope, Payload as MsgPayload, Query, QuerySource, RejectContext, Request,
+        RequestOrResponse, Response, SignedIngress, EXPECTED_MESSAGE_ID_LENGTH,
     },
     signature::ThresholdSignature,
     time::GENESIS,
-    xnet::{CertifiedStreamSlice, StreamIndex},
+    xnet::{CertifiedStreamSlice, RejectReason, StreamIndex, StreamIndexedQueue},
     CanisterLog, CountBytes, CryptoHashOfPartialState, Height, NodeId, Randomness, RegistryVersion,
     ReplicaVersion,
 };
@@ -151,6 +158,7 @@ use ic_types::{
     time::Time,
     CanisterId, CryptoHashOfState, Cycles, NumBytes, PrincipalId, SubnetId, UserId,
 };
+use ic_wasm_types::WasmHash;
 use ic_xnet_payload_builder::{
     certified_slice_pool::{certified_slice_count_bytes, CertifiedSliceError},
     ExpectedIndices, RefillTaskHandle, XNetPayloadBuilderImpl, XNetPayloadBuilderMetrics,
@@ -165,7 +173,7 @@ use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::Serialize;
 use slog::Level;
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     convert::TryFrom,
     fmt,
     io::{self, stderr},
@@ -591,6 +599,88 @@ impl PocketIngressPool {
     }
 }
 
+/// Decides if the message at the given index in the stream to the given remote subnet
+/// is held back, i.e., not delivered to the remote subnet in XNet payloads.
+pub type XNetMessageHoldFilter =
+    dyn Fn(SubnetId, StreamIndex, &RequestOrResponse) -> bool + Send + Sync;
+
+/// Messages taken out of the loopback stream of a `StateMachine` so that they are
+/// not inducted: a message held back by the XNet message hold filter and all messages
+/// routed into the loopback stream after it.
+#[derive(Default)]
+struct HeldLoopbackMessages {
+    /// The index in the loopback stream at which the held messages were taken out of it.
+    begin: StreamIndex,
+    messages: VecDeque<RequestOrResponse>,
+    /// Whether the next loopback message is inducted in the next round even if it is held back.
+    deliver_next: bool,
+}
+
+impl HeldLoopbackMessages {
+    /// Returns the messages in the loopback stream and the held messages
+    /// in the order in which they were routed into the loopback stream.
+    fn merge(&self, loopback_stream: Option<&Stream>) -> VecDeque<RequestOrResponse> {
+        let (routed_before, routed_after): (Vec<_>, Vec<_>) = loopback_stream
+            .into_iter()
+            .flat_map(|stream| stream.messages().iter())
+            .partition(|(index, _)| self.messages.is_empty() || *index < self.begin);
+        routed_before
+            .into_iter()
+            .map(|(_, msg)| msg.clone())
+            .chain(self.messages.iter().cloned())
+            .chain(routed_after.into_iter().map(|(_, msg)| msg.clone()))
+            .collect()
+    }
+}
+
+/// Generates the reject response of the receiving subnet to a request
+/// signaled with the given `RejectReason`.
+fn generate_reject_response(reason: RejectReason, request: &Request) -> RequestOrResponse {
+    let (code, message) = match reason {
+        RejectReason::CanisterMigrating => (
+            RejectCode::SysTransient,
+            format!("Canister {} is migrating", request.receiver),
+        ),
+        RejectReason::CanisterNotFound => (
+            RejectCode::DestinationInvalid,
+            format!("Canister {} not found", request.receiver),
+        ),
+        RejectReason::CanisterStopped => (
+            RejectCode::CanisterError,
+            format!("Canister {} is stopped", request.receiver),
+        ),
+        RejectReason::CanisterStopping => (
+            RejectCode::CanisterError,
+            format!("Canister {} is stopping", request.receiver),
+        ),
+        RejectReason::QueueFull => (
+            RejectCode::SysTransient,
+            format!("Canister {} input queue is full", request.receiver),
+        ),
+        RejectReason::OutOfMemory => (
+            RejectCode::CanisterError,
+            "Cannot induct request. Out of memory".to_string(),
+        ),
+        RejectReason::Unknown => (
+            RejectCode::SysFatal,
+            "Inducting request failed due to an unknown error".to_string(),
+        ),
+    };
+    Response {
+        originator: request.sender,
+        respondent: request.receiver,
+        originator_reply_callback: request.sender_reply_callback,
+        refund: request.payment,
+        response_payload: MsgPayload::Reject(RejectContext::new_with_message_length_limit(
+            code,
+            message,
+            MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN,
+        )),
+        deadline: request.deadline,
+    }
+    .into()
+}
+
 /// Struct mocking the pool of XNet messages required for
 /// instantiating `XNetPayloadBuilderImpl` in `StateMachine`.
 struct PocketXNetSlicePoolImpl {
@@ -626,6 +716,14 @@ impl XNetSlicePool for PocketXNetSlicePoolImpl {
         let subnets = self.subnets.read().unwrap();
         let sm = subnets.get(&subnet_id).unwrap();
         let msg_begin = begin.map(|idx| idx.message_index);
+        // Messages held back by the sending `StateMachine` (and all messages after them
+        // since streams are delivered in order) are not included in the slice.
+        let msg_limit = match sm.num_deliverable_xnet_messages(self.own_subnet_id, msg_begin) {
+            Some(num_deliverable) => Some(msg_limit.map_or(num_deliverable, |msg_limit| {
+                std::cmp::min(msg_limit, num_deliverable)
+            })),
+            None => msg_limit,
+        };
         // We set `witness_begin` equal to `msg_begin` since all states are certified.
         let certified_stream = sm.generate_certified_stream_slice(
             self.own_subnet_id,
@@ -827,6 +925,7 @@ pub struct StateMachine {
     pub metrics_registry: MetricsRegistry,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
     pub query_handler: Arc<Mutex<QueryExecutionService>>,
+    coverage: Option<Arc<CoverageCollector>>,
     runtime: Arc<Runtime>,
     // The atomicity is required for internal mutability and sending across threads.
     checkpoint_interval_length: AtomicU64,
@@ -852,6 +951,8 @@ pub struct StateMachine {
     /// A drop guard to gracefully cancel the ingress watcher task.
     _ingress_watcher_drop_guard: tokio_util::sync::DropGuard,
     query_stats_payload_builder: Arc<PocketQueryStatsPayloadBuilderImpl>,
+    xnet_message_hold_filter: RwLock<Option<Arc<XNetMessageHoldFilter>>>,
+    held_loopback_messages: Mutex<HeldLoopbackMessages>,
     // This field must be the last one so that the temporary directory is deleted at the very end.
     state_dir: Box<dyn StateMachineStateDir>,
     // DO NOT PUT ANY FIELDS AFTER `state_dir`!!!
@@ -1808,6 +1909,7 @@ impl StateMachine {
             message_routing,
             metrics_registry: metrics_registry.clone(),
             query_handler: Arc::new(Mutex::new(execution_services.query_execution_service)),
+            coverage: execution_services.coverage,
             ingress_watcher_handle,
             _ingress_watcher_drop_guard: ingress_watcher_drop_guard,
             certified_height_tx,
@@ -1829,6 +1931,8 @@ impl StateMachine {
             canister_http_pool,
             canister_http_payload_builder,
             query_stats_payload_builder: pocket_query_stats_payload_builder,
+            xnet_message_hold_filter: RwLock::new(None),
+            held_loopback_messages: Mutex::new(HeldLoopbackMessages::default()),
         }
     }
 
@@ -1992,6 +2096,239 @@ impl StateMachine {
         })
     }
 
+    /// Sets the filter deciding which messages in the streams of this state machine
+    /// are held back, i.e., not delivered to the remote subnets in XNet payloads
+    /// built by `Self::execute_round` on the remote subnets and, for the loopback stream,
+    /// not inducted by this state machine. Since streams are delivered in order,
+    /// all messages after a held message in the same stream are held back, too.
+    ///
+    /// Held messages in the loopback stream are kept aside by this state machine
+    /// (and thus not included in its checkpoints) until they are not held back anymore.
+    pub fn set_xnet_message_hold_filter(&self, filter: Option<Arc<XNetMessageHoldFilter>>) {
+        *self.xnet_message_hold_filter.write().unwrap() = filter;
+    }
+
+    /// Returns if the message at the given index in the stream to the given remote subnet
+    /// is held back (see `Self::set_xnet_message_hold_filter`).
+    pub fn is_xnet_message_held(
+        &self,
+        remote_subnet_id: SubnetId,
+        index: StreamIndex,
+        msg: &RequestOrResponse,
+    ) -> bool {
+        self.xnet_message_hold_filter
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(false, |filter| filter(remote_subnet_id, index, msg))
+    }
+
+    /// Returns the number of messages in the stream to the given remote subnet
+    /// starting at `msg_begin` (or at the beginning of the stream) before the first
+    /// held back message, or `None` if no message is held back.
+    fn num_deliverable_xnet_messages(
+        &self,
+        remote_subnet_id: SubnetId,
+        msg_begin: Option<StreamIndex>,
+    ) -> Option<usize> {
+        if self.xnet_message_hold_filter.read().unwrap().is_none() {
+            return None;
+        }
+        let state = self.state_manager.get_latest_state().take();
+        let stream = state.get_stream(&remote_subnet_id)?;
+        let msg_begin = msg_begin.unwrap_or(stream.messages_begin());
+        stream
+            .messages()
+            .iter()
+            .filter(|(index, _)| *index >= msg_begin)
+            .position(|(index, msg)| self.is_xnet_message_held(remote_subnet_id, index, msg))
+    }
+
+    /// Returns the index of the next message this state machine expects
+    /// in the stream from the given remote subnet.
+    pub fn expected_xnet_message_index(&self, remote_subnet_id: SubnetId) -> StreamIndex {
+        let state = self.state_manager.get_latest_state().take();
+        state
+            .get_stream(&remote_subnet_id)
+            .map(|stream| stream.signals_end())
+            .unwrap_or_default()
+    }
+
+    /// Returns the messages in the stream to the given remote subnet
+    /// starting at `msg_begin`, together with their stream indices.
+    /// For the loopback stream, the held messages follow the messages in the stream
+    /// with the indices at which they are put back into the stream.
+    pub fn xnet_messages(
+        &self,
+        remote_subnet_id: SubnetId,
+        msg_begin: StreamIndex,
+    ) -> Vec<(StreamIndex, RequestOrResponse)> {
+        let state = self.state_manager.get_latest_state().take();
+        if remote_subnet_id == self.subnet_id {
+            let loopback_stream = state.get_stream(&self.subnet_id);
+            let begin = loopback_stream.map_or(msg_begin, |stream| stream.messages_begin());
+            return self
+                .held_loopback_messages
+                .lock()
+                .unwrap()
+                .merge(loopback_stream)
+                .into_iter()
+                .enumerate()
+                .map(|(i, msg)| (StreamIndex::new(begin.get() + i as u64), msg))
+                .filter(|(index, _)| *index >= msg_begin)
+                .collect();
+        }
+        state
+            .get_stream(&remote_subnet_id)
+            .map(|stream| {
+                stream
+                    .messages()
+                    .iter()
+                    .filter(|(index, _)| *index >= msg_begin)
+                    .map(|(index, msg)| (index, msg.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the messages in the output queues of this state machine that have not been
+    /// routed into a stream yet, together with the subnet they are going to be routed to.
+    pub fn output_queue_messages(&self) -> Vec<(SubnetId, RequestOrResponse)> {
+        let mut state = self
+            .state_manager
+            .get_latest_state()
+            .take()
+            .as_ref()
+            .clone();
+        let routing_table = state.metadata.network_topology.routing_table.clone();
+        state
+            .output_into_iter()
+            .filter_map(|msg| Some((routing_table.route(msg.receiver().get())?, msg)))
+            .collect()
+    }
+
+    /// Executes a round in which the next message in the loopback stream is inducted
+    /// even if it is held back (see `Self::set_xnet_message_hold_filter`).
+    pub fn deliver_next_loopback_message(&self) {
+        self.held_loopback_messages.lock().unwrap().deliver_next = true;
+        self.execute_payload(PayloadBuilder::new());
+    }
+
+    /// Takes the held messages (see `Self::set_xnet_message_hold_filter`) out of
+    /// the loopback stream and puts the held messages that are not held back anymore
+    /// back into the loopback stream, so that only the messages before the first held one
+    /// are inducted in the next round. If `skip_next` is provided, the next loopback message
+    /// is removed first, generating a reject response for a request if a `RejectReason` is given.
+    fn hold_back_loopback_messages(&self, skip_next: Option<Option<RejectReason>>) {
+        let mut held = self.held_loopback_messages.lock().unwrap();
+        let deliver_next = std::mem::take(&mut held.deliver_next);
+        let count_deliverable = |messages: &VecDeque<RequestOrResponse>, begin: StreamIndex| {
+            if deliver_next {
+                return messages.len().min(1);
+            }
+            messages
+                .iter()
+                .enumerate()
+                .position(|(i, msg)| {
+                    self.is_xnet_message_held(
+                        self.subnet_id,
+                        StreamIndex::new(begin.get() + i as u64),
+                        msg,
+                    )
+                })
+                .unwrap_or(messages.len())
+        };
+
+        // Nothing to do if no message has been or is going to be taken out of the loopback stream.
+        if skip_next.is_none() && held.messages.is_empty() {
+            if !deliver_next && self.xnet_message_hold_filter.read().unwrap().is_none() {
+                return;
+            }
+            let state = self.state_manager.get_latest_state().take();
+            let Some(loopback_stream) = state.get_stream(&self.subnet_id) else {
+                return;
+            };
+            let messages = held.merge(Some(loopback_stream));
+            if count_deliverable(&messages, loopback_stream.messages_begin()) == messages.len() {
+                return;
+            }
+        }
+
+        let (h, mut state) = self.state_manager.take_tip();
+        let loopback_stream = state.get_stream(&self.subnet_id);
+        let begin =
+            loopback_stream.map_or(StreamIndex::default(), |stream| stream.messages_begin());
+        let mut messages = held.merge(loopback_stream);
+        if let Some(reject_reason) = skip_next {
+            let msg = messages.pop_front().expect("No loopback message to skip");
+            if let (Some(reject_reason), RequestOrResponse::Request(request)) = (reject_reason, msg)
+            {
+                state
+                    .push_input(
+                        generate_reject_response(reject_reason, &request),
+                        &mut (i64::MAX / 2),
+                    )
+                    .unwrap();
+            }
+        }
+        let num_deliverable = count_deliverable(&messages, begin);
+        held.messages = messages.split_off(num_deliverable);
+        held.begin = StreamIndex::new(begin.get() + num_deliverable as u64);
+        state.modify_streams(|streams| {
+            let loopback_stream = streams.entry(self.subnet_id).or_default();
+            let mut queue = StreamIndexedQueue::with_begin(begin);
+            for msg in messages {
+                queue.push(msg);
+            }
+            let mut stream = Stream::with_signals(
+                queue,
+                loopback_stream.signals_end(),
+                loopback_stream.reject_signals().clone(),
+            );
+            stream.set_reverse_stream_flags(*loopback_stream.reverse_stream_flags());
+            *loopback_stream = stream;
+        });
+        self.state_manager.commit_and_certify(
+            state,
+            h.increment(),
+            CertificationScope::Metadata,
+            None,
+        );
+    }
+
+    /// Skips the next expected message in the stream from the given remote subnet
+    /// without inducting it: the message is signaled to the remote subnet as rejected
+    /// with the given `reject_reason` (making the remote subnet generate a reject response
+    /// for a request) or, if no `reject_reason` is provided, as accepted (i.e., the message is lost).
+    /// The next message in the loopback stream is skipped if the given remote subnet
+    /// is the subnet of this state machine.
+    pub fn skip_xnet_message(
+        &self,
+        remote_subnet_id: SubnetId,
+        reject_reason: Option<RejectReason>,
+    ) {
+        if remote_subnet_id == self.subnet_id {
+            self.hold_back_loopback_messages(Some(reject_reason));
+            return;
+        }
+        let (h, mut state) = self.state_manager.take_tip();
+        let mut streams = state.take_streams();
+        {
+            let mut stream = streams.get_mut_or_insert(remote_subnet_id);
+            match reject_reason {
+                Some(reject_reason) => stream.push_reject_signal(reject_reason),
+                None => stream.push_accept_signal(),
+            }
+        }
+        state.put_streams(streams);
+        self.state_manager.commit_and_certify(
+            state,
+            h.increment(),
+            CertificationScope::Metadata,
+            None,
+        );
+    }
+
     /// Submit an ingress message into the ingress pool used by `PayloadBuilderImpl`
     /// in `Self::execute_round`.
     pub fn submit_ingress_as(
@@ -2279,6 +2616,9 @@ impl StateMachine {
     /// Advances time by 1ns (to make sure time is strictly monotone)
     /// and triggers a single round of execution with block payload as an input.
     pub fn execute_payload(&self, payload: PayloadBuilder) -> Height {
+        // Held messages in the loopback stream must not be inducted in this round.
+        self.hold_back_loopback_messages(None);
+
         let batch_number = self.message_routing.expected_batch_height();
 
         let mut seed = [0u8; 32];
@@ -2334,6 +2674,9 @@ impl StateMachine {
             batch_number
         );
 
+        // Messages routed into the loopback stream in this round might be held back.
+        self.hold_back_loopback_messages(None);
+
         self.check_critical_errors();
 
         let time_of_next_round = self.time_of_next_round();
@@ -2644,6 +2987,13 @@ impl StateMachine {
         self.set_checkpoint_interval_length(checkpoint_interval_length);
     }
 
+    /// Returns the path of the directory in which this state machine maintains its state.
+    /// To copy a consistent state out of this directory, run `checkpointed_tick()`
+    /// followed by `await_state_hash()` first.
+    pub fn state_dir_path(&self) -> PathBuf {
+        self.state_dir.path()
+    }
+
     /// Replaces the canister state in this state machine with the canister
     /// state in given source replicated state.
     ///
@@ -3085,6 +3435,27 @@ impl StateMachine {
         )
     }
 
+    /// Returns the blocks of the specified canister's Wasm module executed
+    /// since the state machine was created or the coverage was last reset.
+    /// The coverage is shared by all canisters running the same module.
+    ///
+    /// Returns `None` if Wasm coverage is not enabled or the canister has no
+    /// Wasm module. Wasm coverage is enabled by setting
+    /// `embedders_config.feature_flags.wasm_coverage` in the hypervisor config
+    /// passed to `StateMachineBuilder::with_config`.
+    pub fn canister_coverage(&self, canister_id: CanisterId) -> Option<Vec<CoveredBlock>> {
+        let coverage = self.coverage.as_ref()?;
+        let module_hash = self.module_hash(canister_id)?;
+        Some(coverage.covered_blocks(&WasmHash::from(module_hash)))
+    }
+
+    /// Discards the Wasm coverage collected so far.
+    pub fn reset_coverage(&self) {
+        if let Some(coverage) = &self.coverage {
+            coverage.reset();
+        }
+    }
+
     /// Executes an ingress message on the canister with the specified ID.
     ///
     /// This function is synchronous, it blocks until the result of the ingress
@@ -3678,6 +4049,355 @@ fn certify_hash(
     }
 }
 
+/// Conditions of the XNet link from a sending to a receiving subnet
+/// in a `StateMachineEnvironment`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct XNetLinkConditions {
+    /// If set, no stream slices (neither messages nor signals) are delivered over the link.
+    pub partitioned: bool,
+    /// The probability (between 0 and 1) that the stream slice of a round is lost.
+    /// The messages in a lost stream slice are delivered in a later round.
+    pub slice_loss_probability: f64,
+    /// The number of rounds for which a message is delayed before it is delivered
+    /// over the link. Signals are not delayed.
+    pub delay_rounds: usize,
+}
+
+#[derive(Default)]
+struct XNetLink {
+    conditions: XNetLinkConditions,
+    /// The ends of the stream on the sending subnet at the beginning of the most recent
+    /// `conditions.delay_rounds + 1` rounds (the last entry refers to the current round).
+    stream_ends: VecDeque<StreamIndex>,
+}
+
+/// Drives multiple `StateMachine`s (one per subnet) sharing a registry and routing table.
+/// Every round (see `Self::tick`) executes a round on every subnet with the certified stream
+/// slices from all other subnets (subject to the conditions of the XNet links) as XNet payload.
+/// Note that `StateMachine::execute_round` (and thus any method of `StateMachine` awaiting
+/// an ingress message, e.g., `StateMachine::install_canister`) is not supported on the subnets
+/// of an environment: use the corresponding methods of the environment instead.
+pub struct StateMachineEnvironment {
+    subnets: BTreeMap<SubnetId, Arc<StateMachine>>,
+    routing_table: RoutingTable,
+    links: Mutex<BTreeMap<(SubnetId, SubnetId), XNetLink>>,
+    rng: Mutex<StdRng>,
+}
+
+impl StateMachineEnvironment {
+    /// Largest single message is 1T for system subnet install messages
+    /// Considered with 2B instruction slices, this gives us 500 ticks
+    const MAX_TICKS: usize = 500;
+
+    /// Builds a subnet from every given builder and sets up a shared registry in which
+    /// every subnet is assigned a canister range (in the order of the builders)
+    /// and the first subnet is the root subnet.
+    /// The builders must yield distinct subnet IDs (e.g., by specifying distinct subnet seeds).
+    pub fn new(subnet_builders: Vec<StateMachineBuilder>) -> Self {
+        assert!(
+            !subnet_builders.is_empty(),
+            "An environment must contain at least one subnet"
+        );
+        let registry_data_provider = Arc::new(ProtoRegistryDataProvider::new());
+        let mut subnets = BTreeMap::new();
+        let mut subnet_list = vec![];
+        let mut routing_table = RoutingTable::new();
+        for subnet_builder in subnet_builders {
+            let sm = subnet_builder
+                .with_registry_data_provider(registry_data_provider.clone())
+                .build_internal();
+            let subnet_id = sm.get_subnet_id();
+            assert!(
+                subnets.insert(subnet_id, Arc::new(sm)).is_none(),
+                "Duplicate subnet ID {}",
+                subnet_id
+            );
+            subnet_list.push(subnet_id);
+            routing_table_insert_subnet(&mut routing_table, subnet_id).unwrap();
+        }
+        finalize_registry(
+            subnet_list[0],
+            routing_table.clone(),
+            subnet_list,
+            registry_data_provider,
+        );
+        for sm in subnets.values() {
+            sm.reload_registry();
+        }
+        Self {
+            subnets,
+            routing_table,
+            links: Mutex::new(BTreeMap::new()),
+            // We need to use a deterministic PRNG - so we use an arbitrary fixed seed, e.g., 42.
+            rng: Mutex::new(StdRng::seed_from_u64(42)),
+        }
+    }
+
+    /// Builds an environment consisting of the given number of application subnets.
+    pub fn with_application_subnets(num_subnets: u8) -> Self {
+        Self::new(
+            (1..=num_subnets)
+                .map(|subnet_seed| {
+                    StateMachineBuilder::new()
+                        .with_subnet_type(SubnetType::Application)
+                        .with_subnet_seed([subnet_seed; 32])
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the IDs of all subnets in the environment.
+    pub fn subnet_ids(&self) -> Vec<SubnetId> {
+        self.subnets.keys().cloned().collect()
+    }
+
+    /// Returns the `StateMachine` of the subnet with the given ID.
+    pub fn get_subnet(&self, subnet_id: SubnetId) -> Option<Arc<StateMachine>> {
+        self.subnets.get(&subnet_id).cloned()
+    }
+
+    /// Returns the `StateMachine` of the subnet hosting the given canister.
+    pub fn route(&self, canister_id: CanisterId) -> Option<Arc<StateMachine>> {
+        self.routing_table
+            .route(canister_id.get())
+            .and_then(|subnet_id| self.get_subnet(subnet_id))
+    }
+
+    /// Sets the conditions of the XNet link from `sender` to `receiver`.
+    pub fn set_xnet_link_conditions(
+        &self,
+        sender: SubnetId,
+        receiver: SubnetId,
+        conditions: XNetLinkConditions,
+    ) {
+        self.links
+            .lock()
+            .unwrap()
+            .entry((sender, receiver))
+            .or_default()
+            .conditions = conditions;
+    }
+
+    /// Partitions the given groups of subnets from each other, i.e.,
+    /// no stream slices are delivered between a subnet in one group
+    /// and a subnet in the other group (in either direction).
+    pub fn partition(&self, subnets_a: &[SubnetId], subnets_b: &[SubnetId]) {
+        let mut links = self.links.lock().unwrap();
+        for a in subnets_a {
+            for b in subnets_b {
+                links.entry((*a, *b)).or_default().conditions.partitioned = true;
+                links.entry((*b, *a)).or_default().conditions.partitioned = true;
+            }
+        }
+    }
+
+    /// Heals all partitions created by `Self::partition`
+    /// (or by setting `XNetLinkConditions::partitioned`).
+    pub fn heal_partitions(&self) {
+        for link in self.links.lock().unwrap().values_mut() {
+            link.conditions.partitioned = false;
+        }
+    }
+
+    /// Advances time on all subnets by the given amount.
+    pub fn advance_time(&self, amount: Duration) {
+        for sm in self.subnets.values() {
+            sm.advance_time(amount);
+        }
+    }
+
+    /// Executes a round on every subnet, inducting the messages from the streams
+    /// of all other subnets as of the beginning of this round (subject to the
+    /// conditions of the XNet links).
+    pub fn tick(&self) {
+        let mut links = self.links.lock().unwrap();
+        let mut rng = self.rng.lock().unwrap();
+        let mut xnet_payloads = BTreeMap::new();
+        for (receiver_id, receiver) in self.subnets.iter() {
+            let mut stream_slices = BTreeMap::new();
+            for (sender_id, sender) in self.subnets.iter() {
+                // Messages in the loopback stream of a subnet are inducted by the subnet itself.
+                if sender_id == receiver_id {
+                    continue;
+                }
+                let Some(stream_end) = sender
+                    .get_latest_state()
+                    .get_stream(receiver_id)
+                    .map(|stream| stream.messages_end())
+                else {
+                    continue;
+                };
+                let link = links.entry((*sender_id, *receiver_id)).or_default();
+                link.stream_ends.push_back(stream_end);
+                while link.stream_ends.len() > link.conditions.delay_rounds + 1 {
+                    link.stream_ends.pop_front();
+                }
+                let slice_lost = link.conditions.slice_loss_probability > 0.0
+                    && rng.gen_bool(link.conditions.slice_loss_probability.min(1.0));
+                if link.conditions.partitioned || slice_lost {
+                    continue;
+                }
+                let msg_begin = receiver.expected_xnet_message_index(*sender_id);
+                // Only the messages that have been in the stream for `delay_rounds` rounds are delivered.
+                let deliverable_end = if link.stream_ends.len() > link.conditions.delay_rounds {
+                    link.stream_ends[0]
+                } else {
+                    msg_begin
+                };
+                let msg_limit = deliverable_end.get().saturating_sub(msg_begin.get()) as usize;
+                let stream_slice = sender
+                    .generate_certified_stream_slice(
+                        *receiver_id,
+                        Some(msg_begin),
+                        Some(msg_begin),
+                        Some(msg_limit),
+                        None,
+                    )
+                    .unwrap_or_else(|err| {
+                        panic!(
+                            "Failed to generate stream slice from subnet {} to subnet {}: {:?}",
+                            sender_id, receiver_id, err
+                        )
+                    });
+                stream_slices.insert(*sender_id, stream_slice);
+            }
+            xnet_payloads.insert(*receiver_id, XNetPayload { stream_slices });
+        }
+        drop(rng);
+        drop(links);
+        for (receiver_id, xnet_payload) in xnet_payloads {
+            self.subnets[&receiver_id].execute_block_with_xnet_payload(xnet_payload);
+        }
+    }
+
+    /// Sends an ingress message to the canister with the specified ID
+    /// on the subnet hosting that canister.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the canister ID is not routed to any subnet.
+    pub fn send_ingress(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        method: impl ToString,
+        payload: Vec<u8>,
+    ) -> Result<MessageId, UserError> {
+        self.route(canister_id)
+            .unwrap_or_else(|| panic!("Canister {} is not hosted by any subnet", canister_id))
+            .send_ingress_safe(sender, canister_id, method, payload)
+    }
+
+    /// Returns the status of the ingress message with the specified ID
+    /// on the subnet to which it was sent.
+    pub fn ingress_status(&self, msg_id: &MessageId) -> IngressStatus {
+        self.subnets
+            .values()
+            .map(|sm| sm.ingress_status(msg_id))
+            .find(|status| matches!(status, IngressStatus::Known { .. }))
+            .unwrap_or(IngressStatus::Unknown)
+    }
+
+    /// Executes rounds on all subnets (see `Self::tick`) until the ingress message
+    /// with the specified ID completes (including all downstream cross-subnet calls).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the ingress message did not complete within `max_ticks` rounds.
+    pub fn await_response(
+        &self,
+        msg_id: &MessageId,
+        max_ticks: usize,
+    ) -> Result<WasmResult, UserError> {
+        for _tick in 0..max_ticks {
+            match self.ingress_status(msg_id) {
+                IngressStatus::Known {
+                    state: IngressState::Completed(result),
+                    ..
+                } => return Ok(result),
+                IngressStatus::Known {
+                    state: IngressState::Failed(error),
+                    ..
+                } => return Err(error),
+                _ => {
+                    self.tick();
+                }
+            }
+        }
+        panic!(
+            "Did not get answer to ingress {} after {} environment ticks",
+            msg_id, max_ticks
+        )
+    }
+
+    /// Sends an ingress message to the canister with the specified ID
+    /// and awaits its response (see `Self::await_response`).
+    pub fn execute_ingress_as(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        method: impl ToString,
+        payload: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        let msg_id = self.send_ingress(sender, canister_id, method, payload)?;
+        self.await_response(&msg_id, Self::MAX_TICKS)
+    }
+
+    /// Creates a new canister with cycles on the subnet with the specified ID
+    /// and installs its code, executing rounds on all subnets (see `Self::tick`).
+    /// Returns the ID of the newly created canister.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the subnet is not part of the environment.
+    pub fn install_canister_with_cycles(
+        &self,
+        subnet_id: SubnetId,
+        module: Vec<u8>,
+        payload: Vec<u8>,
+        settings: Option<CanisterSettingsArgs>,
+        cycles: Cycles,
+    ) -> Result<CanisterId, UserError> {
+        let sm = self
+            .get_subnet(subnet_id)
+            .unwrap_or_else(|| panic!("Subnet {} is not part of the environment", subnet_id));
+        let msg_id = sm.send_ingress_safe(
+            PrincipalId::new_anonymous(),
+            ic00::IC_00,
+            ic00::Method::ProvisionalCreateCanisterWithCycles,
+            ic00::ProvisionalCreateCanisterWithCyclesArgs {
+                amount: Some(candid::Nat::from(cycles.get())),
+                settings,
+                specified_id: None,
+                sender_canister_version: None,
+            }
+            .encode(),
+        )?;
+        let canister_id = match self.await_response(&msg_id, Self::MAX_TICKS)? {
+            WasmResult::Reply(bytes) => CanisterIdRecord::decode(&bytes[..])
+                .expect("failed to decode canister ID record")
+                .get_canister_id(),
+            WasmResult::Reject(reason) => panic!("create_canister call rejected: {}", reason),
+        };
+        let msg_id = sm.send_ingress_safe(
+            PrincipalId::new_anonymous(),
+            ic00::IC_00,
+            Method::InstallCode,
+            InstallCodeArgs::new(
+                CanisterInstallMode::Install,
+                canister_id,
+                module,
+                payload,
+                None,
+                None,
+            )
+            .encode(),
+        )?;
+        self.await_response(&msg_id, Self::MAX_TICKS)
+            .map(|_| canister_id)
+    }
+}
+
 #[derive(Clone)]
 pub struct PayloadBuilder {
     expiry_time: Time,
@@ -4,10 +4,11 @@ use ic_registry_routing_table::{CanisterIdRange, RoutingTable, CANISTER_IDS_PER_
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
     finalize_registry, StateMachine, StateMachineBuilder, StateMachineConfig,
+    StateMachineEnvironment, XNetLinkConditions,
 };
 use ic_test_utilities_types::ids::user_test_id;
 use ic_types::{
-    ingress::{IngressStatus, WasmResult},
+    ingress::{IngressState, IngressStatus, WasmResult},
     CanisterId, Cycles, SubnetId,
 };
 use ic_universal_canister::{wasm, CallArgs, UNIVERSAL_CANISTER_WASM};
@@ -379,3 +380,93 @@ fn counter_canister_call_test() {
         _ => panic!("unreachable"),
     };
 }
+
+#[test]
+fn environment_xnet_call_test() {
+    const MAX_TICKS: usize = 100;
+    let user_id = user_test_id(1).get();
+
+    let env = StateMachineEnvironment::with_application_subnets(2);
+    let subnet_ids = env.subnet_ids();
+    let (subnet_id1, subnet_id2) = (subnet_ids[0], subnet_ids[1]);
+
+    // Create a canister on each of the two subnets.
+    let canister_id1 = env
+        .install_canister_with_cycles(
+            subnet_id1,
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            INITIAL_CYCLES_BALANCE,
+        )
+        .unwrap();
+    let canister_id2 = env
+        .install_canister_with_cycles(
+            subnet_id2,
+            UNIVERSAL_CANISTER_WASM.to_vec(),
+            vec![],
+            None,
+            INITIAL_CYCLES_BALANCE,
+        )
+        .unwrap();
+    assert_eq!(env.route(canister_id2).unwrap().get_subnet_id(), subnet_id2);
+
+    let xnet_call = |payload: u8| {
+        wasm()
+            .inter_update(
+                canister_id2,
+                CallArgs::default().other_side(wasm().reply_data(&[payload]).build()),
+            )
+            .build()
+    };
+
+    // Invoke a method on the 1st subnet calling into the 2nd subnet.
+    let wasm_result = env
+        .execute_ingress_as(user_id, canister_id1, "update", xnet_call(1))
+        .unwrap();
+    assert_eq!(wasm_result, WasmResult::Reply(vec![1]));
+
+    // The call does not complete while the two subnets are partitioned.
+    env.partition(&[subnet_id1], &[subnet_id2]);
+    let msg_id = env
+        .send_ingress(user_id, canister_id1, "update", xnet_call(2))
+        .unwrap();
+    for _ in 0..10 {
+        env.tick();
+    }
+    assert!(matches!(
+        env.ingress_status(&msg_id),
+        IngressStatus::Known {
+            state: IngressState::Processing,
+            ..
+        }
+    ));
+
+    // The call completes once the partition is healed.
+    env.heal_partitions();
+    let wasm_result = env.await_response(&msg_id, MAX_TICKS).unwrap();
+    assert_eq!(wasm_result, WasmResult::Reply(vec![2]));
+
+    // The call completes despite delayed and lost stream slices in both directions.
+    const DELAY_ROUNDS: usize = 3;
+    let conditions = XNetLinkConditions {
+        partitioned: false,
+        slice_loss_probability: 0.5,
+        delay_rounds: DELAY_ROUNDS,
+    };
+    env.set_xnet_link_conditions(subnet_id1, subnet_id2, conditions.clone());
+    env.set_xnet_link_conditions(subnet_id2, subnet_id1, conditions);
+    let subnet2 = env.get_subnet(subnet_id2).unwrap();
+    let msg_begin = subnet2.expected_xnet_message_index(subnet_id1);
+    let msg_id = env
+        .send_ingress(user_id, canister_id1, "update", xnet_call(3))
+        .unwrap();
+    // The request is routed into the stream in the round executing the ingress message
+    // and not delivered before it has been in the stream for `DELAY_ROUNDS` rounds.
+    for _ in 0..DELAY_ROUNDS {
+        env.tick();
+        assert_eq!(subnet2.expected_xnet_message_index(subnet_id1), msg_begin);
+    }
+    let wasm_result = env.await_response(&msg_id, MAX_TICKS).unwrap();
+    assert_eq!(wasm_result, WasmResult::Reply(vec![3]));
+}
@@ -505,6 +505,10 @@ pub fn load_canister_state(
                 canister_state_bits.time_of_last_allocation_charge_nanos,
             ),
             total_query_stats: canister_state_bits.total_query_stats,
+            last_heartbeat_execution_round: canister_state_bits.last_heartbeat_execution_round,
+            last_global_timer_execution_round: canister_state_bits
+                .last_global_timer_execution_round,
+            task_skip_reason: canister_state_bits.task_skip_reason,
         },
     };
 
@@ -1,4 +1,5 @@
-//! Prunes a replicated state, as part of a subnet split.
+//! Prunes a replicated state, as part of a subnet split; or moves individual
+//! canisters between the replicated states of two subnets.
 use crate::{
     checkpoint::{load_checkpoint, make_checkpoint},
     flush_canister_snapshots_and_page_maps,
@@ -15,11 +16,13 @@ use ic_registry_routing_table::{
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    page_map::PageAllocatorFileDescriptor, page_map::TestPageAllocatorFileDescriptorImpl,
-    ReplicatedState,
+    canister_snapshots::CanisterSnapshot,
+    page_map::{PageAllocatorFileDescriptor, TestPageAllocatorFileDescriptorImpl, PAGE_SIZE},
+    CanisterState, Memory, PageMap, ReplicatedState,
 };
 use ic_state_layout::{CheckpointLayout, ReadOnly, StateLayout};
 use ic_types::{malicious_flags::MaliciousFlags, PrincipalId, SubnetId, Time};
+use ic_wasm_types::CanisterModule;
 use scoped_threadpool::Pool;
 use std::{iter::once, path::PathBuf, sync::Arc};
 
@@ -105,6 +108,231 @@ pub fn split(
     )
 }
 
+/// Loads the latest checkpoints under `source_root` and `destination_root`;
+/// moves all canisters within the provided canister ID ranges, together with
+/// their snapshots, from the source state to the destination state; and writes
+/// back both states as new checkpoints, under the respective roots.
+///
+/// The canisters must be stopped and their queues empty. It is the
+/// responsibility of the caller to ensure that both subnets are halted and
+/// that the registry's canister migrations and routing table entries are
+/// updated accordingly (similar to a subnet split) before the subnets are
+/// restarted from the new checkpoints.
+///
+/// Either both new checkpoints are written or neither is: if writing the
+/// source checkpoint fails, the new destination checkpoint is removed again.
+pub fn migrate_canisters(
+    source_root: PathBuf,
+    destination_root: PathBuf,
+    canister_id_ranges: CanisterIdRanges,
+    metrics_registry: &MetricsRegistry,
+    log: ReplicaLogger,
+) -> Result<Vec<CanisterId>, String> {
+    migrate_canisters_impl(
+        source_root,
+        destination_root,
+        canister_id_ranges,
+        metrics_registry,
+        log,
+        write_checkpoint,
+    )
+}
+
+/// Signature of `write_checkpoint()`, allowing tests to inject write failures.
+type WriteCheckpointFn = fn(
+    &mut ReplicatedState,
+    StateLayout,
+    &CheckpointLayout<ReadOnly>,
+    &mut Pool,
+    Arc<dyn PageAllocatorFileDescriptor>,
+    &Config,
+    &StateManagerMetrics,
+    ReplicaLogger,
+) -> Result<(), String>;
+
+/// Implementation of `migrate_canisters()`, writing checkpoints using
+/// `write_checkpoint`.
+fn migrate_canisters_impl(
+    source_root: PathBuf,
+    destination_root: PathBuf,
+    canister_id_ranges: CanisterIdRanges,
+    metrics_registry: &MetricsRegistry,
+    log: ReplicaLogger,
+    write_checkpoint: WriteCheckpointFn,
+) -> Result<Vec<CanisterId>, String> {
+    let source_config = Config::new(source_root);
+    let destination_config = Config::new(destination_root);
+    let source_layout = StateLayout::try_new(
+        log.clone(),
+        source_config.state_root.clone(),
+        metrics_registry,
+    )
+    .map_err(|e| e.to_string())?;
+    // The two state layouts cannot register their metrics with the same registry.
+    let destination_layout = StateLayout::try_new(
+        log.clone(),
+        destination_config.state_root.clone(),
+        &MetricsRegistry::new(),
+    )
+    .map_err(|e| e.to_string())?;
+
+    // A thread pool to use for reading and writing checkpoints.
+    let mut thread_pool = Pool::new(NUMBER_OF_CHECKPOINT_THREADS);
+
+    // Create the file descriptor factory that is used to create files for PageMaps.
+    let fd_factory: Arc<dyn PageAllocatorFileDescriptor> =
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new());
+
+    let metrics = StateManagerMetrics::new(metrics_registry, log.clone());
+    let (source_cp, mut source_state) = read_checkpoint(
+        &source_layout,
+        &mut thread_pool,
+        fd_factory.clone(),
+        &metrics,
+    )?;
+    let (destination_cp, mut destination_state) = read_checkpoint(
+        &destination_layout,
+        &mut thread_pool,
+        fd_factory.clone(),
+        &metrics,
+    )?;
+
+    let canister_ids: Vec<CanisterId> = source_state
+        .canister_states
+        .keys()
+        .filter(|canister_id| canister_id_ranges.contains(canister_id))
+        .cloned()
+        .collect();
+    if canister_ids.is_empty() {
+        return Err(format!(
+            "No canisters within the ranges {:?} found at {}",
+            canister_id_ranges,
+            source_layout.raw_path().display()
+        ));
+    }
+
+    for canister_id in canister_ids.iter() {
+        let (mut canister_state, snapshots) =
+            source_state.take_canister_for_migration(canister_id)?;
+
+        // The page maps are backed by files of the source checkpoint, which are not
+        // present in the tip of the destination subnet.
+        detach_canister_state(&mut canister_state, fd_factory.clone());
+        let snapshots = snapshots
+            .into_iter()
+            .map(|(snapshot_id, snapshot)| {
+                let mut snapshot = Arc::unwrap_or_clone(snapshot);
+                detach_canister_snapshot(&mut snapshot, fd_factory.clone());
+                (snapshot_id, Arc::new(snapshot))
+            })
+            .collect();
+
+        destination_state.put_migrated_canister(canister_state, snapshots)?;
+    }
+
+    // Write the destination checkpoint first. If writing the source checkpoint
+    // then fails, remove the new destination checkpoint again, so that both
+    // subnets are left at their previous checkpoints, with the canisters only
+    // hosted by the source subnet.
+    let new_destination_height = destination_cp.height().increment();
+    write_checkpoint(
+        &mut destination_state,
+        destination_layout.clone(),
+        &destination_cp,
+        &mut thread_pool,
+        fd_factory.clone(),
+        &destination_config,
+        &metrics,
+        log.clone(),
+    )?;
+    if let Err(err) = write_checkpoint(
+        &mut source_state,
+        source_layout,
+        &source_cp,
+        &mut thread_pool,
+        fd_factory,
+        &source_config,
+        &metrics,
+        log,
+    ) {
+        return Err(
+            match destination_layout.force_remove_checkpoint(new_destination_height) {
+                Ok(()) => format!(
+                    "Failed to write the source checkpoint, removed the new destination \
+                     checkpoint @{}: {}",
+                    new_destination_height, err
+                ),
+                Err(remove_err) => format!(
+                    "Failed to write the source checkpoint: {}; and failed to remove the \
+                     new destination checkpoint @{}: {}",
+                    err, new_destination_height, remove_err
+                ),
+            },
+        );
+    }
+
+    Ok(canister_ids)
+}
+
+/// Replaces the page maps of the given canister with copies holding all their
+/// pages in memory (see `detach_page_map()`).
+fn detach_canister_state(
+    canister_state: &mut CanisterState,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+) {
+    if let Some(execution_state) = canister_state.execution_state.as_mut() {
+        execution_state.wasm_memory = Memory::new(
+            detach_page_map(&execution_state.wasm_memory.page_map, fd_factory.clone()),
+            execution_state.wasm_memory.size,
+        );
+        execution_state.stable_memory = Memory::new(
+            detach_page_map(&execution_state.stable_memory.page_map, fd_factory.clone()),
+            execution_state.stable_memory.size,
+        );
+    }
+    let wasm_chunk_store = &mut canister_state.system_state.wasm_chunk_store;
+    let page_map = detach_page_map(wasm_chunk_store.page_map(), fd_factory);
+    *wasm_chunk_store.page_map_mut() = page_map;
+}
+
+/// Replaces the page maps and Wasm module of the given snapshot with copies
+/// held in memory (see `detach_page_map()`).
+///
+/// Unlike for canisters, the Wasm module of a snapshot is not copied over into
+/// the tip if it is backed by a file.
+fn detach_canister_snapshot(
+    snapshot: &mut CanisterSnapshot,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+) {
+    let execution_snapshot = snapshot.execution_snapshot_mut();
+    execution_snapshot.wasm_binary =
+        CanisterModule::new(execution_snapshot.wasm_binary.as_slice().to_vec());
+    execution_snapshot.wasm_memory.page_map =
+        detach_page_map(&execution_snapshot.wasm_memory.page_map, fd_factory.clone());
+    execution_snapshot.stable_memory.page_map = detach_page_map(
+        &execution_snapshot.stable_memory.page_map,
+        fd_factory.clone(),
+    );
+    let chunk_store = snapshot.chunk_store_mut();
+    let page_map = detach_page_map(chunk_store.page_map(), fd_factory);
+    *chunk_store.page_map_mut() = page_map;
+}
+
+/// Returns a copy of `page_map` holding all of its non-zero pages as page
+/// delta, so that it can be persisted into a checkpoint of another subnet.
+fn detach_page_map(
+    page_map: &PageMap,
+    fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+) -> PageMap {
+    let pages: Vec<_> = page_map
+        .host_pages_iter()
+        .filter(|(_, page)| **page != [0; PAGE_SIZE])
+        .collect();
+    let mut detached = PageMap::new(fd_factory);
+    detached.update(&pages);
+    detached
+}
+
 /// Converts a pair of `retain` and `drop` range vectors (exactly one of which
 /// is expected to be non-empty) into a well-formed [CanisterIdRanges] covering
 /// all canisters to be retained. Returns an error if the provided inputs are
@@ -16,7 +16,8 @@ use ic_registry_routing_table::CanisterIdRange;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
     canister_snapshots::CanisterSnapshot, page_map::TestPageAllocatorFileDescriptorImpl,
-    CheckpointLoadingMetrics, ReplicatedState, SystemMetadata,
+    testing::SystemStateTesting, CanisterStatus, CheckpointLoadingMetrics, PageIndex,
+    ReplicatedState, SystemMetadata,
 };
 use ic_state_layout::{
     ProtoFileWith, StateLayout, CANISTER_FILE, CANISTER_STATES_DIR, CHECKPOINTS_DIR,
@@ -371,27 +372,195 @@ fn split_subnet_b_with_greater_batch_delta() {
     split_subnet_b_helper(Some(Duration::from_nanos(13)));
 }
 
+/// Tests migrating `CANISTER_1` (with a snapshot) from subnet A to subnet B.
+#[test]
+fn migrate_canister_1() {
+    with_test_replica_logger(|log| {
+        let (source_tmp, _) = new_state_layout(log.clone());
+        let source_root = source_tmp.path().to_path_buf();
+        let destination_tmp = make_state_layout(
+            &mut ReplicatedState::new(SUBNET_B, SubnetType::Application),
+            log.clone(),
+        );
+        let destination_root = destination_tmp.path().to_path_buf();
+        let canister_id_ranges: CanisterIdRanges = vec![CanisterIdRange {
+            start: CANISTER_1,
+            end: CANISTER_1,
+        }]
+        .try_into()
+        .unwrap();
+        let migrate = || {
+            migrate_canisters(
+                source_root.clone(),
+                destination_root.clone(),
+                canister_id_ranges.clone(),
+                &MetricsRegistry::new(),
+                log.clone(),
+            )
+        };
+
+        // Running canisters cannot be migrated.
+        assert!(migrate().is_err());
+        assert_eq!(HEIGHT, latest_checkpoint_height(&source_root, &log));
+        assert_eq!(HEIGHT, latest_checkpoint_height(&destination_root, &log));
+
+        // Stop `CANISTER_1` and write a page into its Wasm memory.
+        let page = [42; PAGE_SIZE];
+        stop_canister_1_and_write_page(&source_root, &page, &log);
+
+        assert_eq!(vec![CANISTER_1], migrate().unwrap());
+
+        // `CANISTER_1` and its snapshot were removed from subnet A.
+        let source_state = read_latest_state(&source_root, &log);
+        assert_eq!(
+            vec![CANISTER_2, CANISTER_3],
+            source_state
+                .canister_states
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(0, source_state.canister_snapshots.count());
+
+        // And added to subnet B, including the contents of its Wasm memory.
+        let destination_state = read_latest_state(&destination_root, &log);
+        assert_eq!(
+            vec![CANISTER_1],
+            destination_state
+                .canister_states
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+        let canister_state = destination_state.canister_state(&CANISTER_1).unwrap();
+        assert_eq!(INITIAL_CYCLES, canister_state.system_state.balance());
+        assert_eq!(
+            &page,
+            canister_state
+                .execution_state
+                .as_ref()
+                .unwrap()
+                .wasm_memory
+                .page_map
+                .get_page(PageIndex::new(1))
+        );
+        assert!(destination_state
+            .canister_snapshots
+            .contains(&SnapshotId::from((CANISTER_1, 0))));
+    });
+}
+
+#[test]
+fn migrate_canister_1_is_rolled_back_if_writing_source_fails() {
+    with_test_replica_logger(|log| {
+        let (source_tmp, _) = new_state_layout(log.clone());
+        let source_root = source_tmp.path().to_path_buf();
+        let destination_tmp = make_state_layout(
+            &mut ReplicatedState::new(SUBNET_B, SubnetType::Application),
+            log.clone(),
+        );
+        let destination_root = destination_tmp.path().to_path_buf();
+        stop_canister_1_and_write_page(&source_root, &[42; PAGE_SIZE], &log);
+        let source_height = latest_checkpoint_height(&source_root, &log);
+
+        /// Writes the destination checkpoint, but fails writing the source one.
+        fn fail_writing_source_checkpoint(
+            state: &mut ReplicatedState,
+            state_layout: StateLayout,
+            old_cp: &CheckpointLayout<ReadOnly>,
+            thread_pool: &mut Pool,
+            fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
+            config: &Config,
+            metrics: &StateManagerMetrics,
+            log: ReplicaLogger,
+        ) -> Result<(), String> {
+            if state.metadata.own_subnet_id == SUBNET_A {
+                return Err("injected failure".into());
+            }
+            write_checkpoint(
+                state,
+                state_layout,
+                old_cp,
+                thread_pool,
+                fd_factory,
+                config,
+                metrics,
+                log,
+            )
+        }
+
+        let err = migrate_canisters_impl(
+            source_root.clone(),
+            destination_root.clone(),
+            vec![CanisterIdRange {
+                start: CANISTER_1,
+                end: CANISTER_1,
+            }]
+            .try_into()
+            .unwrap(),
+            &MetricsRegistry::new(),
+            log.clone(),
+            fail_writing_source_checkpoint,
+        )
+        .unwrap_err();
+        assert!(
+            err.contains("injected failure"),
+            "Unexpected error: {}",
+            err
+        );
+
+        // Neither subnet has a new checkpoint and `CANISTER_1` is still only
+        // hosted by subnet A.
+        assert_eq!(source_height, latest_checkpoint_height(&source_root, &log));
+        assert_eq!(HEIGHT, latest_checkpoint_height(&destination_root, &log));
+        let source_state = read_latest_state(&source_root, &log);
+        assert!(source_state.canister_state(&CANISTER_1).is_some());
+        let destination_state = read_latest_state(&destination_root, &log);
+        assert!(destination_state.canister_states.is_empty());
+        assert_eq!(0, destination_state.canister_snapshots.count());
+    });
+}
+
+/// Stops `CANISTER_1` in the latest checkpoint under the state layout at
+/// `root` and writes `page` into its Wasm memory, as a new checkpoint.
+fn stop_canister_1_and_write_page(root: &Path, page: &[u8; PAGE_SIZE], log: &ReplicaLogger) {
+    let metrics_registry = MetricsRegistry::new();
+    let layout = StateLayout::try_new(log.clone(), root.to_path_buf(), &metrics_registry).unwrap();
+    let metrics = StateManagerMetrics::new(&metrics_registry, log.clone());
+    let fd_factory = Arc::new(TestPageAllocatorFileDescriptorImpl::new());
+    let mut thread_pool = thread_pool();
+    let (cp, mut state) =
+        read_checkpoint(&layout, &mut thread_pool, fd_factory.clone(), &metrics).unwrap();
+    let canister_state = state.canister_state_mut(&CANISTER_1).unwrap();
+    canister_state
+        .system_state
+        .set_status(CanisterStatus::Stopped);
+    canister_state
+        .execution_state
+        .as_mut()
+        .unwrap()
+        .wasm_memory
+        .page_map
+        .update(&[(PageIndex::new(1), page)]);
+    write_checkpoint(
+        &mut state,
+        layout,
+        &cp,
+        &mut thread_pool,
+        fd_factory,
+        &Config::new(root.to_path_buf()),
+        &metrics,
+        log.clone(),
+    )
+    .unwrap();
+}
+
 /// Creates a state layout under a temporary directory, with 3 canisters:
 /// `CANISTER_1`, `CANISTER_2` and `CANISTER_3`.
 ///
 /// Returns a handle to the `TempDir` holding the state layoutl; and the batch
 /// time of the last (and only) checkpoint within.
 fn new_state_layout(log: ReplicaLogger) -> (TempDir, Time) {
-    let tmp = tmpdir("checkpoint");
-    let root = tmp.path().to_path_buf();
-    let metrics_registry = MetricsRegistry::new();
-    let layout = StateLayout::try_new(log.clone(), root.clone(), &metrics_registry).unwrap();
-    let tip_handler = layout.capture_tip_handler();
-    let state_manager_metrics = StateManagerMetrics::new(&metrics_registry, log.clone());
-    let (_tip_thread, tip_channel) = spawn_tip_thread(
-        log,
-        tip_handler,
-        layout.clone(),
-        lsmt_config_default(),
-        state_manager_metrics.clone(),
-        MaliciousFlags::default(),
-    );
-
     let mut state = ReplicatedState::new(SUBNET_A, SubnetType::Application);
     state.put_canister_state(new_canister_state_with_execution(
         CANISTER_1,
@@ -446,24 +615,9 @@ fn new_state_layout(log: ReplicaLogger) -> (TempDir, Time) {
         )
         .unwrap();
 
-    flush_canister_snapshots_and_page_maps(
-        &mut state,
-        HEIGHT,
-        &tip_channel,
-        &state_manager_metrics.checkpoint_metrics,
-    );
-
-    let _state = make_checkpoint(
-        &state,
-        HEIGHT,
-        &tip_channel,
-        &state_manager_metrics.checkpoint_metrics,
-        &mut thread_pool(),
-        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
-        lsmt_config_default().lsmt_status,
-    )
-    .unwrap_or_else(|err| panic!("Expected make_checkpoint to succeed, got {:?}", err))
-    .1;
+    let tmp = make_state_layout(&mut state, log.clone());
+    let root = tmp.path().to_path_buf();
+    let layout = StateLayout::try_new(log, root.clone(), &MetricsRegistry::new()).unwrap();
 
     // Sanity checks.
     assert_eq!(layout.checkpoint_heights().unwrap(), vec![HEIGHT]);
@@ -499,6 +653,47 @@ fn new_state_layout(log: ReplicaLogger) -> (TempDir, Time) {
     (tmp, state.metadata.batch_time)
 }
 
+/// Writes `state` as a checkpoint at `HEIGHT` into a new state layout under a
+/// temporary directory.
+///
+/// Returns a handle to the `TempDir` holding the state layout.
+fn make_state_layout(state: &mut ReplicatedState, log: ReplicaLogger) -> TempDir {
+    let tmp = tmpdir("checkpoint");
+    let root = tmp.path().to_path_buf();
+    let metrics_registry = MetricsRegistry::new();
+    let layout = StateLayout::try_new(log.clone(), root, &metrics_registry).unwrap();
+    let tip_handler = layout.capture_tip_handler();
+    let state_manager_metrics = StateManagerMetrics::new(&metrics_registry, log.clone());
+    let (_tip_thread, tip_channel) = spawn_tip_thread(
+        log,
+        tip_handler,
+        layout,
+        lsmt_config_default(),
+        state_manager_metrics.clone(),
+        MaliciousFlags::default(),
+    );
+
+    flush_canister_snapshots_and_page_maps(
+        state,
+        HEIGHT,
+        &tip_channel,
+        &state_manager_metrics.checkpoint_metrics,
+    );
+
+    make_checkpoint(
+        state,
+        HEIGHT,
+        &tip_channel,
+        &state_manager_metrics.checkpoint_metrics,
+        &mut thread_pool(),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        lsmt_config_default().lsmt_status,
+    )
+    .unwrap_or_else(|err| panic!("Expected make_checkpoint to succeed, got {:?}", err));
+
+    tmp
+}
+
 #[test]
 fn test_resolve_retain() {
     let retain = make_range(3, 4);
@@ -609,6 +804,29 @@ fn compute_manifest_for_root(root: &Path, log: &ReplicaLogger) -> (Manifest, Hei
     compute_manifest(&layout, manifest_metrics, log)
 }
 
+/// Returns the height of the latest checkpoint under the state layout at `root`.
+fn latest_checkpoint_height(root: &Path, log: &ReplicaLogger) -> Height {
+    let layout =
+        StateLayout::try_new(log.clone(), root.to_path_buf(), &MetricsRegistry::new()).unwrap();
+    layout.checkpoint_heights().unwrap().pop().unwrap()
+}
+
+/// Reads the replicated state from the latest checkpoint under the state
+/// layout at `root`.
+fn read_latest_state(root: &Path, log: &ReplicaLogger) -> ReplicatedState {
+    let metrics_registry = MetricsRegistry::new();
+    let layout = StateLayout::try_new(log.clone(), root.to_path_buf(), &metrics_registry).unwrap();
+    let metrics = StateManagerMetrics::new(&metrics_registry, log.clone());
+    read_checkpoint(
+        &layout,
+        &mut thread_pool(),
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        &metrics,
+    )
+    .unwrap()
+    .1
+}
+
 fn deserialize_split_from(root: &Path, height: Height) -> SubnetId {
     let split_from: ProtoFileWith<ic_protobuf::state::system_metadata::v1::SplitFrom, ReadOnly> =
         root.join(CHECKPOINTS_DIR)
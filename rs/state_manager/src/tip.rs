@@ -490,6 +490,11 @@ fn flush_snapshot_changes<T>(
             SnapshotOperation::Restore(canister_id, snapshot_id) => {
                 restore(log, layout, canister_id, snapshot_id)?;
             }
+            SnapshotOperation::Upload(snapshot_id) => {
+                // The uploaded snapshot has no files yet, they are written at the next checkpoint.
+                // Creating the layout creates the empty snapshot directory.
+                layout.snapshot(&snapshot_id)?;
+            }
         }
     }
 
@@ -1075,6 +1080,13 @@ fn serialize_canister_to_tip(
                 .system_state
                 .task_queue
                 .peek_hook_status(),
+            last_heartbeat_execution_round: canister_state
+                .scheduler_state
+                .last_heartbeat_execution_round,
+            last_global_timer_execution_round: canister_state
+                .scheduler_state
+                .last_global_timer_execution_round,
+            task_skip_reason: canister_state.scheduler_state.task_skip_reason,
         }
         .into(),
     )?;
@@ -1090,6 +1102,9 @@ fn serialize_snapshot_to_tip(
     lsmt_config: &LsmtConfig,
 ) -> Result<(), CheckpointError> {
     let snapshot_layout = tip.snapshot(snapshot_id)?;
+    // The Wasm module of an upload in progress is built here since it is not part of
+    // the `CanisterModule` of the snapshot yet.
+    let wasm_binary = canister_snapshot.current_canister_module();
 
     // The protobuf is written at each checkpoint.
     snapshot_layout.snapshot().serialize(
@@ -1098,7 +1113,7 @@ fn serialize_snapshot_to_tip(
             canister_id: canister_snapshot.canister_id(),
             taken_at_timestamp: *canister_snapshot.taken_at_timestamp(),
             canister_version: canister_snapshot.canister_version(),
-            binary_hash: Some(canister_snapshot.canister_module().module_hash().into()),
+            binary_hash: Some(wasm_binary.module_hash().into()),
             certified_data: canister_snapshot.certified_data().clone(),
             wasm_chunk_store_metadata: canister_snapshot.chunk_store().metadata().clone(),
             stable_memory_size: canister_snapshot.stable_memory().size,
@@ -1110,9 +1125,8 @@ fn serialize_snapshot_to_tip(
     )?;
 
     // Like for canisters, the wasm binary is either already present on disk, or it is new and needs to be written.
-    let wasm_binary = canister_snapshot.canister_module();
     if wasm_binary.file().is_none() {
-        snapshot_layout.wasm().serialize(wasm_binary)?;
+        snapshot_layout.wasm().serialize(&wasm_binary)?;
     } else {
         // During `flush_page_maps` we created copied this file from the canister directory.
         debug_assert!(snapshot_layout.wasm().raw_path().exists());
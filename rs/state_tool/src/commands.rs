@@ -1,13 +1,18 @@
 //! Command implementations.
+pub mod canister_diff;
 pub mod cdiff;
 pub mod chash;
 pub mod convert_ids;
 pub mod decode;
+pub mod extract_canister;
 pub mod import_state;
 pub mod list;
 pub mod manifest;
+pub mod migrate_canisters;
 pub mod split;
 pub mod split_manifest;
+#[cfg(test)]
+mod test_utils;
 mod utils;
 pub mod verify_manifest;
 
@@ -0,0 +1,391 @@
+//! Computes per-canister differences between two checkpoints.
+//!
+//! Unlike `cdiff`, which only compares the certified parts of the state, this
+//! compares the full canister states: memory pages, Wasm modules, cycles
+//! balances, queues and settings.
+
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::{
+    page_map::{TestPageAllocatorFileDescriptorImpl, PAGE_SIZE},
+    CanisterState, PageIndex, PageMap, ReplicatedState,
+};
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::{checkpoint::load_checkpoint, CheckpointError, CheckpointMetrics};
+use ic_types::{CanisterId, Height};
+use serde_json::{json, Map, Value};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Loads the checkpoint at `path`.
+fn load(path: PathBuf, metrics: &CheckpointMetrics) -> Result<ReplicatedState, CheckpointError> {
+    let unused_height = Height::from(0);
+    load_checkpoint(
+        &CompleteCheckpointLayout::new_untracked(path, unused_height)?,
+        SubnetType::Application,
+        metrics,
+        None,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+    )
+}
+
+/// `canister-diff` command entry point.
+///
+/// Prints a JSON object mapping the IDs of all canisters that differ between
+/// the checkpoints at `path_a` and `path_b` to a description of their
+/// differences.
+pub fn do_canister_diff(path_a: PathBuf, path_b: PathBuf) -> Result<(), String> {
+    let dummy_metrics_registry = ic_metrics::MetricsRegistry::new();
+    let dummy_metrics = CheckpointMetrics::new(&dummy_metrics_registry, crate::commands::logger());
+    let state_a = load(path_a, &dummy_metrics)
+        .map_err(|err| format!("✗ Loading first checkpoint FAILED:\n\t{}", err))?;
+    let state_b = load(path_b, &dummy_metrics)
+        .map_err(|err| format!("✗ Loading second checkpoint FAILED:\n\t{}", err))?;
+
+    let diff = diff_states(&state_a, &state_b);
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&diff).map_err(|err| err.to_string())?
+    );
+    Ok(())
+}
+
+/// Returns the differences of all canisters in the two states.
+fn diff_states(state_a: &ReplicatedState, state_b: &ReplicatedState) -> Value {
+    let canister_ids: BTreeSet<&CanisterId> = state_a
+        .canister_states
+        .keys()
+        .chain(state_b.canister_states.keys())
+        .collect();
+
+    let mut result = Map::new();
+    for canister_id in canister_ids {
+        let diff = match (
+            state_a.canister_state(canister_id),
+            state_b.canister_state(canister_id),
+        ) {
+            (Some(_), None) => Some(json!({ "status": "removed" })),
+            (None, Some(_)) => Some(json!({ "status": "added" })),
+            (Some(a), Some(b)) => diff_canisters(a, b),
+            (None, None) => unreachable!("canister ID taken from one of the states"),
+        };
+        if let Some(diff) = diff {
+            result.insert(canister_id.to_string(), diff);
+        }
+    }
+    Value::Object(result)
+}
+
+/// Returns the differences between two versions of the same canister, or
+/// `None` if no differences were found.
+fn diff_canisters(a: &CanisterState, b: &CanisterState) -> Option<Value> {
+    let mut diff = Map::new();
+
+    let wasm_hash = |canister: &CanisterState| {
+        canister
+            .execution_state
+            .as_ref()
+            .map(|execution_state| hex::encode(execution_state.wasm_binary.binary.module_hash()))
+    };
+    insert_if_changed(&mut diff, "wasm_hash", wasm_hash(a), wasm_hash(b));
+
+    let (balance_a, balance_b) = (
+        a.system_state.balance().get(),
+        b.system_state.balance().get(),
+    );
+    if balance_a != balance_b {
+        diff.insert(
+            "cycles_balance_delta".to_string(),
+            signed_delta(balance_a, balance_b).into(),
+        );
+    }
+
+    let mut pages = Map::new();
+    let wasm_memory = |canister: &CanisterState| {
+        canister
+            .execution_state
+            .as_ref()
+            .map(|execution_state| &execution_state.wasm_memory.page_map)
+    };
+    let stable_memory = |canister: &CanisterState| {
+        canister
+            .execution_state
+            .as_ref()
+            .map(|execution_state| &execution_state.stable_memory.page_map)
+    };
+    for (name, page_map_a, page_map_b) in [
+        ("wasm_memory", wasm_memory(a), wasm_memory(b)),
+        ("stable_memory", stable_memory(a), stable_memory(b)),
+        (
+            "wasm_chunk_store",
+            Some(a.system_state.wasm_chunk_store.page_map()),
+            Some(b.system_state.wasm_chunk_store.page_map()),
+        ),
+    ] {
+        let changed_pages = changed_pages(page_map_a, page_map_b);
+        if !changed_pages.is_empty() {
+            pages.insert(name.to_string(), changed_pages.into());
+        }
+    }
+    if !pages.is_empty() {
+        diff.insert("changed_pages".to_string(), Value::Object(pages));
+    }
+
+    let (queues_a, queues_b) = (a.system_state.queues(), b.system_state.queues());
+    let mut queues = Map::new();
+    for (name, count_a, count_b) in [
+        (
+            "ingress_messages",
+            queues_a.ingress_queue_message_count(),
+            queues_b.ingress_queue_message_count(),
+        ),
+        (
+            "input_messages",
+            queues_a.input_queues_message_count(),
+            queues_b.input_queues_message_count(),
+        ),
+        (
+            "output_messages",
+            queues_a.output_queues_message_count(),
+            queues_b.output_queues_message_count(),
+        ),
+    ] {
+        let delta = count_b as i64 - count_a as i64;
+        if delta != 0 {
+            queues.insert(name.to_string(), delta.into());
+        }
+    }
+    if !queues.is_empty() {
+        diff.insert("queue_message_deltas".to_string(), Value::Object(queues));
+    }
+
+    let mut settings = Map::new();
+    insert_if_changed(&mut settings, "controllers", controllers(a), controllers(b));
+    insert_if_changed(
+        &mut settings,
+        "compute_allocation_percent",
+        a.compute_allocation().as_percent(),
+        b.compute_allocation().as_percent(),
+    );
+    insert_if_changed(
+        &mut settings,
+        "memory_allocation_bytes",
+        a.system_state.memory_allocation.bytes().get(),
+        b.system_state.memory_allocation.bytes().get(),
+    );
+    insert_if_changed(
+        &mut settings,
+        "freeze_threshold_seconds",
+        a.system_state.freeze_threshold.get(),
+        b.system_state.freeze_threshold.get(),
+    );
+    insert_if_changed(
+        &mut settings,
+        "reserved_cycles_limit",
+        a.system_state
+            .reserved_balance_limit()
+            .map(|limit| limit.get().to_string()),
+        b.system_state
+            .reserved_balance_limit()
+            .map(|limit| limit.get().to_string()),
+    );
+    insert_if_changed(
+        &mut settings,
+        "wasm_memory_limit_bytes",
+        a.system_state.wasm_memory_limit.map(|limit| limit.get()),
+        b.system_state.wasm_memory_limit.map(|limit| limit.get()),
+    );
+    insert_if_changed(
+        &mut settings,
+        "log_visibility",
+        format!("{:?}", a.system_state.log_visibility),
+        format!("{:?}", b.system_state.log_visibility),
+    );
+    if !settings.is_empty() {
+        diff.insert("settings".to_string(), Value::Object(settings));
+    }
+
+    if diff.is_empty() {
+        return None;
+    }
+    diff.insert("status".to_string(), "changed".into());
+    Some(Value::Object(diff))
+}
+
+fn controllers(canister: &CanisterState) -> Vec<String> {
+    canister
+        .system_state
+        .controllers
+        .iter()
+        .map(|controller| controller.to_string())
+        .collect()
+}
+
+/// Formats `b - a` as a signed decimal number. Unlike `i128` arithmetic, this
+/// covers the full range of `u128` cycles balances.
+fn signed_delta(a: u128, b: u128) -> String {
+    if b >= a {
+        (b - a).to_string()
+    } else {
+        format!("-{}", a - b)
+    }
+}
+
+/// Inserts `{"before": a, "after": b}` under `key` if `a` and `b` differ.
+fn insert_if_changed<T: PartialEq + Into<Value>>(
+    diff: &mut Map<String, Value>,
+    key: &str,
+    a: T,
+    b: T,
+) {
+    if a != b {
+        diff.insert(
+            key.to_string(),
+            json!({ "before": a.into(), "after": b.into() }),
+        );
+    }
+}
+
+/// Returns the indices of the OS pages whose contents differ between the two
+/// page maps. A missing page map is treated like an empty one.
+fn changed_pages(a: Option<&PageMap>, b: Option<&PageMap>) -> Vec<u64> {
+    const ZERO_PAGE: [u8; PAGE_SIZE] = [0; PAGE_SIZE];
+    fn get_page(page_map: Option<&PageMap>, index: PageIndex) -> &[u8; PAGE_SIZE] {
+        page_map.map_or(&ZERO_PAGE, |page_map| page_map.get_page(index))
+    }
+
+    let num_pages = a
+        .map_or(0, PageMap::num_host_pages)
+        .max(b.map_or(0, PageMap::num_host_pages)) as u64;
+    (0..num_pages)
+        .filter(|index| {
+            let index = PageIndex::new(*index);
+            get_page(a, index) != get_page(b, index)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_state::new_canister_state_with_execution;
+    use ic_test_utilities_types::ids::SUBNET_1;
+    use ic_types::{Cycles, PrincipalId};
+
+    const CANISTER_1: CanisterId = CanisterId::from_u64(1);
+    const CANISTER_2: CanisterId = CanisterId::from_u64(2);
+    const CANISTER_3: CanisterId = CanisterId::from_u64(3);
+
+    fn canister(canister_id: CanisterId, cycles: u128) -> CanisterState {
+        new_canister_state_with_execution(
+            canister_id,
+            PrincipalId::new_user_test_id(42),
+            Cycles::new(cycles),
+            100_000.into(),
+        )
+    }
+
+    fn page_map(pages: &[(u64, u8)]) -> PageMap {
+        let mut page_map = PageMap::new(Arc::new(TestPageAllocatorFileDescriptorImpl::new()));
+        let contents: Vec<_> = pages
+            .iter()
+            .map(|(index, byte)| (PageIndex::new(*index), [*byte; PAGE_SIZE]))
+            .collect();
+        let pages: Vec<_> = contents
+            .iter()
+            .map(|(index, page)| (*index, page))
+            .collect();
+        page_map.update(&pages);
+        page_map
+    }
+
+    #[test]
+    fn changed_pages_compares_page_contents() {
+        let a = page_map(&[(0, 1), (1, 2), (3, 4)]);
+        let b = page_map(&[(0, 1), (1, 3), (5, 6)]);
+
+        assert_eq!(changed_pages(Some(&a), Some(&a)), Vec::<u64>::new());
+        assert_eq!(changed_pages(Some(&a), Some(&b)), vec![1, 3, 5]);
+        assert_eq!(changed_pages(None, Some(&b)), vec![0, 1, 5]);
+        assert_eq!(changed_pages(Some(&a), None), vec![0, 1, 3]);
+        assert_eq!(changed_pages(None, None), Vec::<u64>::new());
+        // Explicitly zeroed pages do not differ from missing ones.
+        assert_eq!(
+            changed_pages(Some(&page_map(&[(2, 0)])), None),
+            Vec::<u64>::new()
+        );
+    }
+
+    #[test]
+    fn diff_canisters_reports_nothing_for_equal_canisters() {
+        assert_eq!(
+            diff_canisters(&canister(CANISTER_1, 1_000), &canister(CANISTER_1, 1_000)),
+            None
+        );
+    }
+
+    #[test]
+    fn diff_canisters_reports_changes() {
+        let a = canister(CANISTER_1, 1_000);
+        let mut b = canister(CANISTER_1, 400);
+        b.system_state.freeze_threshold = 200_000.into();
+        b.system_state
+            .controllers
+            .insert(PrincipalId::new_user_test_id(43));
+        b.execution_state.as_mut().unwrap().wasm_memory.page_map = page_map(&[(2, 7)]);
+
+        let diff = diff_canisters(&a, &b).unwrap();
+        assert_eq!(diff["status"], "changed");
+        assert_eq!(diff["cycles_balance_delta"], "-600");
+        assert_eq!(diff["changed_pages"], json!({ "wasm_memory": [2] }));
+        assert_eq!(
+            diff["settings"]["freeze_threshold_seconds"],
+            json!({ "before": 100_000, "after": 200_000 })
+        );
+        assert_eq!(
+            diff["settings"]["controllers"]["after"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(diff.get("wasm_hash").is_none());
+        assert!(diff.get("queue_message_deltas").is_none());
+    }
+
+    #[test]
+    fn diff_canisters_handles_full_range_of_cycles() {
+        let empty = canister(CANISTER_1, 0);
+        let full = canister(CANISTER_1, u128::MAX);
+
+        assert_eq!(
+            diff_canisters(&empty, &full).unwrap()["cycles_balance_delta"],
+            u128::MAX.to_string()
+        );
+        assert_eq!(
+            diff_canisters(&full, &empty).unwrap()["cycles_balance_delta"],
+            format!("-{}", u128::MAX)
+        );
+    }
+
+    #[test]
+    fn diff_states_reports_added_removed_and_changed_canisters() {
+        let mut state_a = ReplicatedState::new(SUBNET_1, SubnetType::Application);
+        state_a.put_canister_state(canister(CANISTER_1, 1_000));
+        state_a.put_canister_state(canister(CANISTER_2, 1_000));
+        let mut state_b = ReplicatedState::new(SUBNET_1, SubnetType::Application);
+        state_b.put_canister_state(canister(CANISTER_2, 2_000));
+        state_b.put_canister_state(canister(CANISTER_3, 1_000));
+
+        let diff = diff_states(&state_a, &state_b);
+        assert_eq!(
+            diff,
+            json!({
+                CANISTER_1.to_string(): { "status": "removed" },
+                CANISTER_2.to_string(): { "status": "changed", "cycles_balance_delta": "1000" },
+                CANISTER_3.to_string(): { "status": "added" },
+            })
+        );
+        assert_eq!(diff_states(&state_a, &state_a), json!({}));
+    }
+}
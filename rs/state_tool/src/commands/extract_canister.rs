@@ -0,0 +1,302 @@
+//! Extracts the state of a single canister from a checkpoint.
+
+use ic_replicated_state::{
+    canister_state::WASM_PAGE_SIZE_IN_BYTES, page_map::TestPageAllocatorFileDescriptorImpl,
+    CanisterState, Memory,
+};
+use ic_state_layout::CompleteCheckpointLayout;
+use ic_state_manager::{checkpoint::load_canister_state, CheckpointMetrics};
+use ic_sys::{PageIndex, PAGE_SIZE};
+use ic_types::{
+    messages::{CanisterMessage, RequestOrResponse},
+    CanisterId, Height, PrincipalId,
+};
+use serde_json::{json, Value};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// File names of the extracted canister state, relative to the output directory.
+const WASM_FILE: &str = "canister.wasm";
+const WASM_MEMORY_FILE: &str = "wasm_memory.bin";
+const STABLE_MEMORY_FILE: &str = "stable_memory.bin";
+const SYSTEM_STATE_FILE: &str = "system_state.json";
+const CALL_CONTEXTS_FILE: &str = "call_contexts.json";
+const QUEUES_FILE: &str = "queues.json";
+
+/// Loads the canister `canister_id` from the checkpoint at `path` and writes
+/// its Wasm module, memory images, system state, call contexts and queues
+/// into the directory `out`, which is created if it does not exist.
+pub fn do_extract_canister(
+    path: PathBuf,
+    canister_id: PrincipalId,
+    out: PathBuf,
+) -> Result<(), String> {
+    let canister_id = CanisterId::try_from(canister_id)
+        .map_err(|err| format!("invalid canister ID {}: {}", canister_id, err))?;
+    let unused_height = Height::from(0);
+    let dummy_metrics_registry = ic_metrics::MetricsRegistry::new();
+    let dummy_metrics = CheckpointMetrics::new(&dummy_metrics_registry, crate::commands::logger());
+
+    let checkpoint_layout = CompleteCheckpointLayout::new_untracked(path, unused_height)
+        .map_err(|err| format!("failed to open checkpoint: {}", err))?;
+    if !checkpoint_layout
+        .canister_ids()
+        .map_err(|err| format!("failed to list canisters: {}", err))?
+        .contains(&canister_id)
+    {
+        return Err(format!(
+            "canister {} does not exist in checkpoint {}",
+            canister_id,
+            checkpoint_layout.raw_path().display()
+        ));
+    }
+    let canister_layout = checkpoint_layout
+        .canister(&canister_id)
+        .map_err(|err| format!("failed to open canister directory: {}", err))?;
+    let (mut canister, _) = load_canister_state(
+        &canister_layout,
+        &canister_id,
+        unused_height,
+        Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        &dummy_metrics,
+    )
+    .map_err(|err| format!("failed to load canister {}: {}", canister_id, err))?;
+
+    std::fs::create_dir_all(&out)
+        .map_err(|err| format!("failed to create {}: {}", out.display(), err))?;
+
+    if let Some(execution_state) = &canister.execution_state {
+        write_file(
+            &out.join(WASM_FILE),
+            execution_state.wasm_binary.binary.as_slice(),
+        )?;
+        write_memory(&out.join(WASM_MEMORY_FILE), &execution_state.wasm_memory)?;
+        write_memory(
+            &out.join(STABLE_MEMORY_FILE),
+            &execution_state.stable_memory,
+        )?;
+    }
+    write_json(&out.join(SYSTEM_STATE_FILE), &system_state_json(&canister))?;
+    write_json(
+        &out.join(CALL_CONTEXTS_FILE),
+        &call_contexts_json(&canister)?,
+    )?;
+    // Must come last, since it consumes the messages in the canister's queues.
+    write_json(&out.join(QUEUES_FILE), &queues_json(&mut canister)?)?;
+
+    println!("Extracted canister {} into {}", canister_id, out.display());
+    Ok(())
+}
+
+/// Returns the system state of the canister, excluding its queues and call
+/// contexts.
+fn system_state_json(canister: &CanisterState) -> Value {
+    let system_state = &canister.system_state;
+    json!({
+        "canister_id": canister.canister_id().to_string(),
+        "controllers": system_state
+            .controllers
+            .iter()
+            .map(|controller| controller.to_string())
+            .collect::<Vec<_>>(),
+        "status": system_state.status_string(),
+        "canister_version": system_state.canister_version,
+        "cycles_balance": system_state.balance().get().to_string(),
+        "reserved_cycles": system_state.reserved_balance().get().to_string(),
+        "freeze_threshold_seconds": system_state.freeze_threshold.get(),
+        "memory_allocation_bytes": system_state.memory_allocation.bytes().get(),
+        "compute_allocation_percent": canister.compute_allocation().as_percent(),
+        "wasm_memory_limit_bytes": system_state.wasm_memory_limit.map(|limit| limit.get()),
+        "wasm_memory_threshold_bytes": system_state.wasm_memory_threshold.get(),
+        "certified_data": hex::encode(&system_state.certified_data),
+        "global_timer_nanos": system_state.global_timer.to_nanos_since_unix_epoch(),
+        "wasm_chunk_store_chunks": system_state
+            .wasm_chunk_store
+            .keys()
+            .map(hex::encode)
+            .collect::<Vec<_>>(),
+        "snapshots_memory_usage_bytes": system_state.snapshots_memory_usage.get(),
+        "memory_usage_bytes": canister.memory_usage().get(),
+        "has_execution_state": canister.execution_state.is_some(),
+    })
+}
+
+/// Returns the call contexts of the canister, keyed by call context ID.
+fn call_contexts_json(canister: &CanisterState) -> Result<Value, String> {
+    let Some(call_context_manager) = canister.system_state.call_context_manager() else {
+        return Ok(json!({}));
+    };
+    let mut call_contexts = serde_json::Map::new();
+    for (id, call_context) in call_context_manager.call_contexts() {
+        call_contexts.insert(
+            id.get().to_string(),
+            json!({
+                "call_origin": to_json(call_context.call_origin())?,
+                "responded": call_context.has_responded(),
+                "deleted": call_context.is_deleted(),
+                "available_cycles": call_context.available_cycles().get().to_string(),
+                "time_nanos": call_context.time().as_nanos_since_unix_epoch(),
+                "instructions_executed": call_context.instructions_executed().get(),
+            }),
+        );
+    }
+    Ok(Value::Object(call_contexts))
+}
+
+/// Returns the messages in the canister's input and output queues. The
+/// messages are popped from the queues in the process.
+fn queues_json(canister: &mut CanisterState) -> Result<Value, String> {
+    let mut input = Vec::new();
+    while let Some(message) = canister.pop_input() {
+        input.push(match message {
+            CanisterMessage::Ingress(ingress) => {
+                json!({ "ingress": to_json(ingress.as_ref())? })
+            }
+            CanisterMessage::Request(request) => {
+                json!({ "request": to_json(request.as_ref())? })
+            }
+            CanisterMessage::Response(response) => {
+                json!({ "response": to_json(response.as_ref())? })
+            }
+        });
+    }
+
+    let mut output = Vec::new();
+    for message in canister.output_into_iter() {
+        output.push(match message {
+            RequestOrResponse::Request(request) => {
+                json!({ "request": to_json(request.as_ref())? })
+            }
+            RequestOrResponse::Response(response) => {
+                json!({ "response": to_json(response.as_ref())? })
+            }
+        });
+    }
+
+    Ok(json!({ "input": input, "output": output }))
+}
+
+fn to_json<T: serde::Serialize + ?Sized>(value: &T) -> Result<Value, String> {
+    serde_json::to_value(value).map_err(|err| format!("failed to serialize to JSON: {}", err))
+}
+
+fn write_json(path: &Path, value: &Value) -> Result<(), String> {
+    let bytes = serde_json::to_vec_pretty(value)
+        .map_err(|err| format!("failed to serialize {}: {}", path.display(), err))?;
+    write_file(path, &bytes)
+}
+
+fn write_file(path: &Path, bytes: &[u8]) -> Result<(), String> {
+    std::fs::write(path, bytes)
+        .map_err(|err| format!("failed to write {}: {}", path.display(), err))
+}
+
+/// Writes the raw contents of `memory`, i.e. all of its Wasm pages.
+fn write_memory(path: &Path, memory: &Memory) -> Result<(), String> {
+    let map_err = |err: std::io::Error| format!("failed to write {}: {}", path.display(), err);
+    let mut writer = BufWriter::new(File::create(path).map_err(map_err)?);
+    let num_os_pages = memory.size.get() * WASM_PAGE_SIZE_IN_BYTES / PAGE_SIZE;
+    for page_index in 0..num_os_pages {
+        writer
+            .write_all(memory.page_map.get_page(PageIndex::new(page_index as u64)))
+            .map_err(map_err)?;
+    }
+    writer.flush().map_err(map_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::test_utils::write_checkpoint;
+    use ic_replicated_state::NumWasmPages;
+    use ic_test_utilities_state::new_canister_state_with_execution;
+    use ic_types::Cycles;
+
+    #[test]
+    fn extracts_one_of_two_canisters() {
+        let canister_1 = CanisterId::from_u64(1);
+        let canister_2 = CanisterId::from_u64(2);
+        let controller = PrincipalId::new_user_test_id(42);
+
+        let mut canister = new_canister_state_with_execution(
+            canister_1,
+            controller,
+            Cycles::new(1_000),
+            100_000.into(),
+        );
+        let execution_state = canister.execution_state.as_mut().unwrap();
+        execution_state
+            .wasm_memory
+            .page_map
+            .update(&[(PageIndex::new(1), &[13; PAGE_SIZE])]);
+        execution_state.wasm_memory.size = NumWasmPages::new(1);
+        let other = new_canister_state_with_execution(
+            canister_2,
+            controller,
+            Cycles::new(2_000),
+            100_000.into(),
+        );
+        let (_tmp, checkpoint) = write_checkpoint(vec![canister, other]);
+
+        let out = tempfile::tempdir().unwrap();
+        do_extract_canister(checkpoint, canister_1.get(), out.path().to_path_buf()).unwrap();
+
+        let mut files: Vec<_> = std::fs::read_dir(out.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                CALL_CONTEXTS_FILE,
+                WASM_FILE,
+                QUEUES_FILE,
+                STABLE_MEMORY_FILE,
+                SYSTEM_STATE_FILE,
+                WASM_MEMORY_FILE,
+            ]
+        );
+
+        let wasm_memory = std::fs::read(out.path().join(WASM_MEMORY_FILE)).unwrap();
+        assert_eq!(wasm_memory.len(), WASM_PAGE_SIZE_IN_BYTES);
+        assert!(wasm_memory[..PAGE_SIZE].iter().all(|byte| *byte == 0));
+        assert!(wasm_memory[PAGE_SIZE..2 * PAGE_SIZE]
+            .iter()
+            .all(|byte| *byte == 13));
+        assert!(std::fs::read(out.path().join(STABLE_MEMORY_FILE))
+            .unwrap()
+            .is_empty());
+
+        let system_state: Value =
+            serde_json::from_slice(&std::fs::read(out.path().join(SYSTEM_STATE_FILE)).unwrap())
+                .unwrap();
+        assert_eq!(system_state["canister_id"], canister_1.to_string());
+        assert_eq!(system_state["controllers"], json!([controller.to_string()]));
+        assert_eq!(system_state["cycles_balance"], "1000");
+        let queues: Value =
+            serde_json::from_slice(&std::fs::read(out.path().join(QUEUES_FILE)).unwrap()).unwrap();
+        assert_eq!(queues, json!({ "input": [], "output": [] }));
+    }
+
+    #[test]
+    fn missing_canister_is_an_error() {
+        let (_tmp, checkpoint) = write_checkpoint(vec![new_canister_state_with_execution(
+            CanisterId::from_u64(1),
+            PrincipalId::new_user_test_id(42),
+            Cycles::new(1_000),
+            100_000.into(),
+        )]);
+        let out = tempfile::tempdir().unwrap();
+
+        let err = do_extract_canister(
+            checkpoint,
+            CanisterId::from_u64(2).get(),
+            out.path().to_path_buf(),
+        )
+        .unwrap_err();
+        assert!(err.contains("does not exist"), "{}", err);
+    }
+}
@@ -0,0 +1,32 @@
+//! Moves canisters between the replicated states of two subnets.
+
+use ic_logger::replica_logger::no_op_logger;
+use ic_metrics::MetricsRegistry;
+use ic_registry_routing_table::{CanisterIdRange, CanisterIdRanges};
+use ic_state_manager::split::migrate_canisters;
+use std::path::PathBuf;
+
+/// Loads the latest checkpoints under `source_root` and `destination_root`;
+/// moves the (stopped) canisters within the provided canister ID ranges and
+/// their snapshots from the source state to the destination state; and writes
+/// back both states as new checkpoints, under the respective roots.
+pub fn do_migrate_canisters(
+    source_root: PathBuf,
+    destination_root: PathBuf,
+    canister_id_ranges: Vec<CanisterIdRange>,
+) -> Result<(), String> {
+    let canister_id_ranges =
+        CanisterIdRanges::try_from(canister_id_ranges).map_err(|e| format!("{:?}", e))?;
+
+    let canister_ids = migrate_canisters(
+        source_root,
+        destination_root,
+        canister_id_ranges,
+        &MetricsRegistry::new(),
+        no_op_logger(),
+    )?;
+    for canister_id in canister_ids {
+        println!("Migrated canister {}", canister_id);
+    }
+    Ok(())
+}
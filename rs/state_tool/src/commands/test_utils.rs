@@ -0,0 +1,59 @@
+//! Helpers for testing commands against checkpoints.
+
+use ic_config::state_manager::Config;
+use ic_interfaces::certification::Verifier;
+use ic_interfaces_state_manager::{CertificationScope, StateManager};
+use ic_logger::no_op_logger;
+use ic_metrics::MetricsRegistry;
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::CanisterState;
+use ic_state_layout::StateLayout;
+use ic_state_manager::StateManagerImpl;
+use ic_test_utilities_consensus::fake::{Fake, FakeVerifier};
+use ic_test_utilities_types::ids::SUBNET_1;
+use ic_types::{malicious_flags::MaliciousFlags, Height};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Writes a checkpoint at height 1 holding the given canisters into a fresh
+/// state layout under a temporary directory.
+///
+/// Returns a handle to the `TempDir` holding the state layout; and the path
+/// of the checkpoint.
+pub(crate) fn write_checkpoint(canisters: Vec<CanisterState>) -> (TempDir, PathBuf) {
+    let tmp = tempfile::Builder::new()
+        .prefix("state_tool")
+        .tempdir()
+        .unwrap();
+    let verifier: Arc<dyn Verifier> = Arc::new(FakeVerifier::new());
+    let state_manager = StateManagerImpl::new(
+        verifier,
+        SUBNET_1,
+        SubnetType::Application,
+        no_op_logger(),
+        &MetricsRegistry::new(),
+        &Config::new(tmp.path().into()),
+        None,
+        MaliciousFlags::default(),
+    );
+
+    let (_height, mut state) = state_manager.take_tip();
+    for canister in canisters {
+        state.put_canister_state(canister);
+    }
+    let height = Height::new(1);
+    state_manager.commit_and_certify(state, height, CertificationScope::Full, None);
+    state_manager.flush_tip_channel();
+
+    let path = state_manager
+        .state_layout()
+        .checkpoints()
+        .join(StateLayout::checkpoint_name(height));
+    assert!(
+        path.exists(),
+        "Expected checkpoint {} to exist",
+        path.display()
+    );
+    (tmp, path)
+}
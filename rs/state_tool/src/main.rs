@@ -1,8 +1,9 @@
 //! IC State Tool
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
-//! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! persisted state files, diff checkpoints and canisters, compute partial
+//! state hashes and checkpoint manifests, import state trees, extract single
+//! canisters, migrate canisters between subnets).
 
 use clap::Parser;
 use ic_registry_routing_table::CanisterIdRange;
@@ -19,6 +20,11 @@ enum Opt {
     #[clap(name = "cdiff")]
     CDiff { path_a: PathBuf, path_b: PathBuf },
 
+    /// Computes per-canister diff of memories, Wasm modules, cycles balances,
+    /// queues and settings between checkpoints, as JSON.
+    #[clap(name = "canister-diff")]
+    CanisterDiff { path_a: PathBuf, path_b: PathBuf },
+
     /// Computes partial state hash that is used for certification.
     #[clap(name = "chash")]
     CHash {
@@ -76,6 +82,24 @@ enum Opt {
         file: PathBuf,
     },
 
+    /// Extracts the state of a single canister from a checkpoint: its Wasm
+    /// module and raw memory images, as well as its system state, call
+    /// contexts and queues as JSON.
+    #[clap(name = "extract-canister")]
+    ExtractCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+
+        /// ID of the canister to extract.
+        #[clap(long = "canister")]
+        canister_id: PrincipalId,
+
+        /// Directory to write the extracted files to.
+        #[clap(long = "out")]
+        out: PathBuf,
+    },
+
     /// Converts textual principal representation to hex.
     #[clap(name = "canister_id_to_hex")]
     CanisterIdToHex {
@@ -125,6 +149,26 @@ enum Opt {
         batch_time_nanos: Option<u64>,
     },
 
+    /// Moves canisters from the replicated state of one subnet to that of
+    /// another subnet, as part of an offline canister migration performed by
+    /// operators (see the `migrate` command of the subnet splitting tool).
+    ///
+    /// The canisters must be stopped and their queues empty. Both subnets must
+    /// be halted; and the registry's canister migrations and routing table
+    /// entries updated accordingly before the subnets are restarted.
+    #[clap(name = "migrate_canisters")]
+    MigrateCanisters {
+        /// Path to the state layout of the subnet currently hosting the canisters.
+        #[clap(long, required = true)]
+        source_root: PathBuf,
+        /// Path to the state layout of the subnet to migrate the canisters to.
+        #[clap(long, required = true)]
+        destination_root: PathBuf,
+        /// Canister ID ranges to migrate.
+        #[clap(long, required = true, num_args(1..))]
+        canister_id_ranges: Vec<CanisterIdRange>,
+    },
+
     /// Splits a manifest, to verify the manifests resulting from a subnet split.
     #[clap(name = "split_manifest")]
     SplitManifest {
@@ -153,6 +197,9 @@ fn main() {
     let opt = Parser::parse();
     let result = match opt {
         Opt::CDiff { path_a, path_b } => commands::cdiff::do_diff(path_a, path_b),
+        Opt::CanisterDiff { path_a, path_b } => {
+            commands::canister_diff::do_canister_diff(path_a, path_b)
+        }
         Opt::CHash { path } => commands::chash::do_hash(path),
         Opt::ImportState {
             state,
@@ -163,6 +210,11 @@ fn main() {
         Opt::VerifyManifest { file } => commands::verify_manifest::do_verify_manifest(&file),
         Opt::ListStates { config } => commands::list::do_list(config),
         Opt::Decode { file } => commands::decode::do_decode(file),
+        Opt::ExtractCanister {
+            path,
+            canister_id,
+            out,
+        } => commands::extract_canister::do_extract_canister(path, canister_id, out),
         Opt::CanisterIdToHex { canister_id } => {
             commands::convert_ids::do_canister_id_to_hex(canister_id)
         }
@@ -185,6 +237,15 @@ fn main() {
             drop,
             batch_time_nanos.map(Time::from_nanos_since_unix_epoch),
         ),
+        Opt::MigrateCanisters {
+            source_root,
+            destination_root,
+            canister_id_ranges,
+        } => commands::migrate_canisters::do_migrate_canisters(
+            source_root,
+            destination_root,
+            canister_id_ranges,
+        ),
         Opt::SplitManifest {
             path,
             from_subnet,
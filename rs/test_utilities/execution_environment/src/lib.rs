@@ -73,6 +73,7 @@ use std::convert::TryFrom;
 use std::sync::Arc;
 use std::{
     collections::{BTreeMap, BTreeSet, HashMap},
+    path::PathBuf,
     time::Duration,
 };
 use std::{os::unix::prelude::FileExt, str::FromStr};
@@ -2085,6 +2086,23 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_instruction_profiling(mut self, instruction_profile_dir: PathBuf) -> Self {
+        self.execution_config
+            .embedders_config
+            .feature_flags
+            .instruction_profiling = FlagStatus::Enabled;
+        self.execution_config.instruction_profile_dir = Some(instruction_profile_dir);
+        self
+    }
+
+    pub fn with_wasm_coverage(mut self) -> Self {
+        self.execution_config
+            .embedders_config
+            .feature_flags
+            .wasm_coverage = FlagStatus::Enabled;
+        self
+    }
+
     pub fn with_time(mut self, time: Time) -> Self {
         self.time = time;
         self
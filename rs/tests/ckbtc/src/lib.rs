@@ -372,6 +372,7 @@ pub async fn install_minter(
         mode: Mode::GeneralAvailability,
         kyt_fee: Some(KYT_FEE),
         kyt_principal: Some(kyt_canister_id),
+        utxo_consolidation: None,
     };
 
     let minter_arg = MinterArg::Init(args);
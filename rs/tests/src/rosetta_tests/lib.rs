@@ -32,7 +32,8 @@ use ic_rosetta_api::{
     },
     request_types::{
         AddHotKey, ChangeAutoStakeMaturity, Disburse, Follow, ListNeurons, MergeMaturity,
-        NeuronInfo, RegisterVote, RemoveHotKey, SetDissolveTimestamp, Spawn, Stake, StakeMaturity,
+        NeuronInfo, RegisterVote, RemoveHotKey, SetDissolveTimestamp, SnsClaimNeuron, SnsDisburse,
+        SnsFollow, SnsRegisterVote, SnsSetDissolveTimestamp, SnsStake, Spawn, Stake, StakeMaturity,
         StartDissolve, StopDissolve,
     },
     transaction_id::TransactionIdentifier,
@@ -378,7 +379,13 @@ where
             | Request::StakeMaturity(StakeMaturity { account, .. })
             | Request::NeuronInfo(NeuronInfo { account, .. })
             | Request::ListNeurons(ListNeurons { account, .. })
-            | Request::Follow(Follow { account, .. }) => {
+            | Request::Follow(Follow { account, .. })
+            | Request::SnsStake(SnsStake { account, .. })
+            | Request::SnsClaimNeuron(SnsClaimNeuron { account, .. })
+            | Request::SnsSetDissolveTimestamp(SnsSetDissolveTimestamp { account, .. })
+            | Request::SnsDisburse(SnsDisburse { account, .. })
+            | Request::SnsFollow(SnsFollow { account, .. })
+            | Request::SnsRegisterVote(SnsRegisterVote { account, .. }) => {
                 all_sender_account_ids.push(to_model_account_identifier(&account));
             }
             Request::Transfer(Operation::Burn { .. }) => {
@@ -116,6 +116,12 @@ pub enum Method {
     LoadCanisterSnapshot,
     ListCanisterSnapshots,
     DeleteCanisterSnapshot,
+
+    // Support for downloading and uploading canister snapshots.
+    ReadCanisterSnapshotMetadata,
+    ReadCanisterSnapshotData,
+    UploadCanisterSnapshotMetadata,
+    UploadCanisterSnapshotData,
 }
 
 fn candid_error_to_user_error(err: candid::Error) -> UserError {
@@ -1006,7 +1012,16 @@ pub struct QueryStats {
 ///         num_instructions: nat;
 ///         ingress_payload_size: nat;
 ///         egress_payload_size: nat;
-///     }
+///     };
+///     global_timer: opt nat64;
+///     last_heartbeat_execution_round: opt nat64;
+///     last_global_timer_execution_round: opt nat64;
+///     task_skip_reason: opt variant {
+///         canister_not_running;
+///         long_execution_pending;
+///         out_of_cycles;
+///         frozen;
+///     };
 /// })`
 #[derive(Eq, PartialEq, Debug, CandidType, Deserialize)]
 pub struct CanisterStatusResultV2 {
@@ -1022,6 +1037,15 @@ pub struct CanisterStatusResultV2 {
     idle_cycles_burned_per_day: candid::Nat,
     reserved_cycles: candid::Nat,
     query_stats: QueryStats,
+    /// The deadline of the pending global timer in nanoseconds since the Unix
+    /// epoch, if the timer is set.
+    global_timer: Option<u64>,
+    /// The last round in which the canister's heartbeat was executed.
+    last_heartbeat_execution_round: Option<u64>,
+    /// The last round in which the canister's global timer was executed.
+    last_global_timer_execution_round: Option<u64>,
+    /// Why the heartbeat or global timer was not executed when it was last due.
+    task_skip_reason: Option<CanisterTaskSkipReason>,
 }
 
 impl CanisterStatusResultV2 {
@@ -1045,6 +1069,10 @@ impl CanisterStatusResultV2 {
         query_ingress_payload_size: u128,
         query_egress_payload_size: u128,
         wasm_memory_limit: Option<u64>,
+        global_timer: Option<u64>,
+        last_heartbeat_execution_round: Option<u64>,
+        last_global_timer_execution_round: Option<u64>,
+        task_skip_reason: Option<CanisterTaskSkipReason>,
     ) -> Self {
         Self {
             status,
@@ -1074,6 +1102,10 @@ impl CanisterStatusResultV2 {
                 request_payload_bytes_total: candid::Nat::from(query_ingress_payload_size),
                 response_payload_bytes_total: candid::Nat::from(query_egress_payload_size),
             },
+            global_timer,
+            last_heartbeat_execution_round,
+            last_global_timer_execution_round,
+            task_skip_reason,
         }
     }
 
@@ -1124,6 +1156,36 @@ impl CanisterStatusResultV2 {
     pub fn settings(&self) -> DefiniteCanisterSettingsArgs {
         self.settings.clone()
     }
+
+    pub fn global_timer(&self) -> Option<u64> {
+        self.global_timer
+    }
+
+    pub fn last_heartbeat_execution_round(&self) -> Option<u64> {
+        self.last_heartbeat_execution_round
+    }
+
+    pub fn last_global_timer_execution_round(&self) -> Option<u64> {
+        self.last_global_timer_execution_round
+    }
+
+    pub fn task_skip_reason(&self) -> Option<CanisterTaskSkipReason> {
+        self.task_skip_reason
+    }
+}
+
+/// The reason why a due heartbeat or global timer of a canister was not
+/// executed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, CandidType, Deserialize, Serialize)]
+pub enum CanisterTaskSkipReason {
+    #[serde(rename = "canister_not_running")]
+    CanisterNotRunning,
+    #[serde(rename = "long_execution_pending")]
+    LongExecutionPending,
+    #[serde(rename = "out_of_cycles")]
+    OutOfCycles,
+    #[serde(rename = "frozen")]
+    Frozen,
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -3301,6 +3363,332 @@ impl ListCanisterSnapshotArgs {
 
 impl Payload<'_> for ListCanisterSnapshotArgs {}
 
+/// Decodes a snapshot ID and reports a malformed one as an invalid payload.
+fn decode_snapshot_id(bytes: &[u8]) -> Result<SnapshotId, UserError> {
+    SnapshotId::try_from(&bytes.to_vec()).map_err(|err| {
+        UserError::new(
+            ErrorCode::InvalidManagementPayload,
+            format!("Payload deserialization error: {err:?}"),
+        )
+    })
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct ReadCanisterSnapshotMetadataArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub snapshot_id: Vec<u8>,
+}
+
+impl ReadCanisterSnapshotMetadataArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: SnapshotId) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.to_vec(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+impl<'a> Payload<'a> for ReadCanisterSnapshotMetadataArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+        // Verify that snapshot ID has the correct format.
+        decode_snapshot_id(&args.snapshot_id)?;
+        Ok(args)
+    }
+}
+
+/// The value of an exported Wasm global variable stored in a snapshot.
+/// `(variant {
+///     i32: int32;
+///     i64: int64;
+///     f32: float32;
+///     f64: float64;
+///     v128: nat;
+/// })`
+#[derive(Copy, Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub enum Global {
+    #[serde(rename = "i32")]
+    I32(i32),
+    #[serde(rename = "i64")]
+    I64(i64),
+    #[serde(rename = "f32")]
+    F32(f32),
+    #[serde(rename = "f64")]
+    F64(f64),
+    #[serde(rename = "v128")]
+    V128(u128),
+}
+
+/// Struct to be returned when reading the metadata of a canister snapshot.
+/// Memory sizes are given in bytes.
+/// `(record {
+///     taken_at_timestamp: nat64;
+///     canister_version: nat64;
+///     wasm_module_size: nat64;
+///     exported_globals: vec global;
+///     wasm_memory_size: nat64;
+///     stable_memory_size: nat64;
+///     wasm_chunk_store: vec record { hash: blob };
+///     certified_data: blob;
+/// })`
+#[derive(Clone, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ReadCanisterSnapshotMetadataResponse {
+    pub taken_at_timestamp: u64,
+    pub canister_version: u64,
+    pub wasm_module_size: u64,
+    pub exported_globals: Vec<Global>,
+    pub wasm_memory_size: u64,
+    pub stable_memory_size: u64,
+    pub wasm_chunk_store: Vec<ChunkHash>,
+    #[serde(with = "serde_bytes")]
+    pub certified_data: Vec<u8>,
+}
+
+impl Payload<'_> for ReadCanisterSnapshotMetadataResponse {}
+
+/// Selects the part of a canister snapshot to be read.
+/// `(variant {
+///     wasm_module: record { offset: nat64; size: nat64 };
+///     wasm_memory: record { offset: nat64; size: nat64 };
+///     stable_memory: record { offset: nat64; size: nat64 };
+///     wasm_chunk: record { hash: blob };
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum CanisterSnapshotDataKind {
+    #[serde(rename = "wasm_module")]
+    WasmModule { offset: u64, size: u64 },
+    #[serde(rename = "wasm_memory")]
+    WasmMemory { offset: u64, size: u64 },
+    #[serde(rename = "stable_memory")]
+    StableMemory { offset: u64, size: u64 },
+    #[serde(rename = "wasm_chunk")]
+    WasmChunk {
+        #[serde(with = "serde_bytes")]
+        hash: Vec<u8>,
+    },
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+///     kind: canister_snapshot_data_kind;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct ReadCanisterSnapshotDataArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub snapshot_id: Vec<u8>,
+    pub kind: CanisterSnapshotDataKind,
+}
+
+impl ReadCanisterSnapshotDataArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+        kind: CanisterSnapshotDataKind,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.to_vec(),
+            kind,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+impl<'a> Payload<'a> for ReadCanisterSnapshotDataArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+        // Verify that snapshot ID has the correct format.
+        decode_snapshot_id(&args.snapshot_id)?;
+        Ok(args)
+    }
+}
+
+/// Struct to be returned when reading a part of a canister snapshot.
+/// `(record {
+///     chunk: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct ReadCanisterSnapshotDataResponse {
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl Payload<'_> for ReadCanisterSnapshotDataResponse {}
+
+impl ReadCanisterSnapshotDataResponse {
+    pub fn new(chunk: Vec<u8>) -> Self {
+        Self { chunk }
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     replace_snapshot: opt blob;
+///     wasm_module_size: nat64;
+///     exported_globals: vec global;
+///     wasm_memory_size: nat64;
+///     stable_memory_size: nat64;
+///     wasm_chunk_store_size: nat64;
+///     certified_data: blob;
+/// })`
+///
+/// Memory sizes are given in bytes and must be multiples of the Wasm page
+/// size. `wasm_chunk_store_size` is the number of chunks that can be uploaded
+/// into the chunk store of the new snapshot.
+#[derive(Clone, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct UploadCanisterSnapshotMetadataArgs {
+    pub canister_id: PrincipalId,
+    pub replace_snapshot: Option<serde_bytes::ByteBuf>,
+    pub wasm_module_size: u64,
+    pub exported_globals: Vec<Global>,
+    pub wasm_memory_size: u64,
+    pub stable_memory_size: u64,
+    pub wasm_chunk_store_size: u64,
+    #[serde(with = "serde_bytes")]
+    pub certified_data: Vec<u8>,
+}
+
+impl UploadCanisterSnapshotMetadataArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn replace_snapshot(&self) -> Option<SnapshotId> {
+        self.replace_snapshot
+            .as_ref()
+            .map(|bytes| SnapshotId::try_from(&bytes.clone().into_vec()).unwrap())
+    }
+}
+
+impl<'a> Payload<'a> for UploadCanisterSnapshotMetadataArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+        if let Some(replace_snapshot) = &args.replace_snapshot {
+            // Verify that snapshot ID has the correct format.
+            decode_snapshot_id(replace_snapshot)?;
+        }
+        Ok(args)
+    }
+}
+
+/// Struct to be returned when uploading the metadata of a canister snapshot.
+/// `(record {
+///     snapshot_id: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, Default, CandidType, Deserialize)]
+pub struct UploadCanisterSnapshotMetadataResponse {
+    #[serde(with = "serde_bytes")]
+    pub snapshot_id: Vec<u8>,
+}
+
+impl Payload<'_> for UploadCanisterSnapshotMetadataResponse {}
+
+impl UploadCanisterSnapshotMetadataResponse {
+    pub fn new(snapshot_id: &SnapshotId) -> Self {
+        Self {
+            snapshot_id: snapshot_id.to_vec(),
+        }
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+/// Selects the part of a canister snapshot to be written.
+/// `(variant {
+///     wasm_module: record { offset: nat64 };
+///     wasm_memory: record { offset: nat64 };
+///     stable_memory: record { offset: nat64 };
+///     wasm_chunk;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub enum CanisterSnapshotDataOffset {
+    #[serde(rename = "wasm_module")]
+    WasmModule { offset: u64 },
+    #[serde(rename = "wasm_memory")]
+    WasmMemory { offset: u64 },
+    #[serde(rename = "stable_memory")]
+    StableMemory { offset: u64 },
+    #[serde(rename = "wasm_chunk")]
+    WasmChunk,
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id: principal;
+///     snapshot_id: blob;
+///     kind: canister_snapshot_data_offset;
+///     chunk: blob;
+/// })`
+#[derive(Clone, Eq, PartialEq, Debug, CandidType, Deserialize)]
+pub struct UploadCanisterSnapshotDataArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub snapshot_id: Vec<u8>,
+    pub kind: CanisterSnapshotDataOffset,
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl UploadCanisterSnapshotDataArgs {
+    pub fn new(
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+        kind: CanisterSnapshotDataOffset,
+        chunk: Vec<u8>,
+    ) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id: snapshot_id.to_vec(),
+            kind,
+            chunk,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::unchecked_from_principal(self.canister_id)
+    }
+
+    pub fn get_snapshot_id(&self) -> SnapshotId {
+        SnapshotId::try_from(&self.snapshot_id).unwrap()
+    }
+}
+
+impl<'a> Payload<'a> for UploadCanisterSnapshotDataArgs {
+    fn decode(blob: &'a [u8]) -> Result<Self, UserError> {
+        let args = Decode!([decoder_config()]; blob, Self).map_err(candid_error_to_user_error)?;
+        // Verify that snapshot ID has the correct format.
+        decode_snapshot_id(&args.snapshot_id)?;
+        Ok(args)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;